@@ -73,9 +73,7 @@ impl BulkClient {
             self.instance_url.bulk_api_base(&self.api_version)
         };
 
-        let response: JobCreateResponse = self
-            .post(&url, &config)
-            .await?;
+        let response: JobCreateResponse = self.post(&url, &config).await?;
 
         // Fetch full job details
         self.get_job(&response.id).await
@@ -100,7 +98,7 @@ impl BulkClient {
 
         while let Some(url) = next_url {
             let response: JobListResponse = self.get(&url).await?;
-            
+
             for job_info in response.records {
                 // Fetch full details for each job
                 if let Ok(job) = self.get_job(&job_info.id).await {
@@ -108,9 +106,9 @@ impl BulkClient {
                 }
             }
 
-            next_url = response.next_records_url.map(|path| {
-                format!("{}{}", self.instance_url.0, path)
-            });
+            next_url = response
+                .next_records_url
+                .map(|path| format!("{}{}", self.instance_url.0, path));
 
             if response.done {
                 break;
@@ -131,7 +129,10 @@ impl BulkClient {
         let response = self
             .client
             .put(&url)
-            .header(header::AUTHORIZATION, format!("Bearer {}", self.access_token))
+            .header(
+                header::AUTHORIZATION,
+                format!("Bearer {}", self.access_token),
+            )
             .header(header::CONTENT_TYPE, ContentType::Csv.to_string())
             .body(csv_data.to_string())
             .send()
@@ -143,7 +144,8 @@ impl BulkClient {
 
     /// Close/start a job (mark upload as complete)
     pub async fn start_job(&self, job_id: &str) -> Result<Job> {
-        self.update_job_state(job_id, JobState::UploadComplete).await
+        self.update_job_state(job_id, JobState::UploadComplete)
+            .await
     }
 
     /// Abort a job
@@ -205,7 +207,7 @@ impl BulkClient {
                 _ => {
                     debug!("Job {} state: {:?}", job_id, job.state);
                     sleep(poll_interval).await;
-                    
+
                     // Gradually increase poll interval up to 30 seconds
                     if poll_interval < Duration::from_secs(30) {
                         poll_interval += Duration::from_secs(5);
@@ -226,7 +228,10 @@ impl BulkClient {
         let response = self
             .client
             .get(&url)
-            .header(header::AUTHORIZATION, format!("Bearer {}", self.access_token))
+            .header(
+                header::AUTHORIZATION,
+                format!("Bearer {}", self.access_token),
+            )
             .send()
             .await?;
 
@@ -248,7 +253,10 @@ impl BulkClient {
         let response = self
             .client
             .get(&url)
-            .header(header::AUTHORIZATION, format!("Bearer {}", self.access_token))
+            .header(
+                header::AUTHORIZATION,
+                format!("Bearer {}", self.access_token),
+            )
             .send()
             .await?;
 
@@ -270,7 +278,10 @@ impl BulkClient {
         let response = self
             .client
             .get(&url)
-            .header(header::AUTHORIZATION, format!("Bearer {}", self.access_token))
+            .header(
+                header::AUTHORIZATION,
+                format!("Bearer {}", self.access_token),
+            )
             .send()
             .await?;
 
@@ -287,7 +298,10 @@ impl BulkClient {
             let response = self
                 .client
                 .get(url)
-                .header(header::AUTHORIZATION, format!("Bearer {}", self.access_token))
+                .header(
+                    header::AUTHORIZATION,
+                    format!("Bearer {}", self.access_token),
+                )
                 .send()
                 .await?;
 
@@ -297,12 +311,19 @@ impl BulkClient {
     }
 
     /// Execute POST request with retry logic
-    async fn post<T: DeserializeOwned>(&self, url: &str, body: &impl serde::Serialize) -> Result<T> {
+    async fn post<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        body: &impl serde::Serialize,
+    ) -> Result<T> {
         self.execute_with_retry(|| async {
             let response = self
                 .client
                 .post(url)
-                .header(header::AUTHORIZATION, format!("Bearer {}", self.access_token))
+                .header(
+                    header::AUTHORIZATION,
+                    format!("Bearer {}", self.access_token),
+                )
                 .json(body)
                 .send()
                 .await?;
@@ -313,12 +334,19 @@ impl BulkClient {
     }
 
     /// Execute PATCH request with retry logic
-    async fn patch<T: DeserializeOwned>(&self, url: &str, body: &impl serde::Serialize) -> Result<T> {
+    async fn patch<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        body: &impl serde::Serialize,
+    ) -> Result<T> {
         self.execute_with_retry(|| async {
             let response = self
                 .client
                 .patch(url)
-                .header(header::AUTHORIZATION, format!("Bearer {}", self.access_token))
+                .header(
+                    header::AUTHORIZATION,
+                    format!("Bearer {}", self.access_token),
+                )
                 .json(body)
                 .send()
                 .await?;
@@ -344,8 +372,10 @@ impl BulkClient {
                     if e.is_retryable() && retries < MAX_RETRIES - 1 {
                         retries += 1;
                         let delay = Duration::from_secs(2u64.pow(retries));
-                        warn!("Request failed (attempt {}/{}): {}. Retrying in {:?}...", 
-                              retries, MAX_RETRIES, e, delay);
+                        warn!(
+                            "Request failed (attempt {}/{}): {}. Retrying in {:?}...",
+                            retries, MAX_RETRIES, e, delay
+                        );
                         sleep(delay).await;
                         last_error = Some(e);
                     } else {
@@ -363,10 +393,9 @@ impl BulkClient {
         let status = response.status();
 
         if status.is_success() {
-            response
-                .json()
-                .await
-                .map_err(|e| BulkApiError::InvalidResponse(format!("Failed to parse response: {}", e)))
+            response.json().await.map_err(|e| {
+                BulkApiError::InvalidResponse(format!("Failed to parse response: {}", e))
+            })
         } else {
             Err(self.handle_error_response(response).await)
         }
@@ -378,7 +407,9 @@ impl BulkClient {
         let body = response.text().await.unwrap_or_default();
 
         match status {
-            StatusCode::UNAUTHORIZED => BulkApiError::AuthenticationError("Invalid or expired access token".to_string()),
+            StatusCode::UNAUTHORIZED => {
+                BulkApiError::AuthenticationError("Invalid or expired access token".to_string())
+            }
             StatusCode::TOO_MANY_REQUESTS => {
                 // Try to parse retry-after header
                 let retry_after = 60; // Default to 60 seconds
@@ -410,10 +441,7 @@ mod tests {
 
     #[test]
     fn test_client_creation() {
-        let client = BulkClient::new(
-            "https://myinstance.salesforce.com",
-            "test_token"
-        );
+        let client = BulkClient::new("https://myinstance.salesforce.com", "test_token");
         assert!(client.is_ok());
     }
 
@@ -421,8 +449,8 @@ mod tests {
     fn test_job_config_validation() {
         let config = JobConfig::new("", Operation::Insert);
         let client = BulkClient::new("https://test.salesforce.com", "token").unwrap();
-        
+
         // This would fail during create_job due to validation
         assert!(config.validate().is_err());
     }
-}
\ No newline at end of file
+}
@@ -100,4 +100,4 @@ mod tests {
         assert!(!BulkApiError::JobAborted.is_retryable());
         assert!(!BulkApiError::InvalidJobConfig("test".to_string()).is_retryable());
     }
-}
\ No newline at end of file
+}
@@ -90,7 +90,9 @@ impl JobConfig {
     /// Validate the configuration
     pub fn validate(&self) -> crate::Result<()> {
         if self.object.is_empty() {
-            return Err(crate::BulkApiError::invalid_config("Object name cannot be empty"));
+            return Err(crate::BulkApiError::invalid_config(
+                "Object name cannot be empty",
+            ));
         }
 
         if self.operation == Operation::Upsert && self.external_id_field_name.is_none() {
@@ -231,8 +233,8 @@ mod tests {
         assert!(config.validate().is_err());
 
         // Valid: upsert with external ID
-        let config = JobConfig::new("Contact", Operation::Upsert)
-            .with_external_id("External_Id__c");
+        let config =
+            JobConfig::new("Contact", Operation::Upsert).with_external_id("External_Id__c");
         assert!(config.validate().is_ok());
     }
 
@@ -275,4 +277,4 @@ mod tests {
         job.number_records_processed = 0;
         assert_eq!(job.success_rate(), 0.0);
     }
-}
\ No newline at end of file
+}
@@ -51,7 +51,7 @@ pub mod utils;
 pub use client::BulkClient;
 pub use error::{BulkApiError, Result};
 pub use job::{Job, JobConfig, JobState};
-pub use models::{Operation, LineEnding, ColumnDelimiter, ContentType};
+pub use models::{ColumnDelimiter, ContentType, LineEnding, Operation};
 
 #[cfg(test)]
-mod tests;
\ No newline at end of file
+mod tests;
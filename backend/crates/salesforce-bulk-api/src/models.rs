@@ -167,7 +167,7 @@ mod tests {
     fn test_instance_url() {
         let url = InstanceUrl::new("https://myinstance.salesforce.com/");
         let version = ApiVersion::new("v62.0");
-        
+
         assert_eq!(url.0, "https://myinstance.salesforce.com");
         assert_eq!(
             url.api_base(&version),
@@ -184,4 +184,4 @@ mod tests {
         assert_eq!(ContentType::Csv.to_string(), "text/csv");
         assert_eq!(ContentType::Json.to_string(), "application/json");
     }
-}
\ No newline at end of file
+}
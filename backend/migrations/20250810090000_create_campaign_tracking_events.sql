@@ -0,0 +1,23 @@
+-- キャンペーンの開封・クリック計測イベントテーブル
+CREATE TABLE IF NOT EXISTS campaign_tracking_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    campaign_id UUID NOT NULL REFERENCES campaigns(id) ON DELETE CASCADE,
+    subscriber_id UUID NOT NULL REFERENCES subscribers(id) ON DELETE CASCADE,
+    event_type VARCHAR(20) NOT NULL CHECK (event_type IN ('open', 'click')),
+    url TEXT,
+    ip_address VARCHAR(255),
+    user_agent TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- インデックス（ユニーク開封数・クリック数の集計用）
+CREATE INDEX IF NOT EXISTS idx_campaign_tracking_events_campaign
+    ON campaign_tracking_events(campaign_id, event_type, subscriber_id);
+CREATE INDEX IF NOT EXISTS idx_campaign_tracking_events_subscriber_id
+    ON campaign_tracking_events(subscriber_id);
+CREATE INDEX IF NOT EXISTS idx_campaign_tracking_events_created_at
+    ON campaign_tracking_events(created_at);
+
+COMMENT ON TABLE campaign_tracking_events IS 'キャンペーンメールの開封・クリック計測イベント';
+COMMENT ON COLUMN campaign_tracking_events.event_type IS 'イベント種別 (open, click)';
+COMMENT ON COLUMN campaign_tracking_events.url IS 'クリックされたリンク先URL（clickのみ）';
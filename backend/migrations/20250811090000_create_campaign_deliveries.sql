@@ -0,0 +1,37 @@
+-- キャンペーンの購読者ごとの配信キューテーブル
+CREATE TABLE IF NOT EXISTS campaign_deliveries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    campaign_id UUID NOT NULL REFERENCES campaigns(id) ON DELETE CASCADE,
+    subscriber_id UUID NOT NULL REFERENCES subscribers(id) ON DELETE CASCADE,
+    email VARCHAR(255) NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'sending', 'sent', 'failed', 'skipped')),
+    attempts INT NOT NULL DEFAULT 0,
+    message_id VARCHAR(255),
+    error TEXT,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    locked_at TIMESTAMPTZ,
+    sent_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (campaign_id, subscriber_id)
+);
+
+-- インデックス
+CREATE INDEX IF NOT EXISTS idx_campaign_deliveries_campaign_status
+    ON campaign_deliveries(campaign_id, status, next_attempt_at);
+CREATE INDEX IF NOT EXISTS idx_campaign_deliveries_subscriber_id
+    ON campaign_deliveries(subscriber_id);
+CREATE INDEX IF NOT EXISTS idx_campaign_deliveries_message_id
+    ON campaign_deliveries(message_id);
+
+-- 更新日時の自動更新トリガー
+CREATE TRIGGER update_campaign_deliveries_updated_at BEFORE UPDATE ON campaign_deliveries
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+COMMENT ON TABLE campaign_deliveries IS 'キャンペーンの購読者ごとの配信状況';
+COMMENT ON COLUMN campaign_deliveries.status IS '配信ステータス (pending, sending, sent, failed, skipped)';
+COMMENT ON COLUMN campaign_deliveries.attempts IS '送信試行回数';
+COMMENT ON COLUMN campaign_deliveries.message_id IS 'メールプロバイダーが発行したメッセージID';
+COMMENT ON COLUMN campaign_deliveries.next_attempt_at IS '次回送信試行日時（リトライのバックオフ用）';
+COMMENT ON COLUMN campaign_deliveries.locked_at IS 'ワーカーが送信処理を確保した日時';
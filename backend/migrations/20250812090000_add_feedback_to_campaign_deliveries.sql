@@ -0,0 +1,11 @@
+-- SESのバウンス・苦情・配信通知を配信レコードに記録するカラムを追加
+ALTER TABLE campaign_deliveries
+    ADD COLUMN IF NOT EXISTS delivered_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS bounced_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS bounce_type VARCHAR(50),
+    ADD COLUMN IF NOT EXISTS complained_at TIMESTAMPTZ;
+
+COMMENT ON COLUMN campaign_deliveries.delivered_at IS '受信サーバーへの配信完了日時（SES配信通知）';
+COMMENT ON COLUMN campaign_deliveries.bounced_at IS 'バウンス通知を受信した日時';
+COMMENT ON COLUMN campaign_deliveries.bounce_type IS 'バウンス種別 (Permanent, Transient, Undetermined)';
+COMMENT ON COLUMN campaign_deliveries.complained_at IS '苦情（迷惑メール報告）通知を受信した日時';
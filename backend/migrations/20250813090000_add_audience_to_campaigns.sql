@@ -0,0 +1,5 @@
+-- キャンペーンの配信対象（オーディエンス）定義を追加
+ALTER TABLE campaigns
+    ADD COLUMN IF NOT EXISTS audience JSONB NOT NULL DEFAULT '{}';
+
+COMMENT ON COLUMN campaigns.audience IS '配信対象の定義（含める・除外するタグ、購読者ステータス、カスタムフィールドの条件）';
@@ -0,0 +1,20 @@
+-- 保存済みセグメント（購読者の条件ツリー）
+CREATE TABLE IF NOT EXISTS segments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    description TEXT,
+    rules JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_segments_user_id ON segments(user_id);
+
+CREATE TRIGGER update_segments_updated_at
+    BEFORE UPDATE ON segments
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+COMMENT ON TABLE segments IS '再利用可能な購読者セグメント';
+COMMENT ON COLUMN segments.rules IS '条件ツリー（all/any/not とタグ・ステータス・登録日時・カスタムフィールド・開封/クリック条件）';
@@ -0,0 +1,50 @@
+-- キャンペーンのA/Bテスト設定
+CREATE TABLE IF NOT EXISTS campaign_ab_tests (
+    campaign_id UUID PRIMARY KEY REFERENCES campaigns(id) ON DELETE CASCADE,
+    test_percentage INTEGER NOT NULL CHECK (test_percentage BETWEEN 1 AND 100),
+    winner_metric VARCHAR(20) NOT NULL CHECK (winner_metric IN ('opens', 'clicks')),
+    wait_minutes INTEGER NOT NULL CHECK (wait_minutes > 0),
+    status VARCHAR(20) NOT NULL DEFAULT 'draft' CHECK (status IN ('draft', 'testing', 'completed')),
+    test_started_at TIMESTAMPTZ,
+    winner_variant_id UUID,
+    winner_selected_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- A/Bテストのバリアント（件名・テンプレート）
+CREATE TABLE IF NOT EXISTS campaign_variants (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    campaign_id UUID NOT NULL REFERENCES campaigns(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    subject VARCHAR(255) NOT NULL,
+    template_id UUID REFERENCES templates(id) ON DELETE SET NULL,
+    split_percentage INTEGER NOT NULL CHECK (split_percentage BETWEEN 1 AND 100),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (campaign_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_campaign_variants_campaign_id ON campaign_variants(campaign_id);
+
+ALTER TABLE campaign_deliveries
+    ADD COLUMN IF NOT EXISTS variant_id UUID REFERENCES campaign_variants(id) ON DELETE SET NULL;
+
+CREATE TRIGGER update_campaign_ab_tests_updated_at
+    BEFORE UPDATE ON campaign_ab_tests
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TRIGGER update_campaign_variants_updated_at
+    BEFORE UPDATE ON campaign_variants
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+COMMENT ON TABLE campaign_ab_tests IS 'キャンペーンのA/Bテスト設定と進行状況';
+COMMENT ON COLUMN campaign_ab_tests.test_percentage IS 'テストに使う配信対象の割合（%）。残りには勝者のバリアントを送信する';
+COMMENT ON COLUMN campaign_ab_tests.winner_metric IS '勝者の判定指標 (opens, clicks)';
+COMMENT ON COLUMN campaign_ab_tests.wait_minutes IS 'テスト送信開始から勝者を判定するまでの待機時間（分）';
+COMMENT ON COLUMN campaign_ab_tests.status IS '進行状況 (draft, testing, completed)';
+COMMENT ON COLUMN campaign_variants.template_id IS 'バリアントのテンプレート（NULLの場合はキャンペーンのテンプレート）';
+COMMENT ON COLUMN campaign_variants.split_percentage IS 'テスト対象内での配分（%）。バリアントの合計は100';
+COMMENT ON COLUMN campaign_deliveries.variant_id IS '送信したバリアント（A/Bテストでない場合はNULL）';
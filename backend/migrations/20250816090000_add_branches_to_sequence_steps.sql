@@ -0,0 +1,19 @@
+-- 条件ステップの分岐先
+-- 削除されたステップを指す分岐は有効化時の検証で検出するため、外部キーは設定しない
+ALTER TABLE sequence_steps
+    ADD COLUMN IF NOT EXISTS true_step_id UUID,
+    ADD COLUMN IF NOT EXISTS false_step_id UUID;
+
+-- 実行中のエンロールメントは次に実行するステップを指すようにする
+UPDATE sequence_enrollments e
+SET current_step_id = (
+    SELECT s.id FROM sequence_steps s
+    WHERE s.sequence_id = e.sequence_id
+    ORDER BY s.step_order ASC
+    LIMIT 1
+)
+WHERE e.status = 'active' AND e.current_step_id IS NULL;
+
+COMMENT ON COLUMN sequence_steps.true_step_id IS '条件を満たした場合の分岐先（未設定の場合は次の順序のステップ）';
+COMMENT ON COLUMN sequence_steps.false_step_id IS '条件を満たさない場合の分岐先（未設定の場合は次の順序のステップ）';
+COMMENT ON COLUMN sequence_enrollments.current_step_id IS '次に実行するステップ（未設定の場合は完了）';
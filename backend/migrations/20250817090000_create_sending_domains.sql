@@ -0,0 +1,38 @@
+-- ユーザーの送信ドメイン（DKIM鍵とDNS認証の状態）
+CREATE TABLE IF NOT EXISTS sending_domains (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    domain VARCHAR(253) NOT NULL,
+    dkim_selector VARCHAR(63) NOT NULL,
+    dkim_private_key TEXT NOT NULL,
+    dkim_public_key TEXT NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'verified', 'failed')),
+    dkim_verified BOOLEAN NOT NULL DEFAULT false,
+    spf_verified BOOLEAN NOT NULL DEFAULT false,
+    dmarc_verified BOOLEAN NOT NULL DEFAULT false,
+    last_checked_at TIMESTAMPTZ,
+    verified_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, domain)
+);
+
+CREATE INDEX IF NOT EXISTS idx_sending_domains_user_id ON sending_domains(user_id);
+
+CREATE TRIGGER update_sending_domains_updated_at
+    BEFORE UPDATE ON sending_domains
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- キャンペーンの送信元（NULLの場合はプラットフォームの送信元アドレス）
+ALTER TABLE campaigns
+    ADD COLUMN IF NOT EXISTS from_email VARCHAR(255),
+    ADD COLUMN IF NOT EXISTS from_name VARCHAR(255);
+
+COMMENT ON TABLE sending_domains IS 'ユーザーが登録した送信ドメイン';
+COMMENT ON COLUMN sending_domains.dkim_selector IS 'DKIMセレクタ（<selector>._domainkey.<domain> にTXTレコードを公開する）';
+COMMENT ON COLUMN sending_domains.dkim_private_key IS 'DKIM署名用のRSA秘密鍵（PKCS#1 PEM）';
+COMMENT ON COLUMN sending_domains.dkim_public_key IS 'DKIMレコードに公開する公開鍵（SubjectPublicKeyInfoのBase64）';
+COMMENT ON COLUMN sending_domains.status IS '認証状態 (pending: 未確認, verified: 認証済み, failed: 直近の確認で失敗)';
+COMMENT ON COLUMN campaigns.from_email IS '送信元アドレス（認証済みの送信ドメインのアドレスのみ）';
+COMMENT ON COLUMN campaigns.from_name IS '送信者名';
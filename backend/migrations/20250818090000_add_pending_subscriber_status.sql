@@ -0,0 +1,2 @@
+-- ダブルオプトイン: 確認メールのリンクで購読を確定するまでの購読者ステータス
+ALTER TYPE subscriber_status ADD VALUE IF NOT EXISTS 'pending';
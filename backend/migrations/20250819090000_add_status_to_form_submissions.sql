@@ -0,0 +1,7 @@
+-- スパムの疑いがある送信を隔離するためのステータス
+ALTER TABLE form_submissions
+    ADD COLUMN status VARCHAR(20) NOT NULL DEFAULT 'accepted'
+        CHECK (status IN ('accepted', 'quarantined')),
+    ADD COLUMN quarantine_reason VARCHAR(50);
+
+CREATE INDEX IF NOT EXISTS idx_form_submissions_form_id_status ON form_submissions(form_id, status);
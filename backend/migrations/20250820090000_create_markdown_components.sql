@@ -0,0 +1,24 @@
+-- ユーザー定義のマークダウンコンポーネントとスニペット
+CREATE TABLE IF NOT EXISTS markdown_components (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(50) NOT NULL,
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('component', 'snippet')),
+    description TEXT,
+    content TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, kind, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_markdown_components_user_id ON markdown_components(user_id);
+
+CREATE TRIGGER update_markdown_components_updated_at
+    BEFORE UPDATE ON markdown_components
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+COMMENT ON TABLE markdown_components IS 'マークダウンから呼び出すコンポーネントとスニペット';
+COMMENT ON COLUMN markdown_components.name IS 'マークダウンで指定する名前（:::name または :::snippet{name="name"}）';
+COMMENT ON COLUMN markdown_components.kind IS '種類 (component: 属性と中身を埋め込むHTMLテンプレート, snippet: 埋め込むマークダウン)';
+COMMENT ON COLUMN markdown_components.content IS 'コンポーネントはHTMLテンプレート、スニペットはマークダウン';
@@ -0,0 +1,43 @@
+-- テンプレートのリビジョン（保存ごとの内容を変更不可の履歴として残す）
+CREATE TABLE IF NOT EXISTS template_revisions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    template_id UUID NOT NULL REFERENCES templates(id) ON DELETE CASCADE,
+    revision INTEGER NOT NULL CHECK (revision > 0),
+    subject_template TEXT NOT NULL,
+    markdown_content TEXT NOT NULL,
+    variables JSONB NOT NULL DEFAULT '{}',
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    note TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (template_id, revision)
+);
+
+ALTER TABLE templates
+    ADD COLUMN IF NOT EXISTS current_revision INTEGER NOT NULL DEFAULT 0;
+
+-- 既存のテンプレートは現在の内容を最初のリビジョンにする
+INSERT INTO template_revisions (template_id, revision, subject_template, markdown_content, variables, created_by, created_at)
+SELECT id, 1, subject_template, markdown_content, COALESCE(variables, '{}'), user_id, COALESCE(updated_at, NOW())
+FROM templates
+ON CONFLICT (template_id, revision) DO NOTHING;
+
+UPDATE templates SET current_revision = 1 WHERE current_revision = 0;
+
+-- 配信したリビジョン
+ALTER TABLE campaigns
+    ADD COLUMN IF NOT EXISTS template_revision_id UUID REFERENCES template_revisions(id) ON DELETE SET NULL;
+
+ALTER TABLE campaign_variants
+    ADD COLUMN IF NOT EXISTS template_revision_id UUID REFERENCES template_revisions(id) ON DELETE SET NULL;
+
+ALTER TABLE sequence_step_logs
+    ADD COLUMN IF NOT EXISTS template_revision_id UUID REFERENCES template_revisions(id) ON DELETE SET NULL;
+
+COMMENT ON TABLE template_revisions IS 'テンプレートの変更履歴（作成後は更新しない）';
+COMMENT ON COLUMN template_revisions.revision IS 'テンプレートごとの連番（1から）';
+COMMENT ON COLUMN template_revisions.created_by IS 'リビジョンを作成したユーザー';
+COMMENT ON COLUMN template_revisions.note IS '変更内容のメモ（ロールバック時は復元元のリビジョン）';
+COMMENT ON COLUMN templates.current_revision IS '現在の内容に対応するリビジョン番号';
+COMMENT ON COLUMN campaigns.template_revision_id IS '配信時に固定したテンプレートのリビジョン（配信中にテンプレートを編集しても内容は変わらない）';
+COMMENT ON COLUMN campaign_variants.template_revision_id IS '配信時に固定したバリアントのテンプレートのリビジョン';
+COMMENT ON COLUMN sequence_step_logs.template_revision_id IS '送信したテンプレートのリビジョン';
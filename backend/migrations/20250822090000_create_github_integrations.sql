@@ -0,0 +1,49 @@
+-- GitHub連携（ユーザーごとに1つのアクセストークン）
+CREATE TABLE IF NOT EXISTS github_connections (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL UNIQUE REFERENCES users(id) ON DELETE CASCADE,
+    access_token TEXT NOT NULL,
+    account_login VARCHAR(255) NOT NULL,
+    webhook_secret VARCHAR(64) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- GitHubのマークダウンファイルから取り込んだテンプレート
+CREATE TABLE IF NOT EXISTS github_template_sources (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    template_id UUID NOT NULL UNIQUE REFERENCES templates(id) ON DELETE CASCADE,
+    repository VARCHAR(255) NOT NULL,
+    path TEXT NOT NULL,
+    git_ref VARCHAR(255) NOT NULL,
+    auto_sync BOOLEAN NOT NULL DEFAULT false,
+    last_synced_sha VARCHAR(64),
+    last_synced_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, repository, git_ref, path)
+);
+
+CREATE INDEX IF NOT EXISTS idx_github_template_sources_repository
+    ON github_template_sources(repository, git_ref) WHERE auto_sync = true;
+
+CREATE TRIGGER update_github_connections_updated_at
+    BEFORE UPDATE ON github_connections
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TRIGGER update_github_template_sources_updated_at
+    BEFORE UPDATE ON github_template_sources
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+COMMENT ON TABLE github_connections IS 'GitHub連携の接続情報';
+COMMENT ON COLUMN github_connections.access_token IS 'リポジトリの読み取り権限を持つトークン（個人アクセストークンまたはGitHub Appのインストールトークン）';
+COMMENT ON COLUMN github_connections.account_login IS 'トークンのGitHubアカウント';
+COMMENT ON COLUMN github_connections.webhook_secret IS 'pushイベントのWebhook署名（X-Hub-Signature-256）の検証に使う秘密鍵';
+COMMENT ON TABLE github_template_sources IS 'GitHubから取り込んだテンプレートの取り込み元';
+COMMENT ON COLUMN github_template_sources.repository IS 'リポジトリ（owner/name）';
+COMMENT ON COLUMN github_template_sources.git_ref IS 'ブランチ名';
+COMMENT ON COLUMN github_template_sources.auto_sync IS 'pushのWebhookを受けたときにテンプレートを更新するか';
+COMMENT ON COLUMN github_template_sources.last_synced_sha IS '最後に取り込んだファイルのblob SHA';
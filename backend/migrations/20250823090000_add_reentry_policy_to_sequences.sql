@@ -0,0 +1,19 @@
+-- シーケンスへの再登録ポリシー
+--   never: 一度登録された購読者は再登録しない
+--   after_completion: 完了・キャンセルした購読者は再登録できる
+ALTER TABLE sequences
+    ADD COLUMN IF NOT EXISTS reentry_policy VARCHAR(50) NOT NULL DEFAULT 'never';
+
+ALTER TABLE sequences DROP CONSTRAINT IF EXISTS sequences_reentry_policy_check;
+ALTER TABLE sequences
+    ADD CONSTRAINT sequences_reentry_policy_check CHECK (reentry_policy IN ('never', 'after_completion'));
+
+-- 再登録できるように、一意制約を実行中（active・paused）のエンロールメントだけに限定する
+ALTER TABLE sequence_enrollments
+    DROP CONSTRAINT IF EXISTS sequence_enrollments_sequence_id_subscriber_id_key;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_sequence_enrollments_in_progress
+    ON sequence_enrollments(sequence_id, subscriber_id)
+    WHERE status IN ('active', 'paused');
+
+COMMENT ON COLUMN sequences.reentry_policy IS '再登録ポリシー（never: 再登録しない, after_completion: 完了・キャンセル後は再登録できる）';
@@ -0,0 +1,6 @@
+-- シーケンスの終了条件（満たした購読者のエンロールメントを goal_reached・exited で終了する）
+ALTER TABLE sequences
+    ADD COLUMN IF NOT EXISTS exit_conditions JSONB NOT NULL DEFAULT '[]';
+
+COMMENT ON COLUMN sequences.exit_conditions IS '終了条件（tag_added・custom_field・unsubscribed・link_clicked・form_submitted）';
+COMMENT ON COLUMN sequence_enrollments.status IS 'ステータス（active, paused, completed, cancelled, goal_reached, exited）';
@@ -0,0 +1,5 @@
+-- シーケンスの送信時間帯（購読者のタイムゾーンでの曜日・時間、空の場合は制限なし）
+ALTER TABLE sequences
+    ADD COLUMN IF NOT EXISTS send_window JSONB NOT NULL DEFAULT '{}';
+
+COMMENT ON COLUMN sequences.send_window IS '送信時間帯（days・start_hour・end_hour・timezone・timezone_field）';
@@ -0,0 +1,10 @@
+-- シーケンスワーカーによるエンロールメントの確保（複数のレプリカで同じステップを二重に実行しない）
+ALTER TABLE sequence_enrollments
+    ADD COLUMN IF NOT EXISTS locked_at TIMESTAMPTZ;
+
+COMMENT ON COLUMN sequence_enrollments.locked_at IS 'ワーカーがステップの実行のために確保した日時（処理後にNULLに戻す）';
+
+-- 実行待ちのエンロールメントの検索用
+CREATE INDEX IF NOT EXISTS idx_sequence_enrollments_due
+    ON sequence_enrollments(next_step_at)
+    WHERE status = 'active';
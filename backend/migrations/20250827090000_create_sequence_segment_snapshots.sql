@@ -0,0 +1,10 @@
+-- セグメントトリガーのシーケンスを有効化した時点でセグメントに一致していた購読者
+-- （有効化後に新たに一致した購読者のみを登録するため、既存の一致者を除外する）
+CREATE TABLE IF NOT EXISTS sequence_segment_snapshots (
+    sequence_id UUID NOT NULL REFERENCES sequences(id) ON DELETE CASCADE,
+    subscriber_id UUID NOT NULL REFERENCES subscribers(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (sequence_id, subscriber_id)
+);
+
+COMMENT ON TABLE sequence_segment_snapshots IS 'セグメントトリガーのシーケンスの有効化時点のセグメント一致者（セグメントから外れると削除し、再び一致した時点で登録対象にする）';
@@ -0,0 +1,20 @@
+-- 条件ステップの分岐先に外部キーを設定（分岐先のステップを削除すると未設定に戻す）
+UPDATE sequence_steps s
+SET true_step_id = NULL
+WHERE true_step_id IS NOT NULL
+  AND NOT EXISTS (SELECT 1 FROM sequence_steps t WHERE t.id = s.true_step_id);
+
+UPDATE sequence_steps s
+SET false_step_id = NULL
+WHERE false_step_id IS NOT NULL
+  AND NOT EXISTS (SELECT 1 FROM sequence_steps t WHERE t.id = s.false_step_id);
+
+ALTER TABLE sequence_steps
+    DROP CONSTRAINT IF EXISTS sequence_steps_true_step_id_fkey,
+    DROP CONSTRAINT IF EXISTS sequence_steps_false_step_id_fkey;
+
+ALTER TABLE sequence_steps
+    ADD CONSTRAINT sequence_steps_true_step_id_fkey
+        FOREIGN KEY (true_step_id) REFERENCES sequence_steps(id) ON DELETE SET NULL,
+    ADD CONSTRAINT sequence_steps_false_step_id_fkey
+        FOREIGN KEY (false_step_id) REFERENCES sequence_steps(id) ON DELETE SET NULL;
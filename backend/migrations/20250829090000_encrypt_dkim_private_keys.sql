@@ -0,0 +1,2 @@
+-- DKIM秘密鍵はアプリケーションの鍵で暗号化して保存する（暗号化前の鍵は起動時に暗号化する）
+COMMENT ON COLUMN sending_domains.dkim_private_key IS 'DKIM署名用のRSA秘密鍵（PKCS#1 PEMをAES-256-GCMで暗号化した値、接頭辞 enc:v1:）';
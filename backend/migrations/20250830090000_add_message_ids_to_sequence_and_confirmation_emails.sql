@@ -0,0 +1,18 @@
+-- キャンペーン以外で送信したメールのSESメッセージID（バウンス・苦情の通知から宛先の購読者を特定する）
+ALTER TABLE sequence_step_logs
+    ADD COLUMN IF NOT EXISTS message_id TEXT;
+
+COMMENT ON COLUMN sequence_step_logs.message_id IS '送信したメールのメッセージID（SESの通知との照合用）';
+
+CREATE INDEX IF NOT EXISTS idx_sequence_step_logs_message_id
+    ON sequence_step_logs(message_id)
+    WHERE message_id IS NOT NULL;
+
+ALTER TABLE form_submissions
+    ADD COLUMN IF NOT EXISTS confirmation_message_id TEXT;
+
+COMMENT ON COLUMN form_submissions.confirmation_message_id IS 'ダブルオプトインの確認メールのメッセージID（SESの通知との照合用）';
+
+CREATE INDEX IF NOT EXISTS idx_form_submissions_confirmation_message_id
+    ON form_submissions(confirmation_message_id)
+    WHERE confirmation_message_id IS NOT NULL;
@@ -0,0 +1,2 @@
+-- ステップの実行に失敗し続けた（または再実行しても成功しない）エンロールメントは failed で終了する
+COMMENT ON COLUMN sequence_enrollments.status IS 'ステータス（active, paused, completed, cancelled, goal_reached, exited, failed）';
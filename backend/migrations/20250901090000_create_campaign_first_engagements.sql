@@ -0,0 +1,19 @@
+-- 購読者ごとの初回の開封・クリック（同時に届いた計測リクエストでユニーク数を二重に加算しない）
+CREATE TABLE IF NOT EXISTS campaign_first_engagements (
+    campaign_id UUID NOT NULL REFERENCES campaigns(id) ON DELETE CASCADE,
+    subscriber_id UUID NOT NULL REFERENCES subscribers(id) ON DELETE CASCADE,
+    event_type VARCHAR(20) NOT NULL CHECK (event_type IN ('open', 'click')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_campaign_first_engagements_unique
+    ON campaign_first_engagements(campaign_id, subscriber_id, event_type);
+
+COMMENT ON TABLE campaign_first_engagements IS 'キャンペーンメールの購読者ごとの初回の開封・クリック';
+
+-- 記録済みの計測イベントから初回の開封・クリックを登録
+INSERT INTO campaign_first_engagements (campaign_id, subscriber_id, event_type, created_at)
+SELECT campaign_id, subscriber_id, event_type, MIN(created_at)
+FROM campaign_tracking_events
+GROUP BY campaign_id, subscriber_id, event_type
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,7 @@
+-- GitHub連携のトークン・Webhookの秘密鍵を暗号化して保存する（暗号文が64文字を超えるためTEXTに変更）
+-- 既存の平文の値はアプリケーション起動時に暗号化される
+ALTER TABLE github_connections
+    ALTER COLUMN webhook_secret TYPE TEXT;
+
+COMMENT ON COLUMN github_connections.access_token IS 'リポジトリの読み取り権限を持つトークン（暗号化して保存）';
+COMMENT ON COLUMN github_connections.webhook_secret IS 'pushイベントのWebhook署名（X-Hub-Signature-256）の検証に使う秘密鍵（暗号化して保存）';
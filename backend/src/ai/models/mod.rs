@@ -2,6 +2,7 @@ pub mod ai_responses;
 pub mod prompts;
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests;
 
 use chrono::{DateTime, Utc};
@@ -3,6 +3,7 @@
 use crate::ai::models::Language;
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests;
 
 /// シナリオ生成用のシステムプロンプト（英語）
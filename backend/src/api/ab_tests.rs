@@ -0,0 +1,131 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::{
+    database::campaigns,
+    middleware::auth::AuthUser,
+    models::{
+        ab_test::{AbTestResponse, ConfigureAbTestRequest},
+        campaign::Campaign,
+    },
+    services::{ab_test_service::AbTestService, subscription_service},
+    AppState,
+};
+
+fn error(status: StatusCode, message: &str) -> (StatusCode, Json<Value>) {
+    (status, Json(json!({ "error": message })))
+}
+
+async fn find_campaign(
+    state: &AppState,
+    campaign_id: Uuid,
+    user_id: Uuid,
+) -> Result<Campaign, (StatusCode, Json<Value>)> {
+    match campaigns::find_campaign_by_id(&state.db, campaign_id, user_id).await {
+        Ok(Some(campaign)) => Ok(campaign),
+        Ok(None) => Err(error(StatusCode::NOT_FOUND, "キャンペーンが見つかりません")),
+        Err(e) => {
+            tracing::error!("キャンペーン取得エラー: {:?}", e);
+            Err(error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "キャンペーンの取得に失敗しました",
+            ))
+        }
+    }
+}
+
+/// キャンペーンのA/Bテスト設定と結果を取得
+pub async fn get_ab_test(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<AbTestResponse>, (StatusCode, Json<Value>)> {
+    let campaign = find_campaign(&state, id, auth_user.user_id).await?;
+
+    match AbTestService::new()
+        .get_ab_test(&state.db, campaign.id)
+        .await
+    {
+        Ok(Some(response)) => Ok(Json(response)),
+        Ok(None) => Err(error(
+            StatusCode::NOT_FOUND,
+            "A/Bテストが設定されていません",
+        )),
+        Err(e) => {
+            tracing::error!("A/Bテスト取得エラー: {}", e);
+            Err(error(StatusCode::INTERNAL_SERVER_ERROR, &e))
+        }
+    }
+}
+
+/// キャンペーンのA/Bテストを設定（既存の設定は置き換える）
+pub async fn configure_ab_test(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ConfigureAbTestRequest>,
+) -> Result<Json<AbTestResponse>, (StatusCode, Json<Value>)> {
+    // プランの機能制限をチェック
+    let has_access =
+        subscription_service::check_feature_access(&state.db, auth_user.user_id, "ab_testing")
+            .await
+            .map_err(|e| {
+                tracing::error!("機能アクセスチェックエラー: {:?}", e);
+                error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "プラン情報の確認に失敗しました",
+                )
+            })?;
+
+    if !has_access {
+        return Err(error(
+            StatusCode::PAYMENT_REQUIRED,
+            "A/Bテスト機能はご利用のプランでは使用できません。プランをアップグレードしてください。",
+        ));
+    }
+
+    let campaign = find_campaign(&state, id, auth_user.user_id).await?;
+
+    match AbTestService::new()
+        .configure_ab_test(&state.db, &campaign, &payload)
+        .await
+    {
+        Ok(response) => {
+            tracing::info!("A/Bテスト設定: {}", campaign.id);
+            Ok(Json(response))
+        }
+        Err(e) => {
+            tracing::error!("A/Bテスト設定エラー: {}", e);
+            Err(error(StatusCode::BAD_REQUEST, &e))
+        }
+    }
+}
+
+/// キャンペーンのA/Bテストを削除
+pub async fn delete_ab_test(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let campaign = find_campaign(&state, id, auth_user.user_id).await?;
+
+    match AbTestService::new()
+        .delete_ab_test(&state.db, &campaign)
+        .await
+    {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err(error(
+            StatusCode::NOT_FOUND,
+            "A/Bテストが設定されていません",
+        )),
+        Err(e) => {
+            tracing::error!("A/Bテスト削除エラー: {}", e);
+            Err(error(StatusCode::BAD_REQUEST, &e))
+        }
+    }
+}
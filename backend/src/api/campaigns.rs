@@ -7,23 +7,23 @@ use axum::{
 };
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use sqlx::PgPool;
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
+    api::markdown::user_markdown_service,
     database::{
+        campaign_deliveries,
         campaigns::{self, find_campaign_by_id},
         templates,
     },
     middleware::auth::AuthUser,
     models::campaign::{
-        CampaignListResponse, CampaignResponse, CampaignStatus, CreateCampaignRequest,
-        ListCampaignOptions, ScheduleCampaignRequest, UpdateCampaignRequest,
-    },
-    services::{
-        campaign_service::CampaignService, markdown_service::MarkdownService,
-        subscriber_service::SubscriberService,
+        CampaignListResponse, CampaignResponse, CampaignStatus, CampaignSubscribersQuery,
+        CreateCampaignRequest, ListCampaignOptions, ScheduleCampaignRequest, UpdateCampaignRequest,
     },
+    services::campaign_service::CampaignService,
     AppState,
 };
 
@@ -256,20 +256,16 @@ pub async fn validate_campaign_before_send(
         }
     };
 
-    // マークダウンサービスを初期化
-    let markdown_service = MarkdownService::new();
+    // マークダウンサービスを初期化（ユーザーのコンポーネントを読み込む）
+    let markdown_service = user_markdown_service(&state, &auth_user).await?;
 
-    // テンプレートから変数を抽出
-    let used_variables = markdown_service.extract_variables(&template.markdown_content);
-    let subject_variables = markdown_service.extract_variables(&template.subject_template);
-
-    // 全ての使用変数をマージ（重複除去）
-    let mut all_variables: Vec<String> = used_variables;
-    for var in subject_variables {
-        if !all_variables.contains(&var) {
-            all_variables.push(var);
-        }
-    }
+    // テンプレートの構文を検証し、変数を抽出
+    let syntax_error = markdown_service
+        .validate_template(&template.markdown_content, &template.subject_template)
+        .err();
+    let template_vars =
+        markdown_service.analyze_template(&template.markdown_content, &template.subject_template);
+    let all_variables = template_vars.variables.clone();
 
     // システムで自動的に提供される標準変数
     let standard_variables = vec![
@@ -294,17 +290,19 @@ pub async fn validate_campaign_before_send(
             vec![]
         };
 
-    // 不足している変数（カスタム変数のうち定義されていないもの）
+    // 不足している変数（カスタム変数のうち定義されておらず、値なしでは描画できないもの）
     let missing_variables: Vec<String> = custom_variables
         .iter()
-        .filter(|var| !defined_variables.contains(var))
+        .filter(|var| {
+            !defined_variables.contains(var) && !template_vars.optional_variables.contains(var)
+        })
         .cloned()
         .collect();
 
-    // 購読者数を取得
+    // 配信対象の購読者数を取得
     let campaign_service = CampaignService::new();
-    let subscribers = campaign_service
-        .get_campaign_subscribers(&state.db, id, auth_user.user_id)
+    let subscriber_count = campaign_service
+        .count_campaign_subscribers(&state.db, id, auth_user.user_id)
         .await
         .map_err(|e| {
             tracing::error!("購読者取得エラー: {}", e);
@@ -316,27 +314,45 @@ pub async fn validate_campaign_before_send(
             )
         })?;
 
-    let is_valid = missing_variables.is_empty() && !subscribers.is_empty();
+    let is_valid = missing_variables.is_empty() && syntax_error.is_none() && subscriber_count > 0;
+
+    let mut warnings = Vec::new();
+    if let Some(error) = syntax_error {
+        warnings.push(error);
+    }
+    if !missing_variables.is_empty() {
+        warnings.push(format!(
+            "以下の変数がテンプレートで定義されていません: {}",
+            missing_variables.join(", ")
+        ));
+    }
 
     Ok(Json(json!({
         "is_valid": is_valid,
-        "subscriber_count": subscribers.len(),
+        "subscriber_count": subscriber_count,
         "used_variables": all_variables,
         "standard_variables": standard_variables,
         "custom_variables": custom_variables,
         "defined_variables": defined_variables,
+        "optional_variables": template_vars.optional_variables,
         "missing_variables": missing_variables,
-        "warnings": if missing_variables.is_empty() {
-            vec![]
-        } else {
-            vec![format!(
-                "以下の変数がテンプレートで定義されていません: {}",
-                missing_variables.join(", ")
-            )]
-        }
+        "warnings": warnings,
     })))
 }
 
+/// バックグラウンドでキャンペーンの送信処理を実行
+fn spawn_campaign_sending(db: PgPool, campaign_id: Uuid, user_id: Uuid) {
+    tokio::spawn(async move {
+        let campaign_service = CampaignService::new();
+        if let Err(e) = campaign_service
+            .process_campaign_sending(&db, campaign_id, user_id)
+            .await
+        {
+            tracing::error!("キャンペーン送信処理エラー: {}", e);
+        }
+    });
+}
+
 /// キャンペーン送信
 pub async fn send_campaign(
     Extension(auth_user): Extension<AuthUser>,
@@ -353,19 +369,7 @@ pub async fn send_campaign(
             tracing::info!("キャンペーン送信開始: {}", campaign.id);
 
             // 非同期でメール送信処理を開始
-            let db = state.db.clone();
-            let campaign_id = campaign.id;
-            let user_id = auth_user.user_id;
-
-            tokio::spawn(async move {
-                let campaign_service = CampaignService::new();
-                if let Err(e) = campaign_service
-                    .process_campaign_sending(&db, campaign_id, user_id)
-                    .await
-                {
-                    tracing::error!("キャンペーン送信処理エラー: {}", e);
-                }
-            });
+            spawn_campaign_sending(state.db.clone(), campaign.id, auth_user.user_id);
 
             Ok(Json(json!({
                 "message": "キャンペーンの送信を開始しました",
@@ -414,6 +418,33 @@ pub async fn schedule_campaign(
     }
 }
 
+/// キャンペーンのスケジュールを取り消し
+pub async fn cancel_campaign_schedule(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CampaignResponse>, (StatusCode, Json<Value>)> {
+    let campaign_service = CampaignService::new();
+    match campaign_service
+        .cancel_scheduled_campaign(&state.db, id, auth_user.user_id)
+        .await
+    {
+        Ok(campaign) => {
+            tracing::info!("キャンペーンスケジュール取り消し: {}", campaign.id);
+            Ok(Json(campaign.into()))
+        }
+        Err(e) => {
+            tracing::error!("キャンペーンスケジュール取り消しエラー: {}", e);
+            Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": e
+                })),
+            ))
+        }
+    }
+}
+
 /// キャンペーンプレビュー
 pub async fn preview_campaign(
     Extension(auth_user): Extension<AuthUser>,
@@ -444,17 +475,20 @@ pub struct PreviewCampaignResponse {
     pub html: String,
 }
 
-/// キャンペーンの購読者一覧を取得
+/// キャンペーンの配信対象の購読者一覧を取得（オーディエンス定義のプレビュー）
 pub async fn get_campaign_subscribers(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
     Path(id): Path<Uuid>,
+    Query(query): Query<CampaignSubscribersQuery>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    let offset = query.offset.unwrap_or(0).max(0);
+
     // キャンペーンの存在確認と権限チェック
-    let _campaign = match find_campaign_by_id(&state.db, id, auth_user.user_id).await {
-        Ok(campaign) => campaign,
-        Err(e) => {
-            tracing::error!("キャンペーン取得エラー: {}", e);
+    match find_campaign_by_id(&state.db, id, auth_user.user_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
             return Err((
                 StatusCode::NOT_FOUND,
                 Json(json!({
@@ -462,28 +496,28 @@ pub async fn get_campaign_subscribers(
                 })),
             ));
         }
-    };
+        Err(e) => {
+            tracing::error!("キャンペーン取得エラー: {}", e);
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": "キャンペーンの取得に失敗しました"
+                })),
+            ));
+        }
+    }
 
-    // 購読者サービスを使用して購読者を取得
-    let subscriber_service = SubscriberService::new();
-
-    // TODO: キャンペーンに関連する購読者のみを取得する実装
-    // 現在は全てのアクティブな購読者を取得
-    match subscriber_service
-        .list_subscribers(
-            &state.db,
-            auth_user.user_id,
-            None, // ステータスフィルタを一時的に無効化
-            None,
-            100, // 最大100件
-            0,
-        )
+    let campaign_service = CampaignService::new();
+    match campaign_service
+        .preview_campaign_subscribers(&state.db, id, auth_user.user_id, limit, offset)
         .await
     {
-        Ok(subscribers) => Ok(Json(json!({
+        Ok((subscribers, total)) => Ok(Json(json!({
             "subscribers": subscribers,
             "campaign_id": id,
-            "total": subscribers.len()
+            "total": total,
+            "limit": limit,
+            "offset": offset
         }))),
         Err(e) => {
             tracing::error!("購読者取得エラー: {}", e);
@@ -531,6 +565,50 @@ pub async fn resend_campaign(
         | CampaignStatus::Error
         | CampaignStatus::Cancelled
         | CampaignStatus::Sending => {
+            // 失敗した配信がある場合はその購読者のみに再送
+            let campaign_service = CampaignService::new();
+            match campaign_service
+                .retry_failed_deliveries(&state.db, id, auth_user.user_id)
+                .await
+            {
+                Ok(0) => {}
+                Ok(retry_count) => {
+                    tracing::info!(
+                        "キャンペーン {} の失敗した配信 {} 件を再送します",
+                        id,
+                        retry_count
+                    );
+                    spawn_campaign_sending(state.db.clone(), id, auth_user.user_id);
+
+                    return Ok(Json(json!({
+                        "message": "失敗した配信の再送を開始しました",
+                        "campaign_id": id,
+                        "status": "sending",
+                        "retry_count": retry_count
+                    })));
+                }
+                Err(e) => {
+                    tracing::error!("失敗した配信の再送設定エラー: {}", e);
+                    return Err((
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(json!({
+                            "error": format!("キャンペーンの再送に失敗しました: {}", e)
+                        })),
+                    ));
+                }
+            }
+
+            // 全件再送のため以前の配信記録を削除
+            if let Err(e) = campaign_deliveries::delete_deliveries(&state.db, id).await {
+                tracing::error!("配信記録の削除エラー: {:?}", e);
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({
+                        "error": "配信記録の削除に失敗しました"
+                    })),
+                ));
+            }
+
             // ステータスをdraftに戻す
             if let Err(e) = campaigns::update_campaign_status(
                 &state.db,
@@ -558,7 +636,6 @@ pub async fn resend_campaign(
             }
 
             // 送信処理を開始
-            let campaign_service = CampaignService::new();
             match campaign_service
                 .start_sending_campaign(&state.db, id, auth_user.user_id)
                 .await
@@ -567,19 +644,7 @@ pub async fn resend_campaign(
                     tracing::info!("キャンペーン再送開始: {}", campaign.id);
 
                     // 非同期でメール送信処理を開始
-                    let db = state.db.clone();
-                    let campaign_id = campaign.id;
-                    let user_id = auth_user.user_id;
-
-                    tokio::spawn(async move {
-                        let campaign_service = CampaignService::new();
-                        if let Err(e) = campaign_service
-                            .process_campaign_sending(&db, campaign_id, user_id)
-                            .await
-                        {
-                            tracing::error!("キャンペーン再送処理エラー: {}", e);
-                        }
-                    });
+                    spawn_campaign_sending(state.db.clone(), campaign.id, auth_user.user_id);
 
                     Ok(Json(json!({
                         "message": "キャンペーンの再送を開始しました",
@@ -5,12 +5,16 @@ use axum::{
     Json,
 };
 use serde::{Deserialize, Serialize};
+use std::future::Future;
 use uuid::Uuid;
 
 use crate::{
     database::subscribers::find_subscriber_by_id,
     middleware::auth::AuthUser,
-    models::crm::{CrmContact, CrmIntegrationSettings, CrmProviderType},
+    models::{
+        crm::{CrmBulkSyncResult, CrmContact, CrmIntegrationSettings, CrmProviderType},
+        subscriber::Subscriber,
+    },
     services::crm_service::{salesforce_auth::SalesforceAuth, CrmService, SaveIntegrationParams},
     AppState,
 };
@@ -74,6 +78,16 @@ pub struct CrmSyncResponse {
     pub results: Vec<SyncResultItem>,
 }
 
+/// セグメント同期レスポンス
+#[derive(Debug, Serialize)]
+pub struct CrmSegmentSyncResponse {
+    pub segment_id: Uuid,
+    pub contacts: CrmSyncResponse,
+    /// CRM側のリストメンバーシップを同期できたか
+    pub list_synced: bool,
+    pub list_error: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct SyncResultItem {
     pub entity_id: Uuid,
@@ -365,6 +379,67 @@ pub async fn sync_contacts(
     Ok(Json(response))
 }
 
+/// CRMへの一括同期で1回に取得・送信する購読者数
+const CRM_SYNC_PAGE_SIZE: i64 = 1000;
+
+/// 購読者を最後のページまで取得しながらCRMへ一括同期し、結果をまとめる
+async fn bulk_sync_in_pages<F, Fut>(
+    crm_service: &CrmService,
+    mut fetch_page: F,
+) -> Result<CrmBulkSyncResult, (StatusCode, String)>
+where
+    F: FnMut(i64) -> Fut,
+    Fut: Future<Output = Result<Vec<Subscriber>, sqlx::Error>>,
+{
+    let mut merged = CrmBulkSyncResult {
+        total: 0,
+        success: 0,
+        failed: 0,
+        results: Vec::new(),
+    };
+    let mut offset = 0;
+
+    loop {
+        let subscribers = fetch_page(offset).await.map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("購読者の取得に失敗: {e}"),
+            )
+        })?;
+        let fetched = subscribers.len() as i64;
+        if fetched == 0 {
+            break;
+        }
+
+        let crm_contacts: Vec<CrmContact> = subscribers
+            .iter()
+            .map(CrmContact::from_subscriber)
+            .collect();
+        let bulk_result = crm_service
+            .provider()
+            .bulk_sync_contacts(crm_contacts)
+            .await
+            .map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("一括同期に失敗: {e}"),
+                )
+            })?;
+
+        merged.total += bulk_result.total;
+        merged.success += bulk_result.success;
+        merged.failed += bulk_result.failed;
+        merged.results.extend(bulk_result.results);
+
+        if fetched < CRM_SYNC_PAGE_SIZE {
+            break;
+        }
+        offset += fetched;
+    }
+
+    Ok(merged)
+}
+
 /// すべての購読者を一括同期
 pub async fn bulk_sync_subscribers(
     State(state): State<AppState>,
@@ -406,41 +481,19 @@ pub async fn bulk_sync_subscribers(
         }
     };
 
-    // すべてのアクティブな購読者を取得
-    let subscribers = list_subscribers(
-        &state.db,
-        auth_user.user_id,
-        Some(10000), // 大量データ処理のため上限を設定
-        Some(0),
-        Some(SubscriberStatus::Active),
-        None, // search
-        None, // tag
-    )
-    .await
-    .map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("購読者の取得に失敗: {e}"),
+    // すべてのアクティブな購読者をページごとにBulk API 2.0で一括同期
+    let bulk_result = bulk_sync_in_pages(&crm_service, |offset| {
+        list_subscribers(
+            &state.db,
+            auth_user.user_id,
+            Some(CRM_SYNC_PAGE_SIZE),
+            Some(offset),
+            Some(SubscriberStatus::Active),
+            None, // search
+            None, // tag
         )
-    })?;
-
-    // 購読者をCRMコンタクトに変換
-    let crm_contacts: Vec<CrmContact> = subscribers
-        .iter()
-        .map(CrmContact::from_subscriber)
-        .collect();
-
-    // Bulk API 2.0を使用して一括同期
-    let bulk_result = crm_service
-        .provider()
-        .bulk_sync_contacts(crm_contacts)
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("一括同期に失敗: {e}"),
-            )
-        })?;
+    })
+    .await?;
 
     // 同期結果をログに記録
     if let Err(e) =
@@ -470,6 +523,124 @@ pub async fn bulk_sync_subscribers(
     Ok(Json(response))
 }
 
+/// セグメントの購読者をコンタクトとして同期し、CRMのリストメンバーシップに反映
+pub async fn sync_segment(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(segment_id): Path<Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    use crate::database::{segments::find_segment_by_id, subscribers::list_segment_subscribers};
+    use crate::models::crm::CrmList;
+
+    // CRMサービスを初期化
+    let crm_service = match CrmService::new(state.db.clone(), auth_user.user_id).await {
+        Ok(service) => service,
+        Err(e) => {
+            return Err((
+                StatusCode::SERVICE_UNAVAILABLE,
+                format!("CRMサービスの初期化に失敗: {e}"),
+            ));
+        }
+    };
+
+    // 統合情報を取得
+    let integration = match CrmService::get_integration(
+        &state.db,
+        auth_user.user_id,
+        CrmProviderType::Salesforce,
+    )
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("統合設定の取得に失敗: {e}"),
+        )
+    })? {
+        Some(integration) => integration,
+        None => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                "CRM統合が設定されていません".to_string(),
+            ));
+        }
+    };
+
+    let segment = find_segment_by_id(&state.db, segment_id, auth_user.user_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("セグメントの取得に失敗: {e}"),
+            )
+        })?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            "セグメントが見つかりません".to_string(),
+        ))?;
+
+    // セグメントに一致する購読者をページごとに一括同期
+    let bulk_result = bulk_sync_in_pages(&crm_service, |offset| {
+        list_segment_subscribers(
+            &state.db,
+            auth_user.user_id,
+            &segment.rules,
+            Some(CRM_SYNC_PAGE_SIZE),
+            Some(offset),
+        )
+    })
+    .await?;
+
+    if let Err(e) =
+        CrmService::log_sync_activity(&state.db, integration.id, "contact", &bulk_result.results)
+            .await
+    {
+        eprintln!("同期ログの記録に失敗: {e}");
+    }
+
+    // 同期できたコンタクトをセグメント名のリストのメンバーとして反映
+    let list = CrmList {
+        id: None,
+        name: segment.name.clone(),
+        member_ids: bulk_result
+            .results
+            .iter()
+            .filter(|r| r.success)
+            .map(|r| r.crm_id.clone())
+            .collect(),
+        created_at: segment.created_at,
+        updated_at: chrono::Utc::now(),
+    };
+    let list_error = crm_service
+        .provider()
+        .sync_list_membership(&list)
+        .await
+        .err()
+        .map(|e| e.to_string());
+
+    let response = CrmSegmentSyncResponse {
+        segment_id: segment.id,
+        contacts: CrmSyncResponse {
+            total: bulk_result.total,
+            success: bulk_result.success,
+            failed: bulk_result.failed,
+            results: bulk_result
+                .results
+                .into_iter()
+                .map(|r| SyncResultItem {
+                    entity_id: r.markmail_id,
+                    crm_id: Some(r.crm_id),
+                    success: r.success,
+                    error: r.error_message,
+                })
+                .collect(),
+        },
+        list_synced: list_error.is_none(),
+        list_error,
+    };
+
+    Ok(Json(response))
+}
+
 /// キャンペーンを同期
 pub async fn sync_campaigns(
     State(state): State<AppState>,
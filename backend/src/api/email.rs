@@ -108,11 +108,13 @@ pub async fn send_test_email(
     // メールメッセージを作成
     let message = EmailMessage {
         to: vec![payload.to.clone()],
+        from: None,
         subject: payload.subject,
         html_body,
         text_body: Some(text_body),
         reply_to: None,
         headers: None,
+        dkim: None,
     };
 
     // メール送信
@@ -1,22 +1,27 @@
 use axum::{
-    extract::{Extension, Path, Query, State},
-    http::StatusCode,
-    response::Json,
+    extract::{Extension, Form as UrlEncodedForm, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Json, Redirect, Response},
 };
 use serde::Deserialize;
 use serde_json::{json, Value};
 use uuid::Uuid;
 
 use crate::{
-    database::{crm_integrations, forms, subscribers},
+    database::forms,
     middleware::auth::AuthUser,
-    models::crm::{CrmLead, CrmProviderType},
-    models::form::{
-        CreateFormRequest, CreateFormSubmissionRequest, Form, FormSubmission, UpdateFormRequest,
+    models::{
+        form::{
+            CreateFormRequest, CreateFormSubmissionRequest, Form, FormSubmissionReceipt,
+            FormSubmissionStatus, PublicForm, UpdateFormRequest, FORM_TOKEN_FIELD,
+        },
+        form_validation::FormSchema,
     },
-    models::sequence::TriggerType,
-    models::subscriber::{CreateSubscriberRequest, SubscriberStatus},
-    services::{crm_service::CrmService, sequence_service::SequenceService},
+    services::{
+        form_page_service::{FormEmbedCode, FormPageService, FormPageState},
+        form_service::{FormError, FormService},
+    },
+    utils::client_info::ClientInfo,
     AppState,
 };
 
@@ -32,45 +37,44 @@ fn default_limit() -> i64 {
     20
 }
 
-/// フォームデータからメールアドレスを抽出
-fn extract_email_from_form_data(form_data: &Value, form_fields: &Value) -> Option<String> {
-    if let (Value::Object(data), Value::Array(fields)) = (form_data, form_fields) {
-        for field in fields {
-            if let Value::Object(field_obj) = field {
-                if let (Some(Value::String(field_type)), Some(Value::String(field_name))) =
-                    (field_obj.get("field_type"), field_obj.get("name"))
-                {
-                    if field_type == "email" {
-                        if let Some(Value::String(email)) = data.get(field_name) {
-                            return Some(email.clone());
-                        }
-                    }
-                }
-            }
-        }
-    }
-    None
+/// 送信データ一覧の絞り込み条件
+#[derive(Deserialize, Default)]
+pub struct SubmissionFilterParams {
+    /// `accepted`または`quarantined`（未指定の場合はすべて）
+    pub status: Option<String>,
 }
 
-/// フォームデータから名前を抽出
-fn extract_name_from_form_data(form_data: &Value, form_fields: &Value) -> Option<String> {
-    if let (Value::Object(data), Value::Array(fields)) = (form_data, form_fields) {
-        for field in fields {
-            if let Value::Object(field_obj) = field {
-                if let (Some(Value::String(field_type)), Some(Value::String(field_name))) =
-                    (field_obj.get("field_type"), field_obj.get("name"))
-                {
-                    if field_type == "text" && (field_name == "name" || field_name.contains("名前"))
-                    {
-                        if let Some(Value::String(name)) = data.get(field_name) {
-                            return Some(name.clone());
-                        }
-                    }
-                }
-            }
+/// 公開フォームページの表示オプション
+#[derive(Deserialize, Default)]
+pub struct HostedFormParams {
+    /// iframeに埋め込んで表示する
+    #[serde(default)]
+    pub embed: bool,
+}
+
+fn error_response(error: FormError) -> (StatusCode, Json<Value>) {
+    let status = match &error {
+        FormError::Invalid(_) => StatusCode::BAD_REQUEST,
+        FormError::NotFound => StatusCode::NOT_FOUND,
+        FormError::Expired => StatusCode::GONE,
+        FormError::Conflict(_) => StatusCode::CONFLICT,
+        FormError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+        FormError::Validation(field_errors) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": error.to_string(),
+                    "field_errors": field_errors
+                })),
+            );
         }
-    }
-    None
+        FormError::Database(message) => {
+            tracing::error!("フォーム処理エラー: {}", message);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+
+    (status, Json(json!({ "error": error.to_string() })))
 }
 
 pub async fn create_form(
@@ -78,6 +82,15 @@ pub async fn create_form(
     State(state): State<AppState>,
     Json(payload): Json<CreateFormRequest>,
 ) -> Result<(StatusCode, Json<Form>), (StatusCode, Json<Value>)> {
+    let form_service = FormService::new();
+    form_service
+        .validate_fields(payload.form_fields.as_ref())
+        .map_err(error_response)?;
+    form_service
+        .validate_settings(&state.db, auth_user.user_id, payload.settings.as_ref())
+        .await
+        .map_err(error_response)?;
+
     match forms::create_form(&state.db, auth_user.user_id, payload).await {
         Ok(form) => Ok((StatusCode::CREATED, Json(form))),
         Err(e) => {
@@ -157,6 +170,21 @@ pub async fn update_form(
     match forms::get_form_by_id(&state.db, form_id).await {
         Ok(Some(form)) => {
             if form.user_id == auth_user.user_id {
+                let form_service = FormService::new();
+                form_service
+                    .validate_fields(payload.form_fields.as_ref())
+                    .map_err(error_response)?;
+                form_service
+                    .validate_settings(&state.db, auth_user.user_id, payload.settings.as_ref())
+                    .await
+                    .map_err(error_response)?;
+                if payload.status.as_deref() == Some("published") {
+                    form_service
+                        .validate_publish(&state.db, &form)
+                        .await
+                        .map_err(error_response)?;
+                }
+
                 match forms::update_form(&state.db, form_id, payload).await {
                     Ok(updated_form) => Ok(Json(updated_form)),
                     Err(e) => {
@@ -248,17 +276,36 @@ pub async fn get_form_submissions(
     Extension(auth_user): Extension<AuthUser>,
     Path(form_id): Path<Uuid>,
     Query(params): Query<PaginationParams>,
+    Query(filter): Query<SubmissionFilterParams>,
     State(state): State<AppState>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let status = match filter.status.as_deref() {
+        Some(status) => Some(FormSubmissionStatus::parse(status).ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": format!("送信データの状態「{status}」は不正です")
+                })),
+            )
+        })?),
+        None => None,
+    };
+
     // Check form ownership
     match forms::get_form_by_id(&state.db, form_id).await {
         Ok(Some(form)) => {
             if form.user_id == auth_user.user_id {
-                match forms::get_form_submissions(&state.db, form_id, params.limit, params.offset)
-                    .await
+                match forms::get_form_submissions(
+                    &state.db,
+                    form_id,
+                    status,
+                    params.limit,
+                    params.offset,
+                )
+                .await
                 {
                     Ok(submissions) => {
-                        match forms::count_form_submissions(&state.db, form_id).await {
+                        match forms::count_form_submissions(&state.db, form_id, status).await {
                             Ok(total) => Ok(Json(json!({
                                 "submissions": submissions,
                                 "total": total,
@@ -317,151 +364,25 @@ pub async fn get_form_submissions(
 pub async fn submit_form(
     Path(form_id): Path<Uuid>,
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(request): Json<CreateFormSubmissionRequest>,
-) -> Result<(StatusCode, Json<FormSubmission>), (StatusCode, Json<Value>)> {
+) -> Result<(StatusCode, Json<FormSubmissionReceipt>), (StatusCode, Json<Value>)> {
     // Check if form exists and is active
     match forms::get_form_by_id(&state.db, form_id).await {
         Ok(Some(form)) => {
             if form.status == "published" {
-                // TODO: Implement form validation based on form fields
-                // TODO: Extract IP address and user agent from request headers
-
-                // フォームデータからメールアドレスを抽出
-                tracing::info!("Form data: {:?}", request.data);
-                tracing::info!("Form fields: {:?}", form.form_fields);
-                let email = extract_email_from_form_data(&request.data, &form.form_fields);
-                tracing::info!("Extracted email: {:?}", email);
-
-                // メールアドレスがある場合は購読者を作成またはリンク
-                let subscriber_id = if let Some(email) = email {
-                    match subscribers::find_subscriber_by_email(&state.db, &email, form.user_id)
-                        .await
-                    {
-                        Ok(Some(subscriber)) => Some(subscriber.id),
-                        Ok(None) => {
-                            // 新規購読者を作成
-                            let create_req = CreateSubscriberRequest {
-                                email,
-                                name: extract_name_from_form_data(&request.data, &form.form_fields),
-                                status: Some(SubscriberStatus::Active),
-                                tags: Some(vec![format!("form:{}", form.slug)]),
-                                custom_fields: Some(request.data.clone()),
-                            };
-                            match subscribers::create_subscriber(
-                                &state.db,
-                                form.user_id,
-                                &create_req,
-                            )
-                            .await
-                            {
-                                Ok(subscriber) => {
-                                    tracing::info!(
-                                        "Created new subscriber {} from form submission",
-                                        subscriber.id
-                                    );
-                                    Some(subscriber.id)
-                                }
-                                Err(e) => {
-                                    tracing::error!("Failed to create subscriber from form: {}", e);
-                                    None
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            tracing::error!("Failed to find subscriber by email: {}", e);
-                            None
-                        }
-                    }
-                } else {
-                    tracing::warn!("No email field found in form submission");
-                    None
-                };
-
-                match forms::create_form_submission(
-                    &state.db,
-                    form_id,
-                    request.data.clone(),
-                    subscriber_id,
-                    None, // TODO: IP address
-                    None, // TODO: User agent
-                    None, // TODO: Referrer
-                )
-                .await
-                {
-                    Ok(submission) => {
-                        // フォーム送信時のシーケンストリガー
-                        if let Some(subscriber_id) = submission.subscriber_id {
-                            let sequence_service = SequenceService::new();
-                            if let Err(e) = sequence_service
-                                .process_trigger_enrollment(
-                                    &state.db,
-                                    form.user_id,
-                                    TriggerType::FormSubmission,
-                                    subscriber_id,
-                                    Some(json!({
-                                        "form_id": form_id.to_string(),
-                                        "submission_id": submission.id.to_string()
-                                    })),
-                                )
-                                .await
-                            {
-                                tracing::error!("シーケンスエンロールメントエラー: {}", e);
-                                // エラーが発生してもレスポンスは返す（フォーム送信は成功しているため）
-                            }
-                        }
-
-                        // CRM統合をチェックしてリードを作成
-                        if let Ok(Some(integration)) = crm_integrations::get_user_crm_integration(
-                            &state.db,
-                            form.user_id,
-                            CrmProviderType::Salesforce,
-                        )
-                        .await
-                        {
-                            if integration.is_active() {
-                                // リードデータを作成
-                                let lead = CrmLead::from_form_submission(
-                                    form_id,
-                                    Some(submission.id),
-                                    &request.data,
-                                    &form.form_fields,
-                                    &form.name,
-                                );
-
-                                // CRMサービスを使用してリードを作成
-                                match CrmService::new(state.db.clone(), form.user_id).await {
-                                    Ok(crm_service) => {
-                                        if let Err(e) =
-                                            crm_service.provider().create_lead(&lead).await
-                                        {
-                                            tracing::error!("Salesforceリード作成エラー: {:?}", e);
-                                            // エラーが発生してもフォーム送信は成功しているのでレスポンスは返す
-                                        } else {
-                                            tracing::info!(
-                                                "Salesforceリードを作成しました: {}",
-                                                lead.email
-                                            );
-                                        }
-                                    }
-                                    Err(e) => {
-                                        tracing::error!("CRMサービス初期化エラー: {:?}", e);
-                                    }
-                                }
-                            }
-                        }
+                let submission = FormService::new()
+                    .submit(
+                        &state.db,
+                        &state.redis,
+                        &form,
+                        &request.data,
+                        ClientInfo::from_headers(&headers),
+                    )
+                    .await
+                    .map_err(error_response)?;
 
-                        Ok((StatusCode::CREATED, Json(submission)))
-                    }
-                    Err(e) => {
-                        tracing::error!("フォーム送信エラー: {:?}", e);
-                        Err((
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(json!({
-                                "error": "フォームの送信に失敗しました"
-                            })),
-                        ))
-                    }
-                }
+                Ok((StatusCode::CREATED, Json(submission.into())))
             } else {
                 Err((
                     StatusCode::FORBIDDEN,
@@ -489,15 +410,48 @@ pub async fn submit_form(
     }
 }
 
+/// ダブルオプトインの確認リンク（認証不要）
+///
+/// 確認後のリダイレクト先が設定されている場合はリダイレクトし、それ以外はJSONで結果を返す。
+pub async fn confirm_submission(
+    Path(token): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Response, (StatusCode, Json<Value>)> {
+    let confirmed = FormService::new()
+        .confirm_submission(&state.db, &token)
+        .await
+        .map_err(error_response)?;
+
+    if let Some(url) = confirmed.form.settings().confirmation_redirect_url {
+        return Ok(Redirect::to(&url).into_response());
+    }
+
+    Ok(Json(json!({
+        "message": if confirmed.newly_confirmed {
+            "ご登録が完了しました"
+        } else {
+            "ご登録は完了済みです"
+        },
+        "form_id": confirmed.form.id,
+        "submission_id": confirmed.submission.id,
+        "confirmed_at": confirmed.submission.confirmed_at,
+    }))
+    .into_response())
+}
+
 // Public endpoint to get form for rendering (no auth required)
 pub async fn get_public_form(
     Path(form_id): Path<Uuid>,
     State(state): State<AppState>,
-) -> Result<Json<Form>, (StatusCode, Json<Value>)> {
+) -> Result<Json<PublicForm>, (StatusCode, Json<Value>)> {
     match forms::get_form_by_id(&state.db, form_id).await {
         Ok(Some(form)) => {
             if form.status == "published" {
-                Ok(Json(form))
+                // 送信時に返してもらい、表示から送信までの時間を確認する
+                let form_token = FormService::new()
+                    .issue_form_token(form.id)
+                    .map_err(error_response)?;
+                Ok(Json(PublicForm { form, form_token }))
             } else {
                 Err((
                     StatusCode::FORBIDDEN,
@@ -524,3 +478,229 @@ pub async fn get_public_form(
         }
     }
 }
+
+/// 公開フォームページのエラー表示
+fn hosted_error_page(status: StatusCode, title: &str, message: &str, embed: bool) -> Response {
+    (
+        status,
+        Html(FormPageService::new().render_message(title, message, embed)),
+    )
+        .into_response()
+}
+
+/// 公開中のフォームとフィールド定義をスラッグで取得（表示できない場合はエラーページ）
+async fn find_hosted_form(
+    state: &AppState,
+    slug: &str,
+    embed: bool,
+) -> Result<(Form, FormSchema), Response> {
+    let form = match forms::get_published_form_by_slug(&state.db, slug).await {
+        Ok(Some(form)) => form,
+        Ok(None) => {
+            return Err(hosted_error_page(
+                StatusCode::NOT_FOUND,
+                "フォームが見つかりません",
+                "フォームが存在しないか、公開されていません。",
+                embed,
+            ))
+        }
+        Err(e) => {
+            tracing::error!("フォーム取得エラー: {:?}", e);
+            return Err(hosted_error_page(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "エラーが発生しました",
+                "時間をおいて再度お試しください。",
+                embed,
+            ));
+        }
+    };
+
+    match FormSchema::parse(&form.form_fields) {
+        Ok(schema) => Ok((form, schema)),
+        Err(e) => {
+            tracing::error!("フォーム{}のフィールド定義エラー: {}", form.id, e);
+            Err(hosted_error_page(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "エラーが発生しました",
+                "フォームの設定に誤りがあるため表示できません。",
+                embed,
+            ))
+        }
+    }
+}
+
+/// 公開フォームページ（認証不要）
+pub async fn get_hosted_form(
+    Path(slug): Path<String>,
+    Query(params): Query<HostedFormParams>,
+    State(state): State<AppState>,
+) -> Response {
+    let (form, schema) = match find_hosted_form(&state, &slug, params.embed).await {
+        Ok(found) => found,
+        Err(response) => return response,
+    };
+
+    // 送信時に返してもらい、表示から送信までの時間を確認する
+    let form_token = match FormService::new().issue_form_token(form.id) {
+        Ok(token) => token,
+        Err(e) => {
+            tracing::error!("送信トークン発行エラー: {}", e);
+            return hosted_error_page(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "エラーが発生しました",
+                "時間をおいて再度お試しください。",
+                params.embed,
+            );
+        }
+    };
+
+    Html(FormPageService::new().render_form(
+        &form,
+        &schema,
+        &form_token,
+        FormPageState::blank(params.embed),
+    ))
+    .into_response()
+}
+
+/// 公開フォームページからの送信（認証不要）
+///
+/// 送信後のリダイレクト先が設定されている場合はリダイレクトし、それ以外は送信後のページを表示する。
+/// 入力内容に誤りがある場合はエラーと入力内容を含めてフォームを再表示する。
+pub async fn submit_hosted_form(
+    Path(slug): Path<String>,
+    Query(params): Query<HostedFormParams>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    UrlEncodedForm(pairs): UrlEncodedForm<Vec<(String, String)>>,
+) -> Response {
+    let (form, schema) = match find_hosted_form(&state, &slug, params.embed).await {
+        Ok(found) => found,
+        Err(response) => return response,
+    };
+    let data = schema.data_from_pairs(&pairs);
+
+    let form_service = FormService::new();
+    let error = match form_service
+        .submit(
+            &state.db,
+            &state.redis,
+            &form,
+            &data,
+            ClientInfo::from_headers(&headers),
+        )
+        .await
+    {
+        Ok(_) => {
+            return match form.settings().success_redirect_url {
+                Some(url) => Redirect::to(&url).into_response(),
+                None => {
+                    Html(FormPageService::new().render_success(&form, params.embed)).into_response()
+                }
+            };
+        }
+        Err(error) => error,
+    };
+
+    let (status, message) = match &error {
+        FormError::Validation(_) | FormError::Invalid(_) => {
+            (StatusCode::BAD_REQUEST, error.to_string())
+        }
+        FormError::RateLimited(_) => (StatusCode::TOO_MANY_REQUESTS, error.to_string()),
+        _ => {
+            tracing::error!("フォーム送信エラー: {}", error);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "フォームの送信に失敗しました。時間をおいて再度お試しください".to_string(),
+            )
+        }
+    };
+    let field_errors = match &error {
+        FormError::Validation(field_errors) => field_errors.as_slice(),
+        _ => &[],
+    };
+
+    // 再送信が表示直後の送信として隔離されないよう、送信トークンは引き継ぐ
+    let form_token = match data.get(FORM_TOKEN_FIELD).and_then(Value::as_str) {
+        Some(token) => token.to_string(),
+        None => form_service.issue_form_token(form.id).unwrap_or_default(),
+    };
+
+    (
+        status,
+        Html(FormPageService::new().render_form(
+            &form,
+            &schema,
+            &form_token,
+            FormPageState {
+                values: &data,
+                field_errors,
+                error: Some(&message),
+                embed: params.embed,
+            },
+        )),
+    )
+        .into_response()
+}
+
+/// フォームの埋め込み用スクリプト（認証不要）
+pub async fn get_form_embed_script(
+    Path(slug): Path<String>,
+    State(state): State<AppState>,
+) -> Response {
+    match forms::get_published_form_by_slug(&state.db, &slug).await {
+        Ok(Some(form)) => (
+            [
+                (
+                    header::CONTENT_TYPE,
+                    "application/javascript; charset=utf-8",
+                ),
+                (header::CACHE_CONTROL, "public, max-age=300"),
+            ],
+            FormPageService::new().embed_script(&form),
+        )
+            .into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            tracing::error!("フォーム取得エラー: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// フォームの公開URLと埋め込み用コード
+pub async fn get_form_embed_code(
+    Extension(auth_user): Extension<AuthUser>,
+    Path(form_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<Json<FormEmbedCode>, (StatusCode, Json<Value>)> {
+    match forms::get_form_by_id(&state.db, form_id).await {
+        Ok(Some(form)) => {
+            if form.user_id == auth_user.user_id {
+                Ok(Json(FormPageService::new().embed_code(&form)))
+            } else {
+                Err((
+                    StatusCode::FORBIDDEN,
+                    Json(json!({
+                        "error": "このフォームへのアクセス権限がありません"
+                    })),
+                ))
+            }
+        }
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "フォームが見つかりません"
+            })),
+        )),
+        Err(e) => {
+            tracing::error!("フォーム取得エラー: {:?}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": "フォームの取得に失敗しました"
+                })),
+            ))
+        }
+    }
+}
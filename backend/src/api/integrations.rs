@@ -1,34 +1,194 @@
 use axum::{
-    extract::State,
-    http::StatusCode,
+    body::Bytes,
+    extract::{Extension, Path, Query, State},
+    http::{HeaderMap, StatusCode},
     response::Json,
     routing::{get, post},
     Router,
 };
 use serde_json::{json, Value};
+use uuid::Uuid;
+use validator::Validate;
 
-use crate::AppState;
+use crate::{
+    middleware::auth::AuthUser,
+    models::github::{
+        ConnectGitHubRequest, GitHubConnectionResponse, GitHubContentsQuery, GitHubImportResult,
+        ImportFromGitHubRequest,
+    },
+    services::{
+        github_client::GitHubError,
+        github_service::{GitHubService, GitHubServiceError},
+    },
+    AppState,
+};
+
+fn error_response(error: GitHubServiceError) -> (StatusCode, Json<Value>) {
+    let status =
+        match &error {
+            GitHubServiceError::NotConnected
+            | GitHubServiceError::GitHub(GitHubError::NotFound) => StatusCode::NOT_FOUND,
+            GitHubServiceError::Invalid(_)
+            | GitHubServiceError::GitHub(GitHubError::Unauthorized) => StatusCode::BAD_REQUEST,
+            GitHubServiceError::InvalidSignature => StatusCode::UNAUTHORIZED,
+            GitHubServiceError::GitHub(e) => {
+                tracing::warn!("GitHub APIエラー: {}", e);
+                StatusCode::BAD_GATEWAY
+            }
+            GitHubServiceError::Database(message) => {
+                tracing::error!("GitHub連携エラー: {}", message);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+
+    (status, Json(json!({ "error": error.to_string() })))
+}
+
+/// GitHub連携の状態を取得
+pub async fn get_github_connection(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+) -> Result<Json<GitHubConnectionResponse>, (StatusCode, Json<Value>)> {
+    GitHubService::new()
+        .connection(&state.db, auth_user.user_id)
+        .await
+        .map(Json)
+        .map_err(error_response)
+}
+
+/// アクセストークンを登録してGitHubと連携
+pub async fn connect_github(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Json(payload): Json<ConnectGitHubRequest>,
+) -> Result<Json<GitHubConnectionResponse>, (StatusCode, Json<Value>)> {
+    if let Err(errors) = payload.validate() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "バリデーションエラー",
+                "details": errors
+            })),
+        ));
+    }
+
+    GitHubService::new()
+        .connect(&state.db, auth_user.user_id, payload.access_token.trim())
+        .await
+        .map(Json)
+        .map_err(error_response)
+}
+
+/// GitHub連携を解除
+pub async fn disconnect_github(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    GitHubService::new()
+        .disconnect(&state.db, auth_user.user_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// アクセスできるリポジトリ一覧
+pub async fn github_repos(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let repositories = GitHubService::new()
+        .list_repositories(&state.db, auth_user.user_id)
+        .await
+        .map_err(error_response)?;
 
-pub async fn github_repos(State(_state): State<AppState>) -> Result<Json<Value>, StatusCode> {
-    // TODO: GitHub リポジトリ一覧取得ロジックを実装
     Ok(Json(json!({
-        "repositories": []
+        "repositories": repositories
     })))
 }
 
-pub async fn import_from_github(
-    State(_state): State<AppState>,
-    Json(_payload): Json<Value>,
-) -> Result<Json<Value>, StatusCode> {
-    // TODO: GitHub連携ロジックを実装
+/// リポジトリ内のディレクトリ・ファイルを閲覧
+pub async fn github_contents(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Path((owner, repo)): Path<(String, String)>,
+    Query(query): Query<GitHubContentsQuery>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let repository = format!("{owner}/{repo}");
+    let path = query.path.unwrap_or_default();
+    let entries = GitHubService::new()
+        .list_contents(
+            &state.db,
+            auth_user.user_id,
+            &repository,
+            &path,
+            query.git_ref.as_deref(),
+        )
+        .await
+        .map_err(error_response)?;
+
     Ok(Json(json!({
-        "message": "GitHubからのインポートが完了しました"
+        "repository": repository,
+        "path": path.trim_matches('/'),
+        "entries": entries
     })))
 }
 
+/// マークダウンファイルをテンプレートとして取り込む
+pub async fn import_from_github(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Json(payload): Json<ImportFromGitHubRequest>,
+) -> Result<Json<GitHubImportResult>, (StatusCode, Json<Value>)> {
+    if let Err(errors) = payload.validate() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "バリデーションエラー",
+                "details": errors
+            })),
+        ));
+    }
+
+    GitHubService::new()
+        .import(&state.db, auth_user.user_id, &payload)
+        .await
+        .map(Json)
+        .map_err(error_response)
+}
+
+/// GitHubのpush Webhook（自動更新を有効にしたテンプレートを取り込み直す）
+pub async fn handle_github_webhook(
+    State(state): State<AppState>,
+    Path(connection_id): Path<Uuid>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<GitHubImportResult>, (StatusCode, Json<Value>)> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+
+    GitHubService::new()
+        .handle_webhook(
+            &state.db,
+            connection_id,
+            header("x-github-event").unwrap_or_default(),
+            header("x-hub-signature-256"),
+            &body,
+        )
+        .await
+        .map(Json)
+        .map_err(error_response)
+}
+
 /// 統合関連のルーターを構築
 pub fn router() -> Router<AppState> {
     Router::new()
+        .route(
+            "/github/connection",
+            get(get_github_connection)
+                .post(connect_github)
+                .delete(disconnect_github),
+        )
         .route("/github/repos", get(github_repos))
+        .route("/github/repos/:owner/:repo/contents", get(github_contents))
         .route("/github/import", post(import_from_github))
 }
@@ -1,9 +1,21 @@
-use axum::{extract::State, http::StatusCode, response::Json, routing::post, Router};
+use axum::{
+    extract::{Extension, State},
+    http::StatusCode,
+    response::Json,
+    routing::post,
+    Router,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use validator::Validate;
 
-use crate::{services::markdown_service::MarkdownService, AppState};
+use crate::{
+    middleware::auth::AuthUser,
+    services::{
+        markdown_component_service::MarkdownComponentService, markdown_service::MarkdownService,
+    },
+    AppState,
+};
 
 #[derive(Debug, Deserialize, Validate)]
 pub struct RenderMarkdownRequest {
@@ -39,9 +51,29 @@ pub struct ValidateMarkdownResponse {
     pub extracted_variables: Vec<String>,
 }
 
+/// ユーザーのコンポーネントとスニペットを読み込んだマークダウンサービス
+pub(crate) async fn user_markdown_service(
+    state: &AppState,
+    auth_user: &AuthUser,
+) -> Result<MarkdownService, (StatusCode, Json<Value>)> {
+    MarkdownComponentService::new()
+        .markdown_service(&state.db, auth_user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("{}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": "マークダウンコンポーネントの取得に失敗しました"
+                })),
+            )
+        })
+}
+
 /// マークダウンをHTMLにレンダリング
 pub async fn render_markdown(
-    State(_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
     Json(payload): Json<RenderMarkdownRequest>,
 ) -> Result<Json<RenderMarkdownResponse>, (StatusCode, Json<Value>)> {
     // バリデーション
@@ -55,7 +87,7 @@ pub async fn render_markdown(
         ));
     }
 
-    let markdown_service = MarkdownService::new();
+    let markdown_service = user_markdown_service(&state, &auth_user).await?;
 
     // 変数を抽出
     let extracted_variables = markdown_service.extract_variables(&payload.markdown);
@@ -85,7 +117,8 @@ pub async fn render_markdown(
 
 /// マークダウンの構文を検証
 pub async fn validate_markdown(
-    State(_state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
     Json(payload): Json<ValidateMarkdownRequest>,
 ) -> Result<Json<ValidateMarkdownResponse>, (StatusCode, Json<Value>)> {
     // バリデーション
@@ -99,7 +132,7 @@ pub async fn validate_markdown(
         ));
     }
 
-    let markdown_service = MarkdownService::new();
+    let markdown_service = user_markdown_service(&state, &auth_user).await?;
 
     // マークダウンの構文チェック
     let validation_errors = match markdown_service.validate_markdown(&payload.markdown) {
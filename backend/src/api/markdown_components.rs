@@ -0,0 +1,154 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    middleware::auth::AuthUser,
+    models::markdown_component::{
+        CreateMarkdownComponentRequest, MarkdownComponent, UpdateMarkdownComponentRequest,
+    },
+    services::{
+        markdown_component_service::{MarkdownComponentError, MarkdownComponentService},
+        subscription_service,
+    },
+    AppState,
+};
+
+fn error_response(error: MarkdownComponentError) -> (StatusCode, Json<Value>) {
+    let status = match &error {
+        MarkdownComponentError::Invalid(_) => StatusCode::BAD_REQUEST,
+        MarkdownComponentError::NotFound => StatusCode::NOT_FOUND,
+        MarkdownComponentError::Conflict(_) => StatusCode::CONFLICT,
+        MarkdownComponentError::Database(message) => {
+            tracing::error!("マークダウンコンポーネント処理エラー: {}", message);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+
+    (status, Json(json!({ "error": error.to_string() })))
+}
+
+/// プランでカスタムMarkdownコンポーネントを利用できるか確認
+async fn require_components_feature(
+    state: &AppState,
+    auth_user: &AuthUser,
+) -> Result<(), (StatusCode, Json<Value>)> {
+    let has_access = subscription_service::check_feature_access(
+        &state.db,
+        auth_user.user_id,
+        "custom_markdown_components",
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("機能アクセスチェックエラー: {:?}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "プラン情報の確認に失敗しました" })),
+        )
+    })?;
+
+    if !has_access {
+        return Err((
+            StatusCode::PAYMENT_REQUIRED,
+            Json(json!({
+                "error": "カスタムMarkdownコンポーネントはご利用のプランでは使用できません。プランをアップグレードしてください。"
+            })),
+        ));
+    }
+
+    Ok(())
+}
+
+fn validation_error(errors: validator::ValidationErrors) -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({
+            "error": "バリデーションエラー",
+            "details": errors
+        })),
+    )
+}
+
+/// コンポーネント・スニペット一覧を取得
+pub async fn list_markdown_components(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let components = MarkdownComponentService::new()
+        .list_components(&state.db, auth_user.user_id)
+        .await
+        .map_err(error_response)?;
+
+    let total = components.len();
+    Ok(Json(json!({
+        "components": components,
+        "total": total
+    })))
+}
+
+/// コンポーネント・スニペットを登録
+pub async fn create_markdown_component(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateMarkdownComponentRequest>,
+) -> Result<(StatusCode, Json<MarkdownComponent>), (StatusCode, Json<Value>)> {
+    require_components_feature(&state, &auth_user).await?;
+    payload.validate().map_err(validation_error)?;
+
+    let component = MarkdownComponentService::new()
+        .create_component(&state.db, auth_user.user_id, &payload)
+        .await
+        .map_err(error_response)?;
+
+    tracing::info!("マークダウンコンポーネント登録: {}", component.name);
+    Ok((StatusCode::CREATED, Json(component)))
+}
+
+/// コンポーネント・スニペットを取得
+pub async fn get_markdown_component(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<MarkdownComponent>, (StatusCode, Json<Value>)> {
+    MarkdownComponentService::new()
+        .get_component(&state.db, id, auth_user.user_id)
+        .await
+        .map(Json)
+        .map_err(error_response)
+}
+
+/// コンポーネント・スニペットを更新
+pub async fn update_markdown_component(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateMarkdownComponentRequest>,
+) -> Result<Json<MarkdownComponent>, (StatusCode, Json<Value>)> {
+    require_components_feature(&state, &auth_user).await?;
+    payload.validate().map_err(validation_error)?;
+
+    MarkdownComponentService::new()
+        .update_component(&state.db, id, auth_user.user_id, &payload)
+        .await
+        .map(Json)
+        .map_err(error_response)
+}
+
+/// コンポーネント・スニペットを削除
+pub async fn delete_markdown_component(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    MarkdownComponentService::new()
+        .delete_component(&state.db, id, auth_user.user_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
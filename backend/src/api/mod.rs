@@ -6,6 +6,7 @@ use axum::{
 
 use crate::{middleware::auth::auth_middleware, AppState};
 
+pub mod ab_tests;
 pub mod ai;
 pub mod ai_usage;
 pub mod auth;
@@ -17,11 +18,17 @@ pub mod email;
 pub mod forms;
 pub mod integrations;
 pub mod markdown;
+pub mod markdown_components;
+pub mod segments;
+pub mod sending_domains;
 pub mod sequences;
+pub mod ses_webhook;
 pub mod stripe_webhook;
 pub mod subscribers;
 pub mod subscriptions;
 pub mod templates;
+pub mod tracking;
+pub mod unsubscribe;
 pub mod users;
 
 pub fn create_routes() -> Router<AppState> {
@@ -35,11 +42,36 @@ pub fn create_routes() -> Router<AppState> {
         // フォームの公開エンドポイント
         .route("/api/forms/:id/public", get(forms::get_public_form))
         .route("/api/forms/:id/submit", post(forms::submit_form))
+        .route("/api/forms/confirm/:token", get(forms::confirm_submission))
+        // 公開フォームページ・埋め込み用スクリプト
+        .route(
+            "/f/:slug",
+            get(forms::get_hosted_form).post(forms::submit_hosted_form),
+        )
+        .route("/f/:slug/embed.js", get(forms::get_form_embed_script))
+        // 開封・クリック計測
+        .route("/t/o/:token", get(tracking::track_open))
+        .route("/t/c/:token", get(tracking::track_click))
+        // 配信停止（ワンクリック配信停止のPOSTを含む）
+        .route(
+            "/unsubscribe/:token",
+            get(unsubscribe::get_unsubscribe_page).post(unsubscribe::unsubscribe),
+        )
         // Stripe Webhook
         .route(
             "/api/stripe/webhook",
             post(stripe_webhook::handle_stripe_webhook),
         )
+        // SESのバウンス・苦情・配信通知（SNS経由）
+        .route(
+            "/api/ses/webhook",
+            post(ses_webhook::handle_ses_notification),
+        )
+        // GitHubのpush Webhook（署名で検証）
+        .route(
+            "/api/integrations/github/webhook/:connection_id",
+            post(integrations::handle_github_webhook),
+        )
         // OAuth2 Callback (公開エンドポイント)
         .route(
             "/api/crm/oauth/salesforce/callback",
@@ -65,6 +97,22 @@ pub fn create_routes() -> Router<AppState> {
             "/api/templates/:id/analyze",
             get(templates::analyze_template_variables),
         )
+        .route(
+            "/api/templates/:id/revisions",
+            get(templates::list_template_revisions),
+        )
+        .route(
+            "/api/templates/:id/revisions/:revision",
+            get(templates::get_template_revision),
+        )
+        .route(
+            "/api/templates/:id/revisions/:revision/rollback",
+            post(templates::rollback_template),
+        )
+        .route(
+            "/api/templates/:id/diff",
+            get(templates::diff_template_revisions),
+        )
         // キャンペーン管理
         .route("/api/campaigns", get(campaigns::list_campaigns))
         .route("/api/campaigns", post(campaigns::create_campaign))
@@ -82,7 +130,7 @@ pub fn create_routes() -> Router<AppState> {
         )
         .route(
             "/api/campaigns/:id/schedule",
-            post(campaigns::schedule_campaign),
+            post(campaigns::schedule_campaign).delete(campaigns::cancel_campaign_schedule),
         )
         .route(
             "/api/campaigns/:id/preview",
@@ -92,18 +140,74 @@ pub fn create_routes() -> Router<AppState> {
             "/api/campaigns/:id/subscribers",
             get(campaigns::get_campaign_subscribers),
         )
+        .route(
+            "/api/campaigns/:id/ab-test",
+            get(ab_tests::get_ab_test)
+                .put(ab_tests::configure_ab_test)
+                .delete(ab_tests::delete_ab_test),
+        )
         // 購読者管理
         .nest("/api/subscribers", subscribers::router())
+        // セグメント管理
+        .route(
+            "/api/segments",
+            get(segments::list_segments).post(segments::create_segment),
+        )
+        .route("/api/segments/preview", post(segments::preview_segment))
+        .route(
+            "/api/segments/:id",
+            get(segments::get_segment)
+                .put(segments::update_segment)
+                .delete(segments::delete_segment),
+        )
+        .route(
+            "/api/segments/:id/subscribers",
+            get(segments::get_segment_subscribers),
+        )
+        // 送信ドメイン管理
+        .route(
+            "/api/sending-domains",
+            get(sending_domains::list_sending_domains).post(sending_domains::create_sending_domain),
+        )
+        .route(
+            "/api/sending-domains/:id",
+            get(sending_domains::get_sending_domain).delete(sending_domains::delete_sending_domain),
+        )
+        .route(
+            "/api/sending-domains/:id/verify",
+            post(sending_domains::verify_sending_domain),
+        )
         // メール送信（開発環境のみ）
         .nest("/api/email", email::router())
         // マークダウン処理
         .route("/api/markdown/render", post(markdown::render_markdown))
         .route("/api/markdown/validate", post(markdown::validate_markdown))
+        .route(
+            "/api/markdown-components",
+            get(markdown_components::list_markdown_components)
+                .post(markdown_components::create_markdown_component),
+        )
+        .route(
+            "/api/markdown-components/:id",
+            get(markdown_components::get_markdown_component)
+                .put(markdown_components::update_markdown_component)
+                .delete(markdown_components::delete_markdown_component),
+        )
         // GitHub連携
+        .route(
+            "/api/integrations/github/connection",
+            get(integrations::get_github_connection)
+                .post(integrations::connect_github)
+                .delete(integrations::disconnect_github),
+        )
         .route(
             "/api/integrations/github/repos",
             get(integrations::github_repos),
         )
+        .route(
+            "/api/integrations/github/repos/:owner/:repo/contents",
+            get(integrations::github_contents),
+        )
         .route(
             "/api/integrations/github/import",
             post(integrations::import_from_github),
@@ -114,6 +218,7 @@ pub fn create_routes() -> Router<AppState> {
         .route("/api/forms/:id", get(forms::get_form))
         .route("/api/forms/:id", put(forms::update_form))
         .route("/api/forms/:id", delete(forms::delete_form))
+        .route("/api/forms/:id/embed", get(forms::get_form_embed_code))
         .route(
             "/api/forms/:id/submissions",
             get(forms::get_form_submissions),
@@ -145,6 +250,22 @@ pub fn create_routes() -> Router<AppState> {
             post(sequences::activate_sequence),
         )
         .route("/api/sequences/:id/pause", post(sequences::pause_sequence))
+        .route(
+            "/api/sequences/:id/enrollments",
+            get(sequences::list_sequence_enrollments).post(sequences::enroll_sequence_subscribers),
+        )
+        .route(
+            "/api/sequences/:id/enrollments/:enrollment_id/cancel",
+            post(sequences::cancel_sequence_enrollment),
+        )
+        .route(
+            "/api/sequences/:id/enrollments/:enrollment_id/pause",
+            post(sequences::pause_sequence_enrollment),
+        )
+        .route(
+            "/api/sequences/:id/enrollments/:enrollment_id/resume",
+            post(sequences::resume_sequence_enrollment),
+        )
         // サブスクリプション管理
         .route("/api/subscriptions/plans", get(subscriptions::get_plans))
         .route(
@@ -199,6 +320,7 @@ pub fn create_routes() -> Router<AppState> {
             "/api/crm/sync/subscribers/bulk",
             post(crm::bulk_sync_subscribers),
         )
+        .route("/api/crm/sync/segments/:id", post(crm::sync_segment))
         // CRM OAuth2
         .route(
             "/api/crm/oauth/salesforce/init",
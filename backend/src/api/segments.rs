@@ -0,0 +1,190 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    database::segments,
+    middleware::auth::AuthUser,
+    models::segment::{
+        CreateSegmentRequest, PreviewSegmentRequest, SegmentResponse, SegmentSubscribersQuery,
+        UpdateSegmentRequest,
+    },
+    services::segment_service::{SegmentError, SegmentService},
+    AppState,
+};
+
+fn error_response(error: SegmentError) -> (StatusCode, Json<Value>) {
+    let status = match &error {
+        SegmentError::Invalid(_) => StatusCode::BAD_REQUEST,
+        SegmentError::NotFound => StatusCode::NOT_FOUND,
+        SegmentError::InUse(_) => StatusCode::CONFLICT,
+        SegmentError::Database(message) => {
+            tracing::error!("セグメント処理エラー: {}", message);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+
+    (status, Json(json!({ "error": error.to_string() })))
+}
+
+fn validation_error(errors: validator::ValidationErrors) -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({
+            "error": "バリデーションエラー",
+            "details": errors
+        })),
+    )
+}
+
+/// セグメント一覧を取得
+pub async fn list_segments(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let segments = segments::list_segments(&state.db, auth_user.user_id)
+        .await
+        .map_err(|e| {
+            error_response(SegmentError::Database(format!(
+                "セグメント一覧の取得に失敗しました: {e}"
+            )))
+        })?;
+
+    let total = segments.len();
+    Ok(Json(json!({
+        "segments": segments,
+        "total": total
+    })))
+}
+
+/// セグメントを作成
+pub async fn create_segment(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateSegmentRequest>,
+) -> Result<(StatusCode, Json<SegmentResponse>), (StatusCode, Json<Value>)> {
+    payload.validate().map_err(validation_error)?;
+
+    let service = SegmentService::new();
+    let segment = service
+        .create_segment(&state.db, auth_user.user_id, &payload)
+        .await
+        .map_err(error_response)?;
+    let count = service
+        .count_subscribers(&state.db, auth_user.user_id, &segment.rules)
+        .await
+        .map_err(error_response)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(SegmentResponse::new(segment, count)),
+    ))
+}
+
+/// セグメント詳細を取得（現在の該当購読者数を含む）
+pub async fn get_segment(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<SegmentResponse>, (StatusCode, Json<Value>)> {
+    let service = SegmentService::new();
+    let segment = service
+        .get_segment(&state.db, id, auth_user.user_id)
+        .await
+        .map_err(error_response)?;
+    let count = service
+        .count_subscribers(&state.db, auth_user.user_id, &segment.rules)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(SegmentResponse::new(segment, count)))
+}
+
+/// セグメントを更新
+pub async fn update_segment(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateSegmentRequest>,
+) -> Result<Json<SegmentResponse>, (StatusCode, Json<Value>)> {
+    payload.validate().map_err(validation_error)?;
+
+    let service = SegmentService::new();
+    let segment = service
+        .update_segment(&state.db, id, auth_user.user_id, &payload)
+        .await
+        .map_err(error_response)?;
+    let count = service
+        .count_subscribers(&state.db, auth_user.user_id, &segment.rules)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(SegmentResponse::new(segment, count)))
+}
+
+/// セグメントを削除
+pub async fn delete_segment(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    SegmentService::new()
+        .delete_segment(&state.db, id, auth_user.user_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// セグメントに一致する購読者を取得
+pub async fn get_segment_subscribers(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<SegmentSubscribersQuery>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let service = SegmentService::new();
+    let segment = service
+        .get_segment(&state.db, id, auth_user.user_id)
+        .await
+        .map_err(error_response)?;
+    let (subscribers, total) = service
+        .list_subscribers(&state.db, auth_user.user_id, &segment.rules, limit, offset)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(json!({
+        "subscribers": subscribers,
+        "segment_id": segment.id,
+        "total": total,
+        "limit": limit,
+        "offset": offset
+    })))
+}
+
+/// 保存前の条件ツリーに一致する購読者数を取得
+pub async fn preview_segment(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Json(payload): Json<PreviewSegmentRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let service = SegmentService::new();
+    service
+        .validate_rules(&state.db, auth_user.user_id, &payload.rules)
+        .await
+        .map_err(error_response)?;
+    let count = service
+        .count_subscribers(&state.db, auth_user.user_id, &payload.rules)
+        .await
+        .map_err(error_response)?;
+
+    Ok(Json(json!({ "subscriber_count": count })))
+}
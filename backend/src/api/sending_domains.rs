@@ -0,0 +1,128 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::{
+    middleware::auth::AuthUser,
+    models::sending_domain::{CreateSendingDomainRequest, SendingDomainResponse},
+    services::{
+        sending_domain_service::{SendingDomainError, SendingDomainService},
+        subscription_service,
+    },
+    AppState,
+};
+
+fn error_response(error: SendingDomainError) -> (StatusCode, Json<Value>) {
+    let status = match &error {
+        SendingDomainError::Invalid(_) => StatusCode::BAD_REQUEST,
+        SendingDomainError::NotFound => StatusCode::NOT_FOUND,
+        SendingDomainError::Conflict(_) => StatusCode::CONFLICT,
+        SendingDomainError::Dns(message) => {
+            tracing::warn!("送信ドメインのDNS確認エラー: {}", message);
+            StatusCode::BAD_GATEWAY
+        }
+        SendingDomainError::Database(message) => {
+            tracing::error!("送信ドメイン処理エラー: {}", message);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+
+    (status, Json(json!({ "error": error.to_string() })))
+}
+
+/// 送信ドメイン一覧を取得
+pub async fn list_sending_domains(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let domains = SendingDomainService::new()
+        .list_domains(&state.db, auth_user.user_id)
+        .await
+        .map_err(error_response)?;
+
+    let total = domains.len();
+    Ok(Json(json!({
+        "sending_domains": domains,
+        "total": total
+    })))
+}
+
+/// 送信ドメインを登録（DKIM鍵を生成し、公開するDNSレコードを返す）
+pub async fn create_sending_domain(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateSendingDomainRequest>,
+) -> Result<(StatusCode, Json<SendingDomainResponse>), (StatusCode, Json<Value>)> {
+    // プランの機能制限をチェック
+    let has_access =
+        subscription_service::check_feature_access(&state.db, auth_user.user_id, "custom_domain")
+            .await
+            .map_err(|e| {
+                tracing::error!("機能アクセスチェックエラー: {:?}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "プラン情報の確認に失敗しました" })),
+                )
+            })?;
+
+    if !has_access {
+        return Err((
+            StatusCode::PAYMENT_REQUIRED,
+            Json(json!({
+                "error": "カスタムドメインはご利用のプランでは使用できません。プランをアップグレードしてください。"
+            })),
+        ));
+    }
+
+    let domain = SendingDomainService::new()
+        .create_domain(&state.db, auth_user.user_id, &payload)
+        .await
+        .map_err(error_response)?;
+
+    tracing::info!("送信ドメイン登録: {}", domain.domain.domain);
+    Ok((StatusCode::CREATED, Json(domain)))
+}
+
+/// 送信ドメインと公開するDNSレコードを取得
+pub async fn get_sending_domain(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<SendingDomainResponse>, (StatusCode, Json<Value>)> {
+    SendingDomainService::new()
+        .get_domain(&state.db, id, auth_user.user_id)
+        .await
+        .map(Json)
+        .map_err(error_response)
+}
+
+/// DNSレコードを確認して送信ドメインを認証
+pub async fn verify_sending_domain(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<SendingDomainResponse>, (StatusCode, Json<Value>)> {
+    SendingDomainService::new()
+        .verify_domain(&state.db, id, auth_user.user_id)
+        .await
+        .map(Json)
+        .map_err(error_response)
+}
+
+/// 送信ドメインを削除
+pub async fn delete_sending_domain(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    SendingDomainService::new()
+        .delete_domain(&state.db, id, auth_user.user_id)
+        .await
+        .map_err(error_response)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
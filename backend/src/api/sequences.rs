@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::Json,
     Extension,
@@ -11,9 +11,11 @@ use crate::{
     database::sequences as db,
     middleware::auth::AuthUser,
     models::sequence::{
-        CreateSequenceRequest, CreateSequenceStepRequest, UpdateSequenceRequest,
-        UpdateSequenceStepRequest,
+        CreateSequenceRequest, CreateSequenceStepRequest, EnrollSubscribersRequest,
+        EnrollSubscribersResult, EnrollmentStatus, ListSequenceEnrollmentsQuery, Sequence,
+        SequenceEnrollment, SequenceStatus, UpdateSequenceRequest, UpdateSequenceStepRequest,
     },
+    services::sequence_service::{SequenceService, StepChange},
     AppState,
 };
 
@@ -22,6 +24,26 @@ pub async fn create_sequence(
     Extension(user): Extension<AuthUser>,
     Json(request): Json<CreateSequenceRequest>,
 ) -> Result<(StatusCode, Json<crate::models::sequence::Sequence>), (StatusCode, Json<Value>)> {
+    SequenceService::new()
+        .validate_trigger_config(
+            &state.db,
+            user.user_id,
+            &request.trigger_type,
+            request.trigger_config.as_ref(),
+        )
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))))?;
+    SequenceService::new()
+        .validate_reentry_policy(request.reentry_policy.as_deref())
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))))?;
+    SequenceService::new()
+        .validate_exit_conditions(&state.db, user.user_id, request.exit_conditions.as_ref())
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))))?;
+    SequenceService::new()
+        .validate_send_window(request.send_window.as_ref())
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))))?;
+
     match db::create_sequence(&state.db, user.user_id, request).await {
         Ok(sequence) => Ok((StatusCode::CREATED, Json(sequence))),
         Err(e) => {
@@ -100,6 +122,82 @@ pub async fn update_sequence(
     match db::get_sequence_by_id(&state.db, sequence_id).await {
         Ok(Some(sequence)) => {
             if sequence.user_id == user.user_id {
+                // トリガーを変更する場合は変更後の設定を検証
+                if request.trigger_type.is_some() || request.trigger_config.is_some() {
+                    let trigger_type = request
+                        .trigger_type
+                        .as_deref()
+                        .unwrap_or(&sequence.trigger_type);
+                    let trigger_config = request
+                        .trigger_config
+                        .as_ref()
+                        .unwrap_or(&sequence.trigger_config);
+                    SequenceService::new()
+                        .validate_trigger_config(
+                            &state.db,
+                            user.user_id,
+                            trigger_type,
+                            Some(trigger_config),
+                        )
+                        .await
+                        .map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))))?;
+                }
+
+                SequenceService::new()
+                    .validate_reentry_policy(request.reentry_policy.as_deref())
+                    .map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))))?;
+                SequenceService::new()
+                    .validate_exit_conditions(
+                        &state.db,
+                        user.user_id,
+                        request.exit_conditions.as_ref(),
+                    )
+                    .await
+                    .map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))))?;
+                SequenceService::new()
+                    .validate_send_window(request.send_window.as_ref())
+                    .map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))))?;
+
+                // 有効化する場合はステップ構成を検証
+                if request.status.as_deref() == Some(SequenceStatus::Active.as_str()) {
+                    SequenceService::new()
+                        .validate_activation(&state.db, &sequence)
+                        .await
+                        .map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))))?;
+                }
+
+                // 有効化またはトリガーの変更の時点のセグメント一致者は登録しない
+                let activating = request.status.as_deref() == Some(SequenceStatus::Active.as_str())
+                    && sequence.status != SequenceStatus::Active.as_str();
+                let trigger_changed =
+                    request.trigger_type.is_some() || request.trigger_config.is_some();
+                let will_be_active = request.status.as_deref().unwrap_or(&sequence.status)
+                    == SequenceStatus::Active.as_str();
+                if activating || (will_be_active && trigger_changed) {
+                    SequenceService::new()
+                        .snapshot_segment_members(
+                            &state.db,
+                            user.user_id,
+                            sequence.id,
+                            request
+                                .trigger_type
+                                .as_deref()
+                                .unwrap_or(&sequence.trigger_type),
+                            request
+                                .trigger_config
+                                .as_ref()
+                                .unwrap_or(&sequence.trigger_config),
+                        )
+                        .await
+                        .map_err(|e| {
+                            tracing::error!("セグメント一致者の記録エラー: {}", e);
+                            (
+                                StatusCode::INTERNAL_SERVER_ERROR,
+                                Json(json!({ "error": "シーケンスの更新に失敗しました" })),
+                            )
+                        })?;
+                }
+
                 match db::update_sequence(&state.db, sequence_id, request).await {
                     Ok(updated_sequence) => Ok(Json(updated_sequence)),
                     Err(e) => {
@@ -234,6 +332,11 @@ pub async fn create_sequence_step(
     match db::get_sequence_by_id(&state.db, sequence_id).await {
         Ok(Some(sequence)) => {
             if sequence.user_id == user.user_id {
+                SequenceService::new()
+                    .validate_step_change(&state.db, &sequence, StepChange::Create(&request))
+                    .await
+                    .map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))))?;
+
                 match db::create_sequence_step(&state.db, sequence_id, request).await {
                     Ok(step) => Ok((StatusCode::CREATED, Json(step))),
                     Err(e) => {
@@ -283,6 +386,15 @@ pub async fn update_sequence_step(
     match db::get_sequence_by_id(&state.db, sequence_id).await {
         Ok(Some(sequence)) => {
             if sequence.user_id == user.user_id {
+                SequenceService::new()
+                    .validate_step_change(
+                        &state.db,
+                        &sequence,
+                        StepChange::Update(step_id, &request),
+                    )
+                    .await
+                    .map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))))?;
+
                 match db::update_sequence_step(&state.db, step_id, request).await {
                     Ok(step) => Ok(Json(step)),
                     Err(e) => {
@@ -331,6 +443,11 @@ pub async fn delete_sequence_step(
     match db::get_sequence_by_id(&state.db, sequence_id).await {
         Ok(Some(sequence)) => {
             if sequence.user_id == user.user_id {
+                SequenceService::new()
+                    .validate_step_change(&state.db, &sequence, StepChange::Delete(step_id))
+                    .await
+                    .map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))))?;
+
                 match db::delete_sequence_step(&state.db, step_id).await {
                     Ok(_) => Ok(StatusCode::NO_CONTENT),
                     Err(e) => {
@@ -379,6 +496,34 @@ pub async fn activate_sequence(
     match db::get_sequence_by_id(&state.db, sequence_id).await {
         Ok(Some(sequence)) => {
             if sequence.user_id == user.user_id {
+                // 分岐先の欠落や循環があるシーケンスは有効化できない
+                SequenceService::new()
+                    .validate_activation(&state.db, &sequence)
+                    .await
+                    .map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))))?;
+
+                // 有効化の時点のセグメント一致者は登録しない
+                if sequence.status != SequenceStatus::Active.as_str() {
+                    SequenceService::new()
+                        .snapshot_segment_members(
+                            &state.db,
+                            user.user_id,
+                            sequence.id,
+                            &sequence.trigger_type,
+                            &sequence.trigger_config,
+                        )
+                        .await
+                        .map_err(|e| {
+                            tracing::error!("セグメント一致者の記録エラー: {}", e);
+                            (
+                                StatusCode::INTERNAL_SERVER_ERROR,
+                                Json(json!({
+                                    "error": "シーケンスのアクティベートに失敗しました"
+                                })),
+                            )
+                        })?;
+                }
+
                 match db::update_sequence_status(&state.db, sequence_id, "active").await {
                     Ok(_) => Ok(StatusCode::NO_CONTENT),
                     Err(e) => {
@@ -465,3 +610,226 @@ pub async fn pause_sequence(
         }
     }
 }
+
+// 自分のシーケンスを取得（存在しない・権限がない場合はエラーレスポンス）
+async fn find_own_sequence(
+    state: &AppState,
+    user: &AuthUser,
+    sequence_id: Uuid,
+) -> Result<Sequence, (StatusCode, Json<Value>)> {
+    match db::get_sequence_by_id(&state.db, sequence_id).await {
+        Ok(Some(sequence)) if sequence.user_id == user.user_id => Ok(sequence),
+        Ok(Some(_)) => Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "error": "このシーケンスへのアクセス権限がありません"
+            })),
+        )),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "シーケンスが見つかりません"
+            })),
+        )),
+        Err(e) => {
+            tracing::error!("シーケンス取得エラー: {:?}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": "シーケンスの取得に失敗しました"
+                })),
+            ))
+        }
+    }
+}
+
+/// シーケンスのエンロールメント一覧（ステータス・現在のステップ付き）
+pub async fn list_sequence_enrollments(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(sequence_id): Path<Uuid>,
+    Query(query): Query<ListSequenceEnrollmentsQuery>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let sequence = find_own_sequence(&state, &user, sequence_id).await?;
+
+    let status = match query.status.as_deref().filter(|s| !s.is_empty()) {
+        Some(status) => Some(EnrollmentStatus::parse(status).ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": format!("ステータス「{status}」は不正です") })),
+            )
+        })?),
+        None => None,
+    };
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let internal_error = |e: anyhow::Error| {
+        tracing::error!("エンロールメント一覧取得エラー: {:?}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "エンロールメント一覧の取得に失敗しました"
+            })),
+        )
+    };
+
+    let enrollments = db::list_sequence_enrollments(
+        &state.db,
+        sequence.id,
+        status.map(|s| s.as_str()),
+        limit,
+        offset,
+    )
+    .await
+    .map_err(internal_error)?;
+    let counts = db::count_enrollments_by_status(&state.db, sequence.id)
+        .await
+        .map_err(internal_error)?;
+
+    let total: i64 = counts
+        .iter()
+        .filter(|(s, _)| status.is_none_or(|status| status.as_str() == s))
+        .map(|(_, count)| count)
+        .sum();
+
+    Ok(Json(json!({
+        "enrollments": enrollments,
+        "counts": counts.into_iter().collect::<std::collections::HashMap<String, i64>>(),
+        "total": total,
+        "limit": limit,
+        "offset": offset
+    })))
+}
+
+/// 購読者ID・タグ・CSVで指定した購読者をシーケンスに登録
+pub async fn enroll_sequence_subscribers(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(sequence_id): Path<Uuid>,
+    Json(request): Json<EnrollSubscribersRequest>,
+) -> Result<Json<EnrollSubscribersResult>, (StatusCode, Json<Value>)> {
+    let sequence = find_own_sequence(&state, &user, sequence_id).await?;
+
+    let service = SequenceService::new();
+    service
+        .validate_enrollment_request(&sequence, &request)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))))?;
+
+    service
+        .enroll_subscribers(&state.db, &sequence, &request)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            tracing::error!("エンロールメント作成エラー: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": "購読者の登録に失敗しました"
+                })),
+            )
+        })
+}
+
+// エンロールメントのステータスを変更（現在のステータスが`from`のいずれかの場合のみ）
+async fn transition_enrollment(
+    state: &AppState,
+    user: &AuthUser,
+    sequence_id: Uuid,
+    enrollment_id: Uuid,
+    from: &[EnrollmentStatus],
+    to: EnrollmentStatus,
+) -> Result<Json<SequenceEnrollment>, (StatusCode, Json<Value>)> {
+    let sequence = find_own_sequence(state, user, sequence_id).await?;
+
+    let internal_error = |e: anyhow::Error| {
+        tracing::error!("エンロールメント更新エラー: {:?}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "エンロールメントの更新に失敗しました"
+            })),
+        )
+    };
+
+    let Some(enrollment) =
+        db::find_sequence_enrollment_by_id(&state.db, sequence.id, enrollment_id)
+            .await
+            .map_err(internal_error)?
+    else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "エンロールメントが見つかりません"
+            })),
+        ));
+    };
+
+    let from: Vec<&str> = from.iter().map(|status| status.as_str()).collect();
+    match db::transition_enrollment_status(&state.db, enrollment.id, &from, to.as_str())
+        .await
+        .map_err(internal_error)?
+    {
+        Some(updated) => Ok(Json(updated)),
+        None => Err((
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": format!(
+                    "ステータスが「{}」のエンロールメントは変更できません",
+                    enrollment.status
+                )
+            })),
+        )),
+    }
+}
+
+/// エンロールメントをキャンセル
+pub async fn cancel_sequence_enrollment(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path((sequence_id, enrollment_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<SequenceEnrollment>, (StatusCode, Json<Value>)> {
+    transition_enrollment(
+        &state,
+        &user,
+        sequence_id,
+        enrollment_id,
+        &[EnrollmentStatus::Active, EnrollmentStatus::Paused],
+        EnrollmentStatus::Cancelled,
+    )
+    .await
+}
+
+/// エンロールメントを一時停止
+pub async fn pause_sequence_enrollment(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path((sequence_id, enrollment_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<SequenceEnrollment>, (StatusCode, Json<Value>)> {
+    transition_enrollment(
+        &state,
+        &user,
+        sequence_id,
+        enrollment_id,
+        &[EnrollmentStatus::Active],
+        EnrollmentStatus::Paused,
+    )
+    .await
+}
+
+/// 一時停止したエンロールメントを再開
+pub async fn resume_sequence_enrollment(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path((sequence_id, enrollment_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<SequenceEnrollment>, (StatusCode, Json<Value>)> {
+    transition_enrollment(
+        &state,
+        &user,
+        sequence_id,
+        enrollment_id,
+        &[EnrollmentStatus::Paused],
+        EnrollmentStatus::Active,
+    )
+    .await
+}
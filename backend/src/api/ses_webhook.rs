@@ -0,0 +1,81 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+
+use crate::{
+    services::ses_feedback_service::{SesFeedbackService, SnsVerificationError},
+    AppState,
+};
+
+/// SES通知（SNS経由のバウンス・苦情・配信通知）の受信エンドポイント
+///
+/// SNSは`Content-Type: text/plain`で送信するため、ボディは文字列として受け取る。
+pub async fn handle_ses_notification(State(state): State<AppState>, body: String) -> Response {
+    let envelope = match SesFeedbackService::parse_envelope(&body) {
+        Ok(envelope) => envelope,
+        Err(e) => {
+            tracing::warn!("{}", e);
+            return error_response(StatusCode::BAD_REQUEST, "SNSメッセージの形式が不正です");
+        }
+    };
+
+    let service = SesFeedbackService::new();
+    if let Err(e) = service.verify(&envelope).await {
+        tracing::warn!("SNSメッセージの検証に失敗しました: {}", e);
+        return match e {
+            SnsVerificationError::Malformed(_) => {
+                error_response(StatusCode::BAD_REQUEST, "SNSメッセージの形式が不正です")
+            }
+            _ => error_response(StatusCode::FORBIDDEN, "SNSメッセージの検証に失敗しました"),
+        };
+    }
+
+    if envelope.is_subscription_confirmation() {
+        return match service.confirm_subscription(&envelope).await {
+            Ok(()) => (
+                StatusCode::OK,
+                Json(json!({"message": "サブスクリプションを確認しました"})),
+            )
+                .into_response(),
+            Err(e) => {
+                tracing::error!("{}", e);
+                error_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "サブスクリプションの確認に失敗しました",
+                )
+            }
+        };
+    }
+
+    if !envelope.is_notification() {
+        tracing::info!("SNSメッセージ種別 {} を無視します", envelope.message_type);
+        return (StatusCode::OK, Json(json!({"message": "無視しました"}))).into_response();
+    }
+
+    let notification = match SesFeedbackService::parse_notification(&envelope) {
+        Ok(notification) => notification,
+        Err(e) => {
+            tracing::warn!("{}", e);
+            return error_response(StatusCode::BAD_REQUEST, "SES通知の形式が不正です");
+        }
+    };
+
+    match service.process_notification(&state.db, &notification).await {
+        Ok(result) => (StatusCode::OK, Json(json!(result))).into_response(),
+        Err(e) => {
+            tracing::error!("SES通知の処理エラー: {}", e);
+            error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "SES通知の処理に失敗しました",
+            )
+        }
+    }
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    (status, Json(json!({"error": message}))).into_response()
+}
@@ -40,6 +40,7 @@ pub async fn list_subscribers(
             "unsubscribed" => Some(SubscriberStatus::Unsubscribed),
             "bounced" => Some(SubscriberStatus::Bounced),
             "complained" => Some(SubscriberStatus::Complained),
+            "pending" => Some(SubscriberStatus::Pending),
             _ => None,
         },
         None => None,
@@ -147,6 +148,14 @@ pub async fn update_subscriber(
         return Err(StatusCode::BAD_REQUEST);
     }
 
+    // 変更前の購読者（タグ・カスタムフィールドの変更トリガーに使う）
+    let before = subscriber_service::get_subscriber(&state.db, subscriber_id, auth_user.user_id)
+        .await
+        .map_err(|e| {
+            eprintln!("購読者取得エラー: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
     // 購読者を更新
     let subscriber =
         subscriber_service::update_subscriber(&state.db, subscriber_id, auth_user.user_id, payload)
@@ -160,13 +169,22 @@ pub async fn update_subscriber(
                 StatusCode::INTERNAL_SERVER_ERROR
             })?;
 
-    match subscriber {
-        Some(subscriber) => Ok(Json(json!({
-            "message": "購読者が更新されました",
-            "subscriber": subscriber
-        }))),
-        None => Err(StatusCode::NOT_FOUND),
+    let (Some(before), Some(subscriber)) = (before, subscriber) else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    // タグの追加・削除、カスタムフィールドの変更によるシーケンスへの自動エンロールメント
+    if let Err(e) = SequenceService::new()
+        .process_subscriber_changes(&state.db, &before, &subscriber)
+        .await
+    {
+        eprintln!("シーケンスエンロールメントエラー: {e}");
     }
+
+    Ok(Json(json!({
+        "message": "購読者が更新されました",
+        "subscriber": subscriber
+    })))
 }
 
 /// 購読者を削除
@@ -11,13 +11,18 @@ use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
+    api::markdown::user_markdown_service,
     database::templates,
     middleware::auth::AuthUser,
     models::template::{
         AnalyzeTemplateResponse, CreateTemplateRequest, PreviewTemplateRequest,
-        PreviewTemplateResponse, TemplateListResponse, TemplateResponse, UpdateTemplateRequest,
+        PreviewTemplateResponse, TemplateDiffQuery, TemplateDiffResponse, TemplateListResponse,
+        TemplateResponse, TemplateRevision, UpdateTemplateRequest,
+    },
+    services::{
+        markdown_service::MarkdownService,
+        template_revision_service::{TemplateRevisionError, TemplateRevisionService},
     },
-    services::markdown_service::MarkdownService,
     AppState,
 };
 
@@ -82,18 +87,15 @@ pub async fn create_template(
         ));
     }
 
-    // マークダウンサービスを使用して変数を抽出
-    let markdown_service = MarkdownService::new();
-    let content_vars = markdown_service.extract_variables(&payload.markdown_content);
-    let subject_vars = markdown_service.extract_variables(&payload.subject_template);
-
-    // 全ての変数をマージ
-    let mut all_vars: Vec<String> = content_vars;
-    for var in subject_vars {
-        if !all_vars.contains(&var) {
-            all_vars.push(var);
-        }
-    }
+    // マークダウンサービスを使用して構文を検証し、変数を抽出
+    let markdown_service = user_markdown_service(&state, &auth_user).await?;
+    validate_template_syntax(
+        &markdown_service,
+        &payload.markdown_content,
+        &payload.subject_template,
+    )?;
+    let template_vars =
+        markdown_service.analyze_template(&payload.markdown_content, &payload.subject_template);
 
     // デフォルト値のマップを作成
     let default_values = get_default_variable_values();
@@ -107,12 +109,12 @@ pub async fn create_template(
 
     if let Value::Object(ref mut map) = variables {
         // 使用されている全ての変数に対してデフォルト値を設定（既存の値がない場合）
-        for var in all_vars {
-            if !map.contains_key(&var) {
-                if let Some(default_value) = default_values.get(&var) {
-                    map.insert(var, default_value.clone());
-                } else {
-                    // カスタム変数のデフォルト値
+        for var in &template_vars.variables {
+            if !map.contains_key(var) {
+                if let Some(default_value) = default_values.get(var) {
+                    map.insert(var.clone(), default_value.clone());
+                } else if !template_vars.optional_variables.contains(var) {
+                    // カスタム変数のデフォルト値（defaultフィルターやifで扱う変数は空のままにする）
                     map.insert(var.clone(), json!(format!("[{}]", var)));
                 }
             }
@@ -226,7 +228,7 @@ pub async fn update_template(
         };
 
     // 更新されるコンテンツから変数を抽出
-    let markdown_service = MarkdownService::new();
+    let markdown_service = user_markdown_service(&state, &auth_user).await?;
     let markdown_content = payload
         .markdown_content
         .as_ref()
@@ -236,16 +238,8 @@ pub async fn update_template(
         .as_ref()
         .unwrap_or(&existing_template.subject_template);
 
-    let content_vars = markdown_service.extract_variables(markdown_content);
-    let subject_vars = markdown_service.extract_variables(subject_template);
-
-    // 全ての変数をマージ
-    let mut all_vars: Vec<String> = content_vars;
-    for var in subject_vars {
-        if !all_vars.contains(&var) {
-            all_vars.push(var);
-        }
-    }
+    validate_template_syntax(&markdown_service, markdown_content, subject_template)?;
+    let template_vars = markdown_service.analyze_template(markdown_content, subject_template);
 
     // デフォルト値のマップを作成
     let default_values = get_default_variable_values();
@@ -259,12 +253,12 @@ pub async fn update_template(
 
     if let Value::Object(ref mut map) = variables {
         // 使用されている全ての変数に対してデフォルト値を設定（既存の値がない場合）
-        for var in all_vars {
-            if !map.contains_key(&var) {
-                if let Some(default_value) = default_values.get(&var) {
-                    map.insert(var, default_value.clone());
-                } else {
-                    // カスタム変数のデフォルト値
+        for var in &template_vars.variables {
+            if !map.contains_key(var) {
+                if let Some(default_value) = default_values.get(var) {
+                    map.insert(var.clone(), default_value.clone());
+                } else if !template_vars.optional_variables.contains(var) {
+                    // カスタム変数のデフォルト値（defaultフィルターやifで扱う変数は空のままにする）
                     map.insert(var.clone(), json!(format!("[{}]", var)));
                 }
             }
@@ -359,7 +353,7 @@ pub async fn preview_template(
         };
 
     // マークダウンサービスを使用してHTMLに変換
-    let markdown_service = MarkdownService::new();
+    let markdown_service = user_markdown_service(&state, &auth_user).await?;
 
     let html = match markdown_service
         .render_with_variables(&template.markdown_content, &variables.variables)
@@ -426,19 +420,12 @@ pub async fn analyze_template_variables(
         };
 
     // マークダウンサービスを初期化
-    let markdown_service = MarkdownService::new();
+    let markdown_service = user_markdown_service(&state, &auth_user).await?;
 
     // テンプレートから変数を抽出
-    let used_variables = markdown_service.extract_variables(&template.markdown_content);
-    let subject_variables = markdown_service.extract_variables(&template.subject_template);
-
-    // 全ての使用変数をマージ（重複除去）
-    let mut all_variables: Vec<String> = used_variables;
-    for var in subject_variables {
-        if !all_variables.contains(&var) {
-            all_variables.push(var);
-        }
-    }
+    let template_vars =
+        markdown_service.analyze_template(&template.markdown_content, &template.subject_template);
+    let all_variables = template_vars.variables.clone();
 
     // システムで自動的に提供される標準変数
     let standard_variables = vec![
@@ -462,10 +449,12 @@ pub async fn analyze_template_variables(
         vec![]
     };
 
-    // 不足している変数（カスタム変数のうち定義されていないもの）
+    // 不足している変数（カスタム変数のうち定義されておらず、値なしでは描画できないもの）
     let missing_variables: Vec<String> = custom_variables
         .iter()
-        .filter(|var| !defined_variables.contains(var))
+        .filter(|var| {
+            !defined_variables.contains(var) && !template_vars.optional_variables.contains(var)
+        })
         .cloned()
         .collect();
 
@@ -474,10 +463,89 @@ pub async fn analyze_template_variables(
         standard_variables,
         custom_variables,
         defined_variables,
+        optional_variables: template_vars.optional_variables,
         missing_variables,
     }))
 }
 
+fn revision_error_response(error: TemplateRevisionError) -> (StatusCode, Json<Value>) {
+    let status = match &error {
+        TemplateRevisionError::TemplateNotFound | TemplateRevisionError::RevisionNotFound(_) => {
+            StatusCode::NOT_FOUND
+        }
+        TemplateRevisionError::Database(message) => {
+            tracing::error!("テンプレートリビジョン処理エラー: {}", message);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+
+    (status, Json(json!({ "error": error.to_string() })))
+}
+
+/// リビジョン一覧取得
+pub async fn list_template_revisions(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let revisions = TemplateRevisionService::new()
+        .list_revisions(&state.db, id, auth_user.user_id)
+        .await
+        .map_err(revision_error_response)?;
+
+    let total = revisions.len();
+    Ok(Json(json!({
+        "revisions": revisions,
+        "total": total
+    })))
+}
+
+/// リビジョン取得
+pub async fn get_template_revision(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Path((id, revision)): Path<(Uuid, i32)>,
+) -> Result<Json<TemplateRevision>, (StatusCode, Json<Value>)> {
+    TemplateRevisionService::new()
+        .get_revision(&state.db, id, auth_user.user_id, revision)
+        .await
+        .map(Json)
+        .map_err(revision_error_response)
+}
+
+/// リビジョン間の差分取得
+pub async fn diff_template_revisions(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<TemplateDiffQuery>,
+) -> Result<Json<TemplateDiffResponse>, (StatusCode, Json<Value>)> {
+    TemplateRevisionService::new()
+        .diff_revisions(&state.db, id, auth_user.user_id, query.from, query.to)
+        .await
+        .map(Json)
+        .map_err(revision_error_response)
+}
+
+/// リビジョンの内容にロールバック
+pub async fn rollback_template(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Path((id, revision)): Path<(Uuid, i32)>,
+) -> Result<Json<TemplateResponse>, (StatusCode, Json<Value>)> {
+    let template = TemplateRevisionService::new()
+        .rollback(&state.db, id, auth_user.user_id, revision)
+        .await
+        .map_err(revision_error_response)?;
+
+    tracing::info!(
+        "テンプレートをリビジョン{}にロールバック: {}",
+        revision,
+        template.id
+    );
+    Ok(Json(template.into()))
+}
+
 /// テンプレート関連のルーターを構築
 pub fn router() -> Router<AppState> {
     Router::new()
@@ -491,3 +559,21 @@ pub fn router() -> Router<AppState> {
         .route("/:id/preview", post(preview_template))
         .route("/:id/analyze", get(analyze_template_variables))
 }
+
+/// テンプレート構文の検証（構文が正しくない場合は400を返す）
+fn validate_template_syntax(
+    markdown_service: &MarkdownService,
+    markdown: &str,
+    subject: &str,
+) -> Result<(), (StatusCode, Json<Value>)> {
+    markdown_service
+        .validate_template(markdown, subject)
+        .map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": e
+                })),
+            )
+        })
+}
@@ -0,0 +1,195 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    database::{campaigns, tracking},
+    models::tracking::{TrackingClaims, TrackingEventType},
+    services::{sequence_service::SequenceService, tracking_service::TrackingService},
+    utils::client_info::ClientInfo,
+    AppState,
+};
+
+/// 1x1の透過GIF
+const TRANSPARENT_GIF: &[u8] = &[
+    0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x01, 0x00, 0x01, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xff, 0xff, 0xff, 0x21, 0xf9, 0x04, 0x01, 0x00, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00,
+    0x01, 0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x44, 0x01, 0x00, 0x3b,
+];
+
+/// 計測イベントを記録して開封数・クリック数を更新
+///
+/// シーケンスのトリガーと終了条件の処理は、リダイレクト・画像の応答を遅らせないようバックグラウンドで行う。
+async fn record_event(
+    pool: &PgPool,
+    claims: &TrackingClaims,
+    event_type: TrackingEventType,
+    headers: &HeaderMap,
+) -> Result<(), sqlx::Error> {
+    let ClientInfo {
+        ip_address,
+        user_agent,
+        ..
+    } = ClientInfo::from_headers(headers);
+
+    // 初回の開封・URLごとの初回のクリックのみシーケンスのトリガーにする
+    // （画像を表示しないクライアントでもクリックされた時点で開封済みとみなす）
+    let mut triggers = Vec::new();
+    let first_open =
+        tracking::record_first_engagement(pool, claims.c, claims.s, TrackingEventType::Open)
+            .await?;
+    if first_open {
+        triggers.push((TrackingEventType::Open, None));
+    }
+
+    if event_type == TrackingEventType::Click {
+        if first_open {
+            tracking::record_tracking_event(
+                pool,
+                claims.c,
+                claims.s,
+                TrackingEventType::Open,
+                None,
+                ip_address.as_deref(),
+                user_agent.as_deref(),
+            )
+            .await?;
+        }
+        tracking::record_first_engagement(pool, claims.c, claims.s, TrackingEventType::Click)
+            .await?;
+        if !tracking::has_clicked_url(pool, claims.c, claims.s, claims.u.as_deref()).await? {
+            triggers.push((TrackingEventType::Click, claims.u.clone()));
+        }
+    }
+
+    tracking::record_tracking_event(
+        pool,
+        claims.c,
+        claims.s,
+        event_type,
+        claims.u.as_deref(),
+        ip_address.as_deref(),
+        user_agent.as_deref(),
+    )
+    .await?;
+
+    spawn_sequence_processing(pool.clone(), claims.c, claims.s, event_type, triggers);
+    Ok(())
+}
+
+/// バックグラウンドでシーケンスのトリガーと終了条件（リンクのクリック）を処理
+fn spawn_sequence_processing(
+    db: PgPool,
+    campaign_id: Uuid,
+    subscriber_id: Uuid,
+    event_type: TrackingEventType,
+    triggers: Vec<(TrackingEventType, Option<String>)>,
+) {
+    if triggers.is_empty() && event_type != TrackingEventType::Click {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let sequence_service = SequenceService::new();
+        for (trigger_event, url) in triggers {
+            if let Err(e) = sequence_service
+                .process_engagement_trigger(
+                    &db,
+                    campaign_id,
+                    subscriber_id,
+                    trigger_event,
+                    url.as_deref(),
+                )
+                .await
+            {
+                tracing::error!("シーケンスエンロールメントエラー: {}", e);
+            }
+        }
+
+        // リンクのクリックを終了条件にしているシーケンスから外す
+        if event_type == TrackingEventType::Click {
+            match campaigns::find_campaign_user_id(&db, campaign_id).await {
+                Ok(Some(user_id)) => {
+                    if let Err(e) = sequence_service
+                        .process_exit_conditions(&db, user_id, subscriber_id)
+                        .await
+                    {
+                        tracing::error!("シーケンスの終了条件の処理エラー: {}", e);
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => tracing::error!("キャンペーンの取得に失敗しました: {}", e),
+            }
+        }
+    });
+}
+
+/// 開封計測ピクセル
+pub async fn track_open(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    match TrackingService::new().verify_token(&token) {
+        Ok(claims) => {
+            if let Err(e) =
+                record_event(&state.db, &claims, TrackingEventType::Open, &headers).await
+            {
+                tracing::error!("開封イベントの記録に失敗しました: {:?}", e);
+            }
+        }
+        Err(e) => tracing::warn!("{}", e),
+    }
+
+    // トークンの成否に関わらず画像を返す
+    (
+        [
+            (header::CONTENT_TYPE, "image/gif"),
+            (
+                header::CACHE_CONTROL,
+                "no-store, no-cache, must-revalidate, max-age=0",
+            ),
+            (header::PRAGMA, "no-cache"),
+        ],
+        TRANSPARENT_GIF,
+    )
+        .into_response()
+}
+
+/// クリック計測リダイレクト
+pub async fn track_click(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let claims = match TrackingService::new().verify_token(&token) {
+        Ok(claims) => claims,
+        Err(e) => {
+            tracing::warn!("{}", e);
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": "リンクが無効です"})),
+            )
+                .into_response();
+        }
+    };
+
+    let Some(target) = claims.u.clone() else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "リダイレクト先が指定されていません"})),
+        )
+            .into_response();
+    };
+
+    if let Err(e) = record_event(&state.db, &claims, TrackingEventType::Click, &headers).await {
+        tracing::error!("クリックイベントの記録に失敗しました: {:?}", e);
+    }
+
+    (StatusCode::FOUND, [(header::LOCATION, target)]).into_response()
+}
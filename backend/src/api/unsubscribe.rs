@@ -0,0 +1,166 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+};
+
+use crate::{
+    database::subscribers,
+    models::subscriber::{SubscriberStatus, UnsubscribeClaims},
+    services::{sequence_service::SequenceService, unsubscribe_service::UnsubscribeService},
+    utils::html::escape_html,
+    AppState,
+};
+
+/// 配信停止ページの共通レイアウト
+fn render_page(status: StatusCode, title: &str, body: &str) -> Response {
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html lang="ja">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <meta name="robots" content="noindex">
+    <title>{title}</title>
+    <style>
+        body {{
+            font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, 'Helvetica Neue', Arial, sans-serif;
+            line-height: 1.6;
+            color: #333;
+            background-color: #f4f4f4;
+            margin: 0;
+            padding: 40px 20px;
+        }}
+        .container {{
+            max-width: 480px;
+            margin: 0 auto;
+            padding: 32px;
+            background-color: #ffffff;
+            border-radius: 8px;
+            text-align: center;
+        }}
+        button {{
+            padding: 12px 32px;
+            background-color: #000;
+            color: #ffffff;
+            border: none;
+            border-radius: 30px;
+            font-size: 16px;
+            cursor: pointer;
+        }}
+    </style>
+</head>
+<body>
+    <div class="container">
+        <h1>{title}</h1>
+        {body}
+    </div>
+</body>
+</html>"#
+    );
+
+    (status, Html(html)).into_response()
+}
+
+fn invalid_link_page() -> Response {
+    render_page(
+        StatusCode::NOT_FOUND,
+        "リンクが無効です",
+        "<p>配信停止リンクが無効か、購読者情報が見つかりません。</p>",
+    )
+}
+
+fn verify(token: &str) -> Option<UnsubscribeClaims> {
+    match UnsubscribeService::new().verify_token(token) {
+        Ok(claims) => Some(claims),
+        Err(e) => {
+            tracing::warn!("{}", e);
+            None
+        }
+    }
+}
+
+/// 配信停止の確認ページ
+pub async fn get_unsubscribe_page(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Response {
+    let Some(claims) = verify(&token) else {
+        return invalid_link_page();
+    };
+
+    let subscriber =
+        match subscribers::find_subscriber_by_id(&state.db, claims.sub, claims.uid).await {
+            Ok(Some(subscriber)) => subscriber,
+            Ok(None) => return invalid_link_page(),
+            Err(e) => {
+                tracing::error!("購読者取得エラー: {:?}", e);
+                return render_page(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "エラーが発生しました",
+                    "<p>時間をおいて再度お試しください。</p>",
+                );
+            }
+        };
+
+    let email = escape_html(&subscriber.email);
+    if matches!(subscriber.status, SubscriberStatus::Unsubscribed) {
+        return render_page(
+            StatusCode::OK,
+            "配信停止済みです",
+            &format!("<p>{email} 宛てのメール配信は既に停止されています。</p>"),
+        );
+    }
+
+    render_page(
+        StatusCode::OK,
+        "配信停止の確認",
+        &format!(
+            r#"<p>{email} 宛てのメール配信を停止しますか？</p>
+        <form method="post">
+            <button type="submit">配信を停止する</button>
+        </form>"#
+        ),
+    )
+}
+
+/// 配信停止の実行（RFC 8058 ワンクリック配信停止にも対応）
+pub async fn unsubscribe(State(state): State<AppState>, Path(token): Path<String>) -> Response {
+    let Some(claims) = verify(&token) else {
+        return invalid_link_page();
+    };
+
+    match subscribers::unsubscribe_subscriber(&state.db, claims.sub, claims.uid).await {
+        Ok(Some(subscriber)) => {
+            tracing::info!(
+                "購読者 {} が配信停止しました（キャンペーン: {:?}）",
+                subscriber.id,
+                claims.cid
+            );
+            // 配信停止を終了条件にしているシーケンスから外す
+            if let Err(e) = SequenceService::new()
+                .process_exit_conditions(&state.db, subscriber.user_id, subscriber.id)
+                .await
+            {
+                tracing::error!("シーケンスの終了条件の処理エラー: {}", e);
+            }
+            render_page(
+                StatusCode::OK,
+                "配信を停止しました",
+                &format!(
+                    "<p>{} 宛てのメール配信を停止しました。</p>",
+                    escape_html(&subscriber.email)
+                ),
+            )
+        }
+        Ok(None) => invalid_link_page(),
+        Err(e) => {
+            tracing::error!("配信停止処理エラー: {:?}", e);
+            render_page(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "エラーが発生しました",
+                "<p>時間をおいて再度お試しください。</p>",
+            )
+        }
+    }
+}
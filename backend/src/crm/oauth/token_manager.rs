@@ -207,7 +207,5 @@ impl TokenManager {
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-
     // TODO: Add tests for TokenManager
 }
@@ -0,0 +1,217 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::ab_test::{
+    AbTestStatus, CampaignAbTest, CampaignVariant, ConfigureAbTestRequest, VariantStats,
+};
+
+const AB_TEST_COLUMNS: &str = r#"
+    campaign_id, test_percentage, winner_metric, wait_minutes, status,
+    test_started_at, winner_variant_id, winner_selected_at, created_at, updated_at
+"#;
+
+const VARIANT_COLUMNS: &str = r#"
+    id, campaign_id, name, subject, template_id, template_revision_id, split_percentage,
+    created_at, updated_at
+"#;
+
+/// キャンペーンのA/Bテスト設定を取得
+pub async fn find_ab_test(
+    pool: &PgPool,
+    campaign_id: Uuid,
+) -> Result<Option<CampaignAbTest>, sqlx::Error> {
+    sqlx::query_as::<_, CampaignAbTest>(&format!(
+        "SELECT {AB_TEST_COLUMNS} FROM campaign_ab_tests WHERE campaign_id = $1"
+    ))
+    .bind(campaign_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// キャンペーンのバリアント一覧を取得
+pub async fn list_variants(
+    pool: &PgPool,
+    campaign_id: Uuid,
+) -> Result<Vec<CampaignVariant>, sqlx::Error> {
+    sqlx::query_as::<_, CampaignVariant>(&format!(
+        "SELECT {VARIANT_COLUMNS} FROM campaign_variants WHERE campaign_id = $1 ORDER BY name ASC"
+    ))
+    .bind(campaign_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// A/Bテスト設定とバリアントを保存（既存の設定は置き換える）
+pub async fn save_ab_test(
+    pool: &PgPool,
+    campaign_id: Uuid,
+    request: &ConfigureAbTestRequest,
+) -> Result<(CampaignAbTest, Vec<CampaignVariant>), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM campaign_variants WHERE campaign_id = $1")
+        .bind(campaign_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let ab_test = sqlx::query_as::<_, CampaignAbTest>(&format!(
+        r#"
+        INSERT INTO campaign_ab_tests (campaign_id, test_percentage, winner_metric, wait_minutes)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (campaign_id) DO UPDATE SET
+            test_percentage = EXCLUDED.test_percentage,
+            winner_metric = EXCLUDED.winner_metric,
+            wait_minutes = EXCLUDED.wait_minutes,
+            status = 'draft',
+            test_started_at = NULL,
+            winner_variant_id = NULL,
+            winner_selected_at = NULL
+        RETURNING {AB_TEST_COLUMNS}
+        "#
+    ))
+    .bind(campaign_id)
+    .bind(request.test_percentage)
+    .bind(request.winner_metric.as_str())
+    .bind(request.wait_minutes)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let mut variants = Vec::with_capacity(request.variants.len());
+    for variant in &request.variants {
+        let variant = sqlx::query_as::<_, CampaignVariant>(&format!(
+            r#"
+            INSERT INTO campaign_variants (campaign_id, name, subject, template_id, split_percentage)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING {VARIANT_COLUMNS}
+            "#
+        ))
+        .bind(campaign_id)
+        .bind(variant.name.trim())
+        .bind(&variant.subject)
+        .bind(variant.template_id)
+        .bind(variant.split_percentage)
+        .fetch_one(&mut *tx)
+        .await?;
+        variants.push(variant);
+    }
+
+    tx.commit().await?;
+
+    variants.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok((ab_test, variants))
+}
+
+/// A/Bテスト設定とバリアントを削除
+pub async fn delete_ab_test(pool: &PgPool, campaign_id: Uuid) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM campaign_variants WHERE campaign_id = $1")
+        .bind(campaign_id)
+        .execute(&mut *tx)
+        .await?;
+    let result = sqlx::query("DELETE FROM campaign_ab_tests WHERE campaign_id = $1")
+        .bind(campaign_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// テスト送信の開始を記録
+pub async fn start_ab_test(pool: &PgPool, campaign_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE campaign_ab_tests
+        SET status = $2, test_started_at = NOW()
+        WHERE campaign_id = $1 AND status = 'draft'
+        "#,
+    )
+    .bind(campaign_id)
+    .bind(AbTestStatus::Testing.as_str())
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// 勝者のバリアントを記録
+pub async fn complete_ab_test(
+    pool: &PgPool,
+    campaign_id: Uuid,
+    winner_variant_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE campaign_ab_tests
+        SET status = $3, winner_variant_id = $2, winner_selected_at = NOW()
+        WHERE campaign_id = $1 AND status = 'testing'
+        "#,
+    )
+    .bind(campaign_id)
+    .bind(winner_variant_id)
+    .bind(AbTestStatus::Completed.as_str())
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// バリアントで配信するテンプレートのリビジョンを固定
+pub async fn pin_variant_template_revision(
+    pool: &PgPool,
+    variant_id: Uuid,
+    template_revision_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE campaign_variants SET template_revision_id = $2 WHERE id = $1")
+        .bind(variant_id)
+        .bind(template_revision_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// バリアントごとの送信数とユニーク開封・クリック数を集計
+///
+/// 勝者の送信分は含めず、テスト送信の結果のみを集計する。
+pub async fn variant_stats(
+    pool: &PgPool,
+    campaign_id: Uuid,
+) -> Result<Vec<VariantStats>, sqlx::Error> {
+    sqlx::query_as::<_, VariantStats>(
+        r#"
+        SELECT
+            v.id AS variant_id,
+            v.name,
+            v.subject,
+            COUNT(d.id) FILTER (WHERE d.status = 'sent') AS sent,
+            COUNT(d.id) FILTER (WHERE d.status = 'sent' AND EXISTS (
+                SELECT 1 FROM campaign_tracking_events e
+                WHERE e.campaign_id = d.campaign_id
+                  AND e.subscriber_id = d.subscriber_id
+                  AND e.event_type = 'open'
+            )) AS opened,
+            COUNT(d.id) FILTER (WHERE d.status = 'sent' AND EXISTS (
+                SELECT 1 FROM campaign_tracking_events e
+                WHERE e.campaign_id = d.campaign_id
+                  AND e.subscriber_id = d.subscriber_id
+                  AND e.event_type = 'click'
+            )) AS clicked
+        FROM campaign_variants v
+        LEFT JOIN campaign_deliveries d
+            ON d.variant_id = v.id
+           AND d.created_at <= COALESCE(
+                (SELECT winner_selected_at FROM campaign_ab_tests WHERE campaign_id = $1),
+                'infinity'::timestamptz
+           )
+        WHERE v.campaign_id = $1
+        GROUP BY v.id, v.name, v.subject
+        ORDER BY v.name ASC
+        "#,
+    )
+    .bind(campaign_id)
+    .fetch_all(pool)
+    .await
+}
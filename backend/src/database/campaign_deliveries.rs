@@ -0,0 +1,431 @@
+use chrono::{DateTime, Utc};
+use sqlx::{pool::PoolConnection, PgPool, Postgres};
+use uuid::Uuid;
+
+use crate::models::{
+    campaign::{CampaignDelivery, DeliveryCounts, DeliveryStatus},
+    subscriber::Subscriber,
+};
+
+const DELIVERY_COLUMNS: &str = r#"
+    id, campaign_id, subscriber_id, email, status, attempts, message_id, error,
+    next_attempt_at, locked_at, sent_at, delivered_at, bounced_at, bounce_type, complained_at,
+    variant_id, created_at, updated_at
+"#;
+
+/// 購読者を配信キューに登録（登録済みの購読者は無視）
+pub async fn enqueue_deliveries(
+    pool: &PgPool,
+    campaign_id: Uuid,
+    subscribers: &[Subscriber],
+) -> Result<u64, sqlx::Error> {
+    let subscriber_ids: Vec<Uuid> = subscribers.iter().map(|s| s.id).collect();
+    let emails: Vec<String> = subscribers.iter().map(|s| s.email.clone()).collect();
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO campaign_deliveries (campaign_id, subscriber_id, email)
+        SELECT $1, subscriber_id, email
+        FROM UNNEST($2::uuid[], $3::text[]) AS t(subscriber_id, email)
+        ON CONFLICT (campaign_id, subscriber_id) DO NOTHING
+        "#,
+    )
+    .bind(campaign_id)
+    .bind(&subscriber_ids)
+    .bind(&emails)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// 購読者をA/Bテストのバリアントの配信キューに登録（登録済みの購読者は無視）
+pub async fn enqueue_variant_deliveries(
+    pool: &PgPool,
+    campaign_id: Uuid,
+    variant_id: Uuid,
+    subscribers: &[Subscriber],
+) -> Result<u64, sqlx::Error> {
+    let subscriber_ids: Vec<Uuid> = subscribers.iter().map(|s| s.id).collect();
+    let emails: Vec<String> = subscribers.iter().map(|s| s.email.clone()).collect();
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO campaign_deliveries (campaign_id, subscriber_id, email, variant_id)
+        SELECT $1, subscriber_id, email, $4
+        FROM UNNEST($2::uuid[], $3::text[]) AS t(subscriber_id, email)
+        ON CONFLICT (campaign_id, subscriber_id) DO NOTHING
+        "#,
+    )
+    .bind(campaign_id)
+    .bind(&subscriber_ids)
+    .bind(&emails)
+    .bind(variant_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// 送信対象の配信レコードを確保
+///
+/// 送信待ちで送信予定時刻を過ぎたものに加え、`stale_after_seconds`以上前に確保されたまま
+/// 完了していないもの（送信中にプロセスが停止したもの）も再度確保する。
+/// `FOR UPDATE SKIP LOCKED`により複数ワーカー間で同じレコードが確保されることはない。
+pub async fn claim_deliveries(
+    pool: &PgPool,
+    campaign_id: Uuid,
+    limit: i64,
+    stale_after_seconds: i64,
+) -> Result<Vec<CampaignDelivery>, sqlx::Error> {
+    let query = format!(
+        r#"
+        UPDATE campaign_deliveries
+        SET
+            status = 'sending',
+            attempts = attempts + 1,
+            locked_at = NOW()
+        WHERE id IN (
+            SELECT id FROM campaign_deliveries
+            WHERE campaign_id = $1
+              AND (
+                (status = 'pending' AND next_attempt_at <= NOW())
+                OR (status = 'sending' AND locked_at < NOW() - make_interval(secs => $3))
+              )
+            ORDER BY next_attempt_at ASC
+            LIMIT $2
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING {DELIVERY_COLUMNS}
+        "#
+    );
+
+    let rows = sqlx::query_as::<_, CampaignDelivery>(&query)
+        .bind(campaign_id)
+        .bind(limit)
+        .bind(stale_after_seconds as f64)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows)
+}
+
+/// 配信ワーカーが送信処理を再開する必要があるか
+///
+/// 送信予定時刻を過ぎた送信待ち・`stale_after_seconds`以上前に確保されたままの配信がある場合に加え、
+/// 未処理の配信が残っていない場合（配信キューの作成・完了処理が中断した場合やA/Bテストの勝者の判定時刻を
+/// 過ぎた場合）も再開する。A/Bテストの勝者の判定時刻を待っている間や再送待ちの間は再開しない。
+pub async fn needs_resume(
+    pool: &PgPool,
+    campaign_id: Uuid,
+    stale_after_seconds: i64,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT
+            EXISTS (
+                SELECT 1 FROM campaign_deliveries
+                WHERE campaign_id = $1
+                  AND (
+                    (status = 'pending' AND next_attempt_at <= NOW())
+                    OR (status = 'sending' AND locked_at < NOW() - make_interval(secs => $2))
+                  )
+            )
+            OR (
+                NOT EXISTS (
+                    SELECT 1 FROM campaign_deliveries
+                    WHERE campaign_id = $1 AND status IN ('pending', 'sending')
+                )
+                AND NOT EXISTS (
+                    SELECT 1 FROM campaign_ab_tests
+                    WHERE campaign_id = $1
+                      AND status = 'testing'
+                      AND test_started_at + make_interval(mins => wait_minutes) > NOW()
+                )
+            )
+        "#,
+    )
+    .bind(campaign_id)
+    .bind(stale_after_seconds as f64)
+    .fetch_one(pool)
+    .await
+}
+
+/// 送信成功を記録
+pub async fn mark_delivery_sent(
+    pool: &PgPool,
+    delivery_id: Uuid,
+    message_id: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE campaign_deliveries
+        SET status = 'sent', message_id = $2, error = NULL, locked_at = NULL, sent_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(delivery_id)
+    .bind(message_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// 一時的な失敗を記録し、指定日時に再送信する
+pub async fn schedule_delivery_retry(
+    pool: &PgPool,
+    delivery_id: Uuid,
+    error: &str,
+    next_attempt_at: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE campaign_deliveries
+        SET status = 'pending', error = $2, next_attempt_at = $3, locked_at = NULL
+        WHERE id = $1
+        "#,
+    )
+    .bind(delivery_id)
+    .bind(error)
+    .bind(next_attempt_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// 送信失敗または送信対象外として確定
+pub async fn finish_delivery(
+    pool: &PgPool,
+    delivery_id: Uuid,
+    status: DeliveryStatus,
+    error: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE campaign_deliveries
+        SET status = $2, error = $3, locked_at = NULL
+        WHERE id = $1
+        "#,
+    )
+    .bind(delivery_id)
+    .bind(status.as_str())
+    .bind(error)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// メールプロバイダーのメッセージIDから配信レコードを取得
+pub async fn find_delivery_by_message_id(
+    pool: &PgPool,
+    message_id: &str,
+) -> Result<Option<CampaignDelivery>, sqlx::Error> {
+    let query = format!(
+        r#"
+        SELECT {DELIVERY_COLUMNS}
+        FROM campaign_deliveries
+        WHERE message_id = $1
+        "#
+    );
+
+    sqlx::query_as::<_, CampaignDelivery>(&query)
+        .bind(message_id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// 受信サーバーへの配信完了を記録
+pub async fn mark_delivery_delivered(pool: &PgPool, delivery_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE campaign_deliveries
+        SET delivered_at = COALESCE(delivered_at, NOW())
+        WHERE id = $1
+        "#,
+    )
+    .bind(delivery_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// バウンスを記録
+pub async fn mark_delivery_bounced(
+    pool: &PgPool,
+    delivery_id: Uuid,
+    bounce_type: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE campaign_deliveries
+        SET bounced_at = COALESCE(bounced_at, NOW()), bounce_type = $2
+        WHERE id = $1
+        "#,
+    )
+    .bind(delivery_id)
+    .bind(bounce_type)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// 苦情（迷惑メール報告）を記録
+pub async fn mark_delivery_complained(pool: &PgPool, delivery_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE campaign_deliveries
+        SET complained_at = COALESCE(complained_at, NOW())
+        WHERE id = $1
+        "#,
+    )
+    .bind(delivery_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// キャンペーンの配信状況を集計
+pub async fn count_deliveries(
+    pool: &PgPool,
+    campaign_id: Uuid,
+) -> Result<DeliveryCounts, sqlx::Error> {
+    let counts = sqlx::query_as::<_, DeliveryCounts>(
+        r#"
+        SELECT
+            COUNT(*) AS total,
+            COUNT(*) FILTER (WHERE status = 'pending') AS pending,
+            COUNT(*) FILTER (WHERE status = 'sending') AS sending,
+            COUNT(*) FILTER (WHERE status = 'sent') AS sent,
+            COUNT(*) FILTER (WHERE status = 'failed') AS failed,
+            COUNT(*) FILTER (WHERE status = 'skipped') AS skipped
+        FROM campaign_deliveries
+        WHERE campaign_id = $1
+        "#,
+    )
+    .bind(campaign_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(counts)
+}
+
+/// キャンペーンの配信レコード一覧を取得
+pub async fn list_deliveries(
+    pool: &PgPool,
+    campaign_id: Uuid,
+    status: Option<DeliveryStatus>,
+) -> Result<Vec<CampaignDelivery>, sqlx::Error> {
+    let query = format!(
+        r#"
+        SELECT {DELIVERY_COLUMNS}
+        FROM campaign_deliveries
+        WHERE campaign_id = $1 AND ($2::text IS NULL OR status = $2)
+        ORDER BY created_at ASC
+        "#
+    );
+
+    let rows = sqlx::query_as::<_, CampaignDelivery>(&query)
+        .bind(campaign_id)
+        .bind(status.map(|s| s.as_str()))
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows)
+}
+
+/// 失敗した配信を送信待ちに戻す（再送用）
+pub async fn reset_failed_deliveries(pool: &PgPool, campaign_id: Uuid) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE campaign_deliveries
+        SET status = 'pending', attempts = 0, error = NULL, next_attempt_at = NOW(), locked_at = NULL
+        WHERE campaign_id = $1 AND status = 'failed'
+        "#,
+    )
+    .bind(campaign_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// キャンペーンの配信レコードを全て削除（全件再送用）
+pub async fn delete_deliveries(pool: &PgPool, campaign_id: Uuid) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM campaign_deliveries WHERE campaign_id = $1")
+        .bind(campaign_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// キャンペーン単位の配信ロック（セッションレベルのアドバイザリロック）
+///
+/// ロックを保持する接続を持ち、`release`で解放してから接続をプールに戻す。
+/// 解放せずに破棄された場合（エラー・パニック・Futureのキャンセル）や解放に失敗した場合は、
+/// 接続をプールに戻さずに閉じ、セッションの終了とともにロックを解放する。
+pub struct CampaignLock {
+    conn: Option<PoolConnection<Postgres>>,
+    campaign_id: Uuid,
+}
+
+impl CampaignLock {
+    /// ロックを取得（他のワーカーが保持している場合は`None`）
+    pub async fn try_acquire(
+        pool: &PgPool,
+        campaign_id: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let mut conn = pool.acquire().await?;
+        let locked =
+            sqlx::query_scalar::<_, bool>("SELECT pg_try_advisory_lock(hashtextextended($1, 0))")
+                .bind(campaign_id.to_string())
+                .fetch_one(&mut *conn)
+                .await?;
+
+        Ok(locked.then_some(Self {
+            conn: Some(conn),
+            campaign_id,
+        }))
+    }
+
+    /// ロックを解放して接続をプールに戻す
+    pub async fn release(mut self) -> Result<(), sqlx::Error> {
+        let Some(mut conn) = self.conn.take() else {
+            return Ok(());
+        };
+
+        match sqlx::query_scalar::<_, bool>("SELECT pg_advisory_unlock(hashtextextended($1, 0))")
+            .bind(self.campaign_id.to_string())
+            .fetch_one(&mut *conn)
+            .await
+        {
+            Ok(true) => Ok(()),
+            Ok(false) => {
+                drop(conn.detach());
+                Ok(())
+            }
+            Err(e) => {
+                drop(conn.detach());
+                Err(e)
+            }
+        }
+    }
+}
+
+impl Drop for CampaignLock {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            tracing::warn!(
+                "キャンペーン {} の配信ロックを解放せずに終了したため接続を閉じます",
+                self.campaign_id
+            );
+            drop(conn.detach());
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use sqlx::PgPool;
+use sqlx::{types::Json, PgPool};
 use uuid::Uuid;
 
 use crate::models::campaign::{
@@ -23,6 +23,7 @@ pub async fn list_user_campaigns(
             id,
             user_id,
             template_id,
+            template_revision_id,
             name,
             description,
             subject,
@@ -33,6 +34,9 @@ pub async fn list_user_campaigns(
             sent_count,
             opened_count,
             clicked_count,
+            audience,
+            from_email,
+            from_name,
             created_at,
             updated_at
         FROM campaigns 
@@ -77,6 +81,17 @@ pub async fn count_user_campaigns(
     Ok(count)
 }
 
+/// キャンペーンの所有者を取得（計測イベントの受信時）
+pub async fn find_campaign_user_id(
+    pool: &PgPool,
+    campaign_id: Uuid,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    sqlx::query_scalar::<_, Uuid>("SELECT user_id FROM campaigns WHERE id = $1")
+        .bind(campaign_id)
+        .fetch_optional(pool)
+        .await
+}
+
 /// キャンペーンをIDで取得
 pub async fn find_campaign_by_id(
     pool: &PgPool,
@@ -89,6 +104,7 @@ pub async fn find_campaign_by_id(
             id,
             user_id,
             template_id,
+            template_revision_id,
             name,
             description,
             subject,
@@ -99,6 +115,9 @@ pub async fn find_campaign_by_id(
             sent_count,
             opened_count,
             clicked_count,
+            audience,
+            from_email,
+            from_name,
             created_at,
             updated_at
         FROM campaigns 
@@ -127,13 +146,17 @@ pub async fn create_campaign(
             name, 
             description, 
             subject,
-            status
+            status,
+            audience,
+            from_email,
+            from_name
         )
-        VALUES ($1, $2, $3, $4, $5, $6)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
         RETURNING 
             id,
             user_id,
             template_id,
+            template_revision_id,
             name,
             description,
             subject,
@@ -144,6 +167,9 @@ pub async fn create_campaign(
             sent_count,
             opened_count,
             clicked_count,
+            audience,
+            from_email,
+            from_name,
             created_at,
             updated_at
         "#,
@@ -154,6 +180,9 @@ pub async fn create_campaign(
     .bind(&request.description)
     .bind(&request.subject)
     .bind(CampaignStatus::Draft.to_string())
+    .bind(Json(request.audience.clone().unwrap_or_default()))
+    .bind(request.from_email.as_deref().map(str::trim))
+    .bind(request.from_name.as_deref().map(str::trim))
     .fetch_one(pool)
     .await?;
 
@@ -174,9 +203,9 @@ pub async fn update_campaign(
     let current_campaign = sqlx::query_as::<_, Campaign>(
         r#"
         SELECT 
-            id, user_id, template_id, name, description, subject, status, 
+            id, user_id, template_id, template_revision_id, name, description, subject, status, 
             scheduled_at, sent_at, recipient_count, sent_count, opened_count, 
-            clicked_count, created_at, updated_at
+            clicked_count, audience, from_email, from_name, created_at, updated_at
         FROM campaigns 
         WHERE id = $1 AND user_id = $2
         FOR UPDATE
@@ -214,12 +243,15 @@ pub async fn update_campaign(
             template_id = COALESCE($6, template_id),
             status = COALESCE($7, status),
             scheduled_at = COALESCE($8, scheduled_at),
+            audience = COALESCE($9, audience),
+            from_email = CASE WHEN $10::text IS NULL THEN from_email ELSE NULLIF($10, '') END,
+            from_name = CASE WHEN $11::text IS NULL THEN from_name ELSE NULLIF($11, '') END,
             updated_at = NOW()
         WHERE id = $1 AND user_id = $2
         RETURNING 
-            id, user_id, template_id, name, description, subject, status, 
+            id, user_id, template_id, template_revision_id, name, description, subject, status, 
             scheduled_at, sent_at, recipient_count, sent_count, opened_count, 
-            clicked_count, created_at, updated_at
+            clicked_count, audience, from_email, from_name, created_at, updated_at
         "#,
     )
     .bind(campaign_id)
@@ -230,6 +262,9 @@ pub async fn update_campaign(
     .bind(request.template_id)
     .bind(request.status.as_ref().map(|s| s.to_string()))
     .bind(request.scheduled_at)
+    .bind(request.audience.clone().map(Json))
+    .bind(request.from_email.as_deref().map(str::trim))
+    .bind(request.from_name.as_deref().map(str::trim))
     .fetch_one(&mut *tx)
     .await?;
 
@@ -252,9 +287,9 @@ pub async fn delete_campaign(
     let current_campaign = sqlx::query_as::<_, Campaign>(
         r#"
         SELECT 
-            id, user_id, template_id, name, description, subject, status, 
+            id, user_id, template_id, template_revision_id, name, description, subject, status, 
             scheduled_at, sent_at, recipient_count, sent_count, opened_count, 
-            clicked_count, created_at, updated_at
+            clicked_count, audience, from_email, from_name, created_at, updated_at
         FROM campaigns 
         WHERE id = $1 AND user_id = $2
         FOR UPDATE
@@ -309,9 +344,9 @@ pub async fn update_campaign_status(
     let current_campaign = sqlx::query_as::<_, Campaign>(
         r#"
         SELECT 
-            id, user_id, template_id, name, description, subject, status, 
+            id, user_id, template_id, template_revision_id, name, description, subject, status, 
             scheduled_at, sent_at, recipient_count, sent_count, opened_count, 
-            clicked_count, created_at, updated_at
+            clicked_count, audience, from_email, from_name, created_at, updated_at
         FROM campaigns 
         WHERE id = $1 AND user_id = $2
         FOR UPDATE
@@ -357,9 +392,9 @@ pub async fn update_campaign_status(
             updated_at = NOW()
         WHERE id = $1 AND user_id = $2
         RETURNING 
-            id, user_id, template_id, name, description, subject, status, 
+            id, user_id, template_id, template_revision_id, name, description, subject, status, 
             scheduled_at, sent_at, recipient_count, sent_count, opened_count, 
-            clicked_count, created_at, updated_at
+            clicked_count, audience, from_email, from_name, created_at, updated_at
         "#
     );
 
@@ -391,9 +426,9 @@ pub async fn schedule_campaign(
     let current_campaign = sqlx::query_as::<_, Campaign>(
         r#"
         SELECT 
-            id, user_id, template_id, name, description, subject, status, 
+            id, user_id, template_id, template_revision_id, name, description, subject, status, 
             scheduled_at, sent_at, recipient_count, sent_count, opened_count, 
-            clicked_count, created_at, updated_at
+            clicked_count, audience, from_email, from_name, created_at, updated_at
         FROM campaigns 
         WHERE id = $1 AND user_id = $2
         FOR UPDATE
@@ -430,9 +465,9 @@ pub async fn schedule_campaign(
             updated_at = NOW()
         WHERE id = $1 AND user_id = $2
         RETURNING 
-            id, user_id, template_id, name, description, subject, status, 
+            id, user_id, template_id, template_revision_id, name, description, subject, status, 
             scheduled_at, sent_at, recipient_count, sent_count, opened_count, 
-            clicked_count, created_at, updated_at
+            clicked_count, audience, from_email, from_name, created_at, updated_at
         "#,
     )
     .bind(campaign_id)
@@ -447,6 +482,71 @@ pub async fn schedule_campaign(
     Ok(Some(row))
 }
 
+/// スケジュール済みキャンペーンのスケジュールを取り消す
+///
+/// 送信待ち（scheduled）の場合のみ取り消し可能。スケジューラーが既に送信を開始している場合は`None`を返す。
+pub async fn cancel_scheduled_campaign(
+    pool: &PgPool,
+    campaign_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<Campaign>, sqlx::Error> {
+    let row = sqlx::query_as::<_, Campaign>(
+        r#"
+        UPDATE campaigns 
+        SET 
+            status = 'cancelled',
+            scheduled_at = NULL,
+            updated_at = NOW()
+        WHERE id = $1 AND user_id = $2 AND status = 'scheduled'
+        RETURNING 
+            id, user_id, template_id, template_revision_id, name, description, subject, status, 
+            scheduled_at, sent_at, recipient_count, sent_count, opened_count, 
+            clicked_count, audience, from_email, from_name, created_at, updated_at
+        "#,
+    )
+    .bind(campaign_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// 送信予定時刻を過ぎたスケジュール済みキャンペーンを送信中として確保
+///
+/// `FOR UPDATE SKIP LOCKED`で行ロックを取得するため、複数のレプリカで実行しても
+/// 同じキャンペーンが二重に確保されることはない。
+pub async fn claim_due_scheduled_campaigns(
+    pool: &PgPool,
+    limit: i64,
+) -> Result<Vec<Campaign>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, Campaign>(
+        r#"
+        UPDATE campaigns 
+        SET 
+            status = 'sending',
+            sent_at = NOW(),
+            updated_at = NOW()
+        WHERE id IN (
+            SELECT id FROM campaigns
+            WHERE status = 'scheduled' AND scheduled_at <= NOW()
+            ORDER BY scheduled_at ASC
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING 
+            id, user_id, template_id, template_revision_id, name, description, subject, status, 
+            scheduled_at, sent_at, recipient_count, sent_count, opened_count, 
+            clicked_count, audience, from_email, from_name, created_at, updated_at
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
 /// キャンペーン送信処理の開始
 pub async fn start_campaign_sending(
     pool: &PgPool,
@@ -460,9 +560,9 @@ pub async fn start_campaign_sending(
     let current_campaign = sqlx::query_as::<_, Campaign>(
         r#"
         SELECT 
-            id, user_id, template_id, name, description, subject, status, 
+            id, user_id, template_id, template_revision_id, name, description, subject, status, 
             scheduled_at, sent_at, recipient_count, sent_count, opened_count, 
-            clicked_count, created_at, updated_at
+            clicked_count, audience, from_email, from_name, created_at, updated_at
         FROM campaigns 
         WHERE id = $1 AND user_id = $2
         FOR UPDATE
@@ -499,9 +599,9 @@ pub async fn start_campaign_sending(
             updated_at = NOW()
         WHERE id = $1 AND user_id = $2
         RETURNING 
-            id, user_id, template_id, name, description, subject, status, 
+            id, user_id, template_id, template_revision_id, name, description, subject, status, 
             scheduled_at, sent_at, recipient_count, sent_count, opened_count, 
-            clicked_count, created_at, updated_at
+            clicked_count, audience, from_email, from_name, created_at, updated_at
         "#,
     )
     .bind(campaign_id)
@@ -515,6 +615,52 @@ pub async fn start_campaign_sending(
     Ok(Some(row))
 }
 
+/// 送信中のキャンペーン一覧を取得（配信再開用）
+pub async fn list_sending_campaigns(pool: &PgPool) -> Result<Vec<Campaign>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, Campaign>(
+        r#"
+        SELECT 
+            id, user_id, template_id, template_revision_id, name, description, subject, status, 
+            scheduled_at, sent_at, recipient_count, sent_count, opened_count, 
+            clicked_count, audience, from_email, from_name, created_at, updated_at
+        FROM campaigns 
+        WHERE status = 'sending'
+        ORDER BY sent_at ASC
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// 失敗した配信の再送のためにキャンペーンを送信中に戻す
+pub async fn resume_campaign_sending(
+    pool: &PgPool,
+    campaign_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<Campaign>, sqlx::Error> {
+    let row = sqlx::query_as::<_, Campaign>(
+        r#"
+        UPDATE campaigns 
+        SET 
+            status = 'sending',
+            updated_at = NOW()
+        WHERE id = $1 AND user_id = $2
+        RETURNING 
+            id, user_id, template_id, template_revision_id, name, description, subject, status, 
+            scheduled_at, sent_at, recipient_count, sent_count, opened_count, 
+            clicked_count, audience, from_email, from_name, created_at, updated_at
+        "#,
+    )
+    .bind(campaign_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row)
+}
+
 /// キャンペーンの送信完了
 #[allow(dead_code)]
 pub async fn complete_campaign_sending(
@@ -529,9 +675,9 @@ pub async fn complete_campaign_sending(
             updated_at = NOW()
         WHERE id = $1 AND status = 'sending'
         RETURNING 
-            id, user_id, template_id, name, description, subject, status, 
+            id, user_id, template_id, template_revision_id, name, description, subject, status, 
             scheduled_at, sent_at, recipient_count, sent_count, opened_count, 
-            clicked_count, created_at, updated_at
+            clicked_count, audience, from_email, from_name, created_at, updated_at
         "#,
     )
     .bind(campaign_id)
@@ -583,7 +729,7 @@ pub async fn update_campaign_stats(
         query_string.push_str("clicked_count = $5");
     }
 
-    query_string.push_str(", updated_at = NOW() WHERE id = $1 RETURNING id, user_id, template_id, name, description, subject, status, scheduled_at, sent_at, recipient_count, sent_count, opened_count, clicked_count, created_at, updated_at");
+    query_string.push_str(", updated_at = NOW() WHERE id = $1 RETURNING id, user_id, template_id, template_revision_id, name, description, subject, status, scheduled_at, sent_at, recipient_count, sent_count, opened_count, clicked_count, audience, from_email, from_name, created_at, updated_at");
 
     // クエリ実行
     let mut query = sqlx::query_as::<_, Campaign>(&query_string).bind(campaign_id);
@@ -608,3 +754,38 @@ pub async fn update_campaign_stats(
 
     Ok(row)
 }
+
+/// 送信ドメインのアドレスを送信元にした送信前のキャンペーン数
+pub async fn count_unsent_campaigns_from_domain(
+    pool: &PgPool,
+    user_id: Uuid,
+    domain: &str,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COUNT(*) FROM campaigns
+        WHERE user_id = $1
+          AND status IN ('draft', 'scheduled', 'sending', 'paused')
+          AND LOWER(SPLIT_PART(from_email, '@', 2)) = $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(domain)
+    .fetch_one(pool)
+    .await
+}
+
+/// 配信するテンプレートのリビジョンを固定
+pub async fn pin_template_revision(
+    pool: &PgPool,
+    campaign_id: Uuid,
+    template_revision_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE campaigns SET template_revision_id = $2, updated_at = NOW() WHERE id = $1")
+        .bind(campaign_id)
+        .bind(template_revision_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
@@ -1,8 +1,12 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::models::form::{CreateFormRequest, Form, FormSubmission, UpdateFormRequest};
+use crate::models::form::{
+    CreateFormRequest, Form, FormSubmission, FormSubmissionStatus, QuarantineReason,
+    UpdateFormRequest,
+};
 
 pub async fn create_form(pool: &PgPool, user_id: Uuid, request: CreateFormRequest) -> Result<Form> {
     let slug = request.slug.unwrap_or_else(|| {
@@ -47,6 +51,46 @@ pub async fn get_form_by_id(pool: &PgPool, form_id: Uuid) -> Result<Option<Form>
     Ok(form)
 }
 
+/// 公開中のフォームをスラッグで取得（同じスラッグが複数ある場合は先に作成されたもの）
+pub async fn get_published_form_by_slug(pool: &PgPool, slug: &str) -> Result<Option<Form>> {
+    let form = sqlx::query_as!(
+        Form,
+        r#"
+        SELECT id, user_id, name, description, slug, markdown_content, form_fields, settings, status, submission_count, created_at, updated_at
+        FROM forms
+        WHERE slug = $1 AND status = 'published'
+        ORDER BY created_at
+        LIMIT 1
+        "#,
+        slug
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(form)
+}
+
+/// 他の公開中のフォームがスラッグを使用しているか
+pub async fn is_slug_published_by_other_form(
+    pool: &PgPool,
+    slug: &str,
+    form_id: Uuid,
+) -> Result<bool> {
+    let exists = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM forms WHERE slug = $1 AND status = 'published' AND id <> $2
+        ) as "exists!"
+        "#,
+        slug,
+        form_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(exists)
+}
+
 pub async fn get_forms_by_user(pool: &PgPool, user_id: Uuid) -> Result<Vec<Form>> {
     let forms = sqlx::query_as!(
         Form,
@@ -107,36 +151,53 @@ pub async fn delete_form(pool: &PgPool, form_id: Uuid) -> Result<()> {
     Ok(())
 }
 
+/// フォーム送信データの登録内容
+pub struct NewFormSubmission {
+    pub data: serde_json::Value,
+    pub subscriber_id: Option<Uuid>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub referrer: Option<String>,
+    /// ダブルオプトインの確認トークン（確認が不要なフォームでは未設定）
+    pub confirmation_token: Option<String>,
+    /// スパムの疑いがある場合の隔離理由（隔離した送信は送信数に含めない）
+    pub quarantine_reason: Option<QuarantineReason>,
+}
+
 pub async fn create_form_submission(
     pool: &PgPool,
     form_id: Uuid,
-    data: serde_json::Value,
-    subscriber_id: Option<Uuid>,
-    ip_address: Option<String>,
-    user_agent: Option<String>,
-    referrer: Option<String>,
+    submission: NewFormSubmission,
 ) -> Result<FormSubmission> {
-    // Generate confirmation token
-    let confirmation_token = uuid::Uuid::new_v4().to_string();
-
-    let submission = sqlx::query_as!(
+    let status = if submission.quarantine_reason.is_some() {
+        FormSubmissionStatus::Quarantined
+    } else {
+        FormSubmissionStatus::Accepted
+    };
+    let created = sqlx::query_as!(
         FormSubmission,
         r#"
-        INSERT INTO form_submissions (form_id, subscriber_id, data, ip_address, user_agent, referrer, confirmation_token)
-        VALUES ($1, $2, $3, $4, $5, $6, $7)
-        RETURNING id, form_id, subscriber_id, data, ip_address, user_agent, referrer, confirmation_token, confirmed_at, created_at
+        INSERT INTO form_submissions (form_id, subscriber_id, data, ip_address, user_agent, referrer, confirmation_token, status, quarantine_reason)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        RETURNING id, form_id, subscriber_id, data, ip_address, user_agent, referrer, confirmation_token, confirmed_at, status, quarantine_reason, created_at
         "#,
         form_id,
-        subscriber_id,
-        data,
-        ip_address,
-        user_agent,
-        referrer,
-        confirmation_token
+        submission.subscriber_id,
+        submission.data,
+        submission.ip_address,
+        submission.user_agent,
+        submission.referrer,
+        submission.confirmation_token,
+        status.as_str(),
+        submission.quarantine_reason.map(|reason| reason.as_str())
     )
     .fetch_one(pool)
     .await?;
 
+    if submission.quarantine_reason.is_some() {
+        return Ok(created);
+    }
+
     // Update submission count
     sqlx::query!(
         r#"
@@ -149,27 +210,86 @@ pub async fn create_form_submission(
     .execute(pool)
     .await?;
 
+    Ok(created)
+}
+
+/// 確認トークンでフォーム送信データを取得
+pub async fn find_form_submission_by_token(
+    pool: &PgPool,
+    confirmation_token: &str,
+) -> Result<Option<FormSubmission>> {
+    let submission = sqlx::query_as!(
+        FormSubmission,
+        r#"
+        SELECT id, form_id, subscriber_id, data, ip_address, user_agent, referrer, confirmation_token, confirmed_at, status, quarantine_reason, created_at
+        FROM form_submissions
+        WHERE confirmation_token = $1
+        "#,
+        confirmation_token
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(submission)
+}
+
+/// フォーム送信データを確認済みにする（確認済みの場合はNone）
+pub async fn confirm_form_submission(
+    pool: &PgPool,
+    submission_id: Uuid,
+) -> Result<Option<FormSubmission>> {
+    let submission = sqlx::query_as!(
+        FormSubmission,
+        r#"
+        UPDATE form_submissions
+        SET confirmed_at = NOW()
+        WHERE id = $1 AND confirmed_at IS NULL
+        RETURNING id, form_id, subscriber_id, data, ip_address, user_agent, referrer, confirmation_token, confirmed_at, status, quarantine_reason, created_at
+        "#,
+        submission_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
     Ok(submission)
 }
 
+/// ダブルオプトインの確認メールのメッセージIDを記録
+pub async fn set_confirmation_message_id(
+    pool: &PgPool,
+    submission_id: Uuid,
+    message_id: &str,
+) -> Result<()> {
+    sqlx::query("UPDATE form_submissions SET confirmation_message_id = $2 WHERE id = $1")
+        .bind(submission_id)
+        .bind(message_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// フォーム送信データ一覧を取得（状態を指定した場合はその状態のみ）
 pub async fn get_form_submissions(
     pool: &PgPool,
     form_id: Uuid,
+    status: Option<FormSubmissionStatus>,
     limit: i64,
     offset: i64,
 ) -> Result<Vec<FormSubmission>> {
     let submissions = sqlx::query_as!(
         FormSubmission,
         r#"
-        SELECT id, form_id, subscriber_id, data, ip_address, user_agent, referrer, confirmation_token, confirmed_at, created_at
+        SELECT id, form_id, subscriber_id, data, ip_address, user_agent, referrer, confirmation_token, confirmed_at, status, quarantine_reason, created_at
         FROM form_submissions
-        WHERE form_id = $1
+        WHERE form_id = $1 AND ($4::text IS NULL OR status = $4)
         ORDER BY created_at DESC
         LIMIT $2 OFFSET $3
         "#,
         form_id,
         limit,
-        offset
+        offset,
+        status.map(|s| s.as_str())
     )
     .fetch_all(pool)
     .await?;
@@ -177,17 +297,50 @@ pub async fn get_form_submissions(
     Ok(submissions)
 }
 
-pub async fn count_form_submissions(pool: &PgPool, form_id: Uuid) -> Result<i64> {
+pub async fn count_form_submissions(
+    pool: &PgPool,
+    form_id: Uuid,
+    status: Option<FormSubmissionStatus>,
+) -> Result<i64> {
     let count = sqlx::query_scalar!(
         r#"
         SELECT COUNT(*) as "count!"
         FROM form_submissions
-        WHERE form_id = $1
+        WHERE form_id = $1 AND ($2::text IS NULL OR status = $2)
         "#,
-        form_id
+        form_id,
+        status.map(|s| s.as_str())
     )
     .fetch_one(pool)
     .await?;
 
     Ok(count)
 }
+
+/// 購読者が指定日時以降にフォーム（未指定の場合はいずれか）を送信したか確認（隔離した送信は含めない）
+pub async fn has_form_submission_since(
+    pool: &PgPool,
+    subscriber_id: Uuid,
+    form_id: Option<Uuid>,
+    since: DateTime<Utc>,
+) -> Result<bool> {
+    let exists = sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM form_submissions
+            WHERE subscriber_id = $1
+              AND ($2::uuid IS NULL OR form_id = $2)
+              AND status = $3
+              AND created_at >= $4
+        )
+        "#,
+    )
+    .bind(subscriber_id)
+    .bind(form_id)
+    .bind(FormSubmissionStatus::Accepted.as_str())
+    .bind(since)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(exists)
+}
@@ -0,0 +1,178 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::github::{GitHubConnection, GitHubFileLocation, GitHubTemplateSource};
+
+const GITHUB_CONNECTION_COLUMNS: &str =
+    "id, user_id, access_token, account_login, webhook_secret, created_at, updated_at";
+
+const GITHUB_TEMPLATE_SOURCE_COLUMNS: &str = "id, user_id, template_id, repository, path, git_ref, auto_sync, last_synced_sha, last_synced_at, created_at, updated_at";
+
+/// GitHub連携を登録（接続済みの場合はトークンを差し替える）
+pub async fn upsert_connection(
+    pool: &PgPool,
+    user_id: Uuid,
+    access_token: &str,
+    account_login: &str,
+    webhook_secret: &str,
+) -> Result<GitHubConnection, sqlx::Error> {
+    sqlx::query_as::<_, GitHubConnection>(&format!(
+        r#"
+        INSERT INTO github_connections (user_id, access_token, account_login, webhook_secret)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (user_id) DO UPDATE
+        SET access_token = EXCLUDED.access_token,
+            account_login = EXCLUDED.account_login
+        RETURNING {GITHUB_CONNECTION_COLUMNS}
+        "#
+    ))
+    .bind(user_id)
+    .bind(access_token)
+    .bind(account_login)
+    .bind(webhook_secret)
+    .fetch_one(pool)
+    .await
+}
+
+/// ユーザーのGitHub連携を取得
+pub async fn find_connection_by_user(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Option<GitHubConnection>, sqlx::Error> {
+    sqlx::query_as::<_, GitHubConnection>(&format!(
+        "SELECT {GITHUB_CONNECTION_COLUMNS} FROM github_connections WHERE user_id = $1"
+    ))
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// GitHub連携を取得（Webhookの受信時）
+pub async fn find_connection_by_id(
+    pool: &PgPool,
+    connection_id: Uuid,
+) -> Result<Option<GitHubConnection>, sqlx::Error> {
+    sqlx::query_as::<_, GitHubConnection>(&format!(
+        "SELECT {GITHUB_CONNECTION_COLUMNS} FROM github_connections WHERE id = $1"
+    ))
+    .bind(connection_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// GitHub連携を解除
+pub async fn delete_connection(pool: &PgPool, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM github_connections WHERE user_id = $1")
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// 暗号化されていないトークン・Webhookの秘密鍵を含むGitHub連携を取得（連携ID・トークン・秘密鍵）
+pub async fn list_plaintext_connection_secrets(
+    pool: &PgPool,
+) -> Result<Vec<(Uuid, String, String)>, sqlx::Error> {
+    sqlx::query_as::<_, (Uuid, String, String)>(
+        r#"
+        SELECT id, access_token, webhook_secret FROM github_connections
+        WHERE access_token NOT LIKE 'enc:%' OR webhook_secret NOT LIKE 'enc:%'
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// GitHub連携のトークン・Webhookの秘密鍵を更新
+pub async fn update_connection_secrets(
+    pool: &PgPool,
+    connection_id: Uuid,
+    access_token: &str,
+    webhook_secret: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE github_connections SET access_token = $2, webhook_secret = $3 WHERE id = $1",
+    )
+    .bind(connection_id)
+    .bind(access_token)
+    .bind(webhook_secret)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// 取り込み元を取得（ファイル指定）
+pub async fn find_template_source(
+    pool: &PgPool,
+    user_id: Uuid,
+    file: &GitHubFileLocation,
+) -> Result<Option<GitHubTemplateSource>, sqlx::Error> {
+    sqlx::query_as::<_, GitHubTemplateSource>(&format!(
+        r#"
+        SELECT {GITHUB_TEMPLATE_SOURCE_COLUMNS} FROM github_template_sources
+        WHERE user_id = $1 AND repository = $2 AND git_ref = $3 AND path = $4
+        "#
+    ))
+    .bind(user_id)
+    .bind(&file.repository)
+    .bind(&file.git_ref)
+    .bind(&file.path)
+    .fetch_optional(pool)
+    .await
+}
+
+/// pushで自動更新する取り込み元を取得
+pub async fn list_auto_sync_sources(
+    pool: &PgPool,
+    user_id: Uuid,
+    repository: &str,
+    git_ref: &str,
+) -> Result<Vec<GitHubTemplateSource>, sqlx::Error> {
+    sqlx::query_as::<_, GitHubTemplateSource>(&format!(
+        r#"
+        SELECT {GITHUB_TEMPLATE_SOURCE_COLUMNS} FROM github_template_sources
+        WHERE user_id = $1 AND repository = $2 AND git_ref = $3 AND auto_sync = true
+        ORDER BY path ASC
+        "#
+    ))
+    .bind(user_id)
+    .bind(repository)
+    .bind(git_ref)
+    .fetch_all(pool)
+    .await
+}
+
+/// 取り込み元を記録（取り込み済みの場合は同期状態を更新）
+pub async fn save_template_source(
+    pool: &PgPool,
+    user_id: Uuid,
+    template_id: Uuid,
+    file: &GitHubFileLocation,
+    auto_sync: bool,
+    sha: &str,
+) -> Result<GitHubTemplateSource, sqlx::Error> {
+    sqlx::query_as::<_, GitHubTemplateSource>(&format!(
+        r#"
+        INSERT INTO github_template_sources (
+            user_id, template_id, repository, path, git_ref, auto_sync, last_synced_sha, last_synced_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, NOW())
+        ON CONFLICT (user_id, repository, git_ref, path) DO UPDATE
+        SET template_id = EXCLUDED.template_id,
+            auto_sync = EXCLUDED.auto_sync,
+            last_synced_sha = EXCLUDED.last_synced_sha,
+            last_synced_at = NOW()
+        RETURNING {GITHUB_TEMPLATE_SOURCE_COLUMNS}
+        "#
+    ))
+    .bind(user_id)
+    .bind(template_id)
+    .bind(&file.repository)
+    .bind(&file.path)
+    .bind(&file.git_ref)
+    .bind(auto_sync)
+    .bind(sha)
+    .fetch_one(pool)
+    .await
+}
@@ -0,0 +1,119 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::markdown_component::{ComponentKind, MarkdownComponent};
+
+const MARKDOWN_COMPONENT_COLUMNS: &str =
+    "id, user_id, name, kind, description, content, created_at, updated_at";
+
+/// コンポーネント・スニペット一覧を取得
+pub async fn list_components(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<MarkdownComponent>, sqlx::Error> {
+    sqlx::query_as::<_, MarkdownComponent>(&format!(
+        "SELECT {MARKDOWN_COMPONENT_COLUMNS} FROM markdown_components WHERE user_id = $1 ORDER BY kind ASC, name ASC"
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// コンポーネント・スニペットを取得（ID指定）
+pub async fn find_component_by_id(
+    pool: &PgPool,
+    component_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<MarkdownComponent>, sqlx::Error> {
+    sqlx::query_as::<_, MarkdownComponent>(&format!(
+        "SELECT {MARKDOWN_COMPONENT_COLUMNS} FROM markdown_components WHERE id = $1 AND user_id = $2"
+    ))
+    .bind(component_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// コンポーネント・スニペットを取得（名前指定）
+pub async fn find_component_by_name(
+    pool: &PgPool,
+    user_id: Uuid,
+    kind: ComponentKind,
+    name: &str,
+) -> Result<Option<MarkdownComponent>, sqlx::Error> {
+    sqlx::query_as::<_, MarkdownComponent>(&format!(
+        "SELECT {MARKDOWN_COMPONENT_COLUMNS} FROM markdown_components WHERE user_id = $1 AND kind = $2 AND name = $3"
+    ))
+    .bind(user_id)
+    .bind(kind.as_str())
+    .bind(name)
+    .fetch_optional(pool)
+    .await
+}
+
+/// コンポーネント・スニペットを登録（同じ名前が登録済みの場合はNone）
+pub async fn create_component(
+    pool: &PgPool,
+    user_id: Uuid,
+    kind: ComponentKind,
+    name: &str,
+    description: Option<&str>,
+    content: &str,
+) -> Result<Option<MarkdownComponent>, sqlx::Error> {
+    sqlx::query_as::<_, MarkdownComponent>(&format!(
+        r#"
+        INSERT INTO markdown_components (user_id, kind, name, description, content)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (user_id, kind, name) DO NOTHING
+        RETURNING {MARKDOWN_COMPONENT_COLUMNS}
+        "#
+    ))
+    .bind(user_id)
+    .bind(kind.as_str())
+    .bind(name)
+    .bind(description)
+    .bind(content)
+    .fetch_optional(pool)
+    .await
+}
+
+/// コンポーネント・スニペットを更新
+pub async fn update_component(
+    pool: &PgPool,
+    component_id: Uuid,
+    user_id: Uuid,
+    name: &str,
+    description: Option<&str>,
+    content: &str,
+) -> Result<Option<MarkdownComponent>, sqlx::Error> {
+    sqlx::query_as::<_, MarkdownComponent>(&format!(
+        r#"
+        UPDATE markdown_components
+        SET name = $3, description = $4, content = $5
+        WHERE id = $1 AND user_id = $2
+        RETURNING {MARKDOWN_COMPONENT_COLUMNS}
+        "#
+    ))
+    .bind(component_id)
+    .bind(user_id)
+    .bind(name)
+    .bind(description)
+    .bind(content)
+    .fetch_optional(pool)
+    .await
+}
+
+/// コンポーネント・スニペットを削除
+pub async fn delete_component(
+    pool: &PgPool,
+    component_id: Uuid,
+    user_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM markdown_components WHERE id = $1 AND user_id = $2")
+        .bind(component_id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -1,11 +1,19 @@
+pub mod ab_tests;
+pub mod campaign_deliveries;
 pub mod campaigns;
 pub mod connection;
 pub mod crm_integrations;
 pub mod forms;
+pub mod github;
+pub mod markdown_components;
 pub mod password_reset;
 pub mod refresh_tokens;
+pub mod segments;
+pub mod sending_domains;
 pub mod sequences;
 pub mod subscribers;
 pub mod subscriptions;
+pub mod template_revisions;
 pub mod templates;
+pub mod tracking;
 pub mod users;
@@ -0,0 +1,120 @@
+use sqlx::{types::Json, PgPool};
+use uuid::Uuid;
+
+use crate::models::segment::{CreateSegmentRequest, Segment, UpdateSegmentRequest};
+
+const SEGMENT_COLUMNS: &str = "id, user_id, name, description, rules, created_at, updated_at";
+
+/// セグメント一覧を取得
+pub async fn list_segments(pool: &PgPool, user_id: Uuid) -> Result<Vec<Segment>, sqlx::Error> {
+    sqlx::query_as::<_, Segment>(&format!(
+        "SELECT {SEGMENT_COLUMNS} FROM segments WHERE user_id = $1 ORDER BY created_at DESC"
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// セグメントを取得（ID指定）
+pub async fn find_segment_by_id(
+    pool: &PgPool,
+    segment_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<Segment>, sqlx::Error> {
+    sqlx::query_as::<_, Segment>(&format!(
+        "SELECT {SEGMENT_COLUMNS} FROM segments WHERE id = $1 AND user_id = $2"
+    ))
+    .bind(segment_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// セグメントを作成
+pub async fn create_segment(
+    pool: &PgPool,
+    user_id: Uuid,
+    request: &CreateSegmentRequest,
+) -> Result<Segment, sqlx::Error> {
+    sqlx::query_as::<_, Segment>(&format!(
+        r#"
+        INSERT INTO segments (user_id, name, description, rules)
+        VALUES ($1, $2, $3, $4)
+        RETURNING {SEGMENT_COLUMNS}
+        "#
+    ))
+    .bind(user_id)
+    .bind(&request.name)
+    .bind(&request.description)
+    .bind(Json(&request.rules))
+    .fetch_one(pool)
+    .await
+}
+
+/// セグメントを更新
+pub async fn update_segment(
+    pool: &PgPool,
+    segment_id: Uuid,
+    user_id: Uuid,
+    request: &UpdateSegmentRequest,
+) -> Result<Option<Segment>, sqlx::Error> {
+    sqlx::query_as::<_, Segment>(&format!(
+        r#"
+        UPDATE segments
+        SET
+            name = COALESCE($3, name),
+            description = COALESCE($4, description),
+            rules = COALESCE($5, rules)
+        WHERE id = $1 AND user_id = $2
+        RETURNING {SEGMENT_COLUMNS}
+        "#
+    ))
+    .bind(segment_id)
+    .bind(user_id)
+    .bind(&request.name)
+    .bind(&request.description)
+    .bind(request.rules.as_ref().map(Json))
+    .fetch_optional(pool)
+    .await
+}
+
+/// セグメントを削除
+pub async fn delete_segment(
+    pool: &PgPool,
+    segment_id: Uuid,
+    user_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM segments WHERE id = $1 AND user_id = $2")
+        .bind(segment_id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// セグメントを参照している未送信のキャンペーン数と有効なシーケンス数を取得
+pub async fn count_segment_references(
+    pool: &PgPool,
+    segment_id: Uuid,
+    user_id: Uuid,
+) -> Result<(i64, i64), sqlx::Error> {
+    sqlx::query_as::<_, (i64, i64)>(
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM campaigns
+             WHERE user_id = $2
+               AND audience ->> 'segment_id' = $1::text
+               AND status IN ('draft', 'scheduled', 'sending', 'paused')),
+            (SELECT COUNT(*) FROM sequences
+             WHERE user_id = $2
+               AND trigger_type = 'segment_entered'
+               AND trigger_config ->> 'segment_id' = $1::text
+               AND status <> 'archived')
+        "#,
+    )
+    .bind(segment_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+}
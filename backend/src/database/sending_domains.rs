@@ -0,0 +1,156 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::sending_domain::{SendingDomain, SendingDomainStatus};
+
+const SENDING_DOMAIN_COLUMNS: &str = "id, user_id, domain, dkim_selector, dkim_private_key, \
+    dkim_public_key, status, dkim_verified, spf_verified, dmarc_verified, last_checked_at, \
+    verified_at, created_at, updated_at";
+
+/// DKIM鍵を生成済みの送信ドメイン
+pub struct NewSendingDomain<'a> {
+    pub domain: &'a str,
+    pub dkim_selector: &'a str,
+    pub dkim_private_key: &'a str,
+    pub dkim_public_key: &'a str,
+}
+
+/// 送信ドメイン一覧を取得
+pub async fn list_sending_domains(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<SendingDomain>, sqlx::Error> {
+    sqlx::query_as::<_, SendingDomain>(&format!(
+        "SELECT {SENDING_DOMAIN_COLUMNS} FROM sending_domains WHERE user_id = $1 ORDER BY domain ASC"
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// 送信ドメインを取得（ID指定）
+pub async fn find_sending_domain_by_id(
+    pool: &PgPool,
+    domain_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<SendingDomain>, sqlx::Error> {
+    sqlx::query_as::<_, SendingDomain>(&format!(
+        "SELECT {SENDING_DOMAIN_COLUMNS} FROM sending_domains WHERE id = $1 AND user_id = $2"
+    ))
+    .bind(domain_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// 送信ドメインを取得（ドメイン名指定）
+pub async fn find_sending_domain_by_name(
+    pool: &PgPool,
+    user_id: Uuid,
+    domain: &str,
+) -> Result<Option<SendingDomain>, sqlx::Error> {
+    sqlx::query_as::<_, SendingDomain>(&format!(
+        "SELECT {SENDING_DOMAIN_COLUMNS} FROM sending_domains WHERE user_id = $1 AND domain = $2"
+    ))
+    .bind(user_id)
+    .bind(domain)
+    .fetch_optional(pool)
+    .await
+}
+
+/// 送信ドメインを登録（同じドメインが登録済みの場合はNone）
+pub async fn create_sending_domain(
+    pool: &PgPool,
+    user_id: Uuid,
+    domain: &NewSendingDomain<'_>,
+) -> Result<Option<SendingDomain>, sqlx::Error> {
+    sqlx::query_as::<_, SendingDomain>(&format!(
+        r#"
+        INSERT INTO sending_domains (
+            user_id, domain, dkim_selector, dkim_private_key, dkim_public_key
+        )
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (user_id, domain) DO NOTHING
+        RETURNING {SENDING_DOMAIN_COLUMNS}
+        "#
+    ))
+    .bind(user_id)
+    .bind(domain.domain)
+    .bind(domain.dkim_selector)
+    .bind(domain.dkim_private_key)
+    .bind(domain.dkim_public_key)
+    .fetch_optional(pool)
+    .await
+}
+
+/// DNSレコードの確認結果を記録
+pub async fn update_verification(
+    pool: &PgPool,
+    domain_id: Uuid,
+    dkim_verified: bool,
+    spf_verified: bool,
+    dmarc_verified: bool,
+    status: SendingDomainStatus,
+) -> Result<SendingDomain, sqlx::Error> {
+    sqlx::query_as::<_, SendingDomain>(&format!(
+        r#"
+        UPDATE sending_domains
+        SET dkim_verified = $2,
+            spf_verified = $3,
+            dmarc_verified = $4,
+            status = $5,
+            last_checked_at = NOW(),
+            verified_at = CASE
+                WHEN $5 = 'verified' THEN COALESCE(verified_at, NOW())
+                ELSE NULL
+            END
+        WHERE id = $1
+        RETURNING {SENDING_DOMAIN_COLUMNS}
+        "#
+    ))
+    .bind(domain_id)
+    .bind(dkim_verified)
+    .bind(spf_verified)
+    .bind(dmarc_verified)
+    .bind(status.as_str())
+    .fetch_one(pool)
+    .await
+}
+
+/// 暗号化されていないDKIM秘密鍵を取得（送信ドメインIDと秘密鍵）
+pub async fn list_plaintext_dkim_keys(pool: &PgPool) -> Result<Vec<(Uuid, String)>, sqlx::Error> {
+    sqlx::query_as::<_, (Uuid, String)>(
+        "SELECT id, dkim_private_key FROM sending_domains WHERE dkim_private_key NOT LIKE 'enc:%'",
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// DKIM秘密鍵を更新
+pub async fn update_dkim_private_key(
+    pool: &PgPool,
+    domain_id: Uuid,
+    dkim_private_key: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE sending_domains SET dkim_private_key = $2 WHERE id = $1")
+        .bind(domain_id)
+        .bind(dkim_private_key)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// 送信ドメインを削除
+pub async fn delete_sending_domain(
+    pool: &PgPool,
+    domain_id: Uuid,
+    user_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM sending_domains WHERE id = $1 AND user_id = $2")
+        .bind(domain_id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -4,11 +4,22 @@ use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::models::sequence::{
-    CreateSequenceEnrollmentRequest, CreateSequenceRequest, CreateSequenceStepRequest, Sequence,
-    SequenceEnrollment, SequenceStep, SequenceStepLog, SequenceStepWithTemplate, SequenceWithSteps,
-    SequenceWithStepsAndTemplates, TriggerType, UpdateSequenceRequest, UpdateSequenceStepRequest,
+    CreateSequenceEnrollmentRequest, CreateSequenceRequest, CreateSequenceStepRequest,
+    DueStepBacklog, Sequence, SequenceEnrollment, SequenceEnrollmentDetail, SequenceStep,
+    SequenceStepLog, SequenceStepWithTemplate, SequenceWithSteps, SequenceWithStepsAndTemplates,
+    TriggerType, UpdateSequenceRequest, UpdateSequenceStepRequest,
 };
 
+const STEP_COLUMNS: &str = r#"
+    id, sequence_id, name, step_order, step_type, delay_value, delay_unit, template_id, subject,
+    conditions, action_config, true_step_id, false_step_id, created_at, updated_at
+"#;
+
+const ENROLLMENT_COLUMNS: &str = r#"
+    id, sequence_id, subscriber_id, current_step_id, status, enrolled_at, completed_at,
+    cancelled_at, next_step_at, metadata, created_at, updated_at
+"#;
+
 pub async fn create_sequence(
     pool: &PgPool,
     user_id: Uuid,
@@ -17,15 +28,18 @@ pub async fn create_sequence(
     let sequence = sqlx::query_as!(
         Sequence,
         r#"
-        INSERT INTO sequences (user_id, name, description, trigger_type, trigger_config, status)
-        VALUES ($1, $2, $3, $4, $5, 'draft')
-        RETURNING id, user_id, name, description, trigger_type, trigger_config, status, active_subscribers, completed_subscribers, created_at, updated_at
+        INSERT INTO sequences (user_id, name, description, trigger_type, trigger_config, status, reentry_policy, exit_conditions, send_window)
+        VALUES ($1, $2, $3, $4, $5, 'draft', COALESCE($6, 'never'), COALESCE($7, '[]'::jsonb), COALESCE($8, '{}'::jsonb))
+        RETURNING id, user_id, name, description, trigger_type, trigger_config, status, active_subscribers, completed_subscribers, reentry_policy, exit_conditions, send_window, created_at, updated_at
         "#,
         user_id,
         request.name,
         request.description,
         request.trigger_type,
-        request.trigger_config.unwrap_or(serde_json::json!({}))
+        request.trigger_config.unwrap_or(serde_json::json!({})),
+        request.reentry_policy,
+        request.exit_conditions,
+        request.send_window
     )
     .fetch_one(pool)
     .await?;
@@ -37,7 +51,7 @@ pub async fn get_sequence_by_id(pool: &PgPool, sequence_id: Uuid) -> Result<Opti
     let sequence = sqlx::query_as!(
         Sequence,
         r#"
-        SELECT id, user_id, name, description, trigger_type, trigger_config, status, active_subscribers, completed_subscribers, created_at, updated_at
+        SELECT id, user_id, name, description, trigger_type, trigger_config, status, active_subscribers, completed_subscribers, reentry_policy, exit_conditions, send_window, created_at, updated_at
         FROM sequences
         WHERE id = $1
         "#,
@@ -53,7 +67,7 @@ pub async fn get_sequences_by_user(pool: &PgPool, user_id: Uuid) -> Result<Vec<S
     let sequences = sqlx::query_as!(
         Sequence,
         r#"
-        SELECT id, user_id, name, description, trigger_type, trigger_config, status, active_subscribers, completed_subscribers, created_at, updated_at
+        SELECT id, user_id, name, description, trigger_type, trigger_config, status, active_subscribers, completed_subscribers, reentry_policy, exit_conditions, send_window, created_at, updated_at
         FROM sequences
         WHERE user_id = $1
         ORDER BY created_at DESC
@@ -80,16 +94,22 @@ pub async fn update_sequence(
             trigger_type = COALESCE($4, trigger_type),
             trigger_config = COALESCE($5, trigger_config),
             status = COALESCE($6, status),
+            reentry_policy = COALESCE($7, reentry_policy),
+            exit_conditions = COALESCE($8, exit_conditions),
+            send_window = COALESCE($9, send_window),
             updated_at = NOW()
         WHERE id = $1
-        RETURNING id, user_id, name, description, trigger_type, trigger_config, status, active_subscribers, completed_subscribers, created_at, updated_at
+        RETURNING id, user_id, name, description, trigger_type, trigger_config, status, active_subscribers, completed_subscribers, reentry_policy, exit_conditions, send_window, created_at, updated_at
         "#,
         sequence_id,
         request.name,
         request.description,
         request.trigger_type,
         request.trigger_config,
-        request.status
+        request.status,
+        request.reentry_policy,
+        request.exit_conditions,
+        request.send_window
     )
     .fetch_one(pool)
     .await?;
@@ -197,24 +217,28 @@ pub async fn create_sequence_step(
     sequence_id: Uuid,
     request: CreateSequenceStepRequest,
 ) -> Result<SequenceStep> {
-    let step = sqlx::query_as!(
-        SequenceStep,
+    let step = sqlx::query_as::<_, SequenceStep>(&format!(
         r#"
-        INSERT INTO sequence_steps (sequence_id, name, step_order, step_type, delay_value, delay_unit, template_id, subject, conditions, action_config)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
-        RETURNING id, sequence_id, name, step_order, step_type, delay_value, delay_unit, template_id, subject, conditions, action_config, created_at, updated_at
-        "#,
-        sequence_id,
-        request.name,
-        request.step_order,
-        request.step_type,
-        request.delay_value.unwrap_or(0),
-        request.delay_unit.unwrap_or("hours".to_string()),
-        request.template_id,
-        request.subject,
-        request.conditions.unwrap_or(serde_json::json!({})),
-        request.action_config.unwrap_or(serde_json::json!({}))
-    )
+        INSERT INTO sequence_steps (
+            sequence_id, name, step_order, step_type, delay_value, delay_unit, template_id,
+            subject, conditions, action_config, true_step_id, false_step_id
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        RETURNING {STEP_COLUMNS}
+        "#
+    ))
+    .bind(sequence_id)
+    .bind(request.name)
+    .bind(request.step_order)
+    .bind(request.step_type)
+    .bind(request.delay_value.unwrap_or(0))
+    .bind(request.delay_unit.unwrap_or("hours".to_string()))
+    .bind(request.template_id)
+    .bind(request.subject)
+    .bind(request.conditions.unwrap_or(serde_json::json!({})))
+    .bind(request.action_config.unwrap_or(serde_json::json!({})))
+    .bind(request.true_step_id)
+    .bind(request.false_step_id)
     .fetch_one(pool)
     .await?;
 
@@ -222,16 +246,15 @@ pub async fn create_sequence_step(
 }
 
 pub async fn get_sequence_steps(pool: &PgPool, sequence_id: Uuid) -> Result<Vec<SequenceStep>> {
-    let steps = sqlx::query_as!(
-        SequenceStep,
+    let steps = sqlx::query_as::<_, SequenceStep>(&format!(
         r#"
-        SELECT id, sequence_id, name, step_order, step_type, delay_value, delay_unit, template_id, subject, conditions, action_config, created_at, updated_at
+        SELECT {STEP_COLUMNS}
         FROM sequence_steps
         WHERE sequence_id = $1
         ORDER BY step_order ASC
-        "#,
-        sequence_id
-    )
+        "#
+    ))
+    .bind(sequence_id)
     .fetch_all(pool)
     .await?;
 
@@ -243,8 +266,7 @@ pub async fn update_sequence_step(
     step_id: Uuid,
     request: UpdateSequenceStepRequest,
 ) -> Result<SequenceStep> {
-    let step = sqlx::query_as!(
-        SequenceStep,
+    let step = sqlx::query_as::<_, SequenceStep>(&format!(
         r#"
         UPDATE sequence_steps
         SET name = COALESCE($2, name),
@@ -256,21 +278,27 @@ pub async fn update_sequence_step(
             subject = COALESCE($8, subject),
             conditions = COALESCE($9, conditions),
             action_config = COALESCE($10, action_config),
+            true_step_id = CASE WHEN $11 THEN $12 ELSE true_step_id END,
+            false_step_id = CASE WHEN $13 THEN $14 ELSE false_step_id END,
             updated_at = NOW()
         WHERE id = $1
-        RETURNING id, sequence_id, name, step_order, step_type, delay_value, delay_unit, template_id, subject, conditions, action_config, created_at, updated_at
-        "#,
-        step_id,
-        request.name,
-        request.step_order,
-        request.step_type,
-        request.delay_value,
-        request.delay_unit,
-        request.template_id,
-        request.subject,
-        request.conditions,
-        request.action_config
-    )
+        RETURNING {STEP_COLUMNS}
+        "#
+    ))
+    .bind(step_id)
+    .bind(request.name)
+    .bind(request.step_order)
+    .bind(request.step_type)
+    .bind(request.delay_value)
+    .bind(request.delay_unit)
+    .bind(request.template_id)
+    .bind(request.subject)
+    .bind(request.conditions)
+    .bind(request.action_config)
+    .bind(request.true_step_id.is_some())
+    .bind(request.true_step_id.flatten())
+    .bind(request.false_step_id.is_some())
+    .bind(request.false_step_id.flatten())
     .fetch_one(pool)
     .await?;
 
@@ -313,7 +341,7 @@ pub async fn get_active_sequences_by_trigger(
     let sequences = sqlx::query_as!(
         Sequence,
         r#"
-        SELECT id, user_id, name, description, trigger_type, trigger_config, status, active_subscribers, completed_subscribers, created_at, updated_at
+        SELECT id, user_id, name, description, trigger_type, trigger_config, status, active_subscribers, completed_subscribers, reentry_policy, exit_conditions, send_window, created_at, updated_at
         FROM sequences
         WHERE trigger_type = $1 AND status = 'active'
         "#,
@@ -414,7 +442,7 @@ pub async fn find_active_sequences_by_trigger(
     let sequences = sqlx::query_as!(
         Sequence,
         r#"
-        SELECT id, user_id, name, description, trigger_type, trigger_config, status, active_subscribers, completed_subscribers, created_at, updated_at
+        SELECT id, user_id, name, description, trigger_type, trigger_config, status, active_subscribers, completed_subscribers, reentry_policy, exit_conditions, send_window, created_at, updated_at
         FROM sequences
         WHERE user_id = $1 AND trigger_type = $2 AND status = 'active'
         "#,
@@ -427,6 +455,26 @@ pub async fn find_active_sequences_by_trigger(
     Ok(sequences)
 }
 
+/// 全ユーザーの該当するトリガータイプのアクティブなシーケンスを取得
+pub async fn find_all_active_sequences_by_trigger(
+    pool: &PgPool,
+    trigger_type: TriggerType,
+) -> Result<Vec<Sequence>> {
+    let sequences = sqlx::query_as::<_, Sequence>(
+        r#"
+        SELECT id, user_id, name, description, trigger_type, trigger_config, status, active_subscribers, completed_subscribers, reentry_policy, exit_conditions, send_window, created_at, updated_at
+        FROM sequences
+        WHERE trigger_type = $1 AND status = 'active'
+        ORDER BY created_at ASC
+        "#,
+    )
+    .bind(trigger_type.as_str())
+    .fetch_all(pool)
+    .await?;
+
+    Ok(sequences)
+}
+
 pub async fn find_sequence_by_id(
     pool: &PgPool,
     sequence_id: Uuid,
@@ -435,7 +483,7 @@ pub async fn find_sequence_by_id(
     let sequence = sqlx::query_as!(
         Sequence,
         r#"
-        SELECT id, user_id, name, description, trigger_type, trigger_config, status, active_subscribers, completed_subscribers, created_at, updated_at
+        SELECT id, user_id, name, description, trigger_type, trigger_config, status, active_subscribers, completed_subscribers, reentry_policy, exit_conditions, send_window, created_at, updated_at
         FROM sequences
         WHERE id = $1
         "#,
@@ -451,87 +499,187 @@ pub async fn find_sequence_steps(pool: &PgPool, sequence_id: Uuid) -> Result<Vec
     get_sequence_steps(pool, sequence_id).await
 }
 
-pub async fn find_pending_sequence_enrollments(pool: &PgPool) -> Result<Vec<SequenceEnrollment>> {
-    let enrollments = sqlx::query_as!(
-        SequenceEnrollment,
+/// 実行日時を過ぎたエンロールメントをステップの実行のために確保
+///
+/// `FOR UPDATE SKIP LOCKED`で行ロックを取得し`locked_at`を記録するため、複数のレプリカで実行しても
+/// 同じエンロールメントが二重に確保されることはない。`stale_after_seconds`以上前に確保されたまま
+/// 解放されていないもの（処理中にプロセスが停止したもの）は再度確保する。
+/// 1回の確保でユーザーごとに`per_user_limit`件までとし、実行日時の古い順にユーザー間で交互に割り当てる。
+/// 実行日時が`due_before`（実行の開始日時）より後のものは確保しないため、同じ実行の中で
+/// 実行したステップの次のステップや、失敗して延期したステップを繰り返し確保することはない。
+pub async fn claim_due_sequence_enrollments(
+    pool: &PgPool,
+    limit: i64,
+    per_user_limit: i64,
+    stale_after_seconds: i64,
+    due_before: DateTime<Utc>,
+) -> Result<Vec<SequenceEnrollment>> {
+    let query = format!(
         r#"
-        SELECT id, sequence_id, subscriber_id, current_step_id, status, enrolled_at, completed_at, cancelled_at, next_step_at, metadata, created_at, updated_at
-        FROM sequence_enrollments
-        WHERE status = 'active' 
-        AND (next_step_at IS NULL OR next_step_at <= NOW())
+        UPDATE sequence_enrollments
+        SET locked_at = NOW()
+        WHERE id IN (
+            SELECT e.id
+            FROM sequence_enrollments e
+            JOIN (
+                SELECT
+                    e.id,
+                    COALESCE(e.next_step_at, e.enrolled_at) AS due_at,
+                    ROW_NUMBER() OVER (
+                        PARTITION BY s.user_id
+                        ORDER BY COALESCE(e.next_step_at, e.enrolled_at)
+                    ) AS user_rank
+                FROM sequence_enrollments e
+                JOIN sequences s ON s.id = e.sequence_id
+                WHERE e.status = 'active'
+                  AND COALESCE(e.next_step_at, e.enrolled_at) <= LEAST(NOW(), $4)
+                  AND (e.locked_at IS NULL OR e.locked_at < NOW() - make_interval(secs => $3))
+            ) due ON due.id = e.id
+            WHERE due.user_rank <= $2
+              AND e.status = 'active'
+              AND (e.locked_at IS NULL OR e.locked_at < NOW() - make_interval(secs => $3))
+            ORDER BY due.user_rank, due.due_at
+            LIMIT $1
+            FOR UPDATE OF e SKIP LOCKED
+        )
+        RETURNING {ENROLLMENT_COLUMNS}
         "#
-    )
-    .fetch_all(pool)
-    .await?;
+    );
+
+    let enrollments = sqlx::query_as::<_, SequenceEnrollment>(&query)
+        .bind(limit)
+        .bind(per_user_limit)
+        .bind(stale_after_seconds as f64)
+        .bind(due_before)
+        .fetch_all(pool)
+        .await?;
 
     Ok(enrollments)
 }
 
-pub async fn complete_sequence_enrollment(pool: &PgPool, enrollment_id: Uuid) -> Result<()> {
-    update_sequence_enrollment_status(pool, enrollment_id, "completed", None).await
+/// ステップの実行後にエンロールメントの確保を解放
+pub async fn release_sequence_enrollment(pool: &PgPool, enrollment_id: Uuid) -> Result<()> {
+    sqlx::query("UPDATE sequence_enrollments SET locked_at = NULL WHERE id = $1")
+        .bind(enrollment_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
 }
 
-pub async fn update_enrollment_progress(
+/// 失敗したステップの再実行日時を設定
+pub async fn defer_enrollment_step(
     pool: &PgPool,
     enrollment_id: Uuid,
-    current_step_order: i32,
+    retry_at: DateTime<Utc>,
 ) -> Result<()> {
-    // 現在のステップIDを取得
-    let step = sqlx::query!(
+    sqlx::query(
+        "UPDATE sequence_enrollments SET next_step_at = $2, updated_at = NOW() WHERE id = $1",
+    )
+    .bind(enrollment_id)
+    .bind(retry_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// ステップの失敗回数（最後に成功・スキップして以降の失敗の記録数）
+pub async fn count_failed_step_executions(
+    pool: &PgPool,
+    enrollment_id: Uuid,
+    step_id: Uuid,
+) -> Result<i64> {
+    let count = sqlx::query_scalar::<_, i64>(
         r#"
-        SELECT s.id
-        FROM sequence_steps s
-        JOIN sequence_enrollments e ON e.sequence_id = s.sequence_id
-        WHERE e.id = $1 AND s.step_order = $2
+        SELECT COUNT(*)
+        FROM sequence_step_logs
+        WHERE enrollment_id = $1
+          AND step_id = $2
+          AND status = 'failed'
+          AND executed_at > COALESCE(
+              (SELECT MAX(executed_at) FROM sequence_step_logs
+               WHERE enrollment_id = $1 AND step_id = $2 AND status <> 'failed'),
+              '-infinity'
+          )
         "#,
-        enrollment_id,
-        current_step_order
     )
-    .fetch_optional(pool)
+    .bind(enrollment_id)
+    .bind(step_id)
+    .fetch_one(pool)
     .await?;
 
-    let current_step_id = step.map(|s| s.id);
+    Ok(count)
+}
 
-    sqlx::query!(
+/// 実行日時を過ぎて未処理のエンロールメントの件数と、最も古い実行日時
+pub async fn find_due_step_backlog(pool: &PgPool) -> Result<DueStepBacklog> {
+    let backlog = sqlx::query_as::<_, DueStepBacklog>(
+        r#"
+        SELECT
+            COUNT(*) AS due_count,
+            MIN(COALESCE(next_step_at, enrolled_at)) AS oldest_due_at
+        FROM sequence_enrollments
+        WHERE status = 'active'
+          AND (next_step_at IS NULL OR next_step_at <= NOW())
+        "#,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(backlog)
+}
+
+/// エンロールメントを完了にする（ステップの実行中に終了条件で終了した場合は変更しない）
+pub async fn complete_sequence_enrollment(pool: &PgPool, enrollment_id: Uuid) -> Result<()> {
+    sqlx::query(
         r#"
         UPDATE sequence_enrollments
-        SET current_step_id = $2,
-            next_step_at = NULL,
+        SET status = 'completed',
+            current_step_id = NULL,
+            completed_at = NOW(),
             updated_at = NOW()
-        WHERE id = $1
+        WHERE id = $1 AND status IN ('active', 'paused')
         "#,
-        enrollment_id,
-        current_step_id
     )
+    .bind(enrollment_id)
     .execute(pool)
     .await?;
 
     Ok(())
 }
 
-pub async fn schedule_next_enrollment_step(
+/// 次に実行するステップを更新（すぐに実行する）
+pub async fn update_enrollment_progress(
     pool: &PgPool,
     enrollment_id: Uuid,
-    next_step_order: i32,
-    next_execution_at: DateTime<Utc>,
+    next_step_id: Uuid,
 ) -> Result<()> {
-    // 次のステップIDを取得
-    let step = sqlx::query!(
+    sqlx::query(
         r#"
-        SELECT s.id
-        FROM sequence_steps s
-        JOIN sequence_enrollments e ON e.sequence_id = s.sequence_id
-        WHERE e.id = $1 AND s.step_order = $2
+        UPDATE sequence_enrollments
+        SET current_step_id = $2,
+            next_step_at = NOW(),
+            updated_at = NOW()
+        WHERE id = $1
         "#,
-        enrollment_id,
-        next_step_order
     )
-    .fetch_optional(pool)
+    .bind(enrollment_id)
+    .bind(next_step_id)
+    .execute(pool)
     .await?;
 
-    let next_step_id = step.map(|s| s.id);
+    Ok(())
+}
 
-    sqlx::query!(
+/// 次に実行するステップと実行日時を更新
+pub async fn schedule_next_enrollment_step(
+    pool: &PgPool,
+    enrollment_id: Uuid,
+    next_step_id: Uuid,
+    next_execution_at: DateTime<Utc>,
+) -> Result<()> {
+    sqlx::query(
         r#"
         UPDATE sequence_enrollments
         SET current_step_id = $2,
@@ -539,10 +687,10 @@ pub async fn schedule_next_enrollment_step(
             updated_at = NOW()
         WHERE id = $1
         "#,
-        enrollment_id,
-        next_step_id,
-        next_execution_at
     )
+    .bind(enrollment_id)
+    .bind(next_step_id)
+    .bind(next_execution_at)
     .execute(pool)
     .await?;
 
@@ -555,25 +703,207 @@ pub async fn create_sequence_step_log(
     step_id: Uuid,
     status: &str,
     error_message: Option<String>,
+    template_revision_id: Option<Uuid>,
+    message_id: Option<&str>,
 ) -> Result<SequenceStepLog> {
-    let log = sqlx::query_as!(
-        SequenceStepLog,
+    let log = sqlx::query_as::<_, SequenceStepLog>(
         r#"
-        INSERT INTO sequence_step_logs (enrollment_id, step_id, status, error_message)
-        VALUES ($1, $2, $3, $4)
-        RETURNING id, enrollment_id, step_id, status, error_message, executed_at
+        INSERT INTO sequence_step_logs (enrollment_id, step_id, status, error_message, template_revision_id, message_id)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, enrollment_id, step_id, status, error_message, template_revision_id, message_id, executed_at
         "#,
-        enrollment_id,
-        step_id,
-        status,
-        error_message
     )
+    .bind(enrollment_id)
+    .bind(step_id)
+    .bind(status)
+    .bind(error_message)
+    .bind(template_revision_id)
+    .bind(message_id)
     .fetch_one(pool)
     .await?;
 
     Ok(log)
 }
 
+/// 購読者ごとの、再登録の判定に使うエンロールメントのステータス
+///
+/// 実行中（active・paused）のエンロールメントがあればそのステータス、なければ最新のエンロールメントのステータス。
+/// 登録されたことのない購読者は含まれない。
+pub async fn find_enrollment_statuses(
+    pool: &PgPool,
+    sequence_id: Uuid,
+    subscriber_ids: &[Uuid],
+) -> Result<Vec<(Uuid, String)>> {
+    let statuses = sqlx::query_as::<_, (Uuid, String)>(
+        r#"
+        SELECT DISTINCT ON (subscriber_id) subscriber_id, status
+        FROM sequence_enrollments
+        WHERE sequence_id = $1 AND subscriber_id = ANY($2)
+        ORDER BY subscriber_id, (status IN ('active', 'paused')) DESC, enrolled_at DESC
+        "#,
+    )
+    .bind(sequence_id)
+    .bind(subscriber_ids)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(statuses)
+}
+
+/// 購読者をまとめて登録し、登録した購読者のIDを返す（実行中のエンロールメントがある購読者は登録しない）
+pub async fn create_sequence_enrollments(
+    pool: &PgPool,
+    sequence_id: Uuid,
+    subscriber_ids: &[Uuid],
+    metadata: &serde_json::Value,
+) -> Result<Vec<Uuid>> {
+    let enrolled = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO sequence_enrollments (sequence_id, subscriber_id, status, metadata, current_step_id)
+        SELECT
+            $1, subscriber_id, 'active', $3,
+            (SELECT id FROM sequence_steps WHERE sequence_id = $1 ORDER BY step_order ASC LIMIT 1)
+        FROM UNNEST($2::uuid[]) AS subscriber_id
+        ON CONFLICT DO NOTHING
+        RETURNING subscriber_id
+        "#,
+    )
+    .bind(sequence_id)
+    .bind(subscriber_ids)
+    .bind(metadata)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(enrolled)
+}
+
+/// シーケンスのエンロールメント一覧（登録日時の新しい順）
+pub async fn list_sequence_enrollments(
+    pool: &PgPool,
+    sequence_id: Uuid,
+    status: Option<&str>,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<SequenceEnrollmentDetail>> {
+    let enrollments = sqlx::query_as::<_, SequenceEnrollmentDetail>(
+        r#"
+        SELECT
+            e.id, e.sequence_id, e.subscriber_id, e.current_step_id, e.status, e.enrolled_at,
+            e.completed_at, e.cancelled_at, e.next_step_at, e.metadata, e.created_at, e.updated_at,
+            s.email AS subscriber_email,
+            s.name AS subscriber_name,
+            st.name AS current_step_name,
+            st.step_order AS current_step_order
+        FROM sequence_enrollments e
+        JOIN subscribers s ON s.id = e.subscriber_id
+        LEFT JOIN sequence_steps st ON st.id = e.current_step_id
+        WHERE e.sequence_id = $1 AND ($2::text IS NULL OR e.status = $2)
+        ORDER BY e.enrolled_at DESC, e.id
+        LIMIT $3 OFFSET $4
+        "#,
+    )
+    .bind(sequence_id)
+    .bind(status)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(enrollments)
+}
+
+/// ステータスごとのエンロールメント数
+pub async fn count_enrollments_by_status(
+    pool: &PgPool,
+    sequence_id: Uuid,
+) -> Result<Vec<(String, i64)>> {
+    let counts = sqlx::query_as::<_, (String, i64)>(
+        r#"
+        SELECT status, COUNT(*)
+        FROM sequence_enrollments
+        WHERE sequence_id = $1
+        GROUP BY status
+        ORDER BY status
+        "#,
+    )
+    .bind(sequence_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(counts)
+}
+
+pub async fn find_sequence_enrollment_by_id(
+    pool: &PgPool,
+    sequence_id: Uuid,
+    enrollment_id: Uuid,
+) -> Result<Option<SequenceEnrollment>> {
+    let enrollment = sqlx::query_as::<_, SequenceEnrollment>(&format!(
+        "SELECT {ENROLLMENT_COLUMNS} FROM sequence_enrollments WHERE id = $1 AND sequence_id = $2"
+    ))
+    .bind(enrollment_id)
+    .bind(sequence_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(enrollment)
+}
+
+/// ステータスが`from`のいずれかの場合のみステータスを変更
+/// （キャンセル時は`cancelled_at`、終了条件による終了時は`completed_at`を記録）
+///
+/// 変更できなかった場合はNoneを返す。
+pub async fn transition_enrollment_status(
+    pool: &PgPool,
+    enrollment_id: Uuid,
+    from: &[&str],
+    status: &str,
+) -> Result<Option<SequenceEnrollment>> {
+    let enrollment = sqlx::query_as::<_, SequenceEnrollment>(&format!(
+        r#"
+        UPDATE sequence_enrollments
+        SET status = $3,
+            cancelled_at = CASE WHEN $3 = 'cancelled' THEN NOW() ELSE cancelled_at END,
+            completed_at = CASE WHEN $3 IN ('goal_reached', 'exited') THEN NOW() ELSE completed_at END,
+            updated_at = NOW()
+        WHERE id = $1 AND status = ANY($2)
+        RETURNING {ENROLLMENT_COLUMNS}
+        "#
+    ))
+    .bind(enrollment_id)
+    .bind(from)
+    .bind(status)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(enrollment)
+}
+
+/// 購読者の実行中（active・paused）のエンロールメントのうち、シーケンスに終了条件があるもの
+pub async fn find_enrollments_with_exit_conditions(
+    pool: &PgPool,
+    subscriber_id: Uuid,
+) -> Result<Vec<SequenceEnrollment>> {
+    let enrollments = sqlx::query_as::<_, SequenceEnrollment>(
+        r#"
+        SELECT
+            e.id, e.sequence_id, e.subscriber_id, e.current_step_id, e.status, e.enrolled_at,
+            e.completed_at, e.cancelled_at, e.next_step_at, e.metadata, e.created_at, e.updated_at
+        FROM sequence_enrollments e
+        JOIN sequences s ON s.id = e.sequence_id
+        WHERE e.subscriber_id = $1
+          AND e.status IN ('active', 'paused')
+          AND s.exit_conditions <> '[]'::jsonb
+        ORDER BY e.enrolled_at
+        "#,
+    )
+    .bind(subscriber_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(enrollments)
+}
+
 // create_sequence_enrollment関数を更新
 pub async fn create_sequence_enrollment(
     pool: &PgPool,
@@ -588,8 +918,11 @@ pub async fn create_sequence_enrollment(
     let enrollment = sqlx::query_as!(
         SequenceEnrollment,
         r#"
-        INSERT INTO sequence_enrollments (sequence_id, subscriber_id, status, metadata)
-        VALUES ($1, $2, 'active', $3)
+        INSERT INTO sequence_enrollments (sequence_id, subscriber_id, status, metadata, current_step_id)
+        VALUES (
+            $1, $2, 'active', $3,
+            (SELECT id FROM sequence_steps WHERE sequence_id = $1 ORDER BY step_order ASC LIMIT 1)
+        )
         RETURNING id, sequence_id, subscriber_id, current_step_id, status, enrolled_at, completed_at, cancelled_at, next_step_at, metadata, created_at, updated_at
         "#,
         sequence_id,
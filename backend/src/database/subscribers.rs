@@ -1,12 +1,22 @@
 use serde_json::Value;
-use sqlx::PgPool;
+use sqlx::{PgPool, Postgres, QueryBuilder};
 use uuid::Uuid;
 
-use crate::models::subscriber::{
-    CreateSubscriberRequest, ListSubscriberOptions, Subscriber, SubscriberStatus,
-    UpdateSubscriberRequest,
+use crate::database::segments;
+use crate::models::{
+    audience::{AudienceDefinition, ConditionMatch, ConditionOperator, CustomFieldCondition},
+    segment::SegmentRule,
+    subscriber::{
+        CreateSubscriberRequest, ListSubscriberOptions, Subscriber, SubscriberStatus,
+        UpdateSubscriberRequest,
+    },
 };
 
+const SUBSCRIBER_COLUMNS: &str = r#"
+    id, user_id, email, name, status, tags, custom_fields,
+    subscribed_at, unsubscribed_at, created_at, updated_at
+"#;
+
 /// 購読者一覧を取得（オプション指定版）
 pub async fn list_user_subscribers(
     pool: &PgPool,
@@ -18,6 +28,7 @@ pub async fn list_user_subscribers(
         "unsubscribed" => Some(SubscriberStatus::Unsubscribed),
         "bounced" => Some(SubscriberStatus::Bounced),
         "complained" => Some(SubscriberStatus::Complained),
+        "pending" => Some(SubscriberStatus::Pending),
         _ => None,
     });
 
@@ -67,7 +78,7 @@ pub async fn list_subscribers(
 
     // フィルタリング条件を追加
     if let Some(status_filter) = status {
-        query_string.push_str(&format!("AND status = '{status_filter:?}' "));
+        query_string.push_str(&format!("AND status = '{}' ", status_filter.as_str()));
     }
 
     // タグフィルタリング
@@ -109,7 +120,7 @@ pub async fn count_subscribers(
 
     // フィルタリング条件を追加
     if let Some(status_filter) = status {
-        query_string.push_str(&format!("AND status = '{status_filter:?}' "));
+        query_string.push_str(&format!("AND status = '{}' ", status_filter.as_str()));
     }
 
     // タグフィルタリング
@@ -133,6 +144,481 @@ pub async fn count_subscribers(
     Ok(count)
 }
 
+/// オーディエンス定義に一致する購読者を取得
+///
+/// 配信停止・バウンス・苦情の購読者は定義にかかわらず含まれない。
+pub async fn list_audience_subscribers(
+    pool: &PgPool,
+    user_id: Uuid,
+    audience: &AudienceDefinition,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<Subscriber>, sqlx::Error> {
+    let segment = resolve_audience_segment(pool, user_id, audience).await?;
+
+    let mut builder = QueryBuilder::<Postgres>::new(format!(
+        "SELECT {SUBSCRIBER_COLUMNS} FROM subscribers WHERE "
+    ));
+    push_audience_filter(&mut builder, user_id, audience, segment.as_ref());
+    builder.push(" ORDER BY created_at ASC, id ASC");
+
+    if let Some(limit) = limit {
+        builder.push(" LIMIT ").push_bind(limit);
+    }
+    if let Some(offset) = offset {
+        builder.push(" OFFSET ").push_bind(offset);
+    }
+
+    builder.build_query_as::<Subscriber>().fetch_all(pool).await
+}
+
+/// オーディエンス定義に一致する購読者数を取得
+pub async fn count_audience_subscribers(
+    pool: &PgPool,
+    user_id: Uuid,
+    audience: &AudienceDefinition,
+) -> Result<i64, sqlx::Error> {
+    let segment = resolve_audience_segment(pool, user_id, audience).await?;
+
+    let mut builder = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM subscribers WHERE ");
+    push_audience_filter(&mut builder, user_id, audience, segment.as_ref());
+
+    builder.build_query_scalar::<i64>().fetch_one(pool).await
+}
+
+/// オーディエンス定義が参照するセグメントの条件ツリーを取得
+async fn resolve_audience_segment(
+    pool: &PgPool,
+    user_id: Uuid,
+    audience: &AudienceDefinition,
+) -> Result<Option<SegmentRule>, sqlx::Error> {
+    let Some(segment_id) = audience.segment_id else {
+        return Ok(None);
+    };
+
+    let segment = segments::find_segment_by_id(pool, segment_id, user_id).await?;
+    Ok(segment.map(|segment| segment.rules.0))
+}
+
+/// オーディエンス定義をWHERE句の条件に変換（値はすべてバインドパラメータで渡す）
+///
+/// `segment`には`audience.segment_id`が参照するセグメントの条件ツリーを渡す。
+/// セグメントが見つからない場合（`None`）は誰にも一致しない。
+pub fn push_audience_filter(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    user_id: Uuid,
+    audience: &AudienceDefinition,
+    segment: Option<&SegmentRule>,
+) {
+    builder.push("user_id = ").push_bind(user_id);
+
+    // 送信可能なステータスのみ（指定がある場合はさらに絞り込む）
+    let statuses: Vec<String> = if audience.statuses.is_empty() {
+        SubscriberStatus::SENDABLE
+    } else {
+        &audience.statuses
+    }
+    .iter()
+    .filter(|s| s.is_sendable())
+    .map(|s| s.as_str().to_string())
+    .collect();
+    builder
+        .push(" AND status::text = ANY(")
+        .push_bind(statuses)
+        .push(")");
+
+    if !audience.include_tags.is_empty() {
+        builder
+            .push(" AND tags && ")
+            .push_bind(audience.include_tags.clone())
+            .push("::text[]");
+    }
+
+    if !audience.exclude_tags.is_empty() {
+        builder
+            .push(" AND NOT (tags && ")
+            .push_bind(audience.exclude_tags.clone())
+            .push("::text[])");
+    }
+
+    if !audience.conditions.is_empty() {
+        let separator = match audience.condition_match {
+            ConditionMatch::All => " AND ",
+            ConditionMatch::Any => " OR ",
+        };
+
+        builder.push(" AND (");
+        for (i, condition) in audience.conditions.iter().enumerate() {
+            if i > 0 {
+                builder.push(separator);
+            }
+            push_custom_field_condition(builder, condition);
+        }
+        builder.push(")");
+    }
+
+    if audience.segment_id.is_some() {
+        builder.push(" AND ");
+        match segment {
+            Some(rule) => push_segment_rule(builder, rule),
+            None => {
+                builder.push("FALSE");
+            }
+        }
+    }
+}
+
+/// セグメントの条件ツリーに一致する購読者を取得
+///
+/// セグメントはステータスも条件として扱うため、配信停止済みなどの購読者も含まれる。
+pub async fn list_segment_subscribers(
+    pool: &PgPool,
+    user_id: Uuid,
+    rule: &SegmentRule,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<Subscriber>, sqlx::Error> {
+    let mut builder = QueryBuilder::<Postgres>::new(format!(
+        "SELECT {SUBSCRIBER_COLUMNS} FROM subscribers WHERE user_id = "
+    ));
+    builder.push_bind(user_id).push(" AND ");
+    push_segment_rule(&mut builder, rule);
+    builder.push(" ORDER BY created_at ASC, id ASC");
+
+    if let Some(limit) = limit {
+        builder.push(" LIMIT ").push_bind(limit);
+    }
+    if let Some(offset) = offset {
+        builder.push(" OFFSET ").push_bind(offset);
+    }
+
+    builder.build_query_as::<Subscriber>().fetch_all(pool).await
+}
+
+/// セグメントの条件ツリーに一致する購読者数を取得
+pub async fn count_segment_subscribers(
+    pool: &PgPool,
+    user_id: Uuid,
+    rule: &SegmentRule,
+) -> Result<i64, sqlx::Error> {
+    let mut builder =
+        QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM subscribers WHERE user_id = ");
+    builder.push_bind(user_id).push(" AND ");
+    push_segment_rule(&mut builder, rule);
+
+    builder.build_query_scalar::<i64>().fetch_one(pool).await
+}
+
+/// セグメントに一致し、まだシーケンスに登録されていない配信中の購読者を取得
+///
+/// シーケンスの有効化時点から一致し続けている購読者（スナップショット）は除外する。
+pub async fn list_segment_entrants(
+    pool: &PgPool,
+    user_id: Uuid,
+    rule: &SegmentRule,
+    sequence_id: Uuid,
+    limit: i64,
+) -> Result<Vec<Subscriber>, sqlx::Error> {
+    let mut builder = QueryBuilder::<Postgres>::new(format!(
+        "SELECT {SUBSCRIBER_COLUMNS} FROM subscribers WHERE user_id = "
+    ));
+    builder
+        .push_bind(user_id)
+        .push(" AND status = 'active' AND NOT EXISTS (SELECT 1 FROM sequence_enrollments se WHERE se.subscriber_id = subscribers.id AND se.sequence_id = ")
+        .push_bind(sequence_id)
+        .push(") AND NOT EXISTS (SELECT 1 FROM sequence_segment_snapshots ss WHERE ss.subscriber_id = subscribers.id AND ss.sequence_id = ")
+        .push_bind(sequence_id)
+        .push(") AND ");
+    push_segment_rule(&mut builder, rule);
+    builder
+        .push(" ORDER BY created_at ASC, id ASC LIMIT ")
+        .push_bind(limit);
+
+    builder.build_query_as::<Subscriber>().fetch_all(pool).await
+}
+
+/// シーケンスの有効化時点でセグメントに一致している配信中の購読者を記録（以前の記録は置き換える）
+pub async fn replace_segment_snapshot(
+    pool: &PgPool,
+    user_id: Uuid,
+    rule: &SegmentRule,
+    sequence_id: Uuid,
+) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM sequence_segment_snapshots WHERE sequence_id = $1")
+        .bind(sequence_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let mut builder = QueryBuilder::<Postgres>::new(
+        "INSERT INTO sequence_segment_snapshots (sequence_id, subscriber_id) SELECT ",
+    );
+    builder
+        .push_bind(sequence_id)
+        .push(", id FROM subscribers WHERE user_id = ")
+        .push_bind(user_id)
+        .push(" AND status = 'active' AND ");
+    push_segment_rule(&mut builder, rule);
+    let inserted = builder.build().execute(&mut *tx).await?.rows_affected();
+
+    tx.commit().await?;
+    Ok(inserted)
+}
+
+/// セグメントから外れた購読者をスナップショットから削除（再び一致した時点で登録対象になる）
+pub async fn prune_segment_snapshot(
+    pool: &PgPool,
+    rule: &SegmentRule,
+    sequence_id: Uuid,
+) -> Result<u64, sqlx::Error> {
+    let mut builder = QueryBuilder::<Postgres>::new(
+        "DELETE FROM sequence_segment_snapshots ss WHERE ss.sequence_id = ",
+    );
+    builder
+        .push_bind(sequence_id)
+        .push(" AND NOT EXISTS (SELECT 1 FROM subscribers WHERE subscribers.id = ss.subscriber_id AND status = 'active' AND ");
+    push_segment_rule(&mut builder, rule);
+    builder.push(")");
+
+    Ok(builder.build().execute(pool).await?.rows_affected())
+}
+
+/// 日付のカスタムフィールドの月日が一致し、その日付でまだシーケンスに登録されていない配信中の購読者を取得
+///
+/// フィールドの値は `YYYY-MM-DD`（時刻付きを含む）・`MM-DD`・`--MM-DD` の形式を対象にする。
+/// 登録済みの判定はエンロールメントのメタデータの`date`で行う。
+pub async fn list_date_trigger_candidates(
+    pool: &PgPool,
+    user_id: Uuid,
+    field: &str,
+    month_days: &[String],
+    sequence_id: Uuid,
+    date: &str,
+    limit: i64,
+) -> Result<Vec<Subscriber>, sqlx::Error> {
+    sqlx::query_as::<_, Subscriber>(&format!(
+        r#"
+        SELECT {SUBSCRIBER_COLUMNS} FROM subscribers
+        WHERE user_id = $1
+          AND status = 'active'
+          AND SUBSTRING(custom_fields->>$2 FROM '^(?:\d{{4}}-|--)?(\d{{2}}-\d{{2}})(?:[T ].*)?$') = ANY($3)
+          AND NOT EXISTS (
+              SELECT 1 FROM sequence_enrollments se
+              WHERE se.subscriber_id = subscribers.id
+                AND se.sequence_id = $4
+                AND se.metadata->>'date' = $5
+          )
+        ORDER BY created_at ASC, id ASC
+        LIMIT $6
+        "#
+    ))
+    .bind(user_id)
+    .bind(field)
+    .bind(month_days)
+    .bind(sequence_id)
+    .bind(date)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// セグメントの条件ツリーをSQLの条件式に変換（値はすべてバインドパラメータで渡す）
+///
+/// 条件式は必ず括弧で囲まれた真偽値になる。NULLになり得るカスタムフィールドの条件は
+/// 偽として扱うため、`not`で反転するとフィールドがない購読者にも一致する。
+pub fn push_segment_rule(builder: &mut QueryBuilder<'_, Postgres>, rule: &SegmentRule) {
+    match rule {
+        SegmentRule::All { rules } | SegmentRule::Any { rules } => {
+            if rules.is_empty() {
+                builder.push(if matches!(rule, SegmentRule::All { .. }) {
+                    "(TRUE)"
+                } else {
+                    "(FALSE)"
+                });
+                return;
+            }
+
+            let separator = if matches!(rule, SegmentRule::All { .. }) {
+                " AND "
+            } else {
+                " OR "
+            };
+            builder.push("(");
+            for (i, rule) in rules.iter().enumerate() {
+                if i > 0 {
+                    builder.push(separator);
+                }
+                push_segment_rule(builder, rule);
+            }
+            builder.push(")");
+        }
+        SegmentRule::Not { rule } => {
+            builder.push("(NOT ");
+            push_segment_rule(builder, rule);
+            builder.push(")");
+        }
+        SegmentRule::Tag { tag } => {
+            builder
+                .push("(")
+                .push_bind(tag.clone())
+                .push(" = ANY(tags))");
+        }
+        SegmentRule::Status { statuses } => {
+            let statuses: Vec<String> = statuses.iter().map(|s| s.as_str().to_string()).collect();
+            builder
+                .push("(status::text = ANY(")
+                .push_bind(statuses)
+                .push("))");
+        }
+        SegmentRule::SubscribedAt { after, before } => {
+            builder.push("(TRUE");
+            if let Some(after) = after {
+                builder.push(" AND subscribed_at >= ").push_bind(*after);
+            }
+            if let Some(before) = before {
+                builder.push(" AND subscribed_at < ").push_bind(*before);
+            }
+            builder.push(")");
+        }
+        SegmentRule::CustomField(condition) => {
+            builder.push("COALESCE(");
+            push_custom_field_condition(builder, condition);
+            builder.push(", FALSE)");
+        }
+        SegmentRule::OpenedCampaign { campaign_id } => {
+            push_tracking_event_exists(builder, *campaign_id, "open");
+        }
+        SegmentRule::ClickedCampaign { campaign_id } => {
+            push_tracking_event_exists(builder, *campaign_id, "click");
+        }
+    }
+}
+
+fn push_tracking_event_exists(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    campaign_id: Uuid,
+    event_type: &'static str,
+) {
+    builder
+        .push("EXISTS (SELECT 1 FROM campaign_tracking_events e WHERE e.subscriber_id = subscribers.id AND e.campaign_id = ")
+        .push_bind(campaign_id)
+        .push(" AND e.event_type = ")
+        .push_bind(event_type)
+        .push(")");
+}
+
+/// カスタムフィールドの条件をSQLに変換
+///
+/// 値の比較はテキストとして行い、大小比較は比較値が数値の場合のみ数値として比較する。
+/// 条件の値が不正な場合は一致しない条件（FALSE）になる。
+fn push_custom_field_condition(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    condition: &CustomFieldCondition,
+) {
+    let field = condition.field.clone();
+
+    match condition.operator {
+        ConditionOperator::Exists => {
+            builder
+                .push("(custom_fields -> ")
+                .push_bind(field)
+                .push(") IS NOT NULL");
+        }
+        ConditionOperator::NotExists => {
+            builder
+                .push("(custom_fields -> ")
+                .push_bind(field)
+                .push(") IS NULL");
+        }
+        ConditionOperator::In | ConditionOperator::NotIn => {
+            let Some(values) = condition.value_texts() else {
+                builder.push("FALSE");
+                return;
+            };
+            let negate = condition.operator == ConditionOperator::NotIn;
+            builder
+                .push(if negate { "COALESCE(NOT (" } else { "(" })
+                .push("(custom_fields ->> ")
+                .push_bind(field)
+                .push(") = ANY(")
+                .push_bind(values)
+                .push(if negate { ")), TRUE)" } else { "))" });
+        }
+        operator => {
+            if let Some(comparison) = operator.comparison_sql() {
+                match &condition.value {
+                    serde_json::Value::Number(n) => {
+                        builder
+                            .push("(CASE WHEN jsonb_typeof(custom_fields -> ")
+                            .push_bind(field.clone())
+                            .push(") = 'number' THEN (custom_fields ->> ")
+                            .push_bind(field)
+                            .push(")::float8 END) ")
+                            .push(comparison)
+                            .push(" ")
+                            .push_bind(n.as_f64().unwrap_or_default());
+                    }
+                    serde_json::Value::String(s) => {
+                        // ISO 8601形式の日付などは文字列のまま比較できる
+                        builder
+                            .push("(custom_fields ->> ")
+                            .push_bind(field)
+                            .push(") ")
+                            .push(comparison)
+                            .push(" ")
+                            .push_bind(s.clone());
+                    }
+                    _ => {
+                        builder.push("FALSE");
+                    }
+                }
+                return;
+            }
+
+            let Some(value) = condition.value_text() else {
+                builder.push("FALSE");
+                return;
+            };
+            match operator {
+                ConditionOperator::Equals => {
+                    builder
+                        .push("(custom_fields ->> ")
+                        .push_bind(field)
+                        .push(") = ")
+                        .push_bind(value);
+                }
+                ConditionOperator::NotEquals => {
+                    builder
+                        .push("(custom_fields ->> ")
+                        .push_bind(field)
+                        .push(") IS DISTINCT FROM ")
+                        .push_bind(value);
+                }
+                ConditionOperator::Contains => {
+                    builder
+                        .push("strpos(lower(custom_fields ->> ")
+                        .push_bind(field)
+                        .push("), lower(")
+                        .push_bind(value)
+                        .push(")) > 0");
+                }
+                ConditionOperator::NotContains => {
+                    builder
+                        .push("COALESCE(strpos(lower(custom_fields ->> ")
+                        .push_bind(field)
+                        .push("), lower(")
+                        .push_bind(value)
+                        .push(")), 0) = 0");
+                }
+                _ => {
+                    builder.push("FALSE");
+                }
+            }
+        }
+    }
+}
+
 /// 購読者を取得（ID指定）
 pub async fn find_subscriber_by_id(
     pool: &PgPool,
@@ -165,6 +651,132 @@ pub async fn find_subscriber_by_id(
     Ok(subscriber)
 }
 
+/// 購読者をまとめて取得（ID指定）
+pub async fn find_subscribers_by_ids(
+    pool: &PgPool,
+    user_id: Uuid,
+    subscriber_ids: &[Uuid],
+) -> Result<Vec<Subscriber>, sqlx::Error> {
+    let subscribers = sqlx::query_as::<_, Subscriber>(
+        r#"
+        SELECT 
+            id,
+            user_id,
+            email,
+            name,
+            status,
+            tags,
+            custom_fields,
+            subscribed_at,
+            unsubscribed_at,
+            created_at,
+            updated_at
+        FROM subscribers 
+        WHERE user_id = $1 AND id = ANY($2)
+        "#,
+    )
+    .bind(user_id)
+    .bind(subscriber_ids)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(subscribers)
+}
+
+/// 購読者をまとめて取得（メールアドレス指定、大文字・小文字を区別しない）
+pub async fn find_subscribers_by_emails(
+    pool: &PgPool,
+    user_id: Uuid,
+    emails: &[String],
+) -> Result<Vec<Subscriber>, sqlx::Error> {
+    let emails: Vec<String> = emails.iter().map(|email| email.to_lowercase()).collect();
+    let subscribers = sqlx::query_as::<_, Subscriber>(
+        r#"
+        SELECT
+            id,
+            user_id,
+            email,
+            name,
+            status,
+            tags,
+            custom_fields,
+            subscribed_at,
+            unsubscribed_at,
+            created_at,
+            updated_at
+        FROM subscribers
+        WHERE user_id = $1 AND LOWER(email) = ANY($2)
+        "#,
+    )
+    .bind(user_id)
+    .bind(&emails)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(subscribers)
+}
+
+/// SESのメッセージIDから、キャンペーン以外で送信したメールの宛先の購読者を取得
+///
+/// シーケンスのステップログと、ダブルオプトインの確認メールを送信したフォーム送信データを検索する。
+pub async fn find_subscriber_by_message_id(
+    pool: &PgPool,
+    message_id: &str,
+) -> Result<Option<Subscriber>, sqlx::Error> {
+    sqlx::query_as::<_, Subscriber>(&format!(
+        r#"
+        SELECT {SUBSCRIBER_COLUMNS} FROM subscribers
+        WHERE id = (
+            SELECT se.subscriber_id
+            FROM sequence_step_logs sl
+            JOIN sequence_enrollments se ON se.id = sl.enrollment_id
+            WHERE sl.message_id = $1
+            UNION ALL
+            SELECT fs.subscriber_id
+            FROM form_submissions fs
+            WHERE fs.confirmation_message_id = $1 AND fs.subscriber_id IS NOT NULL
+            LIMIT 1
+        )
+        "#
+    ))
+    .bind(message_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// タグの付いた購読者をすべて取得
+pub async fn find_subscribers_by_tag(
+    pool: &PgPool,
+    user_id: Uuid,
+    tag: &str,
+) -> Result<Vec<Subscriber>, sqlx::Error> {
+    let subscribers = sqlx::query_as::<_, Subscriber>(
+        r#"
+        SELECT
+            id,
+            user_id,
+            email,
+            name,
+            status,
+            tags,
+            custom_fields,
+            subscribed_at,
+            unsubscribed_at,
+            created_at,
+            updated_at
+        FROM subscribers
+        WHERE user_id = $1 AND $2 = ANY(tags)
+        ORDER BY created_at ASC
+        "#,
+    )
+    .bind(user_id)
+    .bind(tag)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(subscribers)
+}
+
 /// メールアドレスで購読者を検索
 pub async fn find_subscriber_by_email(
     pool: &PgPool,
@@ -322,6 +934,142 @@ pub async fn update_subscriber(
     Ok(subscriber)
 }
 
+/// 購読者を配信停止にする
+///
+/// バウンス・苦情で配信停止された購読者はその理由を残すため変更せず、現在の購読者をそのまま返す。
+pub async fn unsubscribe_subscriber(
+    pool: &PgPool,
+    subscriber_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<Subscriber>, sqlx::Error> {
+    let subscriber = sqlx::query_as::<_, Subscriber>(
+        r#"
+        WITH updated AS (
+            UPDATE subscribers
+            SET
+                status = $3,
+                unsubscribed_at = COALESCE(unsubscribed_at, NOW()),
+                updated_at = NOW()
+            WHERE id = $1 AND user_id = $2 AND status NOT IN ($4, $5)
+            RETURNING *
+        )
+        SELECT
+            id,
+            user_id,
+            email,
+            name,
+            status,
+            tags,
+            custom_fields,
+            subscribed_at,
+            unsubscribed_at,
+            created_at,
+            updated_at
+        FROM updated
+        UNION ALL
+        SELECT
+            id,
+            user_id,
+            email,
+            name,
+            status,
+            tags,
+            custom_fields,
+            subscribed_at,
+            unsubscribed_at,
+            created_at,
+            updated_at
+        FROM subscribers
+        WHERE id = $1 AND user_id = $2 AND NOT EXISTS (SELECT 1 FROM updated)
+        "#,
+    )
+    .bind(subscriber_id)
+    .bind(user_id)
+    .bind(SubscriberStatus::Unsubscribed)
+    .bind(SubscriberStatus::Bounced)
+    .bind(SubscriberStatus::Complained)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(subscriber)
+}
+
+/// ダブルオプトインの確認により確認待ちの購読者を有効にする（確認待ちでない場合は`None`）
+pub async fn confirm_subscriber(
+    pool: &PgPool,
+    subscriber_id: Uuid,
+) -> Result<Option<Subscriber>, sqlx::Error> {
+    let subscriber = sqlx::query_as::<_, Subscriber>(
+        r#"
+        UPDATE subscribers
+        SET
+            status = $2,
+            subscribed_at = NOW(),
+            updated_at = NOW()
+        WHERE id = $1 AND status = $3
+        RETURNING 
+            id,
+            user_id,
+            email,
+            name,
+            status,
+            tags,
+            custom_fields,
+            subscribed_at,
+            unsubscribed_at,
+            created_at,
+            updated_at
+        "#,
+    )
+    .bind(subscriber_id)
+    .bind(SubscriberStatus::Active)
+    .bind(SubscriberStatus::Pending)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(subscriber)
+}
+
+/// バウンス・苦情の通知により購読者を配信対象外にする
+///
+/// バウンスは配信中の購読者のみ、苦情は配信停止済みやバウンス済みの購読者も対象とする。
+/// ステータスが変わらなかった場合は`None`を返す。
+pub async fn suppress_subscriber(
+    pool: &PgPool,
+    subscriber_id: Uuid,
+    status: SubscriberStatus,
+) -> Result<Option<Subscriber>, sqlx::Error> {
+    let subscriber = sqlx::query_as::<_, Subscriber>(
+        r#"
+        UPDATE subscribers
+        SET
+            status = $2,
+            updated_at = NOW()
+        WHERE id = $1
+          AND status <> $2
+          AND (status = 'active' OR $2 = 'complained'::subscriber_status)
+        RETURNING 
+            id,
+            user_id,
+            email,
+            name,
+            status,
+            tags,
+            custom_fields,
+            subscribed_at,
+            unsubscribed_at,
+            created_at,
+            updated_at
+        "#,
+    )
+    .bind(subscriber_id)
+    .bind(status)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(subscriber)
+}
+
 /// 購読者を削除
 pub async fn delete_subscriber(
     pool: &PgPool,
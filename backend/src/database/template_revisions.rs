@@ -0,0 +1,157 @@
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::models::template::TemplateRevision;
+
+const TEMPLATE_REVISION_COLUMNS: &str =
+    "id, template_id, revision, subject_template, markdown_content, variables, created_by, note, created_at";
+
+/// テンプレートの現在の内容を新しいリビジョンとして記録
+///
+/// 最新のリビジョンから件名・本文・変数が変わっていない場合は記録せずNoneを返す。
+pub async fn record_revision(
+    conn: &mut PgConnection,
+    template_id: Uuid,
+    created_by: Uuid,
+    note: Option<&str>,
+) -> Result<Option<TemplateRevision>, sqlx::Error> {
+    sqlx::query_as::<_, TemplateRevision>(&format!(
+        r#"
+        WITH latest AS (
+            SELECT subject_template, markdown_content, variables
+            FROM template_revisions
+            WHERE template_id = $1
+            ORDER BY revision DESC
+            LIMIT 1
+        ),
+        bumped AS (
+            UPDATE templates t
+            SET current_revision = t.current_revision + 1
+            WHERE t.id = $1
+              AND NOT EXISTS (
+                  SELECT 1 FROM latest l
+                  WHERE l.subject_template = t.subject_template
+                    AND l.markdown_content = t.markdown_content
+                    AND l.variables = COALESCE(t.variables, '{{}}')
+              )
+            RETURNING t.id, t.current_revision, t.subject_template, t.markdown_content, t.variables
+        )
+        INSERT INTO template_revisions (template_id, revision, subject_template, markdown_content, variables, created_by, note)
+        SELECT id, current_revision, subject_template, markdown_content, COALESCE(variables, '{{}}'), $2, $3
+        FROM bumped
+        RETURNING {TEMPLATE_REVISION_COLUMNS}
+        "#
+    ))
+    .bind(template_id)
+    .bind(created_by)
+    .bind(note)
+    .fetch_optional(conn)
+    .await
+}
+
+/// テンプレートの現在の内容に対応するリビジョンを取得
+///
+/// リビジョンのないテンプレートや記録後に内容が変わったテンプレートは、その場で記録する。
+pub async fn current_revision(
+    pool: &PgPool,
+    template_id: Uuid,
+    created_by: Uuid,
+) -> Result<Option<TemplateRevision>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    if let Some(revision) = record_revision(&mut tx, template_id, created_by, None).await? {
+        tx.commit().await?;
+        return Ok(Some(revision));
+    }
+
+    let revision = sqlx::query_as::<_, TemplateRevision>(&format!(
+        "SELECT {TEMPLATE_REVISION_COLUMNS} FROM template_revisions WHERE template_id = $1 ORDER BY revision DESC LIMIT 1"
+    ))
+    .bind(template_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(revision)
+}
+
+/// リビジョン一覧を取得（新しい順）
+pub async fn list_revisions(
+    pool: &PgPool,
+    template_id: Uuid,
+) -> Result<Vec<TemplateRevision>, sqlx::Error> {
+    sqlx::query_as::<_, TemplateRevision>(&format!(
+        "SELECT {TEMPLATE_REVISION_COLUMNS} FROM template_revisions WHERE template_id = $1 ORDER BY revision DESC"
+    ))
+    .bind(template_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// リビジョンを取得（番号指定）
+pub async fn find_revision(
+    pool: &PgPool,
+    template_id: Uuid,
+    revision: i32,
+) -> Result<Option<TemplateRevision>, sqlx::Error> {
+    sqlx::query_as::<_, TemplateRevision>(&format!(
+        "SELECT {TEMPLATE_REVISION_COLUMNS} FROM template_revisions WHERE template_id = $1 AND revision = $2"
+    ))
+    .bind(template_id)
+    .bind(revision)
+    .fetch_optional(pool)
+    .await
+}
+
+/// リビジョンを取得（ID指定）
+pub async fn find_revision_by_id(
+    pool: &PgPool,
+    revision_id: Uuid,
+) -> Result<Option<TemplateRevision>, sqlx::Error> {
+    sqlx::query_as::<_, TemplateRevision>(&format!(
+        "SELECT {TEMPLATE_REVISION_COLUMNS} FROM template_revisions WHERE id = $1"
+    ))
+    .bind(revision_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// テンプレートの内容をリビジョンの内容に戻し、新しいリビジョンとして記録
+///
+/// 過去のリビジョンは書き換えず、復元した内容を最新のリビジョンとして追加する。
+pub async fn restore_revision(
+    pool: &PgPool,
+    template_id: Uuid,
+    user_id: Uuid,
+    revision: &TemplateRevision,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query(
+        r#"
+        UPDATE templates
+        SET subject_template = $3,
+            markdown_content = $4,
+            variables = $5,
+            html_content = NULL,
+            updated_at = NOW()
+        WHERE id = $1 AND user_id = $2
+        "#,
+    )
+    .bind(template_id)
+    .bind(user_id)
+    .bind(&revision.subject_template)
+    .bind(&revision.markdown_content)
+    .bind(&revision.variables)
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    let note = format!("リビジョン{}から復元", revision.revision);
+    record_revision(&mut tx, template_id, user_id, Some(&note)).await?;
+    tx.commit().await?;
+
+    Ok(true)
+}
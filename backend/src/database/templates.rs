@@ -1,7 +1,10 @@
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::models::template::{CreateTemplateRequest, Template, UpdateTemplateRequest};
+use crate::{
+    database::template_revisions,
+    models::template::{CreateTemplateRequest, Template, UpdateTemplateRequest},
+};
 
 /// テンプレート一覧を取得（ユーザー別）
 pub async fn list_user_templates(
@@ -24,6 +27,7 @@ pub async fn list_user_templates(
             html_content,
             variables,
             is_public,
+            current_revision,
             created_at,
             updated_at
         FROM templates 
@@ -49,6 +53,7 @@ pub async fn list_user_templates(
             html_content: row.html_content,
             variables: row.variables.unwrap_or_else(|| serde_json::json!({})),
             is_public: row.is_public.unwrap_or(false),
+            current_revision: row.current_revision,
             created_at: row.created_at.unwrap_or_else(chrono::Utc::now),
             updated_at: row.updated_at.unwrap_or_else(chrono::Utc::now),
         })
@@ -90,6 +95,7 @@ pub async fn find_template_by_id(
                 html_content,
                 variables,
                 is_public,
+                current_revision,
                 created_at,
                 updated_at
             FROM templates 
@@ -110,6 +116,7 @@ pub async fn find_template_by_id(
             html_content: row.html_content,
             variables: row.variables.unwrap_or_else(|| serde_json::json!({})),
             is_public: row.is_public.unwrap_or(false),
+            current_revision: row.current_revision,
             created_at: row.created_at.unwrap_or_else(chrono::Utc::now),
             updated_at: row.updated_at.unwrap_or_else(chrono::Utc::now),
         }))
@@ -126,6 +133,7 @@ pub async fn find_template_by_id(
                 html_content,
                 variables,
                 is_public,
+                current_revision,
                 created_at,
                 updated_at
             FROM templates 
@@ -145,13 +153,14 @@ pub async fn find_template_by_id(
             html_content: row.html_content,
             variables: row.variables.unwrap_or_else(|| serde_json::json!({})),
             is_public: row.is_public.unwrap_or(false),
+            current_revision: row.current_revision,
             created_at: row.created_at.unwrap_or_else(chrono::Utc::now),
             updated_at: row.updated_at.unwrap_or_else(chrono::Utc::now),
         }))
     }
 }
 
-/// テンプレートを作成
+/// テンプレートを作成（最初のリビジョンも記録する）
 pub async fn create_template(
     pool: &PgPool,
     user_id: Uuid,
@@ -160,6 +169,7 @@ pub async fn create_template(
     let default_variables = serde_json::json!({});
     let variables = request.variables.as_ref().unwrap_or(&default_variables);
 
+    let mut tx = pool.begin().await?;
     let row = sqlx::query!(
         r#"
         INSERT INTO templates (
@@ -180,6 +190,7 @@ pub async fn create_template(
             html_content,
             variables,
             is_public,
+            current_revision,
             created_at,
             updated_at
         "#,
@@ -190,9 +201,12 @@ pub async fn create_template(
         variables,
         request.is_public.unwrap_or(false)
     )
-    .fetch_one(pool)
+    .fetch_one(&mut *tx)
     .await?;
 
+    let revision = template_revisions::record_revision(&mut tx, row.id, user_id, None).await?;
+    tx.commit().await?;
+
     Ok(Template {
         id: row.id,
         user_id: row.user_id,
@@ -202,18 +216,20 @@ pub async fn create_template(
         html_content: row.html_content,
         variables: row.variables.unwrap_or_else(|| serde_json::json!({})),
         is_public: row.is_public.unwrap_or(false),
+        current_revision: revision.map_or(row.current_revision, |r| r.revision),
         created_at: row.created_at.unwrap_or_else(chrono::Utc::now),
         updated_at: row.updated_at.unwrap_or_else(chrono::Utc::now),
     })
 }
 
-/// テンプレートを更新
+/// テンプレートを更新（件名・本文・変数が変わった場合は新しいリビジョンを記録する）
 pub async fn update_template(
     pool: &PgPool,
     template_id: Uuid,
     user_id: Uuid,
     request: &UpdateTemplateRequest,
 ) -> Result<Option<Template>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
     let row = sqlx::query!(
         r#"
         UPDATE templates 
@@ -235,6 +251,7 @@ pub async fn update_template(
             html_content,
             variables,
             is_public,
+            current_revision,
             created_at,
             updated_at
         "#,
@@ -247,10 +264,16 @@ pub async fn update_template(
         request.variables.as_ref(),
         request.is_public
     )
-    .fetch_optional(pool)
+    .fetch_optional(&mut *tx)
     .await?;
 
-    Ok(row.map(|row| Template {
+    let Some(row) = row else {
+        return Ok(None);
+    };
+    let revision = template_revisions::record_revision(&mut tx, row.id, user_id, None).await?;
+    tx.commit().await?;
+
+    Ok(Some(Template {
         id: row.id,
         user_id: row.user_id,
         name: row.name,
@@ -259,6 +282,7 @@ pub async fn update_template(
         html_content: row.html_content,
         variables: row.variables.unwrap_or_else(|| serde_json::json!({})),
         is_public: row.is_public.unwrap_or(false),
+        current_revision: revision.map_or(row.current_revision, |r| r.revision),
         created_at: row.created_at.unwrap_or_else(chrono::Utc::now),
         updated_at: row.updated_at.unwrap_or_else(chrono::Utc::now),
     }))
@@ -0,0 +1,180 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::tracking::{TrackingEvent, TrackingEventType};
+
+/// 計測イベントを記録
+pub async fn record_tracking_event(
+    pool: &PgPool,
+    campaign_id: Uuid,
+    subscriber_id: Uuid,
+    event_type: TrackingEventType,
+    url: Option<&str>,
+    ip_address: Option<&str>,
+    user_agent: Option<&str>,
+) -> Result<TrackingEvent, sqlx::Error> {
+    let event = sqlx::query_as::<_, TrackingEvent>(
+        r#"
+        INSERT INTO campaign_tracking_events (
+            campaign_id, subscriber_id, event_type, url, ip_address, user_agent
+        )
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, campaign_id, subscriber_id, event_type, url, ip_address, user_agent, created_at
+        "#,
+    )
+    .bind(campaign_id)
+    .bind(subscriber_id)
+    .bind(event_type.as_str())
+    .bind(url)
+    .bind(ip_address)
+    .bind(user_agent)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(event)
+}
+
+/// 指定した購読者がURLを既にクリックしたか確認
+pub async fn has_clicked_url(
+    pool: &PgPool,
+    campaign_id: Uuid,
+    subscriber_id: Uuid,
+    url: Option<&str>,
+) -> Result<bool, sqlx::Error> {
+    let exists = sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM campaign_tracking_events
+            WHERE campaign_id = $1 AND subscriber_id = $2 AND event_type = $3
+              AND url IS NOT DISTINCT FROM $4
+        )
+        "#,
+    )
+    .bind(campaign_id)
+    .bind(subscriber_id)
+    .bind(TrackingEventType::Click.as_str())
+    .bind(url)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(exists)
+}
+
+/// 購読者がキャンペーン（未指定の場合はいずれか）に反応したか確認
+pub async fn has_subscriber_engagement(
+    pool: &PgPool,
+    subscriber_id: Uuid,
+    event_type: TrackingEventType,
+    campaign_id: Option<Uuid>,
+    within_days: Option<i32>,
+) -> Result<bool, sqlx::Error> {
+    let exists = sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM campaign_tracking_events
+            WHERE subscriber_id = $1
+              AND event_type = $2
+              AND ($3::uuid IS NULL OR campaign_id = $3)
+              AND ($4::int IS NULL OR created_at >= NOW() - make_interval(days => $4))
+        )
+        "#,
+    )
+    .bind(subscriber_id)
+    .bind(event_type.as_str())
+    .bind(campaign_id)
+    .bind(within_days)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(exists)
+}
+
+/// 購読者が指定日時以降にリンクをクリックしたか確認（`url`はクリックしたURLに含まれる文字列）
+pub async fn has_clicked_link_since(
+    pool: &PgPool,
+    subscriber_id: Uuid,
+    campaign_id: Option<Uuid>,
+    url: Option<&str>,
+    since: DateTime<Utc>,
+) -> Result<bool, sqlx::Error> {
+    let exists = sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM campaign_tracking_events
+            WHERE subscriber_id = $1
+              AND event_type = $2
+              AND ($3::uuid IS NULL OR campaign_id = $3)
+              AND ($4::text IS NULL OR strpos(url, $4) > 0)
+              AND created_at >= $5
+        )
+        "#,
+    )
+    .bind(subscriber_id)
+    .bind(TrackingEventType::Click.as_str())
+    .bind(campaign_id)
+    .bind(url)
+    .bind(since)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(exists)
+}
+
+/// 購読者の初回の開封・クリックを記録し、キャンペーンのユニーク開封数・クリック数を加算
+///
+/// 記録済みの場合は何もしない。同時に呼ばれても加算は1回のみで、初回として記録した場合のみtrueを返す。
+pub async fn record_first_engagement(
+    pool: &PgPool,
+    campaign_id: Uuid,
+    subscriber_id: Uuid,
+    event_type: TrackingEventType,
+) -> Result<bool, sqlx::Error> {
+    let first = sqlx::query_scalar::<_, bool>(
+        r#"
+        WITH first AS (
+            INSERT INTO campaign_first_engagements (campaign_id, subscriber_id, event_type)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (campaign_id, subscriber_id, event_type) DO NOTHING
+            RETURNING event_type
+        ),
+        counted AS (
+            UPDATE campaigns
+            SET
+                opened_count = COALESCE(opened_count, 0) + CASE WHEN $3 = 'open' THEN 1 ELSE 0 END,
+                clicked_count = COALESCE(clicked_count, 0) + CASE WHEN $3 = 'click' THEN 1 ELSE 0 END,
+                updated_at = NOW()
+            WHERE id = $1 AND EXISTS (SELECT 1 FROM first)
+            RETURNING id
+        )
+        SELECT EXISTS (SELECT 1 FROM first)
+        "#,
+    )
+    .bind(campaign_id)
+    .bind(subscriber_id)
+    .bind(event_type.as_str())
+    .fetch_one(pool)
+    .await?;
+
+    Ok(first)
+}
+
+/// キャンペーンの計測イベント一覧を取得
+pub async fn list_campaign_tracking_events(
+    pool: &PgPool,
+    campaign_id: Uuid,
+) -> Result<Vec<TrackingEvent>, sqlx::Error> {
+    let events = sqlx::query_as::<_, TrackingEvent>(
+        r#"
+        SELECT id, campaign_id, subscriber_id, event_type, url, ip_address, user_agent, created_at
+        FROM campaign_tracking_events
+        WHERE campaign_id = $1
+        ORDER BY created_at ASC
+        "#,
+    )
+    .bind(campaign_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(events)
+}
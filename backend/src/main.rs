@@ -8,7 +8,11 @@ use tower_http::{
 };
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use markmail_backend::{api, database, utils, workers, AppState};
+use markmail_backend::{
+    api, database,
+    services::{github_service, sending_domain_service, ses_feedback_service::SesFeedbackService},
+    utils, workers, AppState,
+};
 
 #[tokio::main]
 async fn main() {
@@ -32,6 +36,18 @@ async fn main() {
         .await
         .expect("データベースに接続できませんでした");
 
+    // 暗号化前に保存されたDKIM秘密鍵を暗号化
+    match sending_domain_service::encrypt_stored_dkim_keys(&pool).await {
+        Ok(0) => {}
+        Ok(count) => tracing::info!("{}件のDKIM秘密鍵を暗号化しました", count),
+        Err(e) => tracing::error!("DKIM秘密鍵の暗号化に失敗しました: {}", e),
+    }
+    match github_service::encrypt_stored_github_secrets(&pool).await {
+        Ok(0) => {}
+        Ok(count) => tracing::info!("{}件のGitHub連携のトークンを暗号化しました", count),
+        Err(e) => tracing::error!("GitHub連携のトークンの暗号化に失敗しました: {}", e),
+    }
+
     // Redis接続
     let redis_client = redis::Client::open(config.redis_url.clone())
         .expect("Redisクライアントの作成に失敗しました");
@@ -43,8 +59,34 @@ async fn main() {
         config,
     };
 
+    // 各ワーカーは停止シグナルを受けると実行中のステップ・送信中のバッチを完了してから終了する
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+
     // シーケンスワーカーを起動
-    workers::sequence_worker::spawn_sequence_worker(std::sync::Arc::new(pool));
+    let sequence_worker = workers::sequence_worker::spawn_sequence_worker(
+        std::sync::Arc::new(pool.clone()),
+        shutdown_rx.clone(),
+    );
+
+    // スケジュール済みキャンペーンの配信ワーカーを起動
+    let campaign_scheduler = workers::campaign_scheduler::spawn_campaign_scheduler(
+        std::sync::Arc::new(pool.clone()),
+        shutdown_rx.clone(),
+    );
+
+    // 中断・リトライ待ちのキャンペーン配信を再開するワーカーを起動
+    let campaign_delivery_worker =
+        workers::campaign_delivery_worker::spawn_campaign_delivery_worker(
+            std::sync::Arc::new(pool),
+            shutdown_rx,
+        );
+
+    // SES通知は受け付けるSNSトピックが設定されている場合のみ処理する
+    if !SesFeedbackService::new().is_configured() {
+        tracing::warn!(
+            "SES_SNS_TOPIC_ARNSが未設定のため、SES通知（/api/ses/webhook）はすべて拒否されます"
+        );
+    }
 
     // ルーター作成
     let app = create_app(app_state);
@@ -60,7 +102,54 @@ async fn main() {
     tracing::info!("MarkMail バックエンドサーバーを起動中... http://{}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move {
+            shutdown_signal().await;
+            tracing::info!("停止シグナルを受信しました。実行中の処理の完了を待っています...");
+            let _ = shutdown_tx.send(true);
+        })
+        .await
+        .unwrap();
+
+    let (sequence_worker, campaign_scheduler, campaign_delivery_worker) = tokio::join!(
+        sequence_worker,
+        campaign_scheduler,
+        campaign_delivery_worker
+    );
+    for (name, result) in [
+        ("シーケンスワーカー", sequence_worker),
+        ("キャンペーンスケジューラー", campaign_scheduler),
+        ("キャンペーン配信ワーカー", campaign_delivery_worker),
+    ] {
+        if let Err(e) = result {
+            tracing::error!("{}の停止に失敗しました: {}", name, e);
+        }
+    }
+}
+
+// SIGTERM または Ctrl+C を待つ
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Ctrl+Cのハンドラを設定できませんでした");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("SIGTERMのハンドラを設定できませんでした")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
 }
 
 fn create_app(state: AppState) -> Router {
@@ -92,6 +181,7 @@ async fn root() -> Json<Value> {
 async fn health_check() -> Result<Json<Value>, StatusCode> {
     Ok(Json(json!({
         "status": "healthy",
-        "timestamp": chrono::Utc::now().to_rfc3339()
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "sequence_worker": workers::sequence_worker::metrics()
     })))
 }
@@ -0,0 +1,277 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// A/Bテストのバリアント数の上限
+pub const AB_TEST_MAX_VARIANTS: usize = 5;
+
+/// 勝者判定までの最大待機時間（分）
+pub const AB_TEST_MAX_WAIT_MINUTES: i32 = 7 * 24 * 60;
+
+/// キャンペーンのA/Bテスト設定
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct CampaignAbTest {
+    pub campaign_id: Uuid,
+    pub test_percentage: i32,
+    pub winner_metric: String,
+    pub wait_minutes: i32,
+    pub status: String,
+    pub test_started_at: Option<DateTime<Utc>>,
+    pub winner_variant_id: Option<Uuid>,
+    pub winner_selected_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl CampaignAbTest {
+    pub fn status(&self) -> AbTestStatus {
+        AbTestStatus::from(self.status.clone())
+    }
+
+    /// テスト送信の開始から待機時間が経過したか
+    pub fn is_wait_elapsed(&self, now: DateTime<Utc>) -> bool {
+        match self.test_started_at {
+            Some(started_at) => {
+                now >= started_at + chrono::Duration::minutes(self.wait_minutes as i64)
+            }
+            None => false,
+        }
+    }
+}
+
+/// A/Bテストのバリアント
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct CampaignVariant {
+    pub id: Uuid,
+    pub campaign_id: Uuid,
+    pub name: String,
+    pub subject: String,
+    pub template_id: Option<Uuid>,
+    /// 配信時に固定したテンプレートのリビジョン
+    pub template_revision_id: Option<Uuid>,
+    pub split_percentage: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 勝者の判定指標
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WinnerMetric {
+    /// ユニーク開封率
+    Opens,
+    /// ユニーククリック率
+    Clicks,
+}
+
+impl WinnerMetric {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WinnerMetric::Opens => "opens",
+            WinnerMetric::Clicks => "clicks",
+        }
+    }
+}
+
+impl From<String> for WinnerMetric {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "clicks" => WinnerMetric::Clicks,
+            _ => WinnerMetric::Opens,
+        }
+    }
+}
+
+/// A/Bテストの進行状況
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AbTestStatus {
+    /// 送信前
+    Draft,
+    /// テスト送信済みで勝者の判定待ち
+    Testing,
+    /// 勝者を判定済み
+    Completed,
+}
+
+impl AbTestStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AbTestStatus::Draft => "draft",
+            AbTestStatus::Testing => "testing",
+            AbTestStatus::Completed => "completed",
+        }
+    }
+}
+
+impl From<String> for AbTestStatus {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "testing" => AbTestStatus::Testing,
+            "completed" => AbTestStatus::Completed,
+            _ => AbTestStatus::Draft,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateCampaignVariantRequest {
+    pub name: String,
+    pub subject: String,
+    /// 未指定の場合はキャンペーンのテンプレートを使う
+    #[serde(default)]
+    pub template_id: Option<Uuid>,
+    pub split_percentage: i32,
+}
+
+/// A/Bテストの設定リクエスト（既存の設定は置き換える）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigureAbTestRequest {
+    pub test_percentage: i32,
+    pub winner_metric: WinnerMetric,
+    pub wait_minutes: i32,
+    pub variants: Vec<CreateCampaignVariantRequest>,
+}
+
+impl ConfigureAbTestRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=100).contains(&self.test_percentage) {
+            return Err("テスト対象の割合は1〜100%で指定してください".to_string());
+        }
+
+        if !(1..=AB_TEST_MAX_WAIT_MINUTES).contains(&self.wait_minutes) {
+            return Err(format!(
+                "勝者判定までの待機時間は1〜{AB_TEST_MAX_WAIT_MINUTES}分で指定してください"
+            ));
+        }
+
+        if !(2..=AB_TEST_MAX_VARIANTS).contains(&self.variants.len()) {
+            return Err(format!(
+                "バリアントは2〜{AB_TEST_MAX_VARIANTS}個で指定してください"
+            ));
+        }
+
+        let mut names = HashSet::new();
+        for variant in &self.variants {
+            let name = variant.name.trim();
+            if name.is_empty() || name.chars().count() > 100 {
+                return Err("バリアント名は1〜100文字で指定してください".to_string());
+            }
+            if !names.insert(name) {
+                return Err(format!("バリアント名「{name}」が重複しています"));
+            }
+            if variant.subject.trim().is_empty() || variant.subject.chars().count() > 255 {
+                return Err("件名は1〜255文字で指定してください".to_string());
+            }
+            if !(1..=100).contains(&variant.split_percentage) {
+                return Err("バリアントの配分は1〜100%で指定してください".to_string());
+            }
+        }
+
+        let total: i32 = self.variants.iter().map(|v| v.split_percentage).sum();
+        if total != 100 {
+            return Err(format!(
+                "バリアントの配分の合計は100%にしてください（現在: {total}%）"
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// バリアントごとの送信結果
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct VariantStats {
+    pub variant_id: Uuid,
+    pub name: String,
+    pub subject: String,
+    pub sent: i64,
+    pub opened: i64,
+    pub clicked: i64,
+}
+
+impl VariantStats {
+    /// 判定指標の率（送信数に対するユニーク開封・クリック数）
+    pub fn rate(&self, metric: WinnerMetric) -> f64 {
+        if self.sent == 0 {
+            return 0.0;
+        }
+        let count = match metric {
+            WinnerMetric::Opens => self.opened,
+            WinnerMetric::Clicks => self.clicked,
+        };
+        count as f64 / self.sent as f64
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AbTestResponse {
+    pub ab_test: CampaignAbTest,
+    pub variants: Vec<CampaignVariant>,
+    pub results: Vec<VariantStats>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(splits: &[i32]) -> ConfigureAbTestRequest {
+        ConfigureAbTestRequest {
+            test_percentage: 20,
+            winner_metric: WinnerMetric::Opens,
+            wait_minutes: 240,
+            variants: splits
+                .iter()
+                .enumerate()
+                .map(|(i, split)| CreateCampaignVariantRequest {
+                    name: format!("{}", (b'A' + i as u8) as char),
+                    subject: format!("件名{i}"),
+                    template_id: None,
+                    split_percentage: *split,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_validate_configure_request() {
+        assert!(request(&[50, 50]).validate().is_ok());
+        assert!(request(&[34, 33, 33]).validate().is_ok());
+
+        // 配分の合計が100%でない
+        assert!(request(&[50, 40]).validate().is_err());
+        // バリアントが1つだけ
+        assert!(request(&[100]).validate().is_err());
+
+        let mut duplicated = request(&[50, 50]);
+        duplicated.variants[1].name = "A".to_string();
+        assert!(duplicated.validate().is_err());
+
+        let mut no_wait = request(&[50, 50]);
+        no_wait.wait_minutes = 0;
+        assert!(no_wait.validate().is_err());
+
+        let mut no_cohort = request(&[50, 50]);
+        no_cohort.test_percentage = 0;
+        assert!(no_cohort.validate().is_err());
+    }
+
+    #[test]
+    fn test_variant_rate() {
+        let stats = VariantStats {
+            variant_id: Uuid::new_v4(),
+            name: "A".to_string(),
+            subject: "件名".to_string(),
+            sent: 10,
+            opened: 4,
+            clicked: 1,
+        };
+        assert_eq!(stats.rate(WinnerMetric::Opens), 0.4);
+        assert_eq!(stats.rate(WinnerMetric::Clicks), 0.1);
+
+        let empty = VariantStats { sent: 0, ..stats };
+        assert_eq!(empty.rate(WinnerMetric::Opens), 0.0);
+    }
+}
@@ -0,0 +1,324 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::models::subscriber::SubscriberStatus;
+
+/// キャンペーンの配信対象（オーディエンス）の定義
+///
+/// 各項目はすべて AND で結合される。配信停止・バウンス・苦情の購読者は定義にかかわらず対象外。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudienceDefinition {
+    /// いずれかのタグを持つ購読者を対象にする（空の場合はタグで絞り込まない）
+    pub include_tags: Vec<String>,
+    /// いずれかのタグを持つ購読者を除外する
+    pub exclude_tags: Vec<String>,
+    /// 対象にする購読者ステータス（空の場合は送信可能なすべてのステータス）
+    pub statuses: Vec<SubscriberStatus>,
+    /// カスタムフィールドの条件
+    pub conditions: Vec<CustomFieldCondition>,
+    /// カスタムフィールドの条件の結合方法
+    pub condition_match: ConditionMatch,
+    /// 保存済みセグメントに一致する購読者に限定する
+    pub segment_id: Option<Uuid>,
+}
+
+/// 条件の結合方法
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConditionMatch {
+    /// すべての条件を満たす
+    #[default]
+    All,
+    /// いずれかの条件を満たす
+    Any,
+}
+
+/// カスタムフィールド（JSONB）に対する条件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomFieldCondition {
+    pub field: String,
+    pub operator: ConditionOperator,
+    #[serde(default)]
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConditionOperator {
+    Equals,
+    NotEquals,
+    Contains,
+    NotContains,
+    GreaterThan,
+    GreaterThanOrEqual,
+    LessThan,
+    LessThanOrEqual,
+    In,
+    NotIn,
+    Exists,
+    NotExists,
+}
+
+impl ConditionOperator {
+    /// 大小比較の演算子の場合はSQLの比較演算子
+    pub fn comparison_sql(&self) -> Option<&'static str> {
+        match self {
+            ConditionOperator::GreaterThan => Some(">"),
+            ConditionOperator::GreaterThanOrEqual => Some(">="),
+            ConditionOperator::LessThan => Some("<"),
+            ConditionOperator::LessThanOrEqual => Some("<="),
+            _ => None,
+        }
+    }
+}
+
+impl CustomFieldCondition {
+    /// 比較値をテキストとして取得（JSONの文字列・数値・真偽値）
+    pub fn value_text(&self) -> Option<String> {
+        scalar_text(&self.value)
+    }
+
+    /// `in`・`not_in`の比較値をテキストの配列として取得
+    pub fn value_texts(&self) -> Option<Vec<String>> {
+        match &self.value {
+            Value::Array(values) => values.iter().map(scalar_text).collect(),
+            _ => None,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.field.trim().is_empty() {
+            return Err("カスタムフィールド名を指定してください".to_string());
+        }
+
+        let valid = match self.operator {
+            ConditionOperator::Exists | ConditionOperator::NotExists => true,
+            ConditionOperator::In | ConditionOperator::NotIn => self.value_texts().is_some(),
+            ConditionOperator::GreaterThan
+            | ConditionOperator::GreaterThanOrEqual
+            | ConditionOperator::LessThan
+            | ConditionOperator::LessThanOrEqual => {
+                matches!(self.value, Value::Number(_) | Value::String(_))
+            }
+            _ => self.value_text().is_some(),
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(format!(
+                "カスタムフィールド「{}」の条件の値が不正です",
+                self.field
+            ))
+        }
+    }
+
+    /// JSONオブジェクトの値が条件を満たすか（SQLでの評価と同じ規則）
+    pub fn matches(&self, fields: &Value) -> bool {
+        let field = fields.get(&self.field);
+        let text = field.and_then(|v| match v {
+            Value::Null => None,
+            Value::Array(_) | Value::Object(_) => Some(v.to_string()),
+            _ => scalar_text(v),
+        });
+
+        match self.operator {
+            ConditionOperator::Exists => field.is_some(),
+            ConditionOperator::NotExists => field.is_none(),
+            ConditionOperator::In | ConditionOperator::NotIn => {
+                let Some(values) = self.value_texts() else {
+                    return false;
+                };
+                let contained = text.is_some_and(|t| values.contains(&t));
+                contained == (self.operator == ConditionOperator::In)
+            }
+            ConditionOperator::GreaterThan
+            | ConditionOperator::GreaterThanOrEqual
+            | ConditionOperator::LessThan
+            | ConditionOperator::LessThanOrEqual => {
+                let ordering = match (&self.value, field) {
+                    (Value::Number(expected), Some(Value::Number(actual))) => actual
+                        .as_f64()
+                        .zip(expected.as_f64())
+                        .and_then(|(a, e)| a.partial_cmp(&e)),
+                    (Value::String(expected), _) => text.map(|t| t.as_str().cmp(expected)),
+                    _ => None,
+                };
+                ordering.is_some_and(|ordering| match self.operator {
+                    ConditionOperator::GreaterThan => ordering.is_gt(),
+                    ConditionOperator::GreaterThanOrEqual => ordering.is_ge(),
+                    ConditionOperator::LessThan => ordering.is_lt(),
+                    _ => ordering.is_le(),
+                })
+            }
+            operator => {
+                let Some(value) = self.value_text() else {
+                    return false;
+                };
+                match operator {
+                    ConditionOperator::Equals => text == Some(value),
+                    ConditionOperator::NotEquals => text != Some(value),
+                    ConditionOperator::Contains => {
+                        text.is_some_and(|t| t.to_lowercase().contains(&value.to_lowercase()))
+                    }
+                    ConditionOperator::NotContains => {
+                        !text.is_some_and(|t| t.to_lowercase().contains(&value.to_lowercase()))
+                    }
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+impl AudienceDefinition {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(status) = self.statuses.iter().find(|s| !s.is_sendable()) {
+            return Err(format!(
+                "ステータス「{}」の購読者は配信対象に含められません",
+                status.as_str()
+            ));
+        }
+
+        for condition in &self.conditions {
+            condition.validate()?;
+        }
+
+        Ok(())
+    }
+}
+
+fn scalar_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_empty_definition_deserializes_to_default() {
+        let audience: AudienceDefinition = serde_json::from_value(json!({})).unwrap();
+        assert_eq!(audience, AudienceDefinition::default());
+        assert_eq!(audience.condition_match, ConditionMatch::All);
+    }
+
+    #[test]
+    fn test_validate_rejects_unsendable_statuses() {
+        let audience = AudienceDefinition {
+            statuses: vec![SubscriberStatus::Active, SubscriberStatus::Bounced],
+            ..Default::default()
+        };
+        assert!(audience.validate().is_err());
+
+        let audience = AudienceDefinition {
+            statuses: vec![SubscriberStatus::Active],
+            ..Default::default()
+        };
+        assert!(audience.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_condition_values() {
+        let condition = |operator, value| CustomFieldCondition {
+            field: "plan".to_string(),
+            operator,
+            value,
+        };
+
+        assert!(condition(ConditionOperator::Equals, json!("pro"))
+            .validate()
+            .is_ok());
+        assert!(condition(ConditionOperator::Equals, json!(null))
+            .validate()
+            .is_err());
+        assert!(condition(ConditionOperator::In, json!(["pro", 1, true]))
+            .validate()
+            .is_ok());
+        assert!(condition(ConditionOperator::In, json!("pro"))
+            .validate()
+            .is_err());
+        assert!(condition(ConditionOperator::GreaterThan, json!(10))
+            .validate()
+            .is_ok());
+        assert!(condition(ConditionOperator::GreaterThan, json!(true))
+            .validate()
+            .is_err());
+        assert!(condition(ConditionOperator::Exists, json!(null))
+            .validate()
+            .is_ok());
+
+        let mut unnamed = condition(ConditionOperator::Exists, json!(null));
+        unnamed.field = " ".to_string();
+        assert!(unnamed.validate().is_err());
+    }
+
+    #[test]
+    fn test_value_text() {
+        let condition = CustomFieldCondition {
+            field: "age".to_string(),
+            operator: ConditionOperator::Equals,
+            value: json!(30),
+        };
+        assert_eq!(condition.value_text().as_deref(), Some("30"));
+    }
+
+    #[test]
+    fn test_condition_matches_fields() {
+        let fields = json!({"plan": "Pro", "age": 30});
+        let matches = |field: &str, operator, value| {
+            CustomFieldCondition {
+                field: field.to_string(),
+                operator,
+                value,
+            }
+            .matches(&fields)
+        };
+
+        assert!(matches("plan", ConditionOperator::Equals, json!("Pro")));
+        assert!(!matches("plan", ConditionOperator::Equals, json!("pro")));
+        assert!(matches("plan", ConditionOperator::Contains, json!("pr")));
+        assert!(matches(
+            "plan",
+            ConditionOperator::In,
+            json!(["Free", "Pro"])
+        ));
+        assert!(!matches("plan", ConditionOperator::NotIn, json!(["Pro"])));
+        assert!(matches("plan", ConditionOperator::Exists, json!(null)));
+        assert!(matches("age", ConditionOperator::GreaterThan, json!(20)));
+        assert!(matches(
+            "age",
+            ConditionOperator::LessThanOrEqual,
+            json!(30)
+        ));
+        assert!(!matches("age", ConditionOperator::LessThan, json!(30)));
+
+        // 存在しないフィールドは否定の条件にのみ一致する
+        assert!(matches(
+            "missing",
+            ConditionOperator::NotExists,
+            json!(null)
+        ));
+        assert!(!matches("missing", ConditionOperator::Equals, json!("x")));
+        assert!(matches("missing", ConditionOperator::NotEquals, json!("x")));
+        assert!(matches(
+            "missing",
+            ConditionOperator::NotContains,
+            json!("x")
+        ));
+        assert!(matches("missing", ConditionOperator::NotIn, json!(["x"])));
+        assert!(!matches(
+            "missing",
+            ConditionOperator::GreaterThan,
+            json!(1)
+        ));
+    }
+}
@@ -1,15 +1,19 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+use sqlx::{types::Json, FromRow};
 use std::fmt;
 use uuid::Uuid;
 use validator::Validate;
 
+use crate::models::audience::AudienceDefinition;
+
 #[derive(Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
 pub struct Campaign {
     pub id: Uuid,
     pub user_id: Uuid,
     pub template_id: Uuid,
+    /// 配信時に固定したテンプレートのリビジョン
+    pub template_revision_id: Option<Uuid>,
     pub name: String,
     pub description: Option<String>,
     pub subject: String,
@@ -20,6 +24,9 @@ pub struct Campaign {
     pub sent_count: i32,
     pub opened_count: i32,
     pub clicked_count: i32,
+    pub audience: Json<AudienceDefinition>,
+    pub from_email: Option<String>,
+    pub from_name: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -100,6 +107,17 @@ pub struct CreateCampaignRequest {
     pub subject: String,
 
     pub template_id: Uuid,
+
+    /// 配信対象の定義（未指定の場合は送信可能な全購読者）
+    #[serde(default)]
+    pub audience: Option<AudienceDefinition>,
+
+    /// 送信元アドレス（認証済みの送信ドメインのみ。未指定の場合はプラットフォームの送信元）
+    #[serde(default)]
+    pub from_email: Option<String>,
+
+    #[serde(default)]
+    pub from_name: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Validate)]
@@ -121,6 +139,17 @@ pub struct UpdateCampaignRequest {
     pub status: Option<CampaignStatus>,
 
     pub scheduled_at: Option<DateTime<Utc>>,
+
+    #[serde(default)]
+    pub audience: Option<AudienceDefinition>,
+
+    /// 空文字の場合はプラットフォームの送信元に戻す
+    #[serde(default)]
+    pub from_email: Option<String>,
+
+    /// 空文字の場合は送信者名を解除する
+    #[serde(default)]
+    pub from_name: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -137,6 +166,12 @@ pub struct ListCampaignOptions {
     pub sort_order: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CampaignSubscribersQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CampaignListResponse {
     pub campaigns: Vec<CampaignResponse>,
@@ -149,6 +184,7 @@ pub struct CampaignListResponse {
 pub struct CampaignResponse {
     pub id: Uuid,
     pub template_id: Uuid,
+    pub template_revision_id: Option<Uuid>,
     pub name: String,
     pub description: Option<String>,
     pub subject: String,
@@ -156,6 +192,9 @@ pub struct CampaignResponse {
     pub scheduled_at: Option<DateTime<Utc>>,
     pub sent_at: Option<DateTime<Utc>>,
     pub stats: CampaignStats,
+    pub audience: AudienceDefinition,
+    pub from_email: Option<String>,
+    pub from_name: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -187,6 +226,7 @@ impl From<Campaign> for CampaignResponse {
         Self {
             id: campaign.id,
             template_id: campaign.template_id,
+            template_revision_id: campaign.template_revision_id,
             name: campaign.name,
             description: campaign.description,
             subject: campaign.subject,
@@ -201,12 +241,90 @@ impl From<Campaign> for CampaignResponse {
                 open_rate,
                 click_rate,
             },
+            audience: campaign.audience.0,
+            from_email: campaign.from_email,
+            from_name: campaign.from_name,
             created_at: campaign.created_at,
             updated_at: campaign.updated_at,
         }
     }
 }
 
+/// 購読者ごとのキャンペーン配信レコード
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct CampaignDelivery {
+    pub id: Uuid,
+    pub campaign_id: Uuid,
+    pub subscriber_id: Uuid,
+    pub email: String,
+    pub status: String,
+    pub attempts: i32,
+    pub message_id: Option<String>,
+    pub error: Option<String>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub locked_at: Option<DateTime<Utc>>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub bounced_at: Option<DateTime<Utc>>,
+    pub bounce_type: Option<String>,
+    pub complained_at: Option<DateTime<Utc>>,
+    pub variant_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending,
+    Sending,
+    Sent,
+    Failed,
+    Skipped,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Sending => "sending",
+            DeliveryStatus::Sent => "sent",
+            DeliveryStatus::Failed => "failed",
+            DeliveryStatus::Skipped => "skipped",
+        }
+    }
+}
+
+impl From<String> for DeliveryStatus {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "sending" => DeliveryStatus::Sending,
+            "sent" => DeliveryStatus::Sent,
+            "failed" => DeliveryStatus::Failed,
+            "skipped" => DeliveryStatus::Skipped,
+            _ => DeliveryStatus::Pending,
+        }
+    }
+}
+
+/// キャンペーン配信状況の集計
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, Default, PartialEq)]
+pub struct DeliveryCounts {
+    pub total: i64,
+    pub pending: i64,
+    pub sending: i64,
+    pub sent: i64,
+    pub failed: i64,
+    pub skipped: i64,
+}
+
+impl DeliveryCounts {
+    /// 未処理（送信待ち・送信中）の配信が残っているか
+    pub fn has_unfinished(&self) -> bool {
+        self.pending > 0 || self.sending > 0
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -229,6 +347,7 @@ mod tests {
             id: Uuid::new_v4(),
             user_id: Uuid::new_v4(),
             template_id: Uuid::new_v4(),
+            template_revision_id: None,
             name: "Test Campaign".to_string(),
             description: Some("Test Description".to_string()),
             subject: "Test Subject".to_string(),
@@ -239,6 +358,9 @@ mod tests {
             sent_count: 50,
             opened_count: 25,
             clicked_count: 10,
+            audience: Json(AudienceDefinition::default()),
+            from_email: None,
+            from_name: None,
             created_at: now - Duration::hours(1),
             updated_at: now,
         };
@@ -270,6 +392,7 @@ mod tests {
             id: Uuid::new_v4(),
             user_id: Uuid::new_v4(),
             template_id: Uuid::new_v4(),
+            template_revision_id: None,
             name: "Test Campaign".to_string(),
             description: None,
             subject: "Test Subject".to_string(),
@@ -280,6 +403,9 @@ mod tests {
             sent_count: 0, // 送信数0
             opened_count: 0,
             clicked_count: 0,
+            audience: Json(AudienceDefinition::default()),
+            from_email: None,
+            from_name: None,
             created_at: now,
             updated_at: now,
         };
@@ -299,6 +425,9 @@ mod tests {
             description: Some("Valid description".to_string()),
             subject: "Valid subject".to_string(),
             template_id: Uuid::new_v4(),
+            audience: None,
+            from_email: None,
+            from_name: None,
         };
         assert!(valid_request.validate().is_ok());
 
@@ -308,6 +437,9 @@ mod tests {
             description: Some("Valid description".to_string()),
             subject: "Valid subject".to_string(),
             template_id: Uuid::new_v4(),
+            audience: None,
+            from_email: None,
+            from_name: None,
         };
         assert!(invalid_name_request.validate().is_err());
 
@@ -317,6 +449,9 @@ mod tests {
             description: Some("Valid description".to_string()),
             subject: "".to_string(),
             template_id: Uuid::new_v4(),
+            audience: None,
+            from_email: None,
+            from_name: None,
         };
         assert!(invalid_subject_request.validate().is_err());
     }
@@ -20,6 +20,76 @@ pub struct Form {
     pub updated_at: DateTime<Utc>,
 }
 
+impl Form {
+    /// フォームの設定（不正な値や未知の項目は無視する）
+    pub fn settings(&self) -> FormSettings {
+        serde_json::from_value(self.settings.clone()).unwrap_or_default()
+    }
+}
+
+/// フォームの設定（`forms.settings`のうちバックエンドで使う項目）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FormSettings {
+    /// ダブルオプトイン（確認メールのリンクを開くまで購読者を確認待ちにする）
+    pub require_confirmation: bool,
+    /// 確認メールのテンプレート（未指定の場合は標準の文面）
+    pub confirmation_template_id: Option<Uuid>,
+    /// 確認メールの件名（未指定の場合はテンプレートの件名）
+    pub confirmation_subject: Option<String>,
+    /// 確認後のリダイレクト先（未指定の場合はJSONで結果を返す）
+    pub confirmation_redirect_url: Option<String>,
+    /// ハニーポット項目の名前（未指定の場合は`_gotcha`）
+    pub honeypot_field: Option<String>,
+    /// 公開フォームの表示から送信までに必要な秒数（未指定の場合は3秒、0で確認しない）
+    pub min_submit_seconds: Option<u32>,
+    /// 送信ボタンの文言
+    pub submit_button_text: Option<String>,
+    /// 送信後に表示するメッセージ（マークダウン）
+    pub success_message: Option<String>,
+    /// 送信後のリダイレクト先（指定した場合はメッセージを表示せずにリダイレクトする）
+    pub success_redirect_url: Option<String>,
+}
+
+/// 送信データに含める送信トークンの項目名
+pub const FORM_TOKEN_FIELD: &str = "_token";
+/// ハニーポット項目の標準の名前
+pub const DEFAULT_HONEYPOT_FIELD: &str = "_gotcha";
+/// 表示から送信までに必要な標準の秒数
+pub const DEFAULT_MIN_SUBMIT_SECONDS: u32 = 3;
+
+impl FormSettings {
+    pub fn honeypot_field(&self) -> &str {
+        self.honeypot_field
+            .as_deref()
+            .unwrap_or(DEFAULT_HONEYPOT_FIELD)
+    }
+
+    pub fn min_submit_seconds(&self) -> u32 {
+        self.min_submit_seconds
+            .unwrap_or(DEFAULT_MIN_SUBMIT_SECONDS)
+    }
+}
+
+/// 公開フォームの送信トークン（表示から送信までの時間を確認する）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormTokenClaims {
+    /// フォームID
+    pub sub: Uuid,
+    /// 発行日時（UNIX秒）
+    pub iat: i64,
+    /// トークンの用途（他の署名付きトークンとの取り違え防止）
+    pub purpose: String,
+}
+
+/// 公開フォームの表示内容（送信時に返す送信トークン付き）
+#[derive(Debug, Clone, Serialize)]
+pub struct PublicForm {
+    #[serde(flatten)]
+    pub form: Form,
+    pub form_token: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateFormRequest {
     pub name: String,
@@ -44,11 +114,15 @@ pub struct UpdateFormRequest {
 pub struct FormField {
     pub field_type: String,
     pub name: String,
+    #[serde(default)]
     pub label: String,
     pub placeholder: Option<String>,
+    #[serde(default)]
     pub required: bool,
+    /// 入力値の検証ルール（`FieldValidationRules`）
     pub validation_rules: Option<JsonValue>,
     pub options: Option<JsonValue>,
+    #[serde(default)]
     pub display_order: i32,
 }
 
@@ -61,12 +135,96 @@ pub struct FormSubmission {
     pub ip_address: Option<String>,
     pub user_agent: Option<String>,
     pub referrer: Option<String>,
+    /// 確認メールのリンクにのみ含める（送信者が確認をスキップできないようにレスポンスには含めない）
+    #[serde(skip_serializing)]
     pub confirmation_token: Option<String>,
     pub confirmed_at: Option<DateTime<Utc>>,
+    /// `accepted`または`quarantined`（`FormSubmissionStatus`）
+    pub status: String,
+    /// 隔離した理由（`QuarantineReason`）
+    pub quarantine_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// フォーム送信データの状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FormSubmissionStatus {
+    /// 受け付け済み
+    Accepted,
+    /// スパムの疑いがあるため購読者を作成せずに保存した
+    Quarantined,
+}
+
+impl FormSubmissionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FormSubmissionStatus::Accepted => "accepted",
+            FormSubmissionStatus::Quarantined => "quarantined",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "accepted" => Some(FormSubmissionStatus::Accepted),
+            "quarantined" => Some(FormSubmissionStatus::Quarantined),
+            _ => None,
+        }
+    }
+}
+
+/// フォーム送信を隔離した理由
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuarantineReason {
+    /// ハニーポット項目に入力があった
+    Honeypot,
+    /// 送信トークンがない
+    MissingToken,
+    /// 送信トークンが不正、または別のフォームのもの
+    InvalidToken,
+    /// 送信トークンの有効期限切れ
+    ExpiredToken,
+    /// 表示から送信までが短すぎる
+    TooFast,
+}
+
+impl QuarantineReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuarantineReason::Honeypot => "honeypot",
+            QuarantineReason::MissingToken => "missing_token",
+            QuarantineReason::InvalidToken => "invalid_token",
+            QuarantineReason::ExpiredToken => "expired_token",
+            QuarantineReason::TooFast => "too_fast",
+        }
+    }
+}
+
+/// 公開フォームの送信結果（隔離したかどうかは送信者に伝えない）
+#[derive(Debug, Clone, Serialize)]
+pub struct FormSubmissionReceipt {
+    pub id: Uuid,
+    pub form_id: Uuid,
+    pub data: JsonValue,
+    pub confirmed_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
 }
 
+impl From<FormSubmission> for FormSubmissionReceipt {
+    fn from(submission: FormSubmission) -> Self {
+        Self {
+            id: submission.id,
+            form_id: submission.form_id,
+            data: submission.data,
+            confirmed_at: submission.confirmed_at,
+            created_at: submission.created_at,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateFormSubmissionRequest {
+    /// 入力内容（送信トークン`_token`とハニーポット項目を含む）
     pub data: JsonValue,
 }
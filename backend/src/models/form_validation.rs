@@ -0,0 +1,510 @@
+use std::collections::HashSet;
+
+use chrono::{NaiveDate, NaiveTime};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::models::form::FormField;
+
+/// 電話番号の桁数（E.164の上限は15桁）
+const PHONE_MIN_DIGITS: usize = 7;
+const PHONE_MAX_DIGITS: usize = 15;
+
+/// 入力値の検証ルール（`FormField.validation_rules`）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FieldValidationRules {
+    /// 最小文字数
+    pub min_length: Option<usize>,
+    /// 最大文字数
+    pub max_length: Option<usize>,
+    /// 数値の最小値
+    pub min: Option<f64>,
+    /// 数値の最大値
+    pub max: Option<f64>,
+    /// 入力値全体が一致する正規表現
+    pub pattern: Option<String>,
+    /// 正規表現に一致しない場合のメッセージ
+    pub pattern_message: Option<String>,
+}
+
+/// 検証エラーの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldErrorCode {
+    Required,
+    InvalidType,
+    InvalidFormat,
+    TooShort,
+    TooLong,
+    TooSmall,
+    TooLarge,
+    PatternMismatch,
+    InvalidOption,
+}
+
+/// フィールドごとの検証エラー
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub code: FieldErrorCode,
+    pub message: String,
+}
+
+/// 検証ルールをコンパイルしたフィールド
+pub struct FormSchema {
+    fields: Vec<(FormField, FieldValidationRules, Option<Regex>)>,
+}
+
+impl FormSchema {
+    /// `forms.form_fields`を読み込み、フィールド定義と検証ルールを検証する
+    pub fn parse(form_fields: &Value) -> Result<Self, String> {
+        let fields: Vec<FormField> = match form_fields {
+            Value::Null => Vec::new(),
+            value => serde_json::from_value(value.clone())
+                .map_err(|e| format!("フォームのフィールド定義が不正です: {e}"))?,
+        };
+
+        let mut names = HashSet::new();
+        let mut compiled = Vec::with_capacity(fields.len());
+        for field in fields {
+            let name = field.name.trim();
+            if name.is_empty() {
+                return Err("フィールド名を指定してください".to_string());
+            }
+            if !names.insert(name.to_string()) {
+                return Err(format!("フィールド名「{name}」が重複しています"));
+            }
+
+            let rules: FieldValidationRules = match &field.validation_rules {
+                None | Some(Value::Null) => FieldValidationRules::default(),
+                Some(rules) => serde_json::from_value(rules.clone())
+                    .map_err(|e| format!("フィールド「{name}」の検証ルールが不正です: {e}"))?,
+            };
+            if let (Some(min), Some(max)) = (rules.min_length, rules.max_length) {
+                if min > max {
+                    return Err(format!(
+                        "フィールド「{name}」の最小文字数が最大文字数を超えています"
+                    ));
+                }
+            }
+            if let (Some(min), Some(max)) = (rules.min, rules.max) {
+                if min > max {
+                    return Err(format!(
+                        "フィールド「{name}」の最小値が最大値を超えています"
+                    ));
+                }
+            }
+            let pattern = rules
+                .pattern
+                .as_deref()
+                .filter(|pattern| !pattern.is_empty())
+                .map(|pattern| Regex::new(&format!("^(?:{pattern})$")))
+                .transpose()
+                .map_err(|e| format!("フィールド「{name}」の正規表現が不正です: {e}"))?;
+
+            if is_choice(&field.field_type) && field.option_values().is_empty() {
+                return Err(format!("フィールド「{name}」の選択肢を指定してください"));
+            }
+
+            compiled.push((field, rules, pattern));
+        }
+
+        Ok(Self { fields: compiled })
+    }
+
+    /// フィールド定義（表示順）
+    pub fn fields(&self) -> Vec<&FormField> {
+        let mut fields: Vec<&FormField> = self.fields.iter().map(|(field, _, _)| field).collect();
+        fields.sort_by_key(|field| field.display_order);
+        fields
+    }
+
+    /// HTMLフォームの送信内容（`application/x-www-form-urlencoded`）を送信データに変換する
+    ///
+    /// チェックボックスは選択肢がなければ真偽値、あれば選択した値の配列にする。定義外の項目
+    /// （送信トークンやハニーポット項目など）は文字列のまま残す。
+    pub fn data_from_pairs(&self, pairs: &[(String, String)]) -> Value {
+        let mut data = Map::new();
+        for (name, value) in pairs {
+            let field = self
+                .fields
+                .iter()
+                .map(|(field, _, _)| field)
+                .find(|field| &field.name == name);
+            match field {
+                Some(field) if field.field_type == "checkbox" => {
+                    if field.option_values().is_empty() {
+                        data.insert(name.clone(), Value::Bool(true));
+                    } else if let Value::Array(selected) = data
+                        .entry(name.clone())
+                        .or_insert_with(|| Value::Array(Vec::new()))
+                    {
+                        selected.push(Value::String(value.clone()));
+                    }
+                }
+                _ => {
+                    data.insert(name.clone(), Value::String(value.clone()));
+                }
+            }
+        }
+        Value::Object(data)
+    }
+
+    /// 送信データを検証し、定義されたフィールドの値だけを返す（文字列は前後の空白を除去）
+    pub fn validate(&self, data: &Value) -> Result<Value, Vec<FieldError>> {
+        let empty = Map::new();
+        let data = data.as_object().unwrap_or(&empty);
+
+        let mut values = Map::new();
+        let mut errors = Vec::new();
+        for (field, rules, pattern) in &self.fields {
+            let value = normalize(data.get(&field.name).unwrap_or(&Value::Null));
+            if is_empty(&value) {
+                if field.required {
+                    errors.push(field.error(FieldErrorCode::Required, "入力してください"));
+                }
+                continue;
+            }
+
+            match field.check(&value, rules, pattern.as_ref()) {
+                Ok(()) => {
+                    values.insert(field.name.clone(), value);
+                }
+                Err(error) => errors.push(error),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(Value::Object(values))
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl FormField {
+    /// 選択肢の値（`[{"value": ..., "label": ...}]`または文字列の配列）
+    pub fn option_values(&self) -> Vec<String> {
+        self.option_entries()
+            .into_iter()
+            .map(|(value, _)| value)
+            .collect()
+    }
+
+    /// 選択肢の値と表示名（表示名がない場合は値）
+    pub fn option_entries(&self) -> Vec<(String, String)> {
+        let Some(Value::Array(options)) = &self.options else {
+            return Vec::new();
+        };
+
+        options
+            .iter()
+            .filter_map(|option| match option {
+                Value::String(value) => Some((value.clone(), value.clone())),
+                Value::Object(option) => {
+                    let value = option
+                        .get("value")
+                        .or_else(|| option.get("label"))
+                        .and_then(Value::as_str)?;
+                    let label = option.get("label").and_then(Value::as_str).unwrap_or(value);
+                    Some((value.to_string(), label.to_string()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn error(&self, code: FieldErrorCode, message: &str) -> FieldError {
+        FieldError {
+            field: self.name.clone(),
+            code,
+            message: message.to_string(),
+        }
+    }
+
+    fn check(
+        &self,
+        value: &Value,
+        rules: &FieldValidationRules,
+        pattern: Option<&Regex>,
+    ) -> Result<(), FieldError> {
+        match self.field_type.as_str() {
+            "number" => {
+                let number = match value {
+                    Value::Number(n) => n.as_f64(),
+                    Value::String(s) => s.parse::<f64>().ok().filter(|n| n.is_finite()),
+                    _ => None,
+                }
+                .ok_or_else(|| {
+                    self.error(FieldErrorCode::InvalidFormat, "数値を入力してください")
+                })?;
+
+                if let Some(min) = rules.min.filter(|min| number < *min) {
+                    return Err(self.error(
+                        FieldErrorCode::TooSmall,
+                        &format!("{min}以上の値を入力してください"),
+                    ));
+                }
+                if let Some(max) = rules.max.filter(|max| number > *max) {
+                    return Err(self.error(
+                        FieldErrorCode::TooLarge,
+                        &format!("{max}以下の値を入力してください"),
+                    ));
+                }
+                Ok(())
+            }
+            "checkbox" => match value {
+                Value::Bool(_) => Ok(()),
+                // 選択肢のあるチェックボックスは選択した値の配列
+                Value::Array(selected) => {
+                    let options = self.option_values();
+                    if selected
+                        .iter()
+                        .all(|v| v.as_str().is_some_and(|v| options.iter().any(|o| o == v)))
+                    {
+                        Ok(())
+                    } else {
+                        Err(self.error(FieldErrorCode::InvalidOption, "選択肢から選んでください"))
+                    }
+                }
+                _ => Err(self.error(FieldErrorCode::InvalidType, "値が不正です")),
+            },
+            "select" | "radio" => {
+                let options = self.option_values();
+                match value.as_str() {
+                    Some(v) if options.iter().any(|o| o == v) => Ok(()),
+                    _ => Err(self.error(FieldErrorCode::InvalidOption, "選択肢から選んでください")),
+                }
+            }
+            "file" => Ok(()),
+            field_type => {
+                let text = value
+                    .as_str()
+                    .ok_or_else(|| self.error(FieldErrorCode::InvalidType, "値が不正です"))?;
+                self.check_text(field_type, text, rules, pattern)
+            }
+        }
+    }
+
+    fn check_text(
+        &self,
+        field_type: &str,
+        text: &str,
+        rules: &FieldValidationRules,
+        pattern: Option<&Regex>,
+    ) -> Result<(), FieldError> {
+        let format_error = match field_type {
+            "email" => (!validator::validate_email(text))
+                .then_some("メールアドレスの形式が正しくありません"),
+            "url" => (!is_http_url(text)).then_some("URLの形式が正しくありません"),
+            "tel" | "phone" => {
+                (!is_phone_number(text)).then_some("電話番号の形式が正しくありません")
+            }
+            "date" => NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .is_err()
+                .then_some("日付の形式が正しくありません"),
+            "time" => (NaiveTime::parse_from_str(text, "%H:%M").is_err()
+                && NaiveTime::parse_from_str(text, "%H:%M:%S").is_err())
+            .then_some("時刻の形式が正しくありません"),
+            _ => None,
+        };
+        if let Some(message) = format_error {
+            return Err(self.error(FieldErrorCode::InvalidFormat, message));
+        }
+
+        let length = text.chars().count();
+        if let Some(min) = rules.min_length.filter(|min| length < *min) {
+            return Err(self.error(
+                FieldErrorCode::TooShort,
+                &format!("{min}文字以上で入力してください"),
+            ));
+        }
+        if let Some(max) = rules.max_length.filter(|max| length > *max) {
+            return Err(self.error(
+                FieldErrorCode::TooLong,
+                &format!("{max}文字以内で入力してください"),
+            ));
+        }
+
+        if pattern.is_some_and(|pattern| !pattern.is_match(text)) {
+            let message = rules
+                .pattern_message
+                .as_deref()
+                .unwrap_or("入力形式が正しくありません");
+            return Err(self.error(FieldErrorCode::PatternMismatch, message));
+        }
+
+        Ok(())
+    }
+}
+
+fn is_choice(field_type: &str) -> bool {
+    matches!(field_type, "select" | "radio")
+}
+
+/// 文字列の前後の空白を除去する
+fn normalize(value: &Value) -> Value {
+    match value {
+        Value::String(s) => Value::String(s.trim().to_string()),
+        value => value.clone(),
+    }
+}
+
+/// 未入力とみなす値（チェックされていないチェックボックスを含む）
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null | Value::Bool(false) => true,
+        Value::String(s) => s.is_empty(),
+        Value::Array(values) => values.is_empty(),
+        _ => false,
+    }
+}
+
+fn is_http_url(text: &str) -> bool {
+    (text.starts_with("https://") || text.starts_with("http://")) && validator::validate_url(text)
+}
+
+/// 数字・空白・ハイフン・括弧・先頭の`+`で構成された電話番号
+fn is_phone_number(text: &str) -> bool {
+    let body = text.strip_prefix('+').unwrap_or(text);
+    let digits = body.chars().filter(char::is_ascii_digit).count();
+    body.chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, ' ' | '-' | '(' | ')' | '.'))
+        && (PHONE_MIN_DIGITS..=PHONE_MAX_DIGITS).contains(&digits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn codes(schema: &FormSchema, data: Value) -> Vec<(String, FieldErrorCode)> {
+        match schema.validate(&data) {
+            Ok(_) => Vec::new(),
+            Err(errors) => errors.into_iter().map(|e| (e.field, e.code)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_schema() {
+        assert!(FormSchema::parse(&json!([
+            {"field_type": "text", "name": "a", "label": "A"},
+            {"field_type": "text", "name": "a", "label": "A"}
+        ]))
+        .is_err());
+        assert!(FormSchema::parse(&json!([
+            {"field_type": "text", "name": "a", "label": "A", "validation_rules": {"pattern": "("}}
+        ]))
+        .is_err());
+        assert!(FormSchema::parse(&json!([
+            {"field_type": "text", "name": "a", "label": "A", "validation_rules": {"min_length": 5, "max_length": 2}}
+        ]))
+        .is_err());
+        assert!(FormSchema::parse(&json!([
+            {"field_type": "select", "name": "a", "label": "A", "options": []}
+        ]))
+        .is_err());
+        assert!(FormSchema::parse(&json!({})).is_err());
+        assert!(FormSchema::parse(&Value::Null).is_ok());
+    }
+
+    #[test]
+    fn test_validate_formats_and_rules() {
+        let schema = FormSchema::parse(&json!([
+            {"field_type": "email", "name": "email", "label": "メール", "required": true},
+            {"field_type": "tel", "name": "phone", "label": "電話"},
+            {"field_type": "url", "name": "site", "label": "サイト"},
+            {"field_type": "number", "name": "age", "label": "年齢", "validation_rules": {"min": 18, "max": 120}},
+            {"field_type": "text", "name": "code", "label": "コード", "validation_rules": {"pattern": "[A-Z]{3}", "pattern_message": "英大文字3桁"}},
+            {"field_type": "textarea", "name": "note", "label": "備考", "validation_rules": {"min_length": 2, "max_length": 5}},
+            {"field_type": "select", "name": "plan", "label": "プラン", "options": [{"value": "free", "label": "無料"}, {"value": "pro", "label": "有料"}]},
+            {"field_type": "checkbox", "name": "agree", "label": "同意", "required": true}
+        ]))
+        .unwrap();
+
+        let valid = schema
+            .validate(&json!({
+                "email": " taro@example.com ",
+                "phone": "+81 90-1234-5678",
+                "site": "https://example.com",
+                "age": "30",
+                "code": "ABC",
+                "note": "あいうえ",
+                "plan": "pro",
+                "agree": true,
+                "unknown": "dropped"
+            }))
+            .unwrap();
+        assert_eq!(valid["email"], "taro@example.com");
+        assert!(valid.get("unknown").is_none());
+
+        assert_eq!(
+            codes(
+                &schema,
+                json!({
+                    "email": "taro",
+                    "phone": "電話",
+                    "site": "javascript:alert(1)",
+                    "age": 10,
+                    "code": "ABCD",
+                    "note": "あいうえおか",
+                    "plan": "enterprise",
+                    "agree": false
+                })
+            ),
+            vec![
+                ("email".to_string(), FieldErrorCode::InvalidFormat),
+                ("phone".to_string(), FieldErrorCode::InvalidFormat),
+                ("site".to_string(), FieldErrorCode::InvalidFormat),
+                ("age".to_string(), FieldErrorCode::TooSmall),
+                ("code".to_string(), FieldErrorCode::PatternMismatch),
+                ("note".to_string(), FieldErrorCode::TooLong),
+                ("plan".to_string(), FieldErrorCode::InvalidOption),
+                ("agree".to_string(), FieldErrorCode::Required),
+            ]
+        );
+
+        assert_eq!(
+            codes(
+                &schema,
+                json!({"email": ["taro@example.com"], "agree": "yes", "note": "a"})
+            ),
+            vec![
+                ("email".to_string(), FieldErrorCode::InvalidType),
+                ("note".to_string(), FieldErrorCode::TooShort),
+                ("agree".to_string(), FieldErrorCode::InvalidType),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_data_from_pairs() {
+        let schema = FormSchema::parse(&json!([
+            {"field_type": "email", "name": "email", "label": "メール"},
+            {"field_type": "checkbox", "name": "agree", "label": "同意"},
+            {"field_type": "checkbox", "name": "topics", "label": "興味", "options": ["news", "events"]}
+        ]))
+        .unwrap();
+        let pairs = [
+            ("email", "taro@example.com"),
+            ("agree", "true"),
+            ("topics", "news"),
+            ("topics", "events"),
+            ("_token", "token"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+
+        assert_eq!(
+            schema.data_from_pairs(&pairs),
+            json!({
+                "email": "taro@example.com",
+                "agree": true,
+                "topics": ["news", "events"],
+                "_token": "token"
+            })
+        );
+        assert_eq!(schema.data_from_pairs(&[]), json!({}));
+    }
+}
@@ -0,0 +1,118 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+/// GitHub連携の接続情報
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct GitHubConnection {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    #[serde(skip_serializing)]
+    pub access_token: String,
+    pub account_login: String,
+    pub webhook_secret: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// GitHubから取り込んだテンプレートの取り込み元
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct GitHubTemplateSource {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub template_id: Uuid,
+    pub repository: String,
+    pub path: String,
+    pub git_ref: String,
+    pub auto_sync: bool,
+    pub last_synced_sha: Option<String>,
+    pub last_synced_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// リポジトリ内のファイルの場所
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitHubFileLocation {
+    /// リポジトリ（owner/name）
+    pub repository: String,
+    /// ブランチ名
+    pub git_ref: String,
+    pub path: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ConnectGitHubRequest {
+    /// リポジトリの読み取り権限を持つトークン（個人アクセストークンまたはGitHub Appのインストールトークン）
+    #[validate(length(min = 1, max = 500, message = "アクセストークンを入力してください"))]
+    pub access_token: String,
+}
+
+/// 接続状態（Webhookの設定に使うURLと秘密鍵を含む）
+#[derive(Debug, Serialize)]
+pub struct GitHubConnectionResponse {
+    pub account_login: String,
+    pub webhook_url: String,
+    pub webhook_secret: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitHubContentsQuery {
+    pub path: Option<String>,
+    #[serde(rename = "ref")]
+    pub git_ref: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ImportFromGitHubRequest {
+    /// リポジトリ（owner/name）
+    #[validate(length(
+        min = 3,
+        max = 255,
+        message = "リポジトリを owner/name の形式で指定してください"
+    ))]
+    pub repository: String,
+    /// マークダウンファイルまたはディレクトリのパス
+    pub path: String,
+    /// ブランチ名（省略時はリポジトリのデフォルトブランチ）
+    #[serde(rename = "ref")]
+    pub git_ref: Option<String>,
+    /// pushのWebhookで再取り込みするか
+    #[serde(default)]
+    pub auto_sync: bool,
+}
+
+/// 取り込み結果
+#[derive(Debug, Default, Serialize)]
+pub struct GitHubImportResult {
+    pub imported: Vec<ImportedTemplate>,
+    pub errors: Vec<GitHubImportError>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportedTemplate {
+    pub path: String,
+    pub template_id: Uuid,
+    pub name: String,
+    /// 既存のテンプレートを更新した場合はtrue
+    pub updated: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GitHubImportError {
+    pub path: String,
+    pub error: String,
+}
+
+/// マークダウンファイルのフロントマター
+#[derive(Debug, Default, Deserialize, PartialEq)]
+pub struct TemplateFrontMatter {
+    pub name: Option<String>,
+    pub subject: Option<String>,
+    #[serde(default)]
+    pub variables: Option<Value>,
+}
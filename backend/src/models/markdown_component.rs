@@ -0,0 +1,109 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+/// 名前の最大文字数
+const MAX_NAME_LENGTH: usize = 50;
+
+/// マークダウンから呼び出すコンポーネント・スニペット
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct MarkdownComponent {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub kind: String,
+    pub description: Option<String>,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// コンポーネントの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComponentKind {
+    /// `:::name{属性}`で呼び出すHTMLテンプレート
+    Component,
+    /// `:::snippet{name="..."}`で埋め込むマークダウン
+    Snippet,
+}
+
+impl ComponentKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ComponentKind::Component => "component",
+            ComponentKind::Snippet => "snippet",
+        }
+    }
+}
+
+impl From<String> for ComponentKind {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "snippet" => ComponentKind::Snippet,
+            _ => ComponentKind::Component,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateMarkdownComponentRequest {
+    pub name: String,
+    pub kind: ComponentKind,
+    pub description: Option<String>,
+    #[validate(length(
+        min = 1,
+        max = 50000,
+        message = "内容は1文字以上50000文字以下である必要があります"
+    ))]
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct UpdateMarkdownComponentRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    #[validate(length(
+        min = 1,
+        max = 50000,
+        message = "内容は1文字以上50000文字以下である必要があります"
+    ))]
+    pub content: Option<String>,
+}
+
+/// コンポーネント名を検証（英小文字で始まる英小文字・数字・ハイフン）
+pub fn normalize_component_name(name: &str) -> Result<String, String> {
+    let name = name.trim().to_ascii_lowercase();
+    let valid = name.len() <= MAX_NAME_LENGTH
+        && name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+
+    if valid {
+        Ok(name)
+    } else {
+        Err(format!(
+            "名前は英小文字で始まる{MAX_NAME_LENGTH}文字以内の英小文字・数字・ハイフンで指定してください"
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_component_name() {
+        assert_eq!(
+            normalize_component_name(" Product-Card2 ").unwrap(),
+            "product-card2"
+        );
+        assert!(normalize_component_name("").is_err());
+        assert!(normalize_component_name("2col").is_err());
+        assert!(normalize_component_name("product_card").is_err());
+        assert!(normalize_component_name(&"a".repeat(51)).is_err());
+    }
+}
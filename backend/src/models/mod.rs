@@ -1,10 +1,23 @@
+pub mod ab_test;
 pub mod ai_usage;
+pub mod audience;
 pub mod campaign;
 pub mod crm;
 pub mod crm_oauth;
 pub mod form;
+pub mod form_validation;
+pub mod github;
+pub mod markdown_component;
+pub mod segment;
+pub mod sending_domain;
 pub mod sequence;
+pub mod sequence_condition;
+pub mod sequence_exit;
+pub mod sequence_schedule;
+pub mod sequence_trigger;
+pub mod ses_feedback;
 pub mod subscriber;
 pub mod subscription;
 pub mod template;
+pub mod tracking;
 pub mod user;
@@ -0,0 +1,282 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Json, FromRow};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::{audience::CustomFieldCondition, subscriber::SubscriberStatus};
+
+/// 条件ツリーの最大の深さ
+pub const SEGMENT_MAX_DEPTH: usize = 8;
+
+/// 条件ツリーに含められる条件の最大数
+pub const SEGMENT_MAX_RULES: usize = 100;
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct Segment {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub rules: Json<SegmentRule>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// セグメントの条件ツリー
+///
+/// `all`・`any`・`not` で条件を組み合わせる。例:
+/// `{"type": "all", "rules": [{"type": "tag", "tag": "customer"}, {"type": "opened_campaign", "campaign_id": "..."}]}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SegmentRule {
+    /// すべての条件を満たす
+    All { rules: Vec<SegmentRule> },
+    /// いずれかの条件を満たす
+    Any { rules: Vec<SegmentRule> },
+    /// 条件を満たさない
+    Not { rule: Box<SegmentRule> },
+    /// タグを持つ
+    Tag { tag: String },
+    /// いずれかのステータスである
+    Status { statuses: Vec<SubscriberStatus> },
+    /// 登録日時が範囲内（after以降・before未満）
+    SubscribedAt {
+        #[serde(default)]
+        after: Option<DateTime<Utc>>,
+        #[serde(default)]
+        before: Option<DateTime<Utc>>,
+    },
+    /// カスタムフィールドの条件
+    CustomField(CustomFieldCondition),
+    /// キャンペーンを開封した
+    OpenedCampaign { campaign_id: Uuid },
+    /// キャンペーンのリンクをクリックした
+    ClickedCampaign { campaign_id: Uuid },
+}
+
+impl SegmentRule {
+    pub fn validate(&self) -> Result<(), String> {
+        let mut count = 0;
+        self.validate_node(1, &mut count)
+    }
+
+    fn validate_node(&self, depth: usize, count: &mut usize) -> Result<(), String> {
+        if depth > SEGMENT_MAX_DEPTH {
+            return Err(format!(
+                "条件の入れ子は{SEGMENT_MAX_DEPTH}階層までにしてください"
+            ));
+        }
+
+        *count += 1;
+        if *count > SEGMENT_MAX_RULES {
+            return Err(format!("条件は{SEGMENT_MAX_RULES}個までにしてください"));
+        }
+
+        match self {
+            SegmentRule::All { rules } | SegmentRule::Any { rules } => {
+                if rules.is_empty() {
+                    return Err("条件グループには1つ以上の条件を指定してください".to_string());
+                }
+                for rule in rules {
+                    rule.validate_node(depth + 1, count)?;
+                }
+                Ok(())
+            }
+            SegmentRule::Not { rule } => rule.validate_node(depth + 1, count),
+            SegmentRule::Tag { tag } => {
+                if tag.trim().is_empty() {
+                    return Err("タグを指定してください".to_string());
+                }
+                Ok(())
+            }
+            SegmentRule::Status { statuses } => {
+                if statuses.is_empty() {
+                    return Err("ステータスを1つ以上指定してください".to_string());
+                }
+                Ok(())
+            }
+            SegmentRule::SubscribedAt { after, before } => match (after, before) {
+                (None, None) => Err("登録日時の範囲を指定してください".to_string()),
+                (Some(after), Some(before)) if after >= before => {
+                    Err("登録日時の範囲が不正です".to_string())
+                }
+                _ => Ok(()),
+            },
+            SegmentRule::CustomField(condition) => condition.validate(),
+            SegmentRule::OpenedCampaign { .. } | SegmentRule::ClickedCampaign { .. } => Ok(()),
+        }
+    }
+
+    /// 条件ツリーが参照するキャンペーンID
+    pub fn referenced_campaign_ids(&self) -> Vec<Uuid> {
+        let mut ids = Vec::new();
+        self.collect_campaign_ids(&mut ids);
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
+    fn collect_campaign_ids(&self, ids: &mut Vec<Uuid>) {
+        match self {
+            SegmentRule::All { rules } | SegmentRule::Any { rules } => {
+                for rule in rules {
+                    rule.collect_campaign_ids(ids);
+                }
+            }
+            SegmentRule::Not { rule } => rule.collect_campaign_ids(ids),
+            SegmentRule::OpenedCampaign { campaign_id }
+            | SegmentRule::ClickedCampaign { campaign_id } => ids.push(*campaign_id),
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreateSegmentRequest {
+    #[validate(length(
+        min = 1,
+        max = 255,
+        message = "セグメント名は1〜255文字で指定してください"
+    ))]
+    pub name: String,
+
+    pub description: Option<String>,
+
+    pub rules: SegmentRule,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct UpdateSegmentRequest {
+    #[validate(length(
+        min = 1,
+        max = 255,
+        message = "セグメント名は1〜255文字で指定してください"
+    ))]
+    pub name: Option<String>,
+
+    pub description: Option<String>,
+
+    pub rules: Option<SegmentRule>,
+}
+
+/// 条件ツリーの該当件数のプレビュー
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PreviewSegmentRequest {
+    pub rules: SegmentRule,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SegmentResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub rules: SegmentRule,
+    pub subscriber_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SegmentResponse {
+    pub fn new(segment: Segment, subscriber_count: i64) -> Self {
+        Self {
+            id: segment.id,
+            name: segment.name,
+            description: segment.description,
+            rules: segment.rules.0,
+            subscriber_count,
+            created_at: segment.created_at,
+            updated_at: segment.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SegmentSubscribersQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::audience::ConditionOperator;
+    use serde_json::json;
+
+    #[test]
+    fn test_deserialize_rule_tree() {
+        let campaign_id = Uuid::new_v4();
+        let rule: SegmentRule = serde_json::from_value(json!({
+            "type": "all",
+            "rules": [
+                {"type": "tag", "tag": "customer"},
+                {"type": "custom_field", "field": "plan", "operator": "equals", "value": "pro"},
+                {"type": "not", "rule": {"type": "clicked_campaign", "campaign_id": campaign_id}},
+                {"type": "subscribed_at", "after": "2025-01-01T00:00:00Z"}
+            ]
+        }))
+        .unwrap();
+
+        let SegmentRule::All { rules } = &rule else {
+            panic!("unexpected rule: {rule:?}");
+        };
+        assert_eq!(rules.len(), 4);
+        assert_eq!(
+            rules[1],
+            SegmentRule::CustomField(CustomFieldCondition {
+                field: "plan".to_string(),
+                operator: ConditionOperator::Equals,
+                value: json!("pro"),
+            })
+        );
+        assert!(rule.validate().is_ok());
+        assert_eq!(rule.referenced_campaign_ids(), vec![campaign_id]);
+    }
+
+    #[test]
+    fn test_validate_rejects_invalid_rules() {
+        assert!(SegmentRule::All { rules: vec![] }.validate().is_err());
+        assert!(SegmentRule::Tag {
+            tag: " ".to_string()
+        }
+        .validate()
+        .is_err());
+        assert!(SegmentRule::Status { statuses: vec![] }.validate().is_err());
+        assert!(SegmentRule::SubscribedAt {
+            after: None,
+            before: None
+        }
+        .validate()
+        .is_err());
+
+        let now = Utc::now();
+        assert!(SegmentRule::SubscribedAt {
+            after: Some(now),
+            before: Some(now - chrono::Duration::days(1))
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn test_validate_limits_depth_and_size() {
+        let mut rule = SegmentRule::Tag {
+            tag: "vip".to_string(),
+        };
+        for _ in 0..SEGMENT_MAX_DEPTH {
+            rule = SegmentRule::Not {
+                rule: Box::new(rule),
+            };
+        }
+        assert!(rule.validate().is_err());
+
+        let rule = SegmentRule::Any {
+            rules: (0..SEGMENT_MAX_RULES)
+                .map(|i| SegmentRule::Tag {
+                    tag: format!("tag-{i}"),
+                })
+                .collect(),
+        };
+        assert!(rule.validate().is_err());
+    }
+}
@@ -0,0 +1,273 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// ユーザーの送信ドメイン
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct SendingDomain {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub domain: String,
+    pub dkim_selector: String,
+    #[serde(skip_serializing)]
+    pub dkim_private_key: String,
+    pub dkim_public_key: String,
+    pub status: String,
+    pub dkim_verified: bool,
+    pub spf_verified: bool,
+    pub dmarc_verified: bool,
+    pub last_checked_at: Option<DateTime<Utc>>,
+    pub verified_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 送信ドメインの認証状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SendingDomainStatus {
+    /// DNSレコードを未確認
+    Pending,
+    /// DKIM・SPF・DMARCのすべてを確認済み
+    Verified,
+    /// 直近の確認でいずれかのレコードが見つからなかった
+    Failed,
+}
+
+impl SendingDomainStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SendingDomainStatus::Pending => "pending",
+            SendingDomainStatus::Verified => "verified",
+            SendingDomainStatus::Failed => "failed",
+        }
+    }
+}
+
+impl From<String> for SendingDomainStatus {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "verified" => SendingDomainStatus::Verified,
+            "failed" => SendingDomainStatus::Failed,
+            _ => SendingDomainStatus::Pending,
+        }
+    }
+}
+
+/// 公開するDNSレコードの用途
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DnsRecordPurpose {
+    Dkim,
+    Spf,
+    Dmarc,
+}
+
+/// ユーザーが公開するDNSレコード
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DnsRecord {
+    pub purpose: DnsRecordPurpose,
+    pub record_type: String,
+    pub name: String,
+    pub value: String,
+    pub verified: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateSendingDomainRequest {
+    pub domain: String,
+}
+
+/// 送信ドメインと公開するDNSレコード
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SendingDomainResponse {
+    #[serde(flatten)]
+    pub domain: SendingDomain,
+    pub dns_records: Vec<DnsRecord>,
+}
+
+impl SendingDomain {
+    pub fn status(&self) -> SendingDomainStatus {
+        SendingDomainStatus::from(self.status.clone())
+    }
+
+    /// DKIMレコードのホスト名
+    pub fn dkim_record_name(&self) -> String {
+        format!("{}._domainkey.{}", self.dkim_selector, self.domain)
+    }
+
+    /// DMARCレコードのホスト名
+    pub fn dmarc_record_name(&self) -> String {
+        format!("_dmarc.{}", self.domain)
+    }
+
+    /// DKIMレコードの値
+    pub fn dkim_record_value(&self) -> String {
+        format!("v=DKIM1; k=rsa; p={}", self.dkim_public_key)
+    }
+
+    /// 公開するDNSレコード（SPFは送信経路の`include`を含める）
+    pub fn dns_records(&self, spf_include: &str) -> Vec<DnsRecord> {
+        let txt = |purpose, name: String, value: String, verified| DnsRecord {
+            purpose,
+            record_type: "TXT".to_string(),
+            name,
+            value,
+            verified,
+        };
+
+        vec![
+            txt(
+                DnsRecordPurpose::Dkim,
+                self.dkim_record_name(),
+                self.dkim_record_value(),
+                self.dkim_verified,
+            ),
+            txt(
+                DnsRecordPurpose::Spf,
+                self.domain.clone(),
+                format!("v=spf1 include:{spf_include} ~all"),
+                self.spf_verified,
+            ),
+            txt(
+                DnsRecordPurpose::Dmarc,
+                self.dmarc_record_name(),
+                "v=DMARC1; p=none;".to_string(),
+                self.dmarc_verified,
+            ),
+        ]
+    }
+}
+
+/// ドメイン名を正規化して検証する（小文字化・末尾のドットを除去）
+pub fn normalize_domain(input: &str) -> Result<String, String> {
+    let domain = input.trim().trim_end_matches('.').to_ascii_lowercase();
+    let invalid = || format!("ドメイン名「{}」が不正です", input.trim());
+
+    if domain.is_empty() || domain.len() > 253 {
+        return Err(invalid());
+    }
+
+    let labels: Vec<&str> = domain.split('.').collect();
+    if labels.len() < 2 {
+        return Err(invalid());
+    }
+
+    for label in &labels {
+        let valid = !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+        if !valid {
+            return Err(invalid());
+        }
+    }
+
+    Ok(domain)
+}
+
+/// メールアドレスのドメイン部分（小文字）
+pub fn email_domain(email: &str) -> Option<String> {
+    let (local, domain) = email.trim().rsplit_once('@')?;
+    if local.is_empty() {
+        return None;
+    }
+    normalize_domain(domain).ok()
+}
+
+/// DKIMレコードに公開鍵が公開されているか
+pub fn dkim_record_matches(records: &[String], public_key: &str) -> bool {
+    records.iter().any(|record| {
+        record.split(';').any(|tag| match tag.split_once('=') {
+            Some((name, value)) if name.trim() == "p" => {
+                value.split_whitespace().collect::<String>() == public_key
+            }
+            _ => false,
+        })
+    })
+}
+
+/// SPFレコードが送信経路を許可しているか（SPFレコードが複数ある場合は無効）
+pub fn spf_record_matches(records: &[String], spf_include: &str) -> bool {
+    let spf: Vec<&String> = records
+        .iter()
+        .filter(|record| {
+            record
+                .split_whitespace()
+                .next()
+                .is_some_and(|version| version.eq_ignore_ascii_case("v=spf1"))
+        })
+        .collect();
+
+    let include = format!("include:{spf_include}");
+    match spf.as_slice() {
+        [record] => record
+            .split_whitespace()
+            .any(|term| term.trim_start_matches('+').eq_ignore_ascii_case(&include)),
+        _ => false,
+    }
+}
+
+/// DMARCレコードが公開されているか
+pub fn dmarc_record_matches(records: &[String]) -> bool {
+    records.iter().any(|record| {
+        record
+            .split(';')
+            .next()
+            .is_some_and(|version| version.trim().eq_ignore_ascii_case("v=DMARC1"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_domain() {
+        assert_eq!(
+            normalize_domain(" Mail.Example.COM. ").unwrap(),
+            "mail.example.com"
+        );
+        assert!(normalize_domain("localhost").is_err());
+        assert!(normalize_domain("-bad.example.com").is_err());
+        assert!(normalize_domain("exa_mple.com").is_err());
+        assert!(normalize_domain("example..com").is_err());
+        assert_eq!(
+            email_domain("News@Example.com").as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(email_domain("@example.com"), None);
+    }
+
+    #[test]
+    fn test_record_matching() {
+        let key = "MIIBIjANBgkq";
+        assert!(dkim_record_matches(
+            &["v=DKIM1; k=rsa; p=MIIB IjANBgkq".to_string()],
+            key
+        ));
+        assert!(!dkim_record_matches(
+            &["v=DKIM1; k=rsa; p=other".to_string()],
+            key
+        ));
+
+        let spf = |records: &[&str]| {
+            let records: Vec<String> = records.iter().map(|r| r.to_string()).collect();
+            spf_record_matches(&records, "amazonses.com")
+        };
+        assert!(spf(&[
+            "google-site-verification=abc",
+            "v=spf1 include:_spf.google.com include:amazonses.com ~all"
+        ]));
+        assert!(!spf(&["v=spf1 include:_spf.google.com ~all"]));
+        // SPFレコードが複数あるとpermerrorになる
+        assert!(!spf(&["v=spf1 include:amazonses.com ~all", "v=spf1 -all"]));
+
+        assert!(dmarc_record_matches(&["v=DMARC1; p=reject".to_string()]));
+        assert!(!dmarc_record_matches(&["v=spf1 -all".to_string()]));
+    }
+}
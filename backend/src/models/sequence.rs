@@ -1,5 +1,7 @@
+use std::collections::HashMap;
+
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value as JsonValue;
 use sqlx::FromRow;
 use uuid::Uuid;
@@ -15,6 +17,12 @@ pub struct Sequence {
     pub status: String,
     pub active_subscribers: i32,
     pub completed_subscribers: i32,
+    /// 再登録ポリシー（never・after_completion）
+    pub reentry_policy: String,
+    /// 終了条件（`ExitCondition`の配列）
+    pub exit_conditions: JsonValue,
+    /// 送信時間帯（`SendWindow`、空の場合は制限なし）
+    pub send_window: JsonValue,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -25,6 +33,12 @@ pub struct CreateSequenceRequest {
     pub description: Option<String>,
     pub trigger_type: String,
     pub trigger_config: Option<JsonValue>,
+    #[serde(default)]
+    pub reentry_policy: Option<String>,
+    #[serde(default)]
+    pub exit_conditions: Option<JsonValue>,
+    #[serde(default)]
+    pub send_window: Option<JsonValue>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +48,12 @@ pub struct UpdateSequenceRequest {
     pub trigger_type: Option<String>,
     pub trigger_config: Option<JsonValue>,
     pub status: Option<String>,
+    #[serde(default)]
+    pub reentry_policy: Option<String>,
+    #[serde(default)]
+    pub exit_conditions: Option<JsonValue>,
+    #[serde(default)]
+    pub send_window: Option<JsonValue>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -49,6 +69,10 @@ pub struct SequenceStep {
     pub subject: Option<String>,
     pub conditions: JsonValue,
     pub action_config: JsonValue,
+    /// 条件ステップで条件を満たした場合の分岐先
+    pub true_step_id: Option<Uuid>,
+    /// 条件ステップで条件を満たさない場合の分岐先
+    pub false_step_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -64,6 +88,10 @@ pub struct CreateSequenceStepRequest {
     pub subject: Option<String>,
     pub conditions: Option<JsonValue>,
     pub action_config: Option<JsonValue>,
+    #[serde(default)]
+    pub true_step_id: Option<Uuid>,
+    #[serde(default)]
+    pub false_step_id: Option<Uuid>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -77,6 +105,18 @@ pub struct UpdateSequenceStepRequest {
     pub subject: Option<String>,
     pub conditions: Option<JsonValue>,
     pub action_config: Option<JsonValue>,
+    /// 未指定の場合は変更しない。`null`を指定すると分岐先を解除する
+    #[serde(default, deserialize_with = "deserialize_branch_target")]
+    pub true_step_id: Option<Option<Uuid>>,
+    #[serde(default, deserialize_with = "deserialize_branch_target")]
+    pub false_step_id: Option<Option<Uuid>>,
+}
+
+fn deserialize_branch_target<'de, D>(deserializer: D) -> Result<Option<Option<Uuid>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<Uuid>::deserialize(deserializer).map(Some)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -95,6 +135,13 @@ pub struct SequenceEnrollment {
     pub updated_at: DateTime<Utc>,
 }
 
+/// 実行日時を過ぎて未処理のステップの状況（ワーカーの遅延の監視用）
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DueStepBacklog {
+    pub due_count: i64,
+    pub oldest_due_at: Option<DateTime<Utc>>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SequenceWithSteps {
     #[serde(flatten)]
@@ -123,6 +170,10 @@ pub struct SequenceStepLog {
     pub step_id: Uuid,
     pub status: String,
     pub error_message: Option<String>,
+    /// 送信したテンプレートのリビジョン（メールステップのみ）
+    pub template_revision_id: Option<Uuid>,
+    /// 送信したメールのメッセージID（メールステップのみ）
+    pub message_id: Option<String>,
     pub executed_at: DateTime<Utc>,
 }
 
@@ -132,6 +183,53 @@ pub struct CreateSequenceEnrollmentRequest {
     pub trigger_data: Option<JsonValue>,
 }
 
+/// 購読者・現在のステップを含むエンロールメント（一覧表示用）
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SequenceEnrollmentDetail {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub enrollment: SequenceEnrollment,
+    pub subscriber_email: String,
+    pub subscriber_name: Option<String>,
+    pub current_step_name: Option<String>,
+    pub current_step_order: Option<i32>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ListSequenceEnrollmentsQuery {
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// 手動でのエンロールメント（購読者ID・タグ・CSVのいずれか、または組み合わせで指定）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EnrollSubscribersRequest {
+    pub subscriber_ids: Option<Vec<Uuid>>,
+    pub tag: Option<String>,
+    /// メールアドレスの列を含むCSV
+    pub csv_content: Option<String>,
+    pub has_header: Option<bool>,
+    /// メールアドレスの列番号（省略時は0）
+    pub email_column: Option<usize>,
+}
+
+/// 手動でのエンロールメントの結果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EnrollSubscribersResult {
+    pub enrolled: usize,
+    /// 実行中（active・paused）のため登録しなかった購読者数
+    pub skipped_in_progress: usize,
+    /// 再登録ポリシーにより登録しなかった（登録済み・完了済みの）購読者数
+    pub skipped_reentry: usize,
+    /// 配信対象外のステータスのため登録しなかった購読者数
+    pub skipped_inactive: usize,
+    /// 見つからなかった購読者IDまたはメールアドレス
+    pub not_found: Vec<String>,
+    /// CSVの読み込みエラー
+    pub errors: Vec<String>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum TriggerType {
@@ -139,6 +237,20 @@ pub enum TriggerType {
     SubscriberCreated,
     FormSubmission,
     TagAdded,
+    /// タグが外された（trigger_config: {"tag": "..."}）
+    TagRemoved,
+    /// セグメントに一致した（trigger_config: {"segment_id": "..."}）
+    SegmentEntered,
+    /// カスタムフィールドの値が変わった（trigger_config: {"field": "...", "from": ..., "to": ...}）
+    CustomFieldChanged,
+    /// キャンペーンを開封した（trigger_config: {"campaign_id": "..."}）
+    CampaignOpened,
+    /// キャンペーンのリンクをクリックした（trigger_config: {"campaign_id": "...", "url": "..."}）
+    CampaignClicked,
+    /// 誕生日（trigger_config: {"field": "birthday", "days_before": 0}）
+    Birthday,
+    /// 日付のカスタムフィールドの記念日（trigger_config: {"field": "...", "days_before": 0}）
+    Anniversary,
 }
 
 impl TriggerType {
@@ -148,19 +260,38 @@ impl TriggerType {
             TriggerType::SubscriberCreated => "subscriber_created",
             TriggerType::FormSubmission => "form_submission",
             TriggerType::TagAdded => "tag_added",
+            TriggerType::TagRemoved => "tag_removed",
+            TriggerType::SegmentEntered => "segment_entered",
+            TriggerType::CustomFieldChanged => "custom_field_changed",
+            TriggerType::CampaignOpened => "campaign_opened",
+            TriggerType::CampaignClicked => "campaign_clicked",
+            TriggerType::Birthday => "birthday",
+            TriggerType::Anniversary => "anniversary",
+        }
+    }
+
+    /// 文字列からトリガータイプを取得（不明な場合はNone）
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "manual" => Some(TriggerType::Manual),
+            "subscriber_created" => Some(TriggerType::SubscriberCreated),
+            "form_submission" => Some(TriggerType::FormSubmission),
+            "tag_added" => Some(TriggerType::TagAdded),
+            "tag_removed" => Some(TriggerType::TagRemoved),
+            "segment_entered" => Some(TriggerType::SegmentEntered),
+            "custom_field_changed" => Some(TriggerType::CustomFieldChanged),
+            "campaign_opened" => Some(TriggerType::CampaignOpened),
+            "campaign_clicked" => Some(TriggerType::CampaignClicked),
+            "birthday" => Some(TriggerType::Birthday),
+            "anniversary" => Some(TriggerType::Anniversary),
+            _ => None,
         }
     }
 }
 
 impl From<String> for TriggerType {
     fn from(s: String) -> Self {
-        match s.as_str() {
-            "manual" => TriggerType::Manual,
-            "subscriber_created" => TriggerType::SubscriberCreated,
-            "form_submission" => TriggerType::FormSubmission,
-            "tag_added" => TriggerType::TagAdded,
-            _ => TriggerType::Manual,
-        }
+        TriggerType::parse(&s).unwrap_or(TriggerType::Manual)
     }
 }
 
@@ -196,6 +327,202 @@ impl From<String> for StepType {
     }
 }
 
+/// シーケンスのステップの実行順序
+///
+/// 各ステップの次は`step_order`が次に大きいステップ。条件ステップは評価結果に応じて
+/// `true_step_id`・`false_step_id`に分岐する（未設定の場合は次のステップ）。
+pub struct StepGraph<'a> {
+    steps: Vec<&'a SequenceStep>,
+}
+
+impl<'a> StepGraph<'a> {
+    pub fn new(steps: &'a [SequenceStep]) -> Self {
+        let mut steps: Vec<&SequenceStep> = steps.iter().collect();
+        steps.sort_by_key(|s| s.step_order);
+        Self { steps }
+    }
+
+    /// 最初に実行するステップ
+    pub fn entry(&self) -> Option<&'a SequenceStep> {
+        self.steps.first().copied()
+    }
+
+    pub fn find(&self, step_id: Uuid) -> Option<&'a SequenceStep> {
+        self.steps.iter().find(|s| s.id == step_id).copied()
+    }
+
+    /// 順序が次のステップ
+    pub fn following(&self, step: &SequenceStep) -> Option<&'a SequenceStep> {
+        self.steps
+            .iter()
+            .find(|s| s.step_order > step.step_order)
+            .copied()
+    }
+
+    /// 条件の評価結果に応じた次のステップ
+    pub fn branch(&self, step: &SequenceStep, matched: bool) -> Option<&'a SequenceStep> {
+        let target = if matched {
+            step.true_step_id
+        } else {
+            step.false_step_id
+        };
+
+        match target {
+            Some(step_id) => self.find(step_id),
+            None => self.following(step),
+        }
+    }
+
+    /// 分岐先が存在し、ステップの遷移が循環していないことを検証
+    pub fn validate(&self) -> Result<(), String> {
+        for step in &self.steps {
+            let targets = [step.true_step_id, step.false_step_id];
+
+            if step.step_type != StepType::Condition.as_str() {
+                if targets.iter().any(Option::is_some) {
+                    return Err(format!(
+                        "ステップ「{}」は条件ステップではないため分岐先を設定できません",
+                        step.name
+                    ));
+                }
+                continue;
+            }
+
+            if targets
+                .into_iter()
+                .flatten()
+                .any(|target| self.find(target).is_none())
+            {
+                return Err(format!(
+                    "ステップ「{}」の分岐先のステップが見つかりません",
+                    step.name
+                ));
+            }
+        }
+
+        let mut visited = HashMap::new();
+        for step in &self.steps {
+            self.visit(step, &mut visited)?;
+        }
+
+        Ok(())
+    }
+
+    fn successors(&self, step: &SequenceStep) -> Vec<&'a SequenceStep> {
+        if step.step_type == StepType::Condition.as_str() {
+            [true, false]
+                .into_iter()
+                .filter_map(|matched| self.branch(step, matched))
+                .collect()
+        } else {
+            self.following(step).into_iter().collect()
+        }
+    }
+
+    // 深さ優先探索で循環を検出（false: 探索中、true: 探索済み）
+    fn visit(&self, step: &SequenceStep, visited: &mut HashMap<Uuid, bool>) -> Result<(), String> {
+        match visited.get(&step.id) {
+            Some(true) => return Ok(()),
+            Some(false) => {
+                return Err(format!(
+                    "ステップ「{}」に戻る分岐があるため、シーケンスが循環しています",
+                    step.name
+                ))
+            }
+            None => {}
+        }
+
+        visited.insert(step.id, false);
+        for next in self.successors(step) {
+            self.visit(next, visited)?;
+        }
+        visited.insert(step.id, true);
+
+        Ok(())
+    }
+}
+
+/// シーケンスへの再登録ポリシー
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReentryPolicy {
+    /// 一度登録された購読者は再登録しない
+    Never,
+    /// 完了・キャンセルした購読者は再登録できる
+    AfterCompletion,
+}
+
+impl ReentryPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReentryPolicy::Never => "never",
+            ReentryPolicy::AfterCompletion => "after_completion",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "never" => Some(ReentryPolicy::Never),
+            "after_completion" => Some(ReentryPolicy::AfterCompletion),
+            _ => None,
+        }
+    }
+}
+
+impl From<String> for ReentryPolicy {
+    fn from(s: String) -> Self {
+        ReentryPolicy::parse(&s).unwrap_or(ReentryPolicy::Never)
+    }
+}
+
+/// エンロールメントのステータス
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnrollmentStatus {
+    Active,
+    Paused,
+    Completed,
+    Cancelled,
+    /// 終了条件（目標）を満たして終了した
+    GoalReached,
+    /// 終了条件（離脱）を満たして終了した
+    Exited,
+    /// ステップの実行に失敗して終了した
+    Failed,
+}
+
+impl EnrollmentStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EnrollmentStatus::Active => "active",
+            EnrollmentStatus::Paused => "paused",
+            EnrollmentStatus::Completed => "completed",
+            EnrollmentStatus::Cancelled => "cancelled",
+            EnrollmentStatus::GoalReached => "goal_reached",
+            EnrollmentStatus::Exited => "exited",
+            EnrollmentStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "active" => Some(EnrollmentStatus::Active),
+            "paused" => Some(EnrollmentStatus::Paused),
+            "completed" => Some(EnrollmentStatus::Completed),
+            "cancelled" => Some(EnrollmentStatus::Cancelled),
+            "goal_reached" => Some(EnrollmentStatus::GoalReached),
+            "exited" => Some(EnrollmentStatus::Exited),
+            "failed" => Some(EnrollmentStatus::Failed),
+            _ => None,
+        }
+    }
+
+    /// 実行中（ステップの実行を待っている・一時停止中）
+    pub fn is_in_progress(&self) -> bool {
+        matches!(self, EnrollmentStatus::Active | EnrollmentStatus::Paused)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum SequenceStatus {
@@ -227,3 +554,88 @@ impl From<String> for SequenceStatus {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn step(name: &str, step_order: i32, step_type: StepType) -> SequenceStep {
+        SequenceStep {
+            id: Uuid::new_v4(),
+            sequence_id: Uuid::nil(),
+            name: name.to_string(),
+            step_order,
+            step_type: step_type.as_str().to_string(),
+            delay_value: 0,
+            delay_unit: "hours".to_string(),
+            template_id: None,
+            subject: None,
+            conditions: json!({}),
+            action_config: json!({}),
+            true_step_id: None,
+            false_step_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_step_graph_branches() {
+        let mut condition = step("条件", 1, StepType::Condition);
+        let welcome = step("ウェルカム", 2, StepType::Email);
+        let follow_up = step("フォローアップ", 3, StepType::Email);
+        condition.false_step_id = Some(follow_up.id);
+        let steps = vec![follow_up.clone(), welcome.clone(), condition.clone()];
+
+        let graph = StepGraph::new(&steps);
+        assert!(graph.validate().is_ok());
+        assert_eq!(graph.entry().map(|s| s.id), Some(condition.id));
+        assert_eq!(
+            graph.branch(&condition, true).map(|s| s.id),
+            Some(welcome.id)
+        );
+        assert_eq!(
+            graph.branch(&condition, false).map(|s| s.id),
+            Some(follow_up.id)
+        );
+        assert_eq!(graph.following(&welcome).map(|s| s.id), Some(follow_up.id));
+        assert!(graph.following(&follow_up).is_none());
+    }
+
+    #[test]
+    fn test_step_graph_rejects_cycles_and_dangling_branches() {
+        let first = step("最初", 1, StepType::Email);
+        let mut condition = step("条件", 2, StepType::Condition);
+
+        // 前のステップに戻る分岐は循環する
+        condition.true_step_id = Some(first.id);
+        let steps = vec![first.clone(), condition.clone()];
+        assert!(StepGraph::new(&steps).validate().is_err());
+
+        // 自分自身への分岐
+        condition.true_step_id = Some(condition.id);
+        let steps = vec![first.clone(), condition.clone()];
+        assert!(StepGraph::new(&steps).validate().is_err());
+
+        // 存在しないステップへの分岐
+        condition.true_step_id = Some(Uuid::new_v4());
+        let steps = vec![first.clone(), condition.clone()];
+        assert!(StepGraph::new(&steps).validate().is_err());
+
+        // 条件ステップ以外には分岐先を設定できない
+        let mut email = first.clone();
+        email.true_step_id = Some(condition.id);
+        condition.true_step_id = None;
+        let steps = vec![email, condition];
+        assert!(StepGraph::new(&steps).validate().is_err());
+    }
+
+    #[test]
+    fn test_update_request_distinguishes_null_branch() {
+        let request: UpdateSequenceStepRequest =
+            serde_json::from_value(json!({"true_step_id": null})).unwrap();
+        assert_eq!(request.true_step_id, Some(None));
+        assert_eq!(request.false_step_id, None);
+    }
+}
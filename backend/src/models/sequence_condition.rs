@@ -0,0 +1,268 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::models::{audience::CustomFieldCondition, tracking::TrackingEventType};
+
+/// 条件ツリーの最大の深さ
+pub const STEP_CONDITION_MAX_DEPTH: usize = 8;
+
+/// 条件ツリーに含められる条件の最大数
+pub const STEP_CONDITION_MAX_RULES: usize = 50;
+
+/// シーケンスステップの条件
+///
+/// セグメントと同じく `all`・`any`・`not` で条件を組み合わせる。例:
+/// `{"type": "all", "rules": [{"type": "tag", "tag": "trial"}, {"type": "opened_campaign", "within_days": 7}]}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StepCondition {
+    /// すべての条件を満たす
+    All { rules: Vec<StepCondition> },
+    /// いずれかの条件を満たす
+    Any { rules: Vec<StepCondition> },
+    /// 条件を満たさない
+    Not { rule: Box<StepCondition> },
+    /// 購読者がタグを持つ
+    Tag { tag: String },
+    /// 購読者のカスタムフィールドの条件
+    CustomField(CustomFieldCondition),
+    /// エンロールメントのメタデータ（トリガー時のデータ）の条件
+    Metadata(CustomFieldCondition),
+    /// キャンペーンを開封した
+    OpenedCampaign(EngagementCondition),
+    /// キャンペーンのリンクをクリックした
+    ClickedCampaign(EngagementCondition),
+}
+
+/// メールへの反応の条件
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EngagementCondition {
+    /// 未指定の場合はいずれかのキャンペーン
+    #[serde(default)]
+    pub campaign_id: Option<Uuid>,
+    /// 直近の日数（未指定の場合は期間を問わない）
+    #[serde(default)]
+    pub within_days: Option<i32>,
+}
+
+/// 条件の評価に使う購読者とエンロールメントの情報
+pub struct ConditionContext<'a> {
+    pub tags: &'a [String],
+    pub custom_fields: &'a Value,
+    pub metadata: &'a Value,
+    /// 満たしているメールへの反応の条件
+    pub engagements: HashSet<(TrackingEventType, EngagementCondition)>,
+}
+
+impl StepCondition {
+    /// ステップの`conditions`を読み込む（空の場合は条件なし）
+    pub fn parse(value: &Value) -> Result<Option<Self>, String> {
+        if value.is_null() || value.as_object().is_some_and(|map| map.is_empty()) {
+            return Ok(None);
+        }
+
+        let condition: StepCondition = serde_json::from_value(value.clone())
+            .map_err(|e| format!("条件の形式が不正です: {e}"))?;
+        condition.validate()?;
+
+        Ok(Some(condition))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let mut count = 0;
+        self.validate_node(1, &mut count)
+    }
+
+    fn validate_node(&self, depth: usize, count: &mut usize) -> Result<(), String> {
+        if depth > STEP_CONDITION_MAX_DEPTH {
+            return Err(format!(
+                "条件の入れ子は{STEP_CONDITION_MAX_DEPTH}階層までにしてください"
+            ));
+        }
+
+        *count += 1;
+        if *count > STEP_CONDITION_MAX_RULES {
+            return Err(format!(
+                "条件は{STEP_CONDITION_MAX_RULES}個までにしてください"
+            ));
+        }
+
+        match self {
+            StepCondition::All { rules } | StepCondition::Any { rules } => {
+                if rules.is_empty() {
+                    return Err("条件グループには1つ以上の条件を指定してください".to_string());
+                }
+                for rule in rules {
+                    rule.validate_node(depth + 1, count)?;
+                }
+                Ok(())
+            }
+            StepCondition::Not { rule } => rule.validate_node(depth + 1, count),
+            StepCondition::Tag { tag } => {
+                if tag.trim().is_empty() {
+                    return Err("タグを指定してください".to_string());
+                }
+                Ok(())
+            }
+            StepCondition::CustomField(condition) | StepCondition::Metadata(condition) => {
+                condition.validate()
+            }
+            StepCondition::OpenedCampaign(engagement)
+            | StepCondition::ClickedCampaign(engagement) => match engagement.within_days {
+                Some(days) if days <= 0 => Err("期間は1日以上で指定してください".to_string()),
+                _ => Ok(()),
+            },
+        }
+    }
+
+    pub fn evaluate(&self, context: &ConditionContext) -> bool {
+        match self {
+            StepCondition::All { rules } => rules.iter().all(|rule| rule.evaluate(context)),
+            StepCondition::Any { rules } => rules.iter().any(|rule| rule.evaluate(context)),
+            StepCondition::Not { rule } => !rule.evaluate(context),
+            StepCondition::Tag { tag } => context.tags.iter().any(|t| t == tag),
+            StepCondition::CustomField(condition) => condition.matches(context.custom_fields),
+            StepCondition::Metadata(condition) => condition.matches(context.metadata),
+            StepCondition::OpenedCampaign(engagement) => context
+                .engagements
+                .contains(&(TrackingEventType::Open, engagement.clone())),
+            StepCondition::ClickedCampaign(engagement) => context
+                .engagements
+                .contains(&(TrackingEventType::Click, engagement.clone())),
+        }
+    }
+
+    /// 評価前にデータベースで確認が必要なメールへの反応の条件
+    pub fn engagement_conditions(&self) -> Vec<(TrackingEventType, EngagementCondition)> {
+        let mut conditions = Vec::new();
+        self.collect_engagements(&mut conditions);
+        conditions
+    }
+
+    fn collect_engagements(&self, conditions: &mut Vec<(TrackingEventType, EngagementCondition)>) {
+        match self {
+            StepCondition::All { rules } | StepCondition::Any { rules } => {
+                for rule in rules {
+                    rule.collect_engagements(conditions);
+                }
+            }
+            StepCondition::Not { rule } => rule.collect_engagements(conditions),
+            StepCondition::OpenedCampaign(engagement) => {
+                conditions.push((TrackingEventType::Open, engagement.clone()))
+            }
+            StepCondition::ClickedCampaign(engagement) => {
+                conditions.push((TrackingEventType::Click, engagement.clone()))
+            }
+            _ => {}
+        }
+    }
+
+    /// 条件ツリーが参照するキャンペーンID
+    pub fn referenced_campaign_ids(&self) -> Vec<Uuid> {
+        let mut ids: Vec<Uuid> = self
+            .engagement_conditions()
+            .into_iter()
+            .filter_map(|(_, engagement)| engagement.campaign_id)
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn context<'a>(
+        tags: &'a [String],
+        custom_fields: &'a Value,
+        metadata: &'a Value,
+    ) -> ConditionContext<'a> {
+        ConditionContext {
+            tags,
+            custom_fields,
+            metadata,
+            engagements: HashSet::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_empty_and_invalid_conditions() {
+        assert_eq!(StepCondition::parse(&json!({})).unwrap(), None);
+        assert_eq!(StepCondition::parse(&Value::Null).unwrap(), None);
+        assert!(StepCondition::parse(&json!({"type": "unknown"})).is_err());
+        assert!(StepCondition::parse(&json!({"type": "all", "rules": []})).is_err());
+        assert!(
+            StepCondition::parse(&json!({"type": "opened_campaign", "within_days": 0})).is_err()
+        );
+
+        let mut nested = json!({"type": "tag", "tag": "vip"});
+        for _ in 0..STEP_CONDITION_MAX_DEPTH {
+            nested = json!({"type": "not", "rule": nested});
+        }
+        assert!(StepCondition::parse(&nested).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_subscriber_and_metadata_conditions() {
+        let tags = vec!["trial".to_string()];
+        let custom_fields = json!({"plan": "pro"});
+        let metadata = json!({"form_id": "contact"});
+        let context = context(&tags, &custom_fields, &metadata);
+
+        let condition = StepCondition::parse(&json!({
+            "type": "all",
+            "rules": [
+                {"type": "tag", "tag": "trial"},
+                {"type": "custom_field", "field": "plan", "operator": "equals", "value": "pro"},
+                {"type": "metadata", "field": "form_id", "operator": "equals", "value": "contact"},
+                {"type": "not", "rule": {"type": "tag", "tag": "customer"}}
+            ]
+        }))
+        .unwrap()
+        .unwrap();
+        assert!(condition.evaluate(&context));
+
+        let condition = StepCondition::parse(&json!({
+            "type": "any",
+            "rules": [
+                {"type": "tag", "tag": "customer"},
+                {"type": "metadata", "field": "form_id", "operator": "equals", "value": "other"}
+            ]
+        }))
+        .unwrap()
+        .unwrap();
+        assert!(!condition.evaluate(&context));
+    }
+
+    #[test]
+    fn test_evaluate_engagement_conditions() {
+        let campaign_id = Uuid::new_v4();
+        let condition = StepCondition::parse(&json!({
+            "type": "any",
+            "rules": [
+                {"type": "opened_campaign", "campaign_id": campaign_id},
+                {"type": "clicked_campaign", "within_days": 7}
+            ]
+        }))
+        .unwrap()
+        .unwrap();
+
+        let engagements = condition.engagement_conditions();
+        assert_eq!(engagements.len(), 2);
+        assert_eq!(condition.referenced_campaign_ids(), vec![campaign_id]);
+
+        let tags = Vec::new();
+        let empty = json!({});
+        let mut context = context(&tags, &empty, &empty);
+        assert!(!condition.evaluate(&context));
+
+        context.engagements.insert(engagements[1].clone());
+        assert!(condition.evaluate(&context));
+    }
+}
@@ -0,0 +1,239 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::models::{
+    audience::CustomFieldCondition,
+    sequence::EnrollmentStatus,
+    subscriber::{Subscriber, SubscriberStatus},
+};
+
+/// シーケンスに設定できる終了条件の最大数
+pub const EXIT_CONDITION_MAX_COUNT: usize = 20;
+
+/// シーケンスの終了条件（いずれかを満たした購読者のエンロールメントを終了する）
+///
+/// 例: `[{"type": "tag_added", "tag": "customer"}, {"type": "unsubscribed"}]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExitCondition {
+    #[serde(flatten)]
+    pub rule: ExitRule,
+    /// 満たした場合のエンロールメントのステータス（省略時は配信停止が離脱、それ以外は目標達成）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<ExitOutcome>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExitRule {
+    /// 購読者にタグが付いた
+    TagAdded { tag: String },
+    /// 購読者のカスタムフィールドが条件を満たした
+    CustomField(CustomFieldCondition),
+    /// 購読者が配信停止した
+    Unsubscribed,
+    /// 登録後にキャンペーンのリンクをクリックした（`url`はクリックしたURLに含まれる文字列）
+    LinkClicked {
+        #[serde(default)]
+        campaign_id: Option<Uuid>,
+        #[serde(default)]
+        url: Option<String>,
+    },
+    /// 登録後にフォームを送信した
+    FormSubmitted {
+        #[serde(default)]
+        form_id: Option<Uuid>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitOutcome {
+    /// 目標を達成した
+    GoalReached,
+    /// シーケンスから離脱した
+    Exited,
+}
+
+impl ExitOutcome {
+    pub fn status(&self) -> EnrollmentStatus {
+        match self {
+            ExitOutcome::GoalReached => EnrollmentStatus::GoalReached,
+            ExitOutcome::Exited => EnrollmentStatus::Exited,
+        }
+    }
+}
+
+impl ExitCondition {
+    /// シーケンスの`exit_conditions`を読み込む（空の場合は終了条件なし）
+    pub fn parse_list(value: &Value) -> Result<Vec<Self>, String> {
+        if value.is_null() {
+            return Ok(Vec::new());
+        }
+
+        let conditions: Vec<ExitCondition> = serde_json::from_value(value.clone())
+            .map_err(|e| format!("終了条件の形式が不正です: {e}"))?;
+        if conditions.len() > EXIT_CONDITION_MAX_COUNT {
+            return Err(format!(
+                "終了条件は{EXIT_CONDITION_MAX_COUNT}個までにしてください"
+            ));
+        }
+        for condition in &conditions {
+            condition.validate()?;
+        }
+
+        Ok(conditions)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match &self.rule {
+            ExitRule::TagAdded { tag } if tag.trim().is_empty() => {
+                Err("終了条件のタグを指定してください".to_string())
+            }
+            ExitRule::CustomField(condition) => condition.validate(),
+            ExitRule::LinkClicked { url: Some(url), .. } if url.trim().is_empty() => {
+                Err("終了条件のURLが空です".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn outcome(&self) -> ExitOutcome {
+        self.outcome.unwrap_or(match self.rule {
+            ExitRule::Unsubscribed => ExitOutcome::Exited,
+            _ => ExitOutcome::GoalReached,
+        })
+    }
+
+    /// ステップのログに記録する説明
+    pub fn describe(&self) -> String {
+        match &self.rule {
+            ExitRule::TagAdded { tag } => format!("タグ「{tag}」が付きました"),
+            ExitRule::CustomField(condition) => {
+                format!(
+                    "カスタムフィールド「{}」が条件を満たしました",
+                    condition.field
+                )
+            }
+            ExitRule::Unsubscribed => "配信停止しました".to_string(),
+            ExitRule::LinkClicked { url: Some(url), .. } => {
+                format!("「{url}」を含むリンクをクリックしました")
+            }
+            ExitRule::LinkClicked { url: None, .. } => "リンクをクリックしました".to_string(),
+            ExitRule::FormSubmitted { .. } => "フォームを送信しました".to_string(),
+        }
+    }
+}
+
+impl ExitRule {
+    /// 購読者の状態で判定できる条件を評価（クリック・フォーム送信は履歴の確認が必要なためNone）
+    pub fn matches_subscriber(&self, subscriber: &Subscriber) -> Option<bool> {
+        match self {
+            ExitRule::TagAdded { tag } => Some(subscriber.tags.iter().any(|t| t == tag)),
+            ExitRule::CustomField(condition) => Some(condition.matches(&subscriber.custom_fields)),
+            ExitRule::Unsubscribed => Some(subscriber.status == SubscriberStatus::Unsubscribed),
+            ExitRule::LinkClicked { .. } | ExitRule::FormSubmitted { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use serde_json::json;
+
+    fn subscriber(tags: &[&str], custom_fields: Value, status: SubscriberStatus) -> Subscriber {
+        Subscriber {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            email: "exit@example.com".to_string(),
+            name: None,
+            status,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            custom_fields,
+            subscribed_at: Utc::now(),
+            unsubscribed_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_parse_exit_conditions() {
+        assert!(ExitCondition::parse_list(&Value::Null).unwrap().is_empty());
+        assert!(ExitCondition::parse_list(&json!([])).unwrap().is_empty());
+        assert!(ExitCondition::parse_list(&json!({"type": "unsubscribed"})).is_err());
+        assert!(ExitCondition::parse_list(&json!([{"type": "unknown"}])).is_err());
+        assert!(ExitCondition::parse_list(&json!([{"type": "tag_added", "tag": " "}])).is_err());
+        assert!(ExitCondition::parse_list(&json!([{"type": "link_clicked", "url": ""}])).is_err());
+        assert!(ExitCondition::parse_list(&json!([
+            {"type": "custom_field", "field": "", "operator": "equals", "value": "paid"}
+        ]))
+        .is_err());
+        assert!(ExitCondition::parse_list(&json!([
+            {"type": "unsubscribed", "outcome": "finished"}
+        ]))
+        .is_err());
+
+        let too_many: Vec<Value> = (0..=EXIT_CONDITION_MAX_COUNT)
+            .map(|_| json!({"type": "unsubscribed"}))
+            .collect();
+        assert!(ExitCondition::parse_list(&json!(too_many)).is_err());
+
+        let form_id = Uuid::new_v4();
+        let conditions = ExitCondition::parse_list(&json!([
+            {"type": "tag_added", "tag": "customer"},
+            {"type": "unsubscribed"},
+            {"type": "form_submitted", "form_id": form_id, "outcome": "exited"}
+        ]))
+        .unwrap();
+        assert_eq!(conditions.len(), 3);
+        assert_eq!(conditions[0].outcome(), ExitOutcome::GoalReached);
+        assert_eq!(conditions[1].outcome(), ExitOutcome::Exited);
+        assert_eq!(
+            conditions[2].rule,
+            ExitRule::FormSubmitted {
+                form_id: Some(form_id)
+            }
+        );
+        assert_eq!(conditions[2].outcome(), ExitOutcome::Exited);
+        assert_eq!(
+            serde_json::to_value(&conditions[0]).unwrap(),
+            json!({"type": "tag_added", "tag": "customer"})
+        );
+    }
+
+    #[test]
+    fn test_matches_subscriber() {
+        let conditions = ExitCondition::parse_list(&json!([
+            {"type": "tag_added", "tag": "customer"},
+            {"type": "custom_field", "field": "plan", "operator": "equals", "value": "paid"},
+            {"type": "unsubscribed"},
+            {"type": "link_clicked", "url": "/checkout"}
+        ]))
+        .unwrap();
+
+        let trial = subscriber(
+            &["trial"],
+            json!({"plan": "free"}),
+            SubscriberStatus::Active,
+        );
+        let results: Vec<Option<bool>> = conditions
+            .iter()
+            .map(|c| c.rule.matches_subscriber(&trial))
+            .collect();
+        assert_eq!(results, vec![Some(false), Some(false), Some(false), None]);
+
+        let customer = subscriber(
+            &["trial", "customer"],
+            json!({"plan": "paid"}),
+            SubscriberStatus::Unsubscribed,
+        );
+        let results: Vec<Option<bool>> = conditions
+            .iter()
+            .map(|c| c.rule.matches_subscriber(&customer))
+            .collect();
+        assert_eq!(results, vec![Some(true), Some(true), Some(true), None]);
+    }
+}
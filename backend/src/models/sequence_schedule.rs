@@ -0,0 +1,418 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::subscriber::Subscriber;
+
+/// タイムゾーンを推定するときに参照する国コードのカスタムフィールド
+pub const COUNTRY_FIELD: &str = "country";
+
+/// 待機ステップの`action_config`で、指定した曜日・時刻まで待つ設定のキー
+pub const WAIT_UNTIL_KEY: &str = "wait_until";
+
+/// シーケンスの送信時間帯（購読者のタイムゾーンでの曜日・時間）
+///
+/// 例: `{"days": ["Mon", "Tue", "Wed", "Thu", "Fri"], "start_hour": 9, "end_hour": 18, "timezone": "Asia/Tokyo"}`
+///
+/// 空のオブジェクトは制限なし。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SendWindow {
+    #[serde(default = "all_weekdays")]
+    pub days: Vec<Weekday>,
+    /// 開始時刻（時）
+    #[serde(default)]
+    pub start_hour: u32,
+    /// 終了時刻（時、この時刻は含まない）
+    #[serde(default = "default_end_hour")]
+    pub end_hour: u32,
+    /// 購読者のタイムゾーンが分からない場合のタイムゾーン（IANA名）
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// 購読者のタイムゾーン（IANA名）を保持するカスタムフィールド
+    #[serde(default = "default_timezone_field")]
+    pub timezone_field: String,
+}
+
+/// 指定した曜日・時刻まで待つ（曜日を省略した場合は毎日）
+///
+/// 例: `{"wait_until": {"days": ["Tue"], "time": "10:00:00"}}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WaitUntil {
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    pub time: NaiveTime,
+}
+
+fn all_weekdays() -> Vec<Weekday> {
+    vec![
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+        Weekday::Fri,
+        Weekday::Sat,
+        Weekday::Sun,
+    ]
+}
+
+fn default_end_hour() -> u32 {
+    24
+}
+
+fn default_timezone_field() -> String {
+    "timezone".to_string()
+}
+
+impl Default for SendWindow {
+    fn default() -> Self {
+        Self {
+            days: all_weekdays(),
+            start_hour: 0,
+            end_hour: default_end_hour(),
+            timezone: None,
+            timezone_field: default_timezone_field(),
+        }
+    }
+}
+
+impl SendWindow {
+    /// シーケンスの`send_window`を読み込む（nullの場合は制限なし）
+    pub fn parse(value: &Value) -> Result<Self, String> {
+        if value.is_null() {
+            return Ok(Self::default());
+        }
+
+        let window: SendWindow = serde_json::from_value(value.clone())
+            .map_err(|e| format!("送信時間帯の形式が不正です: {e}"))?;
+        window.validate()?;
+
+        Ok(window)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.days.is_empty() {
+            return Err("送信時間帯の曜日を1つ以上指定してください".to_string());
+        }
+        if self.start_hour >= self.end_hour || self.end_hour > 24 {
+            return Err(
+                "送信時間帯は開始時刻を終了時刻より前にし、0〜24時の範囲で指定してください"
+                    .to_string(),
+            );
+        }
+        if self.timezone_field.trim().is_empty() {
+            return Err("タイムゾーンのカスタムフィールド名を指定してください".to_string());
+        }
+        if let Some(timezone) = &self.timezone {
+            parse_timezone(timezone)?;
+        }
+
+        Ok(())
+    }
+
+    /// 曜日・時間の制限がない
+    pub fn is_unrestricted(&self) -> bool {
+        self.start_hour == 0
+            && self.end_hour >= 24
+            && all_weekdays().iter().all(|day| self.days.contains(day))
+    }
+
+    /// 購読者のタイムゾーン
+    ///
+    /// カスタムフィールドのタイムゾーン名、国コード・メールアドレスの国別ドメインからの推定、
+    /// 送信時間帯のタイムゾーン、UTCの順に決める。
+    pub fn subscriber_timezone(&self, subscriber: &Subscriber) -> Tz {
+        let field = |name: &str| {
+            subscriber
+                .custom_fields
+                .get(name)
+                .and_then(|value| value.as_str())
+                .map(str::trim)
+        };
+
+        field(&self.timezone_field)
+            .and_then(|name| parse_timezone(name).ok())
+            .or_else(|| field(COUNTRY_FIELD).and_then(country_timezone))
+            .or_else(|| {
+                subscriber
+                    .email
+                    .rsplit_once('.')
+                    .and_then(|(_, tld)| country_timezone(tld))
+            })
+            .or_else(|| {
+                self.timezone
+                    .as_deref()
+                    .and_then(|name| parse_timezone(name).ok())
+            })
+            .unwrap_or(Tz::UTC)
+    }
+
+    /// `at`以降で最初に送信できる日時（時間帯内であれば`at`のまま）
+    pub fn next_slot(&self, at: DateTime<Utc>, timezone: Tz) -> DateTime<Utc> {
+        if self.is_unrestricted() {
+            return at;
+        }
+
+        let today = at.with_timezone(&timezone).date_naive();
+        for date in today.iter_days().take(8) {
+            if !self.days.contains(&date.weekday()) {
+                continue;
+            }
+
+            let midnight = date.and_time(NaiveTime::MIN);
+            let start = to_utc(timezone, midnight + Duration::hours(self.start_hour.into()));
+            let end = to_utc(timezone, midnight + Duration::hours(self.end_hour.into()));
+            if at < start {
+                return start;
+            }
+            if at < end {
+                return at;
+            }
+        }
+
+        at
+    }
+}
+
+impl WaitUntil {
+    /// 待機ステップの`action_config`から読み込む（設定がない場合はNone）
+    pub fn parse(action_config: &Value) -> Result<Option<Self>, String> {
+        match action_config.get(WAIT_UNTIL_KEY) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => serde_json::from_value(value.clone())
+                .map(Some)
+                .map_err(|e| format!("待機する曜日・時刻の形式が不正です: {e}")),
+        }
+    }
+
+    /// `at`以降で最初の指定した曜日・時刻
+    pub fn next_after(&self, at: DateTime<Utc>, timezone: Tz) -> DateTime<Utc> {
+        let today = at.with_timezone(&timezone).date_naive();
+        for date in today.iter_days().take(8) {
+            if !self.days.is_empty() && !self.days.contains(&date.weekday()) {
+                continue;
+            }
+
+            let candidate = to_utc(timezone, date.and_time(self.time));
+            if candidate >= at {
+                return candidate;
+            }
+        }
+
+        at
+    }
+}
+
+/// 待機後に次のステップを実行する日時
+///
+/// 待機時間の経過後（`base`）、指定した曜日・時刻があればそこまで待ち、送信時間帯に合わせる。
+pub fn next_execution_at(
+    base: DateTime<Utc>,
+    wait_until: Option<&WaitUntil>,
+    window: &SendWindow,
+    timezone: Tz,
+) -> DateTime<Utc> {
+    let at = match wait_until {
+        Some(wait_until) => wait_until.next_after(base, timezone),
+        None => base,
+    };
+
+    window.next_slot(at, timezone)
+}
+
+pub fn parse_timezone(name: &str) -> Result<Tz, String> {
+    name.parse::<Tz>()
+        .map_err(|_| format!("タイムゾーン「{name}」は不正です"))
+}
+
+// 現地時刻をUTCに変換（夏時間の切り替えで存在しない時刻は1時間後にずらす）
+fn to_utc(timezone: Tz, local: NaiveDateTime) -> DateTime<Utc> {
+    timezone
+        .from_local_datetime(&local)
+        .earliest()
+        .or_else(|| {
+            timezone
+                .from_local_datetime(&(local + Duration::hours(1)))
+                .earliest()
+        })
+        .map(|at| at.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc.from_utc_datetime(&local))
+}
+
+// 国コード（国別ドメイン）から推定するタイムゾーン（複数のタイムゾーンがある国は推定しない）
+fn country_timezone(code: &str) -> Option<Tz> {
+    let timezone = match code.to_ascii_lowercase().as_str() {
+        "jp" => Tz::Asia__Tokyo,
+        "kr" => Tz::Asia__Seoul,
+        "cn" => Tz::Asia__Shanghai,
+        "tw" => Tz::Asia__Taipei,
+        "hk" => Tz::Asia__Hong_Kong,
+        "sg" => Tz::Asia__Singapore,
+        "th" => Tz::Asia__Bangkok,
+        "vn" => Tz::Asia__Ho_Chi_Minh,
+        "ph" => Tz::Asia__Manila,
+        "in" => Tz::Asia__Kolkata,
+        "gb" | "uk" => Tz::Europe__London,
+        "ie" => Tz::Europe__Dublin,
+        "de" => Tz::Europe__Berlin,
+        "fr" => Tz::Europe__Paris,
+        "it" => Tz::Europe__Rome,
+        "es" => Tz::Europe__Madrid,
+        "nl" => Tz::Europe__Amsterdam,
+        "nz" => Tz::Pacific__Auckland,
+        _ => return None,
+    };
+
+    Some(timezone)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::subscriber::SubscriberStatus;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn subscriber(email: &str, custom_fields: Value) -> Subscriber {
+        Subscriber {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            email: email.to_string(),
+            name: None,
+            status: SubscriberStatus::Active,
+            tags: Vec::new(),
+            custom_fields,
+            subscribed_at: Utc::now(),
+            unsubscribed_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn business_hours() -> SendWindow {
+        SendWindow::parse(&json!({
+            "days": ["Mon", "Tue", "Wed", "Thu", "Fri"],
+            "start_hour": 9,
+            "end_hour": 18,
+            "timezone": "Asia/Tokyo"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_send_window() {
+        assert!(SendWindow::parse(&Value::Null).unwrap().is_unrestricted());
+        assert!(SendWindow::parse(&json!({})).unwrap().is_unrestricted());
+        assert!(!business_hours().is_unrestricted());
+
+        assert!(SendWindow::parse(&json!({"days": []})).is_err());
+        assert!(SendWindow::parse(&json!({"days": ["Someday"]})).is_err());
+        assert!(SendWindow::parse(&json!({"start_hour": 18, "end_hour": 9})).is_err());
+        assert!(SendWindow::parse(&json!({"end_hour": 25})).is_err());
+        assert!(SendWindow::parse(&json!({"timezone": "Asia/Nowhere"})).is_err());
+        assert!(SendWindow::parse(&json!({"timezone_field": " "})).is_err());
+    }
+
+    #[test]
+    fn test_next_slot_snaps_to_business_hours() {
+        let window = business_hours();
+        let tokyo = Tz::Asia__Tokyo;
+
+        // 月曜3時（日本時間）は同じ日の9時に
+        assert_eq!(
+            window.next_slot(utc("2026-10-18T18:00:00Z"), tokyo),
+            utc("2026-10-19T00:00:00Z")
+        );
+        // 時間帯内はそのまま
+        assert_eq!(
+            window.next_slot(utc("2026-10-19T05:30:00Z"), tokyo),
+            utc("2026-10-19T05:30:00Z")
+        );
+        // 金曜18時（日本時間）は翌週月曜の9時に
+        assert_eq!(
+            window.next_slot(utc("2026-10-23T09:00:00Z"), tokyo),
+            utc("2026-10-26T00:00:00Z")
+        );
+        // 制限がなければそのまま
+        assert_eq!(
+            SendWindow::default().next_slot(utc("2026-10-18T18:00:00Z"), tokyo),
+            utc("2026-10-18T18:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_next_slot_handles_daylight_saving_gap() {
+        let window = SendWindow::parse(&json!({"start_hour": 2, "end_hour": 4})).unwrap();
+
+        // 2027-03-14 はニューヨークで2時が存在しないため3時（EDT）に
+        assert_eq!(
+            window.next_slot(utc("2027-03-14T05:00:00Z"), Tz::America__New_York),
+            utc("2027-03-14T07:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_wait_until_next_weekday_and_time() {
+        let config = json!({"wait_until": {"days": ["Tue"], "time": "10:00:00"}});
+        let wait_until = WaitUntil::parse(&config).unwrap().unwrap();
+        let tokyo = Tz::Asia__Tokyo;
+
+        // 水曜（日本時間）からは翌週火曜の10時
+        assert_eq!(
+            wait_until.next_after(utc("2026-10-21T03:00:00Z"), tokyo),
+            utc("2026-10-27T01:00:00Z")
+        );
+        // 火曜10時より前なら同じ日
+        assert_eq!(
+            wait_until.next_after(utc("2026-10-20T00:30:00Z"), tokyo),
+            utc("2026-10-20T01:00:00Z")
+        );
+
+        let daily = WaitUntil::parse(&json!({"wait_until": {"time": "08:30:00"}}))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            daily.next_after(utc("2026-10-21T03:00:00Z"), tokyo),
+            utc("2026-10-21T23:30:00Z")
+        );
+
+        assert_eq!(WaitUntil::parse(&json!({})).unwrap(), None);
+        assert!(WaitUntil::parse(&json!({"wait_until": {"days": ["Tue"]}})).is_err());
+
+        // 指定時刻が送信時間帯外の場合は時間帯に合わせる
+        let window = business_hours();
+        let early = WaitUntil::parse(&json!({"wait_until": {"days": ["Tue"], "time": "07:00:00"}}))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            next_execution_at(utc("2026-10-21T03:00:00Z"), Some(&early), &window, tokyo),
+            utc("2026-10-27T00:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_subscriber_timezone() {
+        let window = business_hours();
+
+        let field = subscriber("a@example.com", json!({"timezone": "America/New_York"}));
+        assert_eq!(window.subscriber_timezone(&field), Tz::America__New_York);
+
+        let country = subscriber("a@example.com", json!({"country": "DE"}));
+        assert_eq!(window.subscriber_timezone(&country), Tz::Europe__Berlin);
+
+        let domain = subscriber("a@example.co.uk", json!({"timezone": "invalid"}));
+        assert_eq!(window.subscriber_timezone(&domain), Tz::Europe__London);
+
+        let unknown = subscriber("a@example.com", json!({}));
+        assert_eq!(window.subscriber_timezone(&unknown), Tz::Asia__Tokyo);
+        assert_eq!(SendWindow::default().subscriber_timezone(&unknown), Tz::UTC);
+
+        let custom = SendWindow::parse(&json!({"timezone_field": "tz"})).unwrap();
+        let custom_field = subscriber("a@example.com", json!({"tz": "Asia/Seoul"}));
+        assert_eq!(custom.subscriber_timezone(&custom_field), Tz::Asia__Seoul);
+    }
+}
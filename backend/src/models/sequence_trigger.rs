@@ -0,0 +1,375 @@
+use chrono::{Datelike, NaiveDate};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::models::sequence::TriggerType;
+
+/// 日付トリガーで何日前から登録できるかの上限
+pub const DATE_TRIGGER_MAX_DAYS_BEFORE: i32 = 60;
+
+/// シーケンスのトリガー設定（`trigger_config`をトリガーの種類ごとに読み込んだもの）
+///
+/// トリガー元は`trigger_data`を渡してシーケンスに登録し、設定と一致した場合のみ登録される。
+#[derive(Debug, Clone, PartialEq)]
+pub enum TriggerConfig {
+    /// 設定なし（手動登録・購読者の登録）
+    None,
+    /// フォーム送信（未指定の場合はいずれかのフォーム）
+    FormSubmission { form_id: Option<Uuid> },
+    /// タグの追加・削除（未指定の場合はいずれかのタグ）
+    Tag { tag: Option<String> },
+    /// セグメントに一致した
+    SegmentEntered { segment_id: Uuid },
+    /// カスタムフィールドの値が変わった
+    CustomFieldChanged(FieldChangeTrigger),
+    /// キャンペーンを開封した・リンクをクリックした
+    Campaign(CampaignTrigger),
+    /// 誕生日・記念日（日付のカスタムフィールド）
+    Date(DateTrigger),
+}
+
+/// カスタムフィールドの変更トリガー
+///
+/// 例: `{"field": "plan", "to": "pro"}`（`from`・`to`を省略した場合はどの値からどの値への変更でも一致する。
+/// `null`は値が未設定であることを表す）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChangeTrigger {
+    pub field: String,
+    #[serde(default, deserialize_with = "present_value")]
+    pub from: Option<Value>,
+    #[serde(default, deserialize_with = "present_value")]
+    pub to: Option<Value>,
+}
+
+/// キャンペーンへの反応のトリガー
+///
+/// 例: `{"campaign_id": "...", "url": "/pricing"}`（`url`はクリックしたURLに含まれる文字列）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CampaignTrigger {
+    /// 未指定の場合はいずれかのキャンペーン
+    #[serde(default)]
+    pub campaign_id: Option<Uuid>,
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+/// 日付のカスタムフィールドによるトリガー（毎年、日付の`days_before`日前に登録する）
+///
+/// 例: `{"field": "contract_date", "days_before": 7}`。フィールドの値は `YYYY-MM-DD` または `MM-DD`。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DateTrigger {
+    pub field: String,
+    #[serde(default)]
+    pub days_before: i32,
+}
+
+impl TriggerConfig {
+    /// トリガーの種類に応じて`trigger_config`を読み込む
+    pub fn parse(trigger_type: TriggerType, config: Option<&Value>) -> Result<Self, String> {
+        let config = match config {
+            Some(Value::Null) | None => json!({}),
+            Some(config @ Value::Object(_)) => config.clone(),
+            Some(_) => return Err("トリガー設定はオブジェクトで指定してください".to_string()),
+        };
+
+        let trigger = match trigger_type {
+            TriggerType::Manual | TriggerType::SubscriberCreated => TriggerConfig::None,
+            TriggerType::FormSubmission => TriggerConfig::FormSubmission {
+                form_id: optional_uuid(&config, "form_id")?,
+            },
+            TriggerType::TagAdded | TriggerType::TagRemoved => {
+                let tag = match config.get("tag") {
+                    None | Some(Value::Null) => None,
+                    Some(Value::String(tag)) if !tag.trim().is_empty() => Some(tag.clone()),
+                    Some(_) => return Err("タグを文字列で指定してください".to_string()),
+                };
+                TriggerConfig::Tag { tag }
+            }
+            TriggerType::SegmentEntered => TriggerConfig::SegmentEntered {
+                segment_id: optional_uuid(&config, "segment_id")?
+                    .ok_or("セグメントトリガーにはsegment_idを指定してください")?,
+            },
+            TriggerType::CustomFieldChanged => {
+                let trigger: FieldChangeTrigger = read(config, "カスタムフィールド変更トリガー")?;
+                if trigger.field.trim().is_empty() {
+                    return Err("変更を監視するカスタムフィールド名を指定してください".to_string());
+                }
+                TriggerConfig::CustomFieldChanged(trigger)
+            }
+            TriggerType::CampaignOpened | TriggerType::CampaignClicked => {
+                let trigger: CampaignTrigger = read(config, "キャンペーントリガー")?;
+                if let Some(url) = &trigger.url {
+                    if trigger_type == TriggerType::CampaignOpened {
+                        return Err("URLはクリックのトリガーにのみ指定できます".to_string());
+                    }
+                    if url.trim().is_empty() {
+                        return Err("URLを指定してください".to_string());
+                    }
+                }
+                TriggerConfig::Campaign(trigger)
+            }
+            TriggerType::Birthday | TriggerType::Anniversary => {
+                // 誕生日はフィールド名を省略した場合に「birthday」を使う
+                let mut config = config;
+                if trigger_type == TriggerType::Birthday && config.get("field").is_none() {
+                    config["field"] = json!("birthday");
+                }
+                let trigger: DateTrigger = read(config, "日付トリガー")?;
+                if trigger.field.trim().is_empty() {
+                    return Err("日付のカスタムフィールド名を指定してください".to_string());
+                }
+                if !(0..=DATE_TRIGGER_MAX_DAYS_BEFORE).contains(&trigger.days_before) {
+                    return Err(format!(
+                        "days_beforeは0から{DATE_TRIGGER_MAX_DAYS_BEFORE}の範囲で指定してください"
+                    ));
+                }
+                TriggerConfig::Date(trigger)
+            }
+        };
+
+        Ok(trigger)
+    }
+
+    /// トリガー元のデータが設定と一致するか
+    pub fn matches(&self, trigger_data: Option<&Value>) -> bool {
+        let data = trigger_data.cloned().unwrap_or_else(|| json!({}));
+        let text = |key: &str| data.get(key).and_then(Value::as_str);
+
+        match self {
+            TriggerConfig::None => true,
+            TriggerConfig::FormSubmission { form_id } => {
+                form_id.is_none_or(|id| text("form_id") == Some(&id.to_string()))
+            }
+            TriggerConfig::Tag { tag } => tag.as_deref().is_none_or(|tag| text("tag") == Some(tag)),
+            TriggerConfig::SegmentEntered { segment_id } => {
+                text("segment_id") == Some(&segment_id.to_string())
+            }
+            TriggerConfig::CustomFieldChanged(trigger) => {
+                let value_matches = |expected: &Option<Value>, key: &str| {
+                    expected.as_ref().is_none_or(|expected| {
+                        same_value(expected, data.get(key).unwrap_or(&Value::Null))
+                    })
+                };
+                text("field") == Some(&trigger.field)
+                    && value_matches(&trigger.from, "from")
+                    && value_matches(&trigger.to, "to")
+            }
+            TriggerConfig::Campaign(trigger) => {
+                trigger
+                    .campaign_id
+                    .is_none_or(|id| text("campaign_id") == Some(&id.to_string()))
+                    && trigger
+                        .url
+                        .as_deref()
+                        .is_none_or(|url| text("url").is_some_and(|u| u.contains(url)))
+            }
+            TriggerConfig::Date(trigger) => {
+                text("field") == Some(&trigger.field)
+                    && data.get("days_before").and_then(Value::as_i64)
+                        == Some(trigger.days_before as i64)
+            }
+        }
+    }
+}
+
+impl DateTrigger {
+    /// `today`に登録する購読者の日付（`today`の`days_before`日後）
+    pub fn target_date(&self, today: NaiveDate) -> NaiveDate {
+        today + chrono::Duration::days(self.days_before as i64)
+    }
+}
+
+/// 日付のカスタムフィールドの値を（年, 月, 日）として読み込む（年は省略可能）
+///
+/// `YYYY-MM-DD`・`YYYY-MM-DDTHH:MM:SS`・`MM-DD`・`--MM-DD` に対応する。
+pub fn parse_field_date(value: &Value) -> Option<(Option<i32>, u32, u32)> {
+    let text = value.as_str()?.trim();
+    let date = text.split(['T', ' ']).next()?;
+    let parts: Vec<&str> = date.trim_start_matches("--").split('-').collect();
+
+    let (year, month, day) = match parts.as_slice() {
+        [year, month, day] if year.len() == 4 => (Some(year.parse().ok()?), month, day),
+        [month, day] => (None, month, day),
+        _ => return None,
+    };
+    let (month, day) = (month.parse().ok()?, day.parse().ok()?);
+
+    // 年のない日付は閏年で検証する（2月29日を許可するため）
+    NaiveDate::from_ymd_opt(year.unwrap_or(2000), month, day)?;
+    Some((year, month, day))
+}
+
+/// `target`の日付に一致する月日（閏年でない年の2月28日は2月29日生まれも含める）
+pub fn month_days_for(target: NaiveDate) -> Vec<String> {
+    let mut month_days = vec![target.format("%m-%d").to_string()];
+    let leap_year = NaiveDate::from_ymd_opt(target.year(), 2, 29).is_some();
+    if target.month() == 2 && target.day() == 28 && !leap_year {
+        month_days.push("02-29".to_string());
+    }
+    month_days
+}
+
+// 指定された値は`null`も含めて`Some`として読み込む（省略時のみ`None`）
+fn present_value<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Value>, D::Error> {
+    Value::deserialize(deserializer).map(Some)
+}
+
+fn read<T: DeserializeOwned>(config: Value, label: &str) -> Result<T, String> {
+    serde_json::from_value(config).map_err(|e| format!("{label}の設定が不正です: {e}"))
+}
+
+fn optional_uuid(config: &Value, key: &str) -> Result<Option<Uuid>, String> {
+    match config.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => value
+            .as_str()
+            .and_then(|id| Uuid::parse_str(id).ok())
+            .map(Some)
+            .ok_or_else(|| format!("{key}はUUIDで指定してください")),
+    }
+}
+
+/// 値が等しいか（数値と数字の文字列など、テキストとして等しい場合も一致とみなす）
+fn same_value(expected: &Value, actual: &Value) -> bool {
+    let text = |value: &Value| match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    };
+
+    expected == actual || (text(expected).is_some() && text(expected) == text(actual))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_validates_each_trigger_type() {
+        assert_eq!(
+            TriggerConfig::parse(TriggerType::Manual, None).unwrap(),
+            TriggerConfig::None
+        );
+        assert!(
+            TriggerConfig::parse(TriggerType::FormSubmission, Some(&json!({"form_id": "x"})))
+                .is_err()
+        );
+        assert!(TriggerConfig::parse(TriggerType::TagRemoved, Some(&json!({"tag": ""}))).is_err());
+        assert!(TriggerConfig::parse(TriggerType::SegmentEntered, Some(&json!({}))).is_err());
+        assert!(TriggerConfig::parse(TriggerType::CustomFieldChanged, Some(&json!({}))).is_err());
+        assert!(TriggerConfig::parse(
+            TriggerType::CustomFieldChanged,
+            Some(&json!({"field": " "}))
+        )
+        .is_err());
+        assert!(TriggerConfig::parse(
+            TriggerType::CampaignOpened,
+            Some(&json!({"url": "/pricing"}))
+        )
+        .is_err());
+        assert!(TriggerConfig::parse(
+            TriggerType::CampaignClicked,
+            Some(&json!({"campaign_id": 1}))
+        )
+        .is_err());
+        assert!(TriggerConfig::parse(TriggerType::Anniversary, Some(&json!({}))).is_err());
+        assert!(
+            TriggerConfig::parse(TriggerType::Birthday, Some(&json!({"days_before": -1}))).is_err()
+        );
+        assert!(
+            TriggerConfig::parse(TriggerType::Birthday, Some(&json!({"days_before": 61}))).is_err()
+        );
+        assert!(TriggerConfig::parse(TriggerType::Birthday, Some(&json!([]))).is_err());
+
+        assert_eq!(
+            TriggerConfig::parse(TriggerType::Birthday, Some(&json!({"days_before": 3}))).unwrap(),
+            TriggerConfig::Date(DateTrigger {
+                field: "birthday".to_string(),
+                days_before: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_matches_trigger_data() {
+        let tag =
+            TriggerConfig::parse(TriggerType::TagRemoved, Some(&json!({"tag": "trial"}))).unwrap();
+        assert!(tag.matches(Some(&json!({"tag": "trial"}))));
+        assert!(!tag.matches(Some(&json!({"tag": "customer"}))));
+        assert!(TriggerConfig::parse(TriggerType::TagAdded, None)
+            .unwrap()
+            .matches(Some(&json!({"tag": "customer"}))));
+
+        let field = TriggerConfig::parse(
+            TriggerType::CustomFieldChanged,
+            Some(&json!({"field": "plan", "to": "pro"})),
+        )
+        .unwrap();
+        assert!(field.matches(Some(&json!({"field": "plan", "from": "free", "to": "pro"}))));
+        assert!(!field.matches(Some(&json!({"field": "plan", "from": "pro", "to": "free"}))));
+        assert!(!field.matches(Some(&json!({"field": "company", "to": "pro"}))));
+
+        let seats = TriggerConfig::parse(
+            TriggerType::CustomFieldChanged,
+            Some(&json!({"field": "seats", "from": null})),
+        )
+        .unwrap();
+        assert!(seats.matches(Some(&json!({"field": "seats", "from": null, "to": 5}))));
+        assert!(!seats.matches(Some(&json!({"field": "seats", "from": 3, "to": 5}))));
+
+        let campaign_id = Uuid::new_v4();
+        let click = TriggerConfig::parse(
+            TriggerType::CampaignClicked,
+            Some(&json!({"campaign_id": campaign_id, "url": "/pricing"})),
+        )
+        .unwrap();
+        assert!(click.matches(Some(&json!({
+            "campaign_id": campaign_id, "url": "https://example.com/pricing?plan=pro"
+        }))));
+        assert!(!click.matches(Some(&json!({
+            "campaign_id": campaign_id, "url": "https://example.com/blog"
+        }))));
+        assert!(!click.matches(Some(&json!({
+            "campaign_id": Uuid::new_v4(), "url": "https://example.com/pricing"
+        }))));
+
+        let birthday = TriggerConfig::parse(TriggerType::Birthday, None).unwrap();
+        assert!(birthday.matches(Some(&json!({"field": "birthday", "days_before": 0}))));
+        assert!(!birthday.matches(Some(&json!({"field": "birthday", "days_before": 7}))));
+    }
+
+    #[test]
+    fn test_parse_field_date() {
+        assert_eq!(
+            parse_field_date(&json!("1990-05-12")),
+            Some((Some(1990), 5, 12))
+        );
+        assert_eq!(
+            parse_field_date(&json!("2020-04-01T09:00:00Z")),
+            Some((Some(2020), 4, 1))
+        );
+        assert_eq!(parse_field_date(&json!("02-29")), Some((None, 2, 29)));
+        assert_eq!(parse_field_date(&json!("--12-24")), Some((None, 12, 24)));
+        assert_eq!(parse_field_date(&json!("2023-02-29")), None);
+        assert_eq!(parse_field_date(&json!("1990/05/12")), None);
+        assert_eq!(parse_field_date(&json!(19900512)), None);
+    }
+
+    #[test]
+    fn test_month_days_for() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!(month_days_for(date(2025, 5, 12)), vec!["05-12"]);
+        assert_eq!(month_days_for(date(2025, 2, 28)), vec!["02-28", "02-29"]);
+        assert_eq!(month_days_for(date(2024, 2, 28)), vec!["02-28"]);
+        assert_eq!(
+            DateTrigger {
+                field: "birthday".to_string(),
+                days_before: 3
+            }
+            .target_date(date(2025, 12, 30)),
+            date(2026, 1, 2)
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// SNSから送信されるメッセージのエンベロープ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SnsEnvelope {
+    #[serde(rename = "Type")]
+    pub message_type: String,
+    pub message_id: String,
+    pub topic_arn: String,
+    pub message: String,
+    pub timestamp: String,
+    pub signature_version: String,
+    pub signature: String,
+    #[serde(rename = "SigningCertURL")]
+    pub signing_cert_url: String,
+    #[serde(default)]
+    pub subject: Option<String>,
+    #[serde(default)]
+    pub token: Option<String>,
+    #[serde(default, rename = "SubscribeURL")]
+    pub subscribe_url: Option<String>,
+}
+
+impl SnsEnvelope {
+    pub fn is_notification(&self) -> bool {
+        self.message_type == "Notification"
+    }
+
+    pub fn is_subscription_confirmation(&self) -> bool {
+        self.message_type == "SubscriptionConfirmation"
+    }
+}
+
+/// SESの通知（SNSメッセージ本文）
+///
+/// SNS通知の`notificationType`と、設定セットのイベント発行の`eventType`の両方に対応する。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SesNotification {
+    #[serde(alias = "eventType")]
+    pub notification_type: String,
+    pub mail: SesMail,
+    #[serde(default)]
+    pub bounce: Option<SesBounce>,
+    #[serde(default)]
+    pub complaint: Option<SesComplaint>,
+    #[serde(default)]
+    pub delivery: Option<SesDelivery>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SesMail {
+    pub message_id: String,
+    #[serde(default)]
+    pub destination: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SesBounce {
+    /// Permanent（ハードバウンス）、Transient、Undetermined
+    pub bounce_type: String,
+    #[serde(default)]
+    pub bounce_sub_type: Option<String>,
+    #[serde(default)]
+    pub bounced_recipients: Vec<SesRecipient>,
+}
+
+impl SesBounce {
+    /// 再送しても届かないハードバウンスか
+    pub fn is_permanent(&self) -> bool {
+        self.bounce_type == "Permanent"
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SesComplaint {
+    #[serde(default)]
+    pub complained_recipients: Vec<SesRecipient>,
+    #[serde(default)]
+    pub complaint_feedback_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SesRecipient {
+    pub email_address: String,
+    #[serde(default)]
+    pub diagnostic_code: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SesDelivery {
+    #[serde(default)]
+    pub recipients: Vec<String>,
+}
+
+/// SES通知の処理結果
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SesFeedbackResult {
+    /// 通知に対応する配信レコードのID
+    pub delivery_id: Option<Uuid>,
+    /// 配信対象外になった購読者のID
+    pub suppressed_subscriber_ids: Vec<Uuid>,
+}
@@ -19,13 +19,48 @@ pub struct Subscriber {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize, sqlx::Type, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, sqlx::Type, Clone, Copy, PartialEq, Eq)]
 #[sqlx(type_name = "subscriber_status", rename_all = "lowercase")]
 pub enum SubscriberStatus {
     Active,
     Unsubscribed,
     Bounced,
     Complained,
+    /// ダブルオプトインの確認待ち
+    Pending,
+}
+
+impl SubscriberStatus {
+    /// メールを送信してよいステータス（配信停止・バウンス・苦情・確認待ちは送信対象外）
+    pub const SENDABLE: &'static [SubscriberStatus] = &[SubscriberStatus::Active];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubscriberStatus::Active => "active",
+            SubscriberStatus::Unsubscribed => "unsubscribed",
+            SubscriberStatus::Bounced => "bounced",
+            SubscriberStatus::Complained => "complained",
+            SubscriberStatus::Pending => "pending",
+        }
+    }
+
+    pub fn is_sendable(&self) -> bool {
+        Self::SENDABLE.contains(self)
+    }
+}
+
+/// 配信停止リンクに埋め込むトークンのクレーム
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnsubscribeClaims {
+    /// 購読者ID
+    pub sub: Uuid,
+    /// 購読者の所有ユーザーID
+    pub uid: Uuid,
+    /// 配信停止のきっかけになったキャンペーンID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cid: Option<Uuid>,
+    /// トークンの用途（他の署名付きトークンとの取り違え防止）
+    pub purpose: String,
 }
 
 // 購読者作成リクエスト
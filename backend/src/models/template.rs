@@ -15,6 +15,8 @@ pub struct Template {
     pub html_content: Option<String>,
     pub variables: Value, // JSONB型
     pub is_public: bool,
+    /// 現在の内容に対応するリビジョン番号
+    pub current_revision: i32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -84,6 +86,7 @@ pub struct TemplateResponse {
     pub html_content: Option<String>,
     pub variables: Value,
     pub is_public: bool,
+    pub current_revision: i32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -99,6 +102,7 @@ impl From<Template> for TemplateResponse {
             html_content: template.html_content,
             variables: template.variables,
             is_public: template.is_public,
+            current_revision: template.current_revision,
             created_at: template.created_at,
             updated_at: template.updated_at,
         }
@@ -130,5 +134,58 @@ pub struct AnalyzeTemplateResponse {
     pub standard_variables: Vec<String>,
     pub custom_variables: Vec<String>,
     pub defined_variables: Vec<String>,
+    /// defaultフィルターやifで扱っていて、値がなくても描画できる変数
+    pub optional_variables: Vec<String>,
     pub missing_variables: Vec<String>,
 }
+
+/// テンプレートのリビジョン（保存時点の件名・本文・変数）
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TemplateRevision {
+    pub id: Uuid,
+    pub template_id: Uuid,
+    pub revision: i32,
+    pub subject_template: String,
+    pub markdown_content: String,
+    pub variables: Value,
+    pub created_by: Option<Uuid>,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TemplateRevision {
+    /// リビジョンの内容に差し替えたテンプレート
+    pub fn apply_to(&self, template: &Template) -> Template {
+        Template {
+            subject_template: self.subject_template.clone(),
+            markdown_content: self.markdown_content.clone(),
+            html_content: None,
+            variables: self.variables.clone(),
+            current_revision: self.revision,
+            ..template.clone()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TemplateDiffQuery {
+    /// 比較元のリビジョン（省略時は比較先の1つ前）
+    pub from: Option<i32>,
+    /// 比較先のリビジョン（省略時は現在のリビジョン）
+    pub to: Option<i32>,
+}
+
+/// 2つのリビジョンの差分
+#[derive(Debug, Serialize)]
+pub struct TemplateDiffResponse {
+    pub from_revision: i32,
+    pub to_revision: i32,
+    pub subject_changed: bool,
+    /// 件名の差分（unified形式）
+    pub subject_diff: String,
+    /// 本文の差分（unified形式）
+    pub markdown_diff: String,
+    pub variables_changed: bool,
+    pub insertions: usize,
+    pub deletions: usize,
+}
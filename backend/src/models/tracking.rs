@@ -0,0 +1,46 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// 計測イベント
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TrackingEvent {
+    pub id: Uuid,
+    pub campaign_id: Uuid,
+    pub subscriber_id: Uuid,
+    pub event_type: String,
+    pub url: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 計測イベント種別
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrackingEventType {
+    Open,
+    Click,
+}
+
+impl TrackingEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrackingEventType::Open => "open",
+            TrackingEventType::Click => "click",
+        }
+    }
+}
+
+/// 計測URLに埋め込むトークンのクレーム
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackingClaims {
+    /// キャンペーンID
+    pub c: Uuid,
+    /// 購読者ID
+    pub s: Uuid,
+    /// リダイレクト先URL（クリック計測のみ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub u: Option<String>,
+}
@@ -0,0 +1,222 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    database::{ab_tests, templates},
+    models::{
+        ab_test::{
+            AbTestResponse, AbTestStatus, ConfigureAbTestRequest, VariantStats, WinnerMetric,
+        },
+        campaign::{Campaign, CampaignStatus},
+    },
+};
+
+pub struct AbTestService;
+
+impl Default for AbTestService {
+    fn default() -> Self {
+        Self
+    }
+}
+
+impl AbTestService {
+    pub fn new() -> Self {
+        Self
+    }
+
+    // A/Bテストを設定（既存の設定は置き換える）
+    pub async fn configure_ab_test(
+        &self,
+        pool: &PgPool,
+        campaign: &Campaign,
+        request: &ConfigureAbTestRequest,
+    ) -> Result<AbTestResponse, String> {
+        if !matches!(
+            campaign.status,
+            CampaignStatus::Draft | CampaignStatus::Scheduled
+        ) {
+            return Err(
+                "送信済みまたは送信中のキャンペーンのA/Bテストは変更できません".to_string(),
+            );
+        }
+
+        self.ensure_not_started(pool, campaign.id).await?;
+        request.validate()?;
+
+        // バリアントのテンプレートが存在するか確認
+        for template_id in request.variants.iter().filter_map(|v| v.template_id) {
+            let template =
+                templates::find_template_by_id(pool, template_id, Some(campaign.user_id))
+                    .await
+                    .map_err(|e| format!("テンプレートの確認に失敗しました: {e}"))?;
+
+            if template.is_none() {
+                return Err(
+                    "指定されたテンプレートが見つからないか、アクセス権限がありません".to_string(),
+                );
+            }
+        }
+
+        let (ab_test, variants) = ab_tests::save_ab_test(pool, campaign.id, request)
+            .await
+            .map_err(|e| format!("A/Bテストの保存に失敗しました: {e}"))?;
+
+        Ok(AbTestResponse {
+            ab_test,
+            variants,
+            results: Vec::new(),
+        })
+    }
+
+    // A/Bテストの設定と結果を取得
+    pub async fn get_ab_test(
+        &self,
+        pool: &PgPool,
+        campaign_id: Uuid,
+    ) -> Result<Option<AbTestResponse>, String> {
+        let Some(ab_test) = ab_tests::find_ab_test(pool, campaign_id)
+            .await
+            .map_err(|e| format!("A/Bテストの取得に失敗しました: {e}"))?
+        else {
+            return Ok(None);
+        };
+
+        let variants = ab_tests::list_variants(pool, campaign_id)
+            .await
+            .map_err(|e| format!("バリアントの取得に失敗しました: {e}"))?;
+        let results = ab_tests::variant_stats(pool, campaign_id)
+            .await
+            .map_err(|e| format!("A/Bテスト結果の集計に失敗しました: {e}"))?;
+
+        Ok(Some(AbTestResponse {
+            ab_test,
+            variants,
+            results,
+        }))
+    }
+
+    // A/Bテストを削除（通常のキャンペーンとして送信される）
+    pub async fn delete_ab_test(&self, pool: &PgPool, campaign: &Campaign) -> Result<bool, String> {
+        self.ensure_not_started(pool, campaign.id).await?;
+
+        ab_tests::delete_ab_test(pool, campaign.id)
+            .await
+            .map_err(|e| format!("A/Bテストの削除に失敗しました: {e}"))
+    }
+
+    async fn ensure_not_started(&self, pool: &PgPool, campaign_id: Uuid) -> Result<(), String> {
+        let existing = ab_tests::find_ab_test(pool, campaign_id)
+            .await
+            .map_err(|e| format!("A/Bテストの取得に失敗しました: {e}"))?;
+
+        match existing {
+            Some(ab_test) if ab_test.status() != AbTestStatus::Draft => {
+                Err("テスト送信を開始したA/Bテストは変更できません".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// テスト対象の人数をバリアントごとに割り当てる
+///
+/// テスト対象は配信対象の`test_percentage`%（切り上げ、各バリアント最低1人）で、
+/// バリアントの配分に従って最大剰余法で分ける。
+pub fn allocate_test_cohort(total: usize, test_percentage: i32, splits: &[i32]) -> Vec<usize> {
+    let split_total: usize = splits.iter().map(|s| (*s).max(0) as usize).sum();
+    if splits.is_empty() || split_total == 0 {
+        return vec![0; splits.len()];
+    }
+
+    let percentage = test_percentage.clamp(0, 100) as usize;
+    let cohort = (total * percentage)
+        .div_ceil(100)
+        .max(splits.len())
+        .min(total);
+
+    let shares: Vec<(usize, usize)> = splits
+        .iter()
+        .map(|s| {
+            let numerator = cohort * (*s).max(0) as usize;
+            (numerator / split_total, numerator % split_total)
+        })
+        .collect();
+
+    let mut counts: Vec<usize> = shares.iter().map(|(count, _)| *count).collect();
+    let mut remaining = cohort - counts.iter().sum::<usize>();
+
+    let mut order: Vec<usize> = (0..splits.len()).collect();
+    order.sort_by(|a, b| shares[*b].1.cmp(&shares[*a].1).then(a.cmp(b)));
+    for i in order {
+        if remaining == 0 {
+            break;
+        }
+        counts[i] += 1;
+        remaining -= 1;
+    }
+
+    counts
+}
+
+/// 判定指標の率が最も高いバリアントを勝者とする（同率の場合は先のバリアント）
+pub fn select_winner(stats: &[VariantStats], metric: WinnerMetric) -> Option<Uuid> {
+    stats
+        .iter()
+        .fold(None::<&VariantStats>, |best, candidate| match best {
+            Some(best) if best.rate(metric) >= candidate.rate(metric) => Some(best),
+            _ => Some(candidate),
+        })
+        .map(|winner| winner.variant_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocate_test_cohort() {
+        // 1000人の20%を50:50で分ける
+        assert_eq!(allocate_test_cohort(1000, 20, &[50, 50]), vec![100, 100]);
+        // 端数は剰余の大きいバリアントから割り当てる
+        assert_eq!(allocate_test_cohort(10, 100, &[34, 33, 33]), vec![4, 3, 3]);
+        assert_eq!(allocate_test_cohort(101, 10, &[50, 50]), vec![6, 5]);
+        // 少人数でも各バリアントに最低1人
+        assert_eq!(allocate_test_cohort(10, 1, &[50, 50]), vec![1, 1]);
+        // 配信対象がバリアント数より少ない場合は全員
+        assert_eq!(allocate_test_cohort(1, 50, &[50, 50]), vec![1, 0]);
+        assert_eq!(allocate_test_cohort(0, 50, &[50, 50]), vec![0, 0]);
+    }
+
+    fn stats(name: &str, sent: i64, opened: i64, clicked: i64) -> VariantStats {
+        VariantStats {
+            variant_id: Uuid::new_v4(),
+            name: name.to_string(),
+            subject: format!("件名{name}"),
+            sent,
+            opened,
+            clicked,
+        }
+    }
+
+    #[test]
+    fn test_select_winner() {
+        let results = vec![stats("A", 100, 30, 2), stats("B", 80, 20, 8)];
+
+        assert_eq!(
+            select_winner(&results, WinnerMetric::Opens),
+            Some(results[0].variant_id)
+        );
+        assert_eq!(
+            select_winner(&results, WinnerMetric::Clicks),
+            Some(results[1].variant_id)
+        );
+
+        // 同率の場合は先のバリアント
+        let tied = vec![stats("A", 10, 0, 0), stats("B", 10, 0, 0)];
+        assert_eq!(
+            select_winner(&tied, WinnerMetric::Opens),
+            Some(tied[0].variant_id)
+        );
+        assert_eq!(select_winner(&[], WinnerMetric::Opens), None);
+    }
+}
@@ -1,32 +1,71 @@
-use chrono::Utc;
+use std::collections::HashMap;
+
+use chrono::{Duration, Utc};
+use rand::seq::SliceRandom;
 use sqlx::PgPool;
+use tokio::sync::watch;
 use uuid::Uuid;
 
 use crate::{
-    database::{campaigns, subscribers, templates},
+    database::{
+        ab_tests,
+        campaign_deliveries::{self, CampaignLock},
+        campaigns, segments, subscribers, template_revisions, templates,
+    },
     models::{
+        ab_test::{AbTestStatus, CampaignAbTest, CampaignVariant, WinnerMetric},
+        audience::AudienceDefinition,
         campaign::{
-            Campaign, CreateCampaignRequest, ScheduleCampaignRequest, UpdateCampaignRequest,
+            Campaign, CampaignDelivery, CampaignStatus, CreateCampaignRequest, DeliveryCounts,
+            DeliveryStatus, ScheduleCampaignRequest, UpdateCampaignRequest,
         },
-        subscriber::Subscriber,
+        subscriber::{Subscriber, SubscriberStatus},
+        template::Template,
     },
     services::{
-        email_service::{EmailMessage, EmailService},
+        ab_test_service::{allocate_test_cohort, select_winner},
+        email_html,
+        email_service::{EmailMessage, EmailService, EmailStatus},
+        markdown_component_service::MarkdownComponentService,
         markdown_service::MarkdownService,
+        sending_domain_service::{SendingDomainService, VerifiedSender},
+        tracking_service::TrackingService,
+        unsubscribe_service::UnsubscribeService,
     },
 };
 
-pub struct CampaignService;
+/// 一時的な送信エラーの最大試行回数
+const DELIVERY_MAX_ATTEMPTS: i32 = 5;
+
+/// 送信中のまま完了しない配信を再確保するまでの秒数
+pub(crate) const DELIVERY_STALE_SECONDS: i64 = 600;
+
+pub struct CampaignService {
+    /// 停止要求（trueになると実行中のバッチの送信後に配信を中断する）
+    shutdown: Option<watch::Receiver<bool>>,
+}
 
 impl Default for CampaignService {
     fn default() -> Self {
-        Self
+        Self::new()
     }
 }
 
 impl CampaignService {
     pub fn new() -> Self {
-        Self
+        Self { shutdown: None }
+    }
+
+    /// 停止要求を受けると配信を中断する（未送信の配信は送信中のまま残り、再起動後に再開する）
+    pub fn with_shutdown(mut self, shutdown: watch::Receiver<bool>) -> Self {
+        self.shutdown = Some(shutdown);
+        self
+    }
+
+    fn is_shutting_down(&self) -> bool {
+        self.shutdown
+            .as_ref()
+            .is_some_and(|shutdown| *shutdown.borrow())
     }
 
     // キャンペーン作成
@@ -47,6 +86,22 @@ impl CampaignService {
             );
         }
 
+        // 配信対象の定義を検証
+        if let Some(audience) = &request.audience {
+            Self::validate_audience(pool, user_id, audience).await?;
+        }
+
+        // 送信元は認証済みの送信ドメインのアドレスのみ
+        if let Some(from_email) = request.from_email.as_deref() {
+            SendingDomainService::verified_sender(
+                pool,
+                user_id,
+                from_email,
+                request.from_name.as_deref(),
+            )
+            .await?;
+        }
+
         // キャンペーンを作成
         let campaign = campaigns::create_campaign(pool, user_id, request)
             .await
@@ -76,6 +131,26 @@ impl CampaignService {
             }
         }
 
+        // 配信対象の定義を検証
+        if let Some(audience) = &request.audience {
+            Self::validate_audience(pool, user_id, audience).await?;
+        }
+
+        // 送信元は認証済みの送信ドメインのアドレスのみ（空文字は解除）
+        if let Some(from_email) = request
+            .from_email
+            .as_deref()
+            .filter(|e| !e.trim().is_empty())
+        {
+            SendingDomainService::verified_sender(
+                pool,
+                user_id,
+                from_email,
+                request.from_name.as_deref(),
+            )
+            .await?;
+        }
+
         // キャンペーンを更新
         let updated_campaign = campaigns::update_campaign(pool, campaign_id, user_id, request)
             .await
@@ -87,6 +162,27 @@ impl CampaignService {
         }
     }
 
+    // 配信対象の定義を検証（参照するセグメントの存在も確認する）
+    async fn validate_audience(
+        pool: &PgPool,
+        user_id: Uuid,
+        audience: &AudienceDefinition,
+    ) -> Result<(), String> {
+        audience.validate()?;
+
+        if let Some(segment_id) = audience.segment_id {
+            let segment = segments::find_segment_by_id(pool, segment_id, user_id)
+                .await
+                .map_err(|e| format!("セグメントの確認に失敗しました: {e}"))?;
+
+            if segment.is_none() {
+                return Err("指定されたセグメントが見つかりません".to_string());
+            }
+        }
+
+        Ok(())
+    }
+
     // キャンペーンをスケジュール
     pub async fn schedule_campaign(
         &self,
@@ -107,11 +203,39 @@ impl CampaignService {
                 .map_err(|e| format!("キャンペーンのスケジュールに失敗しました: {e}"))?;
 
         match updated_campaign {
+            // 既にスケジューラーが送信を開始している場合は変更されない
+            Some(campaign) if campaign.status != CampaignStatus::Scheduled => {
+                Err("送信中または送信済みのキャンペーンはスケジュールを変更できません".to_string())
+            }
             Some(campaign) => Ok(campaign),
             None => Err("キャンペーンが見つからないか、スケジュール権限がありません".to_string()),
         }
     }
 
+    // キャンペーンのスケジュールを取り消し
+    pub async fn cancel_scheduled_campaign(
+        &self,
+        pool: &PgPool,
+        campaign_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Campaign, String> {
+        if let Some(campaign) = campaigns::cancel_scheduled_campaign(pool, campaign_id, user_id)
+            .await
+            .map_err(|e| format!("キャンペーンのスケジュール取り消しに失敗しました: {e}"))?
+        {
+            return Ok(campaign);
+        }
+
+        // 取り消せなかった理由を判定
+        match campaigns::find_campaign_by_id(pool, campaign_id, user_id)
+            .await
+            .map_err(|e| format!("キャンペーン情報の取得に失敗しました: {e}"))?
+        {
+            Some(_) => Err("送信待ちのキャンペーンのみスケジュールを取り消せます".to_string()),
+            None => Err("キャンペーンが見つからないか、更新権限がありません".to_string()),
+        }
+    }
+
     // キャンペーン送信開始
     pub async fn start_sending_campaign(
         &self,
@@ -161,8 +285,10 @@ impl CampaignService {
             "unsubscribe_url": "https://markmail.example.com/unsubscribe?id=12345"
         });
 
-        // マークダウンサービスでHTMLを生成
-        let markdown_service = MarkdownService::new();
+        // マークダウンサービスでHTMLを生成（ユーザーのコンポーネントを展開する）
+        let markdown_service = MarkdownComponentService::new()
+            .markdown_service(pool, user_id)
+            .await?;
         let html = markdown_service
             .render_with_variables(&template.markdown_content, &test_data)
             .map_err(|e| format!("マークダウンのレンダリングに失敗しました: {e}"))?;
@@ -170,29 +296,72 @@ impl CampaignService {
         Ok(html)
     }
 
-    // 指定したキャンペーンの購読者一覧を取得
+    async fn find_campaign(
+        &self,
+        pool: &PgPool,
+        campaign_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Campaign, String> {
+        campaigns::find_campaign_by_id(pool, campaign_id, user_id)
+            .await
+            .map_err(|e| format!("キャンペーン情報の取得に失敗しました: {e}"))?
+            .ok_or_else(|| "キャンペーンが見つかりません".to_string())
+    }
+
+    // 指定したキャンペーンの配信対象の購読者一覧を取得
     pub async fn get_campaign_subscribers(
         &self,
         pool: &PgPool,
-        _campaign_id: Uuid,
+        campaign_id: Uuid,
         user_id: Uuid,
     ) -> Result<Vec<Subscriber>, String> {
-        // アクティブな購読者を取得
-        let options = crate::models::subscriber::ListSubscriberOptions {
-            limit: None,
-            offset: None,
-            search: None,
-            tag: None,
-            status: None, // 一時的にステータスフィルタを無効化
-            sort_by: None,
-            sort_order: None,
-        };
+        let campaign = self.find_campaign(pool, campaign_id, user_id).await?;
 
-        let subscribers = subscribers::list_user_subscribers(pool, user_id, &options)
+        subscribers::list_audience_subscribers(pool, user_id, &campaign.audience, None, None)
             .await
-            .map_err(|e| format!("購読者の取得に失敗しました: {e}"))?;
+            .map_err(|e| format!("購読者の取得に失敗しました: {e}"))
+    }
+
+    // キャンペーンの配信対象の購読者を取得（プレビュー用、ページネーション対応）
+    pub async fn preview_campaign_subscribers(
+        &self,
+        pool: &PgPool,
+        campaign_id: Uuid,
+        user_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<Subscriber>, i64), String> {
+        let campaign = self.find_campaign(pool, campaign_id, user_id).await?;
+
+        let subscribers = subscribers::list_audience_subscribers(
+            pool,
+            user_id,
+            &campaign.audience,
+            Some(limit),
+            Some(offset),
+        )
+        .await
+        .map_err(|e| format!("購読者の取得に失敗しました: {e}"))?;
+
+        let total = subscribers::count_audience_subscribers(pool, user_id, &campaign.audience)
+            .await
+            .map_err(|e| format!("購読者数の取得に失敗しました: {e}"))?;
+
+        Ok((subscribers, total))
+    }
+
+    // キャンペーンの配信対象の購読者数を取得
+    pub async fn count_campaign_subscribers(
+        &self,
+        pool: &PgPool,
+        campaign_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<i64, String> {
+        let campaign = self.find_campaign(pool, campaign_id, user_id).await?;
 
-        Ok(subscribers)
+        subscribers::count_audience_subscribers(pool, user_id, &campaign.audience)
+            .await
+            .map_err(|e| format!("購読者数の取得に失敗しました: {e}"))
     }
 
     // キャンペーンの送信処理
@@ -209,6 +378,24 @@ impl CampaignService {
 
         if let Err(ref e) = result {
             tracing::error!("キャンペーン送信処理でエラー: {}", e);
+
+            // 未処理の配信が残っている場合は送信中のままにし、配信ワーカーに再開させる
+            match campaign_deliveries::count_deliveries(pool, campaign_id).await {
+                Ok(counts) if counts.has_unfinished() => {
+                    tracing::warn!(
+                        "キャンペーン {} の未処理の配信はワーカーが再開します（送信待ち: {}, 送信中: {}）",
+                        campaign_id,
+                        counts.pending,
+                        counts.sending
+                    );
+                    return result;
+                }
+                Ok(_) => {}
+                Err(count_err) => {
+                    tracing::error!("配信状況の取得に失敗: {}", count_err);
+                }
+            }
+
             // エラーステータスに更新
             if let Err(update_err) = campaigns::update_campaign_status(
                 pool,
@@ -226,11 +413,38 @@ impl CampaignService {
     }
 
     // 実際の送信処理
+    //
+    // 購読者ごとの配信レコードを作成し、キューから順に送信する。
+    // 同じキャンペーンを複数のワーカーが同時に送信しないよう、キャンペーン単位でロックを取得する。
     async fn process_campaign_sending_internal(
         &self,
         pool: &PgPool,
         campaign_id: Uuid,
         user_id: Uuid,
+    ) -> Result<(), String> {
+        let Some(lock) = CampaignLock::try_acquire(pool, campaign_id)
+            .await
+            .map_err(|e| format!("配信ロックの取得に失敗しました: {e}"))?
+        else {
+            tracing::debug!("キャンペーン {} は他のワーカーが送信中です", campaign_id);
+            return Ok(());
+        };
+
+        let result = self.deliver_campaign(pool, campaign_id, user_id).await;
+
+        if let Err(e) = lock.release().await {
+            tracing::error!("配信ロックの解放に失敗しました: {}", e);
+        }
+
+        result
+    }
+
+    // 配信キューの作成から送信完了までを実行
+    async fn deliver_campaign(
+        &self,
+        pool: &PgPool,
+        campaign_id: Uuid,
+        user_id: Uuid,
     ) -> Result<(), String> {
         // キャンペーン情報を取得
         let campaign = campaigns::find_campaign_by_id(pool, campaign_id, user_id)
@@ -244,13 +458,89 @@ impl CampaignService {
             .map_err(|e| format!("テンプレート情報の取得に失敗しました: {e}"))?
             .ok_or_else(|| "テンプレートが見つかりません".to_string())?;
 
-        // 購読者リストを取得
-        let subscribers = self
-            .get_campaign_subscribers(pool, campaign_id, user_id)
-            .await?;
+        // 配信するリビジョンを固定（再開時は固定済みのリビジョンで送る）
+        let (template, revision_id) =
+            pinned_template(pool, user_id, template, campaign.template_revision_id).await?;
+        if campaign.template_revision_id.is_none() {
+            campaigns::pin_template_revision(pool, campaign_id, revision_id)
+                .await
+                .map_err(|e| format!("テンプレートのリビジョンの固定に失敗しました: {e}"))?;
+        }
+
+        // A/Bテストの設定（バリアントが2つ以上ある場合のみ有効）
+        let mut ab_test = ab_tests::find_ab_test(pool, campaign_id)
+            .await
+            .map_err(|e| format!("A/Bテストの取得に失敗しました: {e}"))?;
+        let variants = match &ab_test {
+            Some(_) => ab_tests::list_variants(pool, campaign_id)
+                .await
+                .map_err(|e| format!("バリアントの取得に失敗しました: {e}"))?,
+            None => Vec::new(),
+        };
+        if variants.len() < 2 {
+            ab_test = None;
+        }
+
+        let mut content = CampaignContent::load(pool, user_id, template, &variants).await?;
+
+        // ユーザーの送信ドメインから送る場合はドメインの鍵でDKIM署名する
+        if let Some(from_email) = campaign.from_email.as_deref() {
+            content.sender = Some(
+                SendingDomainService::verified_sender(
+                    pool,
+                    user_id,
+                    from_email,
+                    campaign.from_name.as_deref(),
+                )
+                .await?,
+            );
+        }
+
+        // 配信キューが未作成の場合は購読者を登録（再開時は既存のキューをそのまま使う）
+        let counts = campaign_deliveries::count_deliveries(pool, campaign_id)
+            .await
+            .map_err(|e| format!("配信状況の取得に失敗しました: {e}"))?;
+        if counts.total == 0 {
+            let subscribers = subscribers::list_audience_subscribers(
+                pool,
+                user_id,
+                &campaign.audience,
+                None,
+                None,
+            )
+            .await
+            .map_err(|e| format!("購読者の取得に失敗しました: {e}"))?;
 
-        if subscribers.is_empty() {
-            return Err("送信対象の購読者が存在しません".to_string());
+            if subscribers.is_empty() {
+                return Err("送信対象の購読者が存在しません".to_string());
+            }
+
+            match &ab_test {
+                Some(ab_test) => {
+                    self.enqueue_ab_test_cohort(pool, ab_test, &variants, subscribers)
+                        .await?
+                }
+                None => {
+                    campaign_deliveries::enqueue_deliveries(pool, campaign_id, &subscribers)
+                        .await
+                        .map_err(|e| format!("配信キューの作成に失敗しました: {e}"))?;
+                }
+            }
+        }
+
+        // テスト送信の開始を記録し、待機時間が経過していれば勝者を残りの購読者に送信
+        if let Some(test) = &mut ab_test {
+            if test.status() == AbTestStatus::Draft {
+                ab_tests::start_ab_test(pool, campaign_id)
+                    .await
+                    .map_err(|e| format!("A/Bテストの開始に失敗しました: {e}"))?;
+                test.status = AbTestStatus::Testing.as_str().to_string();
+                test.test_started_at = Some(Utc::now());
+            }
+
+            if test.status() == AbTestStatus::Testing && test.is_wait_elapsed(Utc::now()) {
+                self.send_ab_test_winner(pool, &campaign, test).await?;
+            }
         }
 
         // メールサービスを初期化
@@ -258,154 +548,578 @@ impl CampaignService {
             .await
             .map_err(|e| format!("メールサービスの初期化に失敗しました: {e}"))?;
 
-        // マークダウンサービスを初期化
-        let markdown_service = MarkdownService::new();
-
-        // 購読者ごとにメールメッセージを作成
-        let mut email_messages = Vec::new();
-        for subscriber in &subscribers {
-            // 購読者固有の変数を設定
-            let mut variables = if template.variables.is_null() {
-                serde_json::json!({})
-            } else {
-                template.variables.clone()
-            };
+        let renderer = CampaignRenderer::new(
+            MarkdownComponentService::new()
+                .markdown_service(pool, user_id)
+                .await?,
+        );
 
-            // variablesをオブジェクトとして扱う
-            if let serde_json::Value::Object(ref mut map) = variables {
-                let name = subscriber
-                    .name
-                    .clone()
-                    .unwrap_or_else(|| "お客様".to_string());
-
-                // name と first_name の両方を設定（互換性のため）
-                map.insert("name".to_string(), serde_json::json!(name.clone()));
-                map.insert("first_name".to_string(), serde_json::json!(name));
-                map.insert(
-                    "email".to_string(),
-                    serde_json::json!(subscriber.email.clone()),
+        loop {
+            if self.is_shutting_down() {
+                tracing::info!(
+                    "停止要求を受けたためキャンペーン {} の配信を中断します",
+                    campaign_id
                 );
+                break;
+            }
 
-                // カスタムフィールドを変数に追加
-                if let serde_json::Value::Object(custom_fields) = &subscriber.custom_fields {
-                    for (key, value) in custom_fields {
-                        map.insert(key.clone(), value.clone());
-                    }
-                }
+            let deliveries = campaign_deliveries::claim_deliveries(
+                pool,
+                campaign_id,
+                email_service.batch_size() as i64,
+                DELIVERY_STALE_SECONDS,
+            )
+            .await
+            .map_err(|e| format!("配信キューの取得に失敗しました: {e}"))?;
 
-                // 配信停止URLを追加
-                map.insert(
-                    "unsubscribe_url".to_string(),
-                    serde_json::json!(format!(
-                        "https://markmail.example.com/unsubscribe/{}",
-                        subscriber.id
-                    )),
-                );
+            if deliveries.is_empty() {
+                break;
             }
 
-            // HTMLとテキストをレンダリング
-            let html_body = markdown_service
-                .render_with_variables(&template.markdown_content, &variables)
-                .map_err(|e| format!("HTMLレンダリングに失敗しました: {e}"))?;
+            let subscriber_ids: Vec<Uuid> = deliveries.iter().map(|d| d.subscriber_id).collect();
+            let subscribers: HashMap<Uuid, Subscriber> =
+                subscribers::find_subscribers_by_ids(pool, user_id, &subscriber_ids)
+                    .await
+                    .map_err(|e| format!("購読者情報の取得に失敗しました: {e}"))?
+                    .into_iter()
+                    .map(|s| (s.id, s))
+                    .collect();
 
-            let text_body = html2text::from_read(html_body.as_bytes(), 80);
-
-            // 件名の変数を置換
-            let subject = if let serde_json::Value::Object(vars) = &variables {
-                let mut subject = template.subject_template.clone();
-                for (key, value) in vars {
-                    if let serde_json::Value::String(val) = value {
-                        subject = subject.replace(&format!("{{{{{key}}}}}"), val);
-                    }
+            for delivery in &deliveries {
+                // 結果を記録できなかった配信は送信中のまま残り、一定時間後に再度確保される
+                if let Err(e) = self
+                    .send_delivery(
+                        pool,
+                        &email_service,
+                        &renderer,
+                        &content,
+                        delivery,
+                        subscribers.get(&delivery.subscriber_id),
+                    )
+                    .await
+                {
+                    tracing::error!("配信 {} の処理に失敗しました: {}", delivery.id, e);
                 }
-                subject
-            } else {
-                template.subject_template.clone()
-            };
+            }
 
-            let email_message = EmailMessage {
-                to: vec![subscriber.email.clone()],
-                subject,
-                html_body,
-                text_body: Some(text_body),
-                reply_to: None,
-                headers: None,
-            };
+            // 進捗を統計情報に反映
+            self.sync_delivery_stats(pool, campaign_id).await?;
+
+            // レート制限のための待機
+            email_service.wait_for_rate_limit().await;
+        }
+
+        let awaiting_winner = ab_test
+            .as_ref()
+            .is_some_and(|test| test.status() != AbTestStatus::Completed);
+
+        self.finalize_campaign_sending(pool, &campaign, awaiting_winner)
+            .await
+    }
+
+    // 配信対象をシャッフルし、テスト対象をバリアントごとに配信キューに登録
+    async fn enqueue_ab_test_cohort(
+        &self,
+        pool: &PgPool,
+        ab_test: &CampaignAbTest,
+        variants: &[CampaignVariant],
+        mut subscribers: Vec<Subscriber>,
+    ) -> Result<(), String> {
+        subscribers.shuffle(&mut rand::thread_rng());
+
+        let splits: Vec<i32> = variants.iter().map(|v| v.split_percentage).collect();
+        let allocation = allocate_test_cohort(subscribers.len(), ab_test.test_percentage, &splits);
+
+        let mut offset = 0;
+        for (variant, count) in variants.iter().zip(allocation) {
+            let cohort = &subscribers[offset..offset + count];
+            offset += count;
+
+            campaign_deliveries::enqueue_variant_deliveries(
+                pool,
+                ab_test.campaign_id,
+                variant.id,
+                cohort,
+            )
+            .await
+            .map_err(|e| format!("配信キューの作成に失敗しました: {e}"))?;
+        }
+
+        tracing::info!(
+            "キャンペーン {} のA/Bテストを開始します（テスト対象: {}人 / {}人）",
+            ab_test.campaign_id,
+            offset,
+            subscribers.len()
+        );
+
+        Ok(())
+    }
 
-            email_messages.push(email_message);
+    // テスト送信の結果から勝者を判定し、残りの配信対象に勝者のバリアントを送信
+    async fn send_ab_test_winner(
+        &self,
+        pool: &PgPool,
+        campaign: &Campaign,
+        ab_test: &mut CampaignAbTest,
+    ) -> Result<(), String> {
+        // テスト送信の再送待ちが残っている間は判定しない
+        let counts = campaign_deliveries::count_deliveries(pool, campaign.id)
+            .await
+            .map_err(|e| format!("配信状況の取得に失敗しました: {e}"))?;
+        if counts.has_unfinished() {
+            return Ok(());
         }
 
-        // バッチでメール送信
-        let results = email_service
-            .send_campaign(email_messages)
+        let stats = ab_tests::variant_stats(pool, campaign.id)
+            .await
+            .map_err(|e| format!("A/Bテスト結果の集計に失敗しました: {e}"))?;
+        let metric = WinnerMetric::from(ab_test.winner_metric.clone());
+        let Some(winner_id) = select_winner(&stats, metric) else {
+            return Err("A/Bテストのバリアントが見つかりません".to_string());
+        };
+
+        ab_tests::complete_ab_test(pool, campaign.id, winner_id)
             .await
-            .map_err(|e| format!("メール送信に失敗しました: {e}"))?;
-
-        // 送信結果を集計
-        let mut sent_count = 0;
-        let mut failed_count = 0;
-        for result in &results {
-            match result.status {
-                crate::services::email_service::EmailStatus::Sent => sent_count += 1,
-                crate::services::email_service::EmailStatus::Failed => failed_count += 1,
-                _ => {}
+            .map_err(|e| format!("A/Bテスト結果の記録に失敗しました: {e}"))?;
+        ab_test.status = AbTestStatus::Completed.as_str().to_string();
+        ab_test.winner_variant_id = Some(winner_id);
+
+        // テスト送信済みの購読者は配信キューに登録済みのため除外される
+        let subscribers = subscribers::list_audience_subscribers(
+            pool,
+            campaign.user_id,
+            &campaign.audience,
+            None,
+            None,
+        )
+        .await
+        .map_err(|e| format!("購読者の取得に失敗しました: {e}"))?;
+        let enqueued = campaign_deliveries::enqueue_variant_deliveries(
+            pool,
+            campaign.id,
+            winner_id,
+            &subscribers,
+        )
+        .await
+        .map_err(|e| format!("配信キューの作成に失敗しました: {e}"))?;
+
+        tracing::info!(
+            "キャンペーン {} のA/Bテストの勝者 {} を{}人に送信します（指標: {}）",
+            campaign.id,
+            winner_id,
+            enqueued,
+            metric.as_str()
+        );
+
+        Ok(())
+    }
+
+    // 1件の配信レコードを送信し、結果を記録
+    async fn send_delivery(
+        &self,
+        pool: &PgPool,
+        email_service: &EmailService,
+        renderer: &CampaignRenderer,
+        content: &CampaignContent,
+        delivery: &CampaignDelivery,
+        subscriber: Option<&Subscriber>,
+    ) -> Result<(), String> {
+        let record_error = |e: sqlx::Error| format!("配信結果の記録に失敗しました: {e}");
+
+        // 送信までの間に配信停止・バウンスした購読者には送らない
+        let subscriber = match subscriber {
+            Some(subscriber) if matches!(subscriber.status, SubscriberStatus::Active) => subscriber,
+            _ => {
+                return campaign_deliveries::finish_delivery(
+                    pool,
+                    delivery.id,
+                    DeliveryStatus::Skipped,
+                    "配信対象外の購読者です",
+                )
+                .await
+                .map_err(record_error);
             }
+        };
+
+        let (template, subject) = content.for_delivery(delivery);
+        let mut message =
+            match renderer.build_message(template, subject, delivery.campaign_id, subscriber) {
+                Ok(message) => message,
+                Err(e) => {
+                    return campaign_deliveries::finish_delivery(
+                        pool,
+                        delivery.id,
+                        DeliveryStatus::Failed,
+                        &e,
+                    )
+                    .await
+                    .map_err(record_error);
+                }
+            };
+
+        if let Some(sender) = &content.sender {
+            message.from = Some(sender.from.clone());
+            message.dkim = Some(sender.dkim.clone());
+        }
+
+        let (error, transient) = match email_service.send_email(&message).await {
+            Ok(result) if result.status == EmailStatus::Sent => {
+                return campaign_deliveries::mark_delivery_sent(
+                    pool,
+                    delivery.id,
+                    &result.message_id,
+                )
+                .await
+                .map_err(record_error);
+            }
+            Ok(result) => (
+                result
+                    .error
+                    .unwrap_or_else(|| "メール送信に失敗しました".to_string()),
+                true,
+            ),
+            Err(e) => (e.to_string(), e.is_transient()),
+        };
+
+        if transient && delivery.attempts < DELIVERY_MAX_ATTEMPTS {
+            let next_attempt_at = Utc::now() + retry_backoff(delivery.attempts);
+            tracing::warn!(
+                "配信 {} の送信に失敗しました（{}回目）。{}に再送します: {}",
+                delivery.id,
+                delivery.attempts,
+                next_attempt_at,
+                error
+            );
+            campaign_deliveries::schedule_delivery_retry(pool, delivery.id, &error, next_attempt_at)
+                .await
+                .map_err(record_error)
+        } else {
+            tracing::error!("配信 {} の送信に失敗しました: {}", delivery.id, error);
+            campaign_deliveries::finish_delivery(pool, delivery.id, DeliveryStatus::Failed, &error)
+                .await
+                .map_err(record_error)
         }
+    }
+
+    // 配信キューの集計をキャンペーンの統計情報に反映
+    async fn sync_delivery_stats(
+        &self,
+        pool: &PgPool,
+        campaign_id: Uuid,
+    ) -> Result<DeliveryCounts, String> {
+        let counts = campaign_deliveries::count_deliveries(pool, campaign_id)
+            .await
+            .map_err(|e| format!("配信状況の取得に失敗しました: {e}"))?;
 
-        // キャンペーンの統計情報を更新
         campaigns::update_campaign_stats(
             pool,
             campaign_id,
-            Some(subscribers.len() as i32),
-            Some(sent_count),
+            Some(counts.total as i32),
+            Some(counts.sent as i32),
             None,
             None,
         )
         .await
         .map_err(|e| format!("統計情報の更新に失敗しました: {e}"))?;
 
-        // 送信結果に応じてステータスを更新
-        if failed_count == 0 && sent_count > 0 {
-            // 全て成功した場合
-            campaigns::complete_campaign_sending(pool, campaign_id)
-                .await
-                .map_err(|e| format!("キャンペーン状態の更新に失敗しました: {e}"))?;
-        } else if sent_count == 0 && failed_count > 0 {
+        Ok(counts)
+    }
+
+    // 全ての配信が完了していればキャンペーンのステータスを確定
+    async fn finalize_campaign_sending(
+        &self,
+        pool: &PgPool,
+        campaign: &Campaign,
+        awaiting_winner: bool,
+    ) -> Result<(), String> {
+        let counts = self.sync_delivery_stats(pool, campaign.id).await?;
+
+        if awaiting_winner {
+            // 勝者の判定後にワーカーが残りの配信対象への送信を開始する
+            tracing::info!(
+                "キャンペーン {} はA/Bテストの勝者判定を待っています",
+                campaign.id
+            );
+            return Ok(());
+        }
+
+        if counts.has_unfinished() {
+            // リトライ待ちの配信はワーカーが再開する
+            tracing::info!(
+                "キャンペーン {} は再送待ちの配信が残っています（送信待ち: {}）",
+                campaign.id,
+                counts.pending
+            );
+            return Ok(());
+        }
+
+        if counts.sent == 0 && counts.failed > 0 {
             // 全て失敗した場合
             campaigns::update_campaign_status(
                 pool,
-                campaign_id,
-                user_id,
-                crate::models::campaign::CampaignStatus::Error,
+                campaign.id,
+                campaign.user_id,
+                CampaignStatus::Error,
             )
             .await
             .map_err(|e| format!("キャンペーン状態の更新に失敗しました: {e}"))?;
-        } else if failed_count > 0 {
+        } else {
             // 一部失敗した場合も送信完了とする（部分的成功）
-            campaigns::complete_campaign_sending(pool, campaign_id)
+            campaigns::complete_campaign_sending(pool, campaign.id)
                 .await
                 .map_err(|e| format!("キャンペーン状態の更新に失敗しました: {e}"))?;
         }
 
         tracing::info!(
-            "キャンペーン {} の送信が完了しました。成功: {}, 失敗: {}",
-            campaign_id,
-            sent_count,
-            failed_count
+            "キャンペーン {} の送信が完了しました。成功: {}, 失敗: {}, 対象外: {}",
+            campaign.id,
+            counts.sent,
+            counts.failed,
+            counts.skipped
         );
 
         Ok(())
     }
+
+    // 失敗した配信のみを再送対象に戻す
+    pub async fn retry_failed_deliveries(
+        &self,
+        pool: &PgPool,
+        campaign_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<u64, String> {
+        let reset = campaign_deliveries::reset_failed_deliveries(pool, campaign_id)
+            .await
+            .map_err(|e| format!("失敗した配信の再設定に失敗しました: {e}"))?;
+
+        if reset > 0 {
+            campaigns::resume_campaign_sending(pool, campaign_id, user_id)
+                .await
+                .map_err(|e| format!("キャンペーン状態の更新に失敗しました: {e}"))?;
+        }
+
+        Ok(reset)
+    }
+}
+
+/// 再送までの待機時間（30秒から倍々に増やし、最大1時間）
+fn retry_backoff(attempts: i32) -> Duration {
+    let exponent = attempts.clamp(1, 8) - 1;
+    let seconds = 30i64 * 2i64.pow(exponent as u32);
+    Duration::seconds(seconds.min(3600))
+}
+
+/// 固定したリビジョンの内容のテンプレートとリビジョンID
+///
+/// 未固定の場合は現在の内容のリビジョンを使う。
+async fn pinned_template(
+    pool: &PgPool,
+    user_id: Uuid,
+    template: Template,
+    pinned_revision_id: Option<Uuid>,
+) -> Result<(Template, Uuid), String> {
+    let pinned = match pinned_revision_id {
+        Some(revision_id) => template_revisions::find_revision_by_id(pool, revision_id)
+            .await
+            .map_err(|e| format!("テンプレートのリビジョンの取得に失敗しました: {e}"))?
+            .filter(|revision| revision.template_id == template.id),
+        None => None,
+    };
+
+    let revision = match pinned {
+        Some(revision) => revision,
+        None => template_revisions::current_revision(pool, template.id, user_id)
+            .await
+            .map_err(|e| format!("テンプレートのリビジョンの取得に失敗しました: {e}"))?
+            .ok_or_else(|| "テンプレートのリビジョンが見つかりません".to_string())?,
+    };
+
+    Ok((revision.apply_to(&template), revision.id))
+}
+
+/// キャンペーンとA/Bテストのバリアントの送信内容
+struct CampaignContent {
+    template: Template,
+    variants: HashMap<Uuid, (CampaignVariant, Option<Template>)>,
+    /// ユーザーの送信ドメインの送信元（未指定の場合はプラットフォームの送信元）
+    sender: Option<VerifiedSender>,
+}
+
+impl CampaignContent {
+    async fn load(
+        pool: &PgPool,
+        user_id: Uuid,
+        template: Template,
+        variants: &[CampaignVariant],
+    ) -> Result<Self, String> {
+        let mut loaded = HashMap::new();
+        for variant in variants {
+            // テンプレートが削除された場合はキャンペーンのテンプレートを使う
+            let variant_template = match variant.template_id {
+                Some(template_id) => {
+                    templates::find_template_by_id(pool, template_id, Some(user_id))
+                        .await
+                        .map_err(|e| format!("テンプレート情報の取得に失敗しました: {e}"))?
+                }
+                None => None,
+            };
+            let variant_template = match variant_template {
+                Some(template) => {
+                    let (template, revision_id) =
+                        pinned_template(pool, user_id, template, variant.template_revision_id)
+                            .await?;
+                    if variant.template_revision_id.is_none() {
+                        ab_tests::pin_variant_template_revision(pool, variant.id, revision_id)
+                            .await
+                            .map_err(|e| {
+                                format!("テンプレートのリビジョンの固定に失敗しました: {e}")
+                            })?;
+                    }
+                    Some(template)
+                }
+                None => None,
+            };
+            loaded.insert(variant.id, (variant.clone(), variant_template));
+        }
+
+        Ok(Self {
+            template,
+            variants: loaded,
+            sender: None,
+        })
+    }
+
+    /// 配信レコードに対応するテンプレートと件名
+    fn for_delivery(&self, delivery: &CampaignDelivery) -> (&Template, Option<&str>) {
+        match delivery.variant_id.and_then(|id| self.variants.get(&id)) {
+            Some((variant, template)) => (
+                template.as_ref().unwrap_or(&self.template),
+                Some(variant.subject.as_str()),
+            ),
+            None => (&self.template, None),
+        }
+    }
+}
+
+/// 購読者ごとの送信メッセージを組み立てる
+struct CampaignRenderer {
+    markdown_service: MarkdownService,
+    tracking_service: TrackingService,
+    unsubscribe_service: UnsubscribeService,
+}
+
+impl CampaignRenderer {
+    fn new(markdown_service: MarkdownService) -> Self {
+        Self {
+            markdown_service,
+            tracking_service: TrackingService::new(),
+            unsubscribe_service: UnsubscribeService::new(),
+        }
+    }
+
+    fn build_message(
+        &self,
+        template: &Template,
+        subject_template: Option<&str>,
+        campaign_id: Uuid,
+        subscriber: &Subscriber,
+    ) -> Result<EmailMessage, String> {
+        // A/Bテストのバリアントは件名を差し替える
+        let subject_template = subject_template.unwrap_or(&template.subject_template);
+
+        // 購読者ごとの配信停止URL
+        let unsubscribe_url = self
+            .unsubscribe_service
+            .unsubscribe_url(subscriber, Some(campaign_id))?;
+
+        // 購読者固有の変数を設定
+        let mut variables = if template.variables.is_null() {
+            serde_json::json!({})
+        } else {
+            template.variables.clone()
+        };
+
+        // variablesをオブジェクトとして扱う
+        if let serde_json::Value::Object(ref mut map) = variables {
+            let name = subscriber
+                .name
+                .clone()
+                .unwrap_or_else(|| "お客様".to_string());
+
+            // name と first_name の両方を設定（互換性のため）
+            map.insert("name".to_string(), serde_json::json!(name.clone()));
+            map.insert("first_name".to_string(), serde_json::json!(name));
+            map.insert(
+                "email".to_string(),
+                serde_json::json!(subscriber.email.clone()),
+            );
+
+            // カスタムフィールドを変数に追加
+            if let serde_json::Value::Object(custom_fields) = &subscriber.custom_fields {
+                for (key, value) in custom_fields {
+                    map.insert(key.clone(), value.clone());
+                }
+            }
+
+            // 配信停止URLを追加
+            map.insert(
+                "unsubscribe_url".to_string(),
+                serde_json::json!(unsubscribe_url.clone()),
+            );
+        }
+
+        // HTMLとテキストをレンダリング
+        let html_body = self
+            .markdown_service
+            .render_with_variables(&template.markdown_content, &variables)
+            .map_err(|e| format!("HTMLレンダリングに失敗しました: {e}"))?;
+
+        let text_body = email_html::html_to_text(&html_body);
+
+        // 計測リンクと開封ピクセルを埋め込む（テキスト版は元のリンクのまま）
+        let html_body =
+            self.tracking_service
+                .instrument_html(&html_body, campaign_id, subscriber.id)?;
+
+        // 件名の変数を展開
+        let subject = self
+            .markdown_service
+            .render_subject(subject_template, &variables)
+            .map_err(|e| format!("件名のレンダリングに失敗しました: {e}"))?;
+
+        Ok(EmailMessage {
+            to: vec![subscriber.email.clone()],
+            from: None,
+            subject,
+            html_body,
+            text_body: Some(text_body),
+            reply_to: None,
+            headers: Some(
+                self.unsubscribe_service
+                    .list_unsubscribe_headers(&unsubscribe_url),
+            ),
+            dkim: None,
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::Duration;
 
     // mockallの使用は現在のテストでは不要なので削除
     // 必要になったら適切に実装する
 
+    // 再送バックオフのテスト
+    #[test]
+    fn test_retry_backoff() {
+        assert_eq!(retry_backoff(1), Duration::seconds(30));
+        assert_eq!(retry_backoff(2), Duration::seconds(60));
+        assert_eq!(retry_backoff(3), Duration::seconds(120));
+        // 上限は1時間
+        assert_eq!(retry_backoff(10), Duration::seconds(3600));
+    }
+
     // キャンペーンスケジュールのテスト
     #[test]
     fn test_validate_schedule_date() {
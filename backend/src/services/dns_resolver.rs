@@ -0,0 +1,188 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// TXTレコードのタイプ番号
+const DNS_TYPE_TXT: u16 = 16;
+
+/// DNSの応答コード（NXDOMAIN）
+const DNS_STATUS_NXDOMAIN: u32 = 3;
+
+/// 送信ドメインの認証に使うDNSリゾルバーのトレイト
+#[async_trait]
+pub trait DnsResolver: Send + Sync {
+    /// TXTレコードを取得（分割された文字列は連結済み、レコードがない場合は空）
+    async fn lookup_txt(&self, name: &str) -> Result<Vec<String>, String>;
+}
+
+/// DNS over HTTPS（JSON形式）のリゾルバー
+pub struct DohResolver {
+    http: reqwest::Client,
+    endpoint: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DohResponse {
+    #[serde(rename = "Status")]
+    status: u32,
+    #[serde(rename = "Answer", default)]
+    answer: Vec<DohAnswer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DohAnswer {
+    #[serde(rename = "type")]
+    record_type: u16,
+    data: String,
+}
+
+impl Default for DohResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DohResolver {
+    /// `DNS_RESOLVER_URL`のエンドポイントを使う（未設定の場合はCloudflare）
+    pub fn new() -> Self {
+        Self::with_endpoint(
+            std::env::var("DNS_RESOLVER_URL")
+                .unwrap_or_else(|_| "https://cloudflare-dns.com/dns-query".to_string()),
+        )
+    }
+
+    pub fn with_endpoint(endpoint: String) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+            endpoint,
+        }
+    }
+}
+
+#[async_trait]
+impl DnsResolver for DohResolver {
+    async fn lookup_txt(&self, name: &str) -> Result<Vec<String>, String> {
+        let response = self
+            .http
+            .get(&self.endpoint)
+            .query(&[("name", name), ("type", "TXT")])
+            .header("accept", "application/dns-json")
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("DNSの問い合わせに失敗しました: {e}"))?
+            .json::<DohResponse>()
+            .await
+            .map_err(|e| format!("DNSの応答を解析できません: {e}"))?;
+
+        parse_doh_response(response)
+    }
+}
+
+fn parse_doh_response(response: DohResponse) -> Result<Vec<String>, String> {
+    match response.status {
+        0 => Ok(response
+            .answer
+            .iter()
+            .filter(|answer| answer.record_type == DNS_TYPE_TXT)
+            .map(|answer| parse_txt_data(&answer.data))
+            .collect()),
+        DNS_STATUS_NXDOMAIN => Ok(Vec::new()),
+        status => Err(format!("DNSの問い合わせに失敗しました（RCODE {status}）")),
+    }
+}
+
+/// TXTレコードのデータ（`"part1" "part2"`形式）を連結する
+fn parse_txt_data(data: &str) -> String {
+    if !data.starts_with('"') {
+        return data.to_string();
+    }
+
+    let mut text = String::new();
+    let mut quoted = false;
+    let mut chars = data.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted = !quoted,
+            '\\' if quoted => {
+                if let Some(escaped) = chars.next() {
+                    text.push(escaped);
+                }
+            }
+            c if quoted => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// あらかじめ登録したレコードを返すリゾルバー（開発・テスト用）
+#[derive(Default)]
+pub struct StaticDnsResolver {
+    records: HashMap<String, Vec<String>>,
+}
+
+impl StaticDnsResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_txt(mut self, name: &str, value: &str) -> Self {
+        self.records
+            .entry(name.to_ascii_lowercase())
+            .or_default()
+            .push(value.to_string());
+        self
+    }
+}
+
+#[async_trait]
+impl DnsResolver for StaticDnsResolver {
+    async fn lookup_txt(&self, name: &str) -> Result<Vec<String>, String> {
+        Ok(self
+            .records
+            .get(&name.to_ascii_lowercase())
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_txt_data() {
+        assert_eq!(parse_txt_data("\"v=spf1 -all\""), "v=spf1 -all");
+        assert_eq!(
+            parse_txt_data("\"v=DKIM1; k=rsa; p=MIIB\" \"IjANBg\""),
+            "v=DKIM1; k=rsa; p=MIIBIjANBg"
+        );
+        assert_eq!(parse_txt_data("\"say \\\"hi\\\"\""), "say \"hi\"");
+        assert_eq!(parse_txt_data("v=DMARC1; p=none"), "v=DMARC1; p=none");
+    }
+
+    #[test]
+    fn test_parse_doh_response() {
+        let response: DohResponse = serde_json::from_value(serde_json::json!({
+            "Status": 0,
+            "Answer": [
+                {"name": "mail.example.com", "type": 5, "TTL": 300, "data": "example.net."},
+                {"name": "example.net", "type": 16, "TTL": 300, "data": "\"v=spf1 -all\""}
+            ]
+        }))
+        .unwrap();
+        assert_eq!(parse_doh_response(response).unwrap(), vec!["v=spf1 -all"]);
+
+        let nxdomain: DohResponse =
+            serde_json::from_value(serde_json::json!({"Status": 3})).unwrap();
+        assert!(parse_doh_response(nxdomain).unwrap().is_empty());
+
+        let servfail: DohResponse =
+            serde_json::from_value(serde_json::json!({"Status": 2})).unwrap();
+        assert!(parse_doh_response(servfail).is_err());
+    }
+}
@@ -0,0 +1,239 @@
+// メールクライアント向けのHTML（CSSのインライン化・テーブルレイアウト・プリヘッダー・サイズ上限）
+
+use crate::utils::html::escape_html;
+
+/// HTML本文のサイズ上限（Gmailは102KBを超えるメールを途中で切り詰める）
+pub const EMAIL_HTML_SIZE_LIMIT: usize = 102 * 1024;
+
+/// 本文の幅（px）
+const CONTAINER_WIDTH: u32 = 600;
+
+/// プリヘッダーの後ろに続ける空白（受信一覧のプレビューに本文が続けて表示されないようにする）
+const PREHEADER_FILLER: &str = "&#847;&zwnj;&nbsp;";
+const PREHEADER_FILLER_COUNT: usize = 40;
+
+const PREHEADER_START: &str = "<!-- preheader -->";
+const PREHEADER_END: &str = "<!-- /preheader -->";
+
+/// 要素の`style`属性にインライン化するスタイル
+///
+/// Gmail・Outlookは`<head>`の`<style>`をほぼ無視するため、ここに書いたルールはすべて要素に展開し、
+/// インライン化できない`:hover`などの疑似クラスは使わない。
+const EMAIL_CSS: &str = r#"
+body {
+    margin: 0;
+    padding: 0;
+    background-color: #ffffff;
+}
+.email-container {
+    width: 100%;
+    max-width: 600px;
+}
+.email-content {
+    padding: 20px;
+    font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, 'Helvetica Neue', Arial, sans-serif;
+    font-size: 16px;
+    line-height: 1.6;
+    color: #333333;
+}
+p, li, blockquote {
+    font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, 'Helvetica Neue', Arial, sans-serif;
+    line-height: 1.6;
+    color: #333333;
+}
+h1, h2, h3, h4, h5, h6 {
+    font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, 'Helvetica Neue', Arial, sans-serif;
+    color: #2c3e50;
+    margin-top: 30px;
+    margin-bottom: 15px;
+}
+h1 { font-size: 2.2em; border-bottom: 2px solid #3498db; padding-bottom: 10px; }
+h2 { font-size: 1.8em; border-bottom: 1px solid #ecf0f1; padding-bottom: 8px; }
+h3 { font-size: 1.4em; }
+p { margin-top: 0; margin-bottom: 16px; }
+a { color: #3498db; text-decoration: none; }
+blockquote {
+    border-left: 4px solid #3498db;
+    margin: 20px 0;
+    padding: 10px 20px;
+    background-color: #f8f9fa;
+    font-style: italic;
+}
+code {
+    background-color: #f1f2f6;
+    padding: 2px 6px;
+    border-radius: 3px;
+    font-family: 'SF Mono', Monaco, 'Cascadia Code', 'Roboto Mono', Consolas, 'Courier New', monospace;
+    font-size: 0.9em;
+}
+pre {
+    background-color: #f8f9fa;
+    border: 1px solid #e9ecef;
+    border-radius: 5px;
+    padding: 15px;
+    margin: 20px 0;
+    white-space: pre-wrap;
+    word-wrap: break-word;
+}
+pre code {
+    background-color: transparent;
+    padding: 0;
+}
+table.markdown-table {
+    border-collapse: collapse;
+    width: 100%;
+    margin: 20px 0;
+}
+table.markdown-table th, table.markdown-table td {
+    border: 1px solid #dddddd;
+    padding: 12px;
+    text-align: left;
+}
+table.markdown-table th {
+    background-color: #f2f2f2;
+    font-weight: bold;
+}
+ul, ol {
+    margin: 16px 0;
+    padding-left: 30px;
+}
+li {
+    margin-bottom: 8px;
+}
+img {
+    max-width: 100%;
+    height: auto;
+    border: 0;
+}
+.button {
+    display: inline-block;
+    padding: 12px 24px;
+    background-color: #3498db;
+    color: #ffffff !important;
+    text-decoration: none;
+    border-radius: 5px;
+    margin: 10px 0;
+}
+"#;
+
+/// 本文のHTML断片をメール用のHTML文書に組み立てる
+///
+/// 中央寄せの固定幅はテーブルで組み（Outlookは`max-width`を解釈しない）、スタイルはすべて
+/// 要素の`style`属性にインライン化する。
+pub fn build_email_html(body_html: &str, preheader: Option<&str>) -> String {
+    let preheader = preheader
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(|text| {
+            format!(
+                "{PREHEADER_START}<div style=\"display: none; max-height: 0; overflow: hidden; mso-hide: all; font-size: 1px; line-height: 1px; color: #ffffff; opacity: 0;\">{}{}</div>{PREHEADER_END}\n",
+                escape_html(text),
+                PREHEADER_FILLER.repeat(PREHEADER_FILLER_COUNT)
+            )
+        })
+        .unwrap_or_default();
+
+    // マークダウンの表はレイアウト用のテーブルと区別するためクラスを付ける
+    let body_html = body_html.replace("<table>", "<table class=\"markdown-table\">");
+
+    let document = format!(
+        r#"<!DOCTYPE html>
+<html lang="ja">
+<head>
+<meta charset="UTF-8">
+<meta name="viewport" content="width=device-width, initial-scale=1.0">
+<meta http-equiv="X-UA-Compatible" content="IE=edge">
+<style>{EMAIL_CSS}</style>
+</head>
+<body>
+{preheader}<table role="presentation" class="email-wrapper" width="100%" border="0" cellpadding="0" cellspacing="0">
+<tr><td align="center">
+<table role="presentation" class="email-container" width="{CONTAINER_WIDTH}" border="0" cellpadding="0" cellspacing="0">
+<tr><td class="email-content">
+{body_html}
+</td></tr>
+</table>
+</td></tr>
+</table>
+</body>
+</html>"#
+    );
+
+    inline_css(&document)
+}
+
+/// `<style>`のルールを要素の`style`属性に展開する（失敗した場合はそのまま返す）
+fn inline_css(html: &str) -> String {
+    match css_inline::inline(html) {
+        Ok(inlined) => inlined,
+        Err(e) => {
+            tracing::warn!("CSSのインライン化に失敗しました: {}", e);
+            html.to_string()
+        }
+    }
+}
+
+/// HTMLのサイズが上限以内か確認
+pub fn check_size(html: &str) -> Result<(), String> {
+    if html.len() > EMAIL_HTML_SIZE_LIMIT {
+        return Err(format!(
+            "メール本文のHTMLが{}KBあり、上限の{}KBを超えています（Gmailでは本文が途中で切り詰められます）",
+            html.len().div_ceil(1024),
+            EMAIL_HTML_SIZE_LIMIT / 1024
+        ));
+    }
+    Ok(())
+}
+
+/// テキスト版の本文（プリヘッダーは含めない）
+pub fn html_to_text(html: &str) -> String {
+    let html = match (html.find(PREHEADER_START), html.find(PREHEADER_END)) {
+        (Some(start), Some(end)) if start < end => {
+            format!("{}{}", &html[..start], &html[end + PREHEADER_END.len()..])
+        }
+        _ => html.to_string(),
+    };
+    html2text::from_read(html.as_bytes(), 80)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_email_html_inlines_styles() {
+        let html = build_email_html(
+            "<h1>お知らせ</h1>\n<p>本文と<a href=\"https://example.com\">リンク</a></p>\n<table><tr><th>項目</th></tr></table>",
+            None,
+        );
+
+        assert!(!html.contains("<style"));
+        assert!(!html.contains(":hover"));
+        assert!(html.contains("<h1 style=\""));
+        assert!(html.contains("border-bottom: 2px solid #3498db"));
+        assert!(html.contains(
+            "<a href=\"https://example.com\" style=\"color: #3498db;text-decoration: none;\""
+        ));
+        assert!(html.contains("<table class=\"markdown-table\" style=\"border-collapse: collapse"));
+        assert!(html.contains("width=\"600\""));
+        assert!(!html.contains(PREHEADER_START));
+    }
+
+    #[test]
+    fn test_preheader_is_hidden_and_excluded_from_text() {
+        let html = build_email_html("<p>本文です</p>", Some("<今だけ>セール"));
+        assert!(html.contains("&lt;今だけ&gt;セール"));
+        assert!(html.contains("display: none"));
+
+        let text = html_to_text(&html);
+        assert!(text.contains("本文です"));
+        assert!(!text.contains("セール"));
+    }
+
+    #[test]
+    fn test_check_size() {
+        assert!(check_size(&"a".repeat(EMAIL_HTML_SIZE_LIMIT)).is_ok());
+        let error = check_size(&"a".repeat(EMAIL_HTML_SIZE_LIMIT + 1)).unwrap_err();
+        assert!(error.contains("103KB"));
+    }
+}
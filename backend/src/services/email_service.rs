@@ -1,11 +1,24 @@
 use async_trait::async_trait;
-use aws_sdk_sesv2::config::Credentials as AwsCredentials;
+use aws_sdk_sesv2::{config::Credentials as AwsCredentials, error::ProvideErrorMetadata};
 use lettre::{
-    message::Message, transport::smtp::AsyncSmtpTransport, AsyncTransport, Tokio1Executor,
+    message::{
+        dkim::{
+            dkim_sign, DkimCanonicalization, DkimCanonicalizationType, DkimConfig,
+            DkimSigningAlgorithm, DkimSigningKey,
+        },
+        header::{HeaderName, HeaderValue},
+        Message,
+    },
+    transport::smtp::{
+        authentication::{Credentials, Mechanism},
+        client::{Tls, TlsParameters},
+        AsyncSmtpTransport, PoolConfig,
+    },
+    AsyncTransport, Tokio1Executor,
 };
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 use thiserror::Error;
 
 /// メール送信エラー
@@ -15,21 +28,174 @@ pub enum EmailError {
     Send(String),
     #[error("メールビルドに失敗しました: {0}")]
     Build(String),
+    #[error("メールが拒否されました: {0}")]
+    Rejected(String),
     #[error("AWS SESエラー: {0}")]
     AwsSes(String),
     #[error("設定エラー: {0}")]
     Config(String),
 }
 
+/// 再送しても成功しないAWS SESのエラーコード（メッセージ・宛先・送信元の問題）
+///
+/// スロットリングや送信上限、接続・タイムアウトなどそれ以外のエラーは再送の対象とする。
+const PERMANENT_SES_ERROR_CODES: &[&str] = &[
+    "MessageRejected",
+    "MailFromDomainNotVerifiedException",
+    "AccountSuspendedException",
+    "BadRequestException",
+    "NotFoundException",
+];
+
+impl EmailError {
+    /// 時間をおいて再送すれば成功する可能性のあるエラーか
+    ///
+    /// 送信・接続エラーに加え、AWS SESのスロットリングや一時的なサービスエラーも対象とする。
+    pub fn is_transient(&self) -> bool {
+        match self {
+            EmailError::Send(_) | EmailError::AwsSes(_) => true,
+            EmailError::Build(_) | EmailError::Rejected(_) | EmailError::Config(_) => false,
+        }
+    }
+
+    /// AWS SESの送信エラーを再送できるかどうかで分類
+    fn from_ses_error(code: Option<&str>, message: String) -> Self {
+        match code {
+            Some(code) if PERMANENT_SES_ERROR_CODES.contains(&code) => {
+                EmailError::Rejected(message)
+            }
+            _ => EmailError::AwsSes(message),
+        }
+    }
+}
+
 /// メール送信リクエスト
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmailMessage {
     pub to: Vec<String>,
+    /// 送信元（未指定の場合は設定の送信元アドレス）。`名前 <address>`形式も指定できる
+    #[serde(default)]
+    pub from: Option<String>,
     pub subject: String,
     pub html_body: String,
     pub text_body: Option<String>,
     pub reply_to: Option<String>,
     pub headers: Option<std::collections::HashMap<String, String>>,
+    /// 送信元ドメインのDKIM署名（未指定の場合は署名しない）
+    #[serde(skip)]
+    pub dkim: Option<DkimSigner>,
+}
+
+impl EmailMessage {
+    /// MIMEメッセージを組み立てる（DKIM署名が指定されている場合は署名する）
+    pub fn to_mime(&self, default_from: &str) -> Result<Message, EmailError> {
+        let to_address = self
+            .to
+            .first()
+            .ok_or_else(|| EmailError::Build("宛先が指定されていません".to_string()))?;
+
+        let mut email_builder = Message::builder()
+            .from(
+                self.from
+                    .as_deref()
+                    .unwrap_or(default_from)
+                    .parse()
+                    .map_err(|e| EmailError::Build(format!("無効な送信元アドレス: {e}")))?,
+            )
+            .to(to_address
+                .parse()
+                .map_err(|e| EmailError::Build(format!("無効な宛先アドレス: {e}")))?)
+            .subject(&self.subject);
+
+        if let Some(reply_to) = &self.reply_to {
+            email_builder = email_builder.reply_to(
+                reply_to
+                    .parse()
+                    .map_err(|e| EmailError::Build(format!("無効な返信先アドレス: {e}")))?,
+            );
+        }
+
+        let mut email = email_builder
+            .multipart(
+                lettre::message::MultiPart::alternative()
+                    .singlepart(
+                        lettre::message::SinglePart::builder()
+                            .header(lettre::message::header::ContentType::TEXT_PLAIN)
+                            .body(self.text_body.clone().unwrap_or_else(|| {
+                                super::email_html::html_to_text(&self.html_body)
+                            })),
+                    )
+                    .singlepart(
+                        lettre::message::SinglePart::builder()
+                            .header(lettre::message::header::ContentType::TEXT_HTML)
+                            .body(self.html_body.clone()),
+                    ),
+            )
+            .map_err(|e| EmailError::Build(format!("メールビルドエラー: {e}")))?;
+
+        // 追加ヘッダー（List-Unsubscribeなど）を設定
+        if let Some(headers) = &self.headers {
+            for (name, value) in headers {
+                let header_name = HeaderName::new_from_ascii(name.clone())
+                    .map_err(|e| EmailError::Build(format!("無効なヘッダー名 {name}: {e}")))?;
+                email
+                    .headers_mut()
+                    .insert_raw(HeaderValue::new(header_name, value.clone()));
+            }
+        }
+
+        if let Some(signer) = &self.dkim {
+            signer.sign(&mut email);
+        }
+
+        Ok(email)
+    }
+}
+
+/// DKIM署名の設定（送信ドメインごと）
+#[derive(Debug, Clone)]
+pub struct DkimSigner {
+    config: Arc<DkimConfig>,
+}
+
+impl DkimSigner {
+    /// PKCS#1 PEM形式のRSA秘密鍵から作成する
+    pub fn new(domain: &str, selector: &str, private_key_pem: &str) -> Result<Self, EmailError> {
+        let key = DkimSigningKey::new(private_key_pem, DkimSigningAlgorithm::Rsa)
+            .map_err(|e| EmailError::Config(format!("DKIM秘密鍵を読み込めません: {e}")))?;
+
+        let headers = [
+            "From",
+            "Reply-To",
+            "Subject",
+            "To",
+            "Date",
+            "Message-ID",
+            "List-Unsubscribe",
+            "List-Unsubscribe-Post",
+        ]
+        .into_iter()
+        .map(HeaderName::new_from_ascii_str)
+        .collect();
+
+        Ok(Self {
+            config: Arc::new(DkimConfig::new(
+                selector.to_string(),
+                domain.to_string(),
+                key,
+                headers,
+                DkimCanonicalization {
+                    header: DkimCanonicalizationType::Relaxed,
+                    body: DkimCanonicalizationType::Relaxed,
+                },
+            )),
+        })
+    }
+
+    /// メッセージにDKIM-Signatureヘッダーを追加する
+    pub fn sign(&self, message: &mut Message) {
+        dkim_sign(message, &self.config);
+    }
 }
 
 /// メール送信結果
@@ -40,6 +206,17 @@ pub struct EmailResult {
     pub error: Option<String>,
 }
 
+impl EmailResult {
+    /// 送信エラーを送信失敗の結果に変換
+    pub fn failed(error: EmailError) -> Self {
+        Self {
+            message_id: "".to_string(),
+            status: EmailStatus::Failed,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
 /// メール送信ステータス
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum EmailStatus {
@@ -78,6 +255,7 @@ pub struct EmailConfig {
 #[derive(Debug, Clone, PartialEq)]
 pub enum EmailProviderType {
     MailHog,
+    Smtp,
     AwsSes,
 }
 
@@ -88,7 +266,45 @@ pub struct SmtpConfig {
     pub port: u16,
     pub username: Option<String>,
     pub password: Option<String>,
-    pub use_tls: bool,
+    pub security: SmtpSecurity,
+    /// 自己署名証明書などの検証に失敗する証明書を許可する（検証環境用）
+    pub accept_invalid_certs: bool,
+    /// プールする接続の最大数
+    pub pool_max_size: u32,
+    pub timeout_secs: u64,
+}
+
+/// SMTP接続の暗号化方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpSecurity {
+    /// 暗号化しない（MailHogなどのローカル開発用）
+    None,
+    /// STARTTLSで暗号化する（必須）
+    StartTls,
+    /// 接続時からTLSで暗号化する（SMTPS）
+    Tls,
+}
+
+impl SmtpSecurity {
+    fn from_str(value: &str) -> Result<Self, EmailError> {
+        match value {
+            "none" => Ok(SmtpSecurity::None),
+            "starttls" => Ok(SmtpSecurity::StartTls),
+            "tls" | "ssl" => Ok(SmtpSecurity::Tls),
+            other => Err(EmailError::Config(format!(
+                "無効なSMTP暗号化方式: {other}（none・starttls・tlsのいずれか）"
+            ))),
+        }
+    }
+
+    /// 暗号化方式ごとの標準のポート番号
+    fn default_port(&self) -> u16 {
+        match self {
+            SmtpSecurity::None => 25,
+            SmtpSecurity::StartTls => 587,
+            SmtpSecurity::Tls => 465,
+        }
+    }
 }
 
 /// AWS SES設定
@@ -112,14 +328,20 @@ impl EmailService {
     pub async fn new(pool: PgPool) -> Result<Self, EmailError> {
         let config = Self::from_env()?;
         let provider: Box<dyn EmailProvider> = match &config.provider {
-            EmailProviderType::MailHog => {
+            EmailProviderType::MailHog | EmailProviderType::Smtp => {
                 let smtp_config = config
                     .smtp_config
                     .as_ref()
                     .ok_or_else(|| EmailError::Config("SMTP設定が必要です".to_string()))?;
-                Box::new(MailHogProvider::new(
+                let name = if config.provider == EmailProviderType::MailHog {
+                    "MailHog"
+                } else {
+                    "SMTP"
+                };
+                Box::new(SmtpProvider::new(
                     smtp_config.clone(),
                     config.from_email.clone(),
+                    name,
                 )?)
             }
             EmailProviderType::AwsSes => {
@@ -156,15 +378,24 @@ impl EmailService {
             results.extend(batch_results);
 
             // レート制限のための待機
-            if self.config.rate_limit > 0 {
-                let delay = 1000 / self.config.rate_limit;
-                tokio::time::sleep(tokio::time::Duration::from_millis(delay as u64)).await;
-            }
+            self.wait_for_rate_limit().await;
         }
 
         Ok(results)
     }
 
+    /// バッチ送信の件数
+    pub fn batch_size(&self) -> usize {
+        self.config.batch_size
+    }
+
+    /// レート制限のための待機
+    pub async fn wait_for_rate_limit(&self) {
+        if let Some(delay) = 1000u32.checked_div(self.config.rate_limit) {
+            tokio::time::sleep(tokio::time::Duration::from_millis(delay as u64)).await;
+        }
+    }
+
     /// 環境変数から設定を読み込む
     pub fn from_env() -> Result<EmailConfig, EmailError> {
         let provider = match std::env::var("EMAIL_PROVIDER")
@@ -172,6 +403,7 @@ impl EmailService {
             .as_str()
         {
             "aws_ses" => EmailProviderType::AwsSes,
+            "smtp" => EmailProviderType::Smtp,
             _ => EmailProviderType::MailHog,
         };
 
@@ -181,8 +413,8 @@ impl EmailService {
                 EmailError::Config("送信元メールアドレスが設定されていません".to_string())
             })?;
 
-        let smtp_config = if provider == EmailProviderType::MailHog {
-            Some(SmtpConfig {
+        let smtp_config = match provider {
+            EmailProviderType::MailHog => Some(SmtpConfig {
                 host: std::env::var("SMTP_HOST").unwrap_or_else(|_| "localhost".to_string()),
                 port: std::env::var("SMTP_PORT")
                     .unwrap_or_else(|_| "1025".to_string())
@@ -190,10 +422,13 @@ impl EmailService {
                     .map_err(|_| EmailError::Config("無効なSMTPポート番号".to_string()))?,
                 username: std::env::var("SMTP_USERNAME").ok(),
                 password: std::env::var("SMTP_PASSWORD").ok(),
-                use_tls: false,
-            })
-        } else {
-            None
+                security: SmtpSecurity::None,
+                accept_invalid_certs: false,
+                pool_max_size: 1,
+                timeout_secs: 60,
+            }),
+            EmailProviderType::Smtp => Some(Self::smtp_config_from_env()?),
+            EmailProviderType::AwsSes => None,
         };
 
         let aws_config = if provider == EmailProviderType::AwsSes {
@@ -228,6 +463,39 @@ impl EmailService {
         })
     }
 
+    /// `EMAIL_PROVIDER=smtp`のSMTP設定を環境変数から読み込む
+    fn smtp_config_from_env() -> Result<SmtpConfig, EmailError> {
+        let host = std::env::var("SMTP_HOST")
+            .map_err(|_| EmailError::Config("SMTP_HOSTが設定されていません".to_string()))?;
+        let security = SmtpSecurity::from_str(
+            &std::env::var("SMTP_SECURITY").unwrap_or_else(|_| "starttls".to_string()),
+        )?;
+        let port = match std::env::var("SMTP_PORT") {
+            Ok(port) => port
+                .parse()
+                .map_err(|_| EmailError::Config("無効なSMTPポート番号".to_string()))?,
+            Err(_) => security.default_port(),
+        };
+
+        Ok(SmtpConfig {
+            host,
+            port,
+            username: std::env::var("SMTP_USERNAME").ok(),
+            password: std::env::var("SMTP_PASSWORD").ok(),
+            security,
+            accept_invalid_certs: std::env::var("SMTP_ACCEPT_INVALID_CERTS")
+                .is_ok_and(|v| v == "true"),
+            pool_max_size: std::env::var("SMTP_POOL_MAX_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10),
+            timeout_secs: std::env::var("SMTP_TIMEOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60),
+        })
+    }
+
     /// パスワードリセットメール送信
     pub async fn send_password_reset_email(
         &self,
@@ -355,11 +623,13 @@ MarkMail
 
         let message = EmailMessage {
             to: vec![email.to_string()],
+            from: None,
             subject: "【MarkMail】パスワードリセットのご案内".to_string(),
             html_body,
             text_body: Some(text_body),
             reply_to: None,
             headers: None,
+            dkim: None,
         };
 
         self.send_email(&message).await?;
@@ -367,74 +637,59 @@ MarkMail
     }
 }
 
-/// MailHogプロバイダー（開発環境用）
-struct MailHogProvider {
+/// SMTPプロバイダー（MailHog・Postfix・SendGridなどのSMTPリレー）
+pub struct SmtpProvider {
     transport: AsyncSmtpTransport<Tokio1Executor>,
     from_email: String,
+    name: String,
 }
 
-impl MailHogProvider {
-    fn new(config: SmtpConfig, from_email: String) -> Result<Self, EmailError> {
-        let transport = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host)
+impl SmtpProvider {
+    pub fn new(config: SmtpConfig, from_email: String, name: &str) -> Result<Self, EmailError> {
+        let tls_parameters = || {
+            TlsParameters::builder(config.host.clone())
+                .dangerous_accept_invalid_certs(config.accept_invalid_certs)
+                .build()
+                .map_err(|e| EmailError::Config(format!("TLS設定エラー: {e}")))
+        };
+
+        let tls = match config.security {
+            SmtpSecurity::None => Tls::None,
+            SmtpSecurity::StartTls => Tls::Required(tls_parameters()?),
+            SmtpSecurity::Tls => Tls::Wrapper(tls_parameters()?),
+        };
+
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host)
             .port(config.port)
-            .build();
+            .tls(tls)
+            .timeout(Some(Duration::from_secs(config.timeout_secs)))
+            .pool_config(PoolConfig::new().max_size(config.pool_max_size.max(1)));
+
+        if let Some(username) = config.username.filter(|u| !u.is_empty()) {
+            builder = builder
+                .credentials(Credentials::new(
+                    username,
+                    config.password.unwrap_or_default(),
+                ))
+                .authentication(vec![Mechanism::Plain, Mechanism::Login]);
+        }
 
         Ok(Self {
-            transport,
+            transport: builder.build(),
             from_email,
+            name: name.to_string(),
         })
     }
 }
 
 #[async_trait]
-impl EmailProvider for MailHogProvider {
+impl EmailProvider for SmtpProvider {
     async fn send_email(&self, message: &EmailMessage) -> Result<EmailResult, EmailError> {
-        if message.to.is_empty() {
-            return Err(EmailError::Build("宛先が指定されていません".to_string()));
-        }
-
-        let to_address = message.to.first().unwrap();
-
-        let mut email_builder = Message::builder()
-            .from(
-                self.from_email
-                    .parse()
-                    .map_err(|e| EmailError::Build(format!("無効な送信元アドレス: {e}")))?,
-            )
-            .to(to_address
-                .parse()
-                .map_err(|e| EmailError::Build(format!("無効な宛先アドレス: {e}")))?)
-            .subject(&message.subject);
-
-        if let Some(reply_to) = &message.reply_to {
-            email_builder = email_builder.reply_to(
-                reply_to
-                    .parse()
-                    .map_err(|e| EmailError::Build(format!("無効な返信先アドレス: {e}")))?,
-            );
-        }
-
-        let email = email_builder
-            .multipart(
-                lettre::message::MultiPart::alternative()
-                    .singlepart(
-                        lettre::message::SinglePart::builder()
-                            .header(lettre::message::header::ContentType::TEXT_PLAIN)
-                            .body(message.text_body.clone().unwrap_or_else(|| {
-                                html2text::from_read(message.html_body.as_bytes(), 80)
-                            })),
-                    )
-                    .singlepart(
-                        lettre::message::SinglePart::builder()
-                            .header(lettre::message::header::ContentType::TEXT_HTML)
-                            .body(message.html_body.clone()),
-                    ),
-            )
-            .map_err(|e| EmailError::Build(format!("メールビルドエラー: {e}")))?;
+        let email = message.to_mime(&self.from_email)?;
 
         match self.transport.send(email).await {
             Ok(_response) => {
-                // MailHogではメッセージIDは生成されない
+                // SMTPではメッセージIDは返されない
                 let message_id = uuid::Uuid::new_v4().to_string();
                 Ok(EmailResult {
                     message_id,
@@ -442,11 +697,9 @@ impl EmailProvider for MailHogProvider {
                     error: None,
                 })
             }
-            Err(e) => Ok(EmailResult {
-                message_id: "".to_string(),
-                status: EmailStatus::Failed,
-                error: Some(format!("送信エラー: {e}")),
-            }),
+            // 5xx応答（宛先の拒否など）は再送しても成功しない
+            Err(e) if e.is_permanent() => Err(EmailError::Rejected(e.to_string())),
+            Err(e) => Err(EmailError::Send(format!("送信エラー: {e}"))),
         }
     }
 
@@ -456,13 +709,17 @@ impl EmailProvider for MailHogProvider {
     ) -> Result<Vec<EmailResult>, EmailError> {
         let mut results = Vec::new();
         for message in messages {
-            results.push(self.send_email(&message).await?);
+            results.push(
+                self.send_email(&message)
+                    .await
+                    .unwrap_or_else(EmailResult::failed),
+            );
         }
         Ok(results)
     }
 
     fn provider_name(&self) -> &str {
-        "MailHog"
+        &self.name
     }
 }
 
@@ -508,30 +765,15 @@ impl AwsSesProvider {
 
         Ok(Self { client, from_email })
     }
-}
 
-#[async_trait]
-impl EmailProvider for AwsSesProvider {
-    async fn send_email(&self, message: &EmailMessage) -> Result<EmailResult, EmailError> {
+    /// SESで組み立てるメール本文
+    fn simple_content(
+        message: &EmailMessage,
+    ) -> Result<aws_sdk_sesv2::types::EmailContent, EmailError> {
         use aws_sdk_sesv2::types::{
-            Body, Content, Destination, EmailContent, Message as SesMessage,
+            Body, Content, EmailContent, Message as SesMessage, MessageHeader,
         };
 
-        if message.to.is_empty() {
-            return Err(EmailError::Build("宛先が指定されていません".to_string()));
-        }
-
-        tracing::info!(
-            "AWS SESでメール送信: from={}, to={:?}, subject={}",
-            self.from_email,
-            message.to,
-            message.subject
-        );
-
-        let destination = Destination::builder()
-            .set_to_addresses(Some(message.to.clone()))
-            .build();
-
         let subject_content = Content::builder()
             .data(&message.subject)
             .charset("UTF-8")
@@ -555,29 +797,80 @@ impl EmailProvider for AwsSesProvider {
             .text(text_content)
             .build();
 
-        let ses_message = SesMessage::builder()
-            .subject(subject_content)
-            .body(body)
+        let mut ses_message_builder = SesMessage::builder().subject(subject_content).body(body);
+
+        // 追加ヘッダー（List-Unsubscribeなど）を設定
+        if let Some(headers) = &message.headers {
+            for (name, value) in headers {
+                let header = MessageHeader::builder()
+                    .name(name)
+                    .value(value)
+                    .build()
+                    .map_err(|e| EmailError::Build(format!("ヘッダービルドエラー: {e}")))?;
+                ses_message_builder = ses_message_builder.headers(header);
+            }
+        }
+
+        let ses_message = ses_message_builder.build();
+
+        Ok(EmailContent::builder().simple(ses_message).build())
+    }
+}
+
+#[async_trait]
+impl EmailProvider for AwsSesProvider {
+    async fn send_email(&self, message: &EmailMessage) -> Result<EmailResult, EmailError> {
+        use aws_sdk_sesv2::{
+            primitives::Blob,
+            types::{Destination, EmailContent, RawMessage},
+        };
+
+        if message.to.is_empty() {
+            return Err(EmailError::Build("宛先が指定されていません".to_string()));
+        }
+
+        let from_email = message.from.as_deref().unwrap_or(&self.from_email);
+
+        tracing::info!(
+            "AWS SESでメール送信: from={}, to={:?}, subject={}",
+            from_email,
+            message.to,
+            message.subject
+        );
+
+        let destination = Destination::builder()
+            .set_to_addresses(Some(message.to.clone()))
             .build();
 
-        let email_content = EmailContent::builder().simple(ses_message).build();
+        // DKIM署名する場合は署名済みのMIMEをそのまま送信する
+        let email_content = match &message.dkim {
+            Some(_) => {
+                let mime = message.to_mime(&self.from_email)?;
+                let raw = RawMessage::builder()
+                    .data(Blob::new(mime.formatted()))
+                    .build()
+                    .map_err(|e| EmailError::Build(format!("MIMEメッセージビルドエラー: {e}")))?;
+                EmailContent::builder().raw(raw).build()
+            }
+            None => Self::simple_content(message)?,
+        };
 
         let mut request = self
             .client
             .send_email()
-            .from_email_address(&self.from_email)
+            .from_email_address(from_email)
             .destination(destination)
             .content(email_content);
 
+        if let (Some(reply_to), None) = (&message.reply_to, &message.dkim) {
+            request = request.reply_to_addresses(reply_to.clone());
+        }
+
         // Configuration Setを設定
         if let Ok(config_set) = std::env::var("AWS_SES_CONFIGURATION_SET") {
             request = request.configuration_set_name(config_set);
         }
 
-        if let Some(reply_to) = &message.reply_to {
-            request = request.reply_to_addresses(reply_to.clone());
-        }
-
         match request.send().await {
             Ok(output) => {
                 let message_id = output.message_id().unwrap_or("unknown").to_string();
@@ -589,11 +882,11 @@ impl EmailProvider for AwsSesProvider {
             }
             Err(e) => {
                 tracing::error!("AWS SES送信エラー: {:?}", e);
-                Ok(EmailResult {
-                    message_id: "".to_string(),
-                    status: EmailStatus::Failed,
-                    error: Some(format!("AWS SESエラー: {e}")),
-                })
+                let message = match e.message() {
+                    Some(detail) => format!("{e}: {detail}"),
+                    None => e.to_string(),
+                };
+                Err(EmailError::from_ses_error(e.code(), message))
             }
         }
     }
@@ -606,7 +899,11 @@ impl EmailProvider for AwsSesProvider {
         // 現在は単一送信を繰り返す
         let mut results = Vec::new();
         for message in messages {
-            results.push(self.send_email(&message).await?);
+            results.push(
+                self.send_email(&message)
+                    .await
+                    .unwrap_or_else(EmailResult::failed),
+            );
         }
         Ok(results)
     }
@@ -633,15 +930,66 @@ mod tests {
         assert!(config.smtp_config.is_some());
     }
 
+    #[test]
+    fn test_smtp_security_from_str() {
+        assert_eq!(
+            SmtpSecurity::from_str("starttls").unwrap(),
+            SmtpSecurity::StartTls
+        );
+        assert_eq!(SmtpSecurity::from_str("ssl").unwrap(), SmtpSecurity::Tls);
+        assert_eq!(SmtpSecurity::Tls.default_port(), 465);
+        assert_eq!(SmtpSecurity::StartTls.default_port(), 587);
+        assert!(SmtpSecurity::from_str("plain").is_err());
+    }
+
+    #[test]
+    fn test_ses_errors_are_classified_for_retry() {
+        for code in [
+            Some("TooManyRequestsException"),
+            Some("LimitExceededException"),
+            Some("SendingPausedException"),
+            None,
+        ] {
+            assert!(
+                EmailError::from_ses_error(code, "error".to_string()).is_transient(),
+                "{code:?}"
+            );
+        }
+        for code in ["MessageRejected", "MailFromDomainNotVerifiedException"] {
+            assert!(!EmailError::from_ses_error(Some(code), "error".to_string()).is_transient());
+        }
+        assert!(EmailError::Send("connection refused".to_string()).is_transient());
+        assert!(!EmailError::Build("invalid address".to_string()).is_transient());
+    }
+
+    #[tokio::test]
+    async fn test_smtp_provider_builds_tls_transports() {
+        for security in [SmtpSecurity::StartTls, SmtpSecurity::Tls] {
+            let config = SmtpConfig {
+                host: "smtp.example.com".to_string(),
+                port: security.default_port(),
+                username: Some("apikey".to_string()),
+                password: Some("secret".to_string()),
+                security,
+                accept_invalid_certs: false,
+                pool_max_size: 10,
+                timeout_secs: 60,
+            };
+            assert!(SmtpProvider::new(config, "noreply@example.com".to_string(), "SMTP").is_ok());
+        }
+    }
+
     #[tokio::test]
     async fn test_email_message_builder() {
         let message = EmailMessage {
             to: vec!["test@example.com".to_string()],
+            from: None,
             subject: "テストメール".to_string(),
             html_body: "<h1>こんにちは</h1>".to_string(),
             text_body: Some("こんにちは".to_string()),
             reply_to: None,
             headers: None,
+            dkim: None,
         };
 
         assert_eq!(message.to.len(), 1);
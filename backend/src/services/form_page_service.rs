@@ -0,0 +1,560 @@
+// 公開フォームページの描画サービス
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    models::{
+        form::{Form, FormField, FORM_TOKEN_FIELD},
+        form_validation::{FieldError, FormSchema},
+    },
+    services::markdown_service::MarkdownService,
+    utils::{config::api_base_url, html::escape_html},
+};
+
+/// 送信ボタンの標準の文言
+const DEFAULT_SUBMIT_BUTTON_TEXT: &str = "送信する";
+
+/// 送信後の標準のメッセージ
+const DEFAULT_SUCCESS_MESSAGE: &str = "送信が完了しました。ありがとうございました。";
+
+/// ダブルオプトインの場合の送信後の標準のメッセージ
+const DEFAULT_CONFIRMATION_PENDING_MESSAGE: &str =
+    "確認メールを送信しました。メール内のリンクを開いて、ご登録を完了してください。";
+
+/// 埋め込み先に高さを通知するメッセージの種類
+const HEIGHT_MESSAGE_TYPE: &str = "markmail:form-height";
+
+/// フォームの埋め込み用コード
+#[derive(Debug, Clone, Serialize)]
+pub struct FormEmbedCode {
+    /// 公開フォームページのURL
+    pub url: String,
+    /// 埋め込み用スクリプトのURL
+    pub script_url: String,
+    /// 埋め込み用スクリプトタグ（iframeを挿入して高さを自動調整する）
+    pub script_tag: String,
+    /// iframeタグ（スクリプトを使えない場合）
+    pub iframe_tag: String,
+}
+
+/// 公開フォームページの入力状態（検証エラー時の再表示用）
+#[derive(Debug, Clone, Copy)]
+pub struct FormPageState<'a> {
+    /// 入力内容
+    pub values: &'a Value,
+    /// フィールドごとの検証エラー
+    pub field_errors: &'a [FieldError],
+    /// フォーム全体のエラー
+    pub error: Option<&'a str>,
+    /// iframeに埋め込んで表示する
+    pub embed: bool,
+}
+
+impl FormPageState<'_> {
+    /// 未入力のフォーム
+    pub fn blank(embed: bool) -> Self {
+        Self {
+            values: &Value::Null,
+            field_errors: &[],
+            error: None,
+            embed,
+        }
+    }
+}
+
+pub struct FormPageService {
+    base_url: String,
+}
+
+impl Default for FormPageService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FormPageService {
+    pub fn new() -> Self {
+        Self::with_base_url(api_base_url())
+    }
+
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// 公開フォームページのURL
+    pub fn page_url(&self, form: &Form) -> String {
+        format!("{}/f/{}", self.base_url, form.slug)
+    }
+
+    /// 埋め込み用スクリプトのURL
+    pub fn script_url(&self, form: &Form) -> String {
+        format!("{}/embed.js", self.page_url(form))
+    }
+
+    /// フォームの埋め込み用コード
+    pub fn embed_code(&self, form: &Form) -> FormEmbedCode {
+        let url = self.page_url(form);
+        let script_url = self.script_url(form);
+        FormEmbedCode {
+            script_tag: format!(
+                r#"<script src="{}" async></script>"#,
+                escape_html(&script_url)
+            ),
+            iframe_tag: format!(
+                r#"<iframe src="{}?embed=true" title="{}" style="width:100%;min-height:480px;border:0"></iframe>"#,
+                escape_html(&url),
+                escape_html(&form.name)
+            ),
+            url,
+            script_url,
+        }
+    }
+
+    /// 公開フォームページ（マークダウンの本文と入力項目）
+    pub fn render_form(
+        &self,
+        form: &Form,
+        schema: &FormSchema,
+        form_token: &str,
+        state: FormPageState<'_>,
+    ) -> String {
+        let settings = form.settings();
+        let errors: HashMap<&str, &str> = state
+            .field_errors
+            .iter()
+            .map(|error| (error.field.as_str(), error.message.as_str()))
+            .collect();
+
+        let mut body = MarkdownService::new().render_fragment(&form.markdown_content);
+        body.push_str(r#"<form method="post" class="mm-form" novalidate>"#);
+        if let Some(error) = state.error {
+            body.push_str(&format!(
+                r#"<p class="mm-error" role="alert">{}</p>"#,
+                escape_html(error)
+            ));
+        }
+        for field in schema.fields() {
+            body.push_str(&render_field(
+                field,
+                state.values.get(&field.name),
+                errors.get(field.name.as_str()).copied(),
+            ));
+        }
+        // ハニーポット項目は画面にもスクリーンリーダーにも表示しない
+        body.push_str(&format!(
+            r#"<div class="mm-hp" aria-hidden="true"><input type="text" name="{}" tabindex="-1" autocomplete="off"></div>"#,
+            escape_html(settings.honeypot_field())
+        ));
+        body.push_str(&format!(
+            r#"<input type="hidden" name="{FORM_TOKEN_FIELD}" value="{}">"#,
+            escape_html(form_token)
+        ));
+        body.push_str(&format!(
+            r#"<button type="submit">{}</button></form>"#,
+            escape_html(
+                settings
+                    .submit_button_text
+                    .as_deref()
+                    .unwrap_or(DEFAULT_SUBMIT_BUTTON_TEXT)
+            )
+        ));
+
+        render_layout(&form.name, &body, state.embed)
+    }
+
+    /// 送信後のページ（送信後のリダイレクト先が設定されていない場合）
+    pub fn render_success(&self, form: &Form, embed: bool) -> String {
+        let settings = form.settings();
+        let message = match settings.success_message.as_deref() {
+            Some(message) => MarkdownService::new().render_fragment(message),
+            None if settings.require_confirmation => {
+                format!("<p>{DEFAULT_CONFIRMATION_PENDING_MESSAGE}</p>")
+            }
+            None => format!("<p>{DEFAULT_SUCCESS_MESSAGE}</p>"),
+        };
+
+        render_layout(
+            &form.name,
+            &format!(r#"<div class="mm-success">{message}</div>"#),
+            embed,
+        )
+    }
+
+    /// フォームを表示できない場合のページ
+    pub fn render_message(&self, title: &str, message: &str, embed: bool) -> String {
+        render_layout(
+            title,
+            &format!(
+                "<h1>{}</h1><p>{}</p>",
+                escape_html(title),
+                escape_html(message)
+            ),
+            embed,
+        )
+    }
+
+    /// 埋め込み用スクリプト（スクリプトタグの直後にiframeを挿入し、表示内容に合わせて高さを調整する）
+    pub fn embed_script(&self, form: &Form) -> String {
+        let src = Value::String(format!("{}?embed=true", self.page_url(form)));
+        let title = Value::String(form.name.clone());
+        format!(
+            r#"(function () {{
+  var script = document.currentScript;
+  if (!script) return;
+  var iframe = document.createElement("iframe");
+  iframe.src = {src};
+  iframe.title = {title};
+  iframe.style.cssText = "width:100%;min-height:320px;border:0;overflow:hidden";
+  script.parentNode.insertBefore(iframe, script.nextSibling);
+  window.addEventListener("message", function (event) {{
+    var data = event.data;
+    if (event.source !== iframe.contentWindow || !data || data.type !== "{HEIGHT_MESSAGE_TYPE}") return;
+    iframe.style.height = data.height + "px";
+  }});
+}})();
+"#
+        )
+    }
+}
+
+/// 入力項目
+fn render_field(field: &FormField, value: Option<&Value>, error: Option<&str>) -> String {
+    let name = escape_html(&field.name);
+    let id = format!("mm-field-{name}");
+    let label = escape_html(if field.label.is_empty() {
+        &field.name
+    } else {
+        &field.label
+    });
+    let required_mark = if field.required {
+        r#" <span class="mm-required">*</span>"#
+    } else {
+        ""
+    };
+    let required = if field.required { " required" } else { "" };
+    let invalid = if error.is_some() {
+        r#" aria-invalid="true""#
+    } else {
+        ""
+    };
+    let placeholder = field
+        .placeholder
+        .as_deref()
+        .map(|placeholder| format!(r#" placeholder="{}""#, escape_html(placeholder)))
+        .unwrap_or_default();
+    let text = value.map(value_text).unwrap_or_default();
+    let is_selected = |option: &str| match value {
+        Some(Value::String(selected)) => selected == option,
+        Some(Value::Array(selected)) => selected.iter().any(|v| v.as_str() == Some(option)),
+        _ => false,
+    };
+
+    let control = match field.field_type.as_str() {
+        "textarea" => format!(
+            r#"<label for="{id}">{label}{required_mark}</label><textarea id="{id}" name="{name}" rows="5"{placeholder}{required}{invalid}>{}</textarea>"#,
+            escape_html(&text)
+        ),
+        "select" => {
+            let options: String = field
+                .option_entries()
+                .iter()
+                .map(|(value, label)| {
+                    format!(
+                        r#"<option value="{}"{}>{}</option>"#,
+                        escape_html(value),
+                        if is_selected(value) { " selected" } else { "" },
+                        escape_html(label)
+                    )
+                })
+                .collect();
+            format!(
+                r#"<label for="{id}">{label}{required_mark}</label><select id="{id}" name="{name}"{required}{invalid}><option value="">選択してください</option>{options}</select>"#
+            )
+        }
+        "radio" => render_choices(field, "radio", &label, required_mark, &is_selected),
+        "checkbox" if !field.option_values().is_empty() => {
+            render_choices(field, "checkbox", &label, required_mark, &is_selected)
+        }
+        "checkbox" => format!(
+            r#"<label class="mm-choice"><input type="checkbox" id="{id}" name="{name}" value="true"{}{required}{invalid}> {label}{required_mark}</label>"#,
+            if value.is_some_and(|v| v == &Value::Bool(true)) {
+                " checked"
+            } else {
+                ""
+            }
+        ),
+        field_type => {
+            let input_type = match field_type {
+                "email" | "url" | "number" | "date" | "time" => field_type,
+                "tel" | "phone" => "tel",
+                _ => "text",
+            };
+            format!(
+                r#"<label for="{id}">{label}{required_mark}</label><input type="{input_type}" id="{id}" name="{name}" value="{}"{placeholder}{required}{invalid}>"#,
+                escape_html(&text)
+            )
+        }
+    };
+
+    let error = error
+        .map(|error| format!(r#"<p class="mm-field-error">{}</p>"#, escape_html(error)))
+        .unwrap_or_default();
+    format!(r#"<div class="mm-field">{control}{error}</div>"#)
+}
+
+/// ラジオボタンまたは選択肢のあるチェックボックス
+fn render_choices(
+    field: &FormField,
+    input_type: &str,
+    label: &str,
+    required_mark: &str,
+    is_selected: &dyn Fn(&str) -> bool,
+) -> String {
+    let name = escape_html(&field.name);
+    let choices: String = field
+        .option_entries()
+        .iter()
+        .map(|(value, option_label)| {
+            format!(
+                r#"<label class="mm-choice"><input type="{input_type}" name="{name}" value="{}"{}> {}</label>"#,
+                escape_html(value),
+                if is_selected(value) { " checked" } else { "" },
+                escape_html(option_label)
+            )
+        })
+        .collect();
+    format!(r#"<fieldset><legend>{label}{required_mark}</legend>{choices}</fieldset>"#)
+}
+
+/// 入力欄に再表示する値
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        value => value.to_string(),
+    }
+}
+
+/// 公開フォームページの共通レイアウト（埋め込み時は背景を付けず、表示内容の高さを親ページに通知する）
+fn render_layout(title: &str, body: &str, embed: bool) -> String {
+    let title = escape_html(title);
+    let (body_class, script) = if embed {
+        (
+            "mm-embed",
+            format!(
+                r#"<script>
+        (function () {{
+            function notify() {{
+                parent.postMessage({{ type: "{HEIGHT_MESSAGE_TYPE}", height: document.documentElement.scrollHeight }}, "*");
+            }}
+            window.addEventListener("load", notify);
+            window.addEventListener("resize", notify);
+        }})();
+    </script>"#
+            ),
+        )
+    } else {
+        ("", String::new())
+    };
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="ja">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>{title}</title>
+    <style>
+        body {{
+            font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, 'Helvetica Neue', Arial, sans-serif;
+            line-height: 1.6;
+            color: #333;
+            background-color: #f4f4f4;
+            margin: 0;
+            padding: 40px 20px;
+        }}
+        body.mm-embed {{
+            background-color: transparent;
+            padding: 0;
+        }}
+        .container {{
+            max-width: 560px;
+            margin: 0 auto;
+            padding: 32px;
+            background-color: #ffffff;
+            border-radius: 8px;
+        }}
+        .mm-embed .container {{
+            max-width: none;
+            padding: 16px;
+        }}
+        .mm-field {{ margin-bottom: 20px; }}
+        .mm-field label, .mm-field legend {{ display: block; font-weight: 600; margin-bottom: 6px; }}
+        .mm-field label.mm-choice {{ font-weight: normal; }}
+        .mm-field fieldset {{ border: none; margin: 0; padding: 0; }}
+        .mm-field input[type="text"], .mm-field input[type="email"], .mm-field input[type="url"],
+        .mm-field input[type="tel"], .mm-field input[type="number"], .mm-field input[type="date"],
+        .mm-field input[type="time"], .mm-field select, .mm-field textarea {{
+            box-sizing: border-box;
+            width: 100%;
+            padding: 10px 12px;
+            border: 1px solid #ccc;
+            border-radius: 6px;
+            font-size: 16px;
+            font-family: inherit;
+        }}
+        .mm-field [aria-invalid="true"] {{ border-color: #d93025; }}
+        .mm-required, .mm-field-error, .mm-error {{ color: #d93025; }}
+        .mm-field-error {{ margin: 4px 0 0; font-size: 14px; }}
+        .mm-hp {{ position: absolute; left: -10000px; width: 1px; height: 1px; overflow: hidden; }}
+        button {{
+            padding: 12px 32px;
+            background-color: #000;
+            color: #ffffff;
+            border: none;
+            border-radius: 30px;
+            font-size: 16px;
+            cursor: pointer;
+        }}
+    </style>
+</head>
+<body class="{body_class}">
+    <div class="container">
+        {body}
+    </div>
+    {script}
+</body>
+</html>"#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::form_validation::FieldErrorCode;
+    use chrono::Utc;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn test_form(settings: Value) -> Form {
+        Form {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            name: "資料請求".to_string(),
+            description: None,
+            slug: "request".to_string(),
+            markdown_content:
+                "# 資料請求\n\n<script>alert(1)</script>\n\n[規約](javascript:alert(1))".to_string(),
+            form_fields: json!([
+                {"field_type": "email", "name": "email", "label": "メール", "required": true, "display_order": 1},
+                {"field_type": "text", "name": "name", "label": "お名前", "display_order": 0},
+                {"field_type": "select", "name": "plan", "label": "プラン", "display_order": 2,
+                 "options": [{"value": "free", "label": "無料"}, {"value": "pro", "label": "有料"}]},
+                {"field_type": "checkbox", "name": "agree", "label": "同意する", "display_order": 3}
+            ]),
+            settings,
+            status: "published".to_string(),
+            submission_count: 0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_render_form() {
+        let service = FormPageService::with_base_url("https://api.example.com");
+        let form =
+            test_form(json!({"submit_button_text": "資料を請求", "honeypot_field": "website"}));
+        let schema = FormSchema::parse(&form.form_fields).unwrap();
+
+        let html = service.render_form(&form, &schema, "token", FormPageState::blank(false));
+        assert!(html.contains("<h1>資料請求</h1>"));
+        assert!(!html.contains("<script>alert(1)</script>"));
+        assert!(!html.contains("javascript:"));
+        assert!(html.contains(r#"<a href="">規約</a>"#));
+        assert!(html.contains(r#"<input type="hidden" name="_token" value="token">"#));
+        assert!(html.contains(r#"name="website" tabindex="-1""#));
+        assert!(html.contains("資料を請求</button>"));
+        assert!(html.contains(r#"<option value="pro">有料</option>"#));
+        assert!(!html.contains("postMessage"));
+        // 表示順に並べる
+        assert!(html.find(r#"name="name""#).unwrap() < html.find(r#"name="email""#).unwrap());
+    }
+
+    #[test]
+    fn test_render_form_with_errors_keeps_values() {
+        let service = FormPageService::with_base_url("https://api.example.com");
+        let form = test_form(json!({}));
+        let schema = FormSchema::parse(&form.form_fields).unwrap();
+        let values = json!({"email": "taro\"@", "plan": "pro", "agree": true});
+        let field_errors = [FieldError {
+            field: "email".to_string(),
+            code: FieldErrorCode::InvalidFormat,
+            message: "メールアドレスの形式が正しくありません".to_string(),
+        }];
+
+        let html = service.render_form(
+            &form,
+            &schema,
+            "token",
+            FormPageState {
+                values: &values,
+                field_errors: &field_errors,
+                error: Some("入力内容に誤りがあります"),
+                embed: true,
+            },
+        );
+        assert!(html.contains(r#"value="taro&quot;@""#));
+        assert!(html.contains(r#"aria-invalid="true""#));
+        assert!(html.contains("メールアドレスの形式が正しくありません"));
+        assert!(html.contains(r#"<option value="pro" selected>"#));
+        assert!(html.contains(r#"value="true" checked"#));
+        assert!(html.contains("postMessage"));
+    }
+
+    #[test]
+    fn test_render_success() {
+        let service = FormPageService::with_base_url("https://api.example.com");
+
+        let html = service.render_success(&test_form(json!({})), false);
+        assert!(html.contains(DEFAULT_SUCCESS_MESSAGE));
+
+        let html = service.render_success(&test_form(json!({"require_confirmation": true})), false);
+        assert!(html.contains(DEFAULT_CONFIRMATION_PENDING_MESSAGE));
+
+        let html = service.render_success(
+            &test_form(json!({"success_message": "**ありがとうございます**"})),
+            false,
+        );
+        assert!(html.contains("<strong>ありがとうございます</strong>"));
+    }
+
+    #[test]
+    fn test_embed_code() {
+        let service = FormPageService::with_base_url("https://api.example.com/");
+        let form = test_form(json!({}));
+
+        let code = service.embed_code(&form);
+        assert_eq!(code.url, "https://api.example.com/f/request");
+        assert_eq!(
+            code.script_url,
+            "https://api.example.com/f/request/embed.js"
+        );
+        assert_eq!(
+            code.script_tag,
+            r#"<script src="https://api.example.com/f/request/embed.js" async></script>"#
+        );
+        assert!(code
+            .iframe_tag
+            .starts_with(r#"<iframe src="https://api.example.com/f/request?embed=true""#));
+
+        let script = service.embed_script(&form);
+        assert!(script.contains(r#"iframe.src = "https://api.example.com/f/request?embed=true";"#));
+        assert!(script.contains(r#"iframe.title = "資料請求";"#));
+    }
+}
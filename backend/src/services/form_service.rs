@@ -0,0 +1,866 @@
+use chrono::{DateTime, Duration, Utc};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{
+    database::{
+        crm_integrations,
+        forms::{self, NewFormSubmission},
+        subscribers, templates,
+    },
+    models::{
+        crm::{CrmLead, CrmProviderType},
+        form::{
+            Form, FormSettings, FormSubmission, FormTokenClaims, QuarantineReason, FORM_TOKEN_FIELD,
+        },
+        form_validation::{FieldError, FormSchema},
+        sequence::TriggerType,
+        subscriber::{CreateSubscriberRequest, Subscriber, SubscriberStatus},
+        template::Template,
+    },
+    services::{
+        crm_service::CrmService,
+        email_html,
+        email_service::{EmailMessage, EmailService},
+        markdown_component_service::MarkdownComponentService,
+        markdown_service::MarkdownService,
+        rate_limiter::{RateLimit, RateLimiter},
+        sequence_service::SequenceService,
+    },
+    utils::{
+        client_info::ClientInfo,
+        config::api_base_url,
+        jwt::{generate_signed_token, verify_signed_token, TokenAudience},
+    },
+};
+
+/// 確認リンクの有効期間（日）
+const CONFIRMATION_TOKEN_EXPIRY_DAYS: i64 = 7;
+
+/// 送信トークンの用途
+const FORM_TOKEN_PURPOSE: &str = "form_submit";
+
+/// 送信トークンの有効期間（時間）
+const FORM_TOKEN_EXPIRY_HOURS: i64 = 24;
+
+/// IPアドレスごとの送信回数の上限（`FORM_RATE_LIMIT_PER_IP`で変更できる）
+const DEFAULT_IP_RATE_LIMIT: RateLimit = RateLimit::new(10, 60);
+
+/// フォームごとの送信回数の上限（`FORM_RATE_LIMIT_PER_FORM`で変更できる）
+const DEFAULT_FORM_RATE_LIMIT: RateLimit = RateLimit::new(120, 60);
+
+/// 標準の確認メールの件名
+const DEFAULT_CONFIRMATION_SUBJECT: &str = "ご登録の確認";
+
+/// 標準の確認メールの本文
+const DEFAULT_CONFIRMATION_MARKDOWN: &str = r#"{{name}}様
+
+「{{form_name}}」からのご登録ありがとうございます。
+
+以下のリンクを開いて、ご登録を完了してください。
+
+[ご登録を完了する]({{confirmation_url}})
+
+お心当たりのない場合は、このメールを破棄してください。"#;
+
+/// フォーム操作のエラー
+#[derive(Error, Debug)]
+pub enum FormError {
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error("確認リンクが無効です")]
+    NotFound,
+
+    #[error("確認リンクの有効期限が切れています。もう一度フォームから登録してください")]
+    Expired,
+
+    #[error("入力内容に誤りがあります")]
+    Validation(Vec<FieldError>),
+
+    #[error("{0}")]
+    Conflict(String),
+
+    #[error("送信回数の上限に達しました。{0}秒後にもう一度お試しください")]
+    RateLimited(u64),
+
+    #[error("{0}")]
+    Database(String),
+}
+
+/// 確認リンクを開いた結果
+pub struct ConfirmedSubmission {
+    pub form: Form,
+    pub submission: FormSubmission,
+    /// 今回の確認で購読が確定した（確認済みのリンクを再度開いた場合は`false`）
+    pub newly_confirmed: bool,
+}
+
+pub struct FormService;
+
+impl Default for FormService {
+    fn default() -> Self {
+        Self
+    }
+}
+
+impl FormService {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// フィールド定義と検証ルールを検証
+    pub fn validate_fields(&self, form_fields: Option<&Value>) -> Result<(), FormError> {
+        match form_fields {
+            Some(form_fields) => FormSchema::parse(form_fields)
+                .map(|_| ())
+                .map_err(FormError::Invalid),
+            None => Ok(()),
+        }
+    }
+
+    /// フォームの設定を検証
+    pub async fn validate_settings(
+        &self,
+        pool: &PgPool,
+        user_id: Uuid,
+        settings: Option<&Value>,
+    ) -> Result<(), FormError> {
+        let Some(settings) = settings else {
+            return Ok(());
+        };
+        let settings: FormSettings = serde_json::from_value(settings.clone())
+            .map_err(|e| FormError::Invalid(format!("フォームの設定が不正です: {e}")))?;
+
+        if let Some(template_id) = settings.confirmation_template_id {
+            let template = templates::find_template_by_id(pool, template_id, Some(user_id))
+                .await
+                .map_err(|e| {
+                    FormError::Database(format!("テンプレートの確認に失敗しました: {e}"))
+                })?;
+            if template.is_none() {
+                return Err(FormError::Invalid(
+                    "確認メールのテンプレートが見つかりません".to_string(),
+                ));
+            }
+        }
+
+        if let Some(field) = &settings.honeypot_field {
+            if field.trim().is_empty() || field == FORM_TOKEN_FIELD {
+                return Err(FormError::Invalid(format!(
+                    "ハニーポット項目の名前「{field}」は使用できません"
+                )));
+            }
+        }
+
+        if let Some(url) = &settings.confirmation_redirect_url {
+            if !is_http_url(url) {
+                return Err(FormError::Invalid(format!(
+                    "確認後のリダイレクト先「{url}」が不正です"
+                )));
+            }
+        }
+
+        if let Some(url) = &settings.success_redirect_url {
+            if !is_http_url(url) {
+                return Err(FormError::Invalid(format!(
+                    "送信後のリダイレクト先「{url}」が不正です"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// フォームを公開できるか確認（公開URLのスラッグは公開中のフォーム間で重複できない）
+    pub async fn validate_publish(&self, pool: &PgPool, form: &Form) -> Result<(), FormError> {
+        let taken = forms::is_slug_published_by_other_form(pool, &form.slug, form.id)
+            .await
+            .map_err(|e| FormError::Database(format!("スラッグの確認に失敗しました: {e}")))?;
+        if taken {
+            return Err(FormError::Conflict(format!(
+                "スラッグ「{}」は他の公開中のフォームで使用されています",
+                form.slug
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// 公開フォームへの送信を受け付ける
+    ///
+    /// 回数制限・入力内容の検証・スパム判定を行い、購読者の作成と確認メールの送信（ダブルオプトイン）
+    /// または後続処理まで行う。スパムの疑いがある送信は購読者を作成せずに隔離する。
+    pub async fn submit(
+        &self,
+        pool: &PgPool,
+        redis: &redis::Client,
+        form: &Form,
+        raw_data: &Value,
+        client: ClientInfo,
+    ) -> Result<FormSubmission, FormError> {
+        self.check_rate_limit(redis, form.id, client.ip_address.as_deref())
+            .await?;
+
+        // フィールド定義に従って送信データを検証（定義外の項目は保存しない）
+        let schema = FormSchema::parse(&form.form_fields).map_err(|e| {
+            tracing::error!("フォーム{}のフィールド定義エラー: {}", form.id, e);
+            FormError::Database("フォームの設定に誤りがあるため送信できません".to_string())
+        })?;
+        let data = schema.validate(raw_data).map_err(FormError::Validation)?;
+
+        // 送信者には隔離したことを伝えない
+        if let Some(reason) = self.screen_submission(form, raw_data, Utc::now()) {
+            tracing::warn!(
+                "フォーム{}への送信を隔離しました: {}",
+                form.id,
+                reason.as_str()
+            );
+            return forms::create_form_submission(
+                pool,
+                form.id,
+                NewFormSubmission {
+                    data,
+                    subscriber_id: None,
+                    ip_address: client.ip_address,
+                    user_agent: client.user_agent,
+                    referrer: client.referrer,
+                    confirmation_token: None,
+                    quarantine_reason: Some(reason),
+                },
+            )
+            .await
+            .map_err(|e| FormError::Database(format!("フォームの送信に失敗しました: {e}")));
+        }
+
+        // メールアドレスがある場合は購読者を作成またはリンク
+        let settings = form.settings();
+        let subscriber = match extract_email_from_form_data(&data, &form.form_fields) {
+            Some(email) => {
+                self.find_or_create_subscriber(pool, form, &settings, email, &data)
+                    .await
+            }
+            None => {
+                tracing::warn!("No email field found in form submission");
+                None
+            }
+        };
+
+        // ダブルオプトインの場合は確認メールのリンクが開かれるまで後続処理を保留する
+        let confirmation_token = subscriber
+            .as_ref()
+            .filter(|_| settings.require_confirmation)
+            .map(|_| Uuid::new_v4().to_string());
+
+        let submission = forms::create_form_submission(
+            pool,
+            form.id,
+            NewFormSubmission {
+                data,
+                subscriber_id: subscriber.as_ref().map(|s| s.id),
+                ip_address: client.ip_address,
+                user_agent: client.user_agent,
+                referrer: client.referrer,
+                confirmation_token: confirmation_token.clone(),
+                quarantine_reason: None,
+            },
+        )
+        .await
+        .map_err(|e| FormError::Database(format!("フォームの送信に失敗しました: {e}")))?;
+
+        match (&subscriber, &confirmation_token) {
+            (Some(subscriber), Some(token)) => {
+                match self
+                    .send_confirmation_email(pool, form, subscriber, token)
+                    .await
+                {
+                    Ok(message_id) => {
+                        if let Err(e) =
+                            forms::set_confirmation_message_id(pool, submission.id, &message_id)
+                                .await
+                        {
+                            tracing::error!("確認メールのメッセージIDの記録に失敗しました: {}", e);
+                        }
+                    }
+                    Err(e) => tracing::error!("確認メール送信エラー: {}", e),
+                }
+            }
+            _ => self.process_submission(pool, form, &submission).await,
+        }
+
+        Ok(submission)
+    }
+
+    /// 送信者のメールアドレスの購読者を取得し、いなければ作成する（ダブルオプトインの場合は確認待ち）
+    ///
+    /// エラーはログに記録し、購読者なしで送信を受け付ける。
+    async fn find_or_create_subscriber(
+        &self,
+        pool: &PgPool,
+        form: &Form,
+        settings: &FormSettings,
+        email: String,
+        data: &Value,
+    ) -> Option<Subscriber> {
+        match subscribers::find_subscriber_by_email(pool, &email, form.user_id).await {
+            Ok(Some(subscriber)) => Some(subscriber),
+            Ok(None) => {
+                let status = if settings.require_confirmation {
+                    SubscriberStatus::Pending
+                } else {
+                    SubscriberStatus::Active
+                };
+                let create_req = CreateSubscriberRequest {
+                    email,
+                    name: extract_name_from_form_data(data, &form.form_fields),
+                    status: Some(status),
+                    tags: Some(vec![format!("form:{}", form.slug)]),
+                    custom_fields: Some(data.clone()),
+                };
+                match subscribers::create_subscriber(pool, form.user_id, &create_req).await {
+                    Ok(subscriber) => {
+                        tracing::info!(
+                            "Created new subscriber {} from form submission",
+                            subscriber.id
+                        );
+                        Some(subscriber)
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to create subscriber from form: {}", e);
+                        None
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::error!("Failed to find subscriber by email: {}", e);
+                None
+            }
+        }
+    }
+
+    /// 公開フォームの送信トークンを発行
+    pub fn issue_form_token(&self, form_id: Uuid) -> Result<String, FormError> {
+        let claims = FormTokenClaims {
+            sub: form_id,
+            iat: Utc::now().timestamp(),
+            purpose: FORM_TOKEN_PURPOSE.to_string(),
+        };
+        generate_signed_token(TokenAudience::FormSubmit, &claims)
+            .map_err(|e| FormError::Database(format!("送信トークンの生成に失敗しました: {e}")))
+    }
+
+    /// 送信元IPアドレスごと・フォームごとの送信回数を確認
+    ///
+    /// Redisに接続できない場合は警告を記録して送信を受け付ける。
+    pub async fn check_rate_limit(
+        &self,
+        redis: &redis::Client,
+        form_id: Uuid,
+        ip_address: Option<&str>,
+    ) -> Result<(), FormError> {
+        let limiter = RateLimiter::new(redis.clone());
+        let mut limits = vec![(
+            format!("form:{form_id}"),
+            RateLimit::from_env("FORM_RATE_LIMIT_PER_FORM", DEFAULT_FORM_RATE_LIMIT),
+        )];
+        if let Some(ip_address) = ip_address {
+            limits.push((
+                format!("form_ip:{ip_address}"),
+                RateLimit::from_env("FORM_RATE_LIMIT_PER_IP", DEFAULT_IP_RATE_LIMIT),
+            ));
+        }
+
+        for (key, limit) in limits {
+            match limiter.hit(&key, limit).await {
+                Ok(Some(retry_after)) => return Err(FormError::RateLimited(retry_after)),
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!("フォーム送信の回数制限を確認できませんでした: {}", e);
+                    return Ok(());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// スパムの疑いがある送信か確認し、隔離する理由を返す
+    ///
+    /// ハニーポット項目への入力と、送信トークンによる表示から送信までの時間を確認する。
+    pub fn screen_submission(
+        &self,
+        form: &Form,
+        data: &Value,
+        now: DateTime<Utc>,
+    ) -> Option<QuarantineReason> {
+        let settings = form.settings();
+
+        // フィールドとして定義されている名前はハニーポットとして扱わない
+        let honeypot_field = settings.honeypot_field();
+        let is_defined_field = form
+            .form_fields
+            .as_array()
+            .is_some_and(|fields| fields.iter().any(|f| f["name"] == honeypot_field));
+        let honeypot_filled = match data.get(honeypot_field) {
+            None | Some(Value::Null) => false,
+            Some(Value::String(value)) => !value.trim().is_empty(),
+            Some(_) => true,
+        };
+        if honeypot_filled && !is_defined_field {
+            return Some(QuarantineReason::Honeypot);
+        }
+
+        let min_seconds = settings.min_submit_seconds();
+        if min_seconds == 0 {
+            return None;
+        }
+        let Some(token) = data.get(FORM_TOKEN_FIELD).and_then(Value::as_str) else {
+            return Some(QuarantineReason::MissingToken);
+        };
+        let claims = match verify_signed_token::<FormTokenClaims>(TokenAudience::FormSubmit, token)
+        {
+            Ok(claims) if claims.purpose == FORM_TOKEN_PURPOSE && claims.sub == form.id => claims,
+            _ => return Some(QuarantineReason::InvalidToken),
+        };
+
+        let elapsed = now.timestamp() - claims.iat;
+        if elapsed < 0 {
+            Some(QuarantineReason::InvalidToken)
+        } else if elapsed > Duration::hours(FORM_TOKEN_EXPIRY_HOURS).num_seconds() {
+            Some(QuarantineReason::ExpiredToken)
+        } else if elapsed < i64::from(min_seconds) {
+            Some(QuarantineReason::TooFast)
+        } else {
+            None
+        }
+    }
+
+    /// ダブルオプトインの確認メールを送信（送信したメールのメッセージIDを返す）
+    pub async fn send_confirmation_email(
+        &self,
+        pool: &PgPool,
+        form: &Form,
+        subscriber: &Subscriber,
+        confirmation_token: &str,
+    ) -> Result<String, String> {
+        let settings = form.settings();
+        let template = match settings.confirmation_template_id {
+            Some(template_id) => {
+                templates::find_template_by_id(pool, template_id, Some(form.user_id))
+                    .await
+                    .map_err(|e| format!("テンプレートの取得に失敗しました: {e}"))?
+            }
+            None => None,
+        };
+
+        let markdown_service = MarkdownComponentService::new()
+            .markdown_service(pool, form.user_id)
+            .await?;
+        let message = confirmation_message(
+            &markdown_service,
+            form,
+            &settings,
+            template.as_ref(),
+            subscriber,
+            &confirmation_url(confirmation_token),
+        )?;
+
+        let result = EmailService::new(pool.clone())
+            .await
+            .map_err(|e| format!("メールサービスの初期化に失敗しました: {e}"))?
+            .send_email(&message)
+            .await
+            .map_err(|e| format!("確認メールの送信に失敗しました: {e}"))?;
+
+        Ok(result.message_id)
+    }
+
+    /// 確認リンクによる購読の確定
+    pub async fn confirm_submission(
+        &self,
+        pool: &PgPool,
+        confirmation_token: &str,
+    ) -> Result<ConfirmedSubmission, FormError> {
+        let submission = forms::find_form_submission_by_token(pool, confirmation_token)
+            .await
+            .map_err(|e| FormError::Database(format!("送信データの取得に失敗しました: {e}")))?
+            .ok_or(FormError::NotFound)?;
+        let form = forms::get_form_by_id(pool, submission.form_id)
+            .await
+            .map_err(|e| FormError::Database(format!("フォームの取得に失敗しました: {e}")))?
+            .ok_or(FormError::NotFound)?;
+
+        if submission.confirmed_at.is_some() {
+            return Ok(ConfirmedSubmission {
+                form,
+                submission,
+                newly_confirmed: false,
+            });
+        }
+
+        if submission.created_at + Duration::days(CONFIRMATION_TOKEN_EXPIRY_DAYS) < Utc::now() {
+            return Err(FormError::Expired);
+        }
+
+        // 同じリンクが同時に開かれた場合は、先に確認した方だけが後続処理を行う
+        let Some(confirmed) = forms::confirm_form_submission(pool, submission.id)
+            .await
+            .map_err(|e| FormError::Database(format!("送信データの更新に失敗しました: {e}")))?
+        else {
+            return Ok(ConfirmedSubmission {
+                form,
+                submission,
+                newly_confirmed: false,
+            });
+        };
+
+        if let Some(subscriber_id) = confirmed.subscriber_id {
+            subscribers::confirm_subscriber(pool, subscriber_id)
+                .await
+                .map_err(|e| FormError::Database(format!("購読者の更新に失敗しました: {e}")))?;
+        }
+
+        self.process_submission(pool, &form, &confirmed).await;
+
+        Ok(ConfirmedSubmission {
+            form,
+            submission: confirmed,
+            newly_confirmed: true,
+        })
+    }
+
+    /// 購読が確定したフォーム送信の後続処理（シーケンスのトリガーとCRMのリード作成）
+    ///
+    /// エラーはログに記録するだけで、フォーム送信自体は成功として扱う。
+    pub async fn process_submission(
+        &self,
+        pool: &PgPool,
+        form: &Form,
+        submission: &FormSubmission,
+    ) {
+        // フォーム送信を終了条件にしているシーケンスから外し、フォーム送信時のシーケンストリガーを処理
+        if let Some(subscriber_id) = submission.subscriber_id {
+            if let Err(e) = SequenceService::new()
+                .process_exit_conditions(pool, form.user_id, subscriber_id)
+                .await
+            {
+                tracing::error!("シーケンスの終了条件の処理エラー: {}", e);
+            }
+            if let Err(e) = SequenceService::new()
+                .process_trigger_enrollment(
+                    pool,
+                    form.user_id,
+                    TriggerType::FormSubmission,
+                    subscriber_id,
+                    Some(json!({
+                        "form_id": form.id.to_string(),
+                        "submission_id": submission.id.to_string()
+                    })),
+                )
+                .await
+            {
+                tracing::error!("シーケンスエンロールメントエラー: {}", e);
+            }
+        }
+
+        // CRM統合をチェックしてリードを作成
+        if let Ok(Some(integration)) = crm_integrations::get_user_crm_integration(
+            pool,
+            form.user_id,
+            CrmProviderType::Salesforce,
+        )
+        .await
+        {
+            if integration.is_active() {
+                let lead = CrmLead::from_form_submission(
+                    form.id,
+                    Some(submission.id),
+                    &submission.data,
+                    &form.form_fields,
+                    &form.name,
+                );
+
+                match CrmService::new(pool.clone(), form.user_id).await {
+                    Ok(crm_service) => {
+                        if let Err(e) = crm_service.provider().create_lead(&lead).await {
+                            tracing::error!("Salesforceリード作成エラー: {:?}", e);
+                        } else {
+                            tracing::info!("Salesforceリードを作成しました: {}", lead.email);
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("CRMサービス初期化エラー: {:?}", e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// フォームデータからメールアドレスを抽出
+fn extract_email_from_form_data(form_data: &Value, form_fields: &Value) -> Option<String> {
+    if let (Value::Object(data), Value::Array(fields)) = (form_data, form_fields) {
+        for field in fields {
+            if let Value::Object(field_obj) = field {
+                if let (Some(Value::String(field_type)), Some(Value::String(field_name))) =
+                    (field_obj.get("field_type"), field_obj.get("name"))
+                {
+                    if field_type == "email" {
+                        if let Some(Value::String(email)) = data.get(field_name) {
+                            return Some(email.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+/// フォームデータから名前を抽出
+fn extract_name_from_form_data(form_data: &Value, form_fields: &Value) -> Option<String> {
+    if let (Value::Object(data), Value::Array(fields)) = (form_data, form_fields) {
+        for field in fields {
+            if let Value::Object(field_obj) = field {
+                if let (Some(Value::String(field_type)), Some(Value::String(field_name))) =
+                    (field_obj.get("field_type"), field_obj.get("name"))
+                {
+                    if field_type == "text" && (field_name == "name" || field_name.contains("名前"))
+                    {
+                        if let Some(Value::String(name)) = data.get(field_name) {
+                            return Some(name.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+/// http(s)のURLか
+fn is_http_url(url: &str) -> bool {
+    (url.starts_with("https://") || url.starts_with("http://")) && validator::validate_url(url)
+}
+
+/// 確認リンクのURL
+pub fn confirmation_url(confirmation_token: &str) -> String {
+    format!("{}/api/forms/confirm/{confirmation_token}", api_base_url())
+}
+
+/// 確認メールを作成（テンプレート未指定の場合は標準の文面）
+fn confirmation_message(
+    markdown_service: &MarkdownService,
+    form: &Form,
+    settings: &FormSettings,
+    template: Option<&Template>,
+    subscriber: &Subscriber,
+    confirmation_url: &str,
+) -> Result<EmailMessage, String> {
+    let mut variables = template
+        .map(|t| t.variables.clone())
+        .filter(Value::is_object)
+        .unwrap_or_else(|| json!({}));
+    if let Value::Object(map) = &mut variables {
+        let name = subscriber
+            .name
+            .clone()
+            .unwrap_or_else(|| "お客様".to_string());
+        map.insert("name".to_string(), json!(name.clone()));
+        map.insert("first_name".to_string(), json!(name));
+        map.insert("email".to_string(), json!(subscriber.email));
+        map.insert("form_name".to_string(), json!(form.name));
+        map.insert("confirmation_url".to_string(), json!(confirmation_url));
+    }
+
+    let markdown = template.map_or(DEFAULT_CONFIRMATION_MARKDOWN, |t| {
+        t.markdown_content.as_str()
+    });
+    let html_body = markdown_service
+        .render_with_variables(markdown, &variables)
+        .map_err(|e| format!("HTMLレンダリングに失敗しました: {e}"))?;
+    let text_body = email_html::html_to_text(&html_body);
+
+    let subject = settings
+        .confirmation_subject
+        .as_deref()
+        .map(str::trim)
+        .filter(|subject| !subject.is_empty())
+        .or(template.map(|t| t.subject_template.as_str()))
+        .unwrap_or(DEFAULT_CONFIRMATION_SUBJECT);
+    let subject = markdown_service
+        .render_subject(subject, &variables)
+        .map_err(|e| format!("件名のレンダリングに失敗しました: {e}"))?;
+
+    Ok(EmailMessage {
+        to: vec![subscriber.email.clone()],
+        from: None,
+        subject,
+        html_body,
+        text_body: Some(text_body),
+        reply_to: None,
+        headers: None,
+        dkim: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::subscriber::SubscriberStatus;
+
+    fn test_form(settings: Value) -> Form {
+        Form {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            name: "ニュースレター".to_string(),
+            description: None,
+            slug: "newsletter".to_string(),
+            markdown_content: String::new(),
+            form_fields: json!([]),
+            settings,
+            status: "published".to_string(),
+            submission_count: 0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn subscriber(name: Option<&str>) -> Subscriber {
+        Subscriber {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            email: "taro@example.com".to_string(),
+            name: name.map(str::to_string),
+            status: SubscriberStatus::Pending,
+            tags: Vec::new(),
+            custom_fields: json!({}),
+            subscribed_at: Utc::now(),
+            unsubscribed_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_default_confirmation_message() {
+        let form = test_form(json!({"require_confirmation": true, "success_message": "ok"}));
+        let settings = form.settings();
+        assert!(settings.require_confirmation);
+
+        let url = "https://api.example.com/api/forms/confirm/abc";
+        let message = confirmation_message(
+            &MarkdownService::new(),
+            &form,
+            &settings,
+            None,
+            &subscriber(None),
+            url,
+        )
+        .unwrap();
+
+        assert_eq!(message.to, vec!["taro@example.com"]);
+        assert_eq!(message.subject, DEFAULT_CONFIRMATION_SUBJECT);
+        assert!(message.html_body.contains("お客様様"));
+        assert!(message.html_body.contains("「ニュースレター」"));
+        assert!(message.html_body.contains(&format!("href=\"{url}\"")));
+    }
+
+    #[test]
+    fn test_confirmation_message_from_template() {
+        let now = Utc::now();
+        let template = Template {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            name: "確認メール".to_string(),
+            subject_template: "{{name}}さん、登録を確認してください".to_string(),
+            markdown_content: "[{{company}}の登録を確認]({{confirmation_url}})".to_string(),
+            html_content: None,
+            variables: json!({"company": "MarkMail"}),
+            is_public: false,
+            current_revision: 1,
+            created_at: now,
+            updated_at: now,
+        };
+        let url = "https://api.example.com/api/forms/confirm/xyz";
+
+        let form = test_form(json!({"require_confirmation": true}));
+        let message = confirmation_message(
+            &MarkdownService::new(),
+            &form,
+            &form.settings(),
+            Some(&template),
+            &subscriber(Some("太郎")),
+            url,
+        )
+        .unwrap();
+        assert_eq!(message.subject, "太郎さん、登録を確認してください");
+        assert!(message.html_body.contains("MarkMailの登録を確認"));
+        assert!(message.html_body.contains(url));
+
+        // 件名の設定はテンプレートの件名より優先する
+        let form = test_form(json!({"confirmation_subject": "{{form_name}}のご登録確認"}));
+        let message = confirmation_message(
+            &MarkdownService::new(),
+            &form,
+            &form.settings(),
+            Some(&template),
+            &subscriber(None),
+            url,
+        )
+        .unwrap();
+        assert_eq!(message.subject, "ニュースレターのご登録確認");
+    }
+
+    #[test]
+    fn test_screen_submission() {
+        std::env::set_var("JWT_SECRET", "test_secret_key_for_testing_only");
+        let service = FormService::new();
+        let form = test_form(json!({}));
+        let token = service.issue_form_token(form.id).unwrap();
+        let now = Utc::now();
+
+        let data = json!({"email": "taro@example.com", "_token": token});
+        assert_eq!(
+            service.screen_submission(&form, &data, now + Duration::seconds(5)),
+            None
+        );
+        assert_eq!(
+            service.screen_submission(&form, &data, now),
+            Some(QuarantineReason::TooFast)
+        );
+        assert_eq!(
+            service.screen_submission(&form, &data, now + Duration::hours(25)),
+            Some(QuarantineReason::ExpiredToken)
+        );
+        assert_eq!(
+            service.screen_submission(
+                &form,
+                &json!({"_token": token, "_gotcha": "http://spam.example"}),
+                now + Duration::seconds(5)
+            ),
+            Some(QuarantineReason::Honeypot)
+        );
+        assert_eq!(
+            service.screen_submission(&form, &json!({}), now),
+            Some(QuarantineReason::MissingToken)
+        );
+
+        // 別のフォームのトークンは使えない
+        let other = service.issue_form_token(Uuid::new_v4()).unwrap();
+        assert_eq!(
+            service.screen_submission(&form, &json!({"_token": other}), now),
+            Some(QuarantineReason::InvalidToken)
+        );
+
+        // 待ち時間を0にしたフォームではトークンを確認しない
+        let form = test_form(json!({"min_submit_seconds": 0, "honeypot_field": "website"}));
+        assert_eq!(service.screen_submission(&form, &json!({}), now), None);
+        assert_eq!(
+            service.screen_submission(&form, &json!({"website": "x"}), now),
+            Some(QuarantineReason::Honeypot)
+        );
+    }
+}
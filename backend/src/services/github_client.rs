@@ -0,0 +1,226 @@
+// GitHub REST APIクライアント
+
+use std::time::Duration;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::{Client, StatusCode, Url};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::utils::config::github_api_url;
+
+const GITHUB_API_VERSION: &str = "2022-11-28";
+
+#[derive(Error, Debug)]
+pub enum GitHubError {
+    #[error("GitHubのトークンが無効か、権限が不足しています")]
+    Unauthorized,
+
+    #[error("GitHubでリポジトリまたはファイルが見つかりません")]
+    NotFound,
+
+    #[error("GitHub APIエラー ({0}): {1}")]
+    Api(StatusCode, String),
+
+    #[error("GitHub APIへの接続に失敗しました: {0}")]
+    Request(String),
+}
+
+/// 認証中のユーザー
+#[derive(Debug, Clone, Deserialize)]
+pub struct GitHubUser {
+    pub login: String,
+}
+
+/// リポジトリ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitHubRepository {
+    pub full_name: String,
+    pub private: bool,
+    pub default_branch: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// ディレクトリ内のエントリ・ファイル
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitHubContent {
+    pub name: String,
+    pub path: String,
+    pub sha: String,
+    /// file, dir, symlink, submodule
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default, skip_serializing)]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing)]
+    pub encoding: Option<String>,
+}
+
+impl GitHubContent {
+    pub fn is_markdown_file(&self) -> bool {
+        let name = self.name.to_ascii_lowercase();
+        self.kind == "file" && (name.ends_with(".md") || name.ends_with(".markdown"))
+    }
+}
+
+/// Contents APIのレスポンス（ディレクトリは配列、ファイルはオブジェクト）
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum GitHubContents {
+    Directory(Vec<GitHubContent>),
+    File(GitHubContent),
+}
+
+/// 取得したファイル
+#[derive(Debug, Clone)]
+pub struct GitHubFile {
+    pub path: String,
+    pub sha: String,
+    pub content: String,
+}
+
+pub struct GitHubClient {
+    http: Client,
+    base_url: String,
+    token: String,
+}
+
+impl GitHubClient {
+    pub fn new(token: impl Into<String>) -> Self {
+        Self::with_base_url(github_api_url(), token)
+    }
+
+    pub fn with_base_url(base_url: impl Into<String>, token: impl Into<String>) -> Self {
+        let http = Client::builder()
+            .timeout(Duration::from_secs(15))
+            .user_agent("MarkMail")
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            http,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            token: token.into(),
+        }
+    }
+
+    /// トークンのユーザー
+    pub async fn authenticated_user(&self) -> Result<GitHubUser, GitHubError> {
+        self.get(&["user"], &[]).await
+    }
+
+    /// トークンでアクセスできるリポジトリ（更新日時の新しい順）
+    pub async fn list_repositories(&self) -> Result<Vec<GitHubRepository>, GitHubError> {
+        self.get(
+            &["user", "repos"],
+            &[("per_page", "100"), ("sort", "updated")],
+        )
+        .await
+    }
+
+    pub async fn get_repository(&self, repository: &str) -> Result<GitHubRepository, GitHubError> {
+        let (owner, name) = split_repository(repository)?;
+        self.get(&["repos", owner, name], &[]).await
+    }
+
+    /// パスのディレクトリ内のエントリまたはファイル
+    pub async fn get_contents(
+        &self,
+        repository: &str,
+        path: &str,
+        git_ref: &str,
+    ) -> Result<GitHubContents, GitHubError> {
+        let (owner, name) = split_repository(repository)?;
+        let mut segments = vec!["repos", owner, name, "contents"];
+        segments.extend(path.split('/').filter(|segment| !segment.is_empty()));
+        self.get(&segments, &[("ref", git_ref)]).await
+    }
+
+    /// ファイルの内容（UTF-8）
+    pub async fn get_file(
+        &self,
+        repository: &str,
+        path: &str,
+        git_ref: &str,
+    ) -> Result<GitHubFile, GitHubError> {
+        let file = match self.get_contents(repository, path, git_ref).await? {
+            GitHubContents::File(file) if file.kind == "file" => file,
+            _ => return Err(GitHubError::NotFound),
+        };
+
+        let encoded = file.content.as_deref().unwrap_or_default();
+        let content = match file.encoding.as_deref() {
+            Some("base64") => {
+                let compact: String = encoded.split_whitespace().collect();
+                let bytes = STANDARD.decode(compact).map_err(|e| {
+                    GitHubError::Request(format!("ファイルの内容を読み取れません: {e}"))
+                })?;
+                String::from_utf8(bytes).map_err(|_| {
+                    GitHubError::Request("ファイルがUTF-8ではありません".to_string())
+                })?
+            }
+            _ => encoded.to_string(),
+        };
+
+        Ok(GitHubFile {
+            path: file.path,
+            sha: file.sha,
+            content,
+        })
+    }
+
+    async fn get<T: DeserializeOwned>(
+        &self,
+        segments: &[&str],
+        query: &[(&str, &str)],
+    ) -> Result<T, GitHubError> {
+        let mut url = Url::parse(&self.base_url)
+            .map_err(|e| GitHubError::Request(format!("GitHub APIのURLが不正です: {e}")))?;
+        url.path_segments_mut()
+            .map_err(|_| GitHubError::Request("GitHub APIのURLが不正です".to_string()))?
+            .pop_if_empty()
+            .extend(segments);
+
+        let response = self
+            .http
+            .get(url)
+            .query(query)
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", GITHUB_API_VERSION)
+            .send()
+            .await
+            .map_err(|e| GitHubError::Request(e.to_string()))?;
+
+        match response.status() {
+            status if status.is_success() => response
+                .json::<T>()
+                .await
+                .map_err(|e| GitHubError::Request(format!("レスポンスを読み取れません: {e}"))),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(GitHubError::Unauthorized),
+            StatusCode::NOT_FOUND => Err(GitHubError::NotFound),
+            status => {
+                let message = response.text().await.unwrap_or_default();
+                Err(GitHubError::Api(status, message))
+            }
+        }
+    }
+}
+
+/// owner/name形式のリポジトリ名を分割
+pub fn split_repository(repository: &str) -> Result<(&str, &str), GitHubError> {
+    let valid = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    };
+
+    match repository.split_once('/') {
+        Some((owner, name)) if valid(owner) && valid(name) => Ok((owner, name)),
+        _ => Err(GitHubError::NotFound),
+    }
+}
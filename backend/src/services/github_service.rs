@@ -0,0 +1,665 @@
+// GitHubのマークダウンファイルをテンプレートとして取り込むサービス
+
+use openssl::{error::ErrorStack, hash::MessageDigest, memcmp, pkey::PKey, sign::Signer};
+use rand::Rng;
+use serde::Deserialize;
+use serde_json::Value;
+use sqlx::PgPool;
+use thiserror::Error;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    database::{github, templates},
+    models::{
+        github::{
+            GitHubConnection, GitHubConnectionResponse, GitHubFileLocation, GitHubImportError,
+            GitHubImportResult, ImportFromGitHubRequest, ImportedTemplate, TemplateFrontMatter,
+        },
+        template::{CreateTemplateRequest, UpdateTemplateRequest},
+    },
+    services::{
+        github_client::{
+            split_repository, GitHubClient, GitHubContent, GitHubContents, GitHubError,
+            GitHubRepository,
+        },
+        markdown_component_service::MarkdownComponentService,
+        markdown_service::MarkdownService,
+    },
+    utils::{config::api_base_url, encryption},
+};
+
+#[derive(Error, Debug)]
+pub enum GitHubServiceError {
+    #[error("GitHubと連携していません")]
+    NotConnected,
+
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error(transparent)]
+    GitHub(#[from] GitHubError),
+
+    #[error("Webhookの署名が正しくありません")]
+    InvalidSignature,
+
+    #[error("{0}")]
+    Database(String),
+}
+
+/// pushイベントのペイロード（必要な項目のみ）
+#[derive(Debug, Deserialize)]
+struct PushEvent {
+    #[serde(rename = "ref")]
+    git_ref: String,
+    repository: PushRepository,
+    #[serde(default)]
+    commits: Vec<PushCommit>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PushRepository {
+    full_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PushCommit {
+    #[serde(default)]
+    added: Vec<String>,
+    #[serde(default)]
+    modified: Vec<String>,
+}
+
+pub struct GitHubService;
+
+impl Default for GitHubService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GitHubService {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// トークンを検証してGitHubと連携する
+    pub async fn connect(
+        &self,
+        pool: &PgPool,
+        user_id: Uuid,
+        access_token: &str,
+    ) -> Result<GitHubConnectionResponse, GitHubServiceError> {
+        let user = GitHubClient::new(access_token)
+            .authenticated_user()
+            .await
+            .map_err(|e| match e {
+                GitHubError::Unauthorized | GitHubError::NotFound => {
+                    GitHubServiceError::Invalid("GitHubのアクセストークンが無効です".to_string())
+                }
+                e => e.into(),
+            })?;
+
+        let connection = github::upsert_connection(
+            pool,
+            user_id,
+            &encrypt(access_token)?,
+            &user.login,
+            &encrypt(&generate_webhook_secret())?,
+        )
+        .await
+        .map_err(|e| db_error("GitHub連携の保存", e))?;
+
+        Ok(connection_response(decrypt_connection(connection)?))
+    }
+
+    /// 連携状態を取得
+    pub async fn connection(
+        &self,
+        pool: &PgPool,
+        user_id: Uuid,
+    ) -> Result<GitHubConnectionResponse, GitHubServiceError> {
+        self.require_connection(pool, user_id)
+            .await
+            .map(connection_response)
+    }
+
+    /// 連携を解除（取り込み済みのテンプレートは残す）
+    pub async fn disconnect(&self, pool: &PgPool, user_id: Uuid) -> Result<(), GitHubServiceError> {
+        let deleted = github::delete_connection(pool, user_id)
+            .await
+            .map_err(|e| db_error("GitHub連携の削除", e))?;
+
+        if deleted {
+            Ok(())
+        } else {
+            Err(GitHubServiceError::NotConnected)
+        }
+    }
+
+    /// アクセスできるリポジトリ一覧
+    pub async fn list_repositories(
+        &self,
+        pool: &PgPool,
+        user_id: Uuid,
+    ) -> Result<Vec<GitHubRepository>, GitHubServiceError> {
+        let connection = self.require_connection(pool, user_id).await?;
+        Ok(GitHubClient::new(connection.access_token)
+            .list_repositories()
+            .await?)
+    }
+
+    /// リポジトリ内のパスを閲覧（ファイルを指定した場合はそのファイルのみ）
+    pub async fn list_contents(
+        &self,
+        pool: &PgPool,
+        user_id: Uuid,
+        repository: &str,
+        path: &str,
+        git_ref: Option<&str>,
+    ) -> Result<Vec<GitHubContent>, GitHubServiceError> {
+        let connection = self.require_connection(pool, user_id).await?;
+        let client = GitHubClient::new(connection.access_token);
+        let git_ref = resolve_ref(&client, repository, git_ref).await?;
+
+        let mut entries = match client
+            .get_contents(repository, path.trim_matches('/'), &git_ref)
+            .await?
+        {
+            GitHubContents::Directory(entries) => entries,
+            GitHubContents::File(file) => vec![file],
+        };
+        // ディレクトリを先に、名前順に並べる
+        entries.sort_by(|a, b| (a.kind != "dir", &a.name).cmp(&(b.kind != "dir", &b.name)));
+
+        Ok(entries)
+    }
+
+    /// マークダウンファイル（ディレクトリの場合は直下の.mdファイル）をテンプレートとして取り込む
+    ///
+    /// 取り込み済みのファイルは既存のテンプレートを更新する。ファイルごとのエラーは結果に含める。
+    pub async fn import(
+        &self,
+        pool: &PgPool,
+        user_id: Uuid,
+        request: &ImportFromGitHubRequest,
+    ) -> Result<GitHubImportResult, GitHubServiceError> {
+        if split_repository(&request.repository).is_err() {
+            return Err(GitHubServiceError::Invalid(
+                "リポジトリを owner/name の形式で指定してください".to_string(),
+            ));
+        }
+
+        let connection = self.require_connection(pool, user_id).await?;
+        let client = GitHubClient::new(connection.access_token);
+        let git_ref = resolve_ref(&client, &request.repository, request.git_ref.as_deref()).await?;
+
+        let paths: Vec<String> = match client
+            .get_contents(
+                &request.repository,
+                request.path.trim_matches('/'),
+                &git_ref,
+            )
+            .await?
+        {
+            GitHubContents::Directory(entries) => entries
+                .into_iter()
+                .filter(GitHubContent::is_markdown_file)
+                .map(|entry| entry.path)
+                .collect(),
+            GitHubContents::File(file) if file.is_markdown_file() => vec![file.path],
+            GitHubContents::File(_) => {
+                return Err(GitHubServiceError::Invalid(
+                    "マークダウンファイル（.md）またはディレクトリを指定してください".to_string(),
+                ))
+            }
+        };
+
+        let markdown_service = MarkdownComponentService::new()
+            .markdown_service(pool, user_id)
+            .await
+            .map_err(GitHubServiceError::Database)?;
+
+        let mut result = GitHubImportResult::default();
+        for path in paths {
+            let location = GitHubFileLocation {
+                repository: request.repository.clone(),
+                git_ref: git_ref.clone(),
+                path,
+            };
+
+            match sync_file(
+                pool,
+                &client,
+                &markdown_service,
+                user_id,
+                &location,
+                request.auto_sync,
+            )
+            .await
+            {
+                Ok(imported) => result.imported.push(imported),
+                Err(error) => result.errors.push(GitHubImportError {
+                    path: location.path,
+                    error,
+                }),
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// pushのWebhookを処理し、変更された自動更新対象のファイルを取り込み直す
+    pub async fn handle_webhook(
+        &self,
+        pool: &PgPool,
+        connection_id: Uuid,
+        event: &str,
+        signature: Option<&str>,
+        body: &[u8],
+    ) -> Result<GitHubImportResult, GitHubServiceError> {
+        let connection = github::find_connection_by_id(pool, connection_id)
+            .await
+            .map_err(|e| db_error("GitHub連携の取得", e))?
+            .ok_or(GitHubServiceError::NotConnected)?;
+        let connection = decrypt_connection(connection)?;
+
+        if !verify_signature(
+            &connection.webhook_secret,
+            body,
+            signature.unwrap_or_default(),
+        ) {
+            return Err(GitHubServiceError::InvalidSignature);
+        }
+
+        // ping等のpush以外のイベントは何もしない
+        if event != "push" {
+            return Ok(GitHubImportResult::default());
+        }
+
+        let push: PushEvent = serde_json::from_slice(body).map_err(|e| {
+            GitHubServiceError::Invalid(format!("pushイベントを読み取れません: {e}"))
+        })?;
+        // タグへのpushは対象外
+        let Some(branch) = push.git_ref.strip_prefix("refs/heads/") else {
+            return Ok(GitHubImportResult::default());
+        };
+
+        let changed: Vec<&str> = push
+            .commits
+            .iter()
+            .flat_map(|commit| commit.added.iter().chain(&commit.modified))
+            .map(String::as_str)
+            .collect();
+
+        let sources = github::list_auto_sync_sources(
+            pool,
+            connection.user_id,
+            &push.repository.full_name,
+            branch,
+        )
+        .await
+        .map_err(|e| db_error("取り込み元の取得", e))?;
+
+        let mut result = GitHubImportResult::default();
+        let sources: Vec<_> = sources
+            .into_iter()
+            .filter(|source| changed.contains(&source.path.as_str()))
+            .collect();
+        if sources.is_empty() {
+            return Ok(result);
+        }
+
+        let client = GitHubClient::new(connection.access_token);
+        let markdown_service = MarkdownComponentService::new()
+            .markdown_service(pool, connection.user_id)
+            .await
+            .map_err(GitHubServiceError::Database)?;
+
+        for source in sources {
+            let location = GitHubFileLocation {
+                repository: source.repository,
+                git_ref: source.git_ref,
+                path: source.path,
+            };
+
+            match sync_file(
+                pool,
+                &client,
+                &markdown_service,
+                connection.user_id,
+                &location,
+                true,
+            )
+            .await
+            {
+                Ok(imported) => result.imported.push(imported),
+                Err(error) => {
+                    tracing::warn!(
+                        "GitHubからのテンプレート再取り込みに失敗しました: {}/{}: {}",
+                        location.repository,
+                        location.path,
+                        error
+                    );
+                    result.errors.push(GitHubImportError {
+                        path: location.path,
+                        error,
+                    });
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn require_connection(
+        &self,
+        pool: &PgPool,
+        user_id: Uuid,
+    ) -> Result<GitHubConnection, GitHubServiceError> {
+        let connection = github::find_connection_by_user(pool, user_id)
+            .await
+            .map_err(|e| db_error("GitHub連携の取得", e))?
+            .ok_or(GitHubServiceError::NotConnected)?;
+        decrypt_connection(connection)
+    }
+}
+
+/// 暗号化前に保存されたGitHubのトークン・Webhookの秘密鍵を暗号化する（起動時に実行する）
+pub async fn encrypt_stored_github_secrets(pool: &PgPool) -> Result<usize, String> {
+    let stored = github::list_plaintext_connection_secrets(pool)
+        .await
+        .map_err(|e| format!("GitHub連携の取得に失敗しました: {e}"))?;
+
+    for (connection_id, access_token, webhook_secret) in &stored {
+        let encrypt_stored = |value: &str| -> Result<String, String> {
+            if encryption::is_encrypted(value) {
+                Ok(value.to_string())
+            } else {
+                encryption::encrypt_secret(value).map_err(|e| e.to_string())
+            }
+        };
+        github::update_connection_secrets(
+            pool,
+            *connection_id,
+            &encrypt_stored(access_token)?,
+            &encrypt_stored(webhook_secret)?,
+        )
+        .await
+        .map_err(|e| format!("GitHub連携の暗号化に失敗しました: {e}"))?;
+    }
+
+    Ok(stored.len())
+}
+
+/// 保存されたトークン・Webhookの秘密鍵を復号する
+fn decrypt_connection(
+    mut connection: GitHubConnection,
+) -> Result<GitHubConnection, GitHubServiceError> {
+    let decrypt = |value: &str| {
+        encryption::decrypt_secret(value).map_err(|e| GitHubServiceError::Database(e.to_string()))
+    };
+    connection.access_token = decrypt(&connection.access_token)?;
+    connection.webhook_secret = decrypt(&connection.webhook_secret)?;
+    Ok(connection)
+}
+
+fn encrypt(value: &str) -> Result<String, GitHubServiceError> {
+    encryption::encrypt_secret(value).map_err(|e| GitHubServiceError::Database(e.to_string()))
+}
+
+/// ファイルを取得してテンプレートを作成・更新し、取り込み元を記録する
+async fn sync_file(
+    pool: &PgPool,
+    client: &GitHubClient,
+    markdown_service: &MarkdownService,
+    user_id: Uuid,
+    location: &GitHubFileLocation,
+    auto_sync: bool,
+) -> Result<ImportedTemplate, String> {
+    let file = client
+        .get_file(&location.repository, &location.path, &location.git_ref)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let (front_matter, markdown_content) = parse_front_matter(&file.content)?;
+    let subject_template = front_matter
+        .subject
+        .ok_or_else(|| "フロントマターにsubjectがありません".to_string())?;
+    let name = front_matter
+        .name
+        .unwrap_or_else(|| file_stem(&location.path).to_string());
+
+    let request = CreateTemplateRequest {
+        name,
+        subject_template,
+        markdown_content,
+        variables: front_matter.variables,
+        is_public: None,
+    };
+    request.validate().map_err(|e| e.to_string())?;
+    markdown_service.validate_template(&request.markdown_content, &request.subject_template)?;
+
+    let source = github::find_template_source(pool, user_id, location)
+        .await
+        .map_err(|e| format!("取り込み元の取得に失敗しました: {e}"))?;
+
+    let updated = match source {
+        Some(source) => templates::update_template(
+            pool,
+            source.template_id,
+            user_id,
+            &UpdateTemplateRequest {
+                name: Some(request.name.clone()),
+                subject_template: Some(request.subject_template.clone()),
+                markdown_content: Some(request.markdown_content.clone()),
+                html_content: None,
+                variables: request.variables.clone(),
+                is_public: None,
+            },
+        )
+        .await
+        .map_err(|e| format!("テンプレートの更新に失敗しました: {e}"))?,
+        None => None,
+    };
+
+    let (template, updated) = match updated {
+        Some(template) => (template, true),
+        None => (
+            templates::create_template(pool, user_id, &request)
+                .await
+                .map_err(|e| format!("テンプレートの作成に失敗しました: {e}"))?,
+            false,
+        ),
+    };
+
+    github::save_template_source(pool, user_id, template.id, location, auto_sync, &file.sha)
+        .await
+        .map_err(|e| format!("取り込み元の保存に失敗しました: {e}"))?;
+
+    Ok(ImportedTemplate {
+        path: location.path.clone(),
+        template_id: template.id,
+        name: template.name,
+        updated,
+    })
+}
+
+/// ブランチ名（省略時はリポジトリのデフォルトブランチ）
+async fn resolve_ref(
+    client: &GitHubClient,
+    repository: &str,
+    git_ref: Option<&str>,
+) -> Result<String, GitHubServiceError> {
+    match git_ref.map(str::trim).filter(|r| !r.is_empty()) {
+        Some(git_ref) => Ok(git_ref.to_string()),
+        None => Ok(client.get_repository(repository).await?.default_branch),
+    }
+}
+
+/// マークダウンの先頭の `---` で囲まれたYAMLフロントマターと本文を分ける
+pub fn parse_front_matter(content: &str) -> Result<(TemplateFrontMatter, String), String> {
+    let content = content.trim_start_matches('\u{feff}');
+    let mut lines = content.split_inclusive('\n');
+
+    if lines.next().map(str::trim_end) != Some("---") {
+        return Ok((TemplateFrontMatter::default(), content.to_string()));
+    }
+
+    let mut yaml = String::new();
+    let mut closed = false;
+    for line in lines.by_ref() {
+        if line.trim_end() == "---" {
+            closed = true;
+            break;
+        }
+        yaml.push_str(line);
+    }
+    if !closed {
+        return Err("フロントマターが `---` で閉じられていません".to_string());
+    }
+
+    let front_matter: TemplateFrontMatter = if yaml.trim().is_empty() {
+        TemplateFrontMatter::default()
+    } else {
+        serde_yaml_ng::from_str(&yaml)
+            .map_err(|e| format!("フロントマターを読み取れません: {e}"))?
+    };
+    if front_matter
+        .variables
+        .as_ref()
+        .is_some_and(|variables| !matches!(variables, Value::Object(_)))
+    {
+        return Err("フロントマターのvariablesはキーと値の組で指定してください".to_string());
+    }
+
+    let body: String = lines.collect();
+    Ok((
+        front_matter,
+        body.trim_start_matches(['\r', '\n']).to_string(),
+    ))
+}
+
+/// X-Hub-Signature-256（sha256=<HMAC-SHA256の16進数>）を検証（署名を計算できない場合は不一致とする）
+pub fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let Some(expected) = signature.strip_prefix("sha256=") else {
+        return false;
+    };
+    let Ok(actual) = sign_payload(secret, body) else {
+        return false;
+    };
+
+    expected.len() == actual.len() && memcmp::eq(expected.as_bytes(), actual.as_bytes())
+}
+
+/// ペイロードのHMAC-SHA256（16進数）
+pub fn sign_payload(secret: &str, body: &[u8]) -> Result<String, ErrorStack> {
+    let key = PKey::hmac(secret.as_bytes())?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+    signer.update(body)?;
+    let digest = signer.sign_to_vec()?;
+
+    Ok(digest.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+fn generate_webhook_secret() -> String {
+    let bytes: [u8; 32] = rand::thread_rng().gen();
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn connection_response(connection: GitHubConnection) -> GitHubConnectionResponse {
+    GitHubConnectionResponse {
+        webhook_url: format!(
+            "{}/api/integrations/github/webhook/{}",
+            api_base_url(),
+            connection.id
+        ),
+        account_login: connection.account_login,
+        webhook_secret: connection.webhook_secret,
+        created_at: connection.created_at,
+    }
+}
+
+fn file_stem(path: &str) -> &str {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name.rsplit_once('.').map_or(name, |(stem, _)| stem)
+}
+
+fn db_error(action: &str, error: sqlx::Error) -> GitHubServiceError {
+    GitHubServiceError::Database(format!("{action}に失敗しました: {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_front_matter() {
+        let content = "\u{feff}---\r\nname: 週刊ニュース\r\nsubject: \"{{name}}様 今週のお知らせ\"\r\nvariables:\r\n  name: 読者\r\n---\r\n\r\n# 今週のお知らせ\r\n";
+        let (front_matter, body) = parse_front_matter(content).unwrap();
+
+        assert_eq!(front_matter.name.as_deref(), Some("週刊ニュース"));
+        assert_eq!(
+            front_matter.subject.as_deref(),
+            Some("{{name}}様 今週のお知らせ")
+        );
+        assert_eq!(front_matter.variables, Some(json!({"name": "読者"})));
+        assert_eq!(body, "# 今週のお知らせ\r\n");
+    }
+
+    #[test]
+    fn test_parse_front_matter_without_front_matter() {
+        let (front_matter, body) = parse_front_matter("# 見出し\n\n---\n").unwrap();
+        assert_eq!(front_matter, TemplateFrontMatter::default());
+        assert_eq!(body, "# 見出し\n\n---\n");
+    }
+
+    #[test]
+    fn test_parse_front_matter_errors() {
+        assert!(parse_front_matter("---\nsubject: 件名\n# 本文\n").is_err());
+        assert!(parse_front_matter("---\nsubject: [\n---\n").is_err());
+        assert!(parse_front_matter("---\nsubject: 件名\nvariables: [a, b]\n---\n").is_err());
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        // GitHubのドキュメントにある検証用の値
+        let signature = format!(
+            "sha256={}",
+            sign_payload("It's a Secret to Everybody", b"Hello, World!").unwrap()
+        );
+        assert_eq!(
+            signature,
+            "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17"
+        );
+        assert!(verify_signature(
+            "It's a Secret to Everybody",
+            b"Hello, World!",
+            &signature
+        ));
+        assert!(!verify_signature("other", b"Hello, World!", &signature));
+        assert!(!verify_signature(
+            "It's a Secret to Everybody",
+            b"Hello, World!",
+            "sha256=00"
+        ));
+        assert!(!verify_signature(
+            "It's a Secret to Everybody",
+            b"Hello, World!",
+            ""
+        ));
+        // 空の署名は受け付けない
+        assert!(!verify_signature(
+            "It's a Secret to Everybody",
+            b"Hello, World!",
+            "sha256="
+        ));
+    }
+
+    #[test]
+    fn test_file_stem() {
+        assert_eq!(file_stem("newsletters/2025-08.md"), "2025-08");
+        assert_eq!(file_stem("welcome.markdown"), "welcome");
+    }
+}
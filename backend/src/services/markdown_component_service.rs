@@ -0,0 +1,236 @@
+use sqlx::PgPool;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{
+    database::markdown_components,
+    models::markdown_component::{
+        normalize_component_name, ComponentKind, CreateMarkdownComponentRequest, MarkdownComponent,
+        UpdateMarkdownComponentRequest,
+    },
+    services::{
+        markdown_components::{is_reserved_name, ComponentLibrary},
+        markdown_service::MarkdownService,
+        template_engine::TemplateEngine,
+    },
+};
+
+/// コンポーネント操作のエラー
+#[derive(Error, Debug)]
+pub enum MarkdownComponentError {
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error("コンポーネントが見つかりません")]
+    NotFound,
+
+    #[error("{0}")]
+    Conflict(String),
+
+    #[error("{0}")]
+    Database(String),
+}
+
+pub struct MarkdownComponentService;
+
+impl Default for MarkdownComponentService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MarkdownComponentService {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// ユーザーのコンポーネントとスニペットを読み込んだマークダウンサービス
+    pub async fn markdown_service(
+        &self,
+        pool: &PgPool,
+        user_id: Uuid,
+    ) -> Result<MarkdownService, String> {
+        let components = markdown_components::list_components(pool, user_id)
+            .await
+            .map_err(|e| format!("マークダウンコンポーネントの取得に失敗しました: {e}"))?;
+
+        Ok(MarkdownService::with_components(
+            ComponentLibrary::from_components(components),
+        ))
+    }
+
+    /// コンポーネント・スニペット一覧を取得
+    pub async fn list_components(
+        &self,
+        pool: &PgPool,
+        user_id: Uuid,
+    ) -> Result<Vec<MarkdownComponent>, MarkdownComponentError> {
+        markdown_components::list_components(pool, user_id)
+            .await
+            .map_err(|e| {
+                MarkdownComponentError::Database(format!("コンポーネントの取得に失敗しました: {e}"))
+            })
+    }
+
+    /// コンポーネント・スニペットを取得
+    pub async fn get_component(
+        &self,
+        pool: &PgPool,
+        component_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<MarkdownComponent, MarkdownComponentError> {
+        markdown_components::find_component_by_id(pool, component_id, user_id)
+            .await
+            .map_err(|e| {
+                MarkdownComponentError::Database(format!("コンポーネントの取得に失敗しました: {e}"))
+            })?
+            .ok_or(MarkdownComponentError::NotFound)
+    }
+
+    /// コンポーネント・スニペットを登録
+    pub async fn create_component(
+        &self,
+        pool: &PgPool,
+        user_id: Uuid,
+        request: &CreateMarkdownComponentRequest,
+    ) -> Result<MarkdownComponent, MarkdownComponentError> {
+        let name = validate_component(request.kind, &request.name, &request.content)?;
+
+        markdown_components::create_component(
+            pool,
+            user_id,
+            request.kind,
+            &name,
+            description(&request.description),
+            &request.content,
+        )
+        .await
+        .map_err(|e| {
+            MarkdownComponentError::Database(format!("コンポーネントの登録に失敗しました: {e}"))
+        })?
+        .ok_or_else(|| MarkdownComponentError::Conflict(format!("「{name}」は登録済みです")))
+    }
+
+    /// コンポーネント・スニペットを更新
+    pub async fn update_component(
+        &self,
+        pool: &PgPool,
+        component_id: Uuid,
+        user_id: Uuid,
+        request: &UpdateMarkdownComponentRequest,
+    ) -> Result<MarkdownComponent, MarkdownComponentError> {
+        let existing = self.get_component(pool, component_id, user_id).await?;
+        let kind = ComponentKind::from(existing.kind.clone());
+        let content = request.content.as_ref().unwrap_or(&existing.content);
+        let name = validate_component(
+            kind,
+            request.name.as_ref().unwrap_or(&existing.name),
+            content,
+        )?;
+
+        if name != existing.name {
+            let duplicate = markdown_components::find_component_by_name(pool, user_id, kind, &name)
+                .await
+                .map_err(|e| {
+                    MarkdownComponentError::Database(format!(
+                        "コンポーネントの取得に失敗しました: {e}"
+                    ))
+                })?;
+            if duplicate.is_some() {
+                return Err(MarkdownComponentError::Conflict(format!(
+                    "「{name}」は登録済みです"
+                )));
+            }
+        }
+
+        let description = match &request.description {
+            Some(_) => description(&request.description),
+            None => existing.description.as_deref(),
+        };
+
+        markdown_components::update_component(
+            pool,
+            component_id,
+            user_id,
+            &name,
+            description,
+            content,
+        )
+        .await
+        .map_err(|e| {
+            MarkdownComponentError::Database(format!("コンポーネントの更新に失敗しました: {e}"))
+        })?
+        .ok_or(MarkdownComponentError::NotFound)
+    }
+
+    /// コンポーネント・スニペットを削除
+    pub async fn delete_component(
+        &self,
+        pool: &PgPool,
+        component_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<(), MarkdownComponentError> {
+        let deleted = markdown_components::delete_component(pool, component_id, user_id)
+            .await
+            .map_err(|e| {
+                MarkdownComponentError::Database(format!("コンポーネントの削除に失敗しました: {e}"))
+            })?;
+
+        if deleted {
+            Ok(())
+        } else {
+            Err(MarkdownComponentError::NotFound)
+        }
+    }
+}
+
+fn description(description: &Option<String>) -> Option<&str> {
+    description
+        .as_deref()
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+}
+
+/// 名前とテンプレート構文を検証し、正規化した名前を返す
+fn validate_component(
+    kind: ComponentKind,
+    name: &str,
+    content: &str,
+) -> Result<String, MarkdownComponentError> {
+    let name = normalize_component_name(name).map_err(MarkdownComponentError::Invalid)?;
+    if kind == ComponentKind::Component && is_reserved_name(&name) {
+        return Err(MarkdownComponentError::Invalid(format!(
+            "「{name}」は組み込みのコンポーネント名のため使用できません"
+        )));
+    }
+
+    TemplateEngine::shared()
+        .validate(content)
+        .map_err(MarkdownComponentError::Invalid)?;
+
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_component() {
+        assert_eq!(
+            validate_component(ComponentKind::Component, "Hero", "<div>{{{content}}}</div>")
+                .unwrap(),
+            "hero"
+        );
+        // スニペットは組み込みのコンポーネント名と重ならない
+        assert!(validate_component(ComponentKind::Snippet, "button", "本文").is_ok());
+        assert!(matches!(
+            validate_component(ComponentKind::Component, "button", "<a></a>"),
+            Err(MarkdownComponentError::Invalid(_))
+        ));
+        assert!(matches!(
+            validate_component(ComponentKind::Component, "hero", "{{#if title}}"),
+            Err(MarkdownComponentError::Invalid(_))
+        ));
+    }
+}
@@ -0,0 +1,613 @@
+// マークダウンコンポーネント（`:::button{href="..."}`など）とスニペットの展開
+
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+use crate::{
+    models::markdown_component::{ComponentKind, MarkdownComponent},
+    services::template_engine::{escape_outside_tags, TemplateEngine},
+};
+
+/// スニペットを埋め込むディレクティブ名（`:::snippet{name="footer"}`）
+pub const SNIPPET_DIRECTIVE: &str = "snippet";
+
+/// スニペットの入れ子の上限
+const MAX_SNIPPET_DEPTH: usize = 5;
+
+/// コンテナの中身を差し込む位置の目印
+const CONTENT_MARKER: &str = "\u{0}markmail-component-content\u{0}";
+
+/// 組み込みコンポーネント（名前、中身を`:::`で囲めるか、HTMLテンプレート）
+///
+/// メールクライアントで崩れないよう、テーブルとインラインスタイルで組む。
+const BUILTIN_COMPONENTS: &[(&str, bool, &str)] = &[
+    (
+        "button",
+        false,
+        r##"<table role="presentation" border="0" cellpadding="0" cellspacing="0" width="100%" style="margin: 16px 0;">
+<tr><td align="{{ align | default: "center" }}">
+<table role="presentation" border="0" cellpadding="0" cellspacing="0">
+<tr><td align="center" bgcolor="{{ color | default: "#3498db" }}" style="border-radius: 5px;">
+<a href="{{href}}" target="_blank" style="display: inline-block; padding: 12px 24px; font-weight: bold; color: {{ text_color | default: "#ffffff" }}; text-decoration: none; border-radius: 5px;">{{#if content}}{{{content}}}{{else}}{{ label | default: "詳しく見る" }}{{/if}}</a>
+</td></tr>
+</table>
+</td></tr>
+</table>"##,
+    ),
+    (
+        "callout",
+        true,
+        r##"<table role="presentation" border="0" cellpadding="0" cellspacing="0" width="100%" style="margin: 16px 0;">
+<tr><td style="border-left: 4px solid {{ color | default: "#3498db" }}; background-color: {{ background | default: "#f8f9fa" }}; padding: 12px 20px;">
+{{{content}}}
+</td></tr>
+</table>"##,
+    ),
+    (
+        "product-card",
+        true,
+        r##"<table role="presentation" border="0" cellpadding="0" cellspacing="0" width="100%" style="margin: 16px 0; border: 1px solid #e9ecef; border-radius: 5px;">
+{{#if image}}<tr><td><img src="{{image}}" alt="{{title}}" width="100%" style="display: block; width: 100%; height: auto; border: 0;"></td></tr>{{/if}}
+<tr><td style="padding: 16px 20px;">
+{{#if title}}<h3 style="margin: 0 0 8px;">{{title}}</h3>{{/if}}
+{{#if price}}<p style="margin: 0 0 8px; font-size: 1.2em; font-weight: bold;">{{price}}</p>{{/if}}
+{{{content}}}
+{{#if href}}<table role="presentation" border="0" cellpadding="0" cellspacing="0" style="margin-top: 12px;"><tr><td align="center" bgcolor="{{ color | default: "#3498db" }}" style="border-radius: 5px;"><a href="{{href}}" target="_blank" style="display: inline-block; padding: 10px 20px; color: #ffffff; text-decoration: none; border-radius: 5px;">{{ button | default: "購入する" }}</a></td></tr></table>{{/if}}
+</td></tr>
+</table>"##,
+    ),
+    (
+        "divider",
+        false,
+        r##"<table role="presentation" border="0" cellpadding="0" cellspacing="0" width="100%" style="margin: 24px 0;">
+<tr><td style="border-top: 1px solid {{ color | default: "#e9ecef" }}; font-size: 0; line-height: 0;">&nbsp;</td></tr>
+</table>"##,
+    ),
+];
+
+/// 組み込みのコンポーネント名（ユーザー定義のコンポーネントには使えない）
+pub fn is_reserved_name(name: &str) -> bool {
+    name == SNIPPET_DIRECTIVE || BUILTIN_COMPONENTS.iter().any(|(n, _, _)| *n == name)
+}
+
+/// 展開の結果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ComponentExpansion {
+    /// コンポーネントをHTMLに置き換えたマークダウン
+    pub markdown: String,
+    /// 不明なコンポーネントなどの問題（該当のディレクティブはそのまま残す）
+    pub errors: Vec<String>,
+}
+
+/// ユーザーが利用できるコンポーネントとスニペット
+///
+/// コンポーネントは属性と`{{{content}}}`（コンテナの中身）を埋め込むHTMLテンプレート、
+/// スニペットは名前で埋め込むマークダウン。組み込みコンポーネントは常に利用できる。
+#[derive(Debug, Clone, Default)]
+pub struct ComponentLibrary {
+    components: HashMap<String, String>,
+    snippets: HashMap<String, String>,
+}
+
+impl ComponentLibrary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_components(components: impl IntoIterator<Item = MarkdownComponent>) -> Self {
+        components
+            .into_iter()
+            .fold(
+                Self::new(),
+                |library, component| match ComponentKind::from(component.kind) {
+                    ComponentKind::Component => {
+                        library.with_component(&component.name, &component.content)
+                    }
+                    ComponentKind::Snippet => {
+                        library.with_snippet(&component.name, &component.content)
+                    }
+                },
+            )
+    }
+
+    pub fn with_component(mut self, name: &str, template: &str) -> Self {
+        self.components
+            .insert(name.to_string(), template.to_string());
+        self
+    }
+
+    pub fn with_snippet(mut self, name: &str, markdown: &str) -> Self {
+        self.snippets.insert(name.to_string(), markdown.to_string());
+        self
+    }
+
+    fn component_template(&self, name: &str) -> Option<&str> {
+        BUILTIN_COMPONENTS
+            .iter()
+            .find(|(n, _, _)| *n == name)
+            .map(|(_, _, template)| *template)
+            .or_else(|| self.components.get(name).map(String::as_str))
+    }
+
+    /// 中身を`:::`で囲めるディレクティブか（不明なコンポーネントは中身を残すため囲めるものとする）
+    fn is_container(&self, name: &str) -> bool {
+        if name == SNIPPET_DIRECTIVE {
+            return false;
+        }
+        if let Some((_, container, _)) = BUILTIN_COMPONENTS.iter().find(|(n, _, _)| *n == name) {
+            return *container;
+        }
+        self.components.get(name).is_none_or(|template| {
+            TemplateEngine::shared()
+                .analyze(template)
+                .is_ok_and(|variables| variables.variables.iter().any(|v| v == "content"))
+        })
+    }
+
+    /// マークダウン中のコンポーネントとスニペットを展開
+    pub fn expand(&self, markdown: &str) -> ComponentExpansion {
+        let mut errors = Vec::new();
+        let markdown = self.expand_markdown(markdown, &mut Vec::new(), &mut errors);
+        ComponentExpansion { markdown, errors }
+    }
+
+    fn expand_markdown(
+        &self,
+        markdown: &str,
+        snippets: &mut Vec<String>,
+        errors: &mut Vec<String>,
+    ) -> String {
+        let lines = parse_lines(markdown);
+        if lines.iter().all(|line| matches!(line, Line::Text(_))) {
+            return markdown.to_string();
+        }
+
+        let pairs = self.match_containers(&lines);
+        let mut output = Vec::new();
+        self.expand_lines(
+            &lines,
+            0,
+            lines.len(),
+            &pairs,
+            snippets,
+            errors,
+            &mut output,
+        );
+        output.join("\n")
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn expand_lines(
+        &self,
+        lines: &[Line],
+        start: usize,
+        end: usize,
+        pairs: &HashMap<usize, usize>,
+        snippets: &mut Vec<String>,
+        errors: &mut Vec<String>,
+        output: &mut Vec<String>,
+    ) {
+        let mut i = start;
+        while i < end {
+            match &lines[i] {
+                Line::Text(text) => output.push(text.to_string()),
+                Line::Close(raw) => {
+                    errors.push(format!("{}行目: 対応する開始のない「:::」です", i + 1));
+                    output.push(raw.to_string());
+                }
+                Line::Open(directive) => {
+                    let (body, next) = match pairs.get(&i) {
+                        Some(&close) => {
+                            let mut body = Vec::new();
+                            self.expand_lines(
+                                lines,
+                                i + 1,
+                                close,
+                                pairs,
+                                snippets,
+                                errors,
+                                &mut body,
+                            );
+                            (Some(body.join("\n")), close + 1)
+                        }
+                        None => (None, i + 1),
+                    };
+
+                    match self.render_directive(directive, body.as_deref(), i + 1, snippets, errors)
+                    {
+                        Some(rendered) => {
+                            output.push(String::new());
+                            output.push(rendered);
+                            output.push(String::new());
+                        }
+                        None => {
+                            // 展開できないディレクティブは元の記述のまま残す
+                            output.push(directive.raw.to_string());
+                            if let Some(body) = body {
+                                output.push(body);
+                                output.push(":::".to_string());
+                            }
+                        }
+                    }
+                    i = next;
+                    continue;
+                }
+            }
+            i += 1;
+        }
+    }
+
+    fn render_directive(
+        &self,
+        directive: &Directive,
+        body: Option<&str>,
+        line: usize,
+        snippets: &mut Vec<String>,
+        errors: &mut Vec<String>,
+    ) -> Option<String> {
+        if directive.name == SNIPPET_DIRECTIVE {
+            return self.render_snippet(directive, line, snippets, errors);
+        }
+
+        let Some(template) = self.component_template(&directive.name) else {
+            errors.push(format!(
+                "{line}行目: 不明なコンポーネント「{}」です",
+                directive.name
+            ));
+            return None;
+        };
+
+        let mut attributes = Map::new();
+        for (key, value) in &directive.attributes {
+            attributes.insert(key.clone(), Value::String(value.clone()));
+        }
+        let content = match (&directive.inline, body) {
+            (Some(inline), _) => escape_outside_tags(inline),
+            (None, Some(_)) => CONTENT_MARKER.to_string(),
+            (None, None) => String::new(),
+        };
+        attributes.insert("content".to_string(), Value::String(content));
+
+        let html =
+            match TemplateEngine::shared().render_component(template, &Value::Object(attributes)) {
+                Ok(html) => html,
+                Err(e) => {
+                    errors.push(format!(
+                        "{line}行目: コンポーネント「{}」の描画に失敗しました: {e}",
+                        directive.name
+                    ));
+                    return None;
+                }
+            };
+
+        // HTMLブロックとして扱われるよう、コメントで始まる1行にまとめる
+        let (open, close) = match (body, html.split_once(CONTENT_MARKER)) {
+            (Some(body), Some((open, close))) => (open.to_string(), Some((body, close))),
+            (Some(body), None) => (html.clone(), Some((body, ""))),
+            (None, _) => (html.clone(), None),
+        };
+        let name = &directive.name;
+        let mut rendered = format!("<!-- component:{name} -->{}", single_line(&open));
+        if let Some((body, close)) = close {
+            rendered.push_str(&format!(
+                "\n\n{body}\n\n<!-- /component:{name} -->{}",
+                single_line(close)
+            ));
+        }
+        Some(rendered)
+    }
+
+    fn render_snippet(
+        &self,
+        directive: &Directive,
+        line: usize,
+        snippets: &mut Vec<String>,
+        errors: &mut Vec<String>,
+    ) -> Option<String> {
+        let Some(name) = directive
+            .attributes
+            .iter()
+            .find(|(key, _)| key == "name")
+            .map(|(_, value)| value.as_str())
+            .or(directive.inline.as_deref())
+        else {
+            errors.push(format!(
+                "{line}行目: スニペット名（name）を指定してください"
+            ));
+            return None;
+        };
+
+        let Some(markdown) = self.snippets.get(name) else {
+            errors.push(format!("{line}行目: 不明なスニペット「{name}」です"));
+            return None;
+        };
+        if snippets.iter().any(|s| s == name) || snippets.len() >= MAX_SNIPPET_DEPTH {
+            errors.push(format!(
+                "{line}行目: スニペット「{name}」が循環しているか、入れ子が深すぎます"
+            ));
+            return None;
+        }
+
+        snippets.push(name.to_string());
+        let mut snippet_errors = Vec::new();
+        let expanded = self.expand_markdown(markdown, snippets, &mut snippet_errors);
+        snippets.pop();
+
+        errors.extend(
+            snippet_errors
+                .into_iter()
+                .map(|e| format!("スニペット「{name}」: {e}")),
+        );
+        Some(expanded)
+    }
+}
+
+impl ComponentLibrary {
+    /// コンテナの開始行と終了行（`:::`）の対応（同じ行に中身を書いたものは対象外）
+    fn match_containers(&self, lines: &[Line]) -> HashMap<usize, usize> {
+        let mut pairs = HashMap::new();
+        let mut stack = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            match line {
+                Line::Open(directive)
+                    if directive.inline.is_none() && self.is_container(&directive.name) =>
+                {
+                    stack.push(i)
+                }
+                Line::Close(_) => {
+                    if let Some(open) = stack.pop() {
+                        pairs.insert(open, i);
+                    }
+                }
+                _ => {}
+            }
+        }
+        pairs
+    }
+}
+
+fn single_line(html: &str) -> String {
+    html.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// ---------------------------------------------------------------------------
+// ディレクティブの構文
+// ---------------------------------------------------------------------------
+
+/// `:::name{key="value"} 中身`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Directive<'a> {
+    raw: &'a str,
+    name: String,
+    attributes: Vec<(String, String)>,
+    /// 同じ行に書いた中身（ボタンのラベルなど）
+    inline: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Line<'a> {
+    Text(&'a str),
+    Open(Directive<'a>),
+    Close(&'a str),
+}
+
+/// 行ごとにディレクティブを判定（コードブロックの中は対象外）
+fn parse_lines(markdown: &str) -> Vec<Line<'_>> {
+    let mut fence: Option<&str> = None;
+    markdown
+        .split('\n')
+        .map(|raw| {
+            let line = raw.trim_end_matches('\r');
+            let indent = line.len() - line.trim_start_matches(' ').len();
+            let trimmed = line.trim();
+
+            if let Some(marker) = fence {
+                if trimmed.starts_with(marker) {
+                    fence = None;
+                }
+                return Line::Text(raw);
+            }
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                fence = Some(&trimmed[..3]);
+                return Line::Text(raw);
+            }
+            if indent > 3 {
+                return Line::Text(raw);
+            }
+
+            if trimmed == ":::" {
+                return Line::Close(raw);
+            }
+            match trimmed
+                .strip_prefix(":::")
+                .and_then(|rest| parse_directive(raw, rest))
+            {
+                Some(directive) => Line::Open(directive),
+                None => Line::Text(raw),
+            }
+        })
+        .collect()
+}
+
+fn parse_directive<'a>(raw: &'a str, rest: &str) -> Option<Directive<'a>> {
+    let name_len = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+        .unwrap_or(rest.len());
+    if name_len == 0 || !rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let name = rest[..name_len].to_ascii_lowercase();
+    let mut rest = rest[name_len..].trim_start();
+
+    let mut attributes = Vec::new();
+    if let Some(attrs) = rest.strip_prefix('{') {
+        let end = find_closing_brace(attrs)?;
+        attributes = parse_attributes(&attrs[..end])?;
+        rest = attrs[end + 1..].trim();
+    }
+
+    let inline = (!rest.is_empty()).then(|| rest.to_string());
+    Some(Directive {
+        raw,
+        name,
+        attributes,
+        inline,
+    })
+}
+
+/// 引用符の外側で最初に現れる`}`の位置
+fn find_closing_brace(text: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '}' => return Some(i),
+            None => {}
+        }
+    }
+    None
+}
+
+/// `key="value" key='value' key=value flag`
+fn parse_attributes(text: &str) -> Option<Vec<(String, String)>> {
+    let mut attributes = Vec::new();
+    let mut rest = text.trim_start();
+
+    while !rest.is_empty() {
+        let key_len = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let key = &rest[..key_len];
+        if key.is_empty() {
+            return None;
+        }
+        rest = rest[key_len..].trim_start();
+
+        let value = match rest.strip_prefix('=') {
+            Some(value) => {
+                let value = value.trim_start();
+                match value.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let end = value[1..].find(quote)?;
+                        rest = &value[end + 2..];
+                        value[1..end + 1].to_string()
+                    }
+                    _ => {
+                        let end = value.find(char::is_whitespace).unwrap_or(value.len());
+                        rest = &value[end..];
+                        value[..end].to_string()
+                    }
+                }
+            }
+            None => "true".to_string(),
+        };
+
+        attributes.push((key.to_string(), value));
+        rest = rest.trim_start();
+    }
+
+    Some(attributes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_directive() {
+        let directive = parse_directive(
+            ":::button",
+            r#"button{href="https://example.com/?a=1" color='#000' wide} 今すぐ購入"#,
+        )
+        .unwrap();
+        assert_eq!(directive.name, "button");
+        assert_eq!(
+            directive.attributes,
+            vec![
+                ("href".to_string(), "https://example.com/?a=1".to_string()),
+                ("color".to_string(), "#000".to_string()),
+                ("wide".to_string(), "true".to_string()),
+            ]
+        );
+        assert_eq!(directive.inline.as_deref(), Some("今すぐ購入"));
+
+        assert!(parse_directive(":::", "{href=x}").is_none());
+        assert!(parse_directive(":::", r#"button{href="x}"#).is_none());
+    }
+
+    #[test]
+    fn test_expand_builtin_components() {
+        let library = ComponentLibrary::new();
+        let expansion = library.expand(
+            r##"# セール
+
+:::button{href="{{shop_url}}"} {{ name | default: "お客様" }}専用ページ
+
+:::callout{color="#e74c3c"}
+**期間限定**のお知らせです。
+:::
+
+```
+:::button{href="https://example.com"}
+```"##,
+        );
+
+        assert!(expansion.errors.is_empty(), "{:?}", expansion.errors);
+        let markdown = &expansion.markdown;
+        assert!(markdown.contains(r#"<a href="{{shop_url}}" target="_blank""#));
+        assert!(markdown.contains(r#">{{ name | default: "お客様" }}専用ページ</a>"#));
+        assert!(markdown.contains("border-left: 4px solid #e74c3c;"));
+        assert!(
+            markdown.contains("\n\n**期間限定**のお知らせです。\n\n<!-- /component:callout -->")
+        );
+        // コードブロックの中はそのまま
+        assert!(markdown.contains("```\n:::button{href=\"https://example.com\"}\n```"));
+    }
+
+    #[test]
+    fn test_expand_user_components_and_snippets() {
+        let library = ComponentLibrary::new()
+            .with_component(
+                "notice",
+                r#"<table><tr><td class="{{ level | default: "info" }}">{{{content}}}</td></tr></table>"#,
+            )
+            .with_snippet("footer", "---\n\n:::snippet{name=\"legal\"}")
+            .with_snippet("legal", "配信停止は[こちら]({{unsubscribe_url}})")
+            .with_snippet("loop", ":::snippet{name=\"loop\"}");
+
+        let expansion =
+            library.expand(":::notice{level=warn}\n本文\n:::\n\n:::snippet{name=\"footer\"}");
+        assert!(expansion.errors.is_empty(), "{:?}", expansion.errors);
+        assert!(expansion
+            .markdown
+            .contains(r#"<!-- component:notice --><table><tr><td class="warn">"#));
+        assert!(expansion
+            .markdown
+            .contains("配信停止は[こちら]({{unsubscribe_url}})"));
+
+        let expansion = library.expand(
+            ":::unknown{a=1}\n中身\n:::\n:::snippet{name=\"loop\"}\n:::snippet{name=\"none\"}\n:::",
+        );
+        assert_eq!(expansion.errors.len(), 4, "{:?}", expansion.errors);
+        assert!(expansion.errors[0].contains("不明なコンポーネント「unknown」"));
+        assert!(expansion.errors[1].contains("循環"));
+        assert!(expansion.errors[2].contains("不明なスニペット「none」"));
+        assert!(expansion.errors[3].contains("対応する開始のない"));
+        assert!(expansion.markdown.starts_with(":::unknown{a=1}\n中身\n:::"));
+    }
+
+    #[test]
+    fn test_markdown_without_directives_is_unchanged() {
+        let markdown = "# Title\r\n\n::: not a directive\n:::\n本文";
+        let expansion = ComponentLibrary::new().expand("# Title\r\n\n本文 :::button");
+        assert_eq!(expansion.markdown, "# Title\r\n\n本文 :::button");
+        assert!(ComponentLibrary::new().expand(markdown).errors.len() == 1);
+    }
+}
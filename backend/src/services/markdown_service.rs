@@ -1,185 +1,171 @@
 // マークダウン処理サービス
 
-use pulldown_cmark::{html, Options, Parser};
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
 use regex::Regex;
 use serde_json::Value;
 
-pub struct MarkdownService;
+use crate::services::{
+    email_html::{self, build_email_html},
+    markdown_components::ComponentLibrary,
+    template_engine::{TemplateEngine, TemplateVariables},
+};
+
+/// Webページに埋め込むリンク・画像で許可するURLスキーム
+const SAFE_URL_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+/// 許可していないスキーム（javascript: など）のURLを空にする
+///
+/// ブラウザはスキームの前後の空白・制御文字を無視するため、除いてから判定する。
+fn safe_url(url: CowStr<'_>) -> CowStr<'_> {
+    let normalized: String = url
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_control())
+        .collect();
+    let scheme = normalized
+        .split_once(':')
+        .map(|(scheme, _)| scheme)
+        .filter(|scheme| !scheme.contains(['/', '?', '#']));
+    match scheme {
+        Some(scheme) if !SAFE_URL_SCHEMES.contains(&scheme.to_ascii_lowercase().as_str()) => {
+            CowStr::Borrowed("")
+        }
+        _ => url,
+    }
+}
+
+/// マークダウンサービス（組み込みコンポーネントのほか、渡されたユーザー定義のコンポーネントを展開する）
+pub struct MarkdownService {
+    components: ComponentLibrary,
+}
 
 impl Default for MarkdownService {
     fn default() -> Self {
-        Self
+        Self::new()
     }
 }
 
 impl MarkdownService {
     pub fn new() -> Self {
-        Self
+        Self::with_components(ComponentLibrary::new())
     }
 
-    /// マークダウンをHTMLに変換
-    pub fn render_to_html(&self, markdown: &str) -> String {
+    pub fn with_components(components: ComponentLibrary) -> Self {
+        Self { components }
+    }
+
+    fn parser_options() -> Options {
         let mut options = Options::empty();
         options.insert(Options::ENABLE_STRIKETHROUGH);
         options.insert(Options::ENABLE_TABLES);
         options.insert(Options::ENABLE_FOOTNOTES);
         options.insert(Options::ENABLE_TASKLISTS);
         options.insert(Options::ENABLE_SMART_PUNCTUATION);
+        options
+    }
 
-        let parser = Parser::new_ext(markdown, options);
+    /// マークダウンをメール用のHTMLに変換
+    pub fn render_to_html(&self, markdown: &str) -> String {
+        let expanded = self.components.expand(markdown).markdown;
+        self.markdown_to_html(&expanded, None)
+    }
+
+    /// コンポーネントを展開済みのマークダウンをメール用のHTMLに変換
+    fn markdown_to_html(&self, markdown: &str, preheader: Option<&str>) -> String {
+        let parser = Parser::new_ext(markdown, Self::parser_options());
         let mut html_output = String::new();
         html::push_html(&mut html_output, parser);
 
         // メール用のスタイリングを追加
-        self.add_email_styles(&html_output)
+        build_email_html(&html_output, preheader)
     }
 
-    /// テンプレート変数を置換
+    /// マークダウンをWebページに埋め込むHTML断片に変換（メール用のスタイルは付けず、生のHTMLはエスケープする）
+    ///
+    /// リンク・画像のURLはhttp・https・mailto（と相対URL）以外を空にする。
+    pub fn render_fragment(&self, markdown: &str) -> String {
+        let parser = Parser::new_ext(markdown, Self::parser_options()).map(|event| match event {
+            Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => Event::Start(Tag::Link {
+                link_type,
+                dest_url: safe_url(dest_url),
+                title,
+                id,
+            }),
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => Event::Start(Tag::Image {
+                link_type,
+                dest_url: safe_url(dest_url),
+                title,
+                id,
+            }),
+            event => event,
+        });
+        let mut html_output = String::new();
+        html::push_html(&mut html_output, parser);
+        html_output
+    }
+
+    /// コンポーネントとテンプレート変数を展開してメール用のHTMLに変換
+    ///
+    /// 変数`preheader`があれば、受信一覧で件名の後に表示されるプリヘッダーにする。
+    /// HTMLがGmailのサイズ上限を超える場合はエラー。
     pub fn render_with_variables(
         &self,
         markdown: &str,
         variables: &Value,
     ) -> Result<String, String> {
-        let substituted_markdown = self.substitute_variables(markdown, variables)?;
-        Ok(self.render_to_html(&substituted_markdown))
+        let engine = TemplateEngine::shared();
+        let expanded = self.components.expand(markdown).markdown;
+        let rendered_markdown = engine.render_markdown(&expanded, variables)?;
+        let preheader = match variables.get("preheader").and_then(Value::as_str) {
+            Some(preheader) => Some(engine.render_text(preheader, variables)?),
+            None => None,
+        };
+
+        let html = self.markdown_to_html(&rendered_markdown, preheader.as_deref());
+        email_html::check_size(&html)?;
+        Ok(html)
     }
 
-    /// テンプレート変数の置換
-    fn substitute_variables(&self, content: &str, variables: &Value) -> Result<String, String> {
-        let re = Regex::new(r"\{\{\s*(\w+)\s*\}\}").map_err(|e| format!("正規表現エラー: {e}"))?;
-
-        let mut result = content.to_string();
-
-        for cap in re.captures_iter(content) {
-            let variable_name = &cap[1];
-            let placeholder = &cap[0];
-
-            if let Some(value) = variables.get(variable_name) {
-                let replacement = match value {
-                    Value::String(s) => s.clone(),
-                    Value::Number(n) => n.to_string(),
-                    Value::Bool(b) => b.to_string(),
-                    _ => value.to_string(),
-                };
-                result = result.replace(placeholder, &replacement);
-            } else {
-                return Err(format!("変数 '{variable_name}' が見つかりません"));
-            }
-        }
-
-        Ok(result)
-    }
-
-    /// テンプレートから変数を抽出
+    /// テンプレートから変数を抽出（構文が正しくない場合は空）
     pub fn extract_variables(&self, content: &str) -> Vec<String> {
-        let re = Regex::new(r"\{\{\s*(\w+)\s*\}\}").unwrap();
-        let mut variables = Vec::new();
-        let mut seen = std::collections::HashSet::new();
-
-        for cap in re.captures_iter(content) {
-            let variable_name = cap[1].to_string();
-            if seen.insert(variable_name.clone()) {
-                variables.push(variable_name);
-            }
-        }
-
-        variables
+        self.analyze_variables(content).variables
     }
 
-    /// メール用のスタイリングを追加
-    fn add_email_styles(&self, html: &str) -> String {
-        let email_css = r#"
-<style>
-    body {
-        font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, 'Helvetica Neue', Arial, sans-serif;
-        line-height: 1.6;
-        color: #333;
-        max-width: 600px;
-        margin: 0 auto;
-        padding: 20px;
-    }
-    h1, h2, h3, h4, h5, h6 {
-        color: #2c3e50;
-        margin-top: 30px;
-        margin-bottom: 15px;
-    }
-    h1 { font-size: 2.2em; border-bottom: 2px solid #3498db; padding-bottom: 10px; }
-    h2 { font-size: 1.8em; border-bottom: 1px solid #ecf0f1; padding-bottom: 8px; }
-    h3 { font-size: 1.4em; }
-    p { margin-bottom: 16px; }
-    a { color: #3498db; text-decoration: none; }
-    a:hover { text-decoration: underline; }
-    blockquote {
-        border-left: 4px solid #3498db;
-        margin: 20px 0;
-        padding: 10px 20px;
-        background-color: #f8f9fa;
-        font-style: italic;
-    }
-    code {
-        background-color: #f1f2f6;
-        padding: 2px 6px;
-        border-radius: 3px;
-        font-family: 'SF Mono', Monaco, 'Cascadia Code', 'Roboto Mono', Consolas, 'Courier New', monospace;
-        font-size: 0.9em;
-    }
-    pre {
-        background-color: #f8f9fa;
-        border: 1px solid #e9ecef;
-        border-radius: 5px;
-        padding: 15px;
-        overflow-x: auto;
-        margin: 20px 0;
-    }
-    pre code {
-        background-color: transparent;
-        padding: 0;
-    }
-    table {
-        border-collapse: collapse;
-        width: 100%;
-        margin: 20px 0;
-    }
-    th, td {
-        border: 1px solid #ddd;
-        padding: 12px;
-        text-align: left;
-    }
-    th {
-        background-color: #f2f2f2;
-        font-weight: bold;
-    }
-    ul, ol {
-        margin: 16px 0;
-        padding-left: 30px;
+    /// テンプレートで使用している変数と、値がなくても描画できる変数を抽出（スニペット内の変数を含む）
+    pub fn analyze_variables(&self, content: &str) -> TemplateVariables {
+        TemplateEngine::shared()
+            .analyze(&self.components.expand(content).markdown)
+            .unwrap_or_default()
     }
-    li {
-        margin-bottom: 8px;
-    }
-    .button {
-        display: inline-block;
-        padding: 12px 24px;
-        background-color: #3498db;
-        color: white !important;
-        text-decoration: none;
-        border-radius: 5px;
-        margin: 10px 0;
-    }
-    .button:hover {
-        background-color: #2980b9;
-        text-decoration: none;
+
+    /// 本文と件名のテンプレートで使用している変数を抽出
+    pub fn analyze_template(&self, markdown: &str, subject: &str) -> TemplateVariables {
+        let mut variables = self.analyze_variables(markdown);
+        variables.merge(&self.analyze_variables(subject));
+        variables
     }
-</style>
-"#;
 
-        format!(
-            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"UTF-8\">\n<meta name=\"viewport\" \
-             content=\"width=device-width, \
-             initial-scale=1.0\">\n{email_css}\n</head>\n<body>\n{html}\n</body>\n</html>"
-        )
+    /// 本文と件名のテンプレート構文を検証
+    pub fn validate_template(&self, markdown: &str, subject: &str) -> Result<(), String> {
+        let engine = TemplateEngine::shared();
+        engine
+            .validate(&self.components.expand(markdown).markdown)
+            .map_err(|e| format!("本文: {e}"))?;
+        engine.validate(subject).map_err(|e| format!("件名: {e}"))
     }
 
     /// マークダウンの構文チェック
+    #[allow(clippy::manual_is_multiple_of)]
     pub fn validate_markdown(&self, markdown: &str) -> Result<Vec<String>, String> {
         let mut errors = Vec::new();
 
@@ -194,6 +180,18 @@ impl MarkdownService {
             errors.push("コードブロックが正しく閉じられていません".to_string());
         }
 
+        // コンポーネントとテンプレート構文のチェック
+        let expansion = self.components.expand(markdown);
+        errors.extend(expansion.errors);
+        if let Err(e) = TemplateEngine::shared().validate(&expansion.markdown) {
+            errors.push(e);
+        }
+
+        // メール本文のサイズ
+        if let Err(e) = email_html::check_size(&self.markdown_to_html(&expansion.markdown, None)) {
+            errors.push(e);
+        }
+
         // リンクの構文チェック
         let link_regex =
             Regex::new(r"\[([^\]]*)\]\(([^\)]*)\)").map_err(|e| format!("正規表現エラー: {e}"))?;
@@ -214,7 +212,7 @@ impl MarkdownService {
         subject_template: &str,
         variables: &Value,
     ) -> Result<String, String> {
-        self.substitute_variables(subject_template, variables)
+        TemplateEngine::shared().render_text(subject_template, variables)
     }
 }
 
@@ -231,7 +229,7 @@ mod tests {
         let markdown = "# Hello\n\nThis is a **test**.";
         let html = service.render_to_html(markdown);
         assert!(
-            html.contains("<h1>Hello</h1>"),
+            html.contains(">Hello</h1>"),
             "HTML should contain h1 header"
         );
         assert!(
@@ -266,8 +264,8 @@ Code block
 "#;
 
         let html = service.render_to_html(complex_markdown);
-        assert!(html.contains("<h1>Header 1</h1>"), "HTML should contain h1");
-        assert!(html.contains("<h2>Header 2</h2>"), "HTML should contain h2");
+        assert!(html.contains(">Header 1</h1>"), "HTML should contain h1");
+        assert!(html.contains(">Header 2</h2>"), "HTML should contain h2");
         assert!(
             html.contains("<em>Italic</em>"),
             "HTML should contain em tag"
@@ -277,22 +275,59 @@ Code block
             "HTML should contain strong tag"
         );
         assert!(
-            html.contains("<li>List item"),
+            html.contains(">List item 1</li>"),
             "HTML should contain list items"
         );
         assert!(
-            html.contains("<a href=\"https://example.com\">"),
+            html.contains("<a href=\"https://example.com\" style=\""),
             "HTML should contain link"
         );
         assert!(
-            html.contains("<blockquote>"),
+            html.contains("<blockquote style=\""),
             "HTML should contain blockquote"
         );
         assert!(
-            html.contains("<pre><code>"),
+            html.contains("<pre style=\""),
             "HTML should contain code block"
         );
-        assert!(html.contains("<table>"), "HTML should contain table");
+        assert!(
+            html.contains("<table class=\"markdown-table\""),
+            "HTML should contain table"
+        );
+    }
+
+    #[test]
+    fn test_render_fragment_escapes_raw_html() {
+        let service = MarkdownService::new();
+
+        let html = service.render_fragment(
+            "# お問い合わせ\n\n<script>alert(1)</script>\n\n**必須**の項目<b>です</b>",
+        );
+        assert!(html.contains("<h1>お問い合わせ</h1>"));
+        assert!(html.contains("<strong>必須</strong>"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(html.contains("&lt;b&gt;"));
+        assert!(!html.contains("<style>"));
+    }
+
+    #[test]
+    fn test_render_fragment_drops_unsafe_urls() {
+        let service = MarkdownService::new();
+
+        let html = service.render_fragment(
+            "[詳細](https://example.com/a) [連絡](mailto:info@example.com) [相対](/terms#a:b)\n\n\
+             [x](javascript:alert(1)) [y](<JavaScript:alert(2)>) [z](&#106;avascript:alert(3)) \
+             [w](data:text/html,hi) ![画像](javascript:alert(4)) ![ok](https://example.com/a.png)",
+        );
+        assert!(html.contains(r#"<a href="https://example.com/a">詳細</a>"#));
+        assert!(html.contains(r#"<a href="mailto:info@example.com">連絡</a>"#));
+        assert!(html.contains(r#"<a href="/terms#a:b">相対</a>"#));
+        assert!(html.contains(r#"<img src="https://example.com/a.png" alt="ok" />"#));
+        assert!(!html.to_lowercase().contains("javascript"));
+        assert!(!html.contains("data:"));
+        assert!(html.contains(r#"<a href="">x</a>"#));
+        assert!(html.contains(r#"<img src="" alt="画像" />"#));
     }
 
     #[test]
@@ -340,7 +375,7 @@ Code block
             "company": "MarkMail"
         });
 
-        let result = service.substitute_variables(template, &variables).unwrap();
+        let result = service.render_subject(template, &variables).unwrap();
         assert_eq!(result, "Hello John, welcome to MarkMail!");
 
         // Test with different variable types
@@ -351,7 +386,7 @@ Code block
             "is_premium": true
         });
 
-        let result = service.substitute_variables(template, &variables).unwrap();
+        let result = service.render_subject(template, &variables).unwrap();
         assert_eq!(result, "User Alice has 30 years and premium status: true.");
 
         // Test with missing variable
@@ -361,8 +396,55 @@ Code block
             // missing "score"
         });
 
-        let result = service.substitute_variables(template, &variables);
-        assert!(result.is_err(), "Should error on missing variable");
+        let result = service.render_subject(template, &variables).unwrap();
+        assert_eq!(result, "Hello Bob, your score is .");
+
+        // Test with default value and HTML escaping in the body
+        let template = "{{ name | default: \"お客様\" }}へ: {{note}}";
+        let variables = json!({ "note": "<b>重要</b>" });
+
+        let result = service.render_with_variables(template, &variables).unwrap();
+        assert!(result.contains("お客様へ: &lt;b&gt;重要&lt;/b&gt;"));
+    }
+
+    #[test]
+    fn test_render_components_and_snippets() {
+        let service = MarkdownService::with_components(
+            ComponentLibrary::new()
+                .with_snippet("footer", "配信停止は[こちら]({{unsubscribe_url}})"),
+        );
+        let markdown = r#":::callout
+{{name}}様、**セール**開催中です。
+:::
+
+:::button{href="{{shop_url}}"} ショップへ
+
+:::snippet{name="footer"}"#;
+
+        let html = service
+            .render_with_variables(
+                markdown,
+                &json!({
+                    "name": "<山田>",
+                    "shop_url": "https://example.com/?a=1&b=2",
+                    "unsubscribe_url": "https://example.com/unsubscribe"
+                }),
+            )
+            .unwrap();
+        assert!(html.contains("<!-- component:callout --><table role=\"presentation\""));
+        assert!(html.contains(">&lt;山田&gt;様、<strong>セール</strong>開催中です。</p>"));
+        assert!(html.contains(r#"href="https://example.com/?a=1&amp;b=2""#));
+        assert!(html.contains("ショップへ</a>"));
+        assert!(html.contains(r#"<a href="https://example.com/unsubscribe" style="#));
+
+        let variables = service.extract_variables(markdown);
+        assert_eq!(variables, vec!["name", "shop_url", "unsubscribe_url"]);
+
+        let errors = service
+            .validate_markdown(":::product-card{title=\"新商品\"}\n説明\n:::\n\n:::banner\n:::")
+            .unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("不明なコンポーネント「banner」"));
     }
 
     #[test]
@@ -397,4 +479,25 @@ Code block
         let validation = service.validate_markdown(empty_url).unwrap();
         assert_eq!(validation.len(), 1, "Should have one error for empty URL");
     }
+
+    #[test]
+    fn test_render_with_preheader_and_size_limit() {
+        let service = MarkdownService::new();
+
+        let html = service
+            .render_with_variables(
+                "# {{name}}様へ\n\n本文です。",
+                &json!({"name": "山田", "preheader": "{{name}}様だけのご案内"}),
+            )
+            .unwrap();
+        assert!(html.contains("山田様だけのご案内"));
+        assert!(!email_html::html_to_text(&html).contains("ご案内"));
+
+        let long_markdown = "長い本文の段落です。\n\n".repeat(3000);
+        assert!(service
+            .render_with_variables(&long_markdown, &json!({}))
+            .unwrap_err()
+            .contains("上限"));
+        assert_eq!(service.validate_markdown(&long_markdown).unwrap().len(), 1);
+    }
 }
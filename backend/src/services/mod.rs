@@ -1,11 +1,28 @@
+pub mod ab_test_service;
 pub mod ai_usage_service;
 pub mod auth_service;
 pub mod campaign_service;
 pub mod crm_service;
+pub mod dns_resolver;
+pub mod email_html;
 pub mod email_service;
+pub mod form_page_service;
+pub mod form_service;
+pub mod github_client;
+pub mod github_service;
+pub mod markdown_component_service;
+pub mod markdown_components;
 pub mod markdown_service;
+pub mod rate_limiter;
+pub mod segment_service;
+pub mod sending_domain_service;
 pub mod sequence_service;
+pub mod ses_feedback_service;
 pub mod stripe_service;
 pub mod subscriber_service;
 pub mod subscription_service;
+pub mod template_engine;
+pub mod template_revision_service;
 pub mod template_service;
+pub mod tracking_service;
+pub mod unsubscribe_service;
@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use chrono::Utc;
+
+/// Redisの応答を待つ上限（Redisの障害で送信自体を止めないようにする）
+const REDIS_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// 固定ウィンドウの回数制限
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub max_requests: u64,
+    pub window_secs: u64,
+}
+
+impl RateLimit {
+    pub const fn new(max_requests: u64, window_secs: u64) -> Self {
+        Self {
+            max_requests,
+            window_secs,
+        }
+    }
+
+    /// 環境変数（`回数/秒数`形式、例: `10/60`）から読み込む
+    pub fn from_env(name: &str, default: RateLimit) -> RateLimit {
+        std::env::var(name)
+            .ok()
+            .and_then(|value| Self::parse(&value))
+            .unwrap_or(default)
+    }
+
+    fn parse(value: &str) -> Option<RateLimit> {
+        let (max_requests, window_secs) = value.split_once('/')?;
+        let limit = RateLimit::new(
+            max_requests.trim().parse().ok()?,
+            window_secs.trim().parse().ok()?,
+        );
+        (limit.window_secs > 0).then_some(limit)
+    }
+
+    /// 現在のウィンドウのキーと、次のウィンドウまでの秒数
+    fn window(&self, key: &str, now: u64) -> (String, u64) {
+        let window_secs = self.window_secs.max(1);
+        let start = now / window_secs * window_secs;
+        (
+            format!("rate_limit:{key}:{start}"),
+            start + window_secs - now,
+        )
+    }
+}
+
+/// Redisで回数を数える回数制限
+pub struct RateLimiter {
+    client: redis::Client,
+}
+
+impl RateLimiter {
+    pub fn new(client: redis::Client) -> Self {
+        Self { client }
+    }
+
+    /// 回数を数え、上限を超えた場合は再試行できるまでの秒数を返す
+    pub async fn hit(&self, key: &str, limit: RateLimit) -> Result<Option<u64>, String> {
+        let now = Utc::now().timestamp().max(0) as u64;
+        let (window_key, retry_after) = limit.window(key, now);
+
+        let count = tokio::time::timeout(REDIS_TIMEOUT, async {
+            let mut conn = self.client.get_multiplexed_async_connection().await?;
+            let (count,): (u64,) = redis::pipe()
+                .atomic()
+                .incr(&window_key, 1)
+                .expire(&window_key, limit.window_secs.max(1) as i64)
+                .ignore()
+                .query_async(&mut conn)
+                .await?;
+            Ok::<_, redis::RedisError>(count)
+        })
+        .await
+        .map_err(|_| "Redisの応答がタイムアウトしました".to_string())?
+        .map_err(|e| format!("Redisでの回数制限に失敗しました: {e}"))?;
+
+        Ok((count > limit.max_requests).then_some(retry_after))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_window() {
+        assert_eq!(RateLimit::parse("10/60"), Some(RateLimit::new(10, 60)));
+        assert_eq!(RateLimit::parse(" 5 / 1 "), Some(RateLimit::new(5, 1)));
+        assert_eq!(RateLimit::parse("10/0"), None);
+        assert_eq!(RateLimit::parse("10"), None);
+
+        let limit = RateLimit::new(10, 60);
+        assert_eq!(
+            limit.window("form:ip:203.0.113.7", 1_000_010),
+            ("rate_limit:form:ip:203.0.113.7:999960".to_string(), 10)
+        );
+        assert_eq!(limit.window("k", 1_000_020).0, "rate_limit:k:1000020");
+    }
+}
@@ -0,0 +1,173 @@
+use sqlx::PgPool;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{
+    database::{campaigns, segments, subscribers},
+    models::{
+        segment::{CreateSegmentRequest, Segment, SegmentRule, UpdateSegmentRequest},
+        subscriber::Subscriber,
+    },
+};
+
+/// セグメント操作のエラー
+#[derive(Error, Debug)]
+pub enum SegmentError {
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error("セグメントが見つかりません")]
+    NotFound,
+
+    #[error("{0}")]
+    InUse(String),
+
+    #[error("{0}")]
+    Database(String),
+}
+
+pub struct SegmentService;
+
+impl Default for SegmentService {
+    fn default() -> Self {
+        Self
+    }
+}
+
+impl SegmentService {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// セグメントを作成
+    pub async fn create_segment(
+        &self,
+        pool: &PgPool,
+        user_id: Uuid,
+        request: &CreateSegmentRequest,
+    ) -> Result<Segment, SegmentError> {
+        self.validate_rules(pool, user_id, &request.rules).await?;
+
+        segments::create_segment(pool, user_id, request)
+            .await
+            .map_err(|e| SegmentError::Database(format!("セグメントの作成に失敗しました: {e}")))
+    }
+
+    /// セグメントを更新
+    pub async fn update_segment(
+        &self,
+        pool: &PgPool,
+        segment_id: Uuid,
+        user_id: Uuid,
+        request: &UpdateSegmentRequest,
+    ) -> Result<Segment, SegmentError> {
+        if let Some(rules) = &request.rules {
+            self.validate_rules(pool, user_id, rules).await?;
+        }
+
+        segments::update_segment(pool, segment_id, user_id, request)
+            .await
+            .map_err(|e| SegmentError::Database(format!("セグメントの更新に失敗しました: {e}")))?
+            .ok_or(SegmentError::NotFound)
+    }
+
+    /// セグメントを削除（キャンペーンやシーケンスから参照されている場合は削除できない）
+    pub async fn delete_segment(
+        &self,
+        pool: &PgPool,
+        segment_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<(), SegmentError> {
+        let (campaign_count, sequence_count) =
+            segments::count_segment_references(pool, segment_id, user_id)
+                .await
+                .map_err(|e| {
+                    SegmentError::Database(format!("セグメントの参照の確認に失敗しました: {e}"))
+                })?;
+
+        if campaign_count > 0 || sequence_count > 0 {
+            return Err(SegmentError::InUse(format!(
+                "このセグメントは{campaign_count}件のキャンペーンと{sequence_count}件のシーケンスで使用されているため削除できません"
+            )));
+        }
+
+        let deleted = segments::delete_segment(pool, segment_id, user_id)
+            .await
+            .map_err(|e| SegmentError::Database(format!("セグメントの削除に失敗しました: {e}")))?;
+
+        if deleted {
+            Ok(())
+        } else {
+            Err(SegmentError::NotFound)
+        }
+    }
+
+    /// セグメントを取得
+    pub async fn get_segment(
+        &self,
+        pool: &PgPool,
+        segment_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Segment, SegmentError> {
+        segments::find_segment_by_id(pool, segment_id, user_id)
+            .await
+            .map_err(|e| SegmentError::Database(format!("セグメントの取得に失敗しました: {e}")))?
+            .ok_or(SegmentError::NotFound)
+    }
+
+    /// セグメントに一致する購読者数を取得
+    pub async fn count_subscribers(
+        &self,
+        pool: &PgPool,
+        user_id: Uuid,
+        rules: &SegmentRule,
+    ) -> Result<i64, SegmentError> {
+        subscribers::count_segment_subscribers(pool, user_id, rules)
+            .await
+            .map_err(|e| SegmentError::Database(format!("購読者数の取得に失敗しました: {e}")))
+    }
+
+    /// セグメントに一致する購読者と総数を取得
+    pub async fn list_subscribers(
+        &self,
+        pool: &PgPool,
+        user_id: Uuid,
+        rules: &SegmentRule,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<Subscriber>, i64), SegmentError> {
+        let members =
+            subscribers::list_segment_subscribers(pool, user_id, rules, Some(limit), Some(offset))
+                .await
+                .map_err(|e| SegmentError::Database(format!("購読者の取得に失敗しました: {e}")))?;
+        let total = self.count_subscribers(pool, user_id, rules).await?;
+
+        Ok((members, total))
+    }
+
+    /// 条件ツリーを検証（参照するキャンペーンがユーザーのものであることも確認する）
+    pub async fn validate_rules(
+        &self,
+        pool: &PgPool,
+        user_id: Uuid,
+        rules: &SegmentRule,
+    ) -> Result<(), SegmentError> {
+        rules.validate().map_err(SegmentError::Invalid)?;
+
+        for campaign_id in rules.referenced_campaign_ids() {
+            let campaign = campaigns::find_campaign_by_id(pool, campaign_id, user_id)
+                .await
+                .map_err(|e| {
+                    SegmentError::Database(format!("キャンペーンの確認に失敗しました: {e}"))
+                })?;
+
+            if campaign.is_none() {
+                return Err(SegmentError::Invalid(format!(
+                    "条件で指定されたキャンペーン（{campaign_id}）が見つかりません"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
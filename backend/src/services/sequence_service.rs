@@ -1,23 +1,193 @@
-use chrono::{DateTime, Duration, Utc};
+use std::collections::HashSet;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use futures::future::join_all;
+use serde::Serialize;
 use serde_json::{json, Value};
 use sqlx::PgPool;
+use thiserror::Error;
+use tokio::sync::{OnceCell, Semaphore};
 use uuid::Uuid;
 
 use crate::{
-    database::{sequences, subscribers, templates},
+    database::{
+        campaigns, forms, segments, sequences, subscribers, template_revisions, templates, tracking,
+    },
     models::{
         sequence::{
-            CreateSequenceEnrollmentRequest, Sequence, SequenceEnrollment, SequenceStep,
-            SequenceStepLog, TriggerType,
+            CreateSequenceEnrollmentRequest, CreateSequenceStepRequest, EnrollSubscribersRequest,
+            EnrollSubscribersResult, EnrollmentStatus, ReentryPolicy, Sequence, SequenceEnrollment,
+            SequenceStatus, SequenceStep, SequenceStepLog, StepGraph, StepType, TriggerType,
+            UpdateSequenceStepRequest,
         },
-        subscriber::Subscriber,
+        sequence_condition::{ConditionContext, StepCondition},
+        sequence_exit::{ExitCondition, ExitRule},
+        sequence_schedule::{next_execution_at, SendWindow, WaitUntil},
+        sequence_trigger::{month_days_for, parse_field_date, TriggerConfig},
+        subscriber::{Subscriber, SubscriberStatus},
+        tracking::TrackingEventType,
     },
     services::{
+        email_html,
         email_service::{EmailMessage, EmailService},
-        markdown_service::MarkdownService,
+        markdown_component_service::MarkdownComponentService,
+        template_engine::TemplateEngine,
+        unsubscribe_service::UnsubscribeService,
     },
 };
 
+/// セグメントトリガーで1シーケンスあたり1回に登録する購読者の上限
+const SEGMENT_ENROLLMENT_BATCH_SIZE: i64 = 500;
+
+/// 日付トリガーで1シーケンスあたり1回に登録する購読者の上限
+const DATE_ENROLLMENT_BATCH_SIZE: i64 = 500;
+
+/// シーケンスのステップの変更内容（有効なシーケンスの編集時の検証に使う）
+pub enum StepChange<'a> {
+    Create(&'a CreateSequenceStepRequest),
+    Update(Uuid, &'a UpdateSequenceStepRequest),
+    Delete(Uuid),
+}
+
+/// シーケンスメールの送信に使うメールサービス
+///
+/// 最初のメール送信時に初期化し、以降は同じインスタンスを共有する。
+/// メールステップを実行するまでは初期化しないため、送信設定がなくても他のステップは実行できる。
+#[derive(Default)]
+pub struct SharedEmailService(OnceCell<EmailService>);
+
+impl SharedEmailService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    async fn get(&self, pool: &PgPool) -> Result<&EmailService, String> {
+        self.0
+            .get_or_try_init(|| async {
+                EmailService::new(pool.clone())
+                    .await
+                    .map_err(|e| format!("メールサービスの初期化に失敗しました: {e}"))
+            })
+            .await
+    }
+}
+
+/// 失敗したステップを再実行するまでの最初の待機時間（秒）
+const STEP_RETRY_BASE_SECONDS: i64 = 60;
+/// 失敗したステップを再実行するまでの最大の待機時間（秒）
+const STEP_RETRY_MAX_SECONDS: i64 = 6 * 60 * 60;
+
+/// ステップの連続した失敗の上限（達した場合はエンロールメントを失敗として終了する）
+const STEP_MAX_FAILURES: i64 = 10;
+
+/// 連続した失敗回数に応じた再実行までの待機時間（1分から倍々に増やし、最大6時間）
+pub fn step_retry_delay(failures: i64) -> Duration {
+    let exponent = failures.clamp(1, 20) - 1;
+    Duration::seconds(
+        STEP_RETRY_BASE_SECONDS
+            .saturating_mul(1 << exponent)
+            .min(STEP_RETRY_MAX_SECONDS),
+    )
+}
+
+/// ステップの実行エラー
+#[derive(Error, Debug)]
+enum StepError {
+    /// 時間をおいて再実行する（データベースや送信サービスの一時的なエラーなど）
+    #[error("{0}")]
+    Retryable(String),
+    /// 再実行しても成功しない（テンプレートの削除やメールの拒否など）
+    #[error("{0}")]
+    Permanent(String),
+}
+
+impl StepError {
+    /// 失敗回数を含め、ステップの再実行を打ち切るか
+    fn should_abandon(&self, failures: i64) -> bool {
+        matches!(self, StepError::Permanent(_)) || failures >= STEP_MAX_FAILURES
+    }
+}
+
+impl From<String> for StepError {
+    fn from(message: String) -> Self {
+        StepError::Retryable(message)
+    }
+}
+
+/// 実行待ちステップのバッチ処理の設定
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepBatchOptions {
+    /// 1回に確保するエンロールメントの数
+    pub batch_size: i64,
+    /// 同時に実行するステップの数
+    pub concurrency: usize,
+    /// 1回の確保でユーザーごとに割り当てるエンロールメントの上限（特定のユーザーが処理を占有しないようにする）
+    pub per_user_limit: i64,
+    /// 確保したまま解放されないエンロールメントを再度確保するまでの秒数
+    pub stale_after_seconds: i64,
+}
+
+impl Default for StepBatchOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: 100,
+            concurrency: 10,
+            per_user_limit: 20,
+            stale_after_seconds: 600,
+        }
+    }
+}
+
+impl StepBatchOptions {
+    /// 環境変数から設定を読み込む（未設定・不正な値は既定値）
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        let default = Self::default();
+        Self {
+            batch_size: var("SEQUENCE_WORKER_BATCH_SIZE", default.batch_size),
+            concurrency: var("SEQUENCE_WORKER_CONCURRENCY", default.concurrency),
+            per_user_limit: var("SEQUENCE_WORKER_PER_USER_LIMIT", default.per_user_limit),
+            stale_after_seconds: default.stale_after_seconds,
+        }
+    }
+}
+
+/// 実行待ちステップの処理結果
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct StepBatchStats {
+    /// 確保したエンロールメントの数
+    pub claimed: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// 実行日時から実行までの遅延の最大（ミリ秒）
+    pub max_lag_ms: i64,
+    /// 実行日時から実行までの遅延の合計（ミリ秒）
+    pub total_lag_ms: i64,
+}
+
+impl StepBatchStats {
+    pub fn merge(&mut self, other: &StepBatchStats) {
+        self.claimed += other.claimed;
+        self.succeeded += other.succeeded;
+        self.failed += other.failed;
+        self.max_lag_ms = self.max_lag_ms.max(other.max_lag_ms);
+        self.total_lag_ms += other.total_lag_ms;
+    }
+
+    /// 実行日時から実行までの遅延の平均（ミリ秒）
+    pub fn average_lag_ms(&self) -> i64 {
+        self.total_lag_ms
+            .checked_div(self.claimed as i64)
+            .unwrap_or(0)
+    }
+}
+
 pub struct SequenceService;
 
 impl Default for SequenceService {
@@ -45,40 +215,902 @@ impl SequenceService {
             .await
             .map_err(|e| format!("シーケンスの取得に失敗しました: {e}"))?;
 
-        let mut enrollments = Vec::new();
+        let mut enrollments = Vec::new();
+
+        for sequence in sequences {
+            // トリガー条件を評価（設定が不正なシーケンスには登録しない）
+            match self.evaluate_trigger_conditions(&sequence, trigger_data.as_ref()) {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(e) => {
+                    tracing::warn!("Sequence {} has invalid trigger_config: {}", sequence.id, e);
+                    continue;
+                }
+            }
+
+            // 登録中の購読者や、再登録ポリシーで再登録できない購読者は登録しない
+            let statuses = sequences::find_enrollment_statuses(pool, sequence.id, &[subscriber_id])
+                .await
+                .map_err(|e| format!("エンロールメントの確認に失敗しました: {e}"))?;
+            let previous = statuses.first().map(|(_, status)| status.as_str());
+            if enrollment_skip(&sequence, previous).is_some() {
+                continue;
+            }
+
+            // エンロールメントを作成
+            let request = CreateSequenceEnrollmentRequest {
+                subscriber_id,
+                trigger_data: trigger_data.clone(),
+            };
+
+            match sequences::create_sequence_enrollment(pool, sequence.id, &request).await {
+                Ok(enrollment) => {
+                    tracing::info!(
+                        "Subscriber {} enrolled in sequence {} ({})",
+                        subscriber_id,
+                        sequence.id,
+                        sequence.name
+                    );
+                    enrollments.push(enrollment);
+                }
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to enroll subscriber {} in sequence {}: {}",
+                        subscriber_id,
+                        sequence.id,
+                        e
+                    );
+                }
+            }
+        }
+
+        Ok(enrollments)
+    }
+
+    // トリガー設定の検証
+    pub async fn validate_trigger_config(
+        &self,
+        pool: &PgPool,
+        user_id: Uuid,
+        trigger_type: &str,
+        trigger_config: Option<&Value>,
+    ) -> Result<(), String> {
+        // 不明なトリガーは自動で登録されないため検証しない
+        let Some(trigger_type) = TriggerType::parse(trigger_type) else {
+            return Ok(());
+        };
+
+        match TriggerConfig::parse(trigger_type, trigger_config)? {
+            TriggerConfig::SegmentEntered { segment_id } => {
+                let segment = segments::find_segment_by_id(pool, segment_id, user_id)
+                    .await
+                    .map_err(|e| format!("セグメントの確認に失敗しました: {e}"))?;
+
+                if segment.is_none() {
+                    return Err("指定されたセグメントが見つかりません".to_string());
+                }
+            }
+            TriggerConfig::Campaign(trigger) => {
+                if let Some(campaign_id) = trigger.campaign_id {
+                    let campaign = campaigns::find_campaign_by_id(pool, campaign_id, user_id)
+                        .await
+                        .map_err(|e| format!("キャンペーンの確認に失敗しました: {e}"))?;
+
+                    if campaign.is_none() {
+                        return Err("指定されたキャンペーンが見つかりません".to_string());
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    // 終了条件の検証
+    pub async fn validate_exit_conditions(
+        &self,
+        pool: &PgPool,
+        user_id: Uuid,
+        exit_conditions: Option<&Value>,
+    ) -> Result<(), String> {
+        let Some(exit_conditions) = exit_conditions else {
+            return Ok(());
+        };
+
+        for condition in ExitCondition::parse_list(exit_conditions)? {
+            match condition.rule {
+                ExitRule::LinkClicked {
+                    campaign_id: Some(campaign_id),
+                    ..
+                } => {
+                    let campaign = campaigns::find_campaign_by_id(pool, campaign_id, user_id)
+                        .await
+                        .map_err(|e| format!("キャンペーンの確認に失敗しました: {e}"))?;
+
+                    if campaign.is_none() {
+                        return Err("終了条件のキャンペーンが見つかりません".to_string());
+                    }
+                }
+                ExitRule::FormSubmitted {
+                    form_id: Some(form_id),
+                } => {
+                    let form = forms::get_form_by_id(pool, form_id)
+                        .await
+                        .map_err(|e| format!("フォームの確認に失敗しました: {e}"))?;
+
+                    if form.is_none_or(|form| form.user_id != user_id) {
+                        return Err("終了条件のフォームが見つかりません".to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    // 送信時間帯の検証
+    pub fn validate_send_window(&self, send_window: Option<&Value>) -> Result<(), String> {
+        match send_window {
+            Some(send_window) => SendWindow::parse(send_window).map(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    // 再登録ポリシーの検証
+    pub fn validate_reentry_policy(&self, reentry_policy: Option<&str>) -> Result<(), String> {
+        match reentry_policy {
+            Some(policy) if ReentryPolicy::parse(policy).is_none() => Err(format!(
+                "再登録ポリシー「{policy}」は不正です（never・after_completionのいずれかを指定してください）"
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    // 手動でのエンロールメントの検証
+    pub fn validate_enrollment_request(
+        &self,
+        sequence: &Sequence,
+        request: &EnrollSubscribersRequest,
+    ) -> Result<(), String> {
+        if sequence.status != SequenceStatus::Active.as_str() {
+            return Err("有効なシーケンスにのみ購読者を登録できます".to_string());
+        }
+
+        let has_ids = request
+            .subscriber_ids
+            .as_ref()
+            .is_some_and(|ids| !ids.is_empty());
+        let has_tag = request
+            .tag
+            .as_deref()
+            .is_some_and(|tag| !tag.trim().is_empty());
+        let has_csv = request
+            .csv_content
+            .as_deref()
+            .is_some_and(|csv| !csv.trim().is_empty());
+        if !has_ids && !has_tag && !has_csv {
+            return Err("購読者ID・タグ・CSVのいずれかを指定してください".to_string());
+        }
+
+        Ok(())
+    }
+
+    // 購読者を手動でシーケンスに登録
+    //
+    // 配信対象外のステータスの購読者、登録中の購読者、再登録ポリシーで再登録できない購読者はスキップする。
+    pub async fn enroll_subscribers(
+        &self,
+        pool: &PgPool,
+        sequence: &Sequence,
+        request: &EnrollSubscribersRequest,
+    ) -> Result<EnrollSubscribersResult, String> {
+        let mut result = EnrollSubscribersResult::default();
+        let mut candidates = Vec::new();
+
+        if let Some(ids) = request
+            .subscriber_ids
+            .as_ref()
+            .filter(|ids| !ids.is_empty())
+        {
+            let found = subscribers::find_subscribers_by_ids(pool, sequence.user_id, ids)
+                .await
+                .map_err(|e| format!("購読者の取得に失敗しました: {e}"))?;
+            let found_ids: HashSet<Uuid> = found.iter().map(|s| s.id).collect();
+            result.not_found.extend(
+                ids.iter()
+                    .filter(|id| !found_ids.contains(id))
+                    .map(Uuid::to_string),
+            );
+            candidates.extend(found);
+        }
+
+        if let Some(tag) = request
+            .tag
+            .as_deref()
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+        {
+            let tagged = subscribers::find_subscribers_by_tag(pool, sequence.user_id, tag)
+                .await
+                .map_err(|e| format!("購読者の取得に失敗しました: {e}"))?;
+            candidates.extend(tagged);
+        }
+
+        if let Some(csv) = request
+            .csv_content
+            .as_deref()
+            .filter(|c| !c.trim().is_empty())
+        {
+            let emails = read_csv_emails(
+                csv,
+                request.has_header.unwrap_or(true),
+                request.email_column.unwrap_or(0),
+                &mut result.errors,
+            );
+            let found = subscribers::find_subscribers_by_emails(pool, sequence.user_id, &emails)
+                .await
+                .map_err(|e| format!("購読者の取得に失敗しました: {e}"))?;
+            let found_emails: HashSet<String> =
+                found.iter().map(|s| s.email.to_lowercase()).collect();
+            result.not_found.extend(
+                emails
+                    .into_iter()
+                    .filter(|email| !found_emails.contains(&email.to_lowercase())),
+            );
+            candidates.extend(found);
+        }
+
+        // 複数の指定方法で重複した購読者は1回だけ登録する
+        let mut seen = HashSet::new();
+        let mut subscriber_ids = Vec::new();
+        for subscriber in candidates {
+            if !seen.insert(subscriber.id) {
+                continue;
+            }
+            if subscriber.status != SubscriberStatus::Active {
+                result.skipped_inactive += 1;
+                continue;
+            }
+            subscriber_ids.push(subscriber.id);
+        }
+
+        let statuses = sequences::find_enrollment_statuses(pool, sequence.id, &subscriber_ids)
+            .await
+            .map_err(|e| format!("エンロールメントの確認に失敗しました: {e}"))?;
+        let mut skipped = HashSet::new();
+        for (subscriber_id, status) in &statuses {
+            match enrollment_skip(sequence, Some(status)) {
+                Some(EnrollmentSkip::InProgress) => result.skipped_in_progress += 1,
+                Some(EnrollmentSkip::Reentry) => result.skipped_reentry += 1,
+                None => continue,
+            }
+            skipped.insert(*subscriber_id);
+        }
+        subscriber_ids.retain(|id| !skipped.contains(id));
+
+        let enrolled = sequences::create_sequence_enrollments(
+            pool,
+            sequence.id,
+            &subscriber_ids,
+            &json!({ "source": "manual" }),
+        )
+        .await
+        .map_err(|e| format!("エンロールメントの作成に失敗しました: {e}"))?;
+
+        // 確認後に他の処理で登録された購読者は登録中として数える
+        result.enrolled = enrolled.len();
+        result.skipped_in_progress += subscriber_ids.len() - enrolled.len();
+
+        tracing::info!(
+            "Manually enrolled {} subscribers in sequence {} ({})",
+            result.enrolled,
+            sequence.id,
+            sequence.name
+        );
+
+        Ok(result)
+    }
+
+    // ステップ構成の検証（分岐先・循環・条件の形式と参照先）
+    pub async fn validate_sequence_steps(
+        &self,
+        pool: &PgPool,
+        user_id: Uuid,
+        steps: &[SequenceStep],
+    ) -> Result<(), String> {
+        StepGraph::new(steps).validate()?;
+
+        for step in steps {
+            WaitUntil::parse(&step.action_config)
+                .map_err(|e| format!("ステップ「{}」: {e}", step.name))?;
+
+            let condition = StepCondition::parse(&step.conditions)
+                .map_err(|e| format!("ステップ「{}」: {e}", step.name))?;
+
+            let Some(condition) = condition else {
+                if step.step_type == StepType::Condition.as_str() {
+                    return Err(format!(
+                        "条件ステップ「{}」に条件を指定してください",
+                        step.name
+                    ));
+                }
+                continue;
+            };
+
+            for campaign_id in condition.referenced_campaign_ids() {
+                let campaign = campaigns::find_campaign_by_id(pool, campaign_id, user_id)
+                    .await
+                    .map_err(|e| format!("キャンペーンの確認に失敗しました: {e}"))?;
+                if campaign.is_none() {
+                    return Err(format!(
+                        "ステップ「{}」の条件で指定されたキャンペーンが見つかりません",
+                        step.name
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // シーケンスを有効化できるか検証
+    pub async fn validate_activation(
+        &self,
+        pool: &PgPool,
+        sequence: &Sequence,
+    ) -> Result<(), String> {
+        let steps = sequences::get_sequence_steps(pool, sequence.id)
+            .await
+            .map_err(|e| format!("シーケンスステップの取得に失敗しました: {e}"))?;
+
+        self.validate_sequence_steps(pool, sequence.user_id, &steps)
+            .await
+    }
+
+    // ステップの変更を検証
+    //
+    // 下書きのシーケンスは条件の形式のみ検証し、ステップ構成全体は有効化時に検証する。
+    // 有効なシーケンスは変更後のステップ構成を検証する。
+    pub async fn validate_step_change(
+        &self,
+        pool: &PgPool,
+        sequence: &Sequence,
+        change: StepChange<'_>,
+    ) -> Result<(), String> {
+        let conditions = match &change {
+            StepChange::Create(request) => request.conditions.as_ref(),
+            StepChange::Update(_, request) => request.conditions.as_ref(),
+            StepChange::Delete(_) => None,
+        };
+        if let Some(conditions) = conditions {
+            StepCondition::parse(conditions)?;
+        }
+        let action_config = match &change {
+            StepChange::Create(request) => request.action_config.as_ref(),
+            StepChange::Update(_, request) => request.action_config.as_ref(),
+            StepChange::Delete(_) => None,
+        };
+        if let Some(action_config) = action_config {
+            WaitUntil::parse(action_config)?;
+        }
+
+        // 分岐先は下書きでも同じシーケンスの既存のステップに限る
+        let branch_targets: Vec<Uuid> = match &change {
+            StepChange::Create(request) => [request.true_step_id, request.false_step_id]
+                .into_iter()
+                .flatten()
+                .collect(),
+            StepChange::Update(_, request) => [request.true_step_id, request.false_step_id]
+                .into_iter()
+                .flatten()
+                .flatten()
+                .collect(),
+            StepChange::Delete(_) => Vec::new(),
+        };
+        let is_active = SequenceStatus::from(sequence.status.clone()) == SequenceStatus::Active;
+        if !is_active && branch_targets.is_empty() {
+            return Ok(());
+        }
+
+        let mut steps = sequences::get_sequence_steps(pool, sequence.id)
+            .await
+            .map_err(|e| format!("シーケンスステップの取得に失敗しました: {e}"))?;
+
+        if branch_targets
+            .iter()
+            .any(|target| !steps.iter().any(|step| step.id == *target))
+        {
+            return Err("分岐先のステップが見つかりません".to_string());
+        }
+        if !is_active {
+            return Ok(());
+        }
+
+        match change {
+            StepChange::Create(request) => steps.push(SequenceStep {
+                id: Uuid::new_v4(),
+                sequence_id: sequence.id,
+                name: request.name.clone(),
+                step_order: request.step_order,
+                step_type: request.step_type.clone(),
+                delay_value: request.delay_value.unwrap_or(0),
+                delay_unit: request.delay_unit.clone().unwrap_or("hours".to_string()),
+                template_id: request.template_id,
+                subject: request.subject.clone(),
+                conditions: request.conditions.clone().unwrap_or(json!({})),
+                action_config: request.action_config.clone().unwrap_or(json!({})),
+                true_step_id: request.true_step_id,
+                false_step_id: request.false_step_id,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            }),
+            StepChange::Update(step_id, request) => {
+                if let Some(step) = steps.iter_mut().find(|s| s.id == step_id) {
+                    if let Some(name) = &request.name {
+                        step.name = name.clone();
+                    }
+                    if let Some(step_order) = request.step_order {
+                        step.step_order = step_order;
+                    }
+                    if let Some(step_type) = &request.step_type {
+                        step.step_type = step_type.clone();
+                    }
+                    if let Some(conditions) = &request.conditions {
+                        step.conditions = conditions.clone();
+                    }
+                    if let Some(true_step_id) = request.true_step_id {
+                        step.true_step_id = true_step_id;
+                    }
+                    if let Some(false_step_id) = request.false_step_id {
+                        step.false_step_id = false_step_id;
+                    }
+                }
+            }
+            StepChange::Delete(step_id) => steps.retain(|s| s.id != step_id),
+        }
+
+        self.validate_sequence_steps(pool, sequence.user_id, &steps)
+            .await
+    }
+
+    // セグメントトリガーのシーケンスを有効化する前に、その時点のセグメント一致者を記録
+    //
+    // 記録した購読者は登録せず、有効化後に新たに一致した購読者のみを登録する。
+    // セグメントトリガー以外のシーケンスでは何もしない。
+    pub async fn snapshot_segment_members(
+        &self,
+        pool: &PgPool,
+        user_id: Uuid,
+        sequence_id: Uuid,
+        trigger_type: &str,
+        trigger_config: &Value,
+    ) -> Result<(), String> {
+        if TriggerType::parse(trigger_type) != Some(TriggerType::SegmentEntered) {
+            return Ok(());
+        }
+        let TriggerConfig::SegmentEntered { segment_id } =
+            TriggerConfig::parse(TriggerType::SegmentEntered, Some(trigger_config))?
+        else {
+            return Ok(());
+        };
+
+        let segment = segments::find_segment_by_id(pool, segment_id, user_id)
+            .await
+            .map_err(|e| format!("セグメントの取得に失敗しました: {e}"))?
+            .ok_or_else(|| "指定されたセグメントが見つかりません".to_string())?;
+
+        subscribers::replace_segment_snapshot(pool, user_id, &segment.rules, sequence_id)
+            .await
+            .map_err(|e| format!("セグメント一致者の記録に失敗しました: {e}"))?;
+
+        Ok(())
+    }
+
+    // セグメントトリガーのシーケンスに、セグメントに新たに一致した購読者を登録
+    //
+    // 有効化時点の一致者はセグメントから外れるまで登録しない。
+    // 一度登録された購読者は、セグメントから外れて再び一致しても再登録しない。
+    pub async fn process_segment_triggers(&self, pool: &PgPool) -> Result<usize, String> {
+        let sequences =
+            sequences::find_all_active_sequences_by_trigger(pool, TriggerType::SegmentEntered)
+                .await
+                .map_err(|e| format!("シーケンスの取得に失敗しました: {e}"))?;
+
+        let mut enrolled = 0;
+
+        for sequence in sequences {
+            let Some(segment_id) = sequence
+                .trigger_config
+                .get("segment_id")
+                .and_then(|id| id.as_str())
+                .and_then(|id| Uuid::parse_str(id).ok())
+            else {
+                tracing::warn!(
+                    "Sequence {} has no segment_id in trigger_config",
+                    sequence.id
+                );
+                continue;
+            };
+
+            let segment =
+                match segments::find_segment_by_id(pool, segment_id, sequence.user_id).await {
+                    Ok(Some(segment)) => segment,
+                    Ok(None) => {
+                        tracing::warn!(
+                            "Segment {} for sequence {} was not found",
+                            segment_id,
+                            sequence.id
+                        );
+                        continue;
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to load segment {}: {}", segment_id, e);
+                        continue;
+                    }
+                };
+
+            if let Err(e) =
+                subscribers::prune_segment_snapshot(pool, &segment.rules, sequence.id).await
+            {
+                tracing::error!(
+                    "Failed to prune segment {} snapshot for sequence {}: {}",
+                    segment_id,
+                    sequence.id,
+                    e
+                );
+                continue;
+            }
+
+            let entrants = match subscribers::list_segment_entrants(
+                pool,
+                sequence.user_id,
+                &segment.rules,
+                sequence.id,
+                SEGMENT_ENROLLMENT_BATCH_SIZE,
+            )
+            .await
+            {
+                Ok(entrants) => entrants,
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to list segment {} entrants for sequence {}: {}",
+                        segment_id,
+                        sequence.id,
+                        e
+                    );
+                    continue;
+                }
+            };
+
+            for subscriber in entrants {
+                enrolled += self
+                    .process_trigger_enrollment(
+                        pool,
+                        sequence.user_id,
+                        TriggerType::SegmentEntered,
+                        subscriber.id,
+                        Some(json!({ "segment_id": segment_id })),
+                    )
+                    .await?
+                    .len();
+            }
+        }
+
+        Ok(enrolled)
+    }
+
+    // 誕生日・記念日トリガーのシーケンスに、日付のカスタムフィールドが`today`に該当する購読者を登録
+    //
+    // 同じ日付では一度だけ登録する（エンロールメントのメタデータの`date`で判定する）。
+    pub async fn process_date_triggers(
+        &self,
+        pool: &PgPool,
+        today: NaiveDate,
+    ) -> Result<usize, String> {
+        let mut enrolled = 0;
+
+        for trigger_type in [TriggerType::Birthday, TriggerType::Anniversary] {
+            let sequences = sequences::find_all_active_sequences_by_trigger(pool, trigger_type)
+                .await
+                .map_err(|e| format!("シーケンスの取得に失敗しました: {e}"))?;
+
+            for sequence in sequences {
+                let trigger =
+                    match TriggerConfig::parse(trigger_type, Some(&sequence.trigger_config)) {
+                        Ok(TriggerConfig::Date(trigger)) => trigger,
+                        Ok(_) => continue,
+                        Err(e) => {
+                            tracing::warn!(
+                                "Sequence {} has invalid trigger_config: {}",
+                                sequence.id,
+                                e
+                            );
+                            continue;
+                        }
+                    };
+
+                let target = trigger.target_date(today);
+                let date = target.to_string();
+                let candidates = match subscribers::list_date_trigger_candidates(
+                    pool,
+                    sequence.user_id,
+                    &trigger.field,
+                    &month_days_for(target),
+                    sequence.id,
+                    &date,
+                    DATE_ENROLLMENT_BATCH_SIZE,
+                )
+                .await
+                {
+                    Ok(candidates) => candidates,
+                    Err(e) => {
+                        tracing::error!(
+                            "Failed to list date trigger candidates for sequence {}: {}",
+                            sequence.id,
+                            e
+                        );
+                        continue;
+                    }
+                };
+
+                for subscriber in candidates {
+                    let Some((year, _, _)) = subscriber
+                        .custom_fields
+                        .get(&trigger.field)
+                        .and_then(parse_field_date)
+                    else {
+                        continue;
+                    };
+                    // 記念日は年のある日付の1年目以降（日付当日は対象外）
+                    let years = year.map(|year| target.year() - year);
+                    if trigger_type == TriggerType::Anniversary && years.is_none_or(|y| y <= 0) {
+                        continue;
+                    }
+
+                    let mut trigger_data = json!({
+                        "field": trigger.field,
+                        "days_before": trigger.days_before,
+                        "date": date,
+                    });
+                    if let Some(years) = years {
+                        trigger_data["years"] = json!(years);
+                    }
+
+                    enrolled += self
+                        .process_trigger_enrollment(
+                            pool,
+                            sequence.user_id,
+                            trigger_type,
+                            subscriber.id,
+                            Some(trigger_data),
+                        )
+                        .await?
+                        .len();
+                }
+            }
+        }
+
+        Ok(enrolled)
+    }
+
+    // 購読者の更新に応じたトリガー（タグの追加・削除、カスタムフィールドの変更）
+    pub async fn process_subscriber_changes(
+        &self,
+        pool: &PgPool,
+        before: &Subscriber,
+        after: &Subscriber,
+    ) -> Result<Vec<SequenceEnrollment>, String> {
+        let mut triggers = Vec::new();
+
+        for tag in after.tags.iter().filter(|tag| !before.tags.contains(tag)) {
+            triggers.push((TriggerType::TagAdded, json!({ "tag": tag })));
+        }
+        for tag in before.tags.iter().filter(|tag| !after.tags.contains(tag)) {
+            triggers.push((TriggerType::TagRemoved, json!({ "tag": tag })));
+        }
+
+        let empty = serde_json::Map::new();
+        let before_fields = before.custom_fields.as_object().unwrap_or(&empty);
+        let after_fields = after.custom_fields.as_object().unwrap_or(&empty);
+        let mut fields: Vec<&String> = before_fields.keys().chain(after_fields.keys()).collect();
+        fields.sort();
+        fields.dedup();
+        for field in fields {
+            let from = before_fields.get(field).unwrap_or(&Value::Null);
+            let to = after_fields.get(field).unwrap_or(&Value::Null);
+            if from != to {
+                triggers.push((
+                    TriggerType::CustomFieldChanged,
+                    json!({ "field": field, "from": from, "to": to }),
+                ));
+            }
+        }
+
+        // タグ・カスタムフィールド・ステータスの終了条件を満たしたシーケンスから先に終了させる
+        self.process_exit_conditions(pool, after.user_id, after.id)
+            .await?;
+
+        let mut enrollments = Vec::new();
+        for (trigger_type, trigger_data) in triggers {
+            enrollments.extend(
+                self.process_trigger_enrollment(
+                    pool,
+                    after.user_id,
+                    trigger_type,
+                    after.id,
+                    Some(trigger_data),
+                )
+                .await?,
+            );
+        }
+
+        Ok(enrollments)
+    }
+
+    // キャンペーンの開封・クリックに応じたトリガー
+    pub async fn process_engagement_trigger(
+        &self,
+        pool: &PgPool,
+        campaign_id: Uuid,
+        subscriber_id: Uuid,
+        event_type: TrackingEventType,
+        url: Option<&str>,
+    ) -> Result<Vec<SequenceEnrollment>, String> {
+        let Some(user_id) = campaigns::find_campaign_user_id(pool, campaign_id)
+            .await
+            .map_err(|e| format!("キャンペーンの取得に失敗しました: {e}"))?
+        else {
+            return Ok(Vec::new());
+        };
+
+        let trigger_type = match event_type {
+            TrackingEventType::Open => TriggerType::CampaignOpened,
+            TrackingEventType::Click => TriggerType::CampaignClicked,
+        };
+
+        self.process_trigger_enrollment(
+            pool,
+            user_id,
+            trigger_type,
+            subscriber_id,
+            Some(json!({ "campaign_id": campaign_id.to_string(), "url": url })),
+        )
+        .await
+    }
+
+    // 購読者の実行中のエンロールメントのうち、終了条件を満たしたものを終了する
+    //
+    // 購読者の更新・配信停止・リンクのクリック・フォームの送信時に呼び出す。終了したエンロールメントを返す。
+    pub async fn process_exit_conditions(
+        &self,
+        pool: &PgPool,
+        user_id: Uuid,
+        subscriber_id: Uuid,
+    ) -> Result<Vec<SequenceEnrollment>, String> {
+        let enrollments = sequences::find_enrollments_with_exit_conditions(pool, subscriber_id)
+            .await
+            .map_err(|e| format!("エンロールメントの取得に失敗しました: {e}"))?;
+        if enrollments.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let Some(subscriber) = subscribers::find_subscriber_by_id(pool, subscriber_id, user_id)
+            .await
+            .map_err(|e| format!("購読者情報の取得に失敗しました: {e}"))?
+        else {
+            return Ok(Vec::new());
+        };
+
+        let mut exited = Vec::new();
+        for enrollment in enrollments {
+            let sequence = sequences::find_sequence_by_id(pool, enrollment.sequence_id, None)
+                .await
+                .map_err(|e| format!("シーケンスの取得に失敗しました: {e}"))?
+                .ok_or_else(|| "シーケンスが見つかりません".to_string())?;
+
+            if let Some(updated) = self
+                .apply_exit_conditions(pool, &sequence, &enrollment, &subscriber)
+                .await?
+            {
+                exited.push(updated);
+            }
+        }
+
+        Ok(exited)
+    }
+
+    // 終了条件を満たしていればエンロールメントを終了し、現在のステップのログに理由を記録
+    async fn apply_exit_conditions(
+        &self,
+        pool: &PgPool,
+        sequence: &Sequence,
+        enrollment: &SequenceEnrollment,
+        subscriber: &Subscriber,
+    ) -> Result<Option<SequenceEnrollment>, String> {
+        let Some(condition) = self
+            .find_met_exit_condition(pool, sequence, enrollment, subscriber)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let status = condition.outcome().status();
+        let in_progress = [
+            EnrollmentStatus::Active.as_str(),
+            EnrollmentStatus::Paused.as_str(),
+        ];
+        // 既に終了している場合は何もしない
+        let Some(updated) = sequences::transition_enrollment_status(
+            pool,
+            enrollment.id,
+            &in_progress,
+            status.as_str(),
+        )
+        .await
+        .map_err(|e| format!("エンロールメントの終了に失敗しました: {e}"))?
+        else {
+            return Ok(None);
+        };
+
+        if let Some(step_id) = enrollment.current_step_id {
+            self.log_step_execution(
+                pool,
+                enrollment.id,
+                step_id,
+                status.as_str(),
+                Some(condition.describe()),
+            )
+            .await?;
+        }
+
+        tracing::info!(
+            "エンロールメント {} が終了条件を満たしました（{}）: {}",
+            enrollment.id,
+            status.as_str(),
+            condition.describe()
+        );
 
-        for sequence in sequences {
-            // トリガー条件を評価
-            if self.evaluate_trigger_conditions(&sequence, trigger_data.as_ref())? {
-                // エンロールメントを作成
-                let request = CreateSequenceEnrollmentRequest {
-                    subscriber_id,
-                    trigger_data: trigger_data.clone(),
-                };
+        Ok(Some(updated))
+    }
 
-                match sequences::create_sequence_enrollment(pool, sequence.id, &request).await {
-                    Ok(enrollment) => {
-                        tracing::info!(
-                            "Subscriber {} enrolled in sequence {} ({})",
-                            subscriber_id,
-                            sequence.id,
-                            sequence.name
-                        );
-                        enrollments.push(enrollment);
-                    }
-                    Err(e) => {
-                        tracing::error!(
-                            "Failed to enroll subscriber {} in sequence {}: {}",
-                            subscriber_id,
-                            sequence.id,
-                            e
-                        );
-                    }
-                }
+    // 最初に満たした終了条件（クリック・フォーム送信は登録後のものだけを対象にする）
+    async fn find_met_exit_condition(
+        &self,
+        pool: &PgPool,
+        sequence: &Sequence,
+        enrollment: &SequenceEnrollment,
+        subscriber: &Subscriber,
+    ) -> Result<Option<ExitCondition>, String> {
+        for condition in ExitCondition::parse_list(&sequence.exit_conditions)? {
+            let met = match &condition.rule {
+                ExitRule::LinkClicked { campaign_id, url } => tracking::has_clicked_link_since(
+                    pool,
+                    subscriber.id,
+                    *campaign_id,
+                    url.as_deref(),
+                    enrollment.enrolled_at,
+                )
+                .await
+                .map_err(|e| format!("クリック履歴の確認に失敗しました: {e}"))?,
+                ExitRule::FormSubmitted { form_id } => forms::has_form_submission_since(
+                    pool,
+                    subscriber.id,
+                    *form_id,
+                    enrollment.enrolled_at,
+                )
+                .await
+                .map_err(|e| format!("フォーム送信履歴の確認に失敗しました: {e}"))?,
+                rule => rule.matches_subscriber(subscriber).unwrap_or(false),
+            };
+
+            if met {
+                return Ok(Some(condition));
             }
         }
 
-        Ok(enrollments)
+        Ok(None)
     }
 
     // トリガー条件の評価
@@ -87,157 +1119,340 @@ impl SequenceService {
         sequence: &Sequence,
         trigger_data: Option<&Value>,
     ) -> Result<bool, String> {
-        // trigger_configが空の場合は常にtrue
-        if sequence.trigger_config.is_null() || sequence.trigger_config == json!({}) {
+        // 不明なトリガーは設定を問わない
+        let Some(trigger_type) = TriggerType::parse(&sequence.trigger_type) else {
             return Ok(true);
-        }
+        };
 
-        // フォーム送信トリガーの場合
-        if sequence.trigger_type == TriggerType::FormSubmission.as_str() {
-            if let Some(data) = trigger_data {
-                if let (Some(form_id_config), Some(form_id_data)) =
-                    (sequence.trigger_config.get("form_id"), data.get("form_id"))
-                {
-                    return Ok(form_id_config == form_id_data);
-                }
+        let config = TriggerConfig::parse(trigger_type, Some(&sequence.trigger_config))?;
+        Ok(config.matches(trigger_data))
+    }
+
+    // 実行待ちのシーケンスステップをすべて処理（実行時点で実行日時を過ぎていたものを1ステップずつ）
+    pub async fn process_pending_sequence_steps(
+        &self,
+        pool: &PgPool,
+    ) -> Result<StepBatchStats, String> {
+        let email_service = SharedEmailService::new();
+        let options = StepBatchOptions::default();
+        let run_started_at = Utc::now();
+        let mut stats = StepBatchStats::default();
+
+        loop {
+            let batch = self
+                .process_step_batch(pool, &email_service, &options, run_started_at)
+                .await?;
+            if batch.claimed == 0 {
+                break;
             }
+            stats.merge(&batch);
         }
 
-        // タグ追加トリガーの場合
-        if sequence.trigger_type == TriggerType::TagAdded.as_str() {
-            if let Some(data) = trigger_data {
-                if let (Some(tag_config), Some(tag_data)) =
-                    (sequence.trigger_config.get("tag"), data.get("tag"))
-                {
-                    return Ok(tag_config == tag_data);
-                }
+        Ok(stats)
+    }
+
+    /// 実行待ちのエンロールメントを1バッチ分確保してステップを実行
+    ///
+    /// 確保したエンロールメントは最大`options.concurrency`件を並行して処理し、処理後に確保を解放する。
+    /// `run_started_at`には実行の開始日時を渡し、それまでに実行日時を迎えたステップのみを確保する。
+    pub async fn process_step_batch(
+        &self,
+        pool: &PgPool,
+        email_service: &SharedEmailService,
+        options: &StepBatchOptions,
+        run_started_at: DateTime<Utc>,
+    ) -> Result<StepBatchStats, String> {
+        let enrollments = sequences::claim_due_sequence_enrollments(
+            pool,
+            options.batch_size,
+            options.per_user_limit,
+            options.stale_after_seconds,
+            run_started_at,
+        )
+        .await
+        .map_err(|e| format!("実行待ちエンロールメントの確保に失敗しました: {e}"))?;
+
+        let claimed_at = Utc::now();
+        let permits = Semaphore::new(options.concurrency.max(1));
+        let results = join_all(enrollments.iter().map(|enrollment| {
+            self.process_claimed_enrollment(pool, email_service, &permits, enrollment, claimed_at)
+        }))
+        .await;
+
+        let mut stats = StepBatchStats {
+            claimed: enrollments.len(),
+            ..Default::default()
+        };
+        for (succeeded, lag_ms) in results {
+            if succeeded {
+                stats.succeeded += 1;
+            } else {
+                stats.failed += 1;
             }
+            stats.max_lag_ms = stats.max_lag_ms.max(lag_ms);
+            stats.total_lag_ms += lag_ms;
         }
 
-        // その他のトリガーは現時点では常にtrue
-        Ok(true)
+        Ok(stats)
     }
 
-    // 実行待ちのシーケンスステップを処理
-    pub async fn process_pending_sequence_steps(&self, pool: &PgPool) -> Result<(), String> {
-        // 実行待ちのエンロールメントを取得
-        let pending_enrollments = sequences::find_pending_sequence_enrollments(pool)
+    // 確保したエンロールメントのステップを実行して確保を解放（成否と実行日時からの遅延を返す）
+    async fn process_claimed_enrollment(
+        &self,
+        pool: &PgPool,
+        email_service: &SharedEmailService,
+        permits: &Semaphore,
+        enrollment: &SequenceEnrollment,
+        claimed_at: DateTime<Utc>,
+    ) -> (bool, i64) {
+        let _permit = permits
+            .acquire()
             .await
-            .map_err(|e| format!("実行待ちエンロールメントの取得に失敗しました: {e}"))?;
+            .expect("セマフォは閉じないため取得に失敗しない");
+        let lag = claimed_at - enrollment.next_step_at.unwrap_or(enrollment.enrolled_at);
+
+        let result = self
+            .process_enrollment_step(pool, email_service, enrollment)
+            .await;
+        if let Err(e) = &result {
+            tracing::error!("Failed to process enrollment {} step: {}", enrollment.id, e);
+            if let Err(e) = self.handle_failed_step(pool, enrollment, e).await {
+                tracing::error!("Failed to defer enrollment {} step: {}", enrollment.id, e);
+            }
+        }
+        if let Err(e) = sequences::release_sequence_enrollment(pool, enrollment.id).await {
+            tracing::error!("Failed to release enrollment {}: {}", enrollment.id, e);
+        }
 
-        for enrollment in pending_enrollments {
-            if let Err(e) = self.process_enrollment_step(pool, &enrollment).await {
-                tracing::error!("Failed to process enrollment {} step: {}", enrollment.id, e);
+        (result.is_ok(), lag.num_milliseconds().max(0))
+    }
+
+    // 失敗したステップを記録し、連続した失敗回数に応じて再実行を遅らせる
+    //
+    // 再実行しても成功しないエラーの場合と、失敗回数が上限に達した場合はエンロールメントを失敗として終了する。
+    async fn handle_failed_step(
+        &self,
+        pool: &PgPool,
+        enrollment: &SequenceEnrollment,
+        error: &StepError,
+    ) -> Result<(), String> {
+        let failures = match enrollment.current_step_id {
+            Some(step_id) => {
+                sequences::count_failed_step_executions(pool, enrollment.id, step_id)
+                    .await
+                    .map_err(|e| format!("ステップの失敗回数の取得に失敗しました: {e}"))?
+                    + 1
             }
+            None => 1,
+        };
+        let abandon = error.should_abandon(failures);
+
+        if let Some(step_id) = enrollment.current_step_id {
+            let message = if abandon {
+                format!("{error}（{failures}回目の失敗のため再実行を中止しました）")
+            } else {
+                error.to_string()
+            };
+            self.log_step_execution(pool, enrollment.id, step_id, "failed", Some(message))
+                .await?;
         }
 
-        Ok(())
+        if abandon {
+            sequences::transition_enrollment_status(
+                pool,
+                enrollment.id,
+                &[EnrollmentStatus::Active.as_str()],
+                EnrollmentStatus::Failed.as_str(),
+            )
+            .await
+            .map_err(|e| format!("エンロールメントの更新に失敗しました: {e}"))?;
+            tracing::warn!(
+                "Enrollment {} failed after {} attempt(s): {}",
+                enrollment.id,
+                failures,
+                error
+            );
+            return Ok(());
+        }
+
+        sequences::defer_enrollment_step(
+            pool,
+            enrollment.id,
+            Utc::now() + step_retry_delay(failures),
+        )
+        .await
+        .map_err(|e| format!("ステップの再実行日時の設定に失敗しました: {e}"))
     }
 
     // エンロールメントの次のステップを処理
     async fn process_enrollment_step(
         &self,
         pool: &PgPool,
+        email_service: &SharedEmailService,
         enrollment: &SequenceEnrollment,
-    ) -> Result<(), String> {
+    ) -> Result<(), StepError> {
         // シーケンスとステップ情報を取得
         let sequence = sequences::find_sequence_by_id(pool, enrollment.sequence_id, None)
             .await
             .map_err(|e| format!("シーケンスの取得に失敗しました: {e}"))?
             .ok_or_else(|| "シーケンスが見つかりません".to_string())?;
 
+        // 終了条件を満たしている場合はステップを実行せずに終了
+        if sequence
+            .exit_conditions
+            .as_array()
+            .is_some_and(|conditions| !conditions.is_empty())
+        {
+            let subscriber = subscribers::find_subscriber_by_id(
+                pool,
+                enrollment.subscriber_id,
+                sequence.user_id,
+            )
+            .await
+            .map_err(|e| format!("購読者情報の取得に失敗しました: {e}"))?;
+
+            if let Some(subscriber) = subscriber {
+                if self
+                    .apply_exit_conditions(pool, &sequence, enrollment, &subscriber)
+                    .await?
+                    .is_some()
+                {
+                    return Ok(());
+                }
+            }
+        }
+
         let steps = sequences::find_sequence_steps(pool, enrollment.sequence_id)
             .await
             .map_err(|e| format!("シーケンスステップの取得に失敗しました: {e}"))?;
+        let graph = StepGraph::new(&steps);
 
-        // 現在のステップ順序を取得
-        let current_step_order = if let Some(current_step_id) = enrollment.current_step_id {
-            steps
-                .iter()
-                .find(|s| s.id == current_step_id)
-                .map(|s| s.step_order)
-                .unwrap_or(0)
-        } else {
-            0
+        // 次に実行するステップ（存在しない場合はすべてのステップが完了）
+        let Some(step) = enrollment.current_step_id.and_then(|id| graph.find(id)) else {
+            return Ok(self.move_to_step(pool, enrollment, None).await?);
         };
+        let following = graph.following(step);
 
-        // 次のステップを特定
-        let next_step_order = current_step_order + 1;
-        let next_step = steps
-            .iter()
-            .find(|s| s.step_order == next_step_order)
-            .ok_or_else(|| "次のステップが見つかりません".to_string())?;
-
-        // ステップの条件を評価
-        if !self
-            .evaluate_step_conditions(pool, next_step, enrollment)
-            .await?
+        // 条件ステップ以外は、条件を満たさない場合にスキップして次のステップへ
+        if step.step_type != StepType::Condition.as_str()
+            && !self
+                .evaluate_step_conditions(pool, &sequence, step, enrollment)
+                .await?
         {
-            // 条件を満たさない場合はスキップして次のステップへ
-            self.move_to_next_step(pool, enrollment, next_step_order + 1)
+            self.log_step_execution(pool, enrollment.id, step.id, "skipped", None)
                 .await?;
-            return Ok(());
+            return Ok(self.move_to_step(pool, enrollment, following).await?);
         }
 
         // ステップタイプに応じて処理
-        match next_step.step_type.as_str() {
+        match step.step_type.as_str() {
             "email" => {
-                self.process_email_step(pool, &sequence, next_step, enrollment)
-                    .await?;
+                self.process_email_step(
+                    pool,
+                    email_service,
+                    &sequence,
+                    step,
+                    following,
+                    enrollment,
+                )
+                .await?;
             }
             "wait" => {
-                self.process_wait_step(pool, next_step, enrollment).await?;
+                self.process_wait_step(pool, &sequence, step, following, enrollment)
+                    .await?;
             }
             "condition" => {
-                self.process_condition_step(pool, next_step, enrollment)
+                self.process_condition_step(pool, &sequence, &graph, step, enrollment)
                     .await?;
             }
             "tag" => {
-                self.process_tag_step(pool, next_step, enrollment).await?;
+                self.process_tag_step(pool, &sequence, step, following, enrollment)
+                    .await?;
             }
             _ => {
-                return Err(format!("不明なステップタイプ: {}", next_step.step_type));
+                return Err(StepError::Permanent(format!(
+                    "不明なステップタイプ: {}",
+                    step.step_type
+                )));
             }
         }
 
         Ok(())
     }
 
-    // ステップ条件の評価
+    // ステップ条件の評価（条件が設定されていない場合は常にtrue）
     async fn evaluate_step_conditions(
         &self,
-        _pool: &PgPool,
+        pool: &PgPool,
+        sequence: &Sequence,
         step: &SequenceStep,
-        _enrollment: &SequenceEnrollment,
+        enrollment: &SequenceEnrollment,
     ) -> Result<bool, String> {
-        // 条件が設定されていない場合は常にtrue
-        if step.conditions.is_null() || step.conditions == json!({}) {
+        let Some(condition) = StepCondition::parse(&step.conditions)? else {
             return Ok(true);
+        };
+
+        let subscriber =
+            subscribers::find_subscriber_by_id(pool, enrollment.subscriber_id, sequence.user_id)
+                .await
+                .map_err(|e| format!("購読者情報の取得に失敗しました: {e}"))?
+                .ok_or_else(|| "購読者が見つかりません".to_string())?;
+
+        // メールへの反応の条件は事前にデータベースで確認する
+        let mut engagements = HashSet::new();
+        for (event_type, engagement) in condition.engagement_conditions() {
+            let engaged = tracking::has_subscriber_engagement(
+                pool,
+                subscriber.id,
+                event_type,
+                engagement.campaign_id,
+                engagement.within_days,
+            )
+            .await
+            .map_err(|e| format!("反応履歴の確認に失敗しました: {e}"))?;
+
+            if engaged {
+                engagements.insert((event_type, engagement));
+            }
         }
 
-        // TODO: 条件評価ロジックの実装
-        // 現時点では常にtrueを返す
-        Ok(true)
+        let context = ConditionContext {
+            tags: &subscriber.tags,
+            custom_fields: &subscriber.custom_fields,
+            metadata: &enrollment.metadata,
+            engagements,
+        };
+
+        Ok(condition.evaluate(&context))
     }
 
     // メール送信ステップの処理
     async fn process_email_step(
         &self,
         pool: &PgPool,
+        email_service: &SharedEmailService,
         sequence: &Sequence,
         step: &SequenceStep,
+        next_step: Option<&SequenceStep>,
         enrollment: &SequenceEnrollment,
-    ) -> Result<(), String> {
+    ) -> Result<(), StepError> {
         // テンプレートIDが必要
-        let template_id = step
-            .template_id
-            .ok_or_else(|| "メールステップにテンプレートIDが設定されていません".to_string())?;
+        let template_id = step.template_id.ok_or_else(|| {
+            StepError::Permanent("メールステップにテンプレートIDが設定されていません".to_string())
+        })?;
 
-        // テンプレートを取得
+        // テンプレートを取得し、送信時点のリビジョンの内容で送る
         let template = templates::find_template_by_id(pool, template_id, Some(sequence.user_id))
             .await
             .map_err(|e| format!("テンプレートの取得に失敗しました: {e}"))?
-            .ok_or_else(|| "テンプレートが見つかりません".to_string())?;
+            .ok_or_else(|| StepError::Permanent("テンプレートが見つかりません".to_string()))?;
+        let revision = template_revisions::current_revision(pool, template_id, sequence.user_id)
+            .await
+            .map_err(|e| format!("テンプレートのリビジョンの取得に失敗しました: {e}"))?
+            .ok_or_else(|| {
+                StepError::Permanent("テンプレートのリビジョンが見つかりません".to_string())
+            })?;
+        let template = revision.apply_to(&template);
 
         // 購読者情報を取得
         let subscriber =
@@ -246,37 +1461,92 @@ impl SequenceService {
                 .map_err(|e| format!("購読者情報の取得に失敗しました: {e}"))?
                 .ok_or_else(|| "購読者が見つかりません".to_string())?;
 
-        // メール送信
-        self.send_sequence_email(pool, sequence, step, &template, &subscriber)
+        // 配信停止・バウンス・苦情の購読者には送信せずエンロールメントを終了
+        if !matches!(subscriber.status, SubscriberStatus::Active) {
+            self.log_step_execution(
+                pool,
+                enrollment.id,
+                step.id,
+                "skipped",
+                Some("購読者が配信対象外のステータスです".to_string()),
+            )
             .await?;
+            sequences::update_sequence_enrollment_status(
+                pool,
+                enrollment.id,
+                "cancelled",
+                enrollment.current_step_id,
+            )
+            .await
+            .map_err(|e| format!("エンロールメントの更新に失敗しました: {e}"))?;
+            return Ok(());
+        }
 
-        // ステップログを記録
-        self.log_step_execution(pool, enrollment.id, step.id, "sent", None)
+        // 送信時間帯外の場合は次に送信できる日時まで延期
+        let window = SendWindow::parse(&sequence.send_window)?;
+        let now = Utc::now();
+        let slot = window.next_slot(now, window.subscriber_timezone(&subscriber));
+        if slot > now {
+            sequences::schedule_next_enrollment_step(pool, enrollment.id, step.id, slot)
+                .await
+                .map_err(|e| format!("送信の延期に失敗しました: {e}"))?;
+            self.log_step_execution(
+                pool,
+                enrollment.id,
+                step.id,
+                "deferred",
+                Some(format!(
+                    "送信時間帯外のため{}まで延期しました",
+                    slot.to_rfc3339()
+                )),
+            )
             .await?;
+            return Ok(());
+        }
 
-        // 次のステップへ移動
-        self.move_to_next_step(pool, enrollment, step.step_order + 1)
+        // メール送信
+        let email_service = email_service.get(pool).await?;
+        let message_id = self
+            .send_sequence_email(pool, email_service, sequence, step, &template, &subscriber)
             .await?;
 
+        // ステップログに送信したリビジョンとメッセージIDを記録
+        sequences::create_sequence_step_log(
+            pool,
+            enrollment.id,
+            step.id,
+            "sent",
+            None,
+            Some(revision.id),
+            Some(&message_id),
+        )
+        .await
+        .map_err(|e| format!("ステップログの記録に失敗しました: {e}"))?;
+
+        // 次のステップへ移動
+        self.move_to_step(pool, enrollment, next_step).await?;
+
         Ok(())
     }
 
-    // シーケンスメールの送信
+    // シーケンスメールの送信（送信したメールのメッセージIDを返す）
     async fn send_sequence_email(
         &self,
         pool: &PgPool,
+        email_service: &EmailService,
         sequence: &Sequence,
         step: &SequenceStep,
         template: &crate::models::template::Template,
         subscriber: &Subscriber,
-    ) -> Result<(), String> {
-        // メールサービスを初期化
-        let email_service = EmailService::new(pool.clone())
-            .await
-            .map_err(|e| format!("メールサービスの初期化に失敗しました: {e}"))?;
+    ) -> Result<String, StepError> {
+        // マークダウンサービスを初期化（シーケンス所有者のコンポーネントを展開する）
+        let markdown_service = MarkdownComponentService::new()
+            .markdown_service(pool, sequence.user_id)
+            .await?;
 
-        // マークダウンサービスを初期化
-        let markdown_service = MarkdownService::new();
+        // 配信停止URL
+        let unsubscribe_service = UnsubscribeService::new();
+        let unsubscribe_url = unsubscribe_service.unsubscribe_url(subscriber, None)?;
 
         // 変数を準備
         let mut variables = if template.variables.is_null() {
@@ -311,63 +1581,67 @@ impl SequenceService {
             // 配信停止URL
             map.insert(
                 "unsubscribe_url".to_string(),
-                json!(format!(
-                    "https://markmail.example.com/unsubscribe/{}",
-                    subscriber.id
-                )),
+                json!(unsubscribe_url.clone()),
             );
         }
 
         // HTMLとテキストをレンダリング
         let html_body = markdown_service
             .render_with_variables(&template.markdown_content, &variables)
-            .map_err(|e| format!("HTMLレンダリングに失敗しました: {e}"))?;
+            .map_err(|e| StepError::Permanent(format!("HTMLレンダリングに失敗しました: {e}")))?;
 
-        let text_body = html2text::from_read(html_body.as_bytes(), 80);
+        let text_body = email_html::html_to_text(&html_body);
 
-        // 件名の変数を置換
-        let subject = if let Some(custom_subject) = &step.subject {
-            self.replace_variables(custom_subject, &variables)
-        } else {
-            self.replace_variables(&template.subject_template, &variables)
-        };
+        // 件名の変数を展開
+        let subject_template = step
+            .subject
+            .as_deref()
+            .unwrap_or(&template.subject_template);
+        let subject = self
+            .replace_variables(subject_template, &variables)
+            .map_err(StepError::Permanent)?;
 
         let email_message = EmailMessage {
             to: vec![subscriber.email.clone()],
+            from: None,
             subject,
             html_body,
             text_body: Some(text_body),
             reply_to: None,
-            headers: None,
+            headers: Some(unsubscribe_service.list_unsubscribe_headers(&unsubscribe_url)),
+            dkim: None,
         };
 
-        // メール送信
-        email_service
+        // メール送信（宛先の拒否などの再送しても成功しないエラーは再実行しない）
+        let result = email_service
             .send_email(&email_message)
             .await
-            .map_err(|e| format!("メール送信に失敗しました: {e}"))?;
+            .map_err(|e| {
+                let message = format!("メール送信に失敗しました: {e}");
+                if e.is_transient() {
+                    StepError::Retryable(message)
+                } else {
+                    StepError::Permanent(message)
+                }
+            })?;
 
-        Ok(())
+        Ok(result.message_id)
     }
 
-    // 変数置換
-    fn replace_variables(&self, text: &str, variables: &Value) -> String {
-        let mut result = text.to_string();
-        if let Value::Object(vars) = variables {
-            for (key, value) in vars {
-                if let Value::String(val) = value {
-                    result = result.replace(&format!("{{{{{key}}}}}"), val);
-                }
-            }
-        }
-        result
+    // 変数展開
+    fn replace_variables(&self, text: &str, variables: &Value) -> Result<String, String> {
+        TemplateEngine::shared()
+            .render_text(text, variables)
+            .map_err(|e| format!("件名のレンダリングに失敗しました: {e}"))
     }
 
     // 待機ステップの処理
     async fn process_wait_step(
         &self,
         pool: &PgPool,
+        sequence: &Sequence,
         step: &SequenceStep,
+        next_step: Option<&SequenceStep>,
         enrollment: &SequenceEnrollment,
     ) -> Result<(), String> {
         // 待機時間を計算
@@ -381,12 +1655,31 @@ impl SequenceService {
             _ => Duration::minutes(delay_minutes as i64),
         };
 
-        let next_execution_at = Utc::now() + delay_duration;
-
-        // 次の実行時刻を設定
-        self.schedule_next_step(pool, enrollment, step.step_order + 1, next_execution_at)
+        let next_execution_at = self
+            .schedule_next_step(
+                pool,
+                sequence,
+                step,
+                enrollment,
+                Utc::now() + delay_duration,
+            )
             .await?;
 
+        // 次の実行時刻を設定（最後のステップの場合は完了）
+        match next_step {
+            Some(next_step) => {
+                sequences::schedule_next_enrollment_step(
+                    pool,
+                    enrollment.id,
+                    next_step.id,
+                    next_execution_at,
+                )
+                .await
+                .map_err(|e| format!("次のステップのスケジューリングに失敗しました: {e}"))?;
+            }
+            None => self.move_to_step(pool, enrollment, None).await?,
+        }
+
         // ステップログを記録
         self.log_step_execution(pool, enrollment.id, step.id, "wait_scheduled", None)
             .await?;
@@ -394,20 +1687,60 @@ impl SequenceService {
         Ok(())
     }
 
-    // 条件ステップの処理
+    // 待機後に次のステップを実行する日時
+    //
+    // 待機時間の経過後（`base`）、指定した曜日・時刻まで待ち、購読者のタイムゾーンでの送信時間帯に合わせる。
+    async fn schedule_next_step(
+        &self,
+        pool: &PgPool,
+        sequence: &Sequence,
+        step: &SequenceStep,
+        enrollment: &SequenceEnrollment,
+        base: DateTime<Utc>,
+    ) -> Result<DateTime<Utc>, String> {
+        let window = SendWindow::parse(&sequence.send_window)?;
+        let wait_until = WaitUntil::parse(&step.action_config)?;
+        if window.is_unrestricted() && wait_until.is_none() {
+            return Ok(base);
+        }
+
+        let subscriber =
+            subscribers::find_subscriber_by_id(pool, enrollment.subscriber_id, sequence.user_id)
+                .await
+                .map_err(|e| format!("購読者情報の取得に失敗しました: {e}"))?
+                .ok_or_else(|| "購読者が見つかりません".to_string())?;
+
+        Ok(next_execution_at(
+            base,
+            wait_until.as_ref(),
+            &window,
+            window.subscriber_timezone(&subscriber),
+        ))
+    }
+
+    // 条件ステップの処理（評価結果に応じて分岐）
     async fn process_condition_step(
         &self,
         pool: &PgPool,
+        sequence: &Sequence,
+        graph: &StepGraph<'_>,
         step: &SequenceStep,
         enrollment: &SequenceEnrollment,
     ) -> Result<(), String> {
-        // TODO: 条件評価ロジックの実装
-        // 現時点では次のステップへ進む
-        self.move_to_next_step(pool, enrollment, step.step_order + 1)
+        let matched = self
+            .evaluate_step_conditions(pool, sequence, step, enrollment)
+            .await?;
+
+        self.move_to_step(pool, enrollment, graph.branch(step, matched))
             .await?;
 
         // ステップログを記録
-        self.log_step_execution(pool, enrollment.id, step.id, "condition_evaluated", None)
+        let status = if matched {
+            "condition_met"
+        } else {
+            "condition_not_met"
+        };
+        self.log_step_execution(pool, enrollment.id, step.id, status, None)
             .await?;
 
         Ok(())
@@ -417,15 +1750,11 @@ impl SequenceService {
     async fn process_tag_step(
         &self,
         pool: &PgPool,
+        sequence: &Sequence,
         step: &SequenceStep,
+        next_step: Option<&SequenceStep>,
         enrollment: &SequenceEnrollment,
     ) -> Result<(), String> {
-        // シーケンスからuser_idを取得
-        let sequence = sequences::find_sequence_by_id(pool, enrollment.sequence_id, None)
-            .await
-            .map_err(|e| format!("シーケンスの取得に失敗しました: {e}"))?
-            .ok_or_else(|| "シーケンスが見つかりません".to_string())?;
-
         // action_configからタグを取得
         if let Some(tag) = step.action_config.get("tag").and_then(|v| v.as_str()) {
             // 購読者にタグを追加
@@ -438,7 +1767,7 @@ impl SequenceService {
             .map_err(|e| format!("購読者の取得に失敗しました: {e}"))?
             .ok_or_else(|| "購読者が見つかりません".to_string())?;
 
-            let mut tags = current_subscriber.tags;
+            let mut tags = current_subscriber.tags.clone();
             if !tags.contains(&tag.to_string()) {
                 tags.push(tag.to_string());
             }
@@ -452,7 +1781,7 @@ impl SequenceService {
                 status: None,
             };
 
-            subscribers::update_subscriber(
+            let updated = subscribers::update_subscriber(
                 pool,
                 enrollment.subscriber_id,
                 sequence.user_id,
@@ -460,11 +1789,20 @@ impl SequenceService {
             )
             .await
             .map_err(|e| format!("タグの更新に失敗しました: {e}"))?;
+
+            // タグ追加トリガーのシーケンスに登録
+            if let Some(updated) = updated {
+                if let Err(e) = self
+                    .process_subscriber_changes(pool, &current_subscriber, &updated)
+                    .await
+                {
+                    tracing::error!("シーケンスエンロールメントエラー: {}", e);
+                }
+            }
         }
 
         // 次のステップへ移動
-        self.move_to_next_step(pool, enrollment, step.step_order + 1)
-            .await?;
+        self.move_to_step(pool, enrollment, next_step).await?;
 
         // ステップログを記録
         self.log_step_execution(pool, enrollment.id, step.id, "tag_added", None)
@@ -473,53 +1811,29 @@ impl SequenceService {
         Ok(())
     }
 
-    // 次のステップへ移動
-    async fn move_to_next_step(
+    // 次のステップへ移動（次のステップがない場合は完了）
+    async fn move_to_step(
         &self,
         pool: &PgPool,
         enrollment: &SequenceEnrollment,
-        next_step_order: i32,
+        next_step: Option<&SequenceStep>,
     ) -> Result<(), String> {
-        // シーケンスの全ステップ数を取得
-        let steps = sequences::find_sequence_steps(pool, enrollment.sequence_id)
-            .await
-            .map_err(|e| format!("ステップ数の取得に失敗しました: {e}"))?;
-
-        if next_step_order > steps.len() as i32 {
-            // すべてのステップが完了
-            sequences::complete_sequence_enrollment(pool, enrollment.id)
-                .await
-                .map_err(|e| format!("エンロールメントの完了に失敗しました: {e}"))?;
-        } else {
-            // 次のステップへ更新
-            sequences::update_enrollment_progress(pool, enrollment.id, next_step_order)
-                .await
-                .map_err(|e| format!("進捗の更新に失敗しました: {e}"))?;
+        match next_step {
+            Some(next_step) => {
+                sequences::update_enrollment_progress(pool, enrollment.id, next_step.id)
+                    .await
+                    .map_err(|e| format!("進捗の更新に失敗しました: {e}"))?;
+            }
+            None => {
+                sequences::complete_sequence_enrollment(pool, enrollment.id)
+                    .await
+                    .map_err(|e| format!("エンロールメントの完了に失敗しました: {e}"))?;
+            }
         }
 
         Ok(())
     }
 
-    // 次のステップをスケジュール
-    async fn schedule_next_step(
-        &self,
-        pool: &PgPool,
-        enrollment: &SequenceEnrollment,
-        next_step_order: i32,
-        next_execution_at: DateTime<Utc>,
-    ) -> Result<(), String> {
-        sequences::schedule_next_enrollment_step(
-            pool,
-            enrollment.id,
-            next_step_order,
-            next_execution_at,
-        )
-        .await
-        .map_err(|e| format!("次のステップのスケジューリングに失敗しました: {e}"))?;
-
-        Ok(())
-    }
-
     // ステップ実行ログの記録
     async fn log_step_execution(
         &self,
@@ -529,12 +1843,79 @@ impl SequenceService {
         status: &str,
         error_message: Option<String>,
     ) -> Result<SequenceStepLog, String> {
-        sequences::create_sequence_step_log(pool, enrollment_id, step_id, status, error_message)
-            .await
-            .map_err(|e| format!("ステップログの記録に失敗しました: {e}"))
+        sequences::create_sequence_step_log(
+            pool,
+            enrollment_id,
+            step_id,
+            status,
+            error_message,
+            None,
+            None,
+        )
+        .await
+        .map_err(|e| format!("ステップログの記録に失敗しました: {e}"))
+    }
+}
+
+/// 購読者をシーケンスに登録しない理由
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EnrollmentSkip {
+    /// 登録中（実行中・一時停止中）
+    InProgress,
+    /// 再登録ポリシーで再登録できない
+    Reentry,
+}
+
+// 直前のエンロールメントのステータスから、購読者を登録できるか判定
+fn enrollment_skip(sequence: &Sequence, previous: Option<&str>) -> Option<EnrollmentSkip> {
+    let status = previous?;
+    if EnrollmentStatus::parse(status).is_some_and(|status| status.is_in_progress()) {
+        return Some(EnrollmentSkip::InProgress);
+    }
+
+    match ReentryPolicy::from(sequence.reentry_policy.clone()) {
+        ReentryPolicy::Never => Some(EnrollmentSkip::Reentry),
+        ReentryPolicy::AfterCompletion => None,
     }
 }
 
+// CSVからメールアドレスを読み込む（空の行・重複は除く）
+fn read_csv_emails(
+    csv: &str,
+    has_header: bool,
+    email_column: usize,
+    errors: &mut Vec<String>,
+) -> Vec<String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(has_header)
+        .flexible(true)
+        .from_reader(csv.as_bytes());
+
+    let mut seen = HashSet::new();
+    let mut emails = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                errors.push(format!("行 {}: CSVフォーマットエラー - {}", index + 1, e));
+                continue;
+            }
+        };
+
+        match record.get(email_column).map(str::trim) {
+            Some(email) if !email.is_empty() => {
+                if seen.insert(email.to_lowercase()) {
+                    emails.push(email.to_string());
+                }
+            }
+            _ if record.iter().all(|field| field.trim().is_empty()) => {}
+            _ => errors.push(format!("行 {}: メールアドレスは必須です", index + 1)),
+        }
+    }
+
+    emails
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -548,8 +1929,12 @@ mod tests {
             "email": "john@example.com"
         });
 
-        let result = service.replace_variables(text, &variables);
+        let result = service.replace_variables(text, &variables).unwrap();
         assert_eq!(result, "Hello John Doe, your email is john@example.com");
+
+        let text = r#"{{ company | default: "皆様" }}へ{{#if plan}}（{{plan}}）{{/if}}"#;
+        let result = service.replace_variables(text, &variables).unwrap();
+        assert_eq!(result, "皆様へ");
     }
 
     #[test]
@@ -567,6 +1952,9 @@ mod tests {
                 .to_string(),
             active_subscribers: 0,
             completed_subscribers: 0,
+            reentry_policy: "never".to_string(),
+            exit_conditions: json!([]),
+            send_window: json!({}),
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -594,6 +1982,9 @@ mod tests {
                 .to_string(),
             active_subscribers: 0,
             completed_subscribers: 0,
+            reentry_policy: "never".to_string(),
+            exit_conditions: json!([]),
+            send_window: json!({}),
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -614,4 +2005,75 @@ mod tests {
         assert!(result.is_ok());
         assert!(!result.unwrap());
     }
+
+    #[test]
+    fn test_enrollment_skip_follows_reentry_policy() {
+        let mut sequence = Sequence {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            name: "Test Sequence".to_string(),
+            description: None,
+            trigger_type: TriggerType::Manual.as_str().to_string(),
+            trigger_config: json!({}),
+            status: SequenceStatus::Active.as_str().to_string(),
+            active_subscribers: 0,
+            completed_subscribers: 0,
+            reentry_policy: ReentryPolicy::Never.as_str().to_string(),
+            exit_conditions: json!([]),
+            send_window: json!({}),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        assert_eq!(enrollment_skip(&sequence, None), None);
+        assert_eq!(
+            enrollment_skip(&sequence, Some("paused")),
+            Some(EnrollmentSkip::InProgress)
+        );
+        assert_eq!(
+            enrollment_skip(&sequence, Some("completed")),
+            Some(EnrollmentSkip::Reentry)
+        );
+
+        sequence.reentry_policy = ReentryPolicy::AfterCompletion.as_str().to_string();
+        assert_eq!(
+            enrollment_skip(&sequence, Some("active")),
+            Some(EnrollmentSkip::InProgress)
+        );
+        assert_eq!(enrollment_skip(&sequence, Some("completed")), None);
+        assert_eq!(enrollment_skip(&sequence, Some("cancelled")), None);
+    }
+
+    #[test]
+    fn test_read_csv_emails() {
+        let mut errors = Vec::new();
+        let emails = read_csv_emails(
+            "email\na@example.com\n\nA@Example.com\n b@example.com \n",
+            true,
+            0,
+            &mut errors,
+        );
+        assert_eq!(emails, vec!["a@example.com", "b@example.com"]);
+        assert!(errors.is_empty());
+
+        let emails = read_csv_emails("名前,c@example.com\n名前のみ\n", false, 1, &mut errors);
+        assert_eq!(emails, vec!["c@example.com"]);
+        assert_eq!(errors, vec!["行 2: メールアドレスは必須です"]);
+    }
+
+    #[test]
+    fn test_step_retry_policy() {
+        assert_eq!(step_retry_delay(1), Duration::minutes(1));
+        assert_eq!(step_retry_delay(3), Duration::minutes(4));
+        assert_eq!(step_retry_delay(30), Duration::hours(6));
+
+        // 一時的なエラーは上限の回数まで再実行する
+        let retryable = StepError::Retryable("接続エラー".to_string());
+        assert!(!retryable.should_abandon(1));
+        assert!(!retryable.should_abandon(STEP_MAX_FAILURES - 1));
+        assert!(retryable.should_abandon(STEP_MAX_FAILURES));
+
+        // 再実行しても成功しないエラーは1回目で打ち切る
+        assert!(StepError::Permanent("拒否".to_string()).should_abandon(1));
+    }
 }
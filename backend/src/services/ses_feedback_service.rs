@@ -0,0 +1,585 @@
+// SESのバウンス・苦情・配信通知（SNS経由）の受信サービス
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Duration, Utc};
+use lazy_static::lazy_static;
+use openssl::{asn1::Asn1Time, hash::MessageDigest, sign::Verifier, x509::X509};
+use regex::Regex;
+use reqwest::Url;
+use sqlx::PgPool;
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::{
+    database::{campaign_deliveries, subscribers},
+    models::{
+        ses_feedback::{SesFeedbackResult, SesNotification, SesRecipient, SnsEnvelope},
+        subscriber::SubscriberStatus,
+    },
+};
+
+/// SNSメッセージの検証エラー
+#[derive(Error, Debug)]
+pub enum SnsVerificationError {
+    #[error("SNSメッセージの形式が不正です: {0}")]
+    Malformed(String),
+    #[error("署名証明書のURLが不正です: {0}")]
+    InvalidCertUrl(String),
+    #[error("署名証明書の取得に失敗しました: {0}")]
+    CertFetch(String),
+    #[error("未対応の署名バージョンです: {0}")]
+    UnsupportedSignatureVersion(String),
+    #[error("SNSメッセージの署名が不正です")]
+    InvalidSignature,
+    #[error("許可されていないトピックです: {0}")]
+    TopicNotAllowed(String),
+    #[error("受け付けるSNSトピック（SES_SNS_TOPIC_ARNS）が設定されていません")]
+    TopicsNotConfigured,
+    #[error("SNSメッセージの有効期間外です: {0}")]
+    Expired(String),
+}
+
+/// 受け付けるSNSメッセージの送信日時からの経過時間（署名済みのメッセージの再送を防ぐ）
+const SNS_MESSAGE_MAX_AGE_MINUTES: i64 = 60;
+/// SNSとの時刻のずれの許容範囲
+const SNS_CLOCK_SKEW_MINUTES: i64 = 5;
+
+lazy_static! {
+    static ref SNS_HOST: Regex = Regex::new(r"^sns\.[a-z0-9-]+\.amazonaws\.com(\.cn)?$").unwrap();
+    /// 署名証明書のキャッシュ（証明書URLごと）
+    static ref SIGNING_CERTS: RwLock<HashMap<String, Vec<u8>>> = RwLock::new(HashMap::new());
+}
+
+pub struct SesFeedbackService {
+    http: reqwest::Client,
+    allowed_topic_arns: Vec<String>,
+}
+
+impl Default for SesFeedbackService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SesFeedbackService {
+    /// `SES_SNS_TOPIC_ARNS`（カンマ区切り）のトピックのみ受け付ける（未設定の場合はすべて拒否する）
+    pub fn new() -> Self {
+        let topic_arns = std::env::var("SES_SNS_TOPIC_ARNS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|arn| arn.trim().to_string())
+            .filter(|arn| !arn.is_empty())
+            .collect();
+        Self::with_allowed_topic_arns(topic_arns)
+    }
+
+    pub fn with_allowed_topic_arns(allowed_topic_arns: Vec<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            allowed_topic_arns,
+        }
+    }
+
+    /// リクエストボディをSNSエンベロープとして解析
+    pub fn parse_envelope(body: &str) -> Result<SnsEnvelope, SnsVerificationError> {
+        serde_json::from_str(body).map_err(|e| SnsVerificationError::Malformed(e.to_string()))
+    }
+
+    /// 署名証明書をキャッシュに登録（取得済みの証明書の再利用や、オフライン環境での検証用）
+    pub fn cache_signing_certificate(url: &str, pem: &[u8]) {
+        SIGNING_CERTS
+            .write()
+            .unwrap()
+            .insert(url.to_string(), pem.to_vec());
+    }
+
+    /// 受け付けるトピックが設定されているか
+    pub fn is_configured(&self) -> bool {
+        !self.allowed_topic_arns.is_empty()
+    }
+
+    /// トピック・署名証明書・署名・送信日時を検証
+    ///
+    /// 受け付けるトピックが未設定の場合は、サブスクリプションの確認を含めてすべて拒否する。
+    pub async fn verify(&self, envelope: &SnsEnvelope) -> Result<(), SnsVerificationError> {
+        if !self.is_configured() {
+            return Err(SnsVerificationError::TopicsNotConfigured);
+        }
+        if !self.allowed_topic_arns.contains(&envelope.topic_arn) {
+            return Err(SnsVerificationError::TopicNotAllowed(
+                envelope.topic_arn.clone(),
+            ));
+        }
+
+        let cert_url = Self::validate_sns_url(&envelope.signing_cert_url)
+            .map_err(SnsVerificationError::InvalidCertUrl)?;
+        if !cert_url.path().ends_with(".pem") {
+            return Err(SnsVerificationError::InvalidCertUrl(
+                envelope.signing_cert_url.clone(),
+            ));
+        }
+
+        let pem = self.signing_certificate(cert_url).await?;
+        Self::verify_signature(envelope, &pem)?;
+        Self::verify_timestamp(envelope, Utc::now())
+    }
+
+    /// 送信日時（Timestamp）が有効期間内か検証
+    pub fn verify_timestamp(
+        envelope: &SnsEnvelope,
+        now: DateTime<Utc>,
+    ) -> Result<(), SnsVerificationError> {
+        let timestamp = DateTime::parse_from_rfc3339(&envelope.timestamp)
+            .map_err(|_| SnsVerificationError::Malformed(envelope.timestamp.clone()))?
+            .with_timezone(&Utc);
+
+        if timestamp < now - Duration::minutes(SNS_MESSAGE_MAX_AGE_MINUTES)
+            || timestamp > now + Duration::minutes(SNS_CLOCK_SKEW_MINUTES)
+        {
+            return Err(SnsVerificationError::Expired(envelope.timestamp.clone()));
+        }
+        Ok(())
+    }
+
+    /// 署名証明書（PEM）で署名を検証
+    pub fn verify_signature(
+        envelope: &SnsEnvelope,
+        pem: &[u8],
+    ) -> Result<(), SnsVerificationError> {
+        let digest = match envelope.signature_version.as_str() {
+            "1" => MessageDigest::sha1(),
+            "2" => MessageDigest::sha256(),
+            other => {
+                return Err(SnsVerificationError::UnsupportedSignatureVersion(
+                    other.to_string(),
+                ))
+            }
+        };
+
+        let cert =
+            X509::from_pem(pem).map_err(|e| SnsVerificationError::CertFetch(e.to_string()))?;
+        let now = Asn1Time::days_from_now(0)
+            .map_err(|e| SnsVerificationError::CertFetch(e.to_string()))?;
+        if cert.not_before() > now || cert.not_after() < now {
+            return Err(SnsVerificationError::CertFetch(
+                "署名証明書の有効期限外です".to_string(),
+            ));
+        }
+
+        let public_key = cert
+            .public_key()
+            .map_err(|e| SnsVerificationError::CertFetch(e.to_string()))?;
+        let signature = STANDARD
+            .decode(&envelope.signature)
+            .map_err(|_| SnsVerificationError::InvalidSignature)?;
+        let string_to_sign = Self::string_to_sign(envelope)?;
+
+        let verified = Verifier::new(digest, &public_key)
+            .and_then(|mut verifier| {
+                verifier.update(string_to_sign.as_bytes())?;
+                verifier.verify(&signature)
+            })
+            .unwrap_or(false);
+
+        if verified {
+            Ok(())
+        } else {
+            Err(SnsVerificationError::InvalidSignature)
+        }
+    }
+
+    /// 署名対象の文字列（メッセージ種別ごとに決められたキーを辞書順に連結したもの）
+    pub fn string_to_sign(envelope: &SnsEnvelope) -> Result<String, SnsVerificationError> {
+        let mut fields = vec![
+            ("Message", Some(envelope.message.as_str())),
+            ("MessageId", Some(envelope.message_id.as_str())),
+        ];
+
+        match envelope.message_type.as_str() {
+            "Notification" => {
+                fields.push(("Subject", envelope.subject.as_deref()));
+            }
+            "SubscriptionConfirmation" | "UnsubscribeConfirmation" => {
+                let (Some(subscribe_url), Some(token)) =
+                    (envelope.subscribe_url.as_deref(), envelope.token.as_deref())
+                else {
+                    return Err(SnsVerificationError::Malformed(
+                        "SubscribeURLまたはTokenがありません".to_string(),
+                    ));
+                };
+                fields.push(("SubscribeURL", Some(subscribe_url)));
+                fields.push(("Timestamp", Some(envelope.timestamp.as_str())));
+                fields.push(("Token", Some(token)));
+            }
+            other => {
+                return Err(SnsVerificationError::Malformed(format!(
+                    "未対応のメッセージ種別です: {other}"
+                )))
+            }
+        }
+
+        if envelope.is_notification() {
+            fields.push(("Timestamp", Some(envelope.timestamp.as_str())));
+        }
+        fields.push(("TopicArn", Some(envelope.topic_arn.as_str())));
+        fields.push(("Type", Some(envelope.message_type.as_str())));
+
+        Ok(fields
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|value| format!("{key}\n{value}\n")))
+            .collect())
+    }
+
+    /// SNSのエンドポイント（https://sns.<region>.amazonaws.com）のURLか検証
+    fn validate_sns_url(url: &str) -> Result<Url, String> {
+        let parsed = Url::parse(url).map_err(|_| url.to_string())?;
+        let host_is_sns = parsed
+            .host_str()
+            .map(|host| SNS_HOST.is_match(host))
+            .unwrap_or(false);
+        if parsed.scheme() != "https" || !host_is_sns {
+            return Err(url.to_string());
+        }
+        Ok(parsed)
+    }
+
+    /// 署名証明書を取得（取得済みの場合はキャッシュを使用）
+    async fn signing_certificate(&self, url: Url) -> Result<Vec<u8>, SnsVerificationError> {
+        if let Some(pem) = SIGNING_CERTS.read().unwrap().get(url.as_str()) {
+            return Ok(pem.clone());
+        }
+
+        let response = self
+            .http
+            .get(url.clone())
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| SnsVerificationError::CertFetch(e.to_string()))?;
+        let pem = response
+            .bytes()
+            .await
+            .map_err(|e| SnsVerificationError::CertFetch(e.to_string()))?
+            .to_vec();
+
+        Self::cache_signing_certificate(url.as_str(), &pem);
+        Ok(pem)
+    }
+
+    /// SNSトピックのサブスクリプションを確認（SubscribeURLにアクセスする）
+    pub async fn confirm_subscription(&self, envelope: &SnsEnvelope) -> Result<(), String> {
+        let subscribe_url = envelope
+            .subscribe_url
+            .as_deref()
+            .ok_or_else(|| "SubscribeURLがありません".to_string())?;
+        let url = Self::validate_sns_url(subscribe_url)
+            .map_err(|url| format!("SubscribeURLが不正です: {url}"))?;
+
+        self.http
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("サブスクリプションの確認に失敗しました: {e}"))?;
+
+        info!(
+            "SNSトピック {} のサブスクリプションを確認しました",
+            envelope.topic_arn
+        );
+        Ok(())
+    }
+
+    /// SNSメッセージ本文をSES通知として解析
+    pub fn parse_notification(
+        envelope: &SnsEnvelope,
+    ) -> Result<SesNotification, SnsVerificationError> {
+        serde_json::from_str(&envelope.message)
+            .map_err(|e| SnsVerificationError::Malformed(e.to_string()))
+    }
+
+    /// SES通知を配信レコードと購読者に反映
+    ///
+    /// ハードバウンスと苦情の場合は購読者を配信対象外のステータスに変更する。
+    pub async fn process_notification(
+        &self,
+        pool: &PgPool,
+        notification: &SesNotification,
+    ) -> Result<SesFeedbackResult, String> {
+        let delivery =
+            campaign_deliveries::find_delivery_by_message_id(pool, &notification.mail.message_id)
+                .await
+                .map_err(|e| format!("配信レコードの取得に失敗しました: {e}"))?;
+
+        let Some(delivery) = delivery else {
+            return self
+                .process_notification_without_delivery(pool, notification)
+                .await;
+        };
+
+        let mut result = SesFeedbackResult {
+            delivery_id: Some(delivery.id),
+            ..Default::default()
+        };
+
+        let suppress_status = match notification.notification_type.as_str() {
+            "Bounce" => {
+                let bounce = notification
+                    .bounce
+                    .as_ref()
+                    .ok_or_else(|| "バウンス情報がありません".to_string())?;
+                campaign_deliveries::mark_delivery_bounced(pool, delivery.id, &bounce.bounce_type)
+                    .await
+                    .map_err(|e| format!("バウンスの記録に失敗しました: {e}"))?;
+
+                (bounce.is_permanent()
+                    && Self::includes_recipient(&bounce.bounced_recipients, &delivery.email))
+                .then_some(SubscriberStatus::Bounced)
+            }
+            "Complaint" => {
+                let complaint = notification
+                    .complaint
+                    .as_ref()
+                    .ok_or_else(|| "苦情情報がありません".to_string())?;
+                campaign_deliveries::mark_delivery_complained(pool, delivery.id)
+                    .await
+                    .map_err(|e| format!("苦情の記録に失敗しました: {e}"))?;
+
+                Self::includes_recipient(&complaint.complained_recipients, &delivery.email)
+                    .then_some(SubscriberStatus::Complained)
+            }
+            "Delivery" => {
+                campaign_deliveries::mark_delivery_delivered(pool, delivery.id)
+                    .await
+                    .map_err(|e| format!("配信完了の記録に失敗しました: {e}"))?;
+                None
+            }
+            other => {
+                info!("未対応のSES通知種別のため無視します: {}", other);
+                None
+            }
+        };
+
+        if let Some(status) = suppress_status {
+            let suppressed = subscribers::suppress_subscriber(pool, delivery.subscriber_id, status)
+                .await
+                .map_err(|e| format!("購読者ステータスの更新に失敗しました: {e}"))?;
+            if let Some(subscriber) = suppressed {
+                info!(
+                    "購読者 {} を配信対象外にしました（{}）",
+                    subscriber.id, notification.notification_type
+                );
+                result.suppressed_subscriber_ids.push(subscriber.id);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// 配信レコードのないSES通知（シーケンスや確認メールなどキャンペーン以外のメール）を購読者に反映
+    ///
+    /// メッセージIDから送信先の購読者を特定する。特定できない場合は他のユーザーの購読者を
+    /// 更新しないよう、ログに記録して通知を破棄する。
+    async fn process_notification_without_delivery(
+        &self,
+        pool: &PgPool,
+        notification: &SesNotification,
+    ) -> Result<SesFeedbackResult, String> {
+        let (status, recipients) = match (
+            notification.notification_type.as_str(),
+            &notification.bounce,
+            &notification.complaint,
+        ) {
+            ("Bounce", Some(bounce), _) if bounce.is_permanent() => {
+                (SubscriberStatus::Bounced, &bounce.bounced_recipients)
+            }
+            ("Complaint", _, Some(complaint)) => (
+                SubscriberStatus::Complained,
+                &complaint.complained_recipients,
+            ),
+            _ => {
+                info!(
+                    "メッセージID {} に対応する配信レコードがないためSES通知を無視します",
+                    notification.mail.message_id
+                );
+                return Ok(SesFeedbackResult::default());
+            }
+        };
+
+        let subscriber =
+            subscribers::find_subscriber_by_message_id(pool, &notification.mail.message_id)
+                .await
+                .map_err(|e| format!("購読者の取得に失敗しました: {e}"))?;
+        let Some(subscriber) = subscriber else {
+            warn!(
+                "メッセージID {} の送信先の購読者が見つからないためSES通知を破棄します（{}）",
+                notification.mail.message_id, notification.notification_type
+            );
+            return Ok(SesFeedbackResult::default());
+        };
+
+        let mut result = SesFeedbackResult::default();
+        if !Self::includes_recipient(recipients, &subscriber.email) {
+            return Ok(result);
+        }
+
+        let suppressed = subscribers::suppress_subscriber(pool, subscriber.id, status)
+            .await
+            .map_err(|e| format!("購読者ステータスの更新に失敗しました: {e}"))?;
+        if let Some(subscriber) = suppressed {
+            info!(
+                "購読者 {} を配信対象外にしました（{}、配信レコードなし）",
+                subscriber.id, notification.notification_type
+            );
+            result.suppressed_subscriber_ids.push(subscriber.id);
+        }
+
+        Ok(result)
+    }
+
+    /// 通知の対象者に配信レコードの宛先が含まれるか（対象者の記載がない場合は含まれるとみなす）
+    fn includes_recipient(recipients: &[SesRecipient], email: &str) -> bool {
+        recipients.is_empty()
+            || recipients
+                .iter()
+                .any(|recipient| recipient.email_address.eq_ignore_ascii_case(email))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn envelope(message_type: &str) -> SnsEnvelope {
+        SnsEnvelope {
+            message_type: message_type.to_string(),
+            message_id: "mid".to_string(),
+            topic_arn: "arn:aws:sns:ap-northeast-1:123456789012:ses-feedback".to_string(),
+            message: "body".to_string(),
+            timestamp: "2025-08-12T00:00:00.000Z".to_string(),
+            signature_version: "1".to_string(),
+            signature: "".to_string(),
+            signing_cert_url: "https://sns.ap-northeast-1.amazonaws.com/cert.pem".to_string(),
+            subject: None,
+            token: Some("token".to_string()),
+            subscribe_url: Some(
+                "https://sns.ap-northeast-1.amazonaws.com/?Action=Confirm".to_string(),
+            ),
+        }
+    }
+
+    #[test]
+    fn test_string_to_sign_notification() {
+        let mut notification = envelope("Notification");
+        assert_eq!(
+            SesFeedbackService::string_to_sign(&notification).unwrap(),
+            "Message\nbody\nMessageId\nmid\nTimestamp\n2025-08-12T00:00:00.000Z\nTopicArn\narn:aws:sns:ap-northeast-1:123456789012:ses-feedback\nType\nNotification\n"
+        );
+
+        // 件名がある場合のみ署名対象に含まれる
+        notification.subject = Some("subject".to_string());
+        assert!(SesFeedbackService::string_to_sign(&notification)
+            .unwrap()
+            .contains("MessageId\nmid\nSubject\nsubject\nTimestamp\n"));
+    }
+
+    #[test]
+    fn test_string_to_sign_subscription_confirmation() {
+        assert_eq!(
+            SesFeedbackService::string_to_sign(&envelope("SubscriptionConfirmation")).unwrap(),
+            "Message\nbody\nMessageId\nmid\nSubscribeURL\nhttps://sns.ap-northeast-1.amazonaws.com/?Action=Confirm\nTimestamp\n2025-08-12T00:00:00.000Z\nToken\ntoken\nTopicArn\narn:aws:sns:ap-northeast-1:123456789012:ses-feedback\nType\nSubscriptionConfirmation\n"
+        );
+        assert!(SesFeedbackService::string_to_sign(&envelope("Unknown")).is_err());
+    }
+
+    #[test]
+    fn test_validate_sns_url() {
+        assert!(
+            SesFeedbackService::validate_sns_url("https://sns.us-east-1.amazonaws.com/a.pem")
+                .is_ok()
+        );
+        assert!(SesFeedbackService::validate_sns_url(
+            "https://sns.cn-north-1.amazonaws.com.cn/a.pem"
+        )
+        .is_ok());
+        assert!(
+            SesFeedbackService::validate_sns_url("http://sns.us-east-1.amazonaws.com/a.pem")
+                .is_err()
+        );
+        assert!(SesFeedbackService::validate_sns_url(
+            "https://sns.us-east-1.amazonaws.com.example.com/a.pem"
+        )
+        .is_err());
+        assert!(SesFeedbackService::validate_sns_url("https://example.com/a.pem").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_topic_allowlist() {
+        let service = SesFeedbackService::with_allowed_topic_arns(vec![
+            "arn:aws:sns:ap-northeast-1:123456789012:other".to_string(),
+        ]);
+
+        let result = service.verify(&envelope("Notification")).await;
+        assert!(matches!(
+            result,
+            Err(SnsVerificationError::TopicNotAllowed(_))
+        ));
+
+        // トピックが未設定の場合はすべて拒否する
+        let service = SesFeedbackService::with_allowed_topic_arns(vec![]);
+        let result = service.verify(&envelope("SubscriptionConfirmation")).await;
+        assert!(matches!(
+            result,
+            Err(SnsVerificationError::TopicsNotConfigured)
+        ));
+    }
+
+    #[test]
+    fn test_verify_timestamp() {
+        let notification = envelope("Notification");
+        let sent_at = DateTime::parse_from_rfc3339(&notification.timestamp)
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert!(SesFeedbackService::verify_timestamp(&notification, sent_at).is_ok());
+        assert!(SesFeedbackService::verify_timestamp(
+            &notification,
+            sent_at + Duration::minutes(SNS_MESSAGE_MAX_AGE_MINUTES - 1)
+        )
+        .is_ok());
+
+        // 古いメッセージの再送は拒否する
+        assert!(matches!(
+            SesFeedbackService::verify_timestamp(
+                &notification,
+                sent_at + Duration::minutes(SNS_MESSAGE_MAX_AGE_MINUTES + 1)
+            ),
+            Err(SnsVerificationError::Expired(_))
+        ));
+        assert!(matches!(
+            SesFeedbackService::verify_timestamp(&notification, sent_at - Duration::hours(1)),
+            Err(SnsVerificationError::Expired(_))
+        ));
+
+        let mut malformed = envelope("Notification");
+        malformed.timestamp = "yesterday".to_string();
+        assert!(SesFeedbackService::verify_timestamp(&malformed, sent_at).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_non_sns_certificate_url() {
+        let service = SesFeedbackService::with_allowed_topic_arns(vec![
+            "arn:aws:sns:ap-northeast-1:123456789012:ses-feedback".to_string(),
+        ]);
+        let mut notification = envelope("Notification");
+        notification.signing_cert_url = "https://attacker.example.com/cert.pem".to_string();
+
+        let result = service.verify(&notification).await;
+        assert!(matches!(
+            result,
+            Err(SnsVerificationError::InvalidCertUrl(_))
+        ));
+    }
+}
@@ -271,7 +271,6 @@ pub async fn record_usage(
 }
 
 /// 機能が利用可能かチェック
-#[allow(dead_code)]
 pub async fn check_feature_access(pool: &PgPool, user_id: Uuid, feature: &str) -> Result<bool> {
     // サブスクリプション情報を取得
     let subscription = subscriptions::get_user_subscription(pool, user_id)
@@ -0,0 +1,776 @@
+// テンプレート言語（Handlebars）による件名・本文の変数展開
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use handlebars::{
+    no_escape,
+    template::{Parameter, TemplateElement},
+    Context, Handlebars, Helper, HelperDef, Path, RenderContext, RenderError, ScopedJson, Template,
+};
+use lazy_static::lazy_static;
+use serde_json::Value;
+
+/// 日付の標準の書式
+const DEFAULT_DATE_FORMAT: &str = "%Y年%m月%d日";
+
+/// Handlebarsの組み込みヘルパーと独自ヘルパー（変数の抽出で除外する）
+const HELPER_NAMES: &[&str] = &[
+    "if", "unless", "each", "with", "lookup", "raw", "log", "eq", "ne", "gt", "gte", "lt", "lte",
+    "and", "or", "not", "len", "default", "date", "number",
+];
+
+/// 中の変数参照が要素を基準にするブロックヘルパー
+const SCOPED_BLOCK_HELPERS: &[&str] = &["each", "with"];
+
+lazy_static! {
+    static ref ENGINE: TemplateEngine = TemplateEngine::new();
+}
+
+/// テンプレートで使用している変数
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TemplateVariables {
+    /// 使用している変数（出現順、重複なし）
+    pub variables: Vec<String>,
+    /// 値がなくても描画できる変数（`default`フィルターや`if`の条件で参照しているもの）
+    pub optional_variables: Vec<String>,
+}
+
+impl TemplateVariables {
+    fn add(&mut self, name: &str, optional: bool) {
+        if !self.variables.iter().any(|v| v == name) {
+            self.variables.push(name.to_string());
+        }
+        if optional && !self.optional_variables.iter().any(|v| v == name) {
+            self.optional_variables.push(name.to_string());
+        }
+    }
+
+    /// 別のテンプレート（件名など）の変数を追加
+    pub fn merge(&mut self, other: &TemplateVariables) {
+        for name in &other.variables {
+            self.add(name, other.optional_variables.contains(name));
+        }
+    }
+
+    /// 値が必須の変数
+    pub fn required_variables(&self) -> Vec<String> {
+        self.variables
+            .iter()
+            .filter(|v| !self.optional_variables.contains(v))
+            .cloned()
+            .collect()
+    }
+}
+
+/// 件名・本文のテンプレートエンジン
+///
+/// Handlebarsの構文（`{{#if}}`・`{{#each}}`・`{{else}}`など）に加えて、`{{ name | default: "お客様" }}`
+/// のようなフィルター記法を使える。本文では`{{name}}`の値をHTMLエスケープし、`{{{name}}}`は
+/// そのまま埋め込む。件名はプレーンテキストなのでエスケープしない。値のない変数は空文字になる。
+pub struct TemplateEngine {
+    html: Handlebars<'static>,
+    text: Handlebars<'static>,
+    component: Handlebars<'static>,
+}
+
+impl Default for TemplateEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TemplateEngine {
+    pub fn new() -> Self {
+        let mut html = Handlebars::new();
+        register_helpers(&mut html);
+
+        let mut text = Handlebars::new();
+        text.register_escape_fn(no_escape);
+        register_helpers(&mut text);
+
+        let mut component = Handlebars::new();
+        component.register_escape_fn(escape_outside_tags);
+        register_helpers(&mut component);
+
+        Self {
+            html,
+            text,
+            component,
+        }
+    }
+
+    /// 共有のインスタンス
+    pub fn shared() -> &'static TemplateEngine {
+        &ENGINE
+    }
+
+    /// マークダウン本文を描画（値はHTMLエスケープする）
+    pub fn render_markdown(&self, template: &str, variables: &Value) -> Result<String, String> {
+        render(&self.html, template, variables)
+    }
+
+    /// 件名などのプレーンテキストを描画
+    pub fn render_text(&self, template: &str, variables: &Value) -> Result<String, String> {
+        render(&self.text, template, variables)
+    }
+
+    /// マークダウンコンポーネントのHTMLテンプレートを属性で描画
+    ///
+    /// 属性値はHTMLエスケープするが、`{{product_url}}`のような変数の参照は本文の描画時に
+    /// 展開できるようにそのまま残す。
+    pub fn render_component(&self, template: &str, attributes: &Value) -> Result<String, String> {
+        render(&self.component, template, attributes)
+    }
+
+    /// テンプレートの構文を検証
+    pub fn validate(&self, template: &str) -> Result<(), String> {
+        compile(template).map(|_| ())
+    }
+
+    /// テンプレートで使用している変数を抽出（ループ内の要素の参照やヘルパー名は含めない）
+    pub fn analyze(&self, template: &str) -> Result<TemplateVariables, String> {
+        let compiled = compile(template)?;
+        let mut variables = TemplateVariables::default();
+        collect_template(&compiled, 0, &mut variables);
+        Ok(variables)
+    }
+}
+
+fn compile(template: &str) -> Result<Template, String> {
+    Template::compile(&translate_filters(template))
+        .map_err(|e| format!("テンプレートの構文が正しくありません: {e}"))
+}
+
+fn render(
+    registry: &Handlebars<'static>,
+    template: &str,
+    variables: &Value,
+) -> Result<String, String> {
+    registry
+        .render_template(&translate_filters(template), variables)
+        .map_err(|e| format!("テンプレートの描画に失敗しました: {e}"))
+}
+
+fn register_helpers(registry: &mut Handlebars<'static>) {
+    registry.register_helper("default", Box::new(DefaultHelper));
+    registry.register_helper("date", Box::new(DateHelper));
+    registry.register_helper("number", Box::new(NumberHelper));
+}
+
+// ---------------------------------------------------------------------------
+// フィルター記法
+// ---------------------------------------------------------------------------
+
+/// `{{ value | filter: arg1, arg2 | filter2 }}`をヘルパー呼び出し`{{filter2 (filter value arg1 arg2)}}`に変換
+fn translate_filters(source: &str) -> String {
+    let mut output = String::with_capacity(source.len());
+    let mut rest = source;
+
+    while let Some(start) = rest.find("{{") {
+        // `\{{`はエスケープされたテキスト
+        if rest[..start].ends_with('\\') {
+            output.push_str(&rest[..start + 2]);
+            rest = &rest[start + 2..];
+            continue;
+        }
+        output.push_str(&rest[..start]);
+
+        let tag = &rest[start..];
+        let (open, close) = if tag.starts_with("{{{") {
+            ("{{{", "}}}")
+        } else {
+            ("{{", "}}")
+        };
+        let Some(end) = find_outside_quotes(&tag[open.len()..], close) else {
+            output.push_str(tag);
+            return output;
+        };
+        let inner = &tag[open.len()..open.len() + end];
+
+        output.push_str(open);
+        match translate_expression(inner) {
+            Some(expression) => output.push_str(&expression),
+            None => output.push_str(inner),
+        }
+        output.push_str(close);
+        rest = &tag[open.len() + end + close.len()..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// 引用符の外側で`pattern`が最初に現れる位置
+fn find_outside_quotes(text: &str, pattern: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if text[i..].starts_with(pattern) => return Some(i),
+            None => {}
+        }
+    }
+    None
+}
+
+/// 引用符と括弧の外側で区切る
+fn split_outside_quotes(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quote = None;
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '(' => depth += 1,
+                ')' => depth = depth.saturating_sub(1),
+                c if c == separator && depth == 0 => {
+                    parts.push(&text[start..i]);
+                    start = i + c.len_utf8();
+                }
+                _ => {}
+            },
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// フィルター記法の式をヘルパー呼び出しに変換（フィルターがなければ`None`）
+fn translate_expression(inner: &str) -> Option<String> {
+    let trimmed = inner.trim();
+    let omit_pre = trimmed.starts_with('~');
+    let omit_post = trimmed.ends_with('~') && trimmed.len() > 1;
+    let body = trimmed.trim_start_matches('~').trim_end_matches('~').trim();
+
+    // ブロック・コメント・パーシャルなどはそのまま
+    if body.starts_with(['#', '/', '!', '>', '^', '&', '*'])
+        || body == "else"
+        || body.starts_with("else ")
+    {
+        return None;
+    }
+
+    let parts = split_outside_quotes(body, '|');
+    if parts.len() < 2 {
+        return None;
+    }
+
+    let value = parts[0].trim();
+    if value.is_empty() {
+        return None;
+    }
+    let mut expression = if value.contains(char::is_whitespace) && !value.starts_with('(') {
+        format!("({value})")
+    } else {
+        value.to_string()
+    };
+
+    for filter in &parts[1..] {
+        let (name, args) = filter.split_once(':').unwrap_or((filter, ""));
+        let name = name.trim();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return None;
+        }
+        let mut call = format!("({name} {expression}");
+        for arg in split_outside_quotes(args, ',') {
+            let arg = arg.trim();
+            if !arg.is_empty() {
+                call.push(' ');
+                call.push_str(arg);
+            }
+        }
+        call.push(')');
+        expression = call;
+    }
+
+    // 最も外側の括弧を外してヘルパー呼び出しにする
+    let expression = &expression[1..expression.len() - 1];
+    Some(format!(
+        "{}{expression}{}",
+        if omit_pre { "~" } else { "" },
+        if omit_post { "~" } else { "" }
+    ))
+}
+
+/// `{{...}}`の外側だけをHTMLエスケープする
+pub fn escape_outside_tags(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        let mut end = start + end + 2;
+        if rest[end..].starts_with('}') {
+            end += 1;
+        }
+        output.push_str(&handlebars::html_escape(&rest[..start]));
+        output.push_str(&rest[start..end]);
+        rest = &rest[end..];
+    }
+    output.push_str(&handlebars::html_escape(rest));
+    output
+}
+
+// ---------------------------------------------------------------------------
+// ヘルパー
+// ---------------------------------------------------------------------------
+
+/// 値が空（未定義・null・false・空文字・空配列）
+fn is_blank(value: &Value) -> bool {
+    match value {
+        Value::Null | Value::Bool(false) => true,
+        Value::String(s) => s.trim().is_empty(),
+        Value::Array(values) => values.is_empty(),
+        _ => false,
+    }
+}
+
+fn param_value<'a>(h: &'a Helper, index: usize) -> Option<&'a Value> {
+    h.param(index)
+        .map(|param| param.value())
+        .filter(|value| !value.is_null())
+}
+
+/// `{{ value | default: "代わりの値" }}`: 値が空の場合は代わりの値
+struct DefaultHelper;
+
+impl HelperDef for DefaultHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'reg, 'rc>, RenderError> {
+        let value = param_value(h, 0).filter(|value| !is_blank(value));
+        let fallback = param_value(h, 1);
+        Ok(ScopedJson::Derived(
+            value.or(fallback).cloned().unwrap_or(Value::Null),
+        ))
+    }
+}
+
+/// `{{ value | date: "%Y/%m/%d" }}`: 日時（RFC 3339・`YYYY-MM-DD`・UNIX秒）を書式化
+///
+/// `now`・`today`は現在日時。解釈できない値はそのまま表示する。
+struct DateHelper;
+
+impl HelperDef for DateHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'reg, 'rc>, RenderError> {
+        let Some(value) = param_value(h, 0) else {
+            return Ok(ScopedJson::Derived(Value::Null));
+        };
+        let format = param_value(h, 1)
+            .and_then(Value::as_str)
+            .unwrap_or(DEFAULT_DATE_FORMAT);
+
+        let formatted = parse_date(value)
+            .and_then(|date| format_date(&date, format))
+            .unwrap_or_else(|| value_text(value));
+        Ok(ScopedJson::Derived(Value::String(formatted)))
+    }
+}
+
+fn parse_date(value: &Value) -> Option<DateTime<FixedOffset>> {
+    let utc = FixedOffset::east_opt(0)?;
+    match value {
+        Value::Number(n) => n
+            .as_i64()
+            .and_then(|secs| utc.timestamp_opt(secs, 0).single()),
+        Value::String(s) => {
+            let s = s.trim();
+            if s == "now" || s == "today" {
+                return Some(Utc::now().fixed_offset());
+            }
+            DateTime::parse_from_rfc3339(s)
+                .ok()
+                .or_else(|| {
+                    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+                        .ok()
+                        .map(|dt| utc.from_utc_datetime(&dt))
+                })
+                .or_else(|| {
+                    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                        .ok()
+                        .and_then(|d| d.and_hms_opt(0, 0, 0))
+                        .map(|dt| utc.from_utc_datetime(&dt))
+                })
+        }
+        _ => None,
+    }
+}
+
+/// 書式が不正な場合は`None`（chronoは不正な書式の表示でパニックする）
+fn format_date(date: &DateTime<FixedOffset>, format: &str) -> Option<String> {
+    use chrono::format::{Item, StrftimeItems};
+    // 不正な指定子の後もItem::Errorを返し続けるので、最初のエラーで打ち切る
+    let mut items = Vec::new();
+    for item in StrftimeItems::new(format) {
+        if matches!(item, Item::Error) {
+            return None;
+        }
+        items.push(item);
+    }
+    Some(date.format_with_items(items.into_iter()).to_string())
+}
+
+/// `{{ value | number: 2 }}`: 数値を3桁区切りで表示（引数は小数点以下の桁数）
+///
+/// 数値として解釈できない値はそのまま表示する。
+struct NumberHelper;
+
+impl HelperDef for NumberHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'reg, 'rc>, RenderError> {
+        let Some(value) = param_value(h, 0) else {
+            return Ok(ScopedJson::Derived(Value::Null));
+        };
+        let number = match value {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => s.trim().parse::<f64>().ok(),
+            _ => None,
+        }
+        .filter(|n| n.is_finite());
+        let decimals = param_value(h, 1)
+            .and_then(Value::as_u64)
+            .map(|d| d.min(10) as usize);
+
+        let formatted = match number {
+            Some(number) => format_number(number, decimals),
+            None => value_text(value),
+        };
+        Ok(ScopedJson::Derived(Value::String(formatted)))
+    }
+}
+
+fn format_number(number: f64, decimals: Option<usize>) -> String {
+    let formatted = match decimals {
+        Some(decimals) => format!("{number:.decimals$}"),
+        None => number.to_string(),
+    };
+    let (sign, unsigned) = match formatted.strip_prefix('-') {
+        Some(unsigned) => ("-", unsigned),
+        None => ("", formatted.as_str()),
+    };
+    let (integer, fraction) = match unsigned.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (unsigned, None),
+    };
+
+    let mut grouped = String::with_capacity(integer.len() + integer.len() / 3);
+    for (i, c) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+
+    match fraction {
+        Some(fraction) => format!("{sign}{grouped}.{fraction}"),
+        None => format!("{sign}{grouped}"),
+    }
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// 変数の抽出
+// ---------------------------------------------------------------------------
+
+fn collect_template(template: &Template, depth: usize, variables: &mut TemplateVariables) {
+    for element in &template.elements {
+        match element {
+            TemplateElement::Expression(helper) | TemplateElement::HtmlExpression(helper) => {
+                collect_expression(&helper.name, &helper.params, depth, false, variables);
+            }
+            TemplateElement::HelperBlock(helper) => {
+                let name = helper.name.as_name().unwrap_or_default();
+                let optional = name == "if" || name == "unless";
+                collect_expression(&helper.name, &helper.params, depth, optional, variables);
+
+                let inner_depth = if SCOPED_BLOCK_HELPERS.contains(&name) {
+                    depth + 1
+                } else {
+                    depth
+                };
+                if let Some(template) = &helper.template {
+                    collect_template(template, inner_depth, variables);
+                }
+                if let Some(inverse) = &helper.inverse {
+                    collect_template(inverse, depth, variables);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn collect_expression(
+    name: &Parameter,
+    params: &[Parameter],
+    depth: usize,
+    optional: bool,
+    variables: &mut TemplateVariables,
+) {
+    match name {
+        // `{{name}}`はヘルパー名でなければ変数の参照
+        Parameter::Name(name) if params.is_empty() => {
+            if !HELPER_NAMES.contains(&name.as_str()) && depth == 0 {
+                variables.add(name, optional);
+            }
+        }
+        Parameter::Name(helper) => {
+            for (index, param) in params.iter().enumerate() {
+                let optional = optional || (helper == "default" && index == 0);
+                collect_parameter(param, depth, optional, variables);
+            }
+        }
+        param => collect_parameter(param, depth, optional, variables),
+    }
+}
+
+fn collect_parameter(
+    param: &Parameter,
+    depth: usize,
+    optional: bool,
+    variables: &mut TemplateVariables,
+) {
+    match param {
+        Parameter::Name(name) if depth == 0 && !HELPER_NAMES.contains(&name.as_str()) => {
+            variables.add(name, optional);
+        }
+        Parameter::Path(path) => {
+            if let Some(name) = root_variable(path, depth) {
+                variables.add(&name, optional);
+            }
+        }
+        Parameter::Subexpression(subexpression) => {
+            if let TemplateElement::Expression(helper) = subexpression.as_element() {
+                collect_expression(&helper.name, &helper.params, depth, optional, variables);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 変数のパスがテンプレートに渡す変数を参照していればその名前（`../`や`@root.`を考慮する）
+fn root_variable(path: &Path, depth: usize) -> Option<String> {
+    let Path::Relative((_, raw)) = path else {
+        return None;
+    };
+
+    let (levels, rest) = match raw.strip_prefix("@root") {
+        Some(rest) => (depth, rest.trim_start_matches(['.', '/'])),
+        None => {
+            let mut rest = raw.as_str();
+            let mut levels = 0;
+            while let Some(stripped) = rest.strip_prefix("../") {
+                levels += 1;
+                rest = stripped;
+            }
+            (levels, rest)
+        }
+    };
+    if levels != depth {
+        return None;
+    }
+
+    let rest = rest
+        .strip_prefix("this.")
+        .or_else(|| rest.strip_prefix("this/"))
+        .unwrap_or(rest);
+    let name = rest
+        .split(['.', '/'])
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    (!name.is_empty() && name != "this").then(|| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_translate_filters() {
+        assert_eq!(
+            translate_filters(r#"{{ name | default: "お客様" }}様"#),
+            r#"{{default name "お客様"}}様"#
+        );
+        assert_eq!(
+            translate_filters(r#"{{{ price | number: 2 | default: "-" }}}"#),
+            r#"{{{default (number price 2) "-"}}}"#
+        );
+        assert_eq!(
+            translate_filters(r#"{{~ note | default: "a|b, c" ~}}"#),
+            r#"{{~default note "a|b, c"~}}"#
+        );
+        // フィルターのない式・ブロック・エスケープはそのまま
+        assert_eq!(
+            translate_filters(r#"{{#each items as |item|}}{{item}}{{/each}} \{{ a | b }}"#),
+            r#"{{#each items as |item|}}{{item}}{{/each}} \{{ a | b }}"#
+        );
+    }
+
+    #[test]
+    fn test_render_conditionals_loops_and_filters() {
+        let engine = TemplateEngine::new();
+        let template = r#"{{ name | default: "お客様" }}様
+{{#if premium}}プレミアム会員{{else}}一般会員{{/if}}
+{{#each interests}}- {{this}}（{{@root.company}}）
+{{/each}}ご購入額: {{ total | number }}円 / {{ rate | number: 1 }}%
+登録日: {{ joined_at | date }} {{ joined_at | date: "%Y/%m/%d %H:%M" }}"#;
+
+        let rendered = engine
+            .render_markdown(
+                template,
+                &json!({
+                    "premium": true,
+                    "interests": ["Rust", "SQL"],
+                    "company": "サンプル",
+                    "total": 1234567,
+                    "rate": "12.345",
+                    "joined_at": "2025-04-01T09:30:00+09:00"
+                }),
+            )
+            .unwrap();
+        assert_eq!(
+            rendered,
+            "お客様様\nプレミアム会員\n- Rust（サンプル）\n- SQL（サンプル）\nご購入額: 1,234,567円 / 12.3%\n登録日: 2025年04月01日 2025/04/01 09:30"
+        );
+
+        let rendered = engine
+            .render_text(
+                "{{#unless premium}}無料プランの{{/unless}}{{name}}さん {{ missing }}",
+                &json!({"name": "山田"}),
+            )
+            .unwrap();
+        assert_eq!(rendered, "無料プランの山田さん ");
+    }
+
+    #[test]
+    fn test_escaping() {
+        let engine = TemplateEngine::new();
+        let variables = json!({"name": "<b>Tom & \"Jerry\"</b>"});
+
+        assert_eq!(
+            engine.render_markdown("{{name}}", &variables).unwrap(),
+            "&lt;b&gt;Tom &amp; &quot;Jerry&quot;&lt;/b&gt;"
+        );
+        assert_eq!(
+            engine.render_markdown("{{{name}}}", &variables).unwrap(),
+            "<b>Tom & \"Jerry\"</b>"
+        );
+        assert_eq!(
+            engine.render_text("{{name}}", &variables).unwrap(),
+            "<b>Tom & \"Jerry\"</b>"
+        );
+    }
+
+    #[test]
+    fn test_render_component_keeps_variable_references() {
+        let engine = TemplateEngine::new();
+        let rendered = engine
+            .render_component(
+                r##"<a href="{{href}}" style="color: {{ color | default: "#3498db" }};">{{{content}}}</a>"##,
+                &json!({
+                    "href": "https://example.com/?a=1&b={{ coupon | default: 'none' }}",
+                    "content": "<b>購入</b>"
+                }),
+            )
+            .unwrap();
+        assert_eq!(
+            rendered,
+            r#"<a href="https://example.com/?a&#x3D;1&amp;b&#x3D;{{ coupon | default: 'none' }}" style="color: #3498db;"><b>購入</b></a>"#
+        );
+    }
+
+    #[test]
+    fn test_formatters_keep_unparseable_values() {
+        assert_eq!(format_number(-1234.5, None), "-1,234.5");
+        assert_eq!(format_number(999.0, Some(0)), "999");
+        assert_eq!(format_number(1000.0, None), "1,000");
+
+        let engine = TemplateEngine::new();
+        let variables = json!({"value": "未定", "ts": 0});
+        assert_eq!(
+            engine
+                .render_text("{{ value | number }} {{ value | date }}", &variables)
+                .unwrap(),
+            "未定 未定"
+        );
+        assert_eq!(
+            engine
+                .render_text(
+                    r#"{{ ts | date: "%Y-%m-%d" }} {{ ts | date: "%Q" }}"#,
+                    &variables
+                )
+                .unwrap(),
+            "1970-01-01 0"
+        );
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        let engine = TemplateEngine::new();
+        assert!(engine.validate("{{#if name}}閉じていない").is_err());
+        assert!(engine
+            .render_text("{{unknown_helper name}}", &json!({}))
+            .is_err());
+        assert!(engine.validate("{{name}}さん").is_ok());
+    }
+
+    #[test]
+    fn test_analyze() {
+        let engine = TemplateEngine::new();
+        let variables = engine
+            .analyze(
+                r#"{{ name | default: "お客様" }} {{email}} {{company.name}}
+{{#if coupon}}{{coupon}}{{/if}}
+{{#each items as |item|}}{{item.title}} {{title}} {{../currency}} {{@index}}{{/each}}
+{{#with address}}{{city}}{{/with}} {{ total | number }} {{{signature}}} {{email}}"#,
+            )
+            .unwrap();
+
+        assert_eq!(
+            variables.variables,
+            vec![
+                "name",
+                "email",
+                "company",
+                "coupon",
+                "items",
+                "currency",
+                "address",
+                "total",
+                "signature"
+            ]
+        );
+        assert_eq!(variables.optional_variables, vec!["name", "coupon"]);
+        assert!(!variables.required_variables().contains(&"name".to_string()));
+    }
+}
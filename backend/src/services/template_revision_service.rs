@@ -0,0 +1,220 @@
+use similar::{ChangeTag, TextDiff};
+use sqlx::PgPool;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{
+    database::{template_revisions, templates},
+    models::template::{Template, TemplateDiffResponse, TemplateRevision},
+};
+
+/// リビジョン操作のエラー
+#[derive(Error, Debug)]
+pub enum TemplateRevisionError {
+    #[error("テンプレートが見つかりません")]
+    TemplateNotFound,
+
+    #[error("リビジョン{0}が見つかりません")]
+    RevisionNotFound(i32),
+
+    #[error("{0}")]
+    Database(String),
+}
+
+pub struct TemplateRevisionService;
+
+impl Default for TemplateRevisionService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TemplateRevisionService {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// リビジョン一覧を取得（新しい順）
+    pub async fn list_revisions(
+        &self,
+        pool: &PgPool,
+        template_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Vec<TemplateRevision>, TemplateRevisionError> {
+        self.owned_template(pool, template_id, user_id).await?;
+
+        template_revisions::list_revisions(pool, template_id)
+            .await
+            .map_err(|e| {
+                TemplateRevisionError::Database(format!("リビジョンの取得に失敗しました: {e}"))
+            })
+    }
+
+    /// リビジョンを取得
+    pub async fn get_revision(
+        &self,
+        pool: &PgPool,
+        template_id: Uuid,
+        user_id: Uuid,
+        revision: i32,
+    ) -> Result<TemplateRevision, TemplateRevisionError> {
+        self.owned_template(pool, template_id, user_id).await?;
+        self.find_revision(pool, template_id, revision).await
+    }
+
+    /// 2つのリビジョンの差分を取得
+    ///
+    /// 比較先を省略した場合は現在のリビジョン、比較元を省略した場合は比較先の1つ前と比べる。
+    pub async fn diff_revisions(
+        &self,
+        pool: &PgPool,
+        template_id: Uuid,
+        user_id: Uuid,
+        from: Option<i32>,
+        to: Option<i32>,
+    ) -> Result<TemplateDiffResponse, TemplateRevisionError> {
+        let template = self.owned_template(pool, template_id, user_id).await?;
+
+        let to = self
+            .find_revision(pool, template_id, to.unwrap_or(template.current_revision))
+            .await?;
+        let from = match from {
+            Some(from) => self.find_revision(pool, template_id, from).await?,
+            // 最初のリビジョンは自身と比べる（差分なし）
+            None if to.revision <= 1 => to.clone(),
+            None => {
+                self.find_revision(pool, template_id, to.revision - 1)
+                    .await?
+            }
+        };
+
+        Ok(diff_revisions(&from, &to))
+    }
+
+    /// テンプレートをリビジョンの内容に戻す
+    pub async fn rollback(
+        &self,
+        pool: &PgPool,
+        template_id: Uuid,
+        user_id: Uuid,
+        revision: i32,
+    ) -> Result<Template, TemplateRevisionError> {
+        self.owned_template(pool, template_id, user_id).await?;
+        let revision = self.find_revision(pool, template_id, revision).await?;
+
+        let restored = template_revisions::restore_revision(pool, template_id, user_id, &revision)
+            .await
+            .map_err(|e| {
+                TemplateRevisionError::Database(format!("リビジョンの復元に失敗しました: {e}"))
+            })?;
+        if !restored {
+            return Err(TemplateRevisionError::TemplateNotFound);
+        }
+
+        self.owned_template(pool, template_id, user_id).await
+    }
+
+    /// ユーザーが所有するテンプレート（公開テンプレートでも他人の履歴は見せない）
+    async fn owned_template(
+        &self,
+        pool: &PgPool,
+        template_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Template, TemplateRevisionError> {
+        templates::find_template_by_id(pool, template_id, Some(user_id))
+            .await
+            .map_err(|e| {
+                TemplateRevisionError::Database(format!("テンプレートの取得に失敗しました: {e}"))
+            })?
+            .filter(|template| template.user_id == user_id)
+            .ok_or(TemplateRevisionError::TemplateNotFound)
+    }
+
+    async fn find_revision(
+        &self,
+        pool: &PgPool,
+        template_id: Uuid,
+        revision: i32,
+    ) -> Result<TemplateRevision, TemplateRevisionError> {
+        template_revisions::find_revision(pool, template_id, revision)
+            .await
+            .map_err(|e| {
+                TemplateRevisionError::Database(format!("リビジョンの取得に失敗しました: {e}"))
+            })?
+            .ok_or(TemplateRevisionError::RevisionNotFound(revision))
+    }
+}
+
+/// リビジョン間の件名・本文・変数の差分
+pub fn diff_revisions(from: &TemplateRevision, to: &TemplateRevision) -> TemplateDiffResponse {
+    let headers = (
+        format!("revision {}", from.revision),
+        format!("revision {}", to.revision),
+    );
+
+    let subject_diff = TextDiff::from_lines(&from.subject_template, &to.subject_template);
+    let markdown_diff = TextDiff::from_lines(&from.markdown_content, &to.markdown_content);
+
+    let (mut insertions, mut deletions) = (0, 0);
+    for change in markdown_diff.iter_all_changes() {
+        match change.tag() {
+            ChangeTag::Insert => insertions += 1,
+            ChangeTag::Delete => deletions += 1,
+            ChangeTag::Equal => {}
+        }
+    }
+
+    TemplateDiffResponse {
+        from_revision: from.revision,
+        to_revision: to.revision,
+        subject_changed: from.subject_template != to.subject_template,
+        subject_diff: subject_diff
+            .unified_diff()
+            .header(&headers.0, &headers.1)
+            .to_string(),
+        markdown_diff: markdown_diff
+            .unified_diff()
+            .header(&headers.0, &headers.1)
+            .to_string(),
+        variables_changed: from.variables != to.variables,
+        insertions,
+        deletions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use serde_json::json;
+
+    fn revision(revision: i32, subject: &str, markdown: &str) -> TemplateRevision {
+        TemplateRevision {
+            id: Uuid::new_v4(),
+            template_id: Uuid::nil(),
+            revision,
+            subject_template: subject.to_string(),
+            markdown_content: markdown.to_string(),
+            variables: json!({}),
+            created_by: None,
+            note: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_diff_revisions() {
+        let from = revision(1, "お知らせ", "# タイトル\n\n本文です。\n");
+        let to = revision(2, "お知らせ", "# タイトル\n\n新しい本文です。\n追記\n");
+
+        let diff = diff_revisions(&from, &to);
+        assert_eq!((diff.from_revision, diff.to_revision), (1, 2));
+        assert!(!diff.subject_changed);
+        assert!(diff.subject_diff.is_empty());
+        assert!(diff.markdown_diff.contains("--- revision 1"));
+        assert!(diff.markdown_diff.contains("-本文です。"));
+        assert!(diff.markdown_diff.contains("+新しい本文です。"));
+        assert_eq!((diff.insertions, diff.deletions), (2, 1));
+        assert!(!diff.variables_changed);
+    }
+}
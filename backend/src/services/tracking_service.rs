@@ -0,0 +1,198 @@
+// 開封・クリック計測サービス
+
+use regex::{Captures, Regex};
+use uuid::Uuid;
+
+use crate::{
+    models::tracking::TrackingClaims,
+    utils::{
+        config::api_base_url,
+        jwt::{generate_signed_token, verify_signed_token, TokenAudience},
+    },
+};
+
+pub struct TrackingService {
+    base_url: String,
+}
+
+impl Default for TrackingService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TrackingService {
+    pub fn new() -> Self {
+        Self::with_base_url(api_base_url())
+    }
+
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// 計測トークンを生成
+    pub fn generate_token(&self, claims: &TrackingClaims) -> Result<String, String> {
+        generate_signed_token(TokenAudience::Tracking, claims)
+            .map_err(|e| format!("計測トークンの生成に失敗しました: {e}"))
+    }
+
+    /// 計測トークンを検証
+    pub fn verify_token(&self, token: &str) -> Result<TrackingClaims, String> {
+        verify_signed_token(TokenAudience::Tracking, token)
+            .map_err(|e| format!("計測トークンが無効です: {e}"))
+    }
+
+    /// 開封計測ピクセルのURL
+    pub fn open_url(&self, campaign_id: Uuid, subscriber_id: Uuid) -> Result<String, String> {
+        let token = self.generate_token(&TrackingClaims {
+            c: campaign_id,
+            s: subscriber_id,
+            u: None,
+        })?;
+        Ok(format!("{}/t/o/{}", self.base_url, token))
+    }
+
+    /// クリック計測リダイレクトのURL
+    pub fn click_url(
+        &self,
+        campaign_id: Uuid,
+        subscriber_id: Uuid,
+        target_url: &str,
+    ) -> Result<String, String> {
+        let token = self.generate_token(&TrackingClaims {
+            c: campaign_id,
+            s: subscriber_id,
+            u: Some(target_url.to_string()),
+        })?;
+        Ok(format!("{}/t/c/{}", self.base_url, token))
+    }
+
+    /// 送信用HTMLにクリック計測リンクと開封計測ピクセルを埋め込む
+    pub fn instrument_html(
+        &self,
+        html: &str,
+        campaign_id: Uuid,
+        subscriber_id: Uuid,
+    ) -> Result<String, String> {
+        let re = Regex::new(r#"href="([^"]*)""#).map_err(|e| format!("正規表現エラー: {e}"))?;
+
+        let mut error = None;
+        let rewritten = re.replace_all(html, |caps: &Captures| {
+            let raw = &caps[1];
+            let target = raw.replace("&amp;", "&");
+            if !Self::is_trackable_link(&target) {
+                return caps[0].to_string();
+            }
+            match self.click_url(campaign_id, subscriber_id, &target) {
+                Ok(url) => format!("href=\"{url}\""),
+                Err(e) => {
+                    error = Some(e);
+                    caps[0].to_string()
+                }
+            }
+        });
+        if let Some(e) = error {
+            return Err(e);
+        }
+
+        let pixel = format!(
+            "<img src=\"{}\" width=\"1\" height=\"1\" alt=\"\" style=\"display:none;border:0;\" />",
+            self.open_url(campaign_id, subscriber_id)?
+        );
+
+        let mut result = rewritten.into_owned();
+        match result.rfind("</body>") {
+            Some(pos) => result.insert_str(pos, &format!("{pixel}\n")),
+            None => result.push_str(&pixel),
+        }
+
+        Ok(result)
+    }
+
+    /// 計測対象のリンクか判定（配信停止リンクは計測しない）
+    fn is_trackable_link(url: &str) -> bool {
+        let lower = url.to_ascii_lowercase();
+        (lower.starts_with("http://") || lower.starts_with("https://"))
+            && !lower.contains("/unsubscribe")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> TrackingService {
+        std::env::set_var("JWT_SECRET", "test_secret_key_for_testing_only");
+        TrackingService::with_base_url("https://api.example.com/")
+    }
+
+    #[test]
+    fn test_token_roundtrip() {
+        let service = setup();
+        let claims = TrackingClaims {
+            c: Uuid::new_v4(),
+            s: Uuid::new_v4(),
+            u: Some("https://example.com/?a=1&b=2".to_string()),
+        };
+
+        let token = service.generate_token(&claims).unwrap();
+        let verified = service.verify_token(&token).unwrap();
+        assert_eq!(verified, claims);
+    }
+
+    #[test]
+    fn test_tampered_token_is_rejected() {
+        let service = setup();
+        let token = service
+            .generate_token(&TrackingClaims {
+                c: Uuid::new_v4(),
+                s: Uuid::new_v4(),
+                u: None,
+            })
+            .unwrap();
+
+        assert!(service.verify_token(&format!("{token}x")).is_err());
+        assert!(service.verify_token("invalid").is_err());
+    }
+
+    #[test]
+    fn test_instrument_html() {
+        let service = setup();
+        let campaign_id = Uuid::new_v4();
+        let subscriber_id = Uuid::new_v4();
+        let html = r##"<html><body>
+<a href="https://example.com/page?a=1&amp;b=2">リンク</a>
+<a href="mailto:info@example.com">メール</a>
+<a href="#top">トップ</a>
+<a href="https://api.example.com/unsubscribe/abc">配信停止</a>
+</body></html>"##;
+
+        let result = service
+            .instrument_html(html, campaign_id, subscriber_id)
+            .unwrap();
+
+        // 外部リンクはクリック計測URLに置換される
+        assert!(!result.contains("https://example.com/page"));
+        assert!(result.contains("href=\"https://api.example.com/t/c/"));
+        // 計測対象外のリンクはそのまま
+        assert!(result.contains("href=\"mailto:info@example.com\""));
+        assert!(result.contains("href=\"#top\""));
+        assert!(result.contains("href=\"https://api.example.com/unsubscribe/abc\""));
+        // 開封ピクセルは</body>の直前に挿入される
+        let pixel_pos = result.find("https://api.example.com/t/o/").unwrap();
+        assert!(pixel_pos < result.find("</body>").unwrap());
+
+        // リダイレクト先はエスケープ解除されたURL
+        let re = Regex::new(r#"/t/c/([^"]+)""#).unwrap();
+        let token = &re.captures(&result).unwrap()[1];
+        let claims = service.verify_token(token).unwrap();
+        assert_eq!(claims.c, campaign_id);
+        assert_eq!(claims.s, subscriber_id);
+        assert_eq!(
+            claims.u.as_deref(),
+            Some("https://example.com/page?a=1&b=2")
+        );
+    }
+}
@@ -0,0 +1,166 @@
+// 配信停止サービス
+
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::{
+    models::subscriber::{Subscriber, UnsubscribeClaims},
+    utils::{
+        config::api_base_url,
+        jwt::{generate_signed_token, verify_signed_token, TokenAudience},
+    },
+};
+
+const UNSUBSCRIBE_PURPOSE: &str = "unsubscribe";
+
+pub struct UnsubscribeService {
+    base_url: String,
+}
+
+impl Default for UnsubscribeService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UnsubscribeService {
+    pub fn new() -> Self {
+        Self::with_base_url(api_base_url())
+    }
+
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// 配信停止トークンを生成
+    pub fn generate_token(
+        &self,
+        subscriber: &Subscriber,
+        campaign_id: Option<Uuid>,
+    ) -> Result<String, String> {
+        let claims = UnsubscribeClaims {
+            sub: subscriber.id,
+            uid: subscriber.user_id,
+            cid: campaign_id,
+            purpose: UNSUBSCRIBE_PURPOSE.to_string(),
+        };
+        generate_signed_token(TokenAudience::Unsubscribe, &claims)
+            .map_err(|e| format!("配信停止トークンの生成に失敗しました: {e}"))
+    }
+
+    /// 配信停止トークンを検証
+    pub fn verify_token(&self, token: &str) -> Result<UnsubscribeClaims, String> {
+        let claims: UnsubscribeClaims = verify_signed_token(TokenAudience::Unsubscribe, token)
+            .map_err(|e| format!("配信停止トークンが無効です: {e}"))?;
+        if claims.purpose != UNSUBSCRIBE_PURPOSE {
+            return Err("配信停止トークンが無効です".to_string());
+        }
+        Ok(claims)
+    }
+
+    /// 配信停止URL
+    pub fn unsubscribe_url(
+        &self,
+        subscriber: &Subscriber,
+        campaign_id: Option<Uuid>,
+    ) -> Result<String, String> {
+        let token = self.generate_token(subscriber, campaign_id)?;
+        Ok(format!("{}/unsubscribe/{}", self.base_url, token))
+    }
+
+    /// RFC 8058 ワンクリック配信停止用のヘッダー
+    pub fn list_unsubscribe_headers(&self, unsubscribe_url: &str) -> HashMap<String, String> {
+        let mut headers = HashMap::new();
+        headers.insert(
+            "List-Unsubscribe".to_string(),
+            format!("<{unsubscribe_url}>"),
+        );
+        headers.insert(
+            "List-Unsubscribe-Post".to_string(),
+            "List-Unsubscribe=One-Click".to_string(),
+        );
+        headers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::subscriber::SubscriberStatus;
+    use crate::models::tracking::TrackingClaims;
+
+    fn setup() -> UnsubscribeService {
+        std::env::set_var("JWT_SECRET", "test_secret_key_for_testing_only");
+        UnsubscribeService::with_base_url("https://api.example.com")
+    }
+
+    fn test_subscriber() -> Subscriber {
+        Subscriber {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            email: "test@example.com".to_string(),
+            name: None,
+            status: SubscriberStatus::Active,
+            tags: vec![],
+            custom_fields: serde_json::json!({}),
+            subscribed_at: chrono::Utc::now(),
+            unsubscribed_at: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_unsubscribe_url_roundtrip() {
+        let service = setup();
+        let subscriber = test_subscriber();
+        let campaign_id = Uuid::new_v4();
+
+        let url = service
+            .unsubscribe_url(&subscriber, Some(campaign_id))
+            .unwrap();
+        let token = url
+            .strip_prefix("https://api.example.com/unsubscribe/")
+            .unwrap();
+
+        let claims = service.verify_token(token).unwrap();
+        assert_eq!(claims.sub, subscriber.id);
+        assert_eq!(claims.uid, subscriber.user_id);
+        assert_eq!(claims.cid, Some(campaign_id));
+    }
+
+    #[test]
+    fn test_other_signed_tokens_are_rejected() {
+        let service = setup();
+        let token = generate_signed_token(
+            TokenAudience::Tracking,
+            &TrackingClaims {
+                c: Uuid::new_v4(),
+                s: Uuid::new_v4(),
+                u: None,
+            },
+        )
+        .unwrap();
+
+        assert!(service.verify_token(&token).is_err());
+        assert!(service.verify_token("invalid").is_err());
+    }
+
+    #[test]
+    fn test_list_unsubscribe_headers() {
+        let service = setup();
+        let headers = service.list_unsubscribe_headers("https://api.example.com/unsubscribe/abc");
+
+        assert_eq!(
+            headers.get("List-Unsubscribe").unwrap(),
+            "<https://api.example.com/unsubscribe/abc>"
+        );
+        assert_eq!(
+            headers.get("List-Unsubscribe-Post").unwrap(),
+            "List-Unsubscribe=One-Click"
+        );
+    }
+}
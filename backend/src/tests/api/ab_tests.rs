@@ -0,0 +1,109 @@
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use crate::{
+    create_app,
+    database::subscriptions,
+    models::subscription::UpgradeRequest,
+    services::subscription_service,
+    tests::api::{
+        segments::send,
+        templates::{create_test_template, get_test_user_with_jwt},
+    },
+};
+
+#[tokio::test]
+async fn test_configure_ab_test_requires_plan_feature() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (user_id, token) = get_test_user_with_jwt(&pool).await;
+    let template = create_test_template(&pool, user_id).await;
+
+    let (status, campaign) = send(
+        &app,
+        Method::POST,
+        "/api/campaigns",
+        &token,
+        Some(json!({
+            "name": "A/Bテスト",
+            "subject": "A/Bテスト",
+            "template_id": template.id
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let uri = format!(
+        "/api/campaigns/{}/ab-test",
+        campaign["id"].as_str().unwrap()
+    );
+
+    let request = json!({
+        "test_percentage": 20,
+        "winner_metric": "clicks",
+        "wait_minutes": 240,
+        "variants": [
+            {"name": "A", "subject": "件名A", "split_percentage": 50},
+            {"name": "B", "subject": "件名B", "template_id": template.id, "split_percentage": 50}
+        ]
+    });
+
+    // freeプランではA/Bテストを利用できない
+    let (status, body) = send(&app, Method::PUT, &uri, &token, Some(request.clone())).await;
+    assert_eq!(status, StatusCode::PAYMENT_REQUIRED);
+    assert!(body["error"].is_string());
+
+    let business_plan = subscriptions::get_plan_by_name(&pool, "business")
+        .await
+        .unwrap();
+    subscription_service::upgrade_plan(
+        &pool,
+        user_id,
+        &UpgradeRequest {
+            plan_id: business_plan.id,
+        },
+    )
+    .await
+    .unwrap();
+
+    let (status, _) = send(&app, Method::GET, &uri, &token, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = send(&app, Method::PUT, &uri, &token, Some(request)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["ab_test"]["status"], "draft");
+    assert_eq!(body["ab_test"]["winner_metric"], "clicks");
+    assert_eq!(body["variants"].as_array().unwrap().len(), 2);
+
+    // 配分の合計が100%でない設定は保存できない
+    let (status, _) = send(
+        &app,
+        Method::PUT,
+        &uri,
+        &token,
+        Some(json!({
+            "test_percentage": 20,
+            "winner_metric": "opens",
+            "wait_minutes": 240,
+            "variants": [
+                {"name": "A", "subject": "件名A", "split_percentage": 50},
+                {"name": "B", "subject": "件名B", "split_percentage": 30}
+            ]
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = send(&app, Method::GET, &uri, &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["variants"][1]["template_id"], json!(template.id));
+    assert_eq!(body["results"].as_array().unwrap().len(), 2);
+
+    // 他のユーザーのキャンペーンには設定できない
+    let (_, other_token) = get_test_user_with_jwt(&pool).await;
+    let (status, _) = send(&app, Method::DELETE, &uri, &other_token, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = send(&app, Method::DELETE, &uri, &token, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send(&app, Method::GET, &uri, &token, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
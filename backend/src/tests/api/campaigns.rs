@@ -9,7 +9,11 @@ use uuid::Uuid;
 
 use crate::{
     create_app,
-    models::campaign::{CampaignResponse, CreateCampaignRequest},
+    database::subscribers,
+    models::{
+        campaign::{CampaignResponse, CreateCampaignRequest},
+        subscriber::{CreateSubscriberRequest, SubscriberStatus},
+    },
     tests::api::templates::{create_test_template, get_test_user_with_jwt},
 };
 
@@ -20,6 +24,9 @@ fn get_test_campaign_request(template_id: Uuid) -> CreateCampaignRequest {
         description: Some("テスト用のキャンペーンです".to_string()),
         subject: "テストメールの件名".to_string(),
         template_id,
+        audience: None,
+        from_email: None,
+        from_name: None,
     }
 }
 
@@ -276,6 +283,9 @@ async fn test_list_campaigns() {
             description: Some(format!("テスト説明 {i}")),
             subject: format!("テスト件名 {i}"),
             template_id: template.id,
+            audience: None,
+            from_email: None,
+            from_name: None,
         };
 
         let response = app
@@ -519,3 +529,242 @@ async fn test_cannot_delete_sent_campaign() {
         .unwrap()
         .contains("送信済み/送信中のキャンペーンは削除できません"));
 }
+
+#[tokio::test]
+async fn test_cancel_campaign_schedule() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (user_id, token) = get_test_user_with_jwt(&pool).await;
+    let template = create_test_template(&pool, user_id).await;
+    let campaign = create_test_campaign(app.clone(), user_id, &token, template.id)
+        .await
+        .unwrap();
+
+    // スケジュール設定
+    let schedule_request = json!({
+        "scheduled_at": (Utc::now() + chrono::Duration::days(1)).to_rfc3339()
+    });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("/api/campaigns/{}/schedule", campaign.id))
+                .header("Content-Type", "application/json")
+                .header("Authorization", format!("Bearer {token}"))
+                .body(Body::from(schedule_request.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // スケジュール取り消し
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::DELETE)
+                .uri(format!("/api/campaigns/{}/schedule", campaign.id))
+                .header("Authorization", format!("Bearer {token}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let cancelled: CampaignResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(cancelled.status, "cancelled");
+    assert!(cancelled.scheduled_at.is_none());
+
+    // 送信待ちでないキャンペーンは取り消せない
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::DELETE)
+                .uri(format!("/api/campaigns/{}/schedule", campaign.id))
+                .header("Authorization", format!("Bearer {token}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_claim_due_scheduled_campaigns_only_once() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (user_id, token) = get_test_user_with_jwt(&pool).await;
+    let template = create_test_template(&pool, user_id).await;
+    let campaign = create_test_campaign(app.clone(), user_id, &token, template.id)
+        .await
+        .unwrap();
+
+    // 送信予定時刻を過ぎたスケジュール済みキャンペーンを用意
+    sqlx::query(
+        "UPDATE campaigns SET status = 'scheduled', scheduled_at = NOW() - INTERVAL '1 minute' WHERE id = $1",
+    )
+    .bind(campaign.id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    // 複数のスケジューラーが同時に確保しても一度しか確保されない
+    let (first, second) = tokio::join!(
+        crate::database::campaigns::claim_due_scheduled_campaigns(&pool, 100),
+        crate::database::campaigns::claim_due_scheduled_campaigns(&pool, 100),
+    );
+    let claimed_count = first
+        .unwrap()
+        .into_iter()
+        .chain(second.unwrap())
+        .filter(|c| c.id == campaign.id)
+        .count();
+    assert_eq!(claimed_count, 1);
+
+    let claimed = crate::database::campaigns::find_campaign_by_id(&pool, campaign.id, user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(claimed.status.to_string(), "sending");
+
+    // 送信開始後はスケジュールを変更できない
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("/api/campaigns/{}/schedule", campaign.id))
+                .header("Content-Type", "application/json")
+                .header("Authorization", format!("Bearer {token}"))
+                .body(Body::from(
+                    json!({
+                        "scheduled_at": (Utc::now() + chrono::Duration::days(1)).to_rfc3339()
+                    })
+                    .to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_campaign_audience_preview() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (user_id, token) = get_test_user_with_jwt(&pool).await;
+    let template = create_test_template(&pool, user_id).await;
+
+    for (label, status, tags, plan) in [
+        (
+            "pro-customer",
+            SubscriberStatus::Active,
+            vec!["customer"],
+            "pro",
+        ),
+        (
+            "free-customer",
+            SubscriberStatus::Active,
+            vec!["customer"],
+            "free",
+        ),
+        ("pro-lead", SubscriberStatus::Active, vec!["lead"], "pro"),
+        (
+            "pro-bounced",
+            SubscriberStatus::Bounced,
+            vec!["customer"],
+            "pro",
+        ),
+        (
+            "pro-unsubscribed",
+            SubscriberStatus::Unsubscribed,
+            vec!["customer"],
+            "pro",
+        ),
+    ] {
+        subscribers::create_subscriber(
+            &pool,
+            user_id,
+            &CreateSubscriberRequest {
+                email: format!("{label}-{}@example.com", Uuid::new_v4()),
+                name: Some(label.to_string()),
+                status: Some(status),
+                tags: Some(tags.into_iter().map(String::from).collect()),
+                custom_fields: Some(json!({ "plan": plan })),
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    let request = json!({
+        "name": "オーディエンステスト",
+        "subject": "オーディエンステスト",
+        "template_id": template.id,
+        "audience": {
+            "include_tags": ["customer"],
+            "conditions": [{"field": "plan", "operator": "equals", "value": "pro"}]
+        }
+    });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/campaigns")
+                .header("Content-Type", "application/json")
+                .header("Authorization", format!("Bearer {token}"))
+                .body(Body::from(request.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let campaign: CampaignResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(campaign.audience.include_tags, vec!["customer"]);
+
+    // 配信対象のプレビュー（バウンス・配信停止の購読者は含まれない）
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri(format!("/api/campaigns/{}/subscribers", campaign.id))
+                .header("Authorization", format!("Bearer {token}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let preview: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(preview["total"], 1);
+    assert_eq!(preview["subscribers"][0]["name"], "pro-customer");
+
+    // 送信できないステータスを指定した定義は保存できない
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::PUT)
+                .uri(format!("/api/campaigns/{}", campaign.id))
+                .header("Content-Type", "application/json")
+                .header("Authorization", format!("Bearer {token}"))
+                .body(Body::from(
+                    json!({"audience": {"statuses": ["Bounced"]}}).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
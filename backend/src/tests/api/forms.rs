@@ -433,6 +433,7 @@ async fn test_form_submission() {
     let submit_result = forms::submit_form(
         Path(form_id),
         axum::extract::State(app_state.clone()),
+        axum::http::HeaderMap::new(),
         AxumJson(crate::models::form::CreateFormSubmissionRequest {
             data: submission_data.clone(),
         }),
@@ -454,6 +455,7 @@ async fn test_form_submission() {
             limit: 10,
             offset: 0,
         }),
+        Query(forms::SubmissionFilterParams::default()),
         axum::extract::State(app_state.clone()),
     )
     .await;
@@ -478,3 +480,692 @@ async fn test_form_submission() {
         .await
         .expect("Failed to clean up test user");
 }
+
+#[tokio::test]
+async fn test_double_opt_in_confirmation() {
+    use crate::{
+        create_app,
+        database::{sequences, subscribers},
+        models::{sequence::CreateSequenceRequest, subscriber::SubscriberStatus},
+        tests::api::{segments::send, templates::get_test_user_with_jwt},
+    };
+    use axum::http::Method;
+
+    let (app, pool, _redis, _config) = create_app().await;
+    let (user_id, token) = get_test_user_with_jwt(&pool).await;
+
+    let sequence = sequences::create_sequence(
+        &pool,
+        user_id,
+        CreateSequenceRequest {
+            name: "登録後フォロー".to_string(),
+            description: None,
+            trigger_type: "form_submission".to_string(),
+            trigger_config: None,
+            reentry_policy: None,
+            exit_conditions: None,
+            send_window: None,
+        },
+    )
+    .await
+    .unwrap();
+    sequences::update_sequence_status(&pool, sequence.id, "active")
+        .await
+        .unwrap();
+
+    let fields = json!([
+        {"field_type": "text", "name": "name", "label": "お名前", "required": false},
+        {"field_type": "email", "name": "email", "label": "メール", "required": true}
+    ]);
+
+    // 存在しないテンプレートや不正なリダイレクト先は設定できない
+    for settings in [
+        json!({"require_confirmation": true, "confirmation_template_id": Uuid::new_v4()}),
+        json!({"require_confirmation": true, "confirmation_redirect_url": "javascript:alert(1)"}),
+        json!({"require_confirmation": "yes"}),
+    ] {
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            "/api/forms",
+            &token,
+            Some(json!({
+                "name": "不正な設定",
+                "markdown_content": "# 登録",
+                "form_fields": fields,
+                "settings": settings
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].is_string());
+    }
+
+    let (status, form) = send(
+        &app,
+        Method::POST,
+        "/api/forms",
+        &token,
+        Some(json!({
+            "name": "ニュースレター登録",
+            "slug": format!("doi-{}", Uuid::new_v4()),
+            "markdown_content": "# 登録",
+            "form_fields": fields,
+            "settings": {"require_confirmation": true, "min_submit_seconds": 0}
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let form_uri = format!("/api/forms/{}", form["id"].as_str().unwrap());
+    let (status, _) = send(
+        &app,
+        Method::PUT,
+        &form_uri,
+        &token,
+        Some(json!({"status": "published"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let submit = |email: String| {
+        let app = app.clone();
+        let uri = format!("{form_uri}/submit");
+        let token = token.clone();
+        async move {
+            send(
+                &app,
+                Method::POST,
+                &uri,
+                &token,
+                Some(json!({"data": {"name": "山田", "email": email}})),
+            )
+            .await
+        }
+    };
+    let enrollment_count = || async {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM sequence_enrollments WHERE sequence_id = $1",
+        )
+        .bind(sequence.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap()
+    };
+
+    // 送信直後の購読者は確認待ちで、シーケンスには登録されない
+    let email = format!("doi-{}@example.com", Uuid::new_v4());
+    let (status, submission) = submit(email.clone()).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert!(submission.get("confirmation_token").is_none());
+    assert!(submission["confirmed_at"].is_null());
+
+    let subscriber = subscribers::find_subscriber_by_email(&pool, &email, user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(subscriber.status, SubscriberStatus::Pending);
+    assert_eq!(enrollment_count().await, 0);
+
+    let confirmation_token: String =
+        sqlx::query_scalar("SELECT confirmation_token FROM form_submissions WHERE id = $1")
+            .bind(Uuid::parse_str(submission["id"].as_str().unwrap()).unwrap())
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    let confirm_uri = format!("/api/forms/confirm/{confirmation_token}");
+
+    let (status, body) = send(&app, Method::GET, &confirm_uri, "", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["submission_id"], submission["id"]);
+    assert!(body["confirmed_at"].is_string());
+
+    let subscriber = subscribers::find_subscriber_by_email(&pool, &email, user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(subscriber.status, SubscriberStatus::Active);
+    assert_eq!(enrollment_count().await, 1);
+
+    // 同じリンクを再度開いてもシーケンスには重複して登録しない
+    let (status, _) = send(&app, Method::GET, &confirm_uri, "", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(enrollment_count().await, 1);
+
+    let (status, _) = send(&app, Method::GET, "/api/forms/confirm/unknown", "", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // 有効期限切れのリンク
+    let (_, expired) = submit(format!("expired-{}@example.com", Uuid::new_v4())).await;
+    let expired_token: String = sqlx::query_scalar(
+        "UPDATE form_submissions SET created_at = NOW() - INTERVAL '8 days' WHERE id = $1 RETURNING confirmation_token",
+    )
+    .bind(Uuid::parse_str(expired["id"].as_str().unwrap()).unwrap())
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    let (status, _) = send(
+        &app,
+        Method::GET,
+        &format!("/api/forms/confirm/{expired_token}"),
+        "",
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::GONE);
+    assert_eq!(enrollment_count().await, 1);
+
+    // リダイレクト先が設定されている場合はリダイレクトする
+    let (status, _) = send(
+        &app,
+        Method::PUT,
+        &form_uri,
+        &token,
+        Some(json!({"settings": {
+            "require_confirmation": true,
+            "min_submit_seconds": 0,
+            "confirmation_redirect_url": "https://example.com/thanks"
+        }})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, redirected) = submit(format!("redirect-{}@example.com", Uuid::new_v4())).await;
+    let redirect_token: String =
+        sqlx::query_scalar("SELECT confirmation_token FROM form_submissions WHERE id = $1")
+            .bind(Uuid::parse_str(redirected["id"].as_str().unwrap()).unwrap())
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    let (status, _) = send(
+        &app,
+        Method::GET,
+        &format!("/api/forms/confirm/{redirect_token}"),
+        "",
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    assert_eq!(enrollment_count().await, 2);
+}
+
+#[tokio::test]
+async fn test_submission_validated_against_form_fields() {
+    use crate::{
+        create_app,
+        tests::api::{segments::send, templates::get_test_user_with_jwt},
+    };
+    use axum::http::Method;
+
+    let (app, pool, _redis, _config) = create_app().await;
+    let (_user_id, token) = get_test_user_with_jwt(&pool).await;
+
+    // 正規表現が不正なフィールドは保存できない
+    let (status, body) = send(
+        &app,
+        Method::POST,
+        "/api/forms",
+        &token,
+        Some(json!({
+            "name": "不正なフィールド",
+            "markdown_content": "# 問い合わせ",
+            "form_fields": [
+                {"field_type": "text", "name": "code", "label": "コード", "validation_rules": {"pattern": "[A-"}}
+            ]
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("code"));
+
+    let (status, form) = send(
+        &app,
+        Method::POST,
+        "/api/forms",
+        &token,
+        Some(json!({
+            "name": "問い合わせ",
+            "slug": format!("contact-{}", Uuid::new_v4()),
+            "markdown_content": "# 問い合わせ",
+            "form_fields": [
+                {"field_type": "email", "name": "email", "label": "メール", "required": true},
+                {"field_type": "text", "name": "company", "label": "会社名", "validation_rules": {"max_length": 10}},
+                {"field_type": "select", "name": "topic", "label": "内容", "required": true,
+                 "options": [{"value": "sales", "label": "営業"}, {"value": "support", "label": "サポート"}]}
+            ]
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let form_uri = format!("/api/forms/{}", form["id"].as_str().unwrap());
+    let (status, _) = send(
+        &app,
+        Method::PUT,
+        &form_uri,
+        &token,
+        Some(json!({"status": "published"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let submit_uri = format!("{form_uri}/submit");
+
+    let (status, body) = send(
+        &app,
+        Method::POST,
+        &submit_uri,
+        "",
+        Some(json!({"data": {"email": "not-an-email", "company": "とても長い会社名株式会社"}})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let errors: Vec<(&str, &str)> = body["field_errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| (e["field"].as_str().unwrap(), e["code"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            ("email", "invalid_format"),
+            ("company", "too_long"),
+            ("topic", "required")
+        ]
+    );
+    assert!(body["field_errors"][0]["message"].is_string());
+
+    let email = format!("contact-{}@example.com", Uuid::new_v4());
+    let (status, submission) = send(
+        &app,
+        Method::POST,
+        &submit_uri,
+        "",
+        Some(json!({"data": {"email": format!(" {email} "), "topic": "support", "extra": "x"}})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(
+        submission["data"],
+        json!({"email": email, "topic": "support"})
+    );
+}
+
+#[tokio::test]
+async fn test_suspicious_submissions_are_quarantined() {
+    use crate::{
+        create_app,
+        database::subscribers,
+        models::form::FormTokenClaims,
+        tests::api::{segments::send, templates::get_test_user_with_jwt},
+        utils::jwt::{generate_signed_token, TokenAudience},
+    };
+    use axum::{
+        body::{self, Body},
+        http::{Method, Request},
+    };
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    // ALB経由の送信（X-Forwarded-Forの末尾がALBの追加した接続元）
+    async fn submit(app: &axum::Router, uri: &str, client_ip: &str, data: Value) -> Value {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .header("Content-Type", "application/json")
+            .header("X-Forwarded-For", format!("198.51.100.1, {client_ip}"))
+            .header("User-Agent", "TestBrowser/1.0")
+            .header("Referer", "https://example.com/lp")
+            .body(Body::from(json!({ "data": data }).to_string()))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    let (app, pool, _redis, _config) = create_app().await;
+    let (user_id, token) = get_test_user_with_jwt(&pool).await;
+
+    let (status, form) = send(
+        &app,
+        Method::POST,
+        "/api/forms",
+        &token,
+        Some(json!({
+            "name": "資料請求",
+            "slug": format!("spam-{}", Uuid::new_v4()),
+            "markdown_content": "# 資料請求",
+            "form_fields": [
+                {"field_type": "text", "name": "name", "label": "お名前"},
+                {"field_type": "email", "name": "email", "label": "メール", "required": true}
+            ]
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let form_id = Uuid::parse_str(form["id"].as_str().unwrap()).unwrap();
+    let form_uri = format!("/api/forms/{form_id}");
+    let (status, _) = send(
+        &app,
+        Method::PUT,
+        &form_uri,
+        &token,
+        Some(json!({"status": "published"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // 公開フォームには送信トークンが付く
+    let (status, public_form) =
+        send(&app, Method::GET, &format!("{form_uri}/public"), "", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(public_form["id"], form["id"]);
+    let fresh_token = public_form["form_token"].as_str().unwrap().to_string();
+
+    let waited_token = generate_signed_token(
+        TokenAudience::FormSubmit,
+        &FormTokenClaims {
+            sub: form_id,
+            iat: chrono::Utc::now().timestamp() - 10,
+            purpose: "form_submit".to_string(),
+        },
+    )
+    .unwrap();
+    let client_ip = format!("2001:db8::{:x}", Uuid::new_v4().as_u128() as u16);
+    let submit_uri = format!("{form_uri}/submit");
+    let email = |label: &str| format!("{label}-{}@example.com", Uuid::new_v4());
+
+    // トークンなし・ハニーポットへの入力・表示直後の送信は隔離され、購読者は作成されない
+    let suspicious = [
+        json!({"email": email("no-token")}),
+        json!({"email": email("honeypot"), "_token": waited_token, "_gotcha": "https://spam.example"}),
+        json!({"email": email("too-fast"), "_token": fresh_token}),
+    ];
+    for data in suspicious {
+        let receipt = submit(&app, &submit_uri, &client_ip, data.clone()).await;
+        assert!(receipt.get("status").is_none());
+        assert!(receipt.get("subscriber_id").is_none());
+        let subscriber =
+            subscribers::find_subscriber_by_email(&pool, data["email"].as_str().unwrap(), user_id)
+                .await
+                .unwrap();
+        assert!(subscriber.is_none());
+    }
+
+    let accepted_email = email("human");
+    submit(
+        &app,
+        &submit_uri,
+        &client_ip,
+        json!({"email": accepted_email, "_token": waited_token, "_gotcha": ""}),
+    )
+    .await;
+    assert!(
+        subscribers::find_subscriber_by_email(&pool, &accepted_email, user_id)
+            .await
+            .unwrap()
+            .is_some()
+    );
+
+    let (status, quarantined) = send(
+        &app,
+        Method::GET,
+        &format!("{form_uri}/submissions?status=quarantined"),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(quarantined["total"], 3);
+    let mut reasons: Vec<&str> = quarantined["submissions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["quarantine_reason"].as_str().unwrap())
+        .collect();
+    reasons.sort_unstable();
+    assert_eq!(reasons, vec!["honeypot", "missing_token", "too_fast"]);
+
+    let (_, accepted) = send(
+        &app,
+        Method::GET,
+        &format!("{form_uri}/submissions?status=accepted"),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(accepted["total"], 1);
+    let submission = &accepted["submissions"][0];
+    assert_eq!(submission["status"], "accepted");
+    assert_eq!(submission["ip_address"], client_ip);
+    assert_eq!(submission["user_agent"], "TestBrowser/1.0");
+    assert_eq!(submission["referrer"], "https://example.com/lp");
+    assert_eq!(submission["data"], json!({"email": accepted_email}));
+
+    // 隔離した送信は送信数に含めない
+    let (_, form) = send(&app, Method::GET, &form_uri, &token, None).await;
+    assert_eq!(form["submission_count"], 1);
+
+    let (status, _) = send(
+        &app,
+        Method::GET,
+        &format!("{form_uri}/submissions?status=spam"),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_hosted_form_page() {
+    use crate::{
+        create_app,
+        database::subscribers,
+        models::form::FormTokenClaims,
+        tests::api::{segments::send, templates::get_test_user_with_jwt},
+        utils::jwt::{generate_signed_token, TokenAudience},
+    };
+    use axum::{
+        body::{self, Body},
+        http::{header, Method, Request},
+        response::Response,
+    };
+    use tower::ServiceExt;
+
+    async fn request(
+        app: &axum::Router,
+        method: Method,
+        uri: &str,
+        form: Option<&str>,
+    ) -> Response {
+        let mut request = Request::builder().method(method).uri(uri);
+        if form.is_some() {
+            request = request.header("Content-Type", "application/x-www-form-urlencoded");
+        }
+        let body = form.map(|f| Body::from(f.to_string())).unwrap_or_default();
+        app.clone()
+            .oneshot(request.body(body).unwrap())
+            .await
+            .unwrap()
+    }
+
+    async fn text(response: Response) -> String {
+        let body = body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    let (app, pool, _redis, _config) = create_app().await;
+    let (user_id, token) = get_test_user_with_jwt(&pool).await;
+    let slug = format!("hosted-{}", Uuid::new_v4());
+
+    let create = |name: &str, settings: serde_json::Value| {
+        json!({
+            "name": name,
+            "slug": slug,
+            "markdown_content": "# ニュースレター登録\n\n最新情報をお届けします。",
+            "form_fields": [
+                {"field_type": "email", "name": "email", "label": "メール", "required": true},
+                {"field_type": "checkbox", "name": "topics", "label": "興味", "options": ["news", "events"]}
+            ],
+            "settings": settings
+        })
+    };
+    let (status, form) = send(
+        &app,
+        Method::POST,
+        "/api/forms",
+        &token,
+        Some(create(
+            "ニュースレター",
+            json!({"submit_button_text": "登録する"}),
+        )),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let form_id = Uuid::parse_str(form["id"].as_str().unwrap()).unwrap();
+    let page_uri = format!("/f/{slug}");
+
+    // 公開前は表示しない
+    let response = request(&app, Method::GET, &page_uri, None).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let (status, _) = send(
+        &app,
+        Method::PUT,
+        &format!("/api/forms/{form_id}"),
+        &token,
+        Some(json!({"status": "published"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let response = request(&app, Method::GET, &page_uri, None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let html = text(response).await;
+    assert!(html.contains("<h1>ニュースレター登録</h1>"));
+    assert!(html.contains(r#"<input type="email" id="mm-field-email" name="email""#));
+    assert!(html.contains(r#"name="_token""#));
+    assert!(html.contains("登録する</button>"));
+
+    let (status, embed) = send(
+        &app,
+        Method::GET,
+        &format!("/api/forms/{form_id}/embed"),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(embed["url"].as_str().unwrap().ends_with(&page_uri));
+    assert!(embed["script_tag"]
+        .as_str()
+        .unwrap()
+        .contains(&format!("{page_uri}/embed.js")));
+
+    let response = request(&app, Method::GET, &format!("{page_uri}/embed.js"), None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "application/javascript; charset=utf-8"
+    );
+    assert!(text(response).await.contains("?embed=true"));
+
+    let form_token = generate_signed_token(
+        TokenAudience::FormSubmit,
+        &FormTokenClaims {
+            sub: form_id,
+            iat: chrono::Utc::now().timestamp() - 10,
+            purpose: "form_submit".to_string(),
+        },
+    )
+    .unwrap();
+
+    // 入力内容に誤りがある場合は入力内容を残して再表示する
+    let response = request(
+        &app,
+        Method::POST,
+        &format!("{page_uri}?embed=true"),
+        Some(&format!("email=taro&topics=events&_token={form_token}")),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let html = text(response).await;
+    assert!(html.contains("メールアドレスの形式が正しくありません"));
+    assert!(html.contains(r#"value="taro""#));
+    assert!(html.contains(r#"value="events" checked"#));
+    assert!(html.contains(&format!(r#"value="{form_token}""#)));
+    assert!(html.contains("postMessage"));
+
+    let email = format!("hosted-{}@example.com", Uuid::new_v4());
+    let response = request(
+        &app,
+        Method::POST,
+        &page_uri,
+        Some(&format!(
+            "email={}&topics=news&topics=events&_token={form_token}&_gotcha=",
+            email.replace('@', "%40")
+        )),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(text(response).await.contains("送信が完了しました"));
+    let subscriber = subscribers::find_subscriber_by_email(&pool, &email, user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        subscriber.custom_fields["topics"],
+        json!(["news", "events"])
+    );
+
+    // 送信後のリダイレクト先が設定されている場合はリダイレクトする
+    let (status, _) = send(
+        &app,
+        Method::PUT,
+        &format!("/api/forms/{form_id}"),
+        &token,
+        Some(json!({"settings": {"success_redirect_url": "https://example.com/thanks"}})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let response = request(
+        &app,
+        Method::POST,
+        &page_uri,
+        Some(&format!(
+            "email=redirect-{}%40example.com&_token={form_token}",
+            Uuid::new_v4()
+        )),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(
+        response.headers()[header::LOCATION],
+        "https://example.com/thanks"
+    );
+
+    // 公開URLのスラッグは公開中のフォーム間で重複できない
+    let (other_user_id, other_token) = get_test_user_with_jwt(&pool).await;
+    assert_ne!(other_user_id, user_id);
+    let (status, other_form) = send(
+        &app,
+        Method::POST,
+        "/api/forms",
+        &other_token,
+        Some(create("別のフォーム", json!({}))),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = send(
+        &app,
+        Method::PUT,
+        &format!("/api/forms/{}", other_form["id"].as_str().unwrap()),
+        &other_token,
+        Some(json!({"status": "published"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
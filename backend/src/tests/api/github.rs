@@ -0,0 +1,424 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    body::{self, Body},
+    extract::{Path, Query, State},
+    http::{HeaderMap, Method, Request, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::get,
+    Router,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use crate::{
+    create_app,
+    services::github_service::sign_payload,
+    tests::api::{segments::send, templates::get_test_user_with_jwt},
+    utils::encryption,
+};
+
+const MOCK_TOKEN: &str = "ghp_mock_token";
+const REPOSITORY: &str = "acme/newsletters";
+
+/// GitHub REST APIのモック（main ブランチのファイルをパスごとに保持する）
+type MockFiles = Arc<Mutex<BTreeMap<String, String>>>;
+
+fn authorized(headers: &HeaderMap) -> bool {
+    headers.get("authorization").and_then(|v| v.to_str().ok())
+        == Some(&format!("Bearer {MOCK_TOKEN}"))
+}
+
+fn blob_sha(content: &str) -> String {
+    sign_payload("sha", content.as_bytes()).unwrap()[..40].to_string()
+}
+
+async fn mock_contents(
+    State(files): State<MockFiles>,
+    headers: HeaderMap,
+    Path((owner, repo, path)): Path<(String, String, String)>,
+    Query(query): Query<BTreeMap<String, String>>,
+) -> Response {
+    if !authorized(&headers) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    if format!("{owner}/{repo}") != REPOSITORY
+        || query.get("ref").map(String::as_str) != Some("main")
+    {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let files = files.lock().unwrap();
+    let path = path.trim_matches('/');
+    if let Some(content) = files.get(path) {
+        return Json(json!({
+            "type": "file",
+            "name": path.rsplit('/').next().unwrap(),
+            "path": path,
+            "sha": blob_sha(content),
+            "size": content.len(),
+            "encoding": "base64",
+            // GitHubは60文字ごとに改行を入れて返す
+            "content": STANDARD
+                .encode(content)
+                .as_bytes()
+                .chunks(60)
+                .map(|chunk| format!("{}\n", std::str::from_utf8(chunk).unwrap()))
+                .collect::<String>(),
+        }))
+        .into_response();
+    }
+
+    let prefix = if path.is_empty() {
+        String::new()
+    } else {
+        format!("{path}/")
+    };
+    let mut entries = BTreeMap::new();
+    for (file_path, content) in files.iter() {
+        let Some(rest) = file_path.strip_prefix(&prefix) else {
+            continue;
+        };
+        let entry = match rest.split_once('/') {
+            Some((dir, _)) => json!({
+                "type": "dir", "name": dir, "path": format!("{prefix}{dir}"), "sha": "d".repeat(40)
+            }),
+            None => json!({
+                "type": "file", "name": rest, "path": file_path, "sha": blob_sha(content), "size": content.len()
+            }),
+        };
+        entries.insert(entry["path"].as_str().unwrap().to_string(), entry);
+    }
+
+    if entries.is_empty() {
+        StatusCode::NOT_FOUND.into_response()
+    } else {
+        Json(entries.into_values().collect::<Vec<_>>()).into_response()
+    }
+}
+
+async fn start_mock_github(files: MockFiles) -> String {
+    let app = Router::new()
+        .route(
+            "/user",
+            get(|headers: HeaderMap| async move {
+                if authorized(&headers) {
+                    Json(json!({"login": "octo-writer"})).into_response()
+                } else {
+                    (
+                        StatusCode::UNAUTHORIZED,
+                        Json(json!({"message": "Bad credentials"})),
+                    )
+                        .into_response()
+                }
+            }),
+        )
+        .route(
+            "/user/repos",
+            get(|| async {
+                Json(json!([{
+                    "full_name": REPOSITORY,
+                    "private": true,
+                    "default_branch": "main",
+                    "description": "ニュースレターの原稿"
+                }]))
+            }),
+        )
+        .route(
+            "/repos/:owner/:repo",
+            get(|Path((owner, repo)): Path<(String, String)>| async move {
+                if format!("{owner}/{repo}") == REPOSITORY {
+                    Json(
+                        json!({"full_name": REPOSITORY, "private": true, "default_branch": "main"}),
+                    )
+                    .into_response()
+                } else {
+                    StatusCode::NOT_FOUND.into_response()
+                }
+            }),
+        )
+        .route("/repos/:owner/:repo/contents/*path", get(mock_contents))
+        .with_state(files);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    format!("http://{address}")
+}
+
+async fn send_webhook(
+    app: &Router,
+    uri: &str,
+    event: &str,
+    signature: &str,
+    payload: &Value,
+) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(uri)
+                .header("Content-Type", "application/json")
+                .header("X-GitHub-Event", event)
+                .header("X-Hub-Signature-256", signature)
+                .body(Body::from(payload.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let body = body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn test_github_import_and_push_sync() {
+    let files: MockFiles = Arc::new(Mutex::new(BTreeMap::from([
+        (
+            "newsletters/weekly.md".to_string(),
+            "---\nname: 週刊ニュース\nsubject: \"{{name}}様 今週のお知らせ\"\nvariables:\n  name: 読者\n---\n\n# 今週のお知らせ\n".to_string(),
+        ),
+        (
+            "newsletters/welcome.md".to_string(),
+            "---\nsubject: ようこそ\n---\n# ようこそ\n".to_string(),
+        ),
+        (
+            "newsletters/broken.md".to_string(),
+            "# 件名のない原稿\n".to_string(),
+        ),
+        ("newsletters/notes.txt".to_string(), "メモ".to_string()),
+        ("README.md".to_string(), "# 原稿置き場\n".to_string()),
+    ])));
+    let base_url = start_mock_github(files.clone()).await;
+    std::env::set_var("GITHUB_API_URL", &base_url);
+
+    let (app, pool, _redis, _config) = create_app().await;
+    let (user_id, token) = get_test_user_with_jwt(&pool).await;
+
+    // 連携前はリポジトリを一覧できない
+    let (status, _) = send(
+        &app,
+        Method::GET,
+        "/api/integrations/github/repos",
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        "/api/integrations/github/connection",
+        &token,
+        Some(json!({"access_token": "ghp_wrong"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, connection) = send(
+        &app,
+        Method::POST,
+        "/api/integrations/github/connection",
+        &token,
+        Some(json!({"access_token": MOCK_TOKEN})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(connection["account_login"], "octo-writer");
+    assert!(connection.get("access_token").is_none());
+    let webhook_secret = connection["webhook_secret"].as_str().unwrap().to_string();
+
+    // トークン・Webhookの秘密鍵は暗号化して保存する
+    let (stored_token, stored_secret): (String, String) = sqlx::query_as(
+        "SELECT access_token, webhook_secret FROM github_connections WHERE user_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert!(encryption::is_encrypted(&stored_token));
+    assert!(encryption::is_encrypted(&stored_secret));
+    assert_ne!(stored_secret, webhook_secret);
+    let webhook_uri = connection["webhook_url"]
+        .as_str()
+        .unwrap()
+        .split_once("/api/")
+        .map(|(_, path)| format!("/api/{path}"))
+        .unwrap();
+
+    let (status, repos) = send(
+        &app,
+        Method::GET,
+        "/api/integrations/github/repos",
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(repos["repositories"][0]["full_name"], REPOSITORY);
+
+    let (status, contents) = send(
+        &app,
+        Method::GET,
+        &format!("/api/integrations/github/repos/{REPOSITORY}/contents?path=newsletters"),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let names: Vec<&str> = contents["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        vec!["broken.md", "notes.txt", "weekly.md", "welcome.md"]
+    );
+
+    // ディレクトリ直下の.mdファイルを取り込む（件名のないファイルはエラーとして返す）
+    let (status, result) = send(
+        &app,
+        Method::POST,
+        "/api/integrations/github/import",
+        &token,
+        Some(json!({"repository": REPOSITORY, "path": "newsletters", "auto_sync": true})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(result["imported"].as_array().unwrap().len(), 2);
+    assert_eq!(result["errors"][0]["path"], "newsletters/broken.md");
+    let weekly = result["imported"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|t| t["path"] == "newsletters/weekly.md")
+        .unwrap();
+    assert_eq!(weekly["name"], "週刊ニュース");
+    assert_eq!(weekly["updated"], false);
+    let weekly_uri = format!("/api/templates/{}", weekly["template_id"].as_str().unwrap());
+
+    let (status, template) = send(&app, Method::GET, &weekly_uri, &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(template["subject_template"], "{{name}}様 今週のお知らせ");
+    assert_eq!(template["markdown_content"], "# 今週のお知らせ\n");
+    assert_eq!(template["variables"]["name"], "読者");
+
+    let (_, welcome) = send(
+        &app,
+        Method::GET,
+        &format!(
+            "/api/templates/{}",
+            result["imported"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|t| t["path"] == "newsletters/welcome.md")
+                .unwrap()["template_id"]
+                .as_str()
+                .unwrap()
+        ),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(welcome["name"], "welcome");
+
+    // 同じファイルを取り込み直すと既存のテンプレートを更新する
+    let (status, result) = send(
+        &app,
+        Method::POST,
+        "/api/integrations/github/import",
+        &token,
+        Some(json!({"repository": REPOSITORY, "path": "newsletters/weekly.md", "ref": "main", "auto_sync": true})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(result["imported"][0]["updated"], true);
+    assert_eq!(result["imported"][0]["template_id"], weekly["template_id"]);
+
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        "/api/integrations/github/import",
+        &token,
+        Some(json!({"repository": REPOSITORY, "path": "newsletters/notes.txt"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // pushで変更されたファイルを取り込み直す
+    files.lock().unwrap().insert(
+        "newsletters/weekly.md".to_string(),
+        "---\nname: 週刊ニュース\nsubject: \"{{name}}様 今週のお知らせ（改訂）\"\n---\n\n# 改訂版\n"
+            .to_string(),
+    );
+    let push = json!({
+        "ref": "refs/heads/main",
+        "repository": {"full_name": REPOSITORY},
+        "commits": [{"added": [], "modified": ["newsletters/weekly.md"], "removed": []}]
+    });
+
+    let (status, _) = send_webhook(&app, &webhook_uri, "push", "sha256=invalid", &push).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let signature = format!(
+        "sha256={}",
+        sign_payload(&webhook_secret, push.to_string().as_bytes()).unwrap()
+    );
+    let (status, synced) = send_webhook(&app, &webhook_uri, "push", &signature, &push).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(synced["imported"].as_array().unwrap().len(), 1);
+    assert_eq!(synced["imported"][0]["path"], "newsletters/weekly.md");
+
+    let (_, template) = send(&app, Method::GET, &weekly_uri, &token, None).await;
+    assert_eq!(
+        template["subject_template"],
+        "{{name}}様 今週のお知らせ（改訂）"
+    );
+    assert_eq!(template["markdown_content"], "# 改訂版\n");
+    assert_eq!(template["current_revision"], 2);
+
+    // ping等のpush以外のイベントは無視する
+    let ping = json!({"zen": "Keep it logically awesome."});
+    let signature = format!(
+        "sha256={}",
+        sign_payload(&webhook_secret, ping.to_string().as_bytes()).unwrap()
+    );
+    let (status, ignored) = send_webhook(&app, &webhook_uri, "ping", &signature, &ping).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(ignored["imported"], json!([]));
+
+    let (status, _) = send(
+        &app,
+        Method::DELETE,
+        "/api/integrations/github/connection",
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _) = send(
+        &app,
+        Method::GET,
+        "/api/integrations/github/connection",
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
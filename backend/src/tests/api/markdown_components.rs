@@ -0,0 +1,165 @@
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use crate::{
+    create_app,
+    database::subscriptions,
+    models::subscription::UpgradeRequest,
+    services::subscription_service,
+    tests::api::{segments::send, templates::get_test_user_with_jwt},
+};
+
+#[tokio::test]
+async fn test_markdown_components_render_in_template_preview() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (user_id, token) = get_test_user_with_jwt(&pool).await;
+    let footer = json!({
+        "name": "footer",
+        "kind": "snippet",
+        "content": "---\n\n配信停止は[こちら]({{unsubscribe_url}})"
+    });
+
+    // freeプランではカスタムコンポーネントを登録できない
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        "/api/markdown-components",
+        &token,
+        Some(footer.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::PAYMENT_REQUIRED);
+
+    let pro_plan = subscriptions::get_plan_by_name(&pool, "pro").await.unwrap();
+    subscription_service::upgrade_plan(
+        &pool,
+        user_id,
+        &UpgradeRequest {
+            plan_id: pro_plan.id,
+        },
+    )
+    .await
+    .unwrap();
+
+    let (status, snippet) = send(
+        &app,
+        Method::POST,
+        "/api/markdown-components",
+        &token,
+        Some(footer.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(snippet["kind"], "snippet");
+
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        "/api/markdown-components",
+        &token,
+        Some(footer),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    // 組み込みのコンポーネント名は使えない
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        "/api/markdown-components",
+        &token,
+        Some(json!({"name": "button", "kind": "component", "content": "<a></a>"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, component) = send(
+        &app,
+        Method::POST,
+        "/api/markdown-components",
+        &token,
+        Some(json!({
+            "name": "Notice",
+            "kind": "component",
+            "description": "お知らせ枠",
+            "content": "<table role=\"presentation\" width=\"100%\"><tr><td class=\"notice-{{ level | default: \"info\" }}\">{{{content}}}</td></tr></table>"
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(component["name"], "notice");
+
+    let (status, template) = send(
+        &app,
+        Method::POST,
+        "/api/templates",
+        &token,
+        Some(json!({
+            "name": "コンポーネント",
+            "subject_template": "お知らせ",
+            "markdown_content": ":::notice{level=warn}\n{{name}}様、**メンテナンス**のお知らせです。\n:::\n\n:::button{href=\"https://example.com\"} 詳細\n\n:::snippet{name=\"footer\"}"
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let uri = format!(
+        "/api/templates/{}/preview",
+        template["id"].as_str().unwrap()
+    );
+    let (status, preview) = send(
+        &app,
+        Method::POST,
+        &uri,
+        &token,
+        Some(json!({"variables": {"name": "山田", "unsubscribe_url": "https://example.com/u"}})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let html = preview["html"].as_str().unwrap();
+    assert!(html.contains("<td class=\"notice-warn\""));
+    assert!(html.contains(">山田様、<strong>メンテナンス</strong>のお知らせです。</p>"));
+    assert!(html.contains("詳細</a>"));
+    assert!(html.contains("<a href=\"https://example.com/u\" style="));
+
+    // 不明なコンポーネントは検証で報告する
+    let (status, body) = send(
+        &app,
+        Method::POST,
+        "/api/markdown/validate",
+        &token,
+        Some(json!({"markdown": ":::notice\n本文\n:::\n\n:::hero{title=\"夏\"}"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["valid"], false);
+    let errors = body["errors"].as_array().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].as_str().unwrap().contains("hero"));
+
+    let component_uri = format!(
+        "/api/markdown-components/{}",
+        component["id"].as_str().unwrap()
+    );
+    let (status, updated) = send(
+        &app,
+        Method::PUT,
+        &component_uri,
+        &token,
+        Some(json!({"name": "hero"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["name"], "hero");
+    assert_eq!(updated["description"], "お知らせ枠");
+
+    let (status, body) = send(&app, Method::GET, "/api/markdown-components", &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["total"], 2);
+
+    let (status, _) = send(&app, Method::DELETE, &component_uri, &token, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _) = send(&app, Method::GET, &component_uri, &token, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -1,7 +1,19 @@
+pub mod ab_tests;
 pub mod ai_test;
 pub mod campaigns;
 pub mod forms;
+pub mod github;
+pub mod markdown_components;
+pub mod segments;
+pub mod sending_domains;
+pub mod sequence_enrollments;
+pub mod sequence_exits;
+pub mod sequence_triggers;
 pub mod sequences;
+pub mod ses_webhook;
 pub mod stripe_test;
 pub mod subscriptions;
+pub mod template_revisions;
 pub mod templates;
+pub mod tracking;
+pub mod unsubscribe;
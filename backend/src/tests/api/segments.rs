@@ -0,0 +1,315 @@
+use axum::{
+    body::{self, Body},
+    http::{Method, Request, StatusCode},
+};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use crate::{
+    create_app,
+    database::subscribers,
+    models::subscriber::{CreateSubscriberRequest, SubscriberStatus},
+    tests::api::templates::{create_test_template, get_test_user_with_jwt},
+};
+
+pub async fn send(
+    app: &axum::Router,
+    method: Method,
+    uri: &str,
+    token: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Authorization", format!("Bearer {token}"));
+    let body = match body {
+        Some(body) => {
+            request = request.header("Content-Type", "application/json");
+            Body::from(body.to_string())
+        }
+        None => Body::empty(),
+    };
+
+    let response = app
+        .clone()
+        .oneshot(request.body(body).unwrap())
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let body = body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body = serde_json::from_slice(&body).unwrap_or(Value::Null);
+
+    (status, body)
+}
+
+#[tokio::test]
+async fn test_segment_crud_and_members() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (user_id, token) = get_test_user_with_jwt(&pool).await;
+
+    for (label, status, tags) in [
+        ("vip", SubscriberStatus::Active, vec!["customer", "vip"]),
+        ("customer", SubscriberStatus::Active, vec!["customer"]),
+        ("lead", SubscriberStatus::Active, vec!["lead"]),
+        ("unsubscribed", SubscriberStatus::Unsubscribed, vec!["vip"]),
+    ] {
+        subscribers::create_subscriber(
+            &pool,
+            user_id,
+            &CreateSubscriberRequest {
+                email: format!("{label}-{}@example.com", Uuid::new_v4()),
+                name: Some(label.to_string()),
+                status: Some(status),
+                tags: Some(tags.into_iter().map(String::from).collect()),
+                custom_fields: None,
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    // 空の条件グループは保存できない
+    let (status, body) = send(
+        &app,
+        Method::POST,
+        "/api/segments",
+        &token,
+        Some(json!({"name": "空", "rules": {"type": "all", "rules": []}})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].is_string());
+
+    // 存在しないキャンペーンを参照する条件は保存できない
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        "/api/segments",
+        &token,
+        Some(json!({
+            "name": "開封者",
+            "rules": {"type": "opened_campaign", "campaign_id": Uuid::new_v4()}
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let rules = json!({
+        "type": "all",
+        "rules": [
+            {"type": "tag", "tag": "vip"},
+            {"type": "status", "statuses": ["Active"]}
+        ]
+    });
+    let (status, segment) = send(
+        &app,
+        Method::POST,
+        "/api/segments",
+        &token,
+        Some(json!({"name": "VIP", "rules": rules})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(segment["subscriber_count"], 1);
+    assert_eq!(segment["rules"], rules);
+    let segment_id = segment["id"].as_str().unwrap().to_string();
+
+    let (status, list) = send(&app, Method::GET, "/api/segments", &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(list["total"], 1);
+    assert_eq!(list["segments"][0]["name"], "VIP");
+
+    // 条件を更新すると該当数も変わる
+    let (status, segment) = send(
+        &app,
+        Method::PUT,
+        &format!("/api/segments/{segment_id}"),
+        &token,
+        Some(json!({"rules": {"type": "tag", "tag": "customer"}})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(segment["name"], "VIP");
+    assert_eq!(segment["subscriber_count"], 2);
+
+    let (status, members) = send(
+        &app,
+        Method::GET,
+        &format!("/api/segments/{segment_id}/subscribers?limit=1"),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(members["total"], 2);
+    assert_eq!(members["subscribers"].as_array().unwrap().len(), 1);
+
+    let (status, preview) = send(
+        &app,
+        Method::POST,
+        "/api/segments/preview",
+        &token,
+        Some(json!({"rules": {"type": "not", "rule": {"type": "tag", "tag": "customer"}}})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(preview["subscriber_count"], 2);
+
+    // 他のユーザーからは参照できない
+    let (_, other_token) = get_test_user_with_jwt(&pool).await;
+    let (status, _) = send(
+        &app,
+        Method::GET,
+        &format!("/api/segments/{segment_id}"),
+        &other_token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // キャンペーンから参照されている間は削除できない
+    let template = create_test_template(&pool, user_id).await;
+    let (status, campaign) = send(
+        &app,
+        Method::POST,
+        "/api/campaigns",
+        &token,
+        Some(json!({
+            "name": "セグメント配信",
+            "subject": "セグメント配信",
+            "template_id": template.id,
+            "audience": {"segment_id": segment_id}
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let campaign_id = campaign["id"].as_str().unwrap().to_string();
+
+    let (status, preview) = send(
+        &app,
+        Method::GET,
+        &format!("/api/campaigns/{campaign_id}/subscribers"),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(preview["total"], 2);
+
+    let (status, _) = send(
+        &app,
+        Method::DELETE,
+        &format!("/api/segments/{segment_id}"),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, _) = send(
+        &app,
+        Method::DELETE,
+        &format!("/api/campaigns/{campaign_id}"),
+        &token,
+        None,
+    )
+    .await;
+    assert!(status.is_success());
+
+    let (status, _) = send(
+        &app,
+        Method::DELETE,
+        &format!("/api/segments/{segment_id}"),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _) = send(
+        &app,
+        Method::GET,
+        &format!("/api/segments/{segment_id}"),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_campaign_and_sequence_require_existing_segment() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (user_id, token) = get_test_user_with_jwt(&pool).await;
+    let template = create_test_template(&pool, user_id).await;
+
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        "/api/campaigns",
+        &token,
+        Some(json!({
+            "name": "セグメント配信",
+            "subject": "セグメント配信",
+            "template_id": template.id,
+            "audience": {"segment_id": Uuid::new_v4()}
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        "/api/sequences",
+        &token,
+        Some(json!({
+            "name": "セグメントシーケンス",
+            "trigger_type": "segment_entered",
+            "trigger_config": {}
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, segment) = send(
+        &app,
+        Method::POST,
+        "/api/segments",
+        &token,
+        Some(json!({"name": "全員", "rules": {"type": "status", "statuses": ["Active"]}})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, sequence) = send(
+        &app,
+        Method::POST,
+        "/api/sequences",
+        &token,
+        Some(json!({
+            "name": "セグメントシーケンス",
+            "trigger_type": "segment_entered",
+            "trigger_config": {"segment_id": segment["id"]}
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(sequence["trigger_type"], "segment_entered");
+
+    // シーケンスから参照されている間は削除できない
+    let (status, _) = send(
+        &app,
+        Method::DELETE,
+        &format!("/api/segments/{}", segment["id"].as_str().unwrap()),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
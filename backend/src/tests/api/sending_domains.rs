@@ -0,0 +1,138 @@
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use crate::{
+    create_app,
+    database::subscriptions,
+    models::subscription::UpgradeRequest,
+    services::subscription_service,
+    tests::api::{
+        segments::send,
+        templates::{create_test_template, get_test_user_with_jwt},
+    },
+    utils::encryption,
+};
+
+#[tokio::test]
+async fn test_sending_domain_crud_requires_plan_feature() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (user_id, token) = get_test_user_with_jwt(&pool).await;
+    let request = json!({"domain": "Mail.Example.COM."});
+
+    // freeプランではカスタムドメインを利用できない
+    let (status, body) = send(
+        &app,
+        Method::POST,
+        "/api/sending-domains",
+        &token,
+        Some(request.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::PAYMENT_REQUIRED);
+    assert!(body["error"].is_string());
+
+    let business_plan = subscriptions::get_plan_by_name(&pool, "business")
+        .await
+        .unwrap();
+    subscription_service::upgrade_plan(
+        &pool,
+        user_id,
+        &UpgradeRequest {
+            plan_id: business_plan.id,
+        },
+    )
+    .await
+    .unwrap();
+
+    let (status, body) = send(
+        &app,
+        Method::POST,
+        "/api/sending-domains",
+        &token,
+        Some(json!({"domain": "localhost"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].is_string());
+
+    let (status, domain) = send(
+        &app,
+        Method::POST,
+        "/api/sending-domains",
+        &token,
+        Some(request.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(domain["domain"], "mail.example.com");
+    assert_eq!(domain["status"], "pending");
+    assert!(domain.get("dkim_private_key").is_none());
+
+    // DKIM秘密鍵は暗号化して保存する
+    let stored_key: String =
+        sqlx::query_scalar("SELECT dkim_private_key FROM sending_domains WHERE id = $1::uuid")
+            .bind(domain["id"].as_str().unwrap())
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert!(encryption::is_encrypted(&stored_key));
+    assert!(!stored_key.contains("PRIVATE KEY"));
+
+    let records = domain["dns_records"].as_array().unwrap();
+    assert_eq!(records.len(), 3);
+    let selector = domain["dkim_selector"].as_str().unwrap();
+    assert_eq!(
+        records[0]["name"],
+        format!("{selector}._domainkey.mail.example.com")
+    );
+    assert!(records[0]["value"]
+        .as_str()
+        .unwrap()
+        .starts_with("v=DKIM1; k=rsa; p="));
+    assert_eq!(records[1]["purpose"], "spf");
+    assert_eq!(records[2]["name"], "_dmarc.mail.example.com");
+
+    // 同じドメインは重複して登録できない
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        "/api/sending-domains",
+        &token,
+        Some(request),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, body) = send(&app, Method::GET, "/api/sending-domains", &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["total"], 1);
+
+    let uri = format!("/api/sending-domains/{}", domain["id"].as_str().unwrap());
+    let (status, body) = send(&app, Method::GET, &uri, &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["dkim_public_key"], domain["dkim_public_key"]);
+
+    // 認証前のドメインは送信元に使えない
+    let template = create_test_template(&pool, user_id).await;
+    let (status, body) = send(
+        &app,
+        Method::POST,
+        "/api/campaigns",
+        &token,
+        Some(json!({
+            "name": "独自ドメイン",
+            "subject": "独自ドメイン",
+            "template_id": template.id,
+            "from_email": "news@mail.example.com"
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("mail.example.com"));
+
+    let (status, _) = send(&app, Method::DELETE, &uri, &token, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _) = send(&app, Method::GET, &uri, &token, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,393 @@
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    create_app,
+    database::subscribers,
+    models::subscriber::{CreateSubscriberRequest, Subscriber, SubscriberStatus},
+    tests::api::{segments::send, templates::get_test_user_with_jwt},
+};
+
+async fn create_subscriber(
+    pool: &PgPool,
+    user_id: Uuid,
+    email: &str,
+    tags: &[&str],
+    status: Option<SubscriberStatus>,
+) -> Subscriber {
+    subscribers::create_subscriber(
+        pool,
+        user_id,
+        &CreateSubscriberRequest {
+            email: email.to_string(),
+            name: Some("登録テスト".to_string()),
+            status,
+            tags: Some(tags.iter().map(|t| t.to_string()).collect()),
+            custom_fields: None,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+// 待機ステップを1つ持つ手動トリガーのシーケンスを作成
+async fn create_sequence(app: &axum::Router, token: &str, activate: bool) -> String {
+    let (status, sequence) = send(
+        app,
+        Method::POST,
+        "/api/sequences",
+        token,
+        Some(json!({
+            "name": "手動登録シーケンス",
+            "trigger_type": "manual"
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(sequence["reentry_policy"], "never");
+    let sequence_id = sequence["id"].as_str().unwrap().to_string();
+
+    let (status, _) = send(
+        app,
+        Method::POST,
+        &format!("/api/sequences/{sequence_id}/steps"),
+        token,
+        Some(json!({
+            "name": "1日待機",
+            "step_order": 1,
+            "step_type": "wait",
+            "delay_value": 1,
+            "delay_unit": "days"
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    if activate {
+        let (status, _) = send(
+            app,
+            Method::POST,
+            &format!("/api/sequences/{sequence_id}/activate"),
+            token,
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+    }
+
+    sequence_id
+}
+
+async fn enroll(app: &axum::Router, token: &str, sequence_id: &str, body: Value) -> Value {
+    let (status, result) = send(
+        app,
+        Method::POST,
+        &format!("/api/sequences/{sequence_id}/enrollments"),
+        token,
+        Some(body),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{result}");
+    result
+}
+
+#[tokio::test]
+async fn test_enroll_subscribers_by_ids_tag_and_csv() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (user_id, token) = get_test_user_with_jwt(&pool).await;
+    let suffix = Uuid::new_v4();
+
+    let by_id = create_subscriber(
+        &pool,
+        user_id,
+        &format!("id-{suffix}@example.com"),
+        &["vip"],
+        None,
+    )
+    .await;
+    let by_tag = create_subscriber(
+        &pool,
+        user_id,
+        &format!("tag-{suffix}@example.com"),
+        &["vip"],
+        None,
+    )
+    .await;
+    let unsubscribed = create_subscriber(
+        &pool,
+        user_id,
+        &format!("unsub-{suffix}@example.com"),
+        &["vip"],
+        Some(SubscriberStatus::Unsubscribed),
+    )
+    .await;
+    let by_csv = create_subscriber(
+        &pool,
+        user_id,
+        &format!("csv-{suffix}@example.com"),
+        &[],
+        None,
+    )
+    .await;
+
+    // 下書きのシーケンスには登録できない
+    let draft_id = create_sequence(&app, &token, false).await;
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        &format!("/api/sequences/{draft_id}/enrollments"),
+        &token,
+        Some(json!({ "subscriber_ids": [by_id.id] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let sequence_id = create_sequence(&app, &token, true).await;
+
+    // 登録対象の指定がない
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        &format!("/api/sequences/{sequence_id}/enrollments"),
+        &token,
+        Some(json!({ "tag": " " })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let missing_id = Uuid::new_v4();
+    let csv = format!(
+        "name,email\nCSV,CSV-{suffix}@EXAMPLE.com\n不明,missing-{suffix}@example.com\n空欄,\n"
+    );
+    let result = enroll(
+        &app,
+        &token,
+        &sequence_id,
+        json!({
+            "subscriber_ids": [by_id.id, missing_id],
+            "tag": "vip",
+            "csv_content": csv,
+            "email_column": 1
+        }),
+    )
+    .await;
+    assert_eq!(result["enrolled"], 3);
+    assert_eq!(result["skipped_inactive"], 1);
+    assert_eq!(result["skipped_in_progress"], 0);
+    assert_eq!(
+        result["not_found"],
+        json!([
+            missing_id.to_string(),
+            format!("missing-{suffix}@example.com")
+        ])
+    );
+    assert_eq!(result["errors"].as_array().unwrap().len(), 1);
+
+    // 登録中の購読者は重複して登録しない
+    let result = enroll(
+        &app,
+        &token,
+        &sequence_id,
+        json!({ "subscriber_ids": [by_id.id, by_tag.id, by_csv.id] }),
+    )
+    .await;
+    assert_eq!(result["enrolled"], 0);
+    assert_eq!(result["skipped_in_progress"], 3);
+
+    let (status, list) = send(
+        &app,
+        Method::GET,
+        &format!("/api/sequences/{sequence_id}/enrollments"),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(list["total"], 3);
+    assert_eq!(list["counts"]["active"], 3);
+    let enrollments = list["enrollments"].as_array().unwrap();
+    let emails: Vec<&str> = enrollments
+        .iter()
+        .map(|e| e["subscriber_email"].as_str().unwrap())
+        .collect();
+    assert!(emails.contains(&by_csv.email.as_str()));
+    assert!(!emails.contains(&unsubscribed.email.as_str()));
+    for enrollment in enrollments {
+        assert_eq!(enrollment["status"], "active");
+        assert_eq!(enrollment["current_step_name"], "1日待機");
+        assert_eq!(enrollment["current_step_order"], 1);
+        assert_eq!(enrollment["metadata"]["source"], "manual");
+    }
+}
+
+#[tokio::test]
+async fn test_pause_resume_cancel_enrollment_and_reentry_policy() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (user_id, token) = get_test_user_with_jwt(&pool).await;
+    let subscriber = create_subscriber(
+        &pool,
+        user_id,
+        &format!("reentry-{}@example.com", Uuid::new_v4()),
+        &[],
+        None,
+    )
+    .await;
+    let sequence_id = create_sequence(&app, &token, true).await;
+
+    let result = enroll(
+        &app,
+        &token,
+        &sequence_id,
+        json!({ "subscriber_ids": [subscriber.id] }),
+    )
+    .await;
+    assert_eq!(result["enrolled"], 1);
+
+    let (_, list) = send(
+        &app,
+        Method::GET,
+        &format!("/api/sequences/{sequence_id}/enrollments"),
+        &token,
+        None,
+    )
+    .await;
+    let enrollment_id = list["enrollments"][0]["id"].as_str().unwrap().to_string();
+    let path =
+        |action: &str| format!("/api/sequences/{sequence_id}/enrollments/{enrollment_id}/{action}");
+
+    let (status, paused) = send(&app, Method::POST, &path("pause"), &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(paused["status"], "paused");
+
+    let (status, _) = send(&app, Method::POST, &path("pause"), &token, None).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    // 一時停止中も登録中として扱う
+    let result = enroll(
+        &app,
+        &token,
+        &sequence_id,
+        json!({ "subscriber_ids": [subscriber.id] }),
+    )
+    .await;
+    assert_eq!(result["skipped_in_progress"], 1);
+
+    let (status, resumed) = send(&app, Method::POST, &path("resume"), &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(resumed["status"], "active");
+
+    let (status, cancelled) = send(&app, Method::POST, &path("cancel"), &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(cancelled["status"], "cancelled");
+    assert!(cancelled["cancelled_at"].is_string());
+
+    let (status, _) = send(&app, Method::POST, &path("resume"), &token, None).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, list) = send(
+        &app,
+        Method::GET,
+        &format!("/api/sequences/{sequence_id}/enrollments?status=cancelled"),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(list["total"], 1);
+    assert_eq!(list["enrollments"][0]["id"], enrollment_id.as_str());
+
+    let (status, _) = send(
+        &app,
+        Method::GET,
+        &format!("/api/sequences/{sequence_id}/enrollments?status=unknown"),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // 既定のポリシー（never）では一度登録された購読者は再登録しない
+    let result = enroll(
+        &app,
+        &token,
+        &sequence_id,
+        json!({ "subscriber_ids": [subscriber.id] }),
+    )
+    .await;
+    assert_eq!(result["enrolled"], 0);
+    assert_eq!(result["skipped_reentry"], 1);
+
+    let (status, _) = send(
+        &app,
+        Method::PUT,
+        &format!("/api/sequences/{sequence_id}"),
+        &token,
+        Some(json!({ "reentry_policy": "always" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, sequence) = send(
+        &app,
+        Method::PUT,
+        &format!("/api/sequences/{sequence_id}"),
+        &token,
+        Some(json!({ "reentry_policy": "after_completion" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(sequence["reentry_policy"], "after_completion");
+
+    let result = enroll(
+        &app,
+        &token,
+        &sequence_id,
+        json!({ "subscriber_ids": [subscriber.id] }),
+    )
+    .await;
+    assert_eq!(result["enrolled"], 1);
+
+    let (_, list) = send(
+        &app,
+        Method::GET,
+        &format!("/api/sequences/{sequence_id}/enrollments"),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(list["total"], 2);
+    assert_eq!(list["counts"], json!({"active": 1, "cancelled": 1}));
+}
+
+#[tokio::test]
+async fn test_enrollments_require_sequence_owner() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (_user_id, token) = get_test_user_with_jwt(&pool).await;
+    let (_other_id, other_token) = get_test_user_with_jwt(&pool).await;
+    let sequence_id = create_sequence(&app, &token, true).await;
+
+    let (status, _) = send(
+        &app,
+        Method::GET,
+        &format!("/api/sequences/{sequence_id}/enrollments"),
+        &other_token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        &format!(
+            "/api/sequences/{sequence_id}/enrollments/{}/cancel",
+            Uuid::new_v4()
+        ),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
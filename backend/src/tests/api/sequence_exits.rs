@@ -0,0 +1,355 @@
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode},
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use crate::{
+    create_app,
+    database::{campaigns, subscribers},
+    models::{
+        campaign::{Campaign, CreateCampaignRequest},
+        subscriber::{CreateSubscriberRequest, Subscriber},
+    },
+    services::{tracking_service::TrackingService, unsubscribe_service::UnsubscribeService},
+    tests::api::{
+        segments::send,
+        templates::{create_test_template, get_test_user_with_jwt},
+    },
+};
+
+async fn create_subscriber(pool: &PgPool, user_id: Uuid) -> Subscriber {
+    subscribers::create_subscriber(
+        pool,
+        user_id,
+        &CreateSubscriberRequest {
+            email: format!("exit-{}@example.com", Uuid::new_v4()),
+            name: None,
+            status: None,
+            tags: Some(vec!["trial".to_string()]),
+            custom_fields: None,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+async fn create_campaign(pool: &PgPool, user_id: Uuid) -> Campaign {
+    let template = create_test_template(pool, user_id).await;
+    campaigns::create_campaign(
+        pool,
+        user_id,
+        &CreateCampaignRequest {
+            name: "終了条件テストキャンペーン".to_string(),
+            description: None,
+            subject: "終了条件テスト".to_string(),
+            template_id: template.id,
+            audience: None,
+            from_email: None,
+            from_name: None,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+// 購読者IDごとのエンロールメントのステータス
+async fn enrollment_status(pool: &PgPool, sequence_id: &str, subscriber_id: Uuid) -> String {
+    sqlx::query_scalar::<_, String>(
+        "SELECT status FROM sequence_enrollments WHERE sequence_id = $1::uuid AND subscriber_id = $2",
+    )
+    .bind(sequence_id)
+    .bind(subscriber_id)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+// 計測リクエストの終了条件はバックグラウンドで処理されるため、ステータスが変わるまで待つ
+async fn wait_for_status(
+    pool: &PgPool,
+    sequence_id: &str,
+    subscriber_id: Uuid,
+    expected: &str,
+) -> String {
+    for _ in 0..50 {
+        let status = enrollment_status(pool, sequence_id, subscriber_id).await;
+        if status == expected {
+            return status;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    enrollment_status(pool, sequence_id, subscriber_id).await
+}
+
+// 終了時に記録されたステップログ（ステータスと理由）
+async fn exit_logs(pool: &PgPool, sequence_id: &str) -> Vec<(String, Option<String>)> {
+    sqlx::query_as::<_, (String, Option<String>)>(
+        r#"
+        SELECT l.status, l.error_message
+        FROM sequence_step_logs l
+        JOIN sequence_enrollments e ON e.id = l.enrollment_id
+        WHERE e.sequence_id = $1::uuid AND l.status IN ('goal_reached', 'exited')
+        ORDER BY l.executed_at
+        "#,
+    )
+    .bind(sequence_id)
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+async fn request(app: &axum::Router, method: Method, path: &str) -> StatusCode {
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method(method)
+                .uri(path)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .status()
+}
+
+#[tokio::test]
+async fn test_exit_conditions_end_enrollments_on_events() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (user_id, token) = get_test_user_with_jwt(&pool).await;
+    let campaign = create_campaign(&pool, user_id).await;
+
+    let (status, sequence) = send(
+        &app,
+        Method::POST,
+        "/api/sequences",
+        &token,
+        Some(json!({
+            "name": "購入促進シーケンス",
+            "trigger_type": "manual",
+            "exit_conditions": [
+                {"type": "tag_added", "tag": "customer"},
+                {"type": "unsubscribed"},
+                {"type": "link_clicked", "campaign_id": campaign.id, "url": "/checkout", "outcome": "exited"}
+            ]
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "{sequence}");
+    assert_eq!(sequence["exit_conditions"].as_array().unwrap().len(), 3);
+    let sequence_id = sequence["id"].as_str().unwrap().to_string();
+
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        &format!("/api/sequences/{sequence_id}/steps"),
+        &token,
+        Some(json!({
+            "name": "3日待機",
+            "step_order": 1,
+            "step_type": "wait",
+            "delay_value": 3,
+            "delay_unit": "days"
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        &format!("/api/sequences/{sequence_id}/activate"),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let buyer = create_subscriber(&pool, user_id).await;
+    let leaver = create_subscriber(&pool, user_id).await;
+    let clicker = create_subscriber(&pool, user_id).await;
+    let waiting = create_subscriber(&pool, user_id).await;
+
+    // 登録前のクリックは終了条件に含めない
+    let tracking = TrackingService::new();
+    let checkout = tracking
+        .click_url(campaign.id, clicker.id, "https://example.com/checkout")
+        .unwrap();
+    let checkout_path = format!("/t/{}", checkout.split_once("/t/").unwrap().1);
+    assert_eq!(
+        request(&app, Method::GET, &checkout_path).await,
+        StatusCode::FOUND
+    );
+
+    let (status, result) = send(
+        &app,
+        Method::POST,
+        &format!("/api/sequences/{sequence_id}/enrollments"),
+        &token,
+        Some(json!({ "subscriber_ids": [buyer.id, leaver.id, clicker.id, waiting.id] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(result["enrolled"], 4);
+
+    // タグの追加で目標達成
+    let (status, _) = send(
+        &app,
+        Method::PUT,
+        &format!("/api/subscribers/{}", buyer.id),
+        &token,
+        Some(json!({ "tags": ["trial", "customer"] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        enrollment_status(&pool, &sequence_id, buyer.id).await,
+        "goal_reached"
+    );
+
+    // 配信停止で離脱
+    let unsubscribe_url = UnsubscribeService::with_base_url("http://localhost:3000")
+        .unsubscribe_url(&leaver, Some(campaign.id))
+        .unwrap();
+    let unsubscribe_path = unsubscribe_url.trim_start_matches("http://localhost:3000");
+    assert_eq!(
+        request(&app, Method::POST, unsubscribe_path).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        enrollment_status(&pool, &sequence_id, leaver.id).await,
+        "exited"
+    );
+
+    // 登録後のクリックで終了（outcomeの指定に従う）
+    assert_eq!(
+        enrollment_status(&pool, &sequence_id, clicker.id).await,
+        "active"
+    );
+    assert_eq!(
+        request(&app, Method::GET, &checkout_path).await,
+        StatusCode::FOUND
+    );
+    assert_eq!(
+        wait_for_status(&pool, &sequence_id, clicker.id, "exited").await,
+        "exited"
+    );
+
+    assert_eq!(
+        enrollment_status(&pool, &sequence_id, waiting.id).await,
+        "active"
+    );
+
+    assert_eq!(
+        exit_logs(&pool, &sequence_id).await,
+        vec![
+            (
+                "goal_reached".to_string(),
+                Some("タグ「customer」が付きました".to_string())
+            ),
+            ("exited".to_string(), Some("配信停止しました".to_string())),
+            (
+                "exited".to_string(),
+                Some("「/checkout」を含むリンクをクリックしました".to_string())
+            ),
+        ]
+    );
+
+    let (status, list) = send(
+        &app,
+        Method::GET,
+        &format!("/api/sequences/{sequence_id}/enrollments"),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        list["counts"],
+        json!({"active": 1, "exited": 2, "goal_reached": 1})
+    );
+    let (status, _) = send(
+        &app,
+        Method::GET,
+        &format!("/api/sequences/{sequence_id}/enrollments?status=goal_reached"),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_sequence_exit_conditions_are_validated() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (_user_id, token) = get_test_user_with_jwt(&pool).await;
+    let (other_user_id, _other_token) = get_test_user_with_jwt(&pool).await;
+    let other_campaign = create_campaign(&pool, other_user_id).await;
+
+    let invalid: [Value; 5] = [
+        json!({"type": "unsubscribed"}),
+        json!([{"type": "purchased"}]),
+        json!([{"type": "tag_added", "tag": ""}]),
+        json!([{"type": "link_clicked", "campaign_id": other_campaign.id}]),
+        json!([{"type": "form_submitted", "form_id": Uuid::new_v4()}]),
+    ];
+    for exit_conditions in invalid {
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            "/api/sequences",
+            &token,
+            Some(json!({
+                "name": "不正な終了条件",
+                "trigger_type": "manual",
+                "exit_conditions": exit_conditions,
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{exit_conditions}");
+        assert!(body["error"].is_string());
+    }
+
+    let (status, sequence) = send(
+        &app,
+        Method::POST,
+        "/api/sequences",
+        &token,
+        Some(json!({ "name": "終了条件なし", "trigger_type": "manual" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(sequence["exit_conditions"], json!([]));
+    let sequence_id = sequence["id"].as_str().unwrap();
+
+    let (status, _) = send(
+        &app,
+        Method::PUT,
+        &format!("/api/sequences/{sequence_id}"),
+        &token,
+        Some(json!({ "exit_conditions": [{"type": "custom_field", "field": "plan"}] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, updated) = send(
+        &app,
+        Method::PUT,
+        &format!("/api/sequences/{sequence_id}"),
+        &token,
+        Some(json!({
+            "exit_conditions": [
+                {"type": "custom_field", "field": "plan", "operator": "equals", "value": "paid"},
+                {"type": "form_submitted"}
+            ]
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        updated["exit_conditions"][1],
+        json!({"type": "form_submitted"})
+    );
+}
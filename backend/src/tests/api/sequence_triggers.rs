@@ -0,0 +1,345 @@
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode},
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use crate::{
+    create_app,
+    database::{campaigns, sequences, subscribers},
+    models::{
+        campaign::{Campaign, CreateCampaignRequest},
+        sequence::{CreateSequenceRequest, Sequence},
+        subscriber::{CreateSubscriberRequest, Subscriber},
+    },
+    services::tracking_service::TrackingService,
+    tests::api::{
+        segments::send,
+        templates::{create_test_template, get_test_user_with_jwt},
+    },
+};
+
+async fn create_active_sequence(
+    pool: &PgPool,
+    user_id: Uuid,
+    trigger_type: &str,
+    trigger_config: Value,
+) -> Sequence {
+    let sequence = sequences::create_sequence(
+        pool,
+        user_id,
+        CreateSequenceRequest {
+            name: format!("{trigger_type}トリガー"),
+            description: None,
+            trigger_type: trigger_type.to_string(),
+            trigger_config: Some(trigger_config),
+            reentry_policy: None,
+            exit_conditions: None,
+            send_window: None,
+        },
+    )
+    .await
+    .unwrap();
+    sequences::update_sequence_status(pool, sequence.id, "active")
+        .await
+        .unwrap();
+    sequence
+}
+
+async fn create_subscriber(
+    pool: &PgPool,
+    user_id: Uuid,
+    tags: &[&str],
+    custom_fields: Value,
+) -> Subscriber {
+    subscribers::create_subscriber(
+        pool,
+        user_id,
+        &CreateSubscriberRequest {
+            email: format!("trigger-{}@example.com", Uuid::new_v4()),
+            name: None,
+            status: None,
+            tags: Some(tags.iter().map(|t| t.to_string()).collect()),
+            custom_fields: Some(custom_fields),
+        },
+    )
+    .await
+    .unwrap()
+}
+
+async fn create_campaign(pool: &PgPool, user_id: Uuid) -> Campaign {
+    let template = create_test_template(pool, user_id).await;
+    campaigns::create_campaign(
+        pool,
+        user_id,
+        &CreateCampaignRequest {
+            name: "トリガーテストキャンペーン".to_string(),
+            description: None,
+            subject: "トリガーテスト".to_string(),
+            template_id: template.id,
+            audience: None,
+            from_email: None,
+            from_name: None,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+// シーケンスに登録された購読者のメタデータ（登録順）
+async fn enrollment_metadata(pool: &PgPool, sequence_id: Uuid) -> Vec<(Uuid, Value)> {
+    sqlx::query_as::<_, (Uuid, Value)>(
+        "SELECT subscriber_id, metadata FROM sequence_enrollments WHERE sequence_id = $1 ORDER BY enrolled_at",
+    )
+    .bind(sequence_id)
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+// 計測リクエストのトリガーはバックグラウンドで処理されるため、登録数が揃うまで待つ
+async fn wait_for_enrollments(
+    pool: &PgPool,
+    sequence_id: Uuid,
+    count: usize,
+) -> Vec<(Uuid, Value)> {
+    for _ in 0..50 {
+        let enrollments = enrollment_metadata(pool, sequence_id).await;
+        if enrollments.len() >= count {
+            return enrollments;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    enrollment_metadata(pool, sequence_id).await
+}
+
+async fn track(app: &axum::Router, url: &str) -> StatusCode {
+    let path = url
+        .split_once("/t/")
+        .map(|(_, path)| format!("/t/{path}"))
+        .unwrap();
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri(path)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .status()
+}
+
+#[tokio::test]
+async fn test_subscriber_update_fires_tag_and_field_triggers() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (user_id, token) = get_test_user_with_jwt(&pool).await;
+
+    let tag_added =
+        create_active_sequence(&pool, user_id, "tag_added", json!({"tag": "vip"})).await;
+    let tag_removed =
+        create_active_sequence(&pool, user_id, "tag_removed", json!({"tag": "trial"})).await;
+    let plan_changed = create_active_sequence(
+        &pool,
+        user_id,
+        "custom_field_changed",
+        json!({"field": "plan", "to": "pro"}),
+    )
+    .await;
+
+    let subscriber = create_subscriber(&pool, user_id, &["trial"], json!({"plan": "free"})).await;
+
+    let (status, _) = send(
+        &app,
+        Method::PUT,
+        &format!("/api/subscribers/{}", subscriber.id),
+        &token,
+        Some(json!({
+            "tags": ["vip"],
+            "custom_fields": {"plan": "pro"}
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let added = enrollment_metadata(&pool, tag_added.id).await;
+    assert_eq!(added.len(), 1);
+    assert_eq!(added[0].0, subscriber.id);
+    assert_eq!(added[0].1, json!({"tag": "vip"}));
+
+    let removed = enrollment_metadata(&pool, tag_removed.id).await;
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0].1, json!({"tag": "trial"}));
+
+    let changed = enrollment_metadata(&pool, plan_changed.id).await;
+    assert_eq!(changed.len(), 1);
+    assert_eq!(
+        changed[0].1,
+        json!({"field": "plan", "from": "free", "to": "pro"})
+    );
+
+    // 変更のない更新や条件に合わない変更では登録しない
+    let (status, _) = send(
+        &app,
+        Method::PUT,
+        &format!("/api/subscribers/{}", subscriber.id),
+        &token,
+        Some(json!({
+            "tags": ["vip", "trial"],
+            "custom_fields": {"plan": "pro", "company": "MarkMail"}
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    assert_eq!(enrollment_metadata(&pool, tag_added.id).await.len(), 1);
+    assert_eq!(enrollment_metadata(&pool, tag_removed.id).await.len(), 1);
+    assert_eq!(enrollment_metadata(&pool, plan_changed.id).await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_campaign_engagement_triggers_enroll_on_first_event() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (user_id, _token) = get_test_user_with_jwt(&pool).await;
+    let campaign = create_campaign(&pool, user_id).await;
+    let other_campaign = create_campaign(&pool, user_id).await;
+
+    let opened = create_active_sequence(
+        &pool,
+        user_id,
+        "campaign_opened",
+        json!({"campaign_id": campaign.id}),
+    )
+    .await;
+    let clicked = create_active_sequence(
+        &pool,
+        user_id,
+        "campaign_clicked",
+        json!({"campaign_id": campaign.id, "url": "/pricing"}),
+    )
+    .await;
+    let other_opened = create_active_sequence(
+        &pool,
+        user_id,
+        "campaign_opened",
+        json!({"campaign_id": other_campaign.id}),
+    )
+    .await;
+
+    let subscriber = create_subscriber(&pool, user_id, &[], json!({})).await;
+    let tracking = TrackingService::new();
+
+    // 条件に合わないURLのクリックでもクリックトリガーは動かない（開封としては計上される）
+    let docs = tracking
+        .click_url(campaign.id, subscriber.id, "https://example.com/docs")
+        .unwrap();
+    assert_eq!(track(&app, &docs).await, StatusCode::FOUND);
+    assert!(enrollment_metadata(&pool, clicked.id).await.is_empty());
+
+    let open = tracking.open_url(campaign.id, subscriber.id).unwrap();
+    assert_eq!(track(&app, &open).await, StatusCode::OK);
+
+    let pricing = tracking
+        .click_url(campaign.id, subscriber.id, "https://example.com/pricing")
+        .unwrap();
+    for _ in 0..2 {
+        assert_eq!(track(&app, &pricing).await, StatusCode::FOUND);
+    }
+
+    // 初回のクリックで開封とみなされたときに登録され、以降の開封では重複しない
+    let opened_enrollments = wait_for_enrollments(&pool, opened.id, 1).await;
+    assert_eq!(opened_enrollments.len(), 1);
+    assert_eq!(
+        opened_enrollments[0].1["campaign_id"],
+        json!(campaign.id.to_string())
+    );
+
+    let clicked_enrollments = wait_for_enrollments(&pool, clicked.id, 1).await;
+    assert_eq!(clicked_enrollments.len(), 1);
+    assert_eq!(
+        clicked_enrollments[0].1["url"],
+        json!("https://example.com/pricing")
+    );
+
+    assert!(enrollment_metadata(&pool, other_opened.id).await.is_empty());
+}
+
+#[tokio::test]
+async fn test_create_sequence_validates_trigger_config() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (user_id, token) = get_test_user_with_jwt(&pool).await;
+    let campaign = create_campaign(&pool, user_id).await;
+
+    let invalid = [
+        ("tag_removed", json!("trial")),
+        ("custom_field_changed", json!({})),
+        ("custom_field_changed", json!({"field": " "})),
+        ("campaign_opened", json!({"campaign_id": Uuid::new_v4()})),
+        (
+            "campaign_opened",
+            json!({"campaign_id": campaign.id, "url": "/pricing"}),
+        ),
+        ("campaign_clicked", json!({"campaign_id": "not-a-uuid"})),
+        ("birthday", json!({"days_before": -1})),
+        ("anniversary", json!({"days_before": 3})),
+        (
+            "anniversary",
+            json!({"field": "joined_on", "days_before": 61}),
+        ),
+    ];
+    for (trigger_type, trigger_config) in invalid {
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            "/api/sequences",
+            &token,
+            Some(json!({
+                "name": "不正なトリガー",
+                "trigger_type": trigger_type,
+                "trigger_config": trigger_config,
+            })),
+        )
+        .await;
+        assert_eq!(
+            status,
+            StatusCode::BAD_REQUEST,
+            "{trigger_type} {trigger_config}"
+        );
+        assert!(body["error"].is_string());
+    }
+
+    let valid = [
+        ("tag_removed", json!({"tag": "trial"})),
+        (
+            "custom_field_changed",
+            json!({"field": "plan", "from": "free"}),
+        ),
+        ("campaign_opened", json!({"campaign_id": campaign.id})),
+        ("campaign_clicked", json!({"url": "/pricing"})),
+        ("birthday", json!({})),
+        (
+            "anniversary",
+            json!({"field": "joined_on", "days_before": 7}),
+        ),
+    ];
+    for (trigger_type, trigger_config) in valid {
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            "/api/sequences",
+            &token,
+            Some(json!({
+                "name": "トリガー",
+                "trigger_type": trigger_type,
+                "trigger_config": trigger_config,
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED, "{trigger_type} {body}");
+        assert_eq!(body["trigger_type"], trigger_type);
+    }
+}
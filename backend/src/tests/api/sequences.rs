@@ -78,6 +78,9 @@ async fn test_create_and_get_sequence() {
         trigger_config: Some(json!({
             "delay_hours": 0
         })),
+        reentry_policy: None,
+        exit_conditions: None,
+        send_window: None,
     };
 
     // シーケンス作成API呼び出し
@@ -175,6 +178,9 @@ async fn test_update_sequence() {
         description: Some("元の説明".to_string()),
         trigger_type: "form_submission".to_string(),
         trigger_config: Some(json!({})),
+        reentry_policy: None,
+        exit_conditions: None,
+        send_window: None,
     };
 
     let create_result = sequences::create_sequence(
@@ -194,6 +200,9 @@ async fn test_update_sequence() {
         trigger_type: Some("registration".to_string()),
         trigger_config: Some(json!({"delay_hours": 24})),
         status: Some("active".to_string()),
+        reentry_policy: None,
+        exit_conditions: None,
+        send_window: None,
     };
 
     // シーケンス更新API呼び出し
@@ -247,6 +256,9 @@ async fn test_delete_sequence() {
         description: None,
         trigger_type: "manual".to_string(),
         trigger_config: Some(json!({})),
+        reentry_policy: None,
+        exit_conditions: None,
+        send_window: None,
     };
 
     let create_result = sequences::create_sequence(
@@ -315,6 +327,9 @@ async fn test_list_sequences() {
                 "form_submission".to_string()
             },
             trigger_config: Some(json!({})),
+            reentry_policy: None,
+            exit_conditions: None,
+            send_window: None,
         };
 
         let result = sequences::create_sequence(
@@ -380,6 +395,9 @@ async fn test_sequence_with_steps() {
         description: Some("ステップ機能をテストするシーケンス".to_string()),
         trigger_type: "registration".to_string(),
         trigger_config: Some(json!({})),
+        reentry_policy: None,
+        exit_conditions: None,
+        send_window: None,
     };
 
     let create_seq_result = sequences::create_sequence(
@@ -403,6 +421,8 @@ async fn test_sequence_with_steps() {
         subject: Some("ようこそ！".to_string()),
         conditions: Some(json!({})),
         action_config: Some(json!({})),
+        true_step_id: None,
+        false_step_id: None,
     };
 
     let create_step_result = sequences::create_sequence_step(
@@ -483,6 +503,9 @@ async fn test_activate_sequence() {
         description: Some("アクティベーションテスト".to_string()),
         trigger_type: "form_submission".to_string(),
         trigger_config: None,
+        reentry_policy: None,
+        exit_conditions: None,
+        send_window: None,
     };
 
     let result = sequences::create_sequence(
@@ -534,6 +557,9 @@ async fn test_pause_sequence() {
         description: Some("一時停止テスト".to_string()),
         trigger_type: "form_submission".to_string(),
         trigger_config: None,
+        reentry_policy: None,
+        exit_conditions: None,
+        send_window: None,
     };
 
     let result = sequences::create_sequence(
@@ -596,6 +622,9 @@ async fn test_activate_sequence_unauthorized() {
         description: Some("権限テスト".to_string()),
         trigger_type: "form_submission".to_string(),
         trigger_config: None,
+        reentry_policy: None,
+        exit_conditions: None,
+        send_window: None,
     };
 
     let result = sequences::create_sequence(
@@ -620,3 +649,117 @@ async fn test_activate_sequence_unauthorized() {
     let (status, _) = result.unwrap_err();
     assert_eq!(status, StatusCode::FORBIDDEN);
 }
+
+#[tokio::test]
+async fn test_send_window_and_wait_until_are_validated() {
+    let app_state = AppState::new_for_test().await;
+    let pool = app_state.db.clone();
+    let user_id = create_test_user(&pool).await;
+
+    let auth_user = AuthUser {
+        user_id,
+        email: "test@example.com".to_string(),
+        name: "Test User".to_string(),
+    };
+    let create_req = |send_window| CreateSequenceRequest {
+        name: "送信時間帯シーケンス".to_string(),
+        description: None,
+        trigger_type: "manual".to_string(),
+        trigger_config: None,
+        reentry_policy: None,
+        exit_conditions: None,
+        send_window: Some(send_window),
+    };
+
+    // 不正な送信時間帯は作成できない
+    for send_window in [
+        json!({"start_hour": 18, "end_hour": 9}),
+        json!({"days": []}),
+        json!({"timezone": "Japan/Osaka"}),
+    ] {
+        let result = sequences::create_sequence(
+            axum::extract::State(app_state.clone()),
+            Extension(auth_user.clone()),
+            AxumJson(create_req(send_window.clone())),
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err().0,
+            StatusCode::BAD_REQUEST,
+            "{send_window}"
+        );
+    }
+
+    let send_window = json!({
+        "days": ["Mon", "Tue", "Wed", "Thu", "Fri"],
+        "start_hour": 9,
+        "end_hour": 18,
+        "timezone": "Asia/Tokyo"
+    });
+    let (status, Json(sequence)) = sequences::create_sequence(
+        axum::extract::State(app_state.clone()),
+        Extension(auth_user.clone()),
+        AxumJson(create_req(send_window.clone())),
+    )
+    .await
+    .unwrap();
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(sequence.send_window, send_window);
+
+    // 待機する曜日・時刻の形式も検証する
+    let step_req = |action_config| CreateSequenceStepRequest {
+        name: "火曜まで待機".to_string(),
+        step_order: 1,
+        step_type: "wait".to_string(),
+        delay_value: Some(0),
+        delay_unit: Some("days".to_string()),
+        template_id: None,
+        subject: None,
+        conditions: None,
+        action_config: Some(action_config),
+        true_step_id: None,
+        false_step_id: None,
+    };
+    let invalid = sequences::create_sequence_step(
+        axum::extract::State(app_state.clone()),
+        Extension(auth_user.clone()),
+        Path(sequence.id),
+        AxumJson(step_req(
+            json!({"wait_until": {"days": ["Tue"], "time": "25:00"}}),
+        )),
+    )
+    .await;
+    assert_eq!(invalid.unwrap_err().0, StatusCode::BAD_REQUEST);
+
+    let valid = sequences::create_sequence_step(
+        axum::extract::State(app_state.clone()),
+        Extension(auth_user.clone()),
+        Path(sequence.id),
+        AxumJson(step_req(
+            json!({"wait_until": {"days": ["Tue"], "time": "10:00:00"}}),
+        )),
+    )
+    .await;
+    assert_eq!(valid.unwrap().0, StatusCode::CREATED);
+
+    // 空のオブジェクトで送信時間帯の制限をなくせる
+    let update_req = UpdateSequenceRequest {
+        name: None,
+        description: None,
+        trigger_type: None,
+        trigger_config: None,
+        status: None,
+        reentry_policy: None,
+        exit_conditions: None,
+        send_window: Some(json!({})),
+    };
+    let Json(updated) = sequences::update_sequence(
+        axum::extract::State(app_state.clone()),
+        Extension(auth_user.clone()),
+        Path(sequence.id),
+        AxumJson(update_req),
+    )
+    .await
+    .unwrap();
+    assert_eq!(updated.send_window, json!({}));
+}
@@ -0,0 +1,535 @@
+use axum::{
+    body::{self, Body},
+    http::{header, Method, Request, StatusCode},
+    Router,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{Duration, SecondsFormat, Utc};
+use openssl::{
+    asn1::Asn1Time,
+    bn::BigNum,
+    hash::MessageDigest,
+    pkey::{PKey, Private},
+    rsa::Rsa,
+    sign::Signer,
+    x509::{X509NameBuilder, X509},
+};
+use serde_json::Value;
+use sqlx::PgPool;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use crate::{
+    create_app,
+    database::{campaign_deliveries, campaigns, sequences, subscribers},
+    models::{
+        campaign::{CampaignDelivery, CreateCampaignRequest},
+        sequence::{
+            CreateSequenceEnrollmentRequest, CreateSequenceRequest, CreateSequenceStepRequest,
+            StepType,
+        },
+        ses_feedback::SnsEnvelope,
+        subscriber::{CreateSubscriberRequest, Subscriber, SubscriberStatus},
+    },
+    services::ses_feedback_service::SesFeedbackService,
+    tests::api::templates::{create_test_template, create_test_user},
+};
+
+// 記録済みのSNSペイロード内の宛先とメッセージID
+const RECORDED_EMAIL: &str = "recipient@example.jp";
+const RECORDED_MESSAGE_ID: &str = "010601897c2b7a5e-3f1a8c1e-2d4b-4c9e-9a57-2c0f5b7e1d22-000000";
+const RECORDED_TOPIC_ARN: &str = "arn:aws:sns:ap-northeast-1:123456789012:markmail-ses-feedback";
+
+fn fixture(name: &str) -> &'static str {
+    match name {
+        "bounce_permanent" => include_str!("../fixtures/ses/bounce_permanent.json"),
+        "bounce_transient" => include_str!("../fixtures/ses/bounce_transient.json"),
+        "complaint" => include_str!("../fixtures/ses/complaint.json"),
+        "delivery" => include_str!("../fixtures/ses/delivery.json"),
+        "subscription_confirmation" => {
+            include_str!("../fixtures/ses/subscription_confirmation.json")
+        }
+        _ => panic!("unknown fixture: {name}"),
+    }
+}
+
+/// テスト用の署名鍵と自己署名証明書（AWSの代わりに記録済みペイロードへ署名する）
+struct TestSigner {
+    key: PKey<Private>,
+    cert_url: String,
+}
+
+impl TestSigner {
+    fn new() -> Self {
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "sns.amazonaws.com")
+            .unwrap();
+        let name = name.build();
+
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        builder
+            .set_serial_number(&BigNum::from_u32(1).unwrap().to_asn1_integer().unwrap())
+            .unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        let cert = builder.build();
+
+        // 証明書をキャッシュに登録し、AWSへアクセスせずに検証できるようにする
+        let cert_url = format!(
+            "https://sns.ap-northeast-1.amazonaws.com/SimpleNotificationService-{}.pem",
+            Uuid::new_v4().simple()
+        );
+        SesFeedbackService::cache_signing_certificate(&cert_url, &cert.to_pem().unwrap());
+
+        Self { key, cert_url }
+    }
+
+    fn sign(&self, envelope: &mut SnsEnvelope) {
+        envelope.signing_cert_url = self.cert_url.clone();
+        let digest = match envelope.signature_version.as_str() {
+            "2" => MessageDigest::sha256(),
+            _ => MessageDigest::sha1(),
+        };
+        let mut signer = Signer::new(digest, &self.key).unwrap();
+        signer
+            .update(
+                SesFeedbackService::string_to_sign(envelope)
+                    .unwrap()
+                    .as_bytes(),
+            )
+            .unwrap();
+        envelope.signature = STANDARD.encode(signer.sign_to_vec().unwrap());
+    }
+}
+
+/// 記録済みペイロードを読み込み、宛先とメッセージIDを置き換える（送信日時は現在時刻にする）
+fn recorded_envelope_for(name: &str, message_id: &str, email: &str) -> SnsEnvelope {
+    let mut envelope: SnsEnvelope = serde_json::from_str(fixture(name)).unwrap();
+    envelope.message = envelope
+        .message
+        .replace(RECORDED_MESSAGE_ID, message_id)
+        .replace(RECORDED_EMAIL, email);
+    envelope.timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+    envelope
+}
+
+/// 記録済みペイロードの宛先とメッセージIDをテスト用の配信に置き換える
+fn recorded_envelope(name: &str, delivery: &CampaignDelivery) -> SnsEnvelope {
+    recorded_envelope_for(
+        name,
+        delivery.message_id.as_deref().unwrap(),
+        &delivery.email,
+    )
+}
+
+// 送信済みの配信レコードを作成
+async fn setup_sent_delivery(pool: &PgPool) -> (Subscriber, CampaignDelivery) {
+    let user_id = create_test_user(pool).await;
+    let template = create_test_template(pool, user_id).await;
+    let campaign = campaigns::create_campaign(
+        pool,
+        user_id,
+        &CreateCampaignRequest {
+            name: "SES通知テスト".to_string(),
+            description: None,
+            subject: "SES通知テスト".to_string(),
+            template_id: template.id,
+            audience: None,
+            from_email: None,
+            from_name: None,
+        },
+    )
+    .await
+    .expect("Failed to create campaign");
+
+    let subscriber = subscribers::create_subscriber(
+        pool,
+        user_id,
+        &CreateSubscriberRequest {
+            email: format!("ses-{}@example.com", Uuid::new_v4()),
+            name: None,
+            status: None,
+            tags: None,
+            custom_fields: None,
+        },
+    )
+    .await
+    .expect("Failed to create subscriber");
+
+    campaign_deliveries::enqueue_deliveries(pool, campaign.id, std::slice::from_ref(&subscriber))
+        .await
+        .unwrap();
+    let delivery = campaign_deliveries::claim_deliveries(pool, campaign.id, 1, 600)
+        .await
+        .unwrap()
+        .remove(0);
+    let message_id = format!("{}-000000", Uuid::new_v4());
+    campaign_deliveries::mark_delivery_sent(pool, delivery.id, &message_id)
+        .await
+        .unwrap();
+
+    let delivery = campaign_deliveries::find_delivery_by_message_id(pool, &message_id)
+        .await
+        .unwrap()
+        .unwrap();
+    (subscriber, delivery)
+}
+
+async fn post_envelope(app: Router, envelope: &SnsEnvelope) -> (StatusCode, Value) {
+    // 記録済みペイロードのトピックを受け付ける
+    std::env::set_var("SES_SNS_TOPIC_ARNS", RECORDED_TOPIC_ARN);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/ses/webhook")
+                // SNSはtext/plainで送信する
+                .header(header::CONTENT_TYPE, "text/plain; charset=UTF-8")
+                .body(Body::from(serde_json::to_string(envelope).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let body = body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+async fn reload(
+    pool: &PgPool,
+    subscriber: &Subscriber,
+    delivery: &CampaignDelivery,
+) -> (Subscriber, CampaignDelivery) {
+    let subscriber = subscribers::find_subscriber_by_id(pool, subscriber.id, subscriber.user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    let delivery = campaign_deliveries::find_delivery_by_message_id(
+        pool,
+        delivery.message_id.as_deref().unwrap(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    (subscriber, delivery)
+}
+
+#[tokio::test]
+async fn test_permanent_bounce_marks_subscriber_bounced() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (subscriber, delivery) = setup_sent_delivery(&pool).await;
+    let signer = TestSigner::new();
+
+    let mut envelope = recorded_envelope("bounce_permanent", &delivery);
+    signer.sign(&mut envelope);
+    let (status, body) = post_envelope(app, &envelope).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["delivery_id"], delivery.id.to_string());
+    assert_eq!(
+        body["suppressed_subscriber_ids"][0],
+        subscriber.id.to_string()
+    );
+
+    let (subscriber, delivery) = reload(&pool, &subscriber, &delivery).await;
+    assert!(matches!(subscriber.status, SubscriberStatus::Bounced));
+    assert!(delivery.bounced_at.is_some());
+    assert_eq!(delivery.bounce_type.as_deref(), Some("Permanent"));
+}
+
+#[tokio::test]
+async fn test_transient_bounce_keeps_subscriber_active() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (subscriber, delivery) = setup_sent_delivery(&pool).await;
+    let signer = TestSigner::new();
+
+    let mut envelope = recorded_envelope("bounce_transient", &delivery);
+    signer.sign(&mut envelope);
+    let (status, body) = post_envelope(app, &envelope).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["suppressed_subscriber_ids"], serde_json::json!([]));
+
+    let (subscriber, delivery) = reload(&pool, &subscriber, &delivery).await;
+    assert!(matches!(subscriber.status, SubscriberStatus::Active));
+    assert_eq!(delivery.bounce_type.as_deref(), Some("Transient"));
+}
+
+#[tokio::test]
+async fn test_complaint_marks_subscriber_complained() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (subscriber, delivery) = setup_sent_delivery(&pool).await;
+    let signer = TestSigner::new();
+
+    // 配信停止済みの購読者でも苦情は記録する
+    subscribers::unsubscribe_subscriber(&pool, subscriber.id, subscriber.user_id)
+        .await
+        .unwrap();
+
+    let mut envelope = recorded_envelope("complaint", &delivery);
+    envelope.signature_version = "2".to_string();
+    signer.sign(&mut envelope);
+    let (status, _body) = post_envelope(app, &envelope).await;
+
+    assert_eq!(status, StatusCode::OK);
+    let (subscriber, delivery) = reload(&pool, &subscriber, &delivery).await;
+    assert!(matches!(subscriber.status, SubscriberStatus::Complained));
+    assert!(delivery.complained_at.is_some());
+}
+
+#[tokio::test]
+async fn test_delivery_notification_records_delivered_at() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (subscriber, delivery) = setup_sent_delivery(&pool).await;
+    let signer = TestSigner::new();
+
+    let mut envelope = recorded_envelope("delivery", &delivery);
+    signer.sign(&mut envelope);
+    let (status, _body) = post_envelope(app, &envelope).await;
+
+    assert_eq!(status, StatusCode::OK);
+    let (subscriber, delivery) = reload(&pool, &subscriber, &delivery).await;
+    assert!(matches!(subscriber.status, SubscriberStatus::Active));
+    assert!(delivery.delivered_at.is_some());
+}
+
+#[tokio::test]
+async fn test_tampered_notification_is_rejected() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (subscriber, delivery) = setup_sent_delivery(&pool).await;
+    let signer = TestSigner::new();
+
+    // 署名後に本文を書き換える
+    let mut envelope = recorded_envelope("delivery", &delivery);
+    signer.sign(&mut envelope);
+    envelope.message = recorded_envelope("bounce_permanent", &delivery).message;
+    let (status, _body) = post_envelope(app, &envelope).await;
+
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (subscriber, delivery) = reload(&pool, &subscriber, &delivery).await;
+    assert!(matches!(subscriber.status, SubscriberStatus::Active));
+    assert!(delivery.bounced_at.is_none());
+}
+
+#[tokio::test]
+async fn test_recorded_aws_signature_without_certificate_is_rejected() {
+    let (app, _pool, _redis, _config) = create_app().await;
+
+    // 証明書URLがSNS以外のホストの場合は証明書を取得せずに拒否する
+    let mut envelope: SnsEnvelope =
+        serde_json::from_str(fixture("subscription_confirmation")).unwrap();
+    envelope.signing_cert_url = "https://example.com/cert.pem".to_string();
+    let (status, _body) = post_envelope(app, &envelope).await;
+
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_unknown_message_id_is_ignored() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (_subscriber, mut delivery) = setup_sent_delivery(&pool).await;
+    let signer = TestSigner::new();
+
+    delivery.message_id = Some(format!("{}-unknown", Uuid::new_v4()));
+    let mut envelope = recorded_envelope("bounce_permanent", &delivery);
+    signer.sign(&mut envelope);
+    let (status, body) = post_envelope(app, &envelope).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["delivery_id"].is_null());
+}
+
+async fn create_subscriber(pool: &PgPool, user_id: Uuid, email: &str) -> Subscriber {
+    subscribers::create_subscriber(
+        pool,
+        user_id,
+        &CreateSubscriberRequest {
+            email: email.to_string(),
+            name: None,
+            status: None,
+            tags: None,
+            custom_fields: None,
+        },
+    )
+    .await
+    .expect("Failed to create subscriber")
+}
+
+// シーケンスのメールステップを送信済みにする（ステップログにメッセージIDを記録）
+async fn record_sequence_email(pool: &PgPool, subscriber: &Subscriber) -> String {
+    let template = create_test_template(pool, subscriber.user_id).await;
+    let sequence = sequences::create_sequence(
+        pool,
+        subscriber.user_id,
+        CreateSequenceRequest {
+            name: "SES通知テスト".to_string(),
+            description: None,
+            trigger_type: "manual".to_string(),
+            trigger_config: None,
+            reentry_policy: None,
+            exit_conditions: None,
+            send_window: None,
+        },
+    )
+    .await
+    .unwrap();
+    let step = sequences::create_sequence_step(
+        pool,
+        sequence.id,
+        CreateSequenceStepRequest {
+            name: "ステップ1".to_string(),
+            step_order: 1,
+            step_type: StepType::Email.as_str().to_string(),
+            delay_value: None,
+            delay_unit: None,
+            template_id: Some(template.id),
+            subject: None,
+            conditions: None,
+            action_config: None,
+            true_step_id: None,
+            false_step_id: None,
+        },
+    )
+    .await
+    .unwrap();
+    let enrollment = sequences::create_sequence_enrollment(
+        pool,
+        sequence.id,
+        &CreateSequenceEnrollmentRequest {
+            subscriber_id: subscriber.id,
+            trigger_data: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let message_id = format!("{}-000000", Uuid::new_v4());
+    sequences::create_sequence_step_log(
+        pool,
+        enrollment.id,
+        step.id,
+        "sent",
+        None,
+        None,
+        Some(&message_id),
+    )
+    .await
+    .unwrap();
+    message_id
+}
+
+#[tokio::test]
+async fn test_sequence_bounce_suppresses_only_sending_subscriber() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let signer = TestSigner::new();
+
+    // 同じメールアドレスの購読者が別のユーザーにもいる
+    let email = format!("ses-{}@example.com", Uuid::new_v4());
+    let owner_id = create_test_user(&pool).await;
+    let subscriber = create_subscriber(&pool, owner_id, &email).await;
+    let other_user_id = create_test_user(&pool).await;
+    let other = create_subscriber(&pool, other_user_id, &email).await;
+
+    // キャンペーン以外（シーケンス）で送信したメールのバウンス
+    let message_id = record_sequence_email(&pool, &subscriber).await;
+    let mut envelope =
+        recorded_envelope_for("bounce_permanent", &message_id, &email.to_uppercase());
+    signer.sign(&mut envelope);
+    let (status, body) = post_envelope(app, &envelope).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["delivery_id"].is_null());
+    assert_eq!(
+        body["suppressed_subscriber_ids"],
+        serde_json::json!([subscriber.id.to_string()])
+    );
+
+    let subscriber = subscribers::find_subscriber_by_id(&pool, subscriber.id, owner_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(subscriber.status, SubscriberStatus::Bounced));
+    let other = subscribers::find_subscriber_by_id(&pool, other.id, other_user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(other.status, SubscriberStatus::Active));
+}
+
+#[tokio::test]
+async fn test_complaint_without_known_sender_is_dropped() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let signer = TestSigner::new();
+
+    let user_id = create_test_user(&pool).await;
+    let email = format!("ses-{}@example.com", Uuid::new_v4());
+    let subscriber = create_subscriber(&pool, user_id, &email).await;
+
+    // 送信記録のないメッセージIDの苦情は、同じアドレスの購読者がいても反映しない
+    let mut envelope =
+        recorded_envelope_for("complaint", &format!("{}-unknown", Uuid::new_v4()), &email);
+    signer.sign(&mut envelope);
+    let (status, body) = post_envelope(app, &envelope).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["suppressed_subscriber_ids"], serde_json::json!([]));
+
+    let subscriber = subscribers::find_subscriber_by_id(&pool, subscriber.id, user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(subscriber.status, SubscriberStatus::Active));
+}
+
+#[tokio::test]
+async fn test_replayed_notification_is_rejected() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (subscriber, delivery) = setup_sent_delivery(&pool).await;
+    let signer = TestSigner::new();
+
+    // 署名は正しいが、送信日時が古いメッセージ
+    let mut envelope = recorded_envelope("bounce_permanent", &delivery);
+    envelope.timestamp =
+        (Utc::now() - Duration::days(1)).to_rfc3339_opts(SecondsFormat::Millis, true);
+    signer.sign(&mut envelope);
+    let (status, _body) = post_envelope(app, &envelope).await;
+
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (subscriber, delivery) = reload(&pool, &subscriber, &delivery).await;
+    assert!(matches!(subscriber.status, SubscriberStatus::Active));
+    assert!(delivery.bounced_at.is_none());
+}
+
+#[tokio::test]
+async fn test_malformed_body_is_rejected() {
+    let (app, _pool, _redis, _config) = create_app().await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/ses/webhook")
+                .header(header::CONTENT_TYPE, "text/plain; charset=UTF-8")
+                .body(Body::from("not json"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,157 @@
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use crate::{
+    create_app,
+    database::template_revisions,
+    tests::api::{
+        segments::send,
+        templates::{create_test_template, get_test_user_with_jwt},
+    },
+};
+
+#[tokio::test]
+async fn test_template_revisions_diff_and_rollback() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (_user_id, token) = get_test_user_with_jwt(&pool).await;
+
+    let (status, template) = send(
+        &app,
+        Method::POST,
+        "/api/templates",
+        &token,
+        Some(json!({
+            "name": "お知らせ",
+            "subject_template": "{{name}}様へのお知らせ",
+            "markdown_content": "# お知らせ\n\n本文です。\n"
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(template["current_revision"], 1);
+    let base = format!("/api/templates/{}", template["id"].as_str().unwrap());
+
+    // 本文を変更すると新しいリビジョンになる
+    let (status, updated) = send(
+        &app,
+        Method::PUT,
+        &base,
+        &token,
+        Some(json!({"markdown_content": "# お知らせ\n\n新しい本文です。\n"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["current_revision"], 2);
+
+    // 名前だけの変更ではリビジョンを増やさない
+    let (status, renamed) = send(
+        &app,
+        Method::PUT,
+        &base,
+        &token,
+        Some(json!({"name": "重要なお知らせ"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(renamed["current_revision"], 2);
+
+    let (status, body) = send(
+        &app,
+        Method::GET,
+        &format!("{base}/revisions"),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["total"], 2);
+    assert_eq!(body["revisions"][0]["revision"], 2);
+    assert_eq!(
+        body["revisions"][1]["markdown_content"],
+        "# お知らせ\n\n本文です。\n"
+    );
+
+    let (status, diff) = send(&app, Method::GET, &format!("{base}/diff"), &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(diff["from_revision"], 1);
+    assert_eq!(diff["to_revision"], 2);
+    assert_eq!(diff["subject_changed"], false);
+    assert!(diff["markdown_diff"]
+        .as_str()
+        .unwrap()
+        .contains("+新しい本文です。"));
+
+    // ロールバックは過去のリビジョンを書き換えず、復元した内容を新しいリビジョンにする
+    let (status, rolled_back) = send(
+        &app,
+        Method::POST,
+        &format!("{base}/revisions/1/rollback"),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(rolled_back["current_revision"], 3);
+    assert_eq!(
+        rolled_back["markdown_content"],
+        "# お知らせ\n\n本文です。\n"
+    );
+    assert_eq!(rolled_back["name"], "重要なお知らせ");
+
+    let (status, revision) = send(
+        &app,
+        Method::GET,
+        &format!("{base}/revisions/3"),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(revision["note"], "リビジョン1から復元");
+
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        &format!("{base}/revisions/9/rollback"),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // 他のユーザーには履歴を見せない
+    let (_, other_token) = get_test_user_with_jwt(&pool).await;
+    let (status, _) = send(
+        &app,
+        Method::GET,
+        &format!("{base}/revisions"),
+        &other_token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_current_revision_records_unversioned_template() {
+    let (_app, pool, _redis, _config) = create_app().await;
+    let (user_id, _token) = get_test_user_with_jwt(&pool).await;
+
+    // リビジョンのないテンプレートは送信時に最初のリビジョンを記録する
+    let template = create_test_template(&pool, user_id).await;
+    assert_eq!(template.current_revision, 0);
+
+    let revision = template_revisions::current_revision(&pool, template.id, user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(revision.revision, 1);
+    assert_eq!(revision.markdown_content, template.markdown_content);
+
+    // 内容が変わらなければ同じリビジョンを返す
+    let again = template_revisions::current_revision(&pool, template.id, user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(again.id, revision.id);
+}
@@ -74,7 +74,7 @@ pub async fn create_test_template(pool: &PgPool, user_id: Uuid) -> Template {
         r#"
         SELECT 
             id, user_id, name, subject_template, markdown_content, html_content as "html_content?",
-            variables, is_public as "is_public!", created_at as "created_at!", updated_at as "updated_at!"
+            variables, is_public as "is_public!", current_revision, created_at as "created_at!", updated_at as "updated_at!"
         FROM templates
         WHERE id = $1
         "#,
@@ -0,0 +1,235 @@
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use crate::{
+    create_app,
+    database::{campaigns, subscribers, tracking},
+    models::{
+        campaign::{Campaign, CreateCampaignRequest},
+        subscriber::{CreateSubscriberRequest, Subscriber},
+    },
+    services::tracking_service::TrackingService,
+    tests::api::templates::{create_test_template, get_test_user_with_jwt},
+};
+
+// テスト用のキャンペーンと購読者を作成
+async fn setup_campaign_and_subscriber(pool: &sqlx::PgPool) -> (Campaign, Subscriber) {
+    let (user_id, _token) = get_test_user_with_jwt(pool).await;
+    let template = create_test_template(pool, user_id).await;
+
+    let campaign = campaigns::create_campaign(
+        pool,
+        user_id,
+        &CreateCampaignRequest {
+            name: "計測テストキャンペーン".to_string(),
+            description: None,
+            subject: "計測テスト".to_string(),
+            template_id: template.id,
+            audience: None,
+            from_email: None,
+            from_name: None,
+        },
+    )
+    .await
+    .expect("Failed to create campaign");
+
+    let subscriber = subscribers::create_subscriber(
+        pool,
+        user_id,
+        &CreateSubscriberRequest {
+            email: format!("tracking-{}@example.com", Uuid::new_v4()),
+            name: Some("計測テスト".to_string()),
+            status: None,
+            tags: None,
+            custom_fields: None,
+        },
+    )
+    .await
+    .expect("Failed to create subscriber");
+
+    (campaign, subscriber)
+}
+
+fn tracking_path(url: &str) -> String {
+    url.split_once("/t/")
+        .map(|(_, path)| format!("/t/{path}"))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_track_open_counts_unique_subscribers() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (campaign, subscriber) = setup_campaign_and_subscriber(&pool).await;
+
+    let open_url = TrackingService::new()
+        .open_url(campaign.id, subscriber.id)
+        .unwrap();
+
+    // 同じ購読者が2回開封しても開封数は1
+    for _ in 0..2 {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::GET)
+                    .uri(tracking_path(&open_url))
+                    .header("X-Forwarded-For", "198.51.100.9, 203.0.113.1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "image/gif"
+        );
+    }
+
+    let events = tracking::list_campaign_tracking_events(&pool, campaign.id)
+        .await
+        .unwrap();
+    assert_eq!(events.len(), 2);
+    // クライアントが送った先頭の値ではなく、ALBが末尾に追加した接続元を記録する
+    assert_eq!(events[0].ip_address.as_deref(), Some("203.0.113.1"));
+
+    let updated = campaigns::find_campaign_by_id(&pool, campaign.id, campaign.user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(updated.opened_count, 1);
+    assert_eq!(updated.clicked_count, 0);
+}
+
+#[tokio::test]
+async fn test_track_click_redirects_and_counts() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (campaign, subscriber) = setup_campaign_and_subscriber(&pool).await;
+
+    let target = "https://example.com/landing?utm_source=markmail&id=1";
+    let click_url = TrackingService::new()
+        .click_url(campaign.id, subscriber.id, target)
+        .unwrap();
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri(tracking_path(&click_url))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(response.headers().get(header::LOCATION).unwrap(), target);
+
+    // クリックは開封としても計上される
+    let updated = campaigns::find_campaign_by_id(&pool, campaign.id, campaign.user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(updated.clicked_count, 1);
+    assert_eq!(updated.opened_count, 1);
+
+    // 同じ購読者が別のリンクをクリックしてもユニーク数は変わらない
+    let other_url = TrackingService::new()
+        .click_url(campaign.id, subscriber.id, "https://example.com/other")
+        .unwrap();
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri(tracking_path(&other_url))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FOUND);
+
+    let updated = campaigns::find_campaign_by_id(&pool, campaign.id, campaign.user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(updated.clicked_count, 1);
+    assert_eq!(updated.opened_count, 1);
+}
+
+#[tokio::test]
+async fn test_concurrent_first_engagements_count_once() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let (campaign, subscriber) = setup_campaign_and_subscriber(&pool).await;
+
+    let tracking_service = TrackingService::new();
+    let open_url = tracking_service
+        .open_url(campaign.id, subscriber.id)
+        .unwrap();
+    let click_url = tracking_service
+        .click_url(campaign.id, subscriber.id, "https://example.com/landing")
+        .unwrap();
+
+    // 画像プロキシやリンクのスキャナーが開封とクリックを同時に送ってもユニーク数は1
+    let requests = (0..10).map(|i| {
+        let path = tracking_path(if i % 2 == 0 { &open_url } else { &click_url });
+        app.clone().oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri(path)
+                .body(Body::empty())
+                .unwrap(),
+        )
+    });
+    for response in futures::future::join_all(requests).await {
+        let status = response.unwrap().status();
+        assert!(
+            matches!(status, StatusCode::OK | StatusCode::FOUND),
+            "{status}"
+        );
+    }
+
+    let updated = campaigns::find_campaign_by_id(&pool, campaign.id, campaign.user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(updated.opened_count, 1);
+    assert_eq!(updated.clicked_count, 1);
+}
+
+#[tokio::test]
+async fn test_track_invalid_token() {
+    let (app, _pool, _redis, _config) = create_app().await;
+
+    // 開封ピクセルは無効なトークンでも画像を返す
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri("/t/o/invalid-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri("/t/c/invalid-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
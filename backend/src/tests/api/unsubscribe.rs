@@ -0,0 +1,154 @@
+use axum::{
+    body::{self, Body},
+    http::{header, Method, Request, StatusCode},
+};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use crate::{
+    create_app,
+    database::subscribers,
+    models::subscriber::{CreateSubscriberRequest, Subscriber, SubscriberStatus},
+    services::unsubscribe_service::UnsubscribeService,
+    tests::api::templates::get_test_user_with_jwt,
+};
+
+async fn create_test_subscriber(pool: &sqlx::PgPool) -> Subscriber {
+    let (user_id, _token) = get_test_user_with_jwt(pool).await;
+
+    subscribers::create_subscriber(
+        pool,
+        user_id,
+        &CreateSubscriberRequest {
+            email: format!("unsubscribe-{}@example.com", Uuid::new_v4()),
+            name: Some("配信停止テスト".to_string()),
+            status: None,
+            tags: None,
+            custom_fields: None,
+        },
+    )
+    .await
+    .expect("Failed to create subscriber")
+}
+
+fn unsubscribe_path(subscriber: &Subscriber) -> String {
+    let url = UnsubscribeService::with_base_url("http://localhost:3000")
+        .unsubscribe_url(subscriber, Some(Uuid::new_v4()))
+        .unwrap();
+    url.trim_start_matches("http://localhost:3000").to_string()
+}
+
+#[tokio::test]
+async fn test_unsubscribe_page_shows_confirmation() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let subscriber = create_test_subscriber(&pool).await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri(unsubscribe_path(&subscriber))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let html = String::from_utf8(body.to_vec()).unwrap();
+    assert!(html.contains(&subscriber.email));
+    assert!(html.contains("<form method=\"post\">"));
+
+    // 確認ページの表示だけでは配信停止にならない
+    let unchanged = subscribers::find_subscriber_by_id(&pool, subscriber.id, subscriber.user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(unchanged.status, SubscriberStatus::Active));
+}
+
+#[tokio::test]
+async fn test_one_click_unsubscribe() {
+    let (app, pool, _redis, _config) = create_app().await;
+    let subscriber = create_test_subscriber(&pool).await;
+
+    // RFC 8058 のワンクリック配信停止リクエスト
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(unsubscribe_path(&subscriber))
+                .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(Body::from("List-Unsubscribe=One-Click"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let updated = subscribers::find_subscriber_by_id(&pool, subscriber.id, subscriber.user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(updated.status, SubscriberStatus::Unsubscribed));
+    assert!(updated.unsubscribed_at.is_some());
+}
+
+#[tokio::test]
+async fn test_unsubscribe_keeps_bounced_and_complained_status() {
+    let (app, pool, _redis, _config) = create_app().await;
+
+    for status in [SubscriberStatus::Bounced, SubscriberStatus::Complained] {
+        let subscriber = create_test_subscriber(&pool).await;
+        sqlx::query("UPDATE subscribers SET status = $2 WHERE id = $1")
+            .bind(subscriber.id)
+            .bind(status)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // 古いメールの配信停止リンクでもバウンス・苦情の理由は上書きしない
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri(unsubscribe_path(&subscriber))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let unchanged =
+            subscribers::find_subscriber_by_id(&pool, subscriber.id, subscriber.user_id)
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!(unchanged.status, status);
+        assert!(unchanged.unsubscribed_at.is_none());
+    }
+}
+
+#[tokio::test]
+async fn test_unsubscribe_with_invalid_token() {
+    let (app, _pool, _redis, _config) = create_app().await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/unsubscribe/invalid-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
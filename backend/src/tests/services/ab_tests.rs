@@ -0,0 +1,194 @@
+use uuid::Uuid;
+
+use crate::{
+    database::{ab_tests, campaign_deliveries, campaigns, subscribers, tracking},
+    models::{
+        ab_test::{
+            AbTestStatus, ConfigureAbTestRequest, CreateCampaignVariantRequest, WinnerMetric,
+        },
+        campaign::CreateCampaignRequest,
+        subscriber::CreateSubscriberRequest,
+        tracking::TrackingEventType,
+    },
+    services::ab_test_service::{select_winner, AbTestService},
+    tests::api::templates::{create_test_template, create_test_user},
+    AppState,
+};
+
+fn configure_request(template_id: Option<Uuid>) -> ConfigureAbTestRequest {
+    ConfigureAbTestRequest {
+        test_percentage: 50,
+        winner_metric: WinnerMetric::Opens,
+        wait_minutes: 60,
+        variants: vec![
+            CreateCampaignVariantRequest {
+                name: "A".to_string(),
+                subject: "件名A".to_string(),
+                template_id,
+                split_percentage: 50,
+            },
+            CreateCampaignVariantRequest {
+                name: "B".to_string(),
+                subject: "件名B".to_string(),
+                template_id: None,
+                split_percentage: 50,
+            },
+        ],
+    }
+}
+
+#[tokio::test]
+async fn test_ab_test_winner_is_sent_to_remaining_audience() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let user_id = create_test_user(&pool).await;
+    let template = create_test_template(&pool, user_id).await;
+
+    let campaign = campaigns::create_campaign(
+        &pool,
+        user_id,
+        &CreateCampaignRequest {
+            name: "A/Bテスト".to_string(),
+            description: None,
+            subject: "A/Bテスト".to_string(),
+            template_id: template.id,
+            audience: None,
+            from_email: None,
+            from_name: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let mut audience = Vec::new();
+    for i in 0..4 {
+        let subscriber = subscribers::create_subscriber(
+            &pool,
+            user_id,
+            &CreateSubscriberRequest {
+                email: format!("ab-{i}-{}@example.com", Uuid::new_v4()),
+                name: None,
+                status: None,
+                tags: None,
+                custom_fields: None,
+            },
+        )
+        .await
+        .unwrap();
+        audience.push(subscriber);
+    }
+
+    let service = AbTestService::new();
+
+    // 他のユーザーのテンプレートはバリアントに使えない
+    let other_template = create_test_template(&pool, create_test_user(&pool).await).await;
+    assert!(service
+        .configure_ab_test(
+            &pool,
+            &campaign,
+            &configure_request(Some(other_template.id))
+        )
+        .await
+        .is_err());
+
+    let configured = service
+        .configure_ab_test(&pool, &campaign, &configure_request(Some(template.id)))
+        .await
+        .unwrap();
+    assert_eq!(configured.ab_test.status(), AbTestStatus::Draft);
+    let (variant_a, variant_b) = (&configured.variants[0], &configured.variants[1]);
+
+    // テスト対象としてA・Bに1人ずつ送信し、Bの購読者のみ開封した
+    for (variant_id, subscriber) in [(variant_a.id, &audience[0]), (variant_b.id, &audience[1])] {
+        campaign_deliveries::enqueue_variant_deliveries(
+            &pool,
+            campaign.id,
+            variant_id,
+            std::slice::from_ref(subscriber),
+        )
+        .await
+        .unwrap();
+    }
+    ab_tests::start_ab_test(&pool, campaign.id).await.unwrap();
+    for delivery in campaign_deliveries::claim_deliveries(&pool, campaign.id, 10, 300)
+        .await
+        .unwrap()
+    {
+        assert!(delivery.variant_id.is_some());
+        campaign_deliveries::mark_delivery_sent(&pool, delivery.id, "message-id")
+            .await
+            .unwrap();
+    }
+    tracking::record_tracking_event(
+        &pool,
+        campaign.id,
+        audience[1].id,
+        TrackingEventType::Open,
+        None,
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+
+    // 勝者の判定時刻までは配信ワーカーも送信処理を再開しない
+    assert!(!campaign_deliveries::needs_resume(&pool, campaign.id, 300)
+        .await
+        .unwrap());
+    sqlx::query(
+        "UPDATE campaign_ab_tests SET test_started_at = NOW() - INTERVAL '2 hours' WHERE campaign_id = $1",
+    )
+    .bind(campaign.id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    assert!(campaign_deliveries::needs_resume(&pool, campaign.id, 300)
+        .await
+        .unwrap());
+
+    // テスト送信開始後は設定を変更できない
+    assert!(service
+        .configure_ab_test(&pool, &campaign, &configure_request(None))
+        .await
+        .is_err());
+    assert!(service.delete_ab_test(&pool, &campaign).await.is_err());
+
+    let stats = ab_tests::variant_stats(&pool, campaign.id).await.unwrap();
+    assert_eq!(
+        stats.iter().map(|s| (s.sent, s.opened)).collect::<Vec<_>>(),
+        vec![(1, 0), (1, 1)]
+    );
+    let winner_id = select_winner(&stats, WinnerMetric::Opens).unwrap();
+    assert_eq!(winner_id, variant_b.id);
+
+    // 勝者の送信はテスト送信済みの購読者を除いた残りのみ
+    ab_tests::complete_ab_test(&pool, campaign.id, winner_id)
+        .await
+        .unwrap();
+    let enqueued =
+        campaign_deliveries::enqueue_variant_deliveries(&pool, campaign.id, winner_id, &audience)
+            .await
+            .unwrap();
+    assert_eq!(enqueued, 2);
+
+    // 勝者の送信分はテスト結果に含めない
+    for delivery in campaign_deliveries::claim_deliveries(&pool, campaign.id, 10, 300)
+        .await
+        .unwrap()
+    {
+        campaign_deliveries::mark_delivery_sent(&pool, delivery.id, "message-id")
+            .await
+            .unwrap();
+    }
+    let result = service
+        .get_ab_test(&pool, campaign.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(result.ab_test.status(), AbTestStatus::Completed);
+    assert_eq!(result.ab_test.winner_variant_id, Some(variant_b.id));
+    assert_eq!(
+        result.results.iter().map(|s| s.sent).collect::<Vec<_>>(),
+        vec![1, 1]
+    );
+}
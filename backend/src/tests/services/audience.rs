@@ -0,0 +1,273 @@
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    database::subscribers,
+    models::{
+        audience::{AudienceDefinition, ConditionMatch, ConditionOperator, CustomFieldCondition},
+        subscriber::{CreateSubscriberRequest, Subscriber, SubscriberStatus},
+    },
+    tests::api::templates::create_test_user,
+    AppState,
+};
+
+async fn create_subscriber(
+    pool: &PgPool,
+    user_id: Uuid,
+    label: &str,
+    status: SubscriberStatus,
+    tags: &[&str],
+    custom_fields: serde_json::Value,
+) -> Subscriber {
+    subscribers::create_subscriber(
+        pool,
+        user_id,
+        &CreateSubscriberRequest {
+            email: format!("{label}-{}@example.com", Uuid::new_v4()),
+            name: Some(label.to_string()),
+            status: Some(status),
+            tags: Some(tags.iter().map(|t| t.to_string()).collect()),
+            custom_fields: Some(custom_fields),
+        },
+    )
+    .await
+    .expect("Failed to create subscriber")
+}
+
+// テスト用の購読者を作成
+async fn setup_subscribers(pool: &PgPool) -> Uuid {
+    let user_id = create_test_user(pool).await;
+
+    create_subscriber(
+        pool,
+        user_id,
+        "alice",
+        SubscriberStatus::Active,
+        &["customer", "vip"],
+        json!({"plan": "pro", "age": 34, "company": "Example株式会社", "signup": "2025-03-01"}),
+    )
+    .await;
+    create_subscriber(
+        pool,
+        user_id,
+        "bob",
+        SubscriberStatus::Active,
+        &["customer"],
+        json!({"plan": "free", "age": 19, "signup": "2025-07-15"}),
+    )
+    .await;
+    create_subscriber(
+        pool,
+        user_id,
+        "carol",
+        SubscriberStatus::Active,
+        &["lead", "test"],
+        json!({"plan": "pro", "age": "unknown"}),
+    )
+    .await;
+    create_subscriber(
+        pool,
+        user_id,
+        "unsubscribed",
+        SubscriberStatus::Unsubscribed,
+        &["customer", "vip"],
+        json!({"plan": "pro"}),
+    )
+    .await;
+    create_subscriber(
+        pool,
+        user_id,
+        "bounced",
+        SubscriberStatus::Bounced,
+        &["customer", "vip"],
+        json!({"plan": "pro"}),
+    )
+    .await;
+
+    user_id
+}
+
+async fn names(pool: &PgPool, user_id: Uuid, audience: &AudienceDefinition) -> Vec<String> {
+    let subscribers = subscribers::list_audience_subscribers(pool, user_id, audience, None, None)
+        .await
+        .unwrap();
+    let count = subscribers::count_audience_subscribers(pool, user_id, audience)
+        .await
+        .unwrap();
+    assert_eq!(count, subscribers.len() as i64);
+
+    let mut names: Vec<String> = subscribers.into_iter().filter_map(|s| s.name).collect();
+    names.sort();
+    names
+}
+
+fn condition(
+    field: &str,
+    operator: ConditionOperator,
+    value: serde_json::Value,
+) -> CustomFieldCondition {
+    CustomFieldCondition {
+        field: field.to_string(),
+        operator,
+        value,
+    }
+}
+
+#[tokio::test]
+async fn test_default_audience_excludes_unsendable_subscribers() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let user_id = setup_subscribers(&pool).await;
+
+    assert_eq!(
+        names(&pool, user_id, &AudienceDefinition::default()).await,
+        vec!["alice", "bob", "carol"]
+    );
+
+    // ステータスを明示しても送信できないステータスは含まれない
+    let audience = AudienceDefinition {
+        statuses: vec![SubscriberStatus::Unsubscribed, SubscriberStatus::Bounced],
+        ..Default::default()
+    };
+    assert!(names(&pool, user_id, &audience).await.is_empty());
+}
+
+#[tokio::test]
+async fn test_audience_tag_filters() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let user_id = setup_subscribers(&pool).await;
+
+    let audience = AudienceDefinition {
+        include_tags: vec!["customer".to_string(), "lead".to_string()],
+        exclude_tags: vec!["test".to_string()],
+        ..Default::default()
+    };
+    assert_eq!(names(&pool, user_id, &audience).await, vec!["alice", "bob"]);
+
+    let audience = AudienceDefinition {
+        include_tags: vec!["vip".to_string()],
+        ..Default::default()
+    };
+    assert_eq!(names(&pool, user_id, &audience).await, vec!["alice"]);
+}
+
+#[tokio::test]
+async fn test_audience_custom_field_conditions() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let user_id = setup_subscribers(&pool).await;
+
+    let cases = vec![
+        (
+            condition("plan", ConditionOperator::Equals, json!("pro")),
+            vec!["alice", "carol"],
+        ),
+        (
+            condition("plan", ConditionOperator::NotEquals, json!("pro")),
+            vec!["bob"],
+        ),
+        (
+            condition("company", ConditionOperator::Contains, json!("example")),
+            vec!["alice"],
+        ),
+        (
+            condition("company", ConditionOperator::NotContains, json!("example")),
+            vec!["bob", "carol"],
+        ),
+        // 数値でない値は大小比較の対象にならない
+        (
+            condition("age", ConditionOperator::GreaterThanOrEqual, json!(20)),
+            vec!["alice"],
+        ),
+        (
+            condition("age", ConditionOperator::LessThan, json!(20)),
+            vec!["bob"],
+        ),
+        (
+            condition(
+                "signup",
+                ConditionOperator::GreaterThan,
+                json!("2025-06-01"),
+            ),
+            vec!["bob"],
+        ),
+        (
+            condition("plan", ConditionOperator::In, json!(["free", "enterprise"])),
+            vec!["bob"],
+        ),
+        (
+            condition("plan", ConditionOperator::NotIn, json!(["free"])),
+            vec!["alice", "carol"],
+        ),
+        (
+            condition("company", ConditionOperator::Exists, json!(null)),
+            vec!["alice"],
+        ),
+        (
+            condition("company", ConditionOperator::NotExists, json!(null)),
+            vec!["bob", "carol"],
+        ),
+    ];
+
+    for (condition, expected) in cases {
+        let audience = AudienceDefinition {
+            conditions: vec![condition.clone()],
+            ..Default::default()
+        };
+        assert_eq!(
+            names(&pool, user_id, &audience).await,
+            expected,
+            "{condition:?}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_audience_condition_match() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let user_id = setup_subscribers(&pool).await;
+
+    let conditions = vec![
+        condition("plan", ConditionOperator::Equals, json!("pro")),
+        condition("age", ConditionOperator::LessThan, json!(20)),
+    ];
+
+    let audience = AudienceDefinition {
+        conditions: conditions.clone(),
+        condition_match: ConditionMatch::All,
+        ..Default::default()
+    };
+    assert!(names(&pool, user_id, &audience).await.is_empty());
+
+    let audience = AudienceDefinition {
+        include_tags: vec!["customer".to_string()],
+        conditions,
+        condition_match: ConditionMatch::Any,
+        ..Default::default()
+    };
+    assert_eq!(names(&pool, user_id, &audience).await, vec!["alice", "bob"]);
+}
+
+#[tokio::test]
+async fn test_audience_is_scoped_to_user() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let user_id = setup_subscribers(&pool).await;
+    let other_user_id = setup_subscribers(&pool).await;
+
+    let subscribers = subscribers::list_audience_subscribers(
+        &pool,
+        user_id,
+        &AudienceDefinition::default(),
+        Some(2),
+        Some(1),
+    )
+    .await
+    .unwrap();
+    assert_eq!(subscribers.len(), 2);
+    assert!(subscribers.iter().all(|s| s.user_id == user_id));
+    assert_ne!(user_id, other_user_id);
+}
@@ -0,0 +1,282 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    database::{
+        campaign_deliveries::{self, CampaignLock},
+        campaigns, subscribers,
+    },
+    models::{
+        campaign::{Campaign, CampaignStatus, CreateCampaignRequest, DeliveryStatus},
+        subscriber::{CreateSubscriberRequest, Subscriber},
+    },
+    services::campaign_service::CampaignService,
+    tests::api::templates::{create_test_template, create_test_user},
+    AppState,
+};
+
+// テスト用のキャンペーンと購読者を作成
+async fn setup_campaign(pool: &PgPool, subscriber_count: usize) -> (Campaign, Vec<Subscriber>) {
+    let user_id = create_test_user(pool).await;
+    let template = create_test_template(pool, user_id).await;
+
+    let campaign = campaigns::create_campaign(
+        pool,
+        user_id,
+        &CreateCampaignRequest {
+            name: "配信キューテスト".to_string(),
+            description: None,
+            subject: "配信キューテスト".to_string(),
+            template_id: template.id,
+            audience: None,
+            from_email: None,
+            from_name: None,
+        },
+    )
+    .await
+    .expect("Failed to create campaign");
+
+    let mut created = Vec::new();
+    for i in 0..subscriber_count {
+        let subscriber = subscribers::create_subscriber(
+            pool,
+            user_id,
+            &CreateSubscriberRequest {
+                email: format!("delivery-{i}-{}@example.com", Uuid::new_v4()),
+                name: None,
+                status: None,
+                tags: None,
+                custom_fields: None,
+            },
+        )
+        .await
+        .expect("Failed to create subscriber");
+        created.push(subscriber);
+    }
+
+    (campaign, created)
+}
+
+#[tokio::test]
+async fn test_enqueue_deliveries_is_idempotent() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let (campaign, subscribers) = setup_campaign(&pool, 3).await;
+
+    let inserted = campaign_deliveries::enqueue_deliveries(&pool, campaign.id, &subscribers)
+        .await
+        .unwrap();
+    assert_eq!(inserted, 3);
+
+    // 同じ購読者を再登録しても重複しない
+    let inserted = campaign_deliveries::enqueue_deliveries(&pool, campaign.id, &subscribers)
+        .await
+        .unwrap();
+    assert_eq!(inserted, 0);
+
+    let counts = campaign_deliveries::count_deliveries(&pool, campaign.id)
+        .await
+        .unwrap();
+    assert_eq!(counts.total, 3);
+    assert_eq!(counts.pending, 3);
+}
+
+#[tokio::test]
+async fn test_claim_deliveries_is_exclusive() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let (campaign, subscribers) = setup_campaign(&pool, 4).await;
+    campaign_deliveries::enqueue_deliveries(&pool, campaign.id, &subscribers)
+        .await
+        .unwrap();
+
+    // 同時に確保しても同じ配信が二重に確保されない
+    let (first, second) = tokio::join!(
+        campaign_deliveries::claim_deliveries(&pool, campaign.id, 3, 600),
+        campaign_deliveries::claim_deliveries(&pool, campaign.id, 3, 600),
+    );
+    let mut claimed: Vec<Uuid> = first
+        .unwrap()
+        .into_iter()
+        .chain(second.unwrap())
+        .map(|d| d.id)
+        .collect();
+    assert_eq!(claimed.len(), 4);
+    claimed.sort();
+    claimed.dedup();
+    assert_eq!(claimed.len(), 4);
+
+    // 確保済みの配信は再度確保されない
+    let again = campaign_deliveries::claim_deliveries(&pool, campaign.id, 10, 600)
+        .await
+        .unwrap();
+    assert!(again.is_empty());
+}
+
+#[tokio::test]
+async fn test_stale_deliveries_are_reclaimed() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let (campaign, subscribers) = setup_campaign(&pool, 1).await;
+    campaign_deliveries::enqueue_deliveries(&pool, campaign.id, &subscribers)
+        .await
+        .unwrap();
+
+    let claimed = campaign_deliveries::claim_deliveries(&pool, campaign.id, 10, 600)
+        .await
+        .unwrap();
+    assert_eq!(claimed.len(), 1);
+    assert!(!campaign_deliveries::needs_resume(&pool, campaign.id, 600)
+        .await
+        .unwrap());
+
+    // 送信中にプロセスが停止した状態を再現
+    sqlx::query(
+        "UPDATE campaign_deliveries SET locked_at = NOW() - INTERVAL '1 hour' WHERE id = $1",
+    )
+    .bind(claimed[0].id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    assert!(campaign_deliveries::needs_resume(&pool, campaign.id, 600)
+        .await
+        .unwrap());
+
+    let reclaimed = campaign_deliveries::claim_deliveries(&pool, campaign.id, 10, 600)
+        .await
+        .unwrap();
+    assert_eq!(reclaimed.len(), 1);
+    assert_eq!(reclaimed[0].id, claimed[0].id);
+    assert_eq!(reclaimed[0].attempts, 2);
+}
+
+#[tokio::test]
+async fn test_retry_waits_for_backoff() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let (campaign, subscribers) = setup_campaign(&pool, 1).await;
+    campaign_deliveries::enqueue_deliveries(&pool, campaign.id, &subscribers)
+        .await
+        .unwrap();
+
+    let claimed = campaign_deliveries::claim_deliveries(&pool, campaign.id, 10, 600)
+        .await
+        .unwrap();
+    campaign_deliveries::schedule_delivery_retry(
+        &pool,
+        claimed[0].id,
+        "送信エラー",
+        chrono::Utc::now() + chrono::Duration::minutes(5),
+    )
+    .await
+    .unwrap();
+
+    // 再送予定時刻までは確保されない
+    let claimed = campaign_deliveries::claim_deliveries(&pool, campaign.id, 10, 600)
+        .await
+        .unwrap();
+    assert!(claimed.is_empty());
+
+    let counts = campaign_deliveries::count_deliveries(&pool, campaign.id)
+        .await
+        .unwrap();
+    assert_eq!(counts.pending, 1);
+    assert!(counts.has_unfinished());
+
+    // 再送待ちの間は配信ワーカーも送信処理を再開しない
+    assert!(!campaign_deliveries::needs_resume(&pool, campaign.id, 600)
+        .await
+        .unwrap());
+}
+
+#[tokio::test]
+async fn test_retry_failed_deliveries_targets_failed_rows_only() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let (campaign, subscribers) = setup_campaign(&pool, 2).await;
+    campaign_deliveries::enqueue_deliveries(&pool, campaign.id, &subscribers)
+        .await
+        .unwrap();
+
+    let claimed = campaign_deliveries::claim_deliveries(&pool, campaign.id, 10, 600)
+        .await
+        .unwrap();
+    campaign_deliveries::mark_delivery_sent(&pool, claimed[0].id, "message-1")
+        .await
+        .unwrap();
+    campaign_deliveries::finish_delivery(
+        &pool,
+        claimed[1].id,
+        DeliveryStatus::Failed,
+        "送信エラー",
+    )
+    .await
+    .unwrap();
+    campaigns::complete_campaign_sending(&pool, campaign.id)
+        .await
+        .unwrap();
+
+    let retried = CampaignService::new()
+        .retry_failed_deliveries(&pool, campaign.id, campaign.user_id)
+        .await
+        .unwrap();
+    assert_eq!(retried, 1);
+
+    let pending =
+        campaign_deliveries::list_deliveries(&pool, campaign.id, Some(DeliveryStatus::Pending))
+            .await
+            .unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].id, claimed[1].id);
+    assert_eq!(pending[0].attempts, 0);
+
+    let sent = campaign_deliveries::list_deliveries(&pool, campaign.id, Some(DeliveryStatus::Sent))
+        .await
+        .unwrap();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].message_id.as_deref(), Some("message-1"));
+
+    let updated = campaigns::find_campaign_by_id(&pool, campaign.id, campaign.user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(updated.status, CampaignStatus::Sending);
+}
+
+#[tokio::test]
+async fn test_campaign_lock_is_released_when_dropped() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let campaign_id = Uuid::new_v4();
+
+    let lock = CampaignLock::try_acquire(&pool, campaign_id)
+        .await
+        .unwrap()
+        .expect("lock should be acquired");
+    assert!(CampaignLock::try_acquire(&pool, campaign_id)
+        .await
+        .unwrap()
+        .is_none());
+    lock.release().await.unwrap();
+
+    // 解放せずに破棄された場合（エラー・キャンセル）は接続を閉じてロックを解放する
+    let lock = CampaignLock::try_acquire(&pool, campaign_id)
+        .await
+        .unwrap()
+        .expect("lock should be acquired after release");
+    drop(lock);
+
+    let mut reacquired = None;
+    for _ in 0..50 {
+        reacquired = CampaignLock::try_acquire(&pool, campaign_id).await.unwrap();
+        if reacquired.is_some() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    reacquired
+        .expect("lock should be released when the connection is closed")
+        .release()
+        .await
+        .unwrap();
+}
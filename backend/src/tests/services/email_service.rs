@@ -17,11 +17,13 @@ async fn test_email_service_creation(pool: PgPool) {
 async fn test_email_message_validation() {
     let valid_message = EmailMessage {
         to: vec!["recipient@example.com".to_string()],
+        from: None,
         subject: "Test Email".to_string(),
         html_body: "<h1>Hello</h1>".to_string(),
         text_body: Some("Hello".to_string()),
         reply_to: None,
         headers: None,
+        dkim: None,
     };
 
     assert!(!valid_message.to.is_empty());
@@ -33,11 +35,13 @@ async fn test_batch_email_creation() {
     let messages: Vec<EmailMessage> = (0..5)
         .map(|i| EmailMessage {
             to: vec![format!("user{}@example.com", i)],
+            from: None,
             subject: format!("Test Email {i}"),
             html_body: format!("<h1>Hello User {i}</h1>"),
             text_body: Some(format!("Hello User {i}")),
             reply_to: None,
             headers: None,
+            dkim: None,
         })
         .collect();
 
@@ -155,11 +159,13 @@ mod mock_tests {
         let provider = MockEmailProvider::new();
         let message = EmailMessage {
             to: vec!["test@example.com".to_string()],
+            from: None,
             subject: "Test".to_string(),
             html_body: "<p>Test</p>".to_string(),
             text_body: Some("Test".to_string()),
             reply_to: None,
             headers: None,
+            dkim: None,
         };
 
         let result = provider.send_email(&message).await.unwrap();
@@ -167,3 +173,184 @@ mod mock_tests {
         assert_eq!(provider.get_sent_messages().len(), 1);
     }
 }
+
+// ローカルのSMTPシンクを使ったSMTPプロバイダーのテスト
+mod smtp_sink_tests {
+    use super::*;
+    use crate::services::email_service::{SmtpConfig, SmtpProvider, SmtpSecurity};
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    };
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    /// シンクが受け取ったメール
+    #[derive(Debug, Clone, Default)]
+    struct ReceivedMail {
+        auth: Option<String>,
+        mail_from: String,
+        rcpt_to: Vec<String>,
+        data: String,
+    }
+
+    #[derive(Default)]
+    struct SmtpSink {
+        connections: AtomicUsize,
+        mails: Mutex<Vec<ReceivedMail>>,
+    }
+
+    /// AUTH PLAINに対応する最小限のSMTPサーバーを起動する
+    async fn start_sink() -> (u16, Arc<SmtpSink>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let sink = Arc::new(SmtpSink::default());
+
+        let state = sink.clone();
+        tokio::spawn(async move {
+            loop {
+                let Ok((stream, _)) = listener.accept().await else {
+                    break;
+                };
+                state.connections.fetch_add(1, Ordering::SeqCst);
+                let state = state.clone();
+                tokio::spawn(async move {
+                    let (reader, mut writer) = stream.into_split();
+                    let mut lines = BufReader::new(reader).lines();
+                    let mut auth = None;
+                    let mut mail = ReceivedMail::default();
+
+                    writer.write_all(b"220 sink ESMTP\r\n").await.unwrap();
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        let command = line.to_ascii_uppercase();
+                        let reply: &[u8] = if command.starts_with("EHLO") {
+                            b"250-sink\r\n250 AUTH PLAIN LOGIN\r\n"
+                        } else if let Some(credentials) = line.strip_prefix("AUTH PLAIN ") {
+                            auth = Some(credentials.to_string());
+                            b"235 2.7.0 Authentication successful\r\n"
+                        } else if command.starts_with("MAIL FROM:") {
+                            mail = ReceivedMail {
+                                auth: auth.clone(),
+                                mail_from: line[10..].to_string(),
+                                ..Default::default()
+                            };
+                            b"250 OK\r\n"
+                        } else if command.starts_with("RCPT TO:") {
+                            mail.rcpt_to.push(line[8..].to_string());
+                            b"250 OK\r\n"
+                        } else if command == "DATA" {
+                            writer
+                                .write_all(b"354 End data with <CR><LF>.<CR><LF>\r\n")
+                                .await
+                                .unwrap();
+                            while let Ok(Some(data)) = lines.next_line().await {
+                                if data == "." {
+                                    break;
+                                }
+                                mail.data.push_str(&data);
+                                mail.data.push('\n');
+                            }
+                            state.mails.lock().unwrap().push(mail.clone());
+                            b"250 OK: queued\r\n"
+                        } else if command == "QUIT" {
+                            writer.write_all(b"221 Bye\r\n").await.unwrap();
+                            break;
+                        } else {
+                            b"250 OK\r\n"
+                        };
+                        writer.write_all(reply).await.unwrap();
+                    }
+                });
+            }
+        });
+
+        (port, sink)
+    }
+
+    fn sink_config(port: u16) -> SmtpConfig {
+        SmtpConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            username: Some("relay-user".to_string()),
+            password: Some("relay-pass".to_string()),
+            security: SmtpSecurity::None,
+            accept_invalid_certs: false,
+            pool_max_size: 2,
+            timeout_secs: 5,
+        }
+    }
+
+    fn message(to: &str) -> EmailMessage {
+        EmailMessage {
+            to: vec![to.to_string()],
+            from: None,
+            subject: "SMTPテスト".to_string(),
+            html_body: "<p>こんにちは</p>".to_string(),
+            text_body: Some("こんにちは".to_string()),
+            reply_to: None,
+            headers: None,
+            dkim: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_smtp_provider_sends_with_auth_and_pooled_connection() {
+        let (port, sink) = start_sink().await;
+        let provider =
+            SmtpProvider::new(sink_config(port), "noreply@example.com".to_string(), "SMTP")
+                .unwrap();
+        assert_eq!(provider.provider_name(), "SMTP");
+
+        let first = provider.send_email(&message("first@example.com")).await;
+        assert_eq!(first.unwrap().status, EmailStatus::Sent);
+
+        // 接続は送信後に非同期でプールへ戻される
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        let custom = EmailMessage {
+            from: Some("News <news@example.com>".to_string()),
+            reply_to: Some("support@example.com".to_string()),
+            ..message("second@example.com")
+        };
+        let second = provider.send_email(&custom).await;
+        assert_eq!(second.unwrap().status, EmailStatus::Sent);
+
+        let mails = sink.mails.lock().unwrap().clone();
+        assert_eq!(mails.len(), 2);
+
+        // 認証情報はAUTH PLAINで送られる
+        let credentials = STANDARD.decode(mails[0].auth.as_ref().unwrap()).unwrap();
+        assert_eq!(credentials, b"\0relay-user\0relay-pass");
+
+        assert_eq!(mails[0].mail_from, "<noreply@example.com>");
+        assert_eq!(mails[0].rcpt_to, vec!["<first@example.com>"]);
+        assert!(mails[0].data.contains("From: noreply@example.com"));
+
+        // メッセージごとの送信元と返信先
+        assert_eq!(mails[1].mail_from, "<news@example.com>");
+        assert!(mails[1].data.contains("From: News <news@example.com>"));
+        assert!(mails[1].data.contains("Reply-To: support@example.com"));
+
+        // 2通目はプールされた接続を再利用する
+        assert_eq!(sink.connections.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_smtp_provider_reports_unreachable_server_as_transient() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        let provider =
+            SmtpProvider::new(sink_config(port), "noreply@example.com".to_string(), "SMTP")
+                .unwrap();
+        let error = provider
+            .send_email(&message("first@example.com"))
+            .await
+            .unwrap_err();
+        assert!(error.is_transient());
+    }
+}
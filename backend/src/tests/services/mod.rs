@@ -1,4 +1,18 @@
 #[cfg(test)]
+pub mod ab_tests;
+#[cfg(test)]
+pub mod audience;
+#[cfg(test)]
+pub mod campaign_deliveries;
+#[cfg(test)]
 pub mod email_service;
 #[cfg(test)]
+pub mod segments;
+#[cfg(test)]
+pub mod sending_domains;
+#[cfg(test)]
+pub mod sequence_triggers;
+#[cfg(test)]
+pub mod sequences;
+#[cfg(test)]
 pub mod subscription_service;
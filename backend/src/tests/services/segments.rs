@@ -0,0 +1,417 @@
+use chrono::{Duration, Utc};
+use serde_json::json;
+use sqlx::{types::Json, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    database::{campaigns, segments, sequences, subscribers, tracking},
+    models::{
+        audience::{AudienceDefinition, ConditionOperator, CustomFieldCondition},
+        campaign::CreateCampaignRequest,
+        segment::{CreateSegmentRequest, SegmentRule},
+        sequence::{CreateSequenceRequest, TriggerType, UpdateSequenceRequest},
+        subscriber::{
+            CreateSubscriberRequest, Subscriber, SubscriberStatus, UpdateSubscriberRequest,
+        },
+        tracking::TrackingEventType,
+    },
+    services::sequence_service::SequenceService,
+    tests::api::templates::{create_test_template, create_test_user},
+    AppState,
+};
+
+async fn create_subscriber(
+    pool: &PgPool,
+    user_id: Uuid,
+    label: &str,
+    status: SubscriberStatus,
+    tags: &[&str],
+    custom_fields: serde_json::Value,
+) -> Subscriber {
+    subscribers::create_subscriber(
+        pool,
+        user_id,
+        &CreateSubscriberRequest {
+            email: format!("{label}-{}@example.com", Uuid::new_v4()),
+            name: Some(label.to_string()),
+            status: Some(status),
+            tags: Some(tags.iter().map(|t| t.to_string()).collect()),
+            custom_fields: Some(custom_fields),
+        },
+    )
+    .await
+    .expect("Failed to create subscriber")
+}
+
+// テスト用の購読者を作成（aliceは1年前に登録したことにする）
+async fn setup_subscribers(pool: &PgPool) -> (Uuid, Vec<Subscriber>) {
+    let user_id = create_test_user(pool).await;
+
+    let alice = create_subscriber(
+        pool,
+        user_id,
+        "alice",
+        SubscriberStatus::Active,
+        &["customer", "vip"],
+        json!({"plan": "pro"}),
+    )
+    .await;
+    let bob = create_subscriber(
+        pool,
+        user_id,
+        "bob",
+        SubscriberStatus::Active,
+        &["customer"],
+        json!({"plan": "free"}),
+    )
+    .await;
+    let carol = create_subscriber(
+        pool,
+        user_id,
+        "carol",
+        SubscriberStatus::Unsubscribed,
+        &["lead"],
+        json!({}),
+    )
+    .await;
+
+    sqlx::query("UPDATE subscribers SET subscribed_at = NOW() - INTERVAL '1 year' WHERE id = $1")
+        .bind(alice.id)
+        .execute(pool)
+        .await
+        .unwrap();
+
+    (user_id, vec![alice, bob, carol])
+}
+
+async fn names(pool: &PgPool, user_id: Uuid, rule: &SegmentRule) -> Vec<String> {
+    let subscribers = subscribers::list_segment_subscribers(pool, user_id, rule, None, None)
+        .await
+        .unwrap();
+    let count = subscribers::count_segment_subscribers(pool, user_id, rule)
+        .await
+        .unwrap();
+    assert_eq!(count, subscribers.len() as i64);
+
+    let mut names: Vec<String> = subscribers.into_iter().filter_map(|s| s.name).collect();
+    names.sort();
+    names
+}
+
+fn tag(tag: &str) -> SegmentRule {
+    SegmentRule::Tag {
+        tag: tag.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_segment_rule_tree() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let (user_id, _) = setup_subscribers(&pool).await;
+
+    let cases = vec![
+        (tag("customer"), vec!["alice", "bob"]),
+        (
+            SegmentRule::Status {
+                statuses: vec![SubscriberStatus::Unsubscribed],
+            },
+            vec!["carol"],
+        ),
+        (
+            SegmentRule::Any {
+                rules: vec![tag("vip"), tag("lead")],
+            },
+            vec!["alice", "carol"],
+        ),
+        (
+            SegmentRule::All {
+                rules: vec![
+                    tag("customer"),
+                    SegmentRule::Not {
+                        rule: Box::new(tag("vip")),
+                    },
+                ],
+            },
+            vec!["bob"],
+        ),
+        (
+            SegmentRule::SubscribedAt {
+                after: Some(Utc::now() - Duration::days(30)),
+                before: None,
+            },
+            vec!["bob", "carol"],
+        ),
+        (
+            SegmentRule::SubscribedAt {
+                after: None,
+                before: Some(Utc::now() - Duration::days(30)),
+            },
+            vec!["alice"],
+        ),
+        // カスタムフィールドがない購読者も否定の条件には一致する
+        (
+            SegmentRule::Not {
+                rule: Box::new(SegmentRule::CustomField(CustomFieldCondition {
+                    field: "plan".to_string(),
+                    operator: ConditionOperator::Equals,
+                    value: json!("pro"),
+                })),
+            },
+            vec!["bob", "carol"],
+        ),
+    ];
+
+    for (rule, expected) in cases {
+        assert_eq!(names(&pool, user_id, &rule).await, expected, "{rule:?}");
+    }
+}
+
+#[tokio::test]
+async fn test_segment_opened_and_clicked_campaign() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let (user_id, subscribers) = setup_subscribers(&pool).await;
+    let template = create_test_template(&pool, user_id).await;
+
+    let campaign = campaigns::create_campaign(
+        &pool,
+        user_id,
+        &CreateCampaignRequest {
+            name: "セグメントテスト".to_string(),
+            description: None,
+            subject: "セグメントテスト".to_string(),
+            template_id: template.id,
+            audience: None,
+            from_email: None,
+            from_name: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    // aliceは開封とクリック、bobは開封のみ
+    for (subscriber, event_type) in [
+        (&subscribers[0], TrackingEventType::Open),
+        (&subscribers[0], TrackingEventType::Click),
+        (&subscribers[1], TrackingEventType::Open),
+    ] {
+        tracking::record_tracking_event(
+            &pool,
+            campaign.id,
+            subscriber.id,
+            event_type,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+    }
+
+    let opened = SegmentRule::OpenedCampaign {
+        campaign_id: campaign.id,
+    };
+    let clicked = SegmentRule::ClickedCampaign {
+        campaign_id: campaign.id,
+    };
+
+    assert_eq!(names(&pool, user_id, &opened).await, vec!["alice", "bob"]);
+    assert_eq!(names(&pool, user_id, &clicked).await, vec!["alice"]);
+    assert_eq!(
+        names(
+            &pool,
+            user_id,
+            &SegmentRule::All {
+                rules: vec![
+                    opened,
+                    SegmentRule::Not {
+                        rule: Box::new(clicked)
+                    }
+                ]
+            }
+        )
+        .await,
+        vec!["bob"]
+    );
+}
+
+#[tokio::test]
+async fn test_campaign_audience_with_segment() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let (user_id, _) = setup_subscribers(&pool).await;
+
+    let segment = segments::create_segment(
+        &pool,
+        user_id,
+        &CreateSegmentRequest {
+            name: "既存顧客".to_string(),
+            description: None,
+            rules: SegmentRule::Any {
+                rules: vec![tag("vip"), tag("lead")],
+            },
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        segment.rules,
+        Json(SegmentRule::Any {
+            rules: vec![tag("vip"), tag("lead")],
+        })
+    );
+
+    // 配信停止済みのcarolはセグメントに一致しても配信対象にならない
+    let audience = AudienceDefinition {
+        segment_id: Some(segment.id),
+        ..Default::default()
+    };
+    let audience_subscribers =
+        subscribers::list_audience_subscribers(&pool, user_id, &audience, None, None)
+            .await
+            .unwrap();
+    assert_eq!(audience_subscribers.len(), 1);
+    assert_eq!(audience_subscribers[0].name.as_deref(), Some("alice"));
+
+    // 存在しないセグメントは誰にも一致しない
+    let audience = AudienceDefinition {
+        segment_id: Some(Uuid::new_v4()),
+        ..Default::default()
+    };
+    assert_eq!(
+        subscribers::count_audience_subscribers(&pool, user_id, &audience)
+            .await
+            .unwrap(),
+        0
+    );
+}
+
+#[tokio::test]
+async fn test_segment_trigger_enrolls_new_members_once() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let (user_id, subscribers) = setup_subscribers(&pool).await;
+
+    let segment = segments::create_segment(
+        &pool,
+        user_id,
+        &CreateSegmentRequest {
+            name: "顧客".to_string(),
+            description: None,
+            rules: tag("customer"),
+        },
+    )
+    .await
+    .unwrap();
+
+    let sequence = sequences::create_sequence(
+        &pool,
+        user_id,
+        CreateSequenceRequest {
+            name: "セグメント登録シーケンス".to_string(),
+            description: None,
+            trigger_type: TriggerType::SegmentEntered.as_str().to_string(),
+            trigger_config: Some(json!({ "segment_id": segment.id })),
+            reentry_policy: None,
+            exit_conditions: None,
+            send_window: None,
+        },
+    )
+    .await
+    .unwrap();
+    let service = SequenceService::new();
+    service
+        .snapshot_segment_members(
+            &pool,
+            user_id,
+            sequence.id,
+            &sequence.trigger_type,
+            &sequence.trigger_config,
+        )
+        .await
+        .unwrap();
+    sequences::update_sequence(
+        &pool,
+        sequence.id,
+        UpdateSequenceRequest {
+            name: None,
+            description: None,
+            trigger_type: None,
+            trigger_config: None,
+            status: Some("active".to_string()),
+            reentry_policy: None,
+            exit_conditions: None,
+            send_window: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    service.process_segment_triggers(&pool).await.unwrap();
+
+    let enrolled_count = |pool: PgPool| async move {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM sequence_enrollments WHERE sequence_id = $1",
+        )
+        .bind(sequence.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap()
+    };
+    // 有効化の時点で一致していたalice・bobは登録されない
+    assert_eq!(enrolled_count(pool.clone()).await, 0);
+
+    // 新たに一致した購読者のみ登録される
+
+    subscribers::update_subscriber(
+        &pool,
+        subscribers[2].id,
+        user_id,
+        &UpdateSubscriberRequest {
+            email: None,
+            name: None,
+            status: Some(SubscriberStatus::Active),
+            tags: Some(vec!["customer".to_string()]),
+            custom_fields: None,
+        },
+    )
+    .await
+    .unwrap();
+    service.process_segment_triggers(&pool).await.unwrap();
+    assert_eq!(enrolled_count(pool.clone()).await, 1);
+
+    // 2回目は既に登録済みのため増えない
+    service.process_segment_triggers(&pool).await.unwrap();
+    assert_eq!(enrolled_count(pool.clone()).await, 1);
+
+    // 有効化の時点の一致者も、セグメントから外れて再び一致すると登録される
+    for tags in [vec![], vec!["customer".to_string()]] {
+        subscribers::update_subscriber(
+            &pool,
+            subscribers[1].id,
+            user_id,
+            &UpdateSubscriberRequest {
+                email: None,
+                name: None,
+                status: None,
+                tags: Some(tags),
+                custom_fields: None,
+            },
+        )
+        .await
+        .unwrap();
+        service.process_segment_triggers(&pool).await.unwrap();
+    }
+    assert_eq!(enrolled_count(pool.clone()).await, 2);
+
+    assert!(service
+        .validate_trigger_config(
+            &pool,
+            user_id,
+            TriggerType::SegmentEntered.as_str(),
+            Some(&json!({ "segment_id": Uuid::new_v4() })),
+        )
+        .await
+        .is_err());
+}
@@ -0,0 +1,115 @@
+use std::sync::Arc;
+
+use crate::{
+    models::{
+        campaign::CreateCampaignRequest,
+        sending_domain::{CreateSendingDomainRequest, SendingDomainStatus},
+    },
+    services::{
+        campaign_service::CampaignService,
+        dns_resolver::StaticDnsResolver,
+        sending_domain_service::{SendingDomainError, SendingDomainService},
+    },
+    tests::api::templates::{create_test_template, create_test_user},
+    AppState,
+};
+
+#[tokio::test]
+async fn test_verify_domain_and_send_from_it() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let user_id = create_test_user(&pool).await;
+
+    let created =
+        SendingDomainService::with_resolver(Arc::new(StaticDnsResolver::new()), "amazonses.com")
+            .create_domain(
+                &pool,
+                user_id,
+                &CreateSendingDomainRequest {
+                    domain: "news.example.com".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+    let domain = created.domain;
+
+    // レコードが公開されていなければ認証に失敗する
+    let unpublished = SendingDomainService::with_resolver(
+        Arc::new(
+            StaticDnsResolver::new()
+                .with_txt("news.example.com", "v=spf1 include:amazonses.com ~all"),
+        ),
+        "amazonses.com",
+    );
+    let failed = unpublished
+        .verify_domain(&pool, domain.id, user_id)
+        .await
+        .unwrap();
+    assert_eq!(failed.domain.status(), SendingDomainStatus::Failed);
+    assert!(failed.domain.spf_verified);
+    assert!(!failed.domain.dkim_verified);
+    assert!(failed.domain.last_checked_at.is_some());
+    assert!(
+        SendingDomainService::verified_sender(&pool, user_id, "info@news.example.com", None)
+            .await
+            .is_err()
+    );
+
+    let published = SendingDomainService::with_resolver(
+        Arc::new(
+            StaticDnsResolver::new()
+                .with_txt(&domain.dkim_record_name(), &domain.dkim_record_value())
+                .with_txt("news.example.com", "v=spf1 include:amazonses.com ~all")
+                .with_txt("_dmarc.news.example.com", "v=DMARC1; p=none;"),
+        ),
+        "amazonses.com",
+    );
+    let verified = published
+        .verify_domain(&pool, domain.id, user_id)
+        .await
+        .unwrap();
+    assert_eq!(verified.domain.status(), SendingDomainStatus::Verified);
+    assert!(verified.dns_records.iter().all(|record| record.verified));
+    assert!(verified.domain.verified_at.is_some());
+
+    let sender = SendingDomainService::verified_sender(
+        &pool,
+        user_id,
+        "Info@News.Example.com",
+        Some("ニュース"),
+    )
+    .await
+    .unwrap();
+    assert!(sender.from.ends_with("<Info@News.Example.com>"));
+    assert!(
+        SendingDomainService::verified_sender(&pool, user_id, "info@example.com", None)
+            .await
+            .is_err()
+    );
+
+    // 認証済みのドメインを送信元にしたキャンペーンは作成でき、送信前はドメインを削除できない
+    let template = create_test_template(&pool, user_id).await;
+    let campaign = CampaignService::new()
+        .create_campaign(
+            &pool,
+            user_id,
+            &CreateCampaignRequest {
+                name: "独自ドメイン".to_string(),
+                description: None,
+                subject: "独自ドメイン".to_string(),
+                template_id: template.id,
+                audience: None,
+                from_email: Some("info@news.example.com".to_string()),
+                from_name: Some("ニュース".to_string()),
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        campaign.from_email.as_deref(),
+        Some("info@news.example.com")
+    );
+
+    let result = published.delete_domain(&pool, domain.id, user_id).await;
+    assert!(matches!(result, Err(SendingDomainError::Conflict(_))));
+}
@@ -0,0 +1,169 @@
+use chrono::NaiveDate;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    database::{sequences, subscribers},
+    models::{
+        sequence::{CreateSequenceRequest, Sequence},
+        subscriber::{CreateSubscriberRequest, Subscriber},
+    },
+    services::sequence_service::SequenceService,
+    tests::api::templates::create_test_user,
+    AppState,
+};
+
+async fn create_date_sequence(
+    pool: &PgPool,
+    user_id: Uuid,
+    trigger_type: &str,
+    trigger_config: Value,
+) -> Sequence {
+    let sequence = sequences::create_sequence(
+        pool,
+        user_id,
+        CreateSequenceRequest {
+            name: format!("{trigger_type}シーケンス"),
+            description: None,
+            trigger_type: trigger_type.to_string(),
+            trigger_config: Some(trigger_config),
+            reentry_policy: None,
+            exit_conditions: None,
+            send_window: None,
+        },
+    )
+    .await
+    .unwrap();
+    sequences::update_sequence_status(pool, sequence.id, "active")
+        .await
+        .unwrap();
+    sequence
+}
+
+async fn create_subscriber(pool: &PgPool, user_id: Uuid, custom_fields: Value) -> Subscriber {
+    subscribers::create_subscriber(
+        pool,
+        user_id,
+        &CreateSubscriberRequest {
+            email: format!("date-{}@example.com", Uuid::new_v4()),
+            name: None,
+            status: None,
+            tags: None,
+            custom_fields: Some(custom_fields),
+        },
+    )
+    .await
+    .unwrap()
+}
+
+// 購読者IDごとのエンロールメントのメタデータ
+async fn enrollments(pool: &PgPool, sequence_id: Uuid) -> Vec<(Uuid, Value)> {
+    sqlx::query_as::<_, (Uuid, Value)>(
+        "SELECT subscriber_id, metadata FROM sequence_enrollments WHERE sequence_id = $1",
+    )
+    .bind(sequence_id)
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+fn metadata_for(enrollments: &[(Uuid, Value)], subscriber_id: Uuid) -> Option<&Value> {
+    enrollments
+        .iter()
+        .find(|(id, _)| *id == subscriber_id)
+        .map(|(_, metadata)| metadata)
+}
+
+#[tokio::test]
+async fn test_birthday_trigger_enrolls_subscribers_before_date() {
+    let state = AppState::new_for_test().await;
+    let user_id = create_test_user(&state.db).await;
+    let sequence =
+        create_date_sequence(&state.db, user_id, "birthday", json!({"days_before": 3})).await;
+
+    let dated = create_subscriber(&state.db, user_id, json!({"birthday": "1990-02-28"})).await;
+    let leap_day = create_subscriber(&state.db, user_id, json!({"birthday": "--02-29"})).await;
+    let with_time = create_subscriber(
+        &state.db,
+        user_id,
+        json!({"birthday": "2000-02-29T00:00:00"}),
+    )
+    .await;
+    let other_day = create_subscriber(&state.db, user_id, json!({"birthday": "03-01"})).await;
+    let no_birthday = create_subscriber(&state.db, user_id, json!({})).await;
+
+    // 2027年は閏年でないため、2月28日に2月29日生まれも対象にする
+    let today = NaiveDate::from_ymd_opt(2027, 2, 25).unwrap();
+    SequenceService::new()
+        .process_date_triggers(&state.db, today)
+        .await
+        .unwrap();
+
+    let enrolled = enrollments(&state.db, sequence.id).await;
+    assert_eq!(enrolled.len(), 3);
+    assert_eq!(
+        metadata_for(&enrolled, dated.id),
+        Some(&json!({
+            "field": "birthday",
+            "days_before": 3,
+            "date": "2027-02-28",
+            "years": 37
+        }))
+    );
+    assert_eq!(
+        metadata_for(&enrolled, leap_day.id),
+        Some(&json!({"field": "birthday", "days_before": 3, "date": "2027-02-28"}))
+    );
+    assert_eq!(metadata_for(&enrolled, with_time.id).unwrap()["years"], 27);
+    assert!(metadata_for(&enrolled, other_day.id).is_none());
+    assert!(metadata_for(&enrolled, no_birthday.id).is_none());
+
+    // 同じ日に再実行しても重複して登録しない
+    SequenceService::new()
+        .process_date_triggers(&state.db, today)
+        .await
+        .unwrap();
+    assert_eq!(enrollments(&state.db, sequence.id).await.len(), 3);
+}
+
+#[tokio::test]
+async fn test_anniversary_trigger_requires_past_year() {
+    let state = AppState::new_for_test().await;
+    let user_id = create_test_user(&state.db).await;
+    let sequence = create_date_sequence(
+        &state.db,
+        user_id,
+        "anniversary",
+        json!({"field": "joined_on"}),
+    )
+    .await;
+
+    let two_years = create_subscriber(&state.db, user_id, json!({"joined_on": "2025-06-01"})).await;
+    let joined_today =
+        create_subscriber(&state.db, user_id, json!({"joined_on": "2027-06-01"})).await;
+    let without_year = create_subscriber(&state.db, user_id, json!({"joined_on": "06-01"})).await;
+    let other_field =
+        create_subscriber(&state.db, user_id, json!({"birthday": "2020-06-01"})).await;
+
+    let today = NaiveDate::from_ymd_opt(2027, 6, 1).unwrap();
+    SequenceService::new()
+        .process_date_triggers(&state.db, today)
+        .await
+        .unwrap();
+
+    let enrolled = enrollments(&state.db, sequence.id).await;
+    assert_eq!(enrolled.len(), 1);
+    assert_eq!(
+        metadata_for(&enrolled, two_years.id),
+        Some(&json!({
+            "field": "joined_on",
+            "days_before": 0,
+            "date": "2027-06-01",
+            "years": 2
+        }))
+    );
+    assert!(metadata_for(&enrolled, joined_today.id).is_none());
+    assert!(metadata_for(&enrolled, without_year.id).is_none());
+    assert!(metadata_for(&enrolled, other_field.id).is_none());
+}
@@ -0,0 +1,881 @@
+use chrono::{Datelike, Duration, Timelike, Utc};
+use chrono_tz::Tz;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    database::{campaigns, sequences, subscribers},
+    models::{
+        campaign::CreateCampaignRequest,
+        sequence::{
+            CreateSequenceEnrollmentRequest, CreateSequenceRequest, CreateSequenceStepRequest,
+            Sequence, SequenceStep, StepType, UpdateSequenceStepRequest,
+        },
+        subscriber::{CreateSubscriberRequest, Subscriber},
+    },
+    services::sequence_service::{SequenceService, StepChange},
+    tests::api::templates::{create_test_template, create_test_user},
+    AppState,
+};
+
+async fn create_sequence(pool: &PgPool, user_id: Uuid) -> Sequence {
+    sequences::create_sequence(
+        pool,
+        user_id,
+        CreateSequenceRequest {
+            name: "分岐シーケンス".to_string(),
+            description: None,
+            trigger_type: "manual".to_string(),
+            trigger_config: None,
+            reentry_policy: None,
+            exit_conditions: None,
+            send_window: None,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+async fn create_step(
+    pool: &PgPool,
+    sequence_id: Uuid,
+    step_order: i32,
+    step_type: StepType,
+    conditions: serde_json::Value,
+    action_config: serde_json::Value,
+    true_step_id: Option<Uuid>,
+) -> SequenceStep {
+    sequences::create_sequence_step(
+        pool,
+        sequence_id,
+        CreateSequenceStepRequest {
+            name: format!("ステップ{step_order}"),
+            step_order,
+            step_type: step_type.as_str().to_string(),
+            delay_value: None,
+            delay_unit: None,
+            template_id: None,
+            subject: None,
+            conditions: Some(conditions),
+            action_config: Some(action_config),
+            true_step_id,
+            false_step_id: None,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+async fn create_subscriber(pool: &PgPool, user_id: Uuid, tags: &[&str]) -> Subscriber {
+    subscribers::create_subscriber(
+        pool,
+        user_id,
+        &CreateSubscriberRequest {
+            email: format!("branch-{}@example.com", Uuid::new_v4()),
+            name: None,
+            status: None,
+            tags: Some(tags.iter().map(|t| t.to_string()).collect()),
+            custom_fields: Some(json!({"plan": "pro"})),
+        },
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_condition_step_branches_enrollments() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let user_id = create_test_user(&pool).await;
+    let sequence = create_sequence(&pool, user_id).await;
+
+    // 1: VIPかつ登録時のフォームがcontactなら3へ、それ以外は2へ
+    // 2: regularタグを追加
+    // 3: welcomedタグを追加
+    let welcomed = create_step(
+        &pool,
+        sequence.id,
+        3,
+        StepType::Tag,
+        json!({}),
+        json!({"tag": "welcomed"}),
+        None,
+    )
+    .await;
+    create_step(
+        &pool,
+        sequence.id,
+        2,
+        StepType::Tag,
+        json!({}),
+        json!({"tag": "regular"}),
+        None,
+    )
+    .await;
+    create_step(
+        &pool,
+        sequence.id,
+        1,
+        StepType::Condition,
+        json!({
+            "type": "all",
+            "rules": [
+                {"type": "tag", "tag": "vip"},
+                {"type": "metadata", "field": "form_id", "operator": "equals", "value": "contact"}
+            ]
+        }),
+        json!({}),
+        Some(welcomed.id),
+    )
+    .await;
+
+    let service = SequenceService::new();
+    service.validate_activation(&pool, &sequence).await.unwrap();
+
+    let vip = create_subscriber(&pool, user_id, &["vip"]).await;
+    let other = create_subscriber(&pool, user_id, &[]).await;
+    let mut enrollment_ids = Vec::new();
+    for subscriber in [&vip, &other] {
+        let enrollment = sequences::create_sequence_enrollment(
+            &pool,
+            sequence.id,
+            &CreateSequenceEnrollmentRequest {
+                subscriber_id: subscriber.id,
+                trigger_data: Some(json!({"form_id": "contact"})),
+            },
+        )
+        .await
+        .unwrap();
+        assert!(enrollment.current_step_id.is_some());
+        enrollment_ids.push(enrollment.id);
+    }
+
+    for _ in 0..4 {
+        service.process_pending_sequence_steps(&pool).await.unwrap();
+    }
+
+    let tags = |subscriber_id| {
+        let pool = pool.clone();
+        async move {
+            subscribers::find_subscriber_by_id(&pool, subscriber_id, user_id)
+                .await
+                .unwrap()
+                .unwrap()
+                .tags
+        }
+    };
+    assert_eq!(tags(vip.id).await, vec!["vip", "welcomed"]);
+    assert_eq!(tags(other.id).await, vec!["regular", "welcomed"]);
+
+    for enrollment_id in enrollment_ids {
+        let status = sqlx::query_scalar::<_, String>(
+            "SELECT status FROM sequence_enrollments WHERE id = $1",
+        )
+        .bind(enrollment_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(status, "completed");
+    }
+}
+
+#[tokio::test]
+async fn test_activation_rejects_cycles_and_dangling_branches() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let user_id = create_test_user(&pool).await;
+    let sequence = create_sequence(&pool, user_id).await;
+    let service = SequenceService::new();
+
+    let first = create_step(
+        &pool,
+        sequence.id,
+        1,
+        StepType::Tag,
+        json!({}),
+        json!({"tag": "first"}),
+        None,
+    )
+    .await;
+    let condition = create_step(
+        &pool,
+        sequence.id,
+        2,
+        StepType::Condition,
+        json!({"type": "tag", "tag": "vip"}),
+        json!({}),
+        Some(first.id),
+    )
+    .await;
+    assert!(service.validate_activation(&pool, &sequence).await.is_err());
+
+    // 分岐先のステップを削除すると分岐は未設定（次のステップ）に戻る
+    let last = create_step(
+        &pool,
+        sequence.id,
+        3,
+        StepType::Tag,
+        json!({}),
+        json!({"tag": "last"}),
+        None,
+    )
+    .await;
+    sequences::update_sequence_step(
+        &pool,
+        condition.id,
+        UpdateSequenceStepRequest {
+            name: None,
+            step_order: None,
+            step_type: None,
+            delay_value: None,
+            delay_unit: None,
+            template_id: None,
+            subject: None,
+            conditions: None,
+            action_config: None,
+            true_step_id: Some(Some(last.id)),
+            false_step_id: None,
+        },
+    )
+    .await
+    .unwrap();
+    service.validate_activation(&pool, &sequence).await.unwrap();
+
+    sequences::delete_sequence_step(&pool, last.id)
+        .await
+        .unwrap();
+    let steps = sequences::get_sequence_steps(&pool, sequence.id)
+        .await
+        .unwrap();
+    let reloaded = steps.iter().find(|s| s.id == condition.id).unwrap();
+    assert_eq!(reloaded.true_step_id, None);
+    service.validate_activation(&pool, &sequence).await.unwrap();
+
+    // 存在しないステップは下書きでも分岐先に指定できない
+    let dangling = UpdateSequenceStepRequest {
+        name: None,
+        step_order: None,
+        step_type: None,
+        delay_value: None,
+        delay_unit: None,
+        template_id: None,
+        subject: None,
+        conditions: None,
+        action_config: None,
+        true_step_id: Some(Some(last.id)),
+        false_step_id: None,
+    };
+    assert!(service
+        .validate_step_change(
+            &pool,
+            &sequence,
+            StepChange::Update(condition.id, &dangling)
+        )
+        .await
+        .is_err());
+
+    // 他のユーザーのキャンペーンは条件に指定できない
+    let other_user_id = create_test_user(&pool).await;
+    let other_template = create_test_template(&pool, other_user_id).await;
+    let other_campaign = campaigns::create_campaign(
+        &pool,
+        other_user_id,
+        &CreateCampaignRequest {
+            name: "他のユーザー".to_string(),
+            description: None,
+            subject: "他のユーザー".to_string(),
+            template_id: other_template.id,
+            audience: None,
+            from_email: None,
+            from_name: None,
+        },
+    )
+    .await
+    .unwrap();
+    sequences::update_sequence_step(
+        &pool,
+        condition.id,
+        UpdateSequenceStepRequest {
+            name: None,
+            step_order: None,
+            step_type: None,
+            delay_value: None,
+            delay_unit: None,
+            template_id: None,
+            subject: None,
+            conditions: Some(json!({"type": "opened_campaign", "campaign_id": other_campaign.id})),
+            action_config: None,
+            true_step_id: Some(None),
+            false_step_id: None,
+        },
+    )
+    .await
+    .unwrap();
+    assert!(service.validate_activation(&pool, &sequence).await.is_err());
+
+    // 有効なシーケンスでは循環する変更を保存前に拒否する
+    sequences::update_sequence_step(
+        &pool,
+        condition.id,
+        UpdateSequenceStepRequest {
+            name: None,
+            step_order: None,
+            step_type: None,
+            delay_value: None,
+            delay_unit: None,
+            template_id: None,
+            subject: None,
+            conditions: Some(json!({"type": "tag", "tag": "vip"})),
+            action_config: None,
+            true_step_id: None,
+            false_step_id: None,
+        },
+    )
+    .await
+    .unwrap();
+    service.validate_activation(&pool, &sequence).await.unwrap();
+
+    let active = Sequence {
+        status: "active".to_string(),
+        ..sequence
+    };
+    let cyclic = UpdateSequenceStepRequest {
+        name: None,
+        step_order: None,
+        step_type: None,
+        delay_value: None,
+        delay_unit: None,
+        template_id: None,
+        subject: None,
+        conditions: None,
+        action_config: None,
+        true_step_id: None,
+        false_step_id: Some(Some(first.id)),
+    };
+    assert!(service
+        .validate_step_change(&pool, &active, StepChange::Update(condition.id, &cyclic))
+        .await
+        .is_err());
+    assert!(service
+        .validate_step_change(&pool, &active, StepChange::Delete(first.id))
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn test_exit_conditions_checked_before_each_step() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let user_id = create_test_user(&pool).await;
+    let sequence = sequences::create_sequence(
+        &pool,
+        user_id,
+        CreateSequenceRequest {
+            name: "終了条件シーケンス".to_string(),
+            description: None,
+            trigger_type: "manual".to_string(),
+            trigger_config: None,
+            reentry_policy: None,
+            exit_conditions: Some(json!([{"type": "tag_added", "tag": "vip"}])),
+            send_window: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    // 1: welcomedタグを追加 → 2: vipタグを追加 → 3: afterタグを追加
+    let first = create_step(
+        &pool,
+        sequence.id,
+        1,
+        StepType::Tag,
+        json!({}),
+        json!({"tag": "welcomed"}),
+        None,
+    )
+    .await;
+    for (step_order, tag) in [(2, "vip"), (3, "after")] {
+        create_step(
+            &pool,
+            sequence.id,
+            step_order,
+            StepType::Tag,
+            json!({}),
+            json!({"tag": tag}),
+            None,
+        )
+        .await;
+    }
+
+    let vip = create_subscriber(&pool, user_id, &["vip"]).await;
+    let other = create_subscriber(&pool, user_id, &[]).await;
+    let mut enrollment_ids = Vec::new();
+    for subscriber in [&vip, &other] {
+        let enrollment = sequences::create_sequence_enrollment(
+            &pool,
+            sequence.id,
+            &CreateSequenceEnrollmentRequest {
+                subscriber_id: subscriber.id,
+                trigger_data: None,
+            },
+        )
+        .await
+        .unwrap();
+        enrollment_ids.push(enrollment.id);
+    }
+
+    let service = SequenceService::new();
+    for _ in 0..4 {
+        service.process_pending_sequence_steps(&pool).await.unwrap();
+    }
+
+    let tags = |subscriber_id| {
+        let pool = pool.clone();
+        async move {
+            subscribers::find_subscriber_by_id(&pool, subscriber_id, user_id)
+                .await
+                .unwrap()
+                .unwrap()
+                .tags
+        }
+    };
+    // 登録時点で条件を満たしている購読者は最初のステップを実行しない
+    assert_eq!(tags(vip.id).await, vec!["vip"]);
+    // ステップの実行中に条件を満たした購読者は以降のステップを実行しない
+    assert_eq!(tags(other.id).await, vec!["welcomed", "vip"]);
+
+    for enrollment_id in &enrollment_ids {
+        let status = sqlx::query_scalar::<_, String>(
+            "SELECT status FROM sequence_enrollments WHERE id = $1",
+        )
+        .bind(enrollment_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(status, "goal_reached");
+    }
+
+    let logs = sqlx::query_as::<_, (Uuid, String)>(
+        "SELECT step_id, status FROM sequence_step_logs WHERE enrollment_id = $1",
+    )
+    .bind(enrollment_ids[0])
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(logs, vec![(first.id, "goal_reached".to_string())]);
+}
+
+async fn create_scheduled_sequence(pool: &PgPool, user_id: Uuid, send_window: Value) -> Sequence {
+    sequences::create_sequence(
+        pool,
+        user_id,
+        CreateSequenceRequest {
+            name: "送信時間帯シーケンス".to_string(),
+            description: None,
+            trigger_type: "manual".to_string(),
+            trigger_config: None,
+            reentry_policy: None,
+            exit_conditions: None,
+            send_window: Some(send_window),
+        },
+    )
+    .await
+    .unwrap()
+}
+
+async fn enroll(pool: &PgPool, sequence_id: Uuid, custom_fields: Value, user_id: Uuid) -> Uuid {
+    let subscriber = subscribers::create_subscriber(
+        pool,
+        user_id,
+        &CreateSubscriberRequest {
+            email: format!("window-{}@example.com", Uuid::new_v4()),
+            name: None,
+            status: None,
+            tags: None,
+            custom_fields: Some(custom_fields),
+        },
+    )
+    .await
+    .unwrap();
+
+    sequences::create_sequence_enrollment(
+        pool,
+        sequence_id,
+        &CreateSequenceEnrollmentRequest {
+            subscriber_id: subscriber.id,
+            trigger_data: None,
+        },
+    )
+    .await
+    .unwrap()
+    .id
+}
+
+async fn next_step_at(pool: &PgPool, enrollment_id: Uuid) -> (Option<Uuid>, chrono::DateTime<Utc>) {
+    sqlx::query_as::<_, (Option<Uuid>, chrono::DateTime<Utc>)>(
+        "SELECT current_step_id, next_step_at FROM sequence_enrollments WHERE id = $1",
+    )
+    .bind(enrollment_id)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_wait_step_waits_until_weekday_in_subscriber_timezone() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let user_id = create_test_user(&pool).await;
+    let sequence = create_scheduled_sequence(
+        &pool,
+        user_id,
+        json!({
+            "days": ["Mon", "Tue", "Wed", "Thu", "Fri"],
+            "start_hour": 9,
+            "end_hour": 18,
+            "timezone": "Asia/Tokyo"
+        }),
+    )
+    .await;
+
+    // 1: 1日待機してから水曜10時まで待つ → 2: タグを追加
+    sequences::create_sequence_step(
+        &pool,
+        sequence.id,
+        CreateSequenceStepRequest {
+            name: "水曜まで待機".to_string(),
+            step_order: 1,
+            step_type: StepType::Wait.as_str().to_string(),
+            delay_value: Some(1),
+            delay_unit: Some("days".to_string()),
+            template_id: None,
+            subject: None,
+            conditions: None,
+            action_config: Some(json!({"wait_until": {"days": ["Wed"], "time": "10:00:00"}})),
+            true_step_id: None,
+            false_step_id: None,
+        },
+    )
+    .await
+    .unwrap();
+    let tag = create_step(
+        &pool,
+        sequence.id,
+        2,
+        StepType::Tag,
+        json!({}),
+        json!({"tag": "wednesday"}),
+        None,
+    )
+    .await;
+
+    let new_york = enroll(
+        &pool,
+        sequence.id,
+        json!({"timezone": "America/New_York"}),
+        user_id,
+    )
+    .await;
+    let tokyo = enroll(&pool, sequence.id, json!({}), user_id).await;
+
+    let started = Utc::now();
+    SequenceService::new()
+        .process_pending_sequence_steps(&pool)
+        .await
+        .unwrap();
+
+    for (enrollment_id, timezone) in [(new_york, Tz::America__New_York), (tokyo, Tz::Asia__Tokyo)] {
+        let (current_step_id, next_at) = next_step_at(&pool, enrollment_id).await;
+        assert_eq!(current_step_id, Some(tag.id));
+        assert!(next_at >= started + Duration::days(1));
+        assert!(next_at <= started + Duration::days(8));
+
+        let local = next_at.with_timezone(&timezone);
+        assert_eq!(local.weekday(), chrono::Weekday::Wed);
+        assert_eq!((local.hour(), local.minute()), (10, 0));
+    }
+}
+
+#[tokio::test]
+async fn test_email_step_deferred_outside_send_window() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let user_id = create_test_user(&pool).await;
+
+    // 今日（日本時間）以外の曜日だけを送信できるようにする
+    let today = Utc::now().with_timezone(&Tz::Asia__Tokyo).weekday();
+    let days: Vec<String> = (1..7)
+        .map(|offset| {
+            let mut day = today;
+            for _ in 0..offset {
+                day = day.succ();
+            }
+            day.to_string()
+        })
+        .collect();
+    let sequence = create_scheduled_sequence(
+        &pool,
+        user_id,
+        json!({"days": days, "start_hour": 9, "end_hour": 18, "timezone": "Asia/Tokyo"}),
+    )
+    .await;
+
+    let template = create_test_template(&pool, user_id).await;
+    let email = sequences::create_sequence_step(
+        &pool,
+        sequence.id,
+        CreateSequenceStepRequest {
+            name: "ウェルカムメール".to_string(),
+            step_order: 1,
+            step_type: StepType::Email.as_str().to_string(),
+            delay_value: None,
+            delay_unit: None,
+            template_id: Some(template.id),
+            subject: None,
+            conditions: None,
+            action_config: None,
+            true_step_id: None,
+            false_step_id: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let enrollment_id = enroll(&pool, sequence.id, json!({}), user_id).await;
+    SequenceService::new()
+        .process_pending_sequence_steps(&pool)
+        .await
+        .unwrap();
+
+    let (current_step_id, next_at) = next_step_at(&pool, enrollment_id).await;
+    assert_eq!(current_step_id, Some(email.id));
+    let local = next_at.with_timezone(&Tz::Asia__Tokyo);
+    assert_ne!(local.weekday(), today);
+    assert_eq!((local.hour(), local.minute()), (9, 0));
+
+    let logs = sqlx::query_as::<_, (String, Option<String>)>(
+        "SELECT status, error_message FROM sequence_step_logs WHERE enrollment_id = $1",
+    )
+    .bind(enrollment_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].0, "deferred");
+    assert!(logs[0].1.as_deref().unwrap().contains("送信時間帯外"));
+}
+
+#[tokio::test]
+async fn test_concurrent_workers_claim_each_enrollment_once() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let user_id = create_test_user(&pool).await;
+    let sequence = create_sequence(&pool, user_id).await;
+    create_step(
+        &pool,
+        sequence.id,
+        1,
+        StepType::Tag,
+        json!({}),
+        json!({"tag": "claimed"}),
+        None,
+    )
+    .await;
+
+    let mut enrollment_ids = Vec::new();
+    for _ in 0..30 {
+        let subscriber = create_subscriber(&pool, user_id, &[]).await;
+        let enrollment = sequences::create_sequence_enrollment(
+            &pool,
+            sequence.id,
+            &CreateSequenceEnrollmentRequest {
+                subscriber_id: subscriber.id,
+                trigger_data: None,
+            },
+        )
+        .await
+        .unwrap();
+        enrollment_ids.push(enrollment.id);
+    }
+    // 1時間前から実行待ちだったことにする
+    sqlx::query(
+        "UPDATE sequence_enrollments SET next_step_at = NOW() - INTERVAL '1 hour' WHERE id = ANY($1)",
+    )
+    .bind(&enrollment_ids)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    // 2つのレプリカで同時に処理しても同じステップは一度だけ実行される
+    let (replica1, replica2) = (SequenceService::new(), SequenceService::new());
+    let (first, second) = tokio::join!(
+        replica1.process_pending_sequence_steps(&pool),
+        replica2.process_pending_sequence_steps(&pool),
+    );
+    let (first, second) = (first.unwrap(), second.unwrap());
+    assert!(first.claimed + second.claimed >= enrollment_ids.len());
+    assert!(first.max_lag_ms.max(second.max_lag_ms) >= 3_600_000);
+
+    let rows = sqlx::query_as::<_, (String, Option<chrono::DateTime<Utc>>, i64)>(
+        r#"
+        SELECT e.status, e.locked_at, COUNT(l.id)
+        FROM sequence_enrollments e
+        LEFT JOIN sequence_step_logs l ON l.enrollment_id = e.id
+        WHERE e.id = ANY($1)
+        GROUP BY e.id
+        "#,
+    )
+    .bind(&enrollment_ids)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(rows.len(), enrollment_ids.len());
+    for (status, locked_at, log_count) in rows {
+        assert_eq!(status, "completed");
+        assert_eq!(locked_at, None);
+        assert_eq!(log_count, 1);
+    }
+}
+
+// 購読者を取得できず失敗し続けるエンロールメント（他のユーザーの購読者を登録する）
+async fn enroll_failing(pool: &PgPool, sequence: &Sequence, step_type: &str) -> (Uuid, Uuid) {
+    let step = sequences::create_sequence_step(
+        pool,
+        sequence.id,
+        CreateSequenceStepRequest {
+            name: "失敗するステップ".to_string(),
+            step_order: 1,
+            step_type: step_type.to_string(),
+            delay_value: None,
+            delay_unit: None,
+            template_id: None,
+            subject: None,
+            conditions: None,
+            action_config: Some(json!({"tag": "failed"})),
+            true_step_id: None,
+            false_step_id: None,
+        },
+    )
+    .await
+    .unwrap();
+    let other_user_id = create_test_user(pool).await;
+    let subscriber = create_subscriber(pool, other_user_id, &[]).await;
+    let enrollment = sequences::create_sequence_enrollment(
+        pool,
+        sequence.id,
+        &CreateSequenceEnrollmentRequest {
+            subscriber_id: subscriber.id,
+            trigger_data: None,
+        },
+    )
+    .await
+    .unwrap();
+    (step.id, enrollment.id)
+}
+
+async fn run_due_step(pool: &PgPool, enrollment_id: Uuid) {
+    sqlx::query(
+        "UPDATE sequence_enrollments SET next_step_at = NOW() - INTERVAL '1 minute' WHERE id = $1",
+    )
+    .bind(enrollment_id)
+    .execute(pool)
+    .await
+    .unwrap();
+    SequenceService::new()
+        .process_pending_sequence_steps(pool)
+        .await
+        .unwrap();
+}
+
+async fn failed_step_logs(pool: &PgPool, enrollment_id: Uuid) -> Vec<String> {
+    sqlx::query_scalar(
+        "SELECT error_message FROM sequence_step_logs WHERE enrollment_id = $1 AND status = 'failed' ORDER BY executed_at",
+    )
+    .bind(enrollment_id)
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+async fn enrollment_status(pool: &PgPool, enrollment_id: Uuid) -> String {
+    sqlx::query_scalar("SELECT status FROM sequence_enrollments WHERE id = $1")
+        .bind(enrollment_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_failed_step_is_retried_with_backoff() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let user_id = create_test_user(&pool).await;
+    let sequence = create_sequence(&pool, user_id).await;
+    let (step_id, enrollment_id) = enroll_failing(&pool, &sequence, StepType::Tag.as_str()).await;
+
+    let mut delays = Vec::new();
+    for _ in 0..2 {
+        // 失敗したステップは同じ実行の中で繰り返し確保されない
+        run_due_step(&pool, enrollment_id).await;
+
+        let (current_step_id, next_at) = next_step_at(&pool, enrollment_id).await;
+        assert_eq!(current_step_id, Some(step_id));
+        delays.push((next_at - Utc::now()).num_seconds());
+    }
+
+    // 失敗するごとに再実行までの待機時間が延びる
+    assert!((50..=60).contains(&delays[0]), "{delays:?}");
+    assert!((110..=120).contains(&delays[1]), "{delays:?}");
+    assert_eq!(failed_step_logs(&pool, enrollment_id).await.len(), 2);
+    assert_eq!(enrollment_status(&pool, enrollment_id).await, "active");
+}
+
+#[tokio::test]
+async fn test_step_failing_repeatedly_fails_enrollment() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let user_id = create_test_user(&pool).await;
+    let sequence = create_sequence(&pool, user_id).await;
+    let (step_id, enrollment_id) = enroll_failing(&pool, &sequence, StepType::Tag.as_str()).await;
+
+    // 上限の1回手前まで失敗済み
+    for _ in 0..9 {
+        sequences::create_sequence_step_log(
+            &pool,
+            enrollment_id,
+            step_id,
+            "failed",
+            Some("購読者が見つかりません".to_string()),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+    }
+
+    run_due_step(&pool, enrollment_id).await;
+
+    // 上限に達したら再実行せずにエンロールメントを失敗として終了する
+    assert_eq!(enrollment_status(&pool, enrollment_id).await, "failed");
+    let logs = failed_step_logs(&pool, enrollment_id).await;
+    assert_eq!(logs.len(), 10);
+    assert!(logs[9].contains("再実行を中止しました"), "{logs:?}");
+}
+
+#[tokio::test]
+async fn test_permanent_step_failure_is_not_retried() {
+    let state = AppState::new_for_test().await;
+    let pool = state.db;
+    let user_id = create_test_user(&pool).await;
+    let sequence = create_sequence(&pool, user_id).await;
+    // 実行できないステップ
+    let (_step_id, enrollment_id) = enroll_failing(&pool, &sequence, "unknown").await;
+
+    run_due_step(&pool, enrollment_id).await;
+
+    assert_eq!(enrollment_status(&pool, enrollment_id).await, "failed");
+    let logs = failed_step_logs(&pool, enrollment_id).await;
+    assert_eq!(logs.len(), 1);
+    assert!(logs[0].starts_with("不明なステップタイプ"), "{logs:?}");
+}
@@ -0,0 +1,103 @@
+use std::{env, net::IpAddr};
+
+use axum::http::{header, HeaderMap};
+
+/// リクエスト元のクライアント情報
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientInfo {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub referrer: Option<String>,
+}
+
+impl ClientInfo {
+    /// リクエストヘッダーから取得（`TRUSTED_PROXY_COUNT`段のプロキシを信頼する。既定はALBの1段）
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let trusted_proxies = env::var("TRUSTED_PROXY_COUNT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1);
+        Self::from_headers_with_proxies(headers, trusted_proxies)
+    }
+
+    pub fn from_headers_with_proxies(headers: &HeaderMap, trusted_proxies: usize) -> Self {
+        Self {
+            ip_address: client_ip(headers, trusted_proxies),
+            user_agent: header_value(headers, header::USER_AGENT.as_str()),
+            referrer: header_value(headers, header::REFERER.as_str()),
+        }
+    }
+}
+
+fn header_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// リクエスト元のIPアドレス
+///
+/// X-Forwarded-Forは各プロキシが接続元を末尾に追加するため、先頭側はクライアントが自由に偽装できる。
+/// 信頼するプロキシの段数だけ末尾から数えた値を接続元とする（0段の場合は転送ヘッダーを使わない）。
+pub fn client_ip(headers: &HeaderMap, trusted_proxies: usize) -> Option<String> {
+    if trusted_proxies == 0 {
+        return None;
+    }
+
+    let forwarded: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .collect();
+
+    let ip = match forwarded.len() {
+        0 => header_value(headers, "x-real-ip")?,
+        len => forwarded[len.saturating_sub(trusted_proxies)].to_string(),
+    };
+    ip.parse::<IpAddr>().ok().map(|ip| ip.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_client_ip_uses_address_appended_by_trusted_proxy() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("10.0.0.1, 203.0.113.7"),
+        );
+
+        // ALBが末尾に追加した値を使い、クライアントが送った先頭の値は信用しない
+        assert_eq!(client_ip(&headers, 1).as_deref(), Some("203.0.113.7"));
+        assert_eq!(client_ip(&headers, 2).as_deref(), Some("10.0.0.1"));
+        assert_eq!(client_ip(&headers, 5).as_deref(), Some("10.0.0.1"));
+        assert_eq!(client_ip(&headers, 0), None);
+
+        headers.insert("x-forwarded-for", HeaderValue::from_static("not-an-ip"));
+        assert_eq!(client_ip(&headers, 1), None);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-real-ip", HeaderValue::from_static("2001:db8::1"));
+        headers.insert(header::USER_AGENT, HeaderValue::from_static("Mozilla/5.0"));
+        headers.insert(
+            header::REFERER,
+            HeaderValue::from_static("https://example.com/lp"),
+        );
+        assert_eq!(
+            ClientInfo::from_headers_with_proxies(&headers, 1),
+            ClientInfo {
+                ip_address: Some("2001:db8::1".to_string()),
+                user_agent: Some("Mozilla/5.0".to_string()),
+                referrer: Some("https://example.com/lp".to_string()),
+            }
+        );
+    }
+}
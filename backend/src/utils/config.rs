@@ -9,6 +9,22 @@ pub struct Config {
     pub environment: Environment,
 }
 
+/// メール内リンク（計測・配信停止など）に使用する公開APIのベースURL
+pub fn api_base_url() -> String {
+    env::var("API_BASE_URL")
+        .unwrap_or_else(|_| "http://localhost:3000".to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// GitHub REST APIのベースURL（GitHub Enterprise Serverやテスト用のモックを使う場合に変更する）
+pub fn github_api_url() -> String {
+    env::var("GITHUB_API_URL")
+        .unwrap_or_else(|_| "https://api.github.com".to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
+
 pub enum Environment {
     Development,
     Production,
@@ -1,6 +1,6 @@
 use chrono::{Duration, Utc};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, TokenData, Validation};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -90,6 +90,73 @@ pub fn verify_token(token: &str) -> Result<TokenData<Claims>, JwtError> {
     Ok(token_data)
 }
 
+/// 署名付きトークンの用途（`aud`クレームに設定し、検証時に一致を確認する）
+///
+/// 用途の異なるトークン（計測URLのトークンを配信停止に使うなど）や、同じ秘密鍵で署名された
+/// 他の署名付きデータをトークンとして受け付けないようにする。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenAudience {
+    /// 開封・クリック計測
+    Tracking,
+    /// 配信停止
+    Unsubscribe,
+    /// フォーム送信
+    FormSubmit,
+}
+
+impl TokenAudience {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenAudience::Tracking => "markmail:tracking",
+            TokenAudience::Unsubscribe => "markmail:unsubscribe",
+            TokenAudience::FormSubmit => "markmail:form_submit",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct AudienceClaims<T> {
+    aud: String,
+    #[serde(flatten)]
+    claims: T,
+}
+
+/// 有効期限を持たない署名付きトークンを生成する（計測URLなどメール内リンク用）
+pub fn generate_signed_token<T: Serialize>(
+    audience: TokenAudience,
+    claims: &T,
+) -> Result<String, JwtError> {
+    let secret = std::env::var("JWT_SECRET").map_err(|_| JwtError::SecretNotSet)?;
+    let token = encode(
+        &Header::default(),
+        &AudienceClaims {
+            aud: audience.as_str().to_string(),
+            claims,
+        },
+        &EncodingKey::from_secret(secret.as_ref()),
+    )?;
+    Ok(token)
+}
+
+/// 有効期限を持たない署名付きトークンを検証し、クレームを取得する（用途が一致しない場合はエラー）
+pub fn verify_signed_token<T: DeserializeOwned>(
+    audience: TokenAudience,
+    token: &str,
+) -> Result<T, JwtError> {
+    let secret = std::env::var("JWT_SECRET").map_err(|_| JwtError::SecretNotSet)?;
+    let mut validation = Validation::default();
+    validation.validate_exp = false;
+    validation.required_spec_claims = ["aud".to_string()].into_iter().collect();
+    validation.set_audience(&[audience.as_str()]);
+
+    let token_data = decode::<AudienceClaims<T>>(
+        token,
+        &DecodingKey::from_secret(secret.as_ref()),
+        &validation,
+    )?;
+    Ok(token_data.claims.claims)
+}
+
 /// リフレッシュトークンを生成する（単純なランダム文字列）
 pub fn generate_refresh_token() -> String {
     use rand::Rng;
@@ -145,4 +212,33 @@ mod tests {
         assert_eq!(verified.claims.email, "test@example.com");
         assert_eq!(verified.claims.token_type, TokenType::Access);
     }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct LinkClaims {
+        s: Uuid,
+    }
+
+    #[test]
+    fn test_signed_token_requires_matching_audience() {
+        std::env::set_var("JWT_SECRET", "test_secret_key_for_testing_only");
+
+        let claims = LinkClaims { s: Uuid::new_v4() };
+        let token = generate_signed_token(TokenAudience::Tracking, &claims).unwrap();
+        assert_eq!(
+            verify_signed_token::<LinkClaims>(TokenAudience::Tracking, &token).unwrap(),
+            claims
+        );
+        assert!(verify_signed_token::<LinkClaims>(TokenAudience::Unsubscribe, &token).is_err());
+
+        // 用途を持たない署名付きデータはトークンとして受け付けない
+        let without_audience = encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(b"test_secret_key_for_testing_only"),
+        )
+        .unwrap();
+        assert!(
+            verify_signed_token::<LinkClaims>(TokenAudience::Tracking, &without_audience).is_err()
+        );
+    }
 }
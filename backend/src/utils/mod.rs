@@ -1,4 +1,7 @@
+pub mod client_info;
 pub mod config;
+pub mod encryption;
+pub mod html;
 pub mod jwt;
 pub mod password;
 pub mod validation;
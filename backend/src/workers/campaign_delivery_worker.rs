@@ -0,0 +1,146 @@
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::{JoinHandle, JoinSet};
+use tokio::time::interval;
+use tracing::{debug, error, info};
+
+use crate::database::{campaign_deliveries, campaigns};
+use crate::services::campaign_service::{CampaignService, DELIVERY_STALE_SECONDS};
+
+/// 送信中キャンペーンの配信キューを処理するワーカー
+///
+/// プロセス停止で中断した配信や、リトライ待ちの配信を再開する。
+/// 同じキャンペーンの二重送信はキャンペーン単位のロックで防止される。
+pub struct CampaignDeliveryWorker {
+    pool: Arc<PgPool>,
+    interval_seconds: u64,
+}
+
+impl CampaignDeliveryWorker {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self {
+            pool,
+            interval_seconds: 30, // 30秒ごとに実行
+        }
+    }
+
+    pub fn with_interval(mut self, seconds: u64) -> Self {
+        self.interval_seconds = seconds;
+        self
+    }
+
+    /// ワーカーを開始（`shutdown`がtrueになると送信中のバッチを完了してから終了する）
+    pub async fn start(self, mut shutdown: watch::Receiver<bool>) {
+        info!(
+            "Starting campaign delivery worker with {}s interval",
+            self.interval_seconds
+        );
+
+        let mut ticker = interval(Duration::from_secs(self.interval_seconds));
+        let mut tasks = JoinSet::new();
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.changed() => {}
+            }
+            if *shutdown.borrow() {
+                break;
+            }
+
+            // 完了した送信タスクを片付ける
+            while tasks.try_join_next().is_some() {}
+
+            if let Err(e) = self.resume_deliveries(&mut tasks, &shutdown).await {
+                error!("Error resuming campaign deliveries: {}", e);
+            }
+        }
+
+        while tasks.join_next().await.is_some() {}
+        info!("Campaign delivery worker stopped");
+    }
+
+    /// 送信中のキャンペーンの配信を再開（送信できる配信がないキャンペーンは再送・勝者の判定時刻まで待つ）
+    async fn resume_deliveries(
+        &self,
+        tasks: &mut JoinSet<()>,
+        shutdown: &watch::Receiver<bool>,
+    ) -> Result<(), String> {
+        let sending = campaigns::list_sending_campaigns(&self.pool)
+            .await
+            .map_err(|e| format!("送信中キャンペーンの取得に失敗しました: {e}"))?;
+
+        for campaign in sending {
+            match campaign_deliveries::needs_resume(&self.pool, campaign.id, DELIVERY_STALE_SECONDS)
+                .await
+            {
+                Ok(true) => {}
+                Ok(false) => {
+                    debug!("キャンペーン {} に送信できる配信はありません", campaign.id);
+                    continue;
+                }
+                Err(e) => {
+                    error!(
+                        "キャンペーン {} の配信状況の取得に失敗しました: {}",
+                        campaign.id, e
+                    );
+                    continue;
+                }
+            }
+
+            let pool = self.pool.clone();
+            let shutdown = shutdown.clone();
+            tasks.spawn(async move {
+                let campaign_service = CampaignService::new().with_shutdown(shutdown);
+                if let Err(e) = campaign_service
+                    .process_campaign_sending(&pool, campaign.id, campaign.user_id)
+                    .await
+                {
+                    error!("キャンペーン {} の配信再開エラー: {}", campaign.id, e);
+                }
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// 配信ワーカーを起動する関数
+pub fn spawn_campaign_delivery_worker(
+    pool: Arc<PgPool>,
+    shutdown: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    let worker = CampaignDeliveryWorker::new(pool);
+
+    let handle = tokio::spawn(async move {
+        worker.start(shutdown).await;
+    });
+
+    info!("Campaign delivery worker spawned");
+    handle
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_worker_creation() {
+        // テスト用の遅延接続プールを作成
+        let pool = Arc::new(PgPool::connect_lazy("postgresql://test").unwrap());
+        let worker = CampaignDeliveryWorker::new(pool);
+
+        assert_eq!(worker.interval_seconds, 30);
+    }
+
+    #[tokio::test]
+    async fn test_worker_with_custom_interval() {
+        // テスト用の遅延接続プールを作成
+        let pool = Arc::new(PgPool::connect_lazy("postgresql://test").unwrap());
+        let worker = CampaignDeliveryWorker::new(pool).with_interval(10);
+
+        assert_eq!(worker.interval_seconds, 10);
+    }
+}
@@ -0,0 +1,159 @@
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::{JoinHandle, JoinSet};
+use tokio::time::interval;
+use tracing::{error, info};
+
+use crate::database::campaigns;
+use crate::services::campaign_service::CampaignService;
+
+pub struct CampaignScheduler {
+    pool: Arc<PgPool>,
+    interval_seconds: u64,
+    batch_size: i64,
+}
+
+impl CampaignScheduler {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self {
+            pool,
+            interval_seconds: 30, // 30秒ごとに実行
+            batch_size: 10,
+        }
+    }
+
+    pub fn with_interval(mut self, seconds: u64) -> Self {
+        self.interval_seconds = seconds;
+        self
+    }
+
+    pub fn with_batch_size(mut self, batch_size: i64) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// ワーカーを開始（`shutdown`がtrueになると送信中のバッチを完了してから終了する）
+    pub async fn start(self, mut shutdown: watch::Receiver<bool>) {
+        info!(
+            "Starting campaign scheduler with {}s interval",
+            self.interval_seconds
+        );
+
+        let mut ticker = interval(Duration::from_secs(self.interval_seconds));
+        let mut tasks = JoinSet::new();
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.changed() => {}
+            }
+            if *shutdown.borrow() {
+                break;
+            }
+
+            // 完了した送信タスクを片付ける
+            while tasks.try_join_next().is_some() {}
+
+            if let Err(e) = self.dispatch_due_campaigns(&mut tasks, &shutdown).await {
+                error!("Error dispatching scheduled campaigns: {}", e);
+            }
+        }
+
+        while tasks.join_next().await.is_some() {}
+        info!("Campaign scheduler stopped");
+    }
+
+    /// 送信予定時刻を過ぎたキャンペーンを確保して送信を開始
+    pub async fn dispatch_due_campaigns(
+        &self,
+        tasks: &mut JoinSet<()>,
+        shutdown: &watch::Receiver<bool>,
+    ) -> Result<usize, String> {
+        let claimed = campaigns::claim_due_scheduled_campaigns(&self.pool, self.batch_size)
+            .await
+            .map_err(|e| format!("スケジュール済みキャンペーンの取得に失敗しました: {e}"))?;
+
+        let count = claimed.len();
+        for campaign in claimed {
+            info!(
+                "スケジュール済みキャンペーン {} の送信を開始します",
+                campaign.id
+            );
+
+            // 大規模なキャンペーンが他のキャンペーンの送信を妨げないよう個別に実行
+            let pool = self.pool.clone();
+            let shutdown = shutdown.clone();
+            tasks.spawn(async move {
+                let campaign_service = CampaignService::new().with_shutdown(shutdown);
+                if let Err(e) = campaign_service
+                    .process_campaign_sending(&pool, campaign.id, campaign.user_id)
+                    .await
+                {
+                    error!(
+                        "スケジュール済みキャンペーン {} の送信処理エラー: {}",
+                        campaign.id, e
+                    );
+                }
+            });
+        }
+
+        Ok(count)
+    }
+}
+
+/// キャンペーンスケジューラーを起動する関数
+pub fn spawn_campaign_scheduler(
+    pool: Arc<PgPool>,
+    shutdown: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    let worker = CampaignScheduler::new(pool);
+
+    let handle = tokio::spawn(async move {
+        worker.start(shutdown).await;
+    });
+
+    info!("Campaign scheduler spawned");
+    handle
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scheduler_creation() {
+        // テスト用の遅延接続プールを作成
+        let pool = Arc::new(PgPool::connect_lazy("postgresql://test").unwrap());
+        let worker = CampaignScheduler::new(pool);
+
+        assert_eq!(worker.interval_seconds, 30);
+        assert_eq!(worker.batch_size, 10);
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_with_custom_settings() {
+        // テスト用の遅延接続プールを作成
+        let pool = Arc::new(PgPool::connect_lazy("postgresql://test").unwrap());
+        let worker = CampaignScheduler::new(pool)
+            .with_interval(5)
+            .with_batch_size(3);
+
+        assert_eq!(worker.interval_seconds, 5);
+        assert_eq!(worker.batch_size, 3);
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_stops_on_shutdown() {
+        // 停止要求を受けたスケジューラーはDBに接続せずに終了する
+        let pool = Arc::new(PgPool::connect_lazy("postgresql://test").unwrap());
+        let worker = CampaignScheduler::new(pool).with_interval(3600);
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+
+        shutdown_tx.send(true).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), worker.start(shutdown_rx))
+            .await
+            .unwrap();
+    }
+}
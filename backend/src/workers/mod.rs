@@ -1 +1,3 @@
+pub mod campaign_delivery_worker;
+pub mod campaign_scheduler;
 pub mod sequence_worker;
@@ -1,14 +1,50 @@
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use serde::Serialize;
 use sqlx::PgPool;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
 use tokio::time::interval;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
-use crate::services::sequence_service::SequenceService;
+use crate::database::sequences;
+use crate::services::sequence_service::{
+    SequenceService, SharedEmailService, StepBatchOptions, StepBatchStats,
+};
+
+lazy_static! {
+    static ref METRICS: Mutex<SequenceWorkerMetrics> = Mutex::new(SequenceWorkerMetrics::default());
+}
+
+/// シーケンスワーカーの直近の実行状況（`/health`で公開する）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SequenceWorkerMetrics {
+    pub last_run_at: Option<DateTime<Utc>>,
+    /// 直近の実行で処理したステップ
+    pub last_run: StepBatchStats,
+    /// 直近の実行の平均遅延（ミリ秒）
+    pub last_run_average_lag_ms: i64,
+    /// 直近の実行後に残っている実行待ちのエンロールメント数
+    pub due_count: i64,
+    /// 直近の実行後に残っている最も古い実行待ちステップの遅延（秒）
+    pub oldest_due_lag_seconds: i64,
+    /// 起動してからの累計
+    pub total_succeeded: usize,
+    pub total_failed: usize,
+}
+
+/// シーケンスワーカーの実行状況を取得
+pub fn metrics() -> SequenceWorkerMetrics {
+    METRICS.lock().unwrap().clone()
+}
 
 pub struct SequenceWorker {
     pool: Arc<PgPool>,
     service: SequenceService,
+    email_service: SharedEmailService,
+    options: StepBatchOptions,
     interval_seconds: u64,
 }
 
@@ -17,6 +53,8 @@ impl SequenceWorker {
         Self {
             pool,
             service: SequenceService::new(),
+            email_service: SharedEmailService::new(),
+            options: StepBatchOptions::default(),
             interval_seconds: 60, // 1分ごとに実行
         }
     }
@@ -26,47 +64,132 @@ impl SequenceWorker {
         self
     }
 
-    /// ワーカーを開始
-    pub async fn start(self) {
+    pub fn with_options(mut self, options: StepBatchOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// ワーカーを開始（`shutdown`がtrueになると実行中のステップを完了してから終了する）
+    pub async fn start(self, mut shutdown: watch::Receiver<bool>) {
         info!(
-            "Starting sequence worker with {}s interval",
-            self.interval_seconds
+            "Starting sequence worker with {}s interval (batch: {}, concurrency: {})",
+            self.interval_seconds, self.options.batch_size, self.options.concurrency
         );
 
         let mut ticker = interval(Duration::from_secs(self.interval_seconds));
 
         loop {
-            ticker.tick().await;
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.changed() => {}
+            }
+            if *shutdown.borrow() {
+                break;
+            }
 
-            if let Err(e) = self.process_sequences().await {
+            if let Err(e) = self.process_sequences(&shutdown).await {
                 error!("Error processing sequences: {}", e);
             }
         }
+
+        info!("Sequence worker stopped");
     }
 
     /// シーケンスの処理を実行
-    async fn process_sequences(&self) -> Result<(), String> {
-        info!("Processing pending sequence steps...");
+    async fn process_sequences(&self, shutdown: &watch::Receiver<bool>) -> Result<(), String> {
+        // セグメントに新たに一致した購読者をシーケンスに登録
+        let enrolled = self.service.process_segment_triggers(&self.pool).await?;
+        if enrolled > 0 {
+            info!("Enrolled {} subscribers from segment triggers", enrolled);
+        }
 
-        // 実行待ちのシーケンスステップを処理
-        self.service
-            .process_pending_sequence_steps(&self.pool)
+        // 誕生日・記念日の購読者をシーケンスに登録
+        let enrolled = self
+            .service
+            .process_date_triggers(&self.pool, Utc::now().date_naive())
             .await?;
+        if enrolled > 0 {
+            info!("Enrolled {} subscribers from date triggers", enrolled);
+        }
+
+        info!("Processing pending sequence steps...");
+
+        // 実行待ちのシーケンスステップをバッチごとに処理（停止要求があれば実行中のバッチの完了後に中断）
+        let run_started_at = Utc::now();
+        let mut stats = StepBatchStats::default();
+        while !*shutdown.borrow() {
+            let batch = self
+                .service
+                .process_step_batch(
+                    &self.pool,
+                    &self.email_service,
+                    &self.options,
+                    run_started_at,
+                )
+                .await?;
+            if batch.claimed == 0 {
+                break;
+            }
+            stats.merge(&batch);
+        }
 
-        info!("Sequence processing completed");
+        self.record_metrics(&stats).await;
+
+        info!(
+            "Sequence processing completed: {} succeeded, {} failed, lag avg {}ms / max {}ms",
+            stats.succeeded,
+            stats.failed,
+            stats.average_lag_ms(),
+            stats.max_lag_ms
+        );
         Ok(())
     }
+
+    /// 実行結果と残っている実行待ちステップの遅延を記録
+    async fn record_metrics(&self, stats: &StepBatchStats) {
+        let now = Utc::now();
+        let backlog = match sequences::find_due_step_backlog(&self.pool).await {
+            Ok(backlog) => Some(backlog),
+            Err(e) => {
+                warn!("Failed to load sequence step backlog: {}", e);
+                None
+            }
+        };
+
+        let mut metrics = METRICS.lock().unwrap();
+        metrics.last_run_at = Some(now);
+        metrics.last_run = stats.clone();
+        metrics.last_run_average_lag_ms = stats.average_lag_ms();
+        metrics.total_succeeded += stats.succeeded;
+        metrics.total_failed += stats.failed;
+        if let Some(backlog) = backlog {
+            metrics.due_count = backlog.due_count;
+            metrics.oldest_due_lag_seconds = backlog
+                .oldest_due_at
+                .map(|at| (now - at).num_seconds().max(0))
+                .unwrap_or(0);
+            if backlog.due_count > 0 {
+                warn!(
+                    "{} sequence steps are still due (oldest lag {}s)",
+                    backlog.due_count, metrics.oldest_due_lag_seconds
+                );
+            }
+        }
+    }
 }
 
 /// バックグラウンドワーカーを起動する関数
-pub fn spawn_sequence_worker(pool: Arc<PgPool>) {
-    let worker = SequenceWorker::new(pool);
+///
+/// 返り値のハンドルを待つと、停止要求の後に実行中のステップが完了するまで待機できる。
+pub fn spawn_sequence_worker(pool: Arc<PgPool>, shutdown: watch::Receiver<bool>) -> JoinHandle<()> {
+    let worker = SequenceWorker::new(pool).with_options(StepBatchOptions::from_env());
 
-    tokio::spawn(async move {
-        worker.start().await;
+    let handle = tokio::spawn(async move {
+        worker.start(shutdown).await;
     });
 
     info!("Sequence worker spawned");
+    handle
 }
 
 #[cfg(test)]
@@ -80,6 +203,7 @@ mod tests {
         let worker = SequenceWorker::new(pool);
 
         assert_eq!(worker.interval_seconds, 60);
+        assert_eq!(worker.options, StepBatchOptions::default());
     }
 
     #[tokio::test]
@@ -90,4 +214,18 @@ mod tests {
 
         assert_eq!(worker.interval_seconds, 30);
     }
+
+    #[tokio::test]
+    async fn test_worker_stops_on_shutdown() {
+        // 停止要求を受けたワーカーはDBに接続せずに終了する
+        let pool = Arc::new(PgPool::connect_lazy("postgresql://test").unwrap());
+        let worker = SequenceWorker::new(pool).with_interval(3600);
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+
+        // 最初のtickは即時に完了するため、停止要求を先に送る
+        shutdown_tx.send(true).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), worker.start(shutdown_rx))
+            .await
+            .unwrap();
+    }
 }
@@ -1,11 +1,23 @@
 import { authStore } from "../stores/authStore";
 import type {
   Form,
+  FormEmbedCode,
   CreateFormRequest,
   UpdateFormRequest,
+  FormFieldError,
   FormSubmissionsResponse,
 } from "../types/form";
 
+// フォーム送信の入力エラー（フィールドごとのエラーを含む）
+export class FormSubmissionError extends Error {
+  constructor(
+    message: string,
+    public fieldErrors: FormFieldError[] = [],
+  ) {
+    super(message);
+  }
+}
+
 // APIベースURL
 const API_BASE_URL = "/api";
 
@@ -22,7 +34,12 @@ const getAuthToken = (): string | null => {
 async function apiRequest<T = any>(
   path: string,
   options: RequestInit = {},
-): Promise<{ data?: T; error?: string; status: number }> {
+): Promise<{
+  data?: T;
+  error?: string;
+  fieldErrors?: FormFieldError[];
+  status: number;
+}> {
   try {
     const url = `${API_BASE_URL}${path}`;
     const token = getAuthToken();
@@ -47,12 +64,14 @@ async function apiRequest<T = any>(
       return { data, status: response.status };
     } else {
       let error: string;
+      let fieldErrors: FormFieldError[] | undefined;
       try {
         const errorData = await response.json();
         error =
           errorData.error ||
           errorData.message ||
           `API Error: ${response.status}`;
+        fieldErrors = errorData.field_errors;
       } catch {
         error = `API Error: ${response.status}`;
       }
@@ -61,7 +80,7 @@ async function apiRequest<T = any>(
         authStore.logout();
       }
 
-      return { error, status: response.status };
+      return { error, fieldErrors, status: response.status };
     }
   } catch (error: any) {
     return {
@@ -139,6 +158,15 @@ export const formService = {
     return response.data!;
   },
 
+  // 公開フォームページのURLと埋め込み用コード取得
+  async getEmbedCode(id: string): Promise<FormEmbedCode> {
+    const response = await apiRequest<FormEmbedCode>(`/forms/${id}/embed`);
+    if (response.error) {
+      throw new Error(response.error);
+    }
+    return response.data!;
+  },
+
   // 公開フォーム取得（認証不要）
   async getPublicForm(id: string): Promise<Form> {
     const response = await apiRequest<Form>(`/forms/${id}/public`);
@@ -155,7 +183,7 @@ export const formService = {
       body: JSON.stringify({ data }),
     });
     if (response.error) {
-      throw new Error(response.error);
+      throw new FormSubmissionError(response.error, response.fieldErrors);
     }
     return response.data;
   },
@@ -10,6 +10,10 @@ import type {
   CreateSequenceStepRequest,
   UpdateSequenceStepRequest,
   SequenceEnrollment,
+  SequenceEnrollmentList,
+  EnrollmentStatus,
+  EnrollSubscribersRequest,
+  EnrollSubscribersResult,
 } from "$lib/types/sequence";
 
 const API_BASE_URL = "/api";
@@ -137,38 +141,60 @@ class SequenceService {
 
   async getSequenceEnrollments(
     sequenceId: string,
-  ): Promise<SequenceEnrollment[]> {
-    return this.request<SequenceEnrollment[]>(
-      `/sequences/${sequenceId}/enrollments`,
+    params: { status?: EnrollmentStatus; limit?: number; offset?: number } = {},
+  ): Promise<SequenceEnrollmentList> {
+    const query = new URLSearchParams();
+    if (params.status) query.set("status", params.status);
+    if (params.limit !== undefined) query.set("limit", String(params.limit));
+    if (params.offset !== undefined) query.set("offset", String(params.offset));
+    const suffix = query.toString() ? `?${query}` : "";
+
+    return this.request<SequenceEnrollmentList>(
+      `/sequences/${sequenceId}/enrollments${suffix}`,
     );
   }
 
-  async enrollSubscriber(
+  async enrollSubscribers(
     sequenceId: string,
-    subscriberId: string,
-  ): Promise<SequenceEnrollment> {
-    return this.request<SequenceEnrollment>(`/sequences/${sequenceId}/enroll`, {
-      method: "POST",
-      body: JSON.stringify({ subscriber_id: subscriberId }),
-    });
+    data: EnrollSubscribersRequest,
+  ): Promise<EnrollSubscribersResult> {
+    return this.request<EnrollSubscribersResult>(
+      `/sequences/${sequenceId}/enrollments`,
+      {
+        method: "POST",
+        body: JSON.stringify(data),
+      },
+    );
   }
 
-  async pauseEnrollment(enrollmentId: string): Promise<void> {
-    await this.request<void>(`/enrollments/${enrollmentId}/pause`, {
-      method: "POST",
-    });
+  async pauseEnrollment(
+    sequenceId: string,
+    enrollmentId: string,
+  ): Promise<SequenceEnrollment> {
+    return this.request<SequenceEnrollment>(
+      `/sequences/${sequenceId}/enrollments/${enrollmentId}/pause`,
+      { method: "POST" },
+    );
   }
 
-  async resumeEnrollment(enrollmentId: string): Promise<void> {
-    await this.request<void>(`/enrollments/${enrollmentId}/resume`, {
-      method: "POST",
-    });
+  async resumeEnrollment(
+    sequenceId: string,
+    enrollmentId: string,
+  ): Promise<SequenceEnrollment> {
+    return this.request<SequenceEnrollment>(
+      `/sequences/${sequenceId}/enrollments/${enrollmentId}/resume`,
+      { method: "POST" },
+    );
   }
 
-  async cancelEnrollment(enrollmentId: string): Promise<void> {
-    await this.request<void>(`/enrollments/${enrollmentId}/cancel`, {
-      method: "POST",
-    });
+  async cancelEnrollment(
+    sequenceId: string,
+    enrollmentId: string,
+  ): Promise<SequenceEnrollment> {
+    return this.request<SequenceEnrollment>(
+      `/sequences/${sequenceId}/enrollments/${enrollmentId}/cancel`,
+      { method: "POST" },
+    );
   }
 
   // シーケンスのアクティベーション
@@ -199,6 +225,12 @@ class SequenceService {
       subscriber_created: "購読者登録時",
       form_submission: "フォーム送信時",
       tag_added: "タグ追加時",
+      tag_removed: "タグ削除時",
+      custom_field_changed: "カスタムフィールド変更時",
+      campaign_opened: "キャンペーン開封時",
+      campaign_clicked: "キャンペーンのリンククリック時",
+      birthday: "誕生日",
+      anniversary: "記念日",
     };
     return triggers[trigger] || trigger;
   }
@@ -22,6 +22,8 @@ export interface Form {
   submission_count: number;
   created_at: string;
   updated_at: string;
+  // 公開フォームの取得時のみ（送信時に`_token`として返す）
+  form_token?: string;
 }
 
 export interface CreateFormRequest {
@@ -52,9 +54,25 @@ export interface FormSubmission {
   referrer?: string;
   confirmation_token?: string;
   confirmed_at?: string;
+  status: "accepted" | "quarantined";
+  quarantine_reason?: string;
   created_at: string;
 }
 
+// バックエンドが配信する公開フォームページの埋め込み用コード
+export interface FormEmbedCode {
+  url: string;
+  script_url: string;
+  script_tag: string;
+  iframe_tag: string;
+}
+
+export interface FormFieldError {
+  field: string;
+  code: string;
+  message: string;
+}
+
 export interface FormSubmissionsResponse {
   submissions: FormSubmission[];
   total: number;
@@ -10,6 +10,9 @@ export interface Sequence {
   status: SequenceStatus;
   active_subscribers: number;
   completed_subscribers: number;
+  reentry_policy: ReentryPolicy;
+  exit_conditions: ExitCondition[];
+  send_window: SendWindow;
   created_at: string;
   updated_at: string;
 }
@@ -18,7 +21,13 @@ export type TriggerType =
   | "manual"
   | "subscriber_created"
   | "form_submission"
-  | "tag_added";
+  | "tag_added"
+  | "tag_removed"
+  | "custom_field_changed"
+  | "campaign_opened"
+  | "campaign_clicked"
+  | "birthday"
+  | "anniversary";
 export type SequenceStatus = "draft" | "active" | "paused" | "archived";
 
 export interface SequenceStep {
@@ -66,7 +75,83 @@ export interface SequenceEnrollment {
   updated_at: string;
 }
 
-export type EnrollmentStatus = "active" | "paused" | "completed" | "cancelled";
+export type EnrollmentStatus =
+  | "active"
+  | "paused"
+  | "completed"
+  | "cancelled"
+  | "goal_reached"
+  | "exited"
+  | "failed";
+
+// never: 一度登録された購読者は再登録しない, after_completion: 完了・キャンセル後は再登録できる
+export type ReentryPolicy = "never" | "after_completion";
+
+// 終了条件を満たした場合のステータス（省略時は配信停止が exited、それ以外は goal_reached）
+export type ExitOutcome = "goal_reached" | "exited";
+
+export type ExitCondition = (
+  | { type: "tag_added"; tag: string }
+  | {
+      type: "custom_field";
+      field: string;
+      operator: string;
+      value?: unknown;
+    }
+  | { type: "unsubscribed" }
+  | { type: "link_clicked"; campaign_id?: string; url?: string }
+  | { type: "form_submitted"; form_id?: string }
+) & { outcome?: ExitOutcome };
+
+export type Weekday = "Mon" | "Tue" | "Wed" | "Thu" | "Fri" | "Sat" | "Sun";
+
+// 送信時間帯（空のオブジェクトは制限なし）
+// 購読者のタイムゾーンは timezone_field のカスタムフィールド → country → メールアドレスの国別ドメイン → timezone の順に決まる
+export interface SendWindow {
+  days?: Weekday[];
+  start_hour?: number;
+  end_hour?: number;
+  timezone?: string;
+  timezone_field?: string;
+}
+
+// 待機ステップの action_config.wait_until（購読者のタイムゾーンで指定曜日・時刻まで待機）
+export interface WaitUntil {
+  days?: Weekday[];
+  time: string;
+}
+
+export interface SequenceEnrollmentDetail extends SequenceEnrollment {
+  subscriber_email: string;
+  subscriber_name?: string;
+  current_step_name?: string;
+  current_step_order?: number;
+}
+
+export interface SequenceEnrollmentList {
+  enrollments: SequenceEnrollmentDetail[];
+  counts: Partial<Record<EnrollmentStatus, number>>;
+  total: number;
+  limit: number;
+  offset: number;
+}
+
+export interface EnrollSubscribersRequest {
+  subscriber_ids?: string[];
+  tag?: string;
+  csv_content?: string;
+  has_header?: boolean;
+  email_column?: number;
+}
+
+export interface EnrollSubscribersResult {
+  enrolled: number;
+  skipped_in_progress: number;
+  skipped_reentry: number;
+  skipped_inactive: number;
+  not_found: string[];
+  errors: string[];
+}
 
 // API リクエスト/レスポンス用の型
 
@@ -75,6 +160,9 @@ export interface CreateSequenceRequest {
   description?: string;
   trigger_type: TriggerType;
   trigger_config?: Record<string, any>;
+  reentry_policy?: ReentryPolicy;
+  exit_conditions?: ExitCondition[];
+  send_window?: SendWindow;
 }
 
 export interface UpdateSequenceRequest {
@@ -83,6 +171,9 @@ export interface UpdateSequenceRequest {
   trigger_type?: TriggerType;
   trigger_config?: Record<string, any>;
   status?: SequenceStatus;
+  reentry_policy?: ReentryPolicy;
+  exit_conditions?: ExitCondition[];
+  send_window?: SendWindow;
 }
 
 export interface CreateSequenceStepRequest {
@@ -8,6 +8,7 @@ export enum SubscriberStatus {
   UNSUBSCRIBED = "unsubscribed",
   BOUNCED = "bounced",
   COMPLAINED = "complained",
+  PENDING = "pending",
 }
 
 /**